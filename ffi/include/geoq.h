@@ -0,0 +1,61 @@
+/*
+ * C interface to geoq's geometry conversions.
+ *
+ * Strings are NUL-terminated UTF-8. Inputs are anything geoq reads as a
+ * line: Lat/Lon, geohash, H3 cell, WKT or GeoJSON. A FeatureCollection
+ * converts to one line (or WKB geometry) per feature.
+ *
+ * Functions returning pointers give NULL when they fail, and those
+ * returning int give -1, with the reason from geoq_last_error().
+ * Returned strings and buffers belong to the caller, to release with
+ * geoq_string_free() and geoq_bytes_free().
+ */
+#ifndef GEOQ_H
+#define GEOQ_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* How geoq reads the input: "latlon", "geohash", "h3", "tile", "quadkey",
+   "wkt" or "geojson" */
+char *geoq_input_type(const char *input);
+
+/* GeoJSON Features, one per line */
+char *geoq_to_geojson(const char *input);
+
+/* WKT geometries, one per line */
+char *geoq_to_wkt(const char *input);
+
+/* WKB geometries, back to back, with their length in *len */
+uint8_t *geoq_to_wkb(const char *input, size_t *len);
+
+/* GeoJSON geometries, one per line, from WKB geometries back to back */
+char *geoq_from_wkb(const uint8_t *data, size_t len);
+
+/* Bounding box of the input as min lon, min lat, max lon, max lat */
+int geoq_bbox(const char *input, double out[4]);
+
+/* Hilbert curve position of the input's bbox center within extent
+ * (min lon, min lat, max lon, max lat), as geoq sorts features */
+int geoq_hilbert(const char *input, const double extent[4], uint32_t *out);
+
+/* A FlatGeobuf file of the entities of each line of input, with its
+ * length in *len */
+uint8_t *geoq_to_fgb(const char *input, size_t *len);
+
+/* Why the last call on this thread failed, or NULL. Owned by geoq, and
+ * valid until the next failure. */
+const char *geoq_last_error(void);
+
+void geoq_string_free(char *s);
+void geoq_bytes_free(uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,242 @@
+// C ABI for geoq's conversions, for binding from other languages without
+// running geoq per call. The functions are declared in include/geoq.h.
+//
+// Strings are NUL-terminated UTF-8. Inputs are anything geoq reads as a
+// line (Lat/Lon, geohash, H3 cell, WKT or GeoJSON). Functions returning
+// pointers give NULL when they fail, and those returning int give -1,
+// with the reason from geoq_last_error. Strings and buffers returned
+// belong to the caller, to release with geoq_string_free and
+// geoq_bytes_free.
+#![allow(clippy::missing_safety_doc)]
+use geoq_core::{convert, error::Error};
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(e: Error) {
+    let message = match e.message() {
+        Some(m) => m.to_string(),
+        None => format!("{:?}", e),
+    };
+    let message = CString::new(message.replace('\0', "")).ok();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+// Runs f, keeping its error (or a panic, which mustn't unwind into C) for
+// geoq_last_error
+fn guard<T, F>(f: F) -> Option<T>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(v)) => Some(v),
+        Ok(Err(e)) => {
+            set_error(e);
+            None
+        }
+        Err(_) => {
+            set_error(Error::ProgramError("geoq panicked".to_string()));
+            None
+        }
+    }
+}
+
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, Error> {
+    if s.is_null() {
+        return Err(Error::InvalidInput("Input is NULL".to_string()));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| Error::InvalidInput("Input is not UTF-8".to_string()))
+}
+
+fn into_c_string(s: String) -> Result<*mut c_char, Error> {
+    CString::new(s)
+        .map(CString::into_raw)
+        .map_err(|_| Error::ProgramError("Output contains NUL".to_string()))
+}
+
+unsafe fn string_fn<F>(input: *const c_char, f: F) -> *mut c_char
+where
+    F: FnOnce(&str) -> Result<String, Error>,
+{
+    guard(|| into_c_string(f(read_str(input)?)?)).unwrap_or(ptr::null_mut())
+}
+
+unsafe fn bytes_fn<F>(input: *const c_char, len: *mut usize, f: F) -> *mut u8
+where
+    F: FnOnce(&str) -> Result<Vec<u8>, Error>,
+{
+    match guard(|| f(read_str(input)?)) {
+        Some(bytes) => {
+            let bytes = bytes.into_boxed_slice();
+            if !len.is_null() {
+                *len = bytes.len();
+            }
+            Box::into_raw(bytes) as *mut u8
+        }
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_input_type(input: *const c_char) -> *mut c_char {
+    string_fn(input, |s| convert::input_type(s).map(|t| t.to_string()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_to_geojson(input: *const c_char) -> *mut c_char {
+    string_fn(input, convert::to_geojson)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_to_wkt(input: *const c_char) -> *mut c_char {
+    string_fn(input, convert::to_wkt)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_to_wkb(input: *const c_char, len: *mut usize) -> *mut u8 {
+    bytes_fn(input, len, convert::to_wkb)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_from_wkb(data: *const u8, len: usize) -> *mut c_char {
+    guard(|| {
+        if data.is_null() {
+            return Err(Error::InvalidInput("Input is NULL".to_string()));
+        }
+        into_c_string(convert::from_wkb(slice::from_raw_parts(data, len))?)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_bbox(input: *const c_char, out: *mut f64) -> c_int {
+    match guard(|| convert::bbox(read_str(input)?)) {
+        Some(bbox) if !out.is_null() => {
+            slice::from_raw_parts_mut(out, 4).copy_from_slice(&bbox);
+            0
+        }
+        _ => -1,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_hilbert(
+    input: *const c_char,
+    extent: *const f64,
+    out: *mut u32,
+) -> c_int {
+    let res = guard(|| {
+        if extent.is_null() || out.is_null() {
+            return Err(Error::InvalidInput("Extent or output is NULL".to_string()));
+        }
+        let mut e = [0.0; 4];
+        e.copy_from_slice(slice::from_raw_parts(extent, 4));
+        convert::hilbert(read_str(input)?, e)
+    });
+    match res {
+        Some(h) => {
+            *out = h;
+            0
+        }
+        None => -1,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_to_fgb(input: *const c_char, len: *mut usize) -> *mut u8 {
+    bytes_fn(input, len, convert::to_fgb)
+}
+
+// The reason the last call on this thread failed, or NULL. It stays valid
+// until the next failure.
+#[no_mangle]
+pub extern "C" fn geoq_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geoq_bytes_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    unsafe fn take(s: *mut c_char) -> String {
+        assert!(!s.is_null());
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_string();
+        geoq_string_free(s);
+        owned
+    }
+
+    #[test]
+    fn test_strings() {
+        unsafe {
+            assert_eq!("POINT(-118 34)", take(geoq_to_wkt(c("34,-118").as_ptr())));
+            assert_eq!("geohash", take(geoq_input_type(c("9q5").as_ptr())));
+            assert!(geoq_to_geojson(c("pizza").as_ptr()).is_null());
+            let err = CStr::from_ptr(geoq_last_error()).to_str().unwrap();
+            assert!(err.contains("pizza"), "{}", err);
+            assert!(geoq_to_wkt(ptr::null()).is_null());
+        }
+    }
+
+    #[test]
+    fn test_bytes() {
+        unsafe {
+            let mut len = 0;
+            let wkb = geoq_to_wkb(c("POINT (1 2)").as_ptr(), &mut len);
+            assert_eq!(21, len);
+            assert_eq!(
+                "{\"coordinates\":[1.0,2.0],\"type\":\"Point\"}",
+                take(geoq_from_wkb(wkb, len))
+            );
+            geoq_bytes_free(wkb, len);
+
+            let fgb = geoq_to_fgb(c("POINT (1 2)\n9q5").as_ptr(), &mut len);
+            assert_eq!(b"fgb", slice::from_raw_parts(fgb, 3));
+            geoq_bytes_free(fgb, len);
+        }
+    }
+
+    #[test]
+    fn test_numbers() {
+        unsafe {
+            let mut bbox = [0.0; 4];
+            assert_eq!(
+                0,
+                geoq_bbox(c("LINESTRING (1 2, 3 4)").as_ptr(), bbox.as_mut_ptr())
+            );
+            assert_eq!([1.0, 2.0, 3.0, 4.0], bbox);
+            let extent = [0.0, 0.0, 10.0, 10.0];
+            let mut h = 1;
+            assert_eq!(0, geoq_hilbert(c("0,0").as_ptr(), extent.as_ptr(), &mut h));
+            assert_eq!(0, h);
+            assert_eq!(-1, geoq_bbox(c("pizza").as_ptr(), bbox.as_mut_ptr()));
+        }
+    }
+}
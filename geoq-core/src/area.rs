@@ -0,0 +1,45 @@
+use geo::algorithm::chamberlain_duquette_area::ChamberlainDuquetteArea;
+use geo_types::Geometry;
+
+// Approximate geodesic area in square meters.
+// Non-areal geometries (points, lines) have an area of 0.
+pub fn area(geom: &Geometry<f64>) -> f64 {
+    match *geom {
+        Geometry::Point(_) => 0.0,
+        Geometry::Line(_) => 0.0,
+        Geometry::LineString(_) => 0.0,
+        Geometry::MultiPoint(_) => 0.0,
+        Geometry::MultiLineString(_) => 0.0,
+        Geometry::Polygon(ref g) => g.chamberlain_duquette_unsigned_area(),
+        Geometry::Rect(ref g) => g.to_polygon().chamberlain_duquette_unsigned_area(),
+        Geometry::Triangle(ref g) => g.to_polygon().chamberlain_duquette_unsigned_area(),
+        Geometry::MultiPolygon(ref g) => {
+            g.0.iter()
+                .map(|p| p.chamberlain_duquette_unsigned_area())
+                .sum()
+        }
+        Geometry::GeometryCollection(ref gc) => gc.0.iter().map(area).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::area::area;
+    use geo_types::{Geometry, Point, Polygon};
+
+    #[test]
+    fn test_point_area() {
+        assert_eq!(0.0, area(&Geometry::Point(Point::new(1.0, 2.0))));
+    }
+
+    #[test]
+    fn test_polygon_area() {
+        // 1 degree square at the equator is roughly 12,364 square km
+        let poly = Polygon::new(
+            vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0], [0.0, 0.0]].into(),
+            vec![],
+        );
+        let km2 = area(&Geometry::Polygon(poly)) / 1e6;
+        assert!((km2 - 12364.0).abs() < 50.0, "got {}", km2);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{buffer::EARTH_RADIUS, error::Error, units};
+use geo_types::*;
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+trait OptRectHelper: Debug {
+    fn or_zero(self) -> geo::Rect<f64>;
+}
+
+pub fn zero_rect() -> geo::Rect<f64> {
+    let p = Coord { x: 0.0, y: 0.0 };
+    geo::Rect::new(p, p)
+}
+
+impl OptRectHelper for Option<geo::Rect<f64>> {
+    fn or_zero(self) -> geo::Rect<f64> {
+        self.unwrap_or_else(|| zero_rect())
+    }
+}
+
+fn rect(p: &Point<f64>) -> geo::Rect<f64> {
+    let c = Coord { x: p.x(), y: p.y() };
+    geo::Rect::new(c, c)
+}
+
+fn min(a: f64, b: f64) -> f64 {
+    match a.partial_cmp(&b).unwrap_or(Ordering::Equal) {
+        Ordering::Less => a,
+        Ordering::Equal => a,
+        Ordering::Greater => b,
+    }
+}
+
+fn max(a: f64, b: f64) -> f64 {
+    match a.partial_cmp(&b).unwrap_or(Ordering::Equal) {
+        Ordering::Less => b,
+        Ordering::Equal => a,
+        Ordering::Greater => a,
+    }
+}
+
+pub fn merge(a: &geo::Rect<f64>, b: &geo::Rect<f64>) -> geo::Rect<f64> {
+    let min = Coord {
+        x: min(a.min().x, b.min().x),
+        y: min(a.min().y, b.min().y),
+    };
+    let max = Coord {
+        x: max(a.max().x, b.max().x),
+        y: max(a.max().y, b.max().y),
+    };
+    geo::Rect::new(min, max)
+}
+
+// Reads a bbox given as min_x,min_y,max_x,max_y, e.g. -180,-90,180,90
+pub fn parse(s: &str) -> Result<geo::Rect<f64>, Error> {
+    let invalid =
+        || Error::InvalidInput(format!("Expected a bbox as min_x,min_y,max_x,max_y: {}", s));
+    let values: Vec<f64> = s
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+    match values.as_slice() {
+        [min_x, min_y, max_x, max_y] if min_x <= max_x && min_y <= max_y => Ok(geo::Rect::new(
+            Coord {
+                x: *min_x,
+                y: *min_y,
+            },
+            Coord {
+                x: *max_x,
+                y: *max_y,
+            },
+        )),
+        _ => Err(invalid()),
+    }
+}
+
+// Formats a bbox the way parse reads it
+pub fn format(r: &geo::Rect<f64>) -> String {
+    format!("{},{},{},{}", r.min().x, r.min().y, r.max().x, r.max().y)
+}
+
+// How far to move each edge of a bbox outward
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Margin {
+    // A fraction of the bbox's width (for the east and west edges) or
+    // height (for the north and south edges)
+    Fraction(f64),
+    Meters(f64),
+}
+
+// Reads a margin such as 10%, 500m or 2mi. Negative margins shrink.
+pub fn parse_margin(s: &str) -> Result<Margin, Error> {
+    match s.trim().strip_suffix('%') {
+        Some(percent) => percent
+            .trim()
+            .parse::<f64>()
+            .map(|p| Margin::Fraction(p / 100.0))
+            .map_err(|_| Error::InvalidNumberFormat(format!("Expected a percentage: {}", s))),
+        None => units::parse_length(s).map(Margin::Meters),
+    }
+}
+
+// The bbox with its edges moved outward by the margin, or None if a
+// negative margin leaves nothing of it. Margins in meters are converted
+// to degrees of longitude at the latitude farthest from the equator, so
+// the bbox grows by at least the margin everywhere. Latitudes stop at the
+// poles and longitudes at the antimeridian.
+pub fn expand(r: &geo::Rect<f64>, margin: Margin) -> Option<geo::Rect<f64>> {
+    let (dx, dy) = match margin {
+        Margin::Fraction(f) => (r.width() * f, r.height() * f),
+        Margin::Meters(m) => {
+            let dy = m / (EARTH_RADIUS * PI / 180.0);
+            let lat = (r.min().y - dy).abs().max((r.max().y + dy).abs()).min(90.0);
+            let dx = match lat.to_radians().cos() {
+                cos if cos > 1e-9 => dy / cos,
+                _ => 360.0 * dy.signum(),
+            };
+            (dx, dy)
+        }
+    };
+    let min = Coord {
+        x: (r.min().x - dx).max(-180.0),
+        y: (r.min().y - dy).max(-90.0),
+    };
+    let max = Coord {
+        x: (r.max().x + dx).min(180.0),
+        y: (r.max().y + dy).min(90.0),
+    };
+    if min.x > max.x || min.y > max.y {
+        None
+    } else {
+        Some(geo::Rect::new(min, max))
+    }
+}
+
+// The overlap of two bboxes, if they overlap
+pub fn intersect(a: &geo::Rect<f64>, b: &geo::Rect<f64>) -> Option<geo::Rect<f64>> {
+    let lo = Coord {
+        x: max(a.min().x, b.min().x),
+        y: max(a.min().y, b.min().y),
+    };
+    let hi = Coord {
+        x: min(a.max().x, b.max().x),
+        y: min(a.max().y, b.max().y),
+    };
+    if lo.x > hi.x || lo.y > hi.y {
+        None
+    } else {
+        Some(geo::Rect::new(lo, hi))
+    }
+}
+
+pub fn bbox(geom: &Geometry<f64>) -> geo::Rect<f64> {
+    use geo::algorithm::bounding_rect::BoundingRect;
+    match *geom {
+        Geometry::Point(ref g) => rect(g),
+        Geometry::Line(ref g) => g.bounding_rect(),
+        Geometry::LineString(ref g) => g.bounding_rect().or_zero(),
+        Geometry::Polygon(ref g) => g.bounding_rect().or_zero(),
+        Geometry::Rect(ref g) => g.to_polygon().bounding_rect().or_zero(),
+        Geometry::Triangle(ref g) => g.to_polygon().bounding_rect().or_zero(),
+        Geometry::MultiPoint(ref g) => g.bounding_rect().or_zero(),
+        Geometry::MultiLineString(ref g) => g.bounding_rect().or_zero(),
+        Geometry::MultiPolygon(ref g) => g.bounding_rect().or_zero(),
+        Geometry::GeometryCollection(ref gc) => {
+            let rects: Vec<geo::Rect<f64>> = gc.0.iter().map(|geom| bbox(geom)).collect();
+
+            rects.iter().fold(zero_rect(), |a, b| merge(&a, b))
+        }
+    }
+}
+
+pub trait BBoxToPoly {
+    fn to_polygon_geoq(&self) -> geo_types::Polygon<f64>;
+}
+
+impl BBoxToPoly for geo::Rect<f64> {
+    fn to_polygon_geoq(&self) -> geo_types::Polygon<f64> {
+        Polygon::new(
+            LineString::from(vec![
+                self.max().x_y(),
+                (self.min().x, self.max().y),
+                (self.min().x, self.min().y),
+                (self.max().x, self.min().y),
+                self.max().x_y(),
+            ]),
+            vec![],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, format, intersect, parse, parse_margin, Margin};
+
+    #[test]
+    fn test_parse_and_format() {
+        assert_eq!("-1,2.5,3,4", format(&parse("-1, 2.5,3,4").unwrap()));
+        assert!(parse("1,2,3").is_err());
+        assert!(parse("3,2,1,4").is_err());
+    }
+
+    #[test]
+    fn test_expand() {
+        let r = parse("0,0,10,20").unwrap();
+        assert_eq!(Margin::Fraction(0.1), parse_margin("10%").unwrap());
+        assert_eq!(
+            "-1,-2,11,22",
+            format(&expand(&r, parse_margin("10%").unwrap()).unwrap())
+        );
+        assert_eq!(None, expand(&r, Margin::Fraction(-0.6)));
+        assert_eq!(
+            "-180,-90,180,90",
+            format(&expand(&r, Margin::Fraction(100.0)).unwrap())
+        );
+
+        // 1 degree of latitude is about 111km, and of longitude at 60
+        // degrees about half that
+        let r = parse("0,59,1,60").unwrap();
+        let expanded = expand(&r, parse_margin("111.195km").unwrap()).unwrap();
+        assert!((expanded.min().y - 58.0).abs() < 1e-4);
+        assert!((expanded.max().y - 61.0).abs() < 1e-4);
+        let dx = -expanded.min().x;
+        assert!((dx - 1.0 / 61f64.to_radians().cos()).abs() < 1e-3);
+        assert!(parse_margin("5ha").is_err());
+    }
+
+    #[test]
+    fn test_intersect() {
+        let a = parse("0,0,10,10").unwrap();
+        let b = parse("5,-5,15,5").unwrap();
+        assert_eq!("5,0,10,5", format(&intersect(&a, &b).unwrap()));
+        assert_eq!(None, intersect(&a, &parse("11,0,12,1").unwrap()));
+    }
+}
@@ -0,0 +1,140 @@
+// Features from GeoPackage (http://www.geopackage.org/spec/) tables.
+// SQLite reads from files, so the input is copied to a temporary one.
+use crate::{binary::wkb, error::Error};
+use geojson::{feature::Id, Feature, Geometry};
+use rusqlite::{types::ValueRef, Connection, OpenFlags};
+use serde_json::{Map, Value};
+use std::io::{self, BufRead};
+
+// A geometry blob: a "GP" header, with an optional envelope, then WKB
+pub fn geometry(blob: &[u8]) -> Result<Option<Geometry>, Error> {
+    if blob.len() < 8 || &blob[..2] != b"GP" {
+        return Err(Error::InvalidInput(
+            "Invalid GeoPackage geometry blob".to_string(),
+        ));
+    }
+    let flags = blob[3];
+    if flags & 0x10 != 0 {
+        return Ok(None);
+    }
+    let envelope = match (flags >> 1) & 0x07 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        e => {
+            return Err(Error::InvalidInput(format!(
+                "Invalid GeoPackage envelope type: {}",
+                e
+            )))
+        }
+    };
+    let wkb = blob
+        .get(8 + envelope..)
+        .ok_or_else(|| Error::InvalidInput("Invalid GeoPackage geometry blob".to_string()))?;
+    wkb::read(wkb).map(Some)
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn property(value: ValueRef) -> Option<Value> {
+    match value {
+        ValueRef::Null => Some(Value::Null),
+        ValueRef::Integer(i) => Some(Value::from(i)),
+        ValueRef::Real(f) => Some(Value::from(f)),
+        ValueRef::Text(t) => Some(Value::from(String::from_utf8_lossy(t).to_string())),
+        ValueRef::Blob(_) => None,
+    }
+}
+
+// Rows of a feature table, with its integer primary key as the feature
+// id and other non-blob columns as properties
+fn table_features(conn: &Connection, table: &str, column: &str) -> Result<Vec<Feature>, Error> {
+    let mut info = conn.prepare(&format!("PRAGMA table_info({})", quote(table)))?;
+    let key: Option<String> = info
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(1)?, row.get::<_, i64>(5)?))
+        })?
+        .filter_map(|r| r.ok())
+        .find(|(_, pk)| *pk == 1)
+        .map(|(name, _)| name);
+
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {}", quote(table)))?;
+    let names: Vec<String> = stmt.column_names().iter().map(|n| n.to_string()).collect();
+    let mut rows = stmt.query([])?;
+    let mut features = vec![];
+    while let Some(row) = rows.next()? {
+        let mut geometry = None;
+        let mut id = None;
+        let mut properties = Map::new();
+        for (i, name) in names.iter().enumerate() {
+            let value = row.get_ref(i)?;
+            if name == column {
+                if let ValueRef::Blob(blob) = value {
+                    geometry = self::geometry(blob)?;
+                }
+            } else if key.as_ref() == Some(name) {
+                if let ValueRef::Integer(i) = value {
+                    id = Some(Id::Number(i.into()));
+                }
+            } else if let Some(v) = property(value) {
+                properties.insert(name.clone(), v);
+            }
+        }
+        features.push(Feature {
+            bbox: None,
+            geometry,
+            id,
+            properties: Some(properties),
+            foreign_members: None,
+        });
+    }
+    Ok(features)
+}
+
+// Features of every feature table, in name order
+pub fn features(input: &mut dyn BufRead) -> Result<Vec<Feature>, Error> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    io::copy(input, &mut file)?;
+    let conn = Connection::open_with_flags(file.path(), OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn
+        .prepare("SELECT table_name, column_name FROM gpkg_geometry_columns ORDER BY table_name")?;
+    let tables = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut features = vec![];
+    for (table, column) in tables {
+        features.extend(table_features(&conn, &table, &column)?);
+    }
+    Ok(features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::geometry;
+    use geojson::Value;
+
+    #[test]
+    fn test_geometry() {
+        // Little endian header with an XY envelope, then POINT (1 2)
+        let mut blob = vec![b'G', b'P', 0, 0b0000_0011, 0xe6, 0x10, 0, 0];
+        for v in &[1.0f64, 1.0, 2.0, 2.0] {
+            blob.extend(&v.to_le_bytes());
+        }
+        blob.extend(&[1, 1, 0, 0, 0]);
+        blob.extend(&1.0f64.to_le_bytes());
+        blob.extend(&2.0f64.to_le_bytes());
+        assert_eq!(
+            Value::Point(vec![1.0, 2.0]),
+            geometry(&blob).unwrap().unwrap().value
+        );
+        assert!(geometry(&[b'G', b'P', 0, 0b0001_0001, 0, 0, 0, 0])
+            .unwrap()
+            .is_none());
+        assert!(geometry(b"not a blob").is_err());
+    }
+}
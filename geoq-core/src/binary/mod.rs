@@ -0,0 +1,188 @@
+// Binary formats piped to stdin, recognized by their leading bytes:
+// FlatGeobuf, GeoPackage, zipped shapefiles and WKB. Their features are
+// converted to lines of GeoJSON for the usual line-based readers.
+use crate::{error::Error, progress, shp};
+#[cfg(feature = "fgb")]
+use flatgeobuf::FgbReader;
+use geojson::{Feature, FeatureCollection};
+use geozero::geojson::GeoJsonWriter;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use zip::{result::ZipError, ZipArchive};
+
+#[cfg(feature = "sqlite")]
+pub mod gpkg;
+pub mod wkb;
+
+#[derive(Debug, PartialEq)]
+pub enum Format {
+    FlatGeobuf,
+    GeoPackage,
+    ZippedShapefile,
+    Wkb,
+}
+
+// Recognizes a format from the first bytes of the input, as peeked from
+// its buffer without consuming any
+pub fn sniff(head: &[u8]) -> Option<Format> {
+    if head.len() >= 8 && &head[..3] == b"fgb" && &head[4..7] == b"fgb" {
+        Some(Format::FlatGeobuf)
+    } else if head.starts_with(b"SQLite format 3\0") {
+        Some(Format::GeoPackage)
+    } else if head.starts_with(b"PK\x03\x04") {
+        Some(Format::ZippedShapefile)
+    } else if wkb::is_wkb(head) {
+        Some(Format::Wkb)
+    } else {
+        None
+    }
+}
+
+// The rest of the input in a temporary file, for formats that aren't
+// read front to back
+fn spool(input: &mut dyn BufRead) -> Result<File, Error> {
+    let mut file = tempfile::tempfile()?;
+    io::copy(input, &mut file)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file)
+}
+
+#[cfg(feature = "fgb")]
+fn fgb_features(file: File) -> Result<Vec<Feature>, Error> {
+    let mut input = BufReader::new(file);
+    let mut fgb = FgbReader::open(&mut input)?;
+    fgb.select_all()?;
+    let mut json_data: Vec<u8> = Vec::new();
+    let mut json = GeoJsonWriter::new(&mut json_data);
+    fgb.process_features(&mut json)?;
+    let collection: FeatureCollection = serde_json::from_slice(&json_data)?;
+    Ok(collection.features)
+}
+
+fn invalid_zip(e: ZipError) -> Error {
+    Error::InvalidInput(format!("Invalid zip archive: {}", e))
+}
+
+// Every shapefile in the archive, each of which needs a .dbf alongside
+fn zipped_shapefile_features(file: File) -> Result<Vec<Feature>, Error> {
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(invalid_zip)?;
+    let names: Vec<String> = archive.file_names().map(String::from).collect();
+    let find = |name: &str| names.iter().find(|n| n.eq_ignore_ascii_case(name));
+    let mut extract = |name: &str| -> Result<Cursor<Vec<u8>>, Error> {
+        let mut data = vec![];
+        archive
+            .by_name(name)
+            .map_err(invalid_zip)?
+            .read_to_end(&mut data)?;
+        Ok(Cursor::new(data))
+    };
+    let mut features = vec![];
+    let shapefiles: Vec<&String> = names
+        .iter()
+        .filter(|n| n.to_lowercase().ends_with(".shp") && !n.starts_with("__MACOSX/"))
+        .collect();
+    if shapefiles.is_empty() {
+        return Err(Error::InvalidInput(
+            "Zip archive contains no shapefile".to_string(),
+        ));
+    }
+    for shp_name in shapefiles {
+        let stem = &shp_name[..shp_name.len() - 4];
+        let dbf = find(&format!("{}.dbf", stem))
+            .ok_or_else(|| Error::InvalidInput(format!("Missing {}.dbf in zip archive", stem)))?;
+        let shp_data = extract(shp_name)?;
+        let shape_reader = match find(&format!("{}.shx", stem)) {
+            Some(shx) => shapefile::ShapeReader::with_shx(shp_data, extract(shx)?)?,
+            None => shapefile::ShapeReader::new(shp_data)?,
+        };
+        let dbase_reader = dbase::Reader::new(extract(dbf)?).map_err(shapefile::Error::from)?;
+        let mut reader = shapefile::Reader::new(shape_reader, dbase_reader);
+        for shape_record in reader.iter_shapes_and_records() {
+            let (shape, record) = shape_record?;
+            features.push(shp::shp_to_geojson(shape, record)?);
+        }
+    }
+    Ok(features)
+}
+
+fn push_line(lines: &mut Vec<u8>, feature: &Feature) -> Result<(), Error> {
+    serde_json::to_writer(&mut *lines, feature)?;
+    lines.push(b'\n');
+    Ok(())
+}
+
+// Geometries in a WKB stream, converted as they're read
+fn wkb_lines(input: &mut dyn BufRead) -> Result<Vec<u8>, Error> {
+    let mut lines = vec![];
+    while let Some(geometry) = wkb::read_next(input)? {
+        let feature = Feature {
+            bbox: None,
+            geometry: Some(geometry),
+            id: None,
+            properties: None,
+            foreign_members: None,
+        };
+        push_line(&mut lines, &feature)?;
+    }
+    Ok(lines)
+}
+
+// If the input starts with a binary format, reads the rest of it and
+// returns its features as lines of GeoJSON. WKB is converted as it's
+// read; the others are copied to a temporary file first.
+pub fn read(input: &mut dyn BufRead) -> Result<Option<Vec<u8>>, Error> {
+    let format = match sniff(input.fill_buf()?) {
+        Some(format) => format,
+        None => return Ok(None),
+    };
+    progress::unknown_total();
+    let features = match format {
+        #[cfg(feature = "fgb")]
+        Format::FlatGeobuf => fgb_features(spool(input)?)?,
+        #[cfg(not(feature = "fgb"))]
+        Format::FlatGeobuf => {
+            return Err(Error::InvalidInput(
+                "Can't read FlatGeobuf input -- geoq was built without the fgb feature".to_string(),
+            ))
+        }
+        #[cfg(feature = "sqlite")]
+        Format::GeoPackage => gpkg::features(input)?,
+        #[cfg(not(feature = "sqlite"))]
+        Format::GeoPackage => {
+            return Err(Error::InvalidInput(
+                "Can't read GeoPackage input -- geoq was built without the sqlite feature"
+                    .to_string(),
+            ))
+        }
+        Format::ZippedShapefile => zipped_shapefile_features(spool(input)?)?,
+        Format::Wkb => return wkb_lines(input).map(Some),
+    };
+    let mut lines = vec![];
+    for f in features {
+        push_line(&mut lines, &f)?;
+    }
+    Ok(Some(lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read, sniff, Format};
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(Some(Format::FlatGeobuf), sniff(b"fgb\x03fgb\x00rest"));
+        assert_eq!(Some(Format::GeoPackage), sniff(b"SQLite format 3\0..."));
+        assert_eq!(Some(Format::ZippedShapefile), sniff(b"PK\x03\x04..."));
+        assert_eq!(Some(Format::Wkb), sniff(b"\x01\x01\x00\x00\x00..."));
+        assert_eq!(None, sniff(b"9q5\n"));
+        assert_eq!(None, sniff(b"{\"type\":\"Point\"}"));
+        assert_eq!(None, sniff(b""));
+    }
+
+    #[test]
+    fn test_read_text() {
+        let mut input = "POINT (1 2)\n".as_bytes();
+        assert!(read(&mut input).unwrap().is_none());
+        assert_eq!(b"POINT (1 2)\n", input);
+    }
+}
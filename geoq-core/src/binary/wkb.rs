@@ -0,0 +1,452 @@
+// Well-Known Binary geometries, in the OGC/ISO form (Z and M types as
+// 1000s) and PostGIS EWKB (Z, M and SRID flags in the high bits), read
+// with geozero. M values are dropped, as GeoJSON has no place for them.
+use crate::error::Error;
+use geojson::{Geometry, Value};
+use geozero::{
+    error::{GeozeroError, Result as GeozeroResult},
+    wkb::{process_wkb_type_geom, WkbDialect},
+    CoordDimensions, GeomProcessor,
+};
+use std::io::{BufRead, Read};
+
+// ISO types count Z and M in 1000s, EWKB sets flags in the high bits.
+// Either way, only the seven GeoJSON types are known.
+fn dialect(code: u32) -> Option<WkbDialect> {
+    let (dialect, base) = if code & 0xe000_0000 != 0 {
+        (WkbDialect::Ewkb, code & 0x0fff_ffff)
+    } else if code / 1000 <= 3 {
+        (WkbDialect::Wkb, code % 1000)
+    } else {
+        return None;
+    };
+    if (1..=7).contains(&base) {
+        Some(dialect)
+    } else {
+        None
+    }
+}
+
+// The geometry type code after a byte order marker
+fn type_code(header: &[u8]) -> Option<u32> {
+    let code = [header[1], header[2], header[3], header[4]];
+    match header[0] {
+        0 => Some(u32::from_be_bytes(code)),
+        1 => Some(u32::from_le_bytes(code)),
+        _ => None,
+    }
+}
+
+// Whether data plausibly starts with a WKB geometry: a byte order
+// marker followed by a known geometry type
+pub fn is_wkb(data: &[u8]) -> bool {
+    data.len() >= 5 && type_code(data).and_then(dialect).is_some()
+}
+
+// Collects the geometry geozero reads as GeoJSON. Lines and rings are
+// gathered in positions, the rings of a polygon in lines, and so on up;
+// collections are stacked as they nest.
+#[derive(Default)]
+struct Builder {
+    positions: Vec<Vec<f64>>,
+    lines: Vec<Vec<Vec<f64>>>,
+    polygons: Vec<Vec<Vec<Vec<f64>>>>,
+    collections: Vec<Vec<Geometry>>,
+    geometry: Option<Value>,
+}
+
+// Empty points are written with NaN coordinates
+fn is_empty(p: &[f64]) -> bool {
+    p.iter().all(|v| v.is_nan())
+}
+
+fn unsupported(name: &str) -> GeozeroResult<()> {
+    Err(GeozeroError::Geometry(format!(
+        "{} is not supported in GeoJSON",
+        name
+    )))
+}
+
+impl Builder {
+    fn finish(&mut self, value: Value) {
+        match self.collections.last_mut() {
+            Some(members) => members.push(Geometry::new(value)),
+            None => self.geometry = Some(value),
+        }
+    }
+}
+
+impl GeomProcessor for Builder {
+    fn dimensions(&self) -> CoordDimensions {
+        CoordDimensions::xyz()
+    }
+
+    fn xy(&mut self, x: f64, y: f64, _idx: usize) -> GeozeroResult<()> {
+        self.positions.push(vec![x, y]);
+        Ok(())
+    }
+
+    fn coordinate(
+        &mut self,
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+        _m: Option<f64>,
+        _t: Option<f64>,
+        _tm: Option<u64>,
+        _idx: usize,
+    ) -> GeozeroResult<()> {
+        self.positions.push(match z {
+            Some(z) => vec![x, y, z],
+            None => vec![x, y],
+        });
+        Ok(())
+    }
+
+    fn point_begin(&mut self, _idx: usize) -> GeozeroResult<()> {
+        self.positions.clear();
+        Ok(())
+    }
+
+    fn point_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        let value = match self.positions.pop() {
+            Some(p) if !is_empty(&p) => Value::Point(p),
+            _ => Value::GeometryCollection(vec![]),
+        };
+        self.finish(value);
+        Ok(())
+    }
+
+    fn multipoint_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        self.positions.clear();
+        Ok(())
+    }
+
+    fn multipoint_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        let points = self.positions.drain(..).filter(|p| !is_empty(p)).collect();
+        self.finish(Value::MultiPoint(points));
+        Ok(())
+    }
+
+    fn linestring_begin(&mut self, _tagged: bool, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        self.positions.clear();
+        Ok(())
+    }
+
+    fn linestring_end(&mut self, tagged: bool, _idx: usize) -> GeozeroResult<()> {
+        let positions = std::mem::take(&mut self.positions);
+        if tagged {
+            self.finish(Value::LineString(positions));
+        } else {
+            self.lines.push(positions);
+        }
+        Ok(())
+    }
+
+    fn multilinestring_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        self.lines.clear();
+        Ok(())
+    }
+
+    fn multilinestring_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        let lines = std::mem::take(&mut self.lines);
+        self.finish(Value::MultiLineString(lines));
+        Ok(())
+    }
+
+    fn polygon_begin(&mut self, _tagged: bool, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        self.lines.clear();
+        Ok(())
+    }
+
+    fn polygon_end(&mut self, tagged: bool, _idx: usize) -> GeozeroResult<()> {
+        let rings = std::mem::take(&mut self.lines);
+        if tagged {
+            self.finish(Value::Polygon(rings));
+        } else {
+            self.polygons.push(rings);
+        }
+        Ok(())
+    }
+
+    fn multipolygon_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        self.polygons.clear();
+        Ok(())
+    }
+
+    fn multipolygon_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        let polygons = std::mem::take(&mut self.polygons);
+        self.finish(Value::MultiPolygon(polygons));
+        Ok(())
+    }
+
+    fn geometrycollection_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        self.collections.push(vec![]);
+        Ok(())
+    }
+
+    fn geometrycollection_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        let members = self.collections.pop().unwrap_or_default();
+        self.finish(Value::GeometryCollection(members));
+        Ok(())
+    }
+
+    fn circularstring_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        unsupported("CircularString")
+    }
+
+    fn compoundcurve_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        unsupported("CompoundCurve")
+    }
+
+    fn curvepolygon_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        unsupported("CurvePolygon")
+    }
+
+    fn multicurve_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        unsupported("MultiCurve")
+    }
+
+    fn multisurface_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        unsupported("MultiSurface")
+    }
+
+    fn triangle_begin(&mut self, _tagged: bool, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        unsupported("Triangle")
+    }
+
+    fn polyhedralsurface_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        unsupported("PolyhedralSurface")
+    }
+
+    fn tin_begin(&mut self, _size: usize, _idx: usize) -> GeozeroResult<()> {
+        unsupported("TIN")
+    }
+}
+
+// The next geometry of a stream of them, one after another, or None at
+// its end. Each geometry's header says whether it's ISO WKB or EWKB.
+pub fn read_next(input: &mut dyn BufRead) -> Result<Option<Geometry>, Error> {
+    if input.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let mut header = [0u8; 5];
+    input
+        .read_exact(&mut header)
+        .map_err(|_| Error::InvalidInput("Unexpected end of WKB".to_string()))?;
+    let dialect = match type_code(&header) {
+        Some(code) => dialect(code)
+            .ok_or_else(|| Error::InvalidInput(format!("Unknown WKB geometry type: {}", code)))?,
+        None => {
+            return Err(Error::InvalidInput(format!(
+                "Invalid WKB byte order: {}",
+                header[0]
+            )))
+        }
+    };
+    let mut builder = Builder::default();
+    process_wkb_type_geom(&mut (&header[..]).chain(input), &mut builder, dialect)
+        .map_err(|e| Error::InvalidInput(format!("Invalid WKB: {}", e)))?;
+    Ok(builder.geometry.map(Geometry::new))
+}
+
+// A stream of geometries, one after another
+pub fn read_all(mut data: &[u8]) -> Result<Vec<Geometry>, Error> {
+    let mut geoms = vec![];
+    while let Some(geom) = read_next(&mut data)? {
+        geoms.push(geom);
+    }
+    Ok(geoms)
+}
+
+// A single geometry, ignoring anything after it
+pub fn read(data: &[u8]) -> Result<Geometry, Error> {
+    read_next(&mut &data[..])?
+        .ok_or_else(|| Error::InvalidInput("Unexpected end of WKB".to_string()))
+}
+
+// Writes geometries as little-endian ISO WKB, with Z types when their
+// first position has a Z value
+struct Writer {
+    data: Vec<u8>,
+}
+
+fn first_position(value: &Value) -> Option<&Vec<f64>> {
+    match value {
+        Value::Point(p) => Some(p),
+        Value::MultiPoint(ps) | Value::LineString(ps) => ps.first(),
+        Value::MultiLineString(ls) | Value::Polygon(ls) => ls.iter().flatten().next(),
+        Value::MultiPolygon(ps) => ps.iter().flatten().flatten().next(),
+        Value::GeometryCollection(gs) => gs.iter().find_map(|g| first_position(&g.value)),
+    }
+}
+
+impl Writer {
+    fn header(&mut self, base: u32, z: bool) {
+        self.data.push(1);
+        let code = if z { base + 1000 } else { base };
+        self.data.extend(&code.to_le_bytes());
+    }
+
+    fn count(&mut self, n: usize) {
+        self.data.extend(&(n as u32).to_le_bytes());
+    }
+
+    fn position(&mut self, p: &[f64], z: bool) {
+        let dims = if z { 3 } else { 2 };
+        for i in 0..dims {
+            self.data
+                .extend(&p.get(i).copied().unwrap_or(0.0).to_le_bytes());
+        }
+    }
+
+    fn positions(&mut self, ps: &[Vec<f64>], z: bool) {
+        self.count(ps.len());
+        for p in ps {
+            self.position(p, z);
+        }
+    }
+
+    fn rings(&mut self, rings: &[Vec<Vec<f64>>], z: bool) {
+        self.count(rings.len());
+        for r in rings {
+            self.positions(r, z);
+        }
+    }
+
+    fn geometry(&mut self, value: &Value, z: bool) {
+        match value {
+            Value::Point(p) => {
+                self.header(1, z);
+                self.position(p, z);
+            }
+            Value::LineString(ps) => {
+                self.header(2, z);
+                self.positions(ps, z);
+            }
+            Value::Polygon(rings) => {
+                self.header(3, z);
+                self.rings(rings, z);
+            }
+            Value::MultiPoint(ps) => {
+                self.header(4, z);
+                self.count(ps.len());
+                for p in ps {
+                    self.header(1, z);
+                    self.position(p, z);
+                }
+            }
+            Value::MultiLineString(ls) => {
+                self.header(5, z);
+                self.count(ls.len());
+                for l in ls {
+                    self.header(2, z);
+                    self.positions(l, z);
+                }
+            }
+            Value::MultiPolygon(ps) => {
+                self.header(6, z);
+                self.count(ps.len());
+                for p in ps {
+                    self.header(3, z);
+                    self.rings(p, z);
+                }
+            }
+            Value::GeometryCollection(gs) => {
+                self.header(7, z);
+                self.count(gs.len());
+                for g in gs {
+                    self.geometry(&g.value, z);
+                }
+            }
+        }
+    }
+}
+
+pub fn write(geom: &Geometry) -> Vec<u8> {
+    let z = first_position(&geom.value).is_some_and(|p| p.len() > 2);
+    let mut writer = Writer { data: vec![] };
+    writer.geometry(&geom.value, z);
+    writer.data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_wkb, read, read_all, write};
+    use geojson::Geometry;
+    use geojson::Value;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_read() {
+        // POINT (1 2), little endian
+        let point = hex("0101000000000000000000f03f0000000000000040");
+        assert!(is_wkb(&point));
+        assert_eq!(Value::Point(vec![1.0, 2.0]), read(&point).unwrap().value);
+
+        // LINESTRING Z (0 0 5, 1 1 6), big endian ISO
+        let line = hex(concat!(
+            "00000003ea00000002",
+            "000000000000000000000000000000004014000000000000",
+            "3ff00000000000003ff00000000000004018000000000000"
+        ));
+        assert_eq!(
+            Value::LineString(vec![vec![0.0, 0.0, 5.0], vec![1.0, 1.0, 6.0]]),
+            read(&line).unwrap().value
+        );
+
+        // SRID=4326;MULTIPOINT((1 2)), as EWKB
+        let multi = hex(concat!(
+            "0104000020e610000001000000",
+            "0101000000000000000000f03f0000000000000040"
+        ));
+        assert_eq!(
+            Value::MultiPoint(vec![vec![1.0, 2.0]]),
+            read(&multi).unwrap().value
+        );
+
+        // POINT M (1 2 3): M values are dropped
+        let point_m = hex("01d1070000000000000000f03f00000000000000400000000000000840");
+        assert_eq!(Value::Point(vec![1.0, 2.0]), read(&point_m).unwrap().value);
+
+        // GEOMETRYCOLLECTION (CIRCULARSTRING EMPTY) has no GeoJSON form
+        assert!(read(&hex("010700000001000000010800000000000000")).is_err());
+
+        let mut stream = point.clone();
+        stream.extend(&multi);
+        assert_eq!(2, read_all(&stream).unwrap().len());
+        assert!(read_all(&point[..10]).is_err());
+        assert!(!is_wkb(b"POINT (1 2)"));
+        assert!(!is_wkb(&hex("0109000000")));
+    }
+
+    #[test]
+    fn test_write() {
+        assert_eq!(
+            hex("0101000000000000000000f03f0000000000000040"),
+            write(&Geometry::new(Value::Point(vec![1.0, 2.0])))
+        );
+        for value in [
+            Value::LineString(vec![vec![0.0, 0.0, 5.0], vec![1.0, 1.0, 6.0]]),
+            Value::MultiPolygon(vec![vec![vec![
+                vec![0.0, 0.0],
+                vec![1.0, 0.0],
+                vec![1.0, 1.0],
+                vec![0.0, 0.0],
+            ]]]),
+            Value::GeometryCollection(vec![
+                Geometry::new(Value::MultiPoint(vec![vec![1.0, 2.0]])),
+                Geometry::new(Value::GeometryCollection(vec![])),
+            ]),
+        ] {
+            let geom = Geometry::new(value);
+            assert_eq!(geom, read(&write(&geom)).unwrap());
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use crate::union::cascaded_union;
+use geo::algorithm::{bool_ops::BooleanOps, bounding_rect::BoundingRect, map_coords::MapCoords};
+use geo_types::{Coord, Geometry, LineString, MultiPolygon, Polygon};
+use std::f64::consts::PI;
+
+// Buffers in meters of lon/lat geometries. The geometry is projected
+// with an azimuthal equidistant projection centered on it, which keeps
+// distances from the center true, so buffers stay round away from the
+// equator and are accurate for features up to a few hundred kilometers
+// across. In the plane, the buffer is the union of a circle around each
+// vertex and a rectangle along each segment (and a polygon's own area).
+// Negative distances shrink polygons, and leave nothing of points or
+// lines.
+
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+// Sides of the polygon standing in for a circle
+const CIRCLE_SIDES: usize = 32;
+
+// An azimuthal equidistant projection of the sphere, in meters from its
+// center
+#[derive(Clone, Copy)]
+struct Plane {
+    // In radians
+    lon: f64,
+    lat: f64,
+}
+
+impl Plane {
+    fn around(geom: &Geometry<f64>) -> Option<Plane> {
+        let center = geom.bounding_rect()?.center();
+        Some(Plane {
+            lon: center.x.to_radians(),
+            lat: center.y.to_radians(),
+        })
+    }
+
+    fn to_meters(self, c: Coord<f64>) -> Coord<f64> {
+        let (lon, lat) = (c.x.to_radians() - self.lon, c.y.to_radians());
+        let cos_c =
+            (self.lat.sin() * lat.sin() + self.lat.cos() * lat.cos() * lon.cos()).clamp(-1.0, 1.0);
+        let angle = cos_c.acos();
+        // Scale from the angle's sine to its arc
+        let k = if angle == 0.0 {
+            1.0
+        } else {
+            angle / angle.sin()
+        };
+        Coord {
+            x: EARTH_RADIUS * k * lat.cos() * lon.sin(),
+            y: EARTH_RADIUS
+                * k
+                * (self.lat.cos() * lat.sin() - self.lat.sin() * lat.cos() * lon.cos()),
+        }
+    }
+
+    // Longitudes aren't wrapped to -180..180, so that geometries across
+    // the antimeridian stay in one piece
+    fn to_degrees(self, c: Coord<f64>) -> Coord<f64> {
+        let rho = c.x.hypot(c.y);
+        if rho == 0.0 {
+            return Coord {
+                x: self.lon.to_degrees(),
+                y: self.lat.to_degrees(),
+            };
+        }
+        let angle = rho / EARTH_RADIUS;
+        let lat = (angle.cos() * self.lat.sin() + c.y * angle.sin() * self.lat.cos() / rho)
+            .clamp(-1.0, 1.0)
+            .asin();
+        let lon = self.lon
+            + (c.x * angle.sin())
+                .atan2(rho * self.lat.cos() * angle.cos() - c.y * self.lat.sin() * angle.sin());
+        Coord {
+            x: lon.to_degrees(),
+            y: lat.to_degrees(),
+        }
+    }
+}
+
+// The vertices are half a side off the axes, so that they don't nearly
+// meet the corners of rectangles along nearly axis-aligned segments,
+// which the boolean operations can't union
+fn circle(c: Coord<f64>, r: f64) -> MultiPolygon<f64> {
+    let ring: Vec<Coord<f64>> = (0..=CIRCLE_SIDES)
+        .map(|i| {
+            let a = 2.0 * PI * ((i % CIRCLE_SIDES) as f64 + 0.5) / CIRCLE_SIDES as f64;
+            Coord {
+                x: c.x + r * a.cos(),
+                y: c.y + r * a.sin(),
+            }
+        })
+        .collect();
+    MultiPolygon(vec![Polygon::new(LineString(ring), vec![])])
+}
+
+// The rectangle reaching r either side of a segment
+fn rectangle(a: Coord<f64>, b: Coord<f64>, r: f64) -> Option<MultiPolygon<f64>> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len = dx.hypot(dy);
+    if len == 0.0 {
+        return None;
+    }
+    let (nx, ny) = (-dy / len * r, dx / len * r);
+    let ring = vec![
+        Coord {
+            x: a.x + nx,
+            y: a.y + ny,
+        },
+        Coord {
+            x: b.x + nx,
+            y: b.y + ny,
+        },
+        Coord {
+            x: b.x - nx,
+            y: b.y - ny,
+        },
+        Coord {
+            x: a.x - nx,
+            y: a.y - ny,
+        },
+        Coord {
+            x: a.x + nx,
+            y: a.y + ny,
+        },
+    ];
+    Some(MultiPolygon(vec![Polygon::new(LineString(ring), vec![])]))
+}
+
+fn line_shapes(line: &LineString<f64>, r: f64, shapes: &mut Vec<MultiPolygon<f64>>) {
+    shapes.extend(line.0.iter().map(|&c| circle(c, r)));
+    shapes.extend(line.0.windows(2).filter_map(|w| rectangle(w[0], w[1], r)));
+}
+
+fn polygon_rings(poly: &Polygon<f64>) -> impl Iterator<Item = &LineString<f64>> {
+    std::iter::once(poly.exterior()).chain(poly.interiors())
+}
+
+pub(crate) fn polygons(geom: &Geometry<f64>) -> Vec<Polygon<f64>> {
+    match geom {
+        Geometry::Polygon(p) => vec![p.clone()],
+        Geometry::MultiPolygon(mp) => mp.0.clone(),
+        Geometry::Rect(r) => vec![r.to_polygon()],
+        Geometry::Triangle(t) => vec![t.to_polygon()],
+        Geometry::GeometryCollection(gc) => gc.0.iter().flat_map(polygons).collect(),
+        _ => vec![],
+    }
+}
+
+// Shapes whose union is the geometry's buffer, other than its polygons
+fn shapes(geom: &Geometry<f64>, r: f64, out: &mut Vec<MultiPolygon<f64>>) {
+    match geom {
+        Geometry::Point(p) => out.push(circle(p.0, r)),
+        Geometry::MultiPoint(mp) => out.extend(mp.0.iter().map(|p| circle(p.0, r))),
+        Geometry::Line(l) => line_shapes(&LineString(vec![l.start, l.end]), r, out),
+        Geometry::LineString(l) => line_shapes(l, r, out),
+        Geometry::MultiLineString(ml) => ml.0.iter().for_each(|l| line_shapes(l, r, out)),
+        Geometry::GeometryCollection(gc) => gc.0.iter().for_each(|g| shapes(g, r, out)),
+        _ => polygons(geom)
+            .iter()
+            .flat_map(polygon_rings)
+            .for_each(|ring| line_shapes(ring, r, out)),
+    }
+}
+
+fn grow(geom: &Geometry<f64>, r: f64) -> MultiPolygon<f64> {
+    let mut all: Vec<MultiPolygon<f64>> = polygons(geom)
+        .into_iter()
+        .map(|p| MultiPolygon(vec![p]))
+        .collect();
+    shapes(geom, r, &mut all);
+    cascaded_union(all).unwrap_or_else(|| MultiPolygon(vec![]))
+}
+
+// Polygons less everything within r of their rings
+fn shrink(geom: &Geometry<f64>, r: f64) -> MultiPolygon<f64> {
+    let polys = MultiPolygon(polygons(geom));
+    let mut edges = vec![];
+    for ring in polys.0.iter().flat_map(polygon_rings) {
+        line_shapes(ring, r, &mut edges);
+    }
+    match cascaded_union(edges) {
+        Some(edges) => polys.difference(&edges),
+        None => polys,
+    }
+}
+
+fn grow_or_shrink(geom: &Geometry<f64>, distance: f64) -> MultiPolygon<f64> {
+    if distance > 0.0 {
+        grow(geom, distance)
+    } else {
+        shrink(geom, -distance)
+    }
+}
+
+fn polygon_or_multi(buffered: MultiPolygon<f64>) -> Geometry<f64> {
+    match buffered.0.len() {
+        1 => Geometry::Polygon(buffered.0.into_iter().next().unwrap()),
+        _ => Geometry::MultiPolygon(buffered),
+    }
+}
+
+// The area within meters of the geometry, or for negative meters, the
+// area of its polygons further than that inside their edges
+pub fn buffer(geom: &Geometry<f64>, meters: f64) -> Geometry<f64> {
+    let plane = match Plane::around(geom) {
+        Some(plane) if meters != 0.0 => plane,
+        _ => return geom.clone(),
+    };
+    let projected = geom.map_coords(|c| plane.to_meters(c));
+    polygon_or_multi(grow_or_shrink(&projected, meters).map_coords(|c| plane.to_degrees(c)))
+}
+
+// A buffer in the geometry's own units, treating its coordinates as a
+// plane, for data that's already projected
+pub fn planar_buffer(geom: &Geometry<f64>, distance: f64) -> Geometry<f64> {
+    if distance == 0.0 || geom.bounding_rect().is_none() {
+        return geom.clone();
+    }
+    polygon_or_multi(grow_or_shrink(geom, distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        area::area,
+        buffer::{buffer, planar_buffer},
+    };
+    use geo::algorithm::{area::Area, haversine_distance::HaversineDistance};
+    use geo_types::{Geometry, LineString, Point, Polygon};
+
+    fn assert_near(expected: f64, actual: f64) {
+        assert!(
+            (actual - expected).abs() < expected * 0.02,
+            "Expected about {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_point() {
+        let b = buffer(&Geometry::Point(Point::new(-74.0, 40.7)), 100.0);
+        assert!(matches!(b, Geometry::Polygon(_)));
+        assert_near(std::f64::consts::PI * 100.0 * 100.0, area(&b));
+    }
+
+    #[test]
+    fn test_geodesic_point() {
+        // Far from the equator and from the center, the buffer's edge is
+        // still the distance away
+        let center = Point::new(20.0, 70.0);
+        let ring = match buffer(&Geometry::Point(center), 500_000.0) {
+            Geometry::Polygon(p) => p.exterior().clone(),
+            g => panic!("Expected a polygon: {:?}", g),
+        };
+        for c in ring.points() {
+            let meters = center.haversine_distance(&c);
+            assert!((meters - 500_000.0).abs() < 1.0, "{} meters", meters);
+        }
+    }
+
+    #[test]
+    fn test_planar() {
+        let b = planar_buffer(&Geometry::Point(Point::new(500.0, 500.0)), 10.0);
+        let square_units = match &b {
+            Geometry::Polygon(p) => p.unsigned_area(),
+            g => panic!("Expected a polygon: {:?}", g),
+        };
+        assert!((square_units - std::f64::consts::PI * 100.0).abs() < 3.0);
+    }
+
+    #[test]
+    fn test_line() {
+        // About 1113m long at the equator
+        let line = LineString::from(vec![(0.0, 0.0), (0.01, 0.0)]);
+        let b = buffer(&Geometry::LineString(line), 10.0);
+        assert_near(1113.2 * 20.0 + std::f64::consts::PI * 100.0, area(&b));
+    }
+
+    #[test]
+    fn test_polygon() {
+        let square = Geometry::Polygon(Polygon::new(
+            LineString::from(vec![
+                (0.0, 0.0),
+                (0.01, 0.0),
+                (0.01, 0.01),
+                (0.0, 0.01),
+                (0.0, 0.0),
+            ]),
+            vec![],
+        ));
+        let side = 1113.2;
+        assert_near(
+            side * side + 4.0 * side * 50.0 + std::f64::consts::PI * 2500.0,
+            area(&buffer(&square, 50.0)),
+        );
+        assert_near(
+            (side - 100.0) * (side - 100.0),
+            area(&buffer(&square, -50.0)),
+        );
+        assert_eq!(0.0, area(&buffer(&square, -1000.0)));
+        assert_eq!(square, buffer(&square, 0.0));
+    }
+}
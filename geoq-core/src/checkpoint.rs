@@ -0,0 +1,385 @@
+// Resumable runs of long conversions (fgb write, tiles build), given
+// --checkpoint state.json. Features are spooled to state.json.features as
+// stdin is read, and the state records how far into stdin they go, along
+// with (for tiles build) the last zoom level whose tiles have all been
+// spooled to state.json.tiles. Running the same command again with the
+// same checkpoint replays the spools, then carries on reading stdin from
+// where it was left. Once the command finishes, the state and spools are
+// removed.
+//
+// The state is saved every second or so, and when reading stops at a bad
+// input. Stdin must be the same input each time: a redirected file is
+// read from the saved offset, and a pipe has that many bytes skipped.
+use crate::{
+    binary,
+    entity::{self, Entity},
+    error::Error,
+    input, on_error,
+    par::Input,
+    reader,
+    tile::Tile,
+};
+use serde_json::{json, Value};
+use std::{
+    convert::TryInto,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default, PartialEq)]
+struct State {
+    command: String,
+    // Size of stdin, when it's a file
+    input_len: Option<u64>,
+    // Bytes and lines of stdin read
+    offset: u64,
+    lines: usize,
+    // Bytes of the spools to keep
+    features: u64,
+    tiles: u64,
+    // The last zoom level of tiles finished
+    zoom: Option<u8>,
+}
+
+impl State {
+    fn to_json(&self) -> Value {
+        json!({
+            "command": self.command,
+            "input_len": self.input_len,
+            "offset": self.offset,
+            "lines": self.lines,
+            "features": self.features,
+            "tiles": self.tiles,
+            "zoom": self.zoom,
+        })
+    }
+
+    fn from_json(v: &Value) -> Option<State> {
+        Some(State {
+            command: v["command"].as_str()?.to_string(),
+            input_len: v["input_len"].as_u64(),
+            offset: v["offset"].as_u64()?,
+            lines: v["lines"].as_u64()? as usize,
+            features: v["features"].as_u64()?,
+            tiles: v["tiles"].as_u64()?,
+            zoom: v["zoom"].as_u64().map(|z| z as u8),
+        })
+    }
+}
+
+pub struct Checkpoint {
+    path: PathBuf,
+    state: State,
+    tiles: Option<BufWriter<File>>,
+}
+
+// A spool file kept to the length the state gives, dropping anything
+// written after the state was last saved
+fn open_spool(path: &Path, len: u64) -> Result<File, Error> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    if file.metadata()?.len() < len {
+        return Err(Error::InvalidInput(format!(
+            "Checkpoint spool {} is shorter than its state records",
+            path.display()
+        )));
+    }
+    file.set_len(len)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file)
+}
+
+impl Checkpoint {
+    // Opens the checkpoint at path, resuming it if it exists. It must
+    // have been made by the same command.
+    pub fn open(path: &str, command: &str) -> Result<Checkpoint, Error> {
+        let path = PathBuf::from(path);
+        let state = match fs::read_to_string(&path) {
+            Ok(text) => {
+                let invalid =
+                    || Error::InvalidInput(format!("Invalid checkpoint file: {}", path.display()));
+                let state = serde_json::from_str(&text)
+                    .ok()
+                    .and_then(|v| State::from_json(&v))
+                    .ok_or_else(invalid)?;
+                if state.command != command {
+                    return Err(Error::InvalidInput(format!(
+                        "Checkpoint {} was made by geoq {}, not {}",
+                        path.display(),
+                        state.command,
+                        command
+                    )));
+                }
+                state
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => State {
+                command: command.to_string(),
+                ..State::default()
+            },
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Checkpoint {
+            path,
+            state,
+            tiles: None,
+        })
+    }
+
+    // Whether an earlier run got anywhere
+    pub fn resuming(&self) -> bool {
+        self.state.offset > 0
+    }
+
+    fn spool_path(&self, kind: &str) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".");
+        path.push(kind);
+        PathBuf::from(path)
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, self.state.to_json().to_string())?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    // Hands each entity of stdin to the handler: first those spooled by
+    // earlier runs, then those from the rest of stdin
+    pub fn read_entities<F>(&mut self, mut handler: F) -> Result<(), Error>
+    where
+        F: FnMut(Entity) -> Result<(), Error>,
+    {
+        let spool = open_spool(&self.spool_path("features"), self.state.features)?;
+        let mut replay = BufReader::new(spool);
+        while let Some(line) = reader::read_line(&mut replay) {
+            for e in input::read_line(line).and_then(entity::from_input)? {
+                handler(e)?;
+            }
+        }
+        let mut spool = BufWriter::new(replay.into_inner());
+
+        let res = match reader::map_stdin() {
+            Some(map) => {
+                self.check_input_len(map.len() as u64)?;
+                let mut rest = map.get(self.state.offset as usize..).unwrap_or_default();
+                self.read_lines(&mut rest, &mut spool, &mut handler)
+            }
+            None => {
+                let mut stdin = BufReader::new(io::stdin());
+                let skipped = io::copy(&mut (&mut stdin).take(self.state.offset), &mut io::sink())?;
+                if skipped < self.state.offset {
+                    return Err(Error::InvalidInput(format!(
+                        "Input ended before the {} bytes read by checkpoint {}",
+                        self.state.offset,
+                        self.path.display()
+                    )));
+                }
+                self.read_lines(&mut stdin, &mut spool, &mut handler)
+            }
+        };
+        // Progress up to a bad input is kept, to carry on from once the
+        // input is fixed
+        spool.flush()?;
+        self.save()?;
+        res
+    }
+
+    fn check_input_len(&mut self, len: u64) -> Result<(), Error> {
+        match self.state.input_len {
+            Some(l) if self.resuming() && l != len => Err(Error::InvalidInput(format!(
+                "Input is {} bytes, but checkpoint {} was made for an input of {} bytes",
+                len,
+                self.path.display(),
+                l
+            ))),
+            _ => {
+                self.state.input_len = Some(len);
+                Ok(())
+            }
+        }
+    }
+
+    fn read_lines<F>(
+        &mut self,
+        input: &mut dyn Input,
+        spool: &mut BufWriter<File>,
+        handler: &mut F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Entity) -> Result<(), Error>,
+    {
+        if self.state.offset == 0 && binary::sniff(input.fill_buf()?).is_some() {
+            return Err(Error::InvalidInput(
+                "--checkpoint needs input with one feature per line".to_string(),
+            ));
+        }
+        let mut saved = Instant::now();
+        let mut line = String::new();
+        while let Some(len) = input.next_line(&mut line) {
+            let entities = input::read_line(line.clone())
+                .and_then(entity::from_input)
+                .map_err(|e| e.at(self.state.lines + 1, self.state.offset, &line));
+            match entities {
+                Ok(entities) => {
+                    // A line's features are spooled once all of them are
+                    // handled, so none are replayed and read again
+                    let mut features = String::new();
+                    for e in entities {
+                        features.push_str(&serde_json::to_string(&e.geojson_feature())?);
+                        features.push('\n');
+                        handler(e)?;
+                    }
+                    spool.write_all(features.as_bytes())?;
+                    self.state.features += features.len() as u64;
+                }
+                Err(e) => on_error::handle(&line, e)?,
+            }
+            self.state.offset += len as u64;
+            self.state.lines += 1;
+            line.clear();
+            if saved.elapsed() >= SAVE_INTERVAL {
+                spool.flush()?;
+                self.save()?;
+                saved = Instant::now();
+            }
+        }
+        Ok(())
+    }
+
+    // The last zoom level of tiles which earlier runs finished
+    pub fn zoom(&self) -> Option<u8> {
+        self.state.zoom
+    }
+
+    // Hands each tile spooled by earlier runs to the handler, in the order
+    // they were built
+    pub fn replay_tiles<F>(&mut self, mut handler: F) -> Result<(), Error>
+    where
+        F: FnMut(Tile, Vec<u8>) -> Result<(), Error>,
+    {
+        let mut spool = BufReader::new(open_spool(&self.spool_path("tiles"), self.state.tiles)?);
+        while !spool.fill_buf()?.is_empty() {
+            let mut header = [0u8; 13];
+            spool.read_exact(&mut header)?;
+            let word = |i: usize| u32::from_le_bytes(header[i..i + 4].try_into().unwrap());
+            let t = Tile {
+                z: header[0],
+                x: word(1),
+                y: word(5),
+            };
+            let mut data = vec![0; word(9) as usize];
+            spool.read_exact(&mut data)?;
+            handler(t, data)?;
+        }
+        self.tiles = Some(BufWriter::new(spool.into_inner()));
+        Ok(())
+    }
+
+    pub fn spool_tile(&mut self, t: Tile, data: &[u8]) -> Result<(), Error> {
+        let spool = match &mut self.tiles {
+            Some(spool) => spool,
+            None => {
+                let mut file = open_spool(&self.spool_path("tiles"), self.state.tiles)?;
+                file.seek(SeekFrom::End(0))?;
+                self.tiles.insert(BufWriter::new(file))
+            }
+        };
+        spool.write_all(&[t.z])?;
+        spool.write_all(&t.x.to_le_bytes())?;
+        spool.write_all(&t.y.to_le_bytes())?;
+        spool.write_all(&(data.len() as u32).to_le_bytes())?;
+        spool.write_all(data)?;
+        Ok(())
+    }
+
+    // Records that all of a zoom level's tiles have been spooled
+    pub fn finish_zoom(&mut self, z: u8) -> Result<(), Error> {
+        if let Some(spool) = &mut self.tiles {
+            spool.flush()?;
+            self.state.tiles = spool.get_ref().metadata()?.len();
+        }
+        self.state.zoom = Some(z);
+        self.save()
+    }
+
+    // Removes the state and spools, once the command has finished
+    pub fn finish(self) -> Result<(), Error> {
+        for path in &[
+            self.spool_path("features"),
+            self.spool_path("tiles"),
+            self.path.clone(),
+        ] {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Checkpoint, State};
+    use crate::tile::Tile;
+
+    #[test]
+    fn test_state_json() {
+        let state = State {
+            command: "tiles build".to_string(),
+            input_len: Some(1000),
+            offset: 500,
+            lines: 10,
+            features: 700,
+            tiles: 4000,
+            zoom: Some(3),
+        };
+        assert_eq!(Some(&state), State::from_json(&state.to_json()).as_ref());
+        assert_eq!(None, State::from_json(&serde_json::json!({"command": "x"})));
+    }
+
+    #[test]
+    fn test_tiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let path = path.to_str().unwrap();
+
+        let mut checkpoint = Checkpoint::open(path, "tiles build").unwrap();
+        checkpoint
+            .replay_tiles(|_, _| panic!("No tiles yet"))
+            .unwrap();
+        checkpoint
+            .spool_tile(Tile { z: 0, x: 0, y: 0 }, b"zero")
+            .unwrap();
+        checkpoint.finish_zoom(0).unwrap();
+        // Not finished, so dropped on resume
+        checkpoint
+            .spool_tile(Tile { z: 1, x: 1, y: 0 }, b"one")
+            .unwrap();
+        drop(checkpoint);
+
+        assert!(Checkpoint::open(path, "fgb write").is_err());
+        let mut checkpoint = Checkpoint::open(path, "tiles build").unwrap();
+        assert_eq!(Some(0), checkpoint.zoom());
+        let mut tiles = vec![];
+        checkpoint
+            .replay_tiles(|t, data| {
+                tiles.push((t, data));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(vec![(Tile { z: 0, x: 0, y: 0 }, b"zero".to_vec())], tiles);
+        checkpoint.finish().unwrap();
+        assert_eq!(0, dir.path().read_dir().unwrap().count());
+    }
+}
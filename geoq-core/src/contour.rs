@@ -0,0 +1,327 @@
+// Contour lines over a triangulated surface. Rasters are triangulated by
+// splitting each cell between pixel centers along its diagonal, and
+// scattered points by Delaunay triangulation. Each level crosses a
+// triangle in at most one segment, and segments are joined into lines
+// through the triangle edges they share.
+use geo_types::{Coord, LineString};
+use std::collections::HashMap;
+
+// A surface sample: an id unique within the surface, its position and height
+pub type Vertex = (usize, Coord<f64>, f64);
+
+// Contour levels at base + k * interval
+#[derive(Clone, Copy)]
+pub struct Levels {
+    pub base: f64,
+    pub interval: f64,
+}
+
+impl Levels {
+    fn value(&self, k: i64) -> f64 {
+        self.base + k as f64 * self.interval
+    }
+}
+
+// Triangle edge crossed by a contour, by its vertex ids in order
+type Crossing = (usize, usize);
+
+pub struct Contours {
+    levels: Levels,
+    segments: HashMap<i64, Vec<(Crossing, Crossing)>>,
+    points: HashMap<(i64, Crossing), Coord<f64>>,
+}
+
+impl Contours {
+    pub fn new(levels: Levels) -> Contours {
+        Contours {
+            levels,
+            segments: HashMap::new(),
+            points: HashMap::new(),
+        }
+    }
+
+    fn crossing(&mut self, k: i64, a: &Vertex, b: &Vertex) -> Crossing {
+        let (a, b) = if a.0 < b.0 { (a, b) } else { (b, a) };
+        let key = (a.0, b.0);
+        let level = self.levels.value(k);
+        self.points.entry((k, key)).or_insert_with(|| {
+            if a.2 == level {
+                return a.1;
+            } else if b.2 == level {
+                return b.1;
+            }
+            let t = (level - a.2) / (b.2 - a.2);
+            Coord {
+                x: a.1.x + (b.1.x - a.1.x) * t,
+                y: a.1.y + (b.1.y - a.1.y) * t,
+            }
+        });
+        key
+    }
+
+    pub fn add_triangle(&mut self, t: [Vertex; 3]) {
+        let min = t.iter().map(|v| v.2).fold(f64::INFINITY, f64::min);
+        let max = t.iter().map(|v| v.2).fold(f64::NEG_INFINITY, f64::max);
+        if !min.is_finite() || !max.is_finite() {
+            return;
+        }
+        let Levels { base, interval } = self.levels;
+        let first = ((min - base) / interval).floor() as i64;
+        let last = ((max - base) / interval).ceil() as i64;
+        for k in first..=last {
+            let level = self.levels.value(k);
+            if !(min < level && level <= max) {
+                continue;
+            }
+            // The two edges between vertices on either side of the level.
+            // Vertices on the level count as above it, which keeps lines
+            // through them connected.
+            let mut crossed = vec![];
+            for (a, b) in &[(0, 1), (1, 2), (2, 0)] {
+                if (t[*a].2 >= level) != (t[*b].2 >= level) {
+                    crossed.push(self.crossing(k, &t[*a], &t[*b]));
+                }
+            }
+            if let [from, to] = crossed[..] {
+                self.segments.entry(k).or_default().push((from, to));
+            }
+        }
+    }
+
+    // Lines for each level, lowest first. Lines which close on
+    // themselves start and end at the same point. Lines passing through
+    // vertices on the level would repeat them, and lines made up only of
+    // such vertices (like a peak exactly at the level) are dropped.
+    pub fn lines(&self) -> Vec<(f64, LineString<f64>)> {
+        let mut ks: Vec<&i64> = self.segments.keys().collect();
+        ks.sort();
+        let mut lines = vec![];
+        for k in ks {
+            for chain in join(&self.segments[k]) {
+                let mut coords: Vec<Coord<f64>> =
+                    chain.iter().map(|c| self.points[&(*k, *c)]).collect();
+                coords.dedup();
+                if coords.len() > 1 {
+                    lines.push((self.levels.value(*k), LineString(coords)));
+                }
+            }
+        }
+        lines
+    }
+}
+
+// Chains segments sharing endpoints into lines, starting with open
+// lines (from endpoints used by a single segment), then closed ones
+fn join(segments: &[(Crossing, Crossing)]) -> Vec<Vec<Crossing>> {
+    let mut at: HashMap<Crossing, Vec<usize>> = HashMap::new();
+    for (i, (a, b)) in segments.iter().enumerate() {
+        at.entry(*a).or_default().push(i);
+        at.entry(*b).or_default().push(i);
+    }
+    let mut starts: Vec<Crossing> = at
+        .iter()
+        .filter(|(_, s)| s.len() == 1)
+        .map(|(c, _)| *c)
+        .collect();
+    starts.sort();
+    starts.extend(segments.iter().map(|s| s.0));
+
+    let mut used = vec![false; segments.len()];
+    let mut chains = vec![];
+    for start in starts {
+        let mut chain = vec![start];
+        let mut current = start;
+        while let Some(&i) = at[&current].iter().find(|i| !used[**i]) {
+            used[i] = true;
+            let (a, b) = segments[i];
+            current = if a == current { b } else { a };
+            chain.push(current);
+        }
+        if chain.len() > 1 {
+            chains.push(chain);
+        }
+    }
+    chains
+}
+
+fn circumcircle(a: Coord<f64>, b: Coord<f64>, c: Coord<f64>) -> (Coord<f64>, f64) {
+    let d = 2.0 * (a.x * (b.y - c.y) + b.x * (c.y - a.y) + c.x * (a.y - b.y));
+    let (a2, b2, c2) = (
+        a.x * a.x + a.y * a.y,
+        b.x * b.x + b.y * b.y,
+        c.x * c.x + c.y * c.y,
+    );
+    let center = Coord {
+        x: (a2 * (b.y - c.y) + b2 * (c.y - a.y) + c2 * (a.y - b.y)) / d,
+        y: (a2 * (c.x - b.x) + b2 * (a.x - c.x) + c2 * (b.x - a.x)) / d,
+    };
+    let r2 = (a.x - center.x).powi(2) + (a.y - center.y).powi(2);
+    (center, r2)
+}
+
+// Delaunay triangulation by the Bowyer-Watson algorithm, as indices into
+// the given points. Duplicate points should be removed beforehand.
+pub fn delaunay(points: &[Coord<f64>]) -> Vec<[usize; 3]> {
+    if points.len() < 3 {
+        return vec![];
+    }
+    let (mut min, mut max) = (points[0], points[0]);
+    for p in points {
+        min = Coord {
+            x: min.x.min(p.x),
+            y: min.y.min(p.y),
+        };
+        max = Coord {
+            x: max.x.max(p.x),
+            y: max.y.max(p.y),
+        };
+    }
+    // A triangle enclosing every point, removed at the end
+    let span = (max.x - min.x).max(max.y - min.y).max(1e-9) * 20.0;
+    let mid = Coord {
+        x: (min.x + max.x) / 2.0,
+        y: (min.y + max.y) / 2.0,
+    };
+    let mut all = points.to_vec();
+    let n = points.len();
+    all.push(Coord {
+        x: mid.x - span,
+        y: mid.y - span,
+    });
+    all.push(Coord {
+        x: mid.x + span,
+        y: mid.y - span,
+    });
+    all.push(Coord {
+        x: mid.x,
+        y: mid.y + span,
+    });
+
+    // Points are inserted from west to east, so triangles whose
+    // circumcircles are entirely west of the current point can't be
+    // affected by later ones, and are set aside
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|a, b| points[*a].x.partial_cmp(&points[*b].x).unwrap());
+    let mut done: Vec<[usize; 3]> = vec![];
+    let mut triangles: Vec<([usize; 3], Coord<f64>, f64)> = vec![];
+    let (c, r2) = circumcircle(all[n], all[n + 1], all[n + 2]);
+    triangles.push(([n, n + 1, n + 2], c, r2));
+    for i in order {
+        let p = points[i];
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        triangles.retain(|(t, c, r2)| {
+            let dx = p.x - c.x;
+            if dx > 0.0 && dx * dx > *r2 {
+                done.push(*t);
+                return false;
+            }
+            let inside = dx * dx + (p.y - c.y).powi(2) < *r2;
+            if inside {
+                for (a, b) in &[(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+                    *edges.entry((*a.min(b), *a.max(b))).or_insert(0) += 1;
+                }
+            }
+            !inside
+        });
+        // Edges of the cavity are those used by a single removed triangle
+        for ((a, b), count) in edges {
+            if count == 1 {
+                let (c, r2) = circumcircle(all[a], all[b], p);
+                if r2.is_finite() {
+                    triangles.push(([a, b, i], c, r2));
+                }
+            }
+        }
+    }
+    done.extend(triangles.into_iter().map(|(t, _, _)| t));
+    done.into_iter()
+        .filter(|t| t.iter().all(|v| *v < n))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delaunay, Contours, Levels};
+    use geo_types::{Coord, LineString};
+
+    #[test]
+    fn test_delaunay() {
+        let points: Vec<Coord<f64>> =
+            vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.5, 0.4)]
+                .into_iter()
+                .map(|(x, y)| Coord { x, y })
+                .collect();
+        let mut triangles: Vec<Vec<usize>> = delaunay(&points)
+            .iter()
+            .map(|t| {
+                let mut t = t.to_vec();
+                t.sort();
+                t
+            })
+            .collect();
+        triangles.sort();
+        assert_eq!(
+            vec![vec![0, 1, 4], vec![0, 3, 4], vec![1, 2, 4], vec![2, 3, 4]],
+            triangles
+        );
+    }
+
+    fn grid_contours(z: [f64; 9]) -> Vec<(f64, LineString<f64>)> {
+        let v = |i: usize| {
+            (
+                i,
+                Coord {
+                    x: (i % 3) as f64,
+                    y: (i / 3) as f64,
+                },
+                z[i],
+            )
+        };
+        let mut contours = Contours::new(Levels {
+            base: 0.0,
+            interval: 5.0,
+        });
+        for (a, b, c, d) in &[(0, 1, 4, 3), (1, 2, 5, 4), (3, 4, 7, 6), (4, 5, 8, 7)] {
+            contours.add_triangle([v(*a), v(*b), v(*c)]);
+            contours.add_triangle([v(*a), v(*c), v(*d)]);
+        }
+        contours.lines()
+    }
+
+    // A peak at the middle vertex of a 3x3 grid gives a closed contour
+    // around it
+    #[test]
+    fn test_contours_closed() {
+        let lines = grid_contours([0.0, 0.0, 0.0, 0.0, 10.0, 0.0, 0.0, 0.0, 0.0]);
+        // Nothing is below 0, and 10 only touches the peak
+        let levels: Vec<f64> = lines.iter().map(|(l, _)| *l).collect();
+        assert_eq!(vec![5.0], levels);
+        let (_, ring) = &lines[0];
+        assert_eq!(ring.0.first(), ring.0.last());
+        assert_eq!(7, ring.0.len());
+        let rounded: Vec<Coord<f64>> = ring
+            .0
+            .iter()
+            .map(|c| Coord {
+                x: (c.x * 1e6).round() / 1e6,
+                y: (c.y * 1e6).round() / 1e6,
+            })
+            .collect();
+        assert!(rounded.contains(&Coord { x: 1.5, y: 1.0 }));
+    }
+
+    // Vertices on the level count as above it, so lines pass through
+    // them, and leave the grid where they are on its edge
+    #[test]
+    fn test_contours_on_vertices() {
+        let lines = grid_contours([0.0, 5.0, 0.0, 5.0, 10.0, 5.0, 0.0, 5.0, 0.0]);
+        assert_eq!(4, lines.len());
+        assert_eq!(
+            (
+                5.0,
+                LineString::from(vec![(1.0, 0.0), (0.5, 0.5), (0.0, 1.0)])
+            ),
+            lines[0]
+        );
+    }
+}
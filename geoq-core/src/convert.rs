@@ -0,0 +1,180 @@
+//! Conversions of a single input, with no IO, for embedding geoq in other
+//! programs (e.g. behind JavaScript bindings) rather than running it over
+//! stdin. An input is anything geoq reads as a line: Lat/Lon, geohash,
+//! H3 cell, tile, quadkey, WKT or GeoJSON. A GeoJSON FeatureCollection gives one entity
+//! per feature, so conversions of it give one line, or WKB geometry, per
+//! feature.
+use crate::{
+    bbox,
+    binary::wkb,
+    entity::{self, Entity},
+    error::Error,
+    fgb::{self, hilbert::BBox},
+    input::{self, Input},
+    tile::{self, Tile},
+};
+use geo_types::{Coord, Point};
+
+/// The entities of the input.
+pub fn parse(input: &str) -> Result<Vec<Entity>, Error> {
+    input::read_line(input.trim().to_string()).and_then(entity::from_input)
+}
+
+/// The kind of input geoq reads it as
+pub fn input_type(input: &str) -> Result<&'static str, Error> {
+    Ok(match input::read_line(input.trim().to_string())? {
+        Input::LatLon(_) => "latlon",
+        Input::Geohash(_) => "geohash",
+        Input::WKT(_) => "wkt",
+        Input::GeoJSON(_) => "geojson",
+        Input::H3(_) => "h3",
+        Input::Tile(_) => "tile",
+        Input::Quadkey(_) => "quadkey",
+    })
+}
+
+fn lines<F>(input: &str, line: F) -> Result<String, Error>
+where
+    F: Fn(&Entity) -> String,
+{
+    let lines: Vec<String> = parse(input)?.iter().map(line).collect();
+    Ok(lines.join("\n"))
+}
+
+/// GeoJSON Features, a line each
+pub fn to_geojson(input: &str) -> Result<String, Error> {
+    lines(input, |e| {
+        serde_json::to_string(&e.geojson_feature()).unwrap()
+    })
+}
+
+/// WKT geometries, a line each
+pub fn to_wkt(input: &str) -> Result<String, Error> {
+    lines(input, |e| e.wkt().to_string())
+}
+
+/// WKB geometries, back to back
+pub fn to_wkb(input: &str) -> Result<Vec<u8>, Error> {
+    Ok(parse(input)?
+        .iter()
+        .flat_map(|e| wkb::write(&e.geojson_geometry()))
+        .collect())
+}
+
+/// GeoJSON geometries, a line each, from WKB geometries back to back
+pub fn from_wkb(data: &[u8]) -> Result<String, Error> {
+    let lines: Vec<String> = wkb::read_all(data)?
+        .iter()
+        .map(|g| serde_json::to_string(g).unwrap())
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// [min lon, min lat, max lon, max lat] of all of the input's entities
+pub fn bbox(input: &str) -> Result<[f64; 4], Error> {
+    let rects: Vec<geo::Rect<f64>> = parse(input)?
+        .iter()
+        .map(|e| bbox::bbox(&e.geom()))
+        .collect();
+    let rect = rects.iter().skip(1).fold(
+        rects.first().cloned().unwrap_or_else(bbox::zero_rect),
+        |a, b| bbox::merge(&a, b),
+    );
+    Ok([rect.min().x, rect.min().y, rect.max().x, rect.max().y])
+}
+
+/// Position of the input's bbox center along a hilbert curve filling the
+/// extent, as fgb write and sort use to order features
+pub fn hilbert(input: &str, extent: [f64; 4]) -> Result<u32, Error> {
+    let [min_x, min_y, max_x, max_y] = bbox(input)?;
+    let bbox = BBox {
+        min_x,
+        min_y,
+        max_x,
+        max_y,
+    };
+    let extent = BBox {
+        min_x: extent[0],
+        min_y: extent[1],
+        max_x: extent[2],
+        max_y: extent[3],
+    };
+    Ok(bbox.hilbert_bbox(&extent))
+}
+
+/// A FlatGeobuf file of the entities of each line of input
+#[cfg(feature = "fgb")]
+pub fn to_fgb(input: &str) -> Result<Vec<u8>, Error> {
+    let mut features = vec![];
+    for line in input.lines().filter(|l| !l.trim().is_empty()) {
+        features.extend(parse(line)?.iter().map(|e| e.geojson_feature()));
+    }
+    Ok(fgb::write(features))
+}
+
+/// The geohash of the given level containing a point
+pub fn geohash(lat: f64, lon: f64, level: usize) -> Result<String, Error> {
+    geohash::encode(Coord { x: lon, y: lat }, level)
+        .map_err(|e| Error::InvalidInput(format!("Can't encode geohash: {:?}", e)))
+}
+
+/// The z/x/y tile containing a point
+pub fn tile(lat: f64, lon: f64, zoom: u8) -> Result<String, Error> {
+    let z = tile::validate_zoom(zoom)?;
+    Ok(Tile::for_point(&Point::new(lon, lat), z).to_string())
+}
+
+/// The quadkey of a z/x/y tile
+pub fn quadkey(tile: &str) -> Result<String, Error> {
+    Ok(tile.parse::<Tile>()?.quadkey())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bbox, from_wkb, geohash, hilbert, input_type, parse, quadkey, tile, to_geojson, to_wkb,
+        to_wkt,
+    };
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!("POINT(-118 34)", to_wkt("34,-118").unwrap());
+        assert_eq!(
+            r#"{"geometry":{"coordinates":[1.0,2.0],"type":"Point"},"properties":{},"type":"Feature"}"#,
+            to_geojson("POINT (1 2)").unwrap()
+        );
+        let collection = r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[1,2]}},{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[3,4]}}]}"#;
+        assert_eq!("POINT(1 2)\nPOINT(3 4)", to_wkt(collection).unwrap());
+        assert_eq!(
+            "{\"coordinates\":[1.0,2.0],\"type\":\"Point\"}\n{\"coordinates\":[3.0,4.0],\"type\":\"Point\"}",
+            from_wkb(&to_wkb(collection).unwrap()).unwrap()
+        );
+        assert_eq!([1.0, 2.0, 3.0, 4.0], bbox(collection).unwrap());
+        assert!(parse("pizza").is_err());
+    }
+
+    #[test]
+    fn test_cells() {
+        assert_eq!("9q5ct", geohash(34.0522, -118.2437, 5).unwrap());
+        assert_eq!("12/702/1635", tile(34.0522, -118.2437, 12).unwrap());
+        assert_eq!("213", quadkey("3/3/5").unwrap());
+        assert!(tile(0.0, 0.0, 40).is_err());
+        assert_eq!("geohash", input_type("9q5").unwrap());
+        assert_eq!("h3", input_type("8729a1d75ffffff").unwrap());
+        assert_eq!("tile", input_type("3/3/5").unwrap());
+        assert_eq!("quadkey", input_type("qk:213").unwrap());
+        assert_eq!("geohash", input_type("0123").unwrap());
+        let extent = [0.0, 0.0, 10.0, 10.0];
+        assert_eq!(0, hilbert("POINT (0 0)", extent).unwrap());
+        assert!(hilbert("POINT (9 1)", extent).unwrap() > hilbert("POINT (1 1)", extent).unwrap());
+    }
+
+    #[cfg(feature = "fgb")]
+    #[test]
+    fn test_fgb() {
+        use super::to_fgb;
+        let data = to_fgb("POINT (1 2)\n\n9q5\n").unwrap();
+        assert_eq!(b"fgb", &data[..3]);
+        assert!(to_fgb("POINT (1 2)\npizza").is_err());
+    }
+}
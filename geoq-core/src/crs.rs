@@ -0,0 +1,269 @@
+// The coordinate reference systems geoq can convert to and from WGS84
+// lon/lat: geographic, Web Mercator and WGS84 UTM. They're used to place
+// rasters and to reproject features.
+use crate::{error::Error, generalize, tile};
+use geo::algorithm::map_coords::MapCoords;
+use geo_types::{Coord, Geometry};
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Crs {
+    Geographic,
+    WebMercator,
+    Utm { zone: u8, north: bool },
+}
+
+impl Crs {
+    pub fn from_epsg(code: u16) -> Result<Crs, Error> {
+        match code {
+            4000..=4999 => Ok(Crs::Geographic),
+            3857 | 3785 => Ok(Crs::WebMercator),
+            32601..=32660 => Ok(Crs::Utm {
+                zone: (code - 32600) as u8,
+                north: true,
+            }),
+            32701..=32760 => Ok(Crs::Utm {
+                zone: (code - 32700) as u8,
+                north: false,
+            }),
+            _ => Err(Error::InvalidInput(format!(
+                "Unsupported CRS: EPSG:{} -- expected geographic, Web Mercator (EPSG:3857) or WGS84 UTM (EPSG:326xx/327xx)",
+                code
+            ))),
+        }
+    }
+
+    pub fn to_lon_lat(self, c: Coord<f64>) -> Coord<f64> {
+        match self {
+            Crs::Geographic => c,
+            Crs::WebMercator => Coord {
+                x: (c.x / WGS84_A).to_degrees(),
+                y: (2.0 * (c.y / WGS84_A).exp().atan() - PI / 2.0).to_degrees(),
+            },
+            Crs::Utm { zone, north } => utm_to_lon_lat(c, zone, north),
+        }
+    }
+
+    pub fn from_lon_lat(self, c: Coord<f64>) -> Coord<f64> {
+        match self {
+            Crs::Geographic => c,
+            Crs::WebMercator => {
+                let lat = c.y.clamp(-tile::MAX_LAT, tile::MAX_LAT).to_radians();
+                Coord {
+                    x: WGS84_A * c.x.to_radians(),
+                    y: WGS84_A * lat.tan().asinh(),
+                }
+            }
+            Crs::Utm { zone, north } => lon_lat_to_utm(c, zone, north),
+        }
+    }
+}
+
+// An EPSG code, such as EPSG:3857 or just 3857
+impl FromStr for Crs {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Crs, Error> {
+        let code = s.trim();
+        let code = code
+            .strip_prefix("EPSG:")
+            .or_else(|| code.strip_prefix("epsg:"))
+            .unwrap_or(code);
+        let code = code.parse::<u16>().map_err(|_| {
+            Error::InvalidInput(format!("Expected an EPSG code, e.g. EPSG:3857: {}", s))
+        })?;
+        Crs::from_epsg(code)
+    }
+}
+
+// Converts a geometry's coordinates from one CRS to another. With
+// densify, segments are first split so that none is longer than that
+// many meters, so that they follow the straight line they were in the
+// original coordinates, rather than cutting across its curve in the new
+// ones.
+pub fn transform(geom: &Geometry<f64>, from: Crs, to: Crs, densify: Option<f64>) -> Geometry<f64> {
+    let geom = match densify {
+        Some(max_meters) => generalize::densify_by(geom, max_meters, &|a, b| {
+            generalize::geodesic_meters(from.to_lon_lat(a), from.to_lon_lat(b))
+        }),
+        None => geom.clone(),
+    };
+    if from == to {
+        return geom;
+    }
+    geom.map_coords(|c| to.from_lon_lat(from.to_lon_lat(c)))
+}
+
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+fn central_meridian(zone: u8) -> f64 {
+    (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0
+}
+
+// Transverse Mercator, following Snyder's "Map Projections: A Working
+// Manual" (1987), equations 8-9 to 8-13
+fn lon_lat_to_utm(c: Coord<f64>, zone: u8, north: bool) -> Coord<f64> {
+    let k0 = 0.9996;
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let ep2 = e2 / (1.0 - e2);
+    let phi = c.y.to_radians();
+    let (sin, cos, tan) = (phi.sin(), phi.cos(), phi.tan());
+    let n = WGS84_A / (1.0 - e2 * sin.powi(2)).sqrt();
+    let t = tan.powi(2);
+    let c1 = ep2 * cos.powi(2);
+    let a = (c.x - central_meridian(zone)).to_radians() * cos;
+    let m = WGS84_A
+        * ((1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0) * phi
+            - (3.0 * e2 / 8.0 + 3.0 * e2.powi(2) / 32.0 + 45.0 * e2.powi(3) / 1024.0)
+                * (2.0 * phi).sin()
+            + (15.0 * e2.powi(2) / 256.0 + 45.0 * e2.powi(3) / 1024.0) * (4.0 * phi).sin()
+            - (35.0 * e2.powi(3) / 3072.0) * (6.0 * phi).sin());
+
+    let x = k0
+        * n
+        * (a + (1.0 - t + c1) * a.powi(3) / 6.0
+            + (5.0 - 18.0 * t + t.powi(2) + 72.0 * c1 - 58.0 * ep2) * a.powi(5) / 120.0);
+    let y = k0
+        * (m + n
+            * tan
+            * (a.powi(2) / 2.0
+                + (5.0 - t + 9.0 * c1 + 4.0 * c1.powi(2)) * a.powi(4) / 24.0
+                + (61.0 - 58.0 * t + t.powi(2) + 600.0 * c1 - 330.0 * ep2) * a.powi(6) / 720.0));
+    Coord {
+        x: x + 500_000.0,
+        y: if north { y } else { y + 10_000_000.0 },
+    }
+}
+
+// Inverse transverse Mercator, following Snyder's "Map Projections:
+// A Working Manual" (1987), equations 8-12 to 8-25
+fn utm_to_lon_lat(c: Coord<f64>, zone: u8, north: bool) -> Coord<f64> {
+    let k0 = 0.9996;
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let ep2 = e2 / (1.0 - e2);
+    let x = c.x - 500_000.0;
+    let y = if north { c.y } else { c.y - 10_000_000.0 };
+
+    let m = y / k0;
+    let mu = m / (WGS84_A * (1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0));
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let phi1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1.powi(2) / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+    let (sin1, cos1, tan1) = (phi1.sin(), phi1.cos(), phi1.tan());
+    let c1 = ep2 * cos1.powi(2);
+    let t1 = tan1.powi(2);
+    let n1 = WGS84_A / (1.0 - e2 * sin1.powi(2)).sqrt();
+    let r1 = WGS84_A * (1.0 - e2) / (1.0 - e2 * sin1.powi(2)).powf(1.5);
+    let d = x / (n1 * k0);
+
+    let lat = phi1
+        - (n1 * tan1 / r1)
+            * (d.powi(2) / 2.0
+                - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1.powi(2) - 9.0 * ep2) * d.powi(4) / 24.0
+                + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1.powi(2)
+                    - 252.0 * ep2
+                    - 3.0 * c1.powi(2))
+                    * d.powi(6)
+                    / 720.0);
+    let lon = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+        + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1.powi(2) + 8.0 * ep2 + 24.0 * t1.powi(2))
+            * d.powi(5)
+            / 120.0)
+        / cos1;
+    Coord {
+        x: central_meridian(zone) + lon.to_degrees(),
+        y: lat.to_degrees(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{transform, Crs};
+    use geo_types::{Coord, Geometry, LineString};
+
+    fn assert_close(expected: (f64, f64), actual: Coord<f64>) {
+        assert!(
+            (expected.0 - actual.x).abs() < 1e-5 && (expected.1 - actual.y).abs() < 1e-5,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_utm_to_lon_lat() {
+        let utm = Crs::from_epsg(32618).unwrap();
+        assert_close(
+            (-75.0, 40.0),
+            utm.to_lon_lat(Coord {
+                x: 500_000.0,
+                y: 4_427_757.218_9,
+            }),
+        );
+        let south = Crs::from_epsg(32756).unwrap();
+        assert_close(
+            (151.209_295, -33.868_820),
+            south.to_lon_lat(Coord {
+                x: 334_368.209_8,
+                y: 6_250_946.119_4,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_web_mercator_to_lon_lat() {
+        let crs = Crs::from_epsg(3857).unwrap();
+        assert_close(
+            (-180.0, 85.051_128_779_806_59),
+            crs.to_lon_lat(Coord {
+                x: -20_037_508.342_789_244,
+                y: 20_037_508.342_789_244,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_unsupported_crs() {
+        assert!(Crs::from_epsg(27700).is_err());
+    }
+
+    #[test]
+    fn test_from_lon_lat() {
+        let utm: Crs = "EPSG:32756".parse().unwrap();
+        let c = utm.from_lon_lat(Coord {
+            x: 151.209_295,
+            y: -33.868_820,
+        });
+        assert!((c.x - 334_368.209_8).abs() < 0.01 && (c.y - 6_250_946.119_4).abs() < 0.01);
+        let mercator: Crs = "3857".parse().unwrap();
+        let back = mercator.to_lon_lat(mercator.from_lon_lat(Coord { x: -75.0, y: 40.0 }));
+        assert_close((-75.0, 40.0), back);
+    }
+
+    #[test]
+    fn test_transform_densify() {
+        // A long meridian-crossing line, straight in lon/lat, bends in UTM
+        let line = Geometry::LineString(LineString::from(vec![(-80.0, 40.0), (-70.0, 45.0)]));
+        let utm = Crs::from_epsg(32618).unwrap();
+        let coarse = transform(&line, Crs::Geographic, utm, None);
+        let dense = transform(&line, Crs::Geographic, utm, Some(10_000.0));
+        let vertices = |g: &Geometry<f64>| match g {
+            Geometry::LineString(ls) => ls.0.clone(),
+            _ => vec![],
+        };
+        assert_eq!(2, vertices(&coarse).len());
+        let dense = vertices(&dense);
+        assert!(dense.len() > 90);
+        assert_eq!(vertices(&coarse)[1], *dense.last().unwrap());
+        // The middle of the densified line is off the straight UTM line
+        let mid = dense[dense.len() / 2];
+        let straight = (vertices(&coarse)[0] + vertices(&coarse)[1]) / 2.0;
+        assert!((mid.y - straight.y).abs() > 1000.0);
+    }
+}
@@ -0,0 +1,94 @@
+// CSV records, with fields quoted as in RFC 4180: "" for a quote, and
+// commas and newlines allowed inside quotes. Shared by lookup tables
+// (props join) and geocode --csv-column, which reads its records as
+// they come in.
+use crate::error::Error;
+use std::io::BufRead;
+
+// Reads the next record, which may span several lines when a quoted
+// field has newlines in it. None at the end of the input.
+pub fn read_record(input: &mut dyn BufRead) -> Result<Option<Vec<String>>, Error> {
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            if quoted {
+                return Err(Error::InvalidInput(
+                    "Unterminated quoted field in CSV".to_string(),
+                ));
+            }
+            if field.is_empty() && record.is_empty() {
+                return Ok(None);
+            }
+            record.push(field);
+            return Ok(Some(record));
+        }
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (quoted, c) {
+                (true, '"') if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                (true, '"') => quoted = false,
+                (true, c) => field.push(c),
+                (false, '"') if field.is_empty() => quoted = true,
+                (false, ',') => record.push(std::mem::take(&mut field)),
+                (false, '\r') if chars.peek() == Some(&'\n') => (),
+                (false, '\n') => {
+                    record.push(field);
+                    return Ok(Some(record));
+                }
+                (false, c) => field.push(c),
+            }
+        }
+    }
+}
+
+// All the records of CSV text
+pub fn records(text: &str) -> Result<Vec<Vec<String>>, Error> {
+    let mut input = text.as_bytes();
+    let mut records = vec![];
+    while let Some(record) = read_record(&mut input)? {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+// True for records of a blank line, or of only empty fields
+pub fn is_blank(record: &[String]) -> bool {
+    record.iter().all(|f| f.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_record, records};
+
+    #[test]
+    fn test_records() {
+        assert_eq!(
+            vec![vec!["a", "b"], vec!["1", "x, \"y\"\nz"], vec!["", ""]],
+            records("a,b\r\n1,\"x, \"\"y\"\"\nz\"\n,\n").unwrap()
+        );
+        assert_eq!(vec![vec!["a", "b", ""]], records("a,b,").unwrap());
+        assert!(records("a,\"b").is_err());
+    }
+
+    #[test]
+    fn test_read_record() {
+        let mut input =
+            "1,\"1600 Pennsylvania Ave, Washington\",\"say \"\"hi\"\"\"\nnext".as_bytes();
+        assert_eq!(
+            Some(vec![
+                "1".to_string(),
+                "1600 Pennsylvania Ave, Washington".to_string(),
+                "say \"hi\"".to_string()
+            ]),
+            read_record(&mut input).unwrap()
+        );
+        assert_eq!(b"next", input);
+    }
+}
@@ -0,0 +1,207 @@
+// Elevation sampling from Terrarium-encoded DEM tiles, as published by
+// the AWS Terrain Tiles dataset. Each pixel encodes meters above sea level
+// as (R * 256 + G + B / 256) - 32768.
+use crate::{
+    error::Error,
+    net,
+    png::{self, Image},
+    tile::{Tile, MAX_LAT},
+};
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub struct Dem {
+    // Directory of {z}/{x}/{y}.png tiles, or a path or URL template
+    // containing {z}, {x} and {y}
+    source: String,
+    zoom: u8,
+    // Decoded tiles, or None for tiles missing from the source
+    tiles: HashMap<Tile, Option<Image>>,
+}
+
+fn terrarium(rgb: [u8; 3]) -> f64 {
+    rgb[0] as f64 * 256.0 + rgb[1] as f64 + rgb[2] as f64 / 256.0 - 32768.0
+}
+
+impl Dem {
+    pub fn new(source: &str, zoom: u8) -> Dem {
+        Dem {
+            source: source.to_string(),
+            zoom,
+            tiles: HashMap::new(),
+        }
+    }
+
+    fn location(&self, t: Tile) -> String {
+        if self.source.contains("{z}") {
+            self.source
+                .replace("{z}", &t.z.to_string())
+                .replace("{x}", &t.x.to_string())
+                .replace("{y}", &t.y.to_string())
+        } else {
+            Path::new(&self.source)
+                .join(format!("{}.png", t))
+                .to_string_lossy()
+                .to_string()
+        }
+    }
+
+    fn is_remote(&self) -> bool {
+        self.source.starts_with("http://") || self.source.starts_with("https://")
+    }
+
+    async fn download(location: String) -> Result<Option<Vec<u8>>, Error> {
+        let resp = net::send(net::client().get(&location)).await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(Error::ProgramError(format!(
+                "DEM tile request to {} failed: {}",
+                location,
+                resp.status()
+            )));
+        }
+        Ok(Some(resp.bytes().await?.to_vec()))
+    }
+
+    fn fetch(&self, t: Tile) -> Result<Option<Vec<u8>>, Error> {
+        let location = self.location(t);
+        if self.is_remote() {
+            net::block_on(Dem::download(location))
+        } else {
+            match fs::read(&location) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+
+    fn insert(&mut self, t: Tile, data: Option<Vec<u8>>) -> Result<(), Error> {
+        let image = match data {
+            Some(data) => Some(png::decode(&data).map_err(|e| match e {
+                Error::InvalidInput(m) => {
+                    Error::InvalidInput(format!("{} (DEM tile {})", m, self.location(t)))
+                }
+                e => e,
+            })?),
+            None => None,
+        };
+        self.tiles.insert(t, image);
+        Ok(())
+    }
+
+    fn load(&mut self, t: Tile) -> Result<Option<&Image>, Error> {
+        if !self.tiles.contains_key(&t) {
+            let data = self.fetch(t)?;
+            self.insert(t, data)?;
+        }
+        Ok(self.tiles[&t].as_ref())
+    }
+
+    fn tile_for(&self, lon: f64, lat: f64) -> Tile {
+        let (fx, fy) = self.fraction(lon, lat);
+        let n = 1i64 << self.zoom;
+        Tile {
+            z: self.zoom,
+            x: (fx.floor() as i64).rem_euclid(n) as u32,
+            y: (fy.floor() as i64).clamp(0, n - 1) as u32,
+        }
+    }
+
+    // Downloads the tiles under the given (lon, lat) points which haven't
+    // been loaded yet all at once, rather than one by one as they're
+    // sampled
+    pub fn prefetch(&mut self, points: &[(f64, f64)]) -> Result<(), Error> {
+        if !self.is_remote() {
+            return Ok(());
+        }
+        let tiles: HashSet<Tile> = points
+            .iter()
+            .map(|&(lon, lat)| self.tile_for(lon, lat))
+            .filter(|t| !self.tiles.contains_key(t))
+            .collect();
+        let tiles: Vec<Tile> = tiles.into_iter().collect();
+        let locations = tiles.iter().map(|&t| self.location(t)).collect();
+        let downloads = net::batch(locations, Dem::download);
+        for (t, data) in tiles.into_iter().zip(downloads) {
+            self.insert(t, data?)?;
+        }
+        Ok(())
+    }
+
+    // Position of a point in tiles across the zoom level
+    fn fraction(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let n = (1u64 << self.zoom) as f64;
+        let lat = lat.clamp(-MAX_LAT, MAX_LAT).to_radians();
+        let fx = (lon + 180.0) / 360.0 * n;
+        let fy = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+        (fx, fy)
+    }
+
+    // Elevation of a pixel, counted across the whole zoom level. Columns
+    // wrap around the antimeridian and rows are clamped at the poles.
+    fn pixel(&mut self, x: i64, y: i64, size: usize) -> Result<Option<f64>, Error> {
+        let span = (size as i64) << self.zoom;
+        let (x, y) = (x.rem_euclid(span), y.clamp(0, span - 1));
+        let size = size as i64;
+        let t = Tile {
+            z: self.zoom,
+            x: (x / size) as u32,
+            y: (y / size) as u32,
+        };
+        Ok(self.load(t)?.and_then(|image| {
+            let (px, py) = ((x % size) as usize, (y % size) as usize);
+            if px < image.width && py < image.height {
+                Some(terrarium(image.get(px, py)))
+            } else {
+                None
+            }
+        }))
+    }
+
+    // Bilinearly interpolated elevation in meters, or None where
+    // the source has no tiles
+    pub fn sample(&mut self, lon: f64, lat: f64) -> Result<Option<f64>, Error> {
+        let (fx, fy) = self.fraction(lon, lat);
+        let t = self.tile_for(lon, lat);
+        let size = match self.load(t)? {
+            Some(image) => image.width,
+            None => return Ok(None),
+        };
+
+        // Interpolate between the centers of the 4 nearest pixels
+        let (px, py) = (fx * size as f64 - 0.5, fy * size as f64 - 0.5);
+        let (x0, y0) = (px.floor() as i64, py.floor() as i64);
+        let (dx, dy) = (px - x0 as f64, py - y0 as f64);
+        let mut total = 0.0;
+        for (x, y, weight) in &[
+            (x0, y0, (1.0 - dx) * (1.0 - dy)),
+            (x0 + 1, y0, dx * (1.0 - dy)),
+            (x0, y0 + 1, (1.0 - dx) * dy),
+            (x0 + 1, y0 + 1, dx * dy),
+        ] {
+            match self.pixel(*x, *y, size)? {
+                Some(e) => total += e * weight,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::terrarium;
+
+    #[test]
+    fn test_terrarium() {
+        assert_eq!(0.0, terrarium([128, 0, 0]));
+        assert_eq!(100.5, terrarium([128, 100, 128]));
+        assert_eq!(-10.0, terrarium([127, 246, 0]));
+    }
+}
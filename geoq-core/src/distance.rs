@@ -1,3 +1,4 @@
+use crate::rhumb;
 use geo::algorithm::closest_point::ClosestPoint;
 use geo::algorithm::contains::Contains;
 use geo::algorithm::vincenty_distance::VincentyDistance;
@@ -78,9 +79,18 @@ pub fn distance(a: &Point<f64>, b: &Geometry<f64>) -> Option<f64> {
     }
 }
 
+// Distance in meters along the rhumb line to the closest point of b
+pub fn rhumb_distance(a: &Point<f64>, b: &Geometry<f64>) -> Option<f64> {
+    match closest_point(a, b) {
+        geo::Closest::Intersection(_) => Some(0.0),
+        geo::Closest::SinglePoint(p) => Some(rhumb::distance(a.0, p.0)),
+        geo::Closest::Indeterminate => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::geoq::distance::distance;
+    use crate::distance::distance;
     use geo_types::{Geometry, Point, Polygon};
     use wkt::ToWkt;
 
@@ -0,0 +1,985 @@
+//! [`Entity`], the geometries geoq reads from a line of input, and their
+//! conversions to `geo`, WKT and GeoJSON.
+use crate::{
+    bbox, coord_count,
+    error::Error,
+    fast_geojson::{self, Parsed},
+    input::{self, Input, Parsing},
+    report,
+    tile::Tile,
+};
+use geo_types::{Coord, Geometry, LineString, Point, Polygon};
+use geojson::GeoJson;
+#[cfg(feature = "h3")]
+use h3ron::{H3Cell, Index, ToPolygon};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{self, value::RawValue};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    convert::TryInto,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use wkt::ToWkt;
+
+static LATLON_SPLIT: Lazy<Regex> = Lazy::new(|| Regex::new(",|\t").unwrap());
+
+// Set from the global --strip-foreign flag
+static STRIP_FOREIGN: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether foreign members of GeoJSON inputs are dropped from output, as
+/// with the global --strip-foreign flag.
+pub fn set_strip_foreign(strip: bool) {
+    STRIP_FOREIGN.store(strip, Ordering::Relaxed);
+}
+
+fn foreign(
+    members: &Option<serde_json::Map<String, serde_json::Value>>,
+) -> Option<serde_json::Map<String, serde_json::Value>> {
+    if STRIP_FOREIGN.load(Ordering::Relaxed) {
+        None
+    } else {
+        members.clone()
+    }
+}
+
+/// A geometry read from input, along with the text it came from. More
+/// kinds may be added in minor releases.
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum Entity {
+    /// A "lat,lon" or tab-separated point
+    LatLon(String),
+    Geohash(String),
+    Wkt(String, geo_types::Geometry<f64>),
+    GeoJsonFeature(String, geojson::Feature),
+    GeoJsonGeometry(String, geojson::Geometry),
+    /// A z/x/y tile or a quadkey, as it was given
+    Tile(String, Tile),
+    #[cfg(feature = "h3")]
+    H3(H3Cell),
+}
+
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Entity::LatLon(ref raw) => write!(f, "LatLon: {}", raw),
+            Entity::Geohash(ref raw) => write!(f, "Geohash: {}", raw),
+            Entity::Wkt(ref raw, _) => write!(f, "WKT: {}", raw),
+            Entity::GeoJsonFeature(ref raw, _) => write!(f, "GeoJSON Feature: {}", raw),
+            Entity::GeoJsonGeometry(ref raw, _) => write!(f, "GeoJSON Geometry: {}", raw),
+            #[cfg(feature = "h3")]
+            Entity::H3(ref raw) => write!(
+                f,
+                "H3 Cell: {} at resolution {}",
+                raw.to_string(),
+                raw.resolution()
+            ),
+            Entity::Tile(ref raw, tile) => write!(f, "Tile: {} at zoom {}", raw, tile.z),
+        }
+    }
+}
+
+fn latlon_geom(raw: &String) -> geo_types::Geometry<f64> {
+    let pieces = LATLON_SPLIT.split(raw).collect::<Vec<&str>>();
+    let lat = pieces[0].parse::<f64>().unwrap();
+    let lon = pieces[1].parse::<f64>().unwrap();
+    Geometry::Point(Point::new(lon, lat))
+}
+
+fn geohash_geom(raw: &String) -> geo_types::Geometry<f64> {
+    let rect = geohash::decode_bbox(raw).expect("Invalid geohash");
+    let bl = rect.min();
+    let tr = rect.max();
+    let outer = LineString(vec![
+        Coord::from((bl.x, bl.y)),
+        Coord::from((tr.x, bl.y)),
+        Coord::from((tr.x, tr.y)),
+        Coord::from((bl.x, tr.y)),
+        Coord::from((bl.x, bl.y)),
+    ]);
+    Geometry::Polygon(Polygon::new(outer, Vec::new()))
+}
+
+#[cfg(feature = "h3")]
+fn h3_geom(raw: &H3Cell) -> geo_types::Geometry<f64> {
+    // TODO: does this ever fail?
+    geo_types::Geometry::Polygon(raw.to_polygon().unwrap())
+}
+
+// GeoJSON geometries are only converted when a command asks for them,
+// and straight from the parsed positions, as geojson's own conversions
+// would, without copying them first
+fn gj_coord(p: &[f64]) -> Coord<f64> {
+    Coord { x: p[0], y: p[1] }
+}
+
+fn gj_line(positions: &[Vec<f64>]) -> LineString<f64> {
+    LineString(positions.iter().map(|p| gj_coord(p)).collect())
+}
+
+fn gj_polygon(rings: &[Vec<Vec<f64>>]) -> Polygon<f64> {
+    let exterior = rings
+        .first()
+        .map_or_else(|| LineString(vec![]), |r| gj_line(r));
+    let interiors = rings.iter().skip(1).map(|r| gj_line(r)).collect();
+    Polygon::new(exterior, interiors)
+}
+
+fn gj_value(value: &geojson::Value) -> geo_types::Geometry<f64> {
+    use geo_types::{GeometryCollection, MultiLineString, MultiPoint, MultiPolygon};
+    match value {
+        geojson::Value::Point(p) => Geometry::Point(Point(gj_coord(p))),
+        geojson::Value::MultiPoint(ps) => {
+            Geometry::MultiPoint(MultiPoint(ps.iter().map(|p| Point(gj_coord(p))).collect()))
+        }
+        geojson::Value::LineString(ps) => Geometry::LineString(gj_line(ps)),
+        geojson::Value::MultiLineString(ls) => {
+            Geometry::MultiLineString(MultiLineString(ls.iter().map(|l| gj_line(l)).collect()))
+        }
+        geojson::Value::Polygon(rings) => Geometry::Polygon(gj_polygon(rings)),
+        geojson::Value::MultiPolygon(polys) => {
+            Geometry::MultiPolygon(MultiPolygon(polys.iter().map(|p| gj_polygon(p)).collect()))
+        }
+        geojson::Value::GeometryCollection(gs) => {
+            Geometry::GeometryCollection(GeometryCollection(gs.iter().map(gj_geometry).collect()))
+        }
+    }
+}
+
+fn gj_geometry(gj_geom: &geojson::Geometry) -> geo_types::Geometry<f64> {
+    gj_value(&gj_geom.value)
+}
+
+// The bbox of a GeoJSON geometry, read from its positions without
+// converting it. Matches bbox::bbox of the converted geometry, including
+// its quirks: Polygons are bounded by their exteriors, and collections
+// always include the origin.
+fn gj_bbox(value: &geojson::Value) -> geo::Rect<f64> {
+    fn positions<'a>(ps: impl Iterator<Item = &'a Vec<f64>>) -> geo::Rect<f64> {
+        ps.map(|p| geo::Rect::new(gj_coord(p), gj_coord(p)))
+            .reduce(|a, b| bbox::merge(&a, &b))
+            .unwrap_or_else(bbox::zero_rect)
+    }
+    match value {
+        geojson::Value::Point(p) => geo::Rect::new(gj_coord(p), gj_coord(p)),
+        geojson::Value::MultiPoint(ps) | geojson::Value::LineString(ps) => positions(ps.iter()),
+        geojson::Value::MultiLineString(ls) => positions(ls.iter().flatten()),
+        geojson::Value::Polygon(rings) => positions(rings.iter().take(1).flatten()),
+        geojson::Value::MultiPolygon(polys) => positions(
+            polys
+                .iter()
+                .flat_map(|rings| rings.iter().take(1))
+                .flatten(),
+        ),
+        geojson::Value::GeometryCollection(gs) => gs.iter().fold(bbox::zero_rect(), |a, g| {
+            bbox::merge(&a, &gj_bbox(&g.value))
+        }),
+    }
+}
+
+fn gj_is_empty(value: &geojson::Value) -> bool {
+    match value {
+        geojson::Value::Point(_) => false,
+        geojson::Value::MultiPoint(ps) | geojson::Value::LineString(ps) => ps.is_empty(),
+        geojson::Value::MultiLineString(ls) | geojson::Value::Polygon(ls) => {
+            ls.iter().all(|l| l.is_empty())
+        }
+        geojson::Value::MultiPolygon(polys) => polys.iter().flatten().all(|r| r.is_empty()),
+        geojson::Value::GeometryCollection(gs) => gs.iter().all(|g| gj_is_empty(&g.value)),
+    }
+}
+
+fn wkt_entities(raw: &String) -> Result<Vec<Entity>, Error> {
+    let wkt_res: Result<wkt::Wkt<f64>, &str> = wkt::Wkt::from_str(&raw);
+    let mut entities = Vec::new();
+    match wkt_res {
+        Ok(wkts) => {
+            for wkt_geom in wkts.items {
+                let wkt_raw = wkt_geom.to_string();
+                let geom: Geometry<f64> = wkt_geom.try_into().unwrap();
+                entities.push(Entity::Wkt(wkt_raw, geom))
+            }
+        }
+        Err(_e) => return Err(Error::InvalidWkt),
+    }
+    Ok(entities)
+}
+
+// The text of each of a FeatureCollection's features, as written
+fn feature_texts(raw: &str) -> Option<Vec<String>> {
+    let members: BTreeMap<String, &RawValue> = serde_json::from_str(raw).ok()?;
+    let features: Vec<&RawValue> = serde_json::from_str(members.get("features")?.get()).ok()?;
+    Some(features.iter().map(|f| f.get().to_string()).collect())
+}
+
+// The collection's foreign members (e.g. "crs") apply to each of its
+// features, unless they have their own
+fn collection_feature(
+    raw: String,
+    mut f: geojson::Feature,
+    fc_members: &Option<serde_json::Map<String, serde_json::Value>>,
+) -> Entity {
+    if let Some(members) = fc_members {
+        let f_members = f.foreign_members.get_or_insert_with(Default::default);
+        for (k, v) in members {
+            f_members.entry(k.clone()).or_insert_with(|| v.clone());
+        }
+    }
+    Entity::GeoJsonFeature(raw, f)
+}
+
+fn parsed_geojson_entities(raw: String, gj: GeoJson) -> Vec<Entity> {
+    match gj {
+        GeoJson::Geometry(gj_geom) => vec![Entity::GeoJsonGeometry(raw, gj_geom)],
+        GeoJson::Feature(gj_feature) => vec![Entity::GeoJsonFeature(raw, gj_feature)],
+        GeoJson::FeatureCollection(gj_fc) => {
+            // Features keep their own text, so they can be passed through
+            // as they were written
+            let texts = feature_texts(&raw)
+                .filter(|t| t.len() == gj_fc.features.len())
+                .unwrap_or_default();
+            let mut texts = texts.into_iter();
+            let fc_members = gj_fc.foreign_members;
+            gj_fc
+                .features
+                .into_iter()
+                .map(|f| {
+                    let gj_raw = texts
+                        .next()
+                        .unwrap_or_else(|| serde_json::to_string(&f).unwrap());
+                    collection_feature(gj_raw, f, &fc_members)
+                })
+                .collect()
+        }
+    }
+}
+
+// Drops commas directly before a closing bracket or brace, outside of
+// strings
+fn strip_trailing_commas(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in raw.chars() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ']' || c == '}' {
+            let trimmed = out.trim_end().len();
+            if out[..trimmed].ends_with(',') {
+                out.truncate(trimmed - 1);
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+// Converts numeric strings within "coordinates" members to numbers
+fn coerce_coordinates(value: &mut serde_json::Value, in_coordinates: bool) {
+    match value {
+        serde_json::Value::String(s) if in_coordinates => {
+            if let Some(n) = s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+            {
+                *value = serde_json::Value::Number(n);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for v in items {
+                coerce_coordinates(v, in_coordinates);
+            }
+        }
+        serde_json::Value::Object(members) => {
+            for (k, v) in members.iter_mut() {
+                coerce_coordinates(v, k == "coordinates");
+            }
+        }
+        _ => (),
+    }
+}
+
+// A second attempt at GeoJSON which didn't parse, for --lenient
+fn lenient_geojson_entities(raw: &str) -> Option<Vec<Entity>> {
+    let mut value: serde_json::Value = serde_json::from_str(&strip_trailing_commas(raw)).ok()?;
+    coerce_coordinates(&mut value, false);
+    let values = match value {
+        serde_json::Value::Array(items) => items,
+        v => vec![v],
+    };
+    let mut entities = vec![];
+    for v in values {
+        let raw = v.to_string();
+        let gj = GeoJson::from_json_value(v).ok()?;
+        entities.extend(parsed_geojson_entities(raw, gj));
+    }
+    Some(entities)
+}
+
+fn geojson_entities(raw: String) -> Result<Vec<Entity>, Error> {
+    match fast_geojson::parse(&raw) {
+        Some(Parsed::GeoJson(gj)) => return Ok(parsed_geojson_entities(raw, gj)),
+        Some(Parsed::FeatureCollection(fc)) => {
+            let fc_members = fc.foreign_members;
+            return Ok(fc
+                .features
+                .into_iter()
+                .map(|(gj_raw, f)| collection_feature(gj_raw, f, &fc_members))
+                .collect());
+        }
+        None => (),
+    }
+    match raw.parse() {
+        Ok(gj) => Ok(parsed_geojson_entities(raw, gj)),
+        Err(e) => {
+            if input::parsing() == Parsing::Lenient {
+                if let Some(entities) = lenient_geojson_entities(&raw) {
+                    return Ok(entities);
+                }
+            }
+            report::warning(&format!("Error parsing geojson ({})", e), &raw);
+            Err(Error::InvalidGeoJSON)
+        }
+    }
+}
+
+fn check_position(p: &[f64]) -> Result<(), String> {
+    if p.len() < 2 || p.len() > 3 {
+        return Err(format!("Expected 2 or 3 values in position {:?}", p));
+    }
+    if !p.iter().all(|v| v.is_finite()) {
+        return Err(format!("Non-finite coordinate in position {:?}", p));
+    }
+    if !(-180.0..=180.0).contains(&p[0]) || !(-90.0..=90.0).contains(&p[1]) {
+        return Err(format!("Lon/Lat out of range in position {:?}", p));
+    }
+    Ok(())
+}
+
+fn check_line(line: &[Vec<f64>]) -> Result<(), String> {
+    if line.len() < 2 {
+        return Err("Expected at least 2 positions in LineString".to_string());
+    }
+    line.iter().try_for_each(|p| check_position(p))
+}
+
+fn check_polygon(rings: &[Vec<Vec<f64>>]) -> Result<(), String> {
+    for ring in rings {
+        if ring.len() < 4 {
+            return Err("Expected at least 4 positions in Polygon ring".to_string());
+        }
+        if ring.first() != ring.last() {
+            return Err("Expected Polygon ring to be closed".to_string());
+        }
+        ring.iter().try_for_each(|p| check_position(p))?;
+    }
+    Ok(())
+}
+
+fn check_geometry(value: &geojson::Value) -> Result<(), String> {
+    match value {
+        geojson::Value::Point(p) => check_position(p),
+        geojson::Value::MultiPoint(points) => points.iter().try_for_each(|p| check_position(p)),
+        geojson::Value::LineString(line) => check_line(line),
+        geojson::Value::MultiLineString(lines) => lines.iter().try_for_each(|l| check_line(l)),
+        geojson::Value::Polygon(rings) => check_polygon(rings),
+        geojson::Value::MultiPolygon(polys) => polys.iter().try_for_each(|p| check_polygon(p)),
+        geojson::Value::GeometryCollection(geoms) => {
+            geoms.iter().try_for_each(|g| check_geometry(&g.value))
+        }
+    }
+}
+
+// Validation for --strict. GeoJSON is checked as written, since its
+// positions may not survive conversion to other geometry types.
+fn check_entity(e: &Entity) -> Result<(), Error> {
+    let res = match e {
+        Entity::GeoJsonFeature(_, f) => match &f.geometry {
+            Some(g) => check_geometry(&g.value),
+            None => Ok(()),
+        },
+        Entity::GeoJsonGeometry(_, g) => check_geometry(&g.value),
+        Entity::LatLon(_) | Entity::Wkt(_, _) => check_geometry(&geojson::Value::from(&e.geom())),
+        Entity::Geohash(_) | Entity::Tile(_, _) => Ok(()),
+        #[cfg(feature = "h3")]
+        Entity::H3(_) => Ok(()),
+    };
+    res.map_err(|msg| Error::InvalidInput(format!("{}: {}", msg, e.raw())))
+}
+
+impl Entity {
+    /// The entity's geometry. Cells are their polygons, and features with
+    /// null geometries give an empty GeometryCollection.
+    pub fn geom(&self) -> geo_types::Geometry<f64> {
+        match self {
+            Entity::LatLon(ref raw) => latlon_geom(raw),
+            Entity::Geohash(ref raw) => geohash_geom(raw),
+            #[cfg(feature = "h3")]
+            Entity::H3(ref cell) => h3_geom(cell),
+            Entity::Tile(_, ref tile) => Geometry::Polygon(tile.polygon()),
+            Entity::Wkt(_, ref geom) => geom.clone(),
+            Entity::GeoJsonGeometry(_, gj_geom) => gj_geometry(gj_geom),
+            Entity::GeoJsonFeature(_, gj_feature) => match gj_feature.geometry {
+                Some(ref gj_geom) => gj_geometry(gj_geom),
+                // Null geometries are treated as empty
+                None => {
+                    geo_types::Geometry::GeometryCollection(geo_types::GeometryCollection(vec![]))
+                }
+            },
+        }
+    }
+
+    /// True for features with null geometries, and for geometries
+    /// with no coordinates (e.g. an empty GeometryCollection)
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Entity::GeoJsonFeature(_, f) => {
+                f.geometry.as_ref().is_none_or(|g| gj_is_empty(&g.value))
+            }
+            Entity::GeoJsonGeometry(_, g) => gj_is_empty(&g.value),
+            Entity::Wkt(_, geom) => coord_count::coord_count(geom) == 0,
+            _ => false,
+        }
+    }
+
+    /// The entity's geometry as WKT.
+    pub fn wkt(&self) -> wkt::Geometry<f64> {
+        let geom = self.geom();
+        let mut wkt = geom.to_wkt();
+        wkt.items.pop().unwrap()
+    }
+
+    /// Bounding box of the entity's geometry, which is a zero rect at the
+    /// origin for empty geometries.
+    pub fn bbox(&self) -> geo::Rect<f64> {
+        match self {
+            // Null geometries are treated as empty collections
+            Entity::GeoJsonFeature(_, f) => f
+                .geometry
+                .as_ref()
+                .map_or_else(bbox::zero_rect, |g| gj_bbox(&g.value)),
+            Entity::GeoJsonGeometry(_, g) => gj_bbox(&g.value),
+            Entity::Wkt(_, geom) => bbox::bbox(geom),
+            _ => bbox::bbox(&self.geom()),
+        }
+    }
+
+    /// The entity's geometry as GeoJSON, keeping any foreign members of a
+    /// GeoJSON input's geometry.
+    pub fn geojson_geometry(&self) -> geojson::Geometry {
+        let geom = self.geom();
+        let mut gj_geom = geojson::Geometry::new(geojson::Value::from(&geom));
+        gj_geom.foreign_members = match self {
+            Entity::GeoJsonGeometry(_, g) => foreign(&g.foreign_members),
+            Entity::GeoJsonFeature(_, f) => f
+                .geometry
+                .as_ref()
+                .and_then(|g| foreign(&g.foreign_members)),
+            _ => None,
+        };
+        gj_geom
+    }
+
+    /// Properties of GeoJSON Features, or the resolution, id and index of H3
+    /// cells. Other entities have none.
+    pub fn geojson_properties(&self) -> serde_json::Map<String, serde_json::value::Value> {
+        match *self {
+            Entity::GeoJsonFeature(_, ref f) => {
+                if let Some(props) = &f.properties {
+                    props.clone()
+                } else {
+                    serde_json::Map::new()
+                }
+            }
+            #[cfg(feature = "h3")]
+            Entity::H3(cell) => {
+                let mut props = serde_json::Map::new();
+                props.insert("resolution".to_string(), cell.resolution().into());
+                props.insert("id".to_string(), cell.to_string().into());
+                props.insert("index".to_string(), cell.h3index().into());
+                props
+            }
+            _ => serde_json::Map::new(),
+        }
+    }
+
+    /// The entity as a GeoJSON Feature, with its properties, id and foreign
+    /// members.
+    pub fn geojson_feature(&self) -> geojson::Feature {
+        let props = self.geojson_properties();
+        let geom = match self {
+            Entity::GeoJsonFeature(_, f) if f.geometry.is_none() => None,
+            _ => Some(self.geojson_geometry()),
+        };
+        geojson::Feature {
+            bbox: None,
+            geometry: geom,
+            id: self.id(),
+            properties: Some(props),
+            foreign_members: self.foreign_members(),
+        }
+    }
+
+    /// Non-standard top-level members of GeoJSON Feature inputs (e.g. "crs"),
+    /// re-emitted on output unless --strip-foreign is given
+    pub fn foreign_members(&self) -> Option<serde_json::Map<String, serde_json::Value>> {
+        match self {
+            Entity::GeoJsonFeature(_, f) => foreign(&f.foreign_members),
+            _ => None,
+        }
+    }
+
+    /// Top-level "id" member, for GeoJSON Feature inputs which have one
+    pub fn id(&self) -> Option<geojson::feature::Id> {
+        match self {
+            Entity::GeoJsonFeature(_, f) => f.id.clone(),
+            _ => None,
+        }
+    }
+
+    /// The text of the entity: the input it came from, re-serialized for
+    /// WKT, and each feature's own text for FeatureCollections.
+    pub fn raw(&self) -> String {
+        match *self {
+            Entity::LatLon(ref raw) => raw.clone(),
+            Entity::Geohash(ref raw) => raw.clone(),
+            Entity::Wkt(ref raw, _) => raw.clone(),
+            Entity::GeoJsonGeometry(ref raw, _) => raw.clone(),
+            Entity::GeoJsonFeature(ref raw, _) => raw.clone(),
+            Entity::Tile(ref raw, _) => raw.clone(),
+            #[cfg(feature = "h3")]
+            Entity::H3(ref cell) => cell.to_string(),
+        }
+    }
+
+    /// The input the entity came from, for commands which pass entities
+    /// through unchanged, without copying it
+    pub fn into_raw(self) -> String {
+        match self {
+            Entity::LatLon(raw)
+            | Entity::Geohash(raw)
+            | Entity::Wkt(raw, _)
+            | Entity::GeoJsonGeometry(raw, _)
+            | Entity::GeoJsonFeature(raw, _)
+            | Entity::Tile(raw, _) => raw,
+            #[cfg(feature = "h3")]
+            Entity::H3(cell) => cell.to_string(),
+        }
+    }
+
+    /// The entity's geometry as a geo type, for running geo algorithms on.
+    /// Features with null geometries give an empty GeometryCollection.
+    pub fn to_geo(&self) -> geo::Geometry<f64> {
+        self.geom()
+    }
+
+    /// An entity for a geometry from geo, read back as WKT
+    pub fn from_geo(geom: geo::Geometry<f64>) -> Entity {
+        let raw = geom.to_wkt().items.pop().unwrap().to_string();
+        Entity::Wkt(raw, geom)
+    }
+}
+
+impl From<geo_types::Geometry<f64>> for Entity {
+    fn from(geom: geo_types::Geometry<f64>) -> Entity {
+        Entity::from_geo(geom)
+    }
+}
+
+impl From<&Entity> for geo_types::Geometry<f64> {
+    fn from(e: &Entity) -> geo_types::Geometry<f64> {
+        e.to_geo()
+    }
+}
+
+impl From<Entity> for geo_types::Geometry<f64> {
+    fn from(e: Entity) -> geo_types::Geometry<f64> {
+        e.to_geo()
+    }
+}
+
+// Conversions to each kind of geo geometry, which fail for entities of
+// other kinds, e.g. Point::try_from(&entity)
+macro_rules! try_from_entity {
+    ($($kind:ident),*) => {$(
+        impl TryFrom<&Entity> for geo_types::$kind<f64> {
+            type Error = Error;
+
+            fn try_from(e: &Entity) -> Result<Self, Error> {
+                geo_types::$kind::try_from(e.to_geo()).map_err(|_| {
+                    Error::InvalidInput(format!(
+                        "Expected a {}: {}",
+                        stringify!($kind),
+                        e.raw()
+                    ))
+                })
+            }
+        }
+    )*};
+}
+
+try_from_entity!(
+    Point,
+    LineString,
+    Polygon,
+    MultiPoint,
+    MultiLineString,
+    MultiPolygon
+);
+
+use std::str::FromStr;
+/// Entities of a line of input, as recognized by
+/// [`input::read_line`]. A FeatureCollection gives
+/// one entity per feature; other inputs give one.
+pub fn from_input(i: Input) -> Result<Vec<Entity>, Error> {
+    let entities = parse_input(i)?;
+    if input::parsing() == Parsing::Strict {
+        entities.iter().try_for_each(check_entity)?;
+    }
+    Ok(entities)
+}
+
+fn parse_input(i: Input) -> Result<Vec<Entity>, Error> {
+    match i {
+        Input::LatLon(raw) => Ok(vec![Entity::LatLon(raw)]),
+        Input::Geohash(raw) => Ok(vec![Entity::Geohash(raw)]),
+        Input::WKT(raw) => wkt_entities(&raw),
+        Input::GeoJSON(raw) => geojson_entities(raw),
+        Input::Tile(raw) => {
+            let tile = raw.parse::<Tile>()?;
+            Ok(vec![Entity::Tile(raw, tile)])
+        }
+        Input::Quadkey(raw) => {
+            let tile = Tile::from_quadkey(&raw[3..])?;
+            Ok(vec![Entity::Tile(raw, tile)])
+        }
+        #[cfg(feature = "h3")]
+        Input::H3(raw) => match H3Cell::from_str(&raw) {
+            Ok(cell) => Ok(vec![Entity::H3(cell)]),
+            Err(e) => Err(Error::InvalidInput(format!(
+                "Unable to parse String as H3 Cell: {} - {}",
+                raw, e
+            ))),
+        },
+        #[cfg(not(feature = "h3"))]
+        Input::H3(raw) => Err(Error::InvalidInput(format!(
+            "Can't read H3 cell {} -- geoq was built without the h3 feature",
+            raw
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::{self, Entity};
+    use crate::input::Input;
+    use geo_types::{Coord, Geometry, LineString, Point, Polygon};
+    use serde_json::value::Value as JValue;
+    use serde_json::Map as JMap;
+
+    fn entities(i: &Input) -> Vec<Entity> {
+        entity::from_input(i.clone()).expect(&format!("Should get entities from input {}", i))
+    }
+
+    fn check(
+        input: Input,
+        exp_raw: Vec<&str>,
+        exp_geoms: Vec<Geometry<f64>>,
+        exp_wkts: Vec<&str>,
+        exp_gj_geoms: Vec<&str>,
+        exp_gj_properties: Vec<JMap<String, JValue>>,
+        exp_gj_features: Vec<&str>,
+    ) {
+        let res_raw: Vec<String> = entities(&input).iter().map(|e| e.raw()).collect();
+        assert_eq!(exp_raw, res_raw);
+
+        let res_raw: Vec<String> = entities(&input).iter().map(|e| e.raw()).collect();
+        assert_eq!(exp_raw, res_raw);
+
+        let res_geoms: Vec<Geometry<f64>> =
+            entities(&input).into_iter().map(|e| e.geom()).collect();
+        assert_eq!(exp_geoms, res_geoms);
+
+        let res_wkts: Vec<String> = entities(&input)
+            .into_iter()
+            .map(|e| e.wkt().to_string())
+            .collect();
+        assert_eq!(exp_wkts, res_wkts);
+
+        let res_gj_geoms: Vec<String> = entities(&input)
+            .into_iter()
+            .map(|e| e.geojson_geometry())
+            .map(|ref gj| serde_json::to_string(gj).unwrap())
+            .collect();
+        assert_eq!(exp_gj_geoms, res_gj_geoms);
+
+        let res_gj_properties: Vec<JMap<String, JValue>> = entities(&input)
+            .into_iter()
+            .map(|e| e.geojson_properties())
+            .collect();
+        assert_eq!(exp_gj_properties, res_gj_properties);
+
+        let res_gj_features: Vec<String> = entities(&input)
+            .into_iter()
+            .map(|e| e.geojson_feature())
+            .map(|ref gj| serde_json::to_string(gj).unwrap())
+            .collect();
+        assert_eq!(exp_gj_features, res_gj_features);
+    }
+
+    #[test]
+    fn entities_for_latlon() {
+        check(Input::LatLon("12,34".to_string()),
+              vec!["12,34"],
+              vec![Geometry::Point(Point::new(34.0, 12.0))],
+              vec!["POINT(34 12)"],
+              vec!["{\"coordinates\":[34.0,12.0],\"type\":\"Point\"}"],
+              vec![serde_json::Map::new()],
+              vec!["{\"geometry\":{\"coordinates\":[34.0,12.0],\"type\":\"Point\"},\"properties\":{},\"type\":\"Feature\"}"]
+        );
+    }
+
+    #[test]
+    fn entities_for_geohash() {
+        let exp_poly = Polygon::new(
+            vec![
+                [-119.53125, 33.75],
+                [-118.125, 33.75],
+                [-118.125, 35.15625],
+                [-119.53125, 35.15625],
+                [-119.53125, 33.75],
+            ]
+            .into(),
+            vec![],
+        );
+        check(Input::Geohash("9q5".to_string()),
+              vec!["9q5"],
+              vec![Geometry::Polygon(exp_poly)],
+              vec!["POLYGON((-119.53125 33.75,-118.125 33.75,-118.125 35.15625,-119.53125 35.15625,-119.53125 33.75))"],
+              vec!["{\"coordinates\":[[[-119.53125,33.75],[-118.125,33.75],[-118.125,35.15625],[-119.53125,35.15625],[-119.53125,33.75]]],\"type\":\"Polygon\"}"],
+              vec![serde_json::Map::new()],
+              vec!["{\"geometry\":{\"coordinates\":[[[-119.53125,33.75],[-118.125,33.75],[-118.125,35.15625],[-119.53125,35.15625],[-119.53125,33.75]]],\"type\":\"Polygon\"},\"properties\":{},\"type\":\"Feature\"}"]);
+    }
+
+    #[test]
+    fn entities_for_wkt() {
+        let exp_geom = LineString(
+            vec![
+                Coord::from((30.0, 10.0)),
+                Coord::from((10.0, 30.0)),
+                Coord::from((40.0, 40.0)),
+            ]
+            .into(),
+        );
+        check(Input::WKT("LINESTRING (30 10, 10 30, 40 40)".to_string()),
+              vec!["LINESTRING(30 10,10 30,40 40)"],
+              vec![Geometry::LineString(exp_geom)],
+              vec!["LINESTRING(30 10,10 30,40 40)"],
+              vec!["{\"coordinates\":[[30.0,10.0],[10.0,30.0],[40.0,40.0]],\"type\":\"LineString\"}"],
+              vec![serde_json::Map::new()],
+              vec!["{\"geometry\":{\"coordinates\":[[30.0,10.0],[10.0,30.0],[40.0,40.0]],\"type\":\"LineString\"},\"properties\":{},\"type\":\"Feature\"}"]);
+    }
+
+    #[test]
+    fn entities_for_geojson_geometry() {
+        let exp_geom = LineString(
+            vec![
+                Coord::from((-26.01, 59.17)),
+                Coord::from((-15.46, 45.58)),
+                Coord::from((0.35, 35.74)),
+            ]
+            .into(),
+        );
+        let raw = "{\"type\": \"LineString\", \"coordinates\": [[-26.01, 59.17], [-15.46, 45.58], [0.35, 35.74]]}";
+        check(Input::GeoJSON(raw.to_string()),
+              vec!["{\"type\": \"LineString\", \"coordinates\": [[-26.01, 59.17], [-15.46, 45.58], [0.35, 35.74]]}"],
+              vec![Geometry::LineString(exp_geom)],
+              vec!["LINESTRING(-26.01 59.17,-15.46 45.58,0.35 35.74)"],
+              vec!["{\"coordinates\":[[-26.01,59.17],[-15.46,45.58],[0.35,35.74]],\"type\":\"LineString\"}"],
+              vec![serde_json::Map::new()],
+              vec!["{\"geometry\":{\"coordinates\":[[-26.01,59.17],[-15.46,45.58],[0.35,35.74]],\"type\":\"LineString\"},\"properties\":{},\"type\":\"Feature\"}"]
+        )
+    }
+
+    #[test]
+    fn entities_for_geojson_feature() {
+        // TODO - make properties map optional for geojson inputs?
+        let raw = "{\"type\": \"Feature\", \"properties\": {\"pizza\": \"pie\"}, \"geometry\": {\"type\": \"LineString\", \"coordinates\": [[-26.01, 59.17], [-15.46, 45.58], [0.35, 35.74]]}}";
+        let i = Input::GeoJSON(raw.to_string());
+        let exp_geom = LineString(
+            vec![
+                Coord::from((-26.01, 59.17)),
+                Coord::from((-15.46, 45.58)),
+                Coord::from((0.35, 35.74)),
+            ]
+            .into(),
+        );
+
+        let mut exp_properties = serde_json::Map::new();
+        exp_properties.insert(String::from("pizza"), serde_json::to_value("pie").unwrap());
+        check(i,
+              vec![raw],
+              vec![Geometry::LineString(exp_geom)],
+              vec!["LINESTRING(-26.01 59.17,-15.46 45.58,0.35 35.74)"],
+              vec!["{\"coordinates\":[[-26.01,59.17],[-15.46,45.58],[0.35,35.74]],\"type\":\"LineString\"}"],
+              vec![exp_properties],
+              vec!["{\"geometry\":{\"coordinates\":[[-26.01,59.17],[-15.46,45.58],[0.35,35.74]],\"type\":\"LineString\"},\"properties\":{\"pizza\":\"pie\"},\"type\":\"Feature\"}"]
+        )
+    }
+
+    #[test]
+    fn entities_for_geojson_feature_collection() {
+        let raw = r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{"a":"b"},"geometry":{"type":"Point","coordinates":[34.0,12.0]}},{"type":"Feature","properties":{"c":1},"geometry":{"type":"Point","coordinates":[78.0,56.0]}}]}"#;
+        let i = Input::GeoJSON(raw.to_string());
+        let geoms = vec![
+            Geometry::Point(Point::new(34.0, 12.0)),
+            Geometry::Point(Point::new(78.0, 56.0)),
+        ];
+        let mut props1 = serde_json::Map::new();
+        props1.insert(String::from("a"), serde_json::to_value("b").unwrap());
+        let mut props2 = serde_json::Map::new();
+        props2.insert(String::from("c"), serde_json::to_value(1).unwrap());
+        // Each feature's raw text is as it was written
+        check(i,
+              vec![
+                  r#"{"type":"Feature","properties":{"a":"b"},"geometry":{"type":"Point","coordinates":[34.0,12.0]}}"#,
+                  r#"{"type":"Feature","properties":{"c":1},"geometry":{"type":"Point","coordinates":[78.0,56.0]}}"#
+              ],
+              geoms,
+              vec!["POINT(34 12)", "POINT(78 56)"],
+              vec![
+                  "{\"coordinates\":[34.0,12.0],\"type\":\"Point\"}",
+                  "{\"coordinates\":[78.0,56.0],\"type\":\"Point\"}",
+              ],
+              vec![props1, props2],
+              vec![
+                  "{\"geometry\":{\"coordinates\":[34.0,12.0],\"type\":\"Point\"},\"properties\":{\"a\":\"b\"},\"type\":\"Feature\"}",
+                  "{\"geometry\":{\"coordinates\":[78.0,56.0],\"type\":\"Point\"},\"properties\":{\"c\":1},\"type\":\"Feature\"}"
+              ]
+        )
+    }
+
+    #[test]
+    fn test_geojson_geometry_in_place() {
+        use crate::{bbox, coord_count};
+        use std::convert::TryInto;
+        // Converted by geojson, which doesn't take collections
+        fn convert(value: geojson::Value) -> Geometry<f64> {
+            match value {
+                geojson::Value::GeometryCollection(gs) => {
+                    Geometry::GeometryCollection(geo_types::GeometryCollection(
+                        gs.into_iter().map(|g| convert(g.value)).collect(),
+                    ))
+                }
+                v => v.try_into().unwrap(),
+            }
+        }
+        for raw in &[
+            r#"{"type":"Point","coordinates":[1,2]}"#,
+            r#"{"type":"MultiPoint","coordinates":[[1,2],[-3,4]]}"#,
+            r#"{"type":"LineString","coordinates":[]}"#,
+            r#"{"type":"LineString","coordinates":[[1,2],[3,-4]]}"#,
+            r#"{"type":"MultiLineString","coordinates":[[],[[5,6],[7,8]]]}"#,
+            r#"{"type":"Polygon","coordinates":[[[1,1],[4,1],[4,4],[1,1]],[[-9,-9],[9,9],[9,-9]]]}"#,
+            r#"{"type":"Polygon","coordinates":[]}"#,
+            r#"{"type":"MultiPolygon","coordinates":[[[[5,5],[6,5],[6,6],[5,5]]],[[[7,7],[8,7],[8,8]]]]}"#,
+            r#"{"type":"GeometryCollection","geometries":[]}"#,
+            r#"{"type":"GeometryCollection","geometries":[{"type":"Point","coordinates":[5,6]},{"type":"LineString","coordinates":[[7,8],[9,10]]}]}"#,
+        ] {
+            let e = entities(&Input::GeoJSON(raw.to_string())).remove(0);
+            let value = match &e {
+                Entity::GeoJsonGeometry(_, g) => g.value.clone(),
+                _ => panic!("Expected a geometry for {}", raw),
+            };
+            let expected = convert(value);
+            assert_eq!(expected, e.geom(), "{}", raw);
+            assert_eq!(bbox::bbox(&expected), e.bbox(), "{}", raw);
+            assert_eq!(
+                coord_count::coord_count(&expected) == 0,
+                e.is_empty(),
+                "{}",
+                raw
+            );
+        }
+        let null = r#"{"type":"Feature","geometry":null,"properties":{}}"#;
+        let e = entities(&Input::GeoJSON(null.to_string())).remove(0);
+        assert_eq!(bbox::bbox(&e.geom()), e.bbox());
+        assert!(e.is_empty());
+    }
+
+    #[test]
+    fn test_strict_checks() {
+        let check = |raw: &str| {
+            let e = entity::parse_input(Input::GeoJSON(raw.to_string())).unwrap();
+            entity::check_entity(&e[0]).is_ok()
+        };
+        assert!(check(r#"{"type":"Point","coordinates":[-118,34,10]}"#));
+        assert!(!check(r#"{"type":"Point","coordinates":[-118]}"#));
+        assert!(!check(r#"{"type":"Point","coordinates":[-218,34]}"#));
+        assert!(!check(r#"{"type":"LineString","coordinates":[[0,0]]}"#));
+        assert!(!check(
+            r#"{"type":"Polygon","coordinates":[[[0,0],[1,0],[1,1],[0,1]]]}"#
+        ));
+        assert!(check(
+            r#"{"type":"Feature","properties":{},"geometry":null}"#
+        ));
+        let latlon = entity::parse_input(Input::LatLon("91,0".to_string())).unwrap();
+        assert!(entity::check_entity(&latlon[0]).is_err());
+    }
+
+    #[test]
+    fn test_geo_conversions() {
+        use std::convert::TryFrom;
+
+        let e = &entities(&Input::LatLon("34,-118".to_string()))[0];
+        assert_eq!(Geometry::Point(Point::new(-118.0, 34.0)), e.to_geo());
+        assert_eq!(Point::new(-118.0, 34.0), Point::try_from(e).unwrap());
+        assert!(LineString::try_from(e).is_err());
+
+        let line = Geometry::LineString(LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]));
+        let e = Entity::from_geo(line.clone());
+        assert_eq!("LINESTRING(0 0,1 1)", e.raw());
+        assert_eq!(line, Geometry::from(&e));
+        assert_eq!(line, Entity::from(line.clone()).geom());
+    }
+
+    #[test]
+    fn test_lenient_geojson() {
+        assert_eq!(
+            r#"{"a":[1,2],"b":"x,]"}"#,
+            entity::strip_trailing_commas(r#"{"a":[1,2,],"b":"x,]",}"#)
+        );
+        let e = entity::lenient_geojson_entities(
+            r#"[{"type":"Point","coordinates":["-118.5"," 34"],},{"type":"Point","coordinates":[1,2]}]"#,
+        )
+        .unwrap();
+        assert_eq!(2, e.len());
+        assert_eq!(Geometry::Point(Point::new(-118.5, 34.0)), e[0].geom());
+        assert!(entity::lenient_geojson_entities(r#"{"type":"Point"}"#).is_none());
+    }
+
+    #[test]
+    fn test_feature_collection_foreign_members() {
+        let input = Input::GeoJSON(
+            r#"{"type":"FeatureCollection","crs":{"type":"name"},"src":"a","features":[{"type":"Feature","src":"b","properties":null,"geometry":null}]}"#
+                .to_string(),
+        );
+        let es = entities(&input);
+        assert_eq!(
+            r#"{"crs":{"type":"name"},"geometry":null,"properties":{},"src":"b","type":"Feature"}"#,
+            serde_json::to_string(&es[0].geojson_feature()).unwrap()
+        );
+        assert_eq!(
+            r#"{"type":"Feature","src":"b","properties":null,"geometry":null}"#,
+            es[0].raw()
+        );
+    }
+}
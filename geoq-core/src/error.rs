@@ -0,0 +1,208 @@
+//! The [`Error`] all of geoq's modules give.
+use std::{convert::From, io, str::Utf8Error, time::Duration};
+
+use geozero::error::GeozeroError;
+
+/// The errors geoq gives. More may be added in minor releases.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    // InvalidLatLon,
+    InvalidGeoJSON,
+    NotImplemented,
+    UnknownCommand,
+    UnknownEntityFormat,
+    InvalidWkt,
+    MissingArgument,
+    InvalidNumberFormat(String),
+    InputTooLarge,
+    IOError,
+    JSONParseError,
+    InvalidJSONType,
+    PolygonRequired,
+    IPGeolocationError,
+    HTTPError,
+    TooManyFeatures,
+    PointRequired,
+    DistanceFailed,
+    InvalidGeohashPoint,
+    NoInputGiven,
+    ShapefileReaderError(String),
+    ProgramError(String),
+    InvalidInput(String),
+    /// Handling an entity took longer than --timeout-per-entity
+    EntityTimeout(Duration),
+    /// Too many handlers are still running past --timeout-per-entity
+    TooManyTimeouts(String),
+    /// An error along with where in the input it happened
+    AtInput(Location, Box<Error>),
+}
+
+// Inputs kept with errors are cut down to this many characters, so a
+// huge feature doesn't swamp the output
+const MAX_INPUT_CHARS: usize = 200;
+
+/// The input, cut down to keep with an error.
+pub fn truncate_input(input: &str) -> String {
+    match input.char_indices().nth(MAX_INPUT_CHARS) {
+        Some((i, _)) => format!("{}...", &input[..i]),
+        None => input.to_string(),
+    }
+}
+
+/// Where in the input an error happened.
+#[derive(Debug)]
+pub struct Location {
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Byte offset of the start of the line
+    pub offset: u64,
+    /// The start of the line
+    pub input: String,
+}
+
+impl Error {
+    /// Adds the line an error came from, unless it already has one
+    pub fn at(self, line: usize, offset: u64, input: &str) -> Error {
+        match self {
+            Error::AtInput(_, _) => self,
+            e => Error::AtInput(
+                Location {
+                    line,
+                    offset,
+                    input: truncate_input(input),
+                },
+                Box::new(e),
+            ),
+        }
+    }
+
+    /// Where in the input the error happened, if that's known.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::AtInput(location, _) => Some(location),
+            _ => None,
+        }
+    }
+
+    /// The error without its location
+    pub fn cause(&self) -> &Error {
+        match self {
+            Error::AtInput(_, e) => e.cause(),
+            e => e,
+        }
+    }
+
+    /// The variant name, for machine-readable error output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::AtInput(_, e) => e.kind(),
+            Error::InvalidGeoJSON => "InvalidGeoJSON",
+            Error::NotImplemented => "NotImplemented",
+            Error::UnknownCommand => "UnknownCommand",
+            Error::UnknownEntityFormat => "UnknownEntityFormat",
+            Error::InvalidWkt => "InvalidWkt",
+            Error::MissingArgument => "MissingArgument",
+            Error::InvalidNumberFormat(_) => "InvalidNumberFormat",
+            Error::InputTooLarge => "InputTooLarge",
+            Error::IOError => "IOError",
+            Error::JSONParseError => "JSONParseError",
+            Error::InvalidJSONType => "InvalidJSONType",
+            Error::PolygonRequired => "PolygonRequired",
+            Error::IPGeolocationError => "IPGeolocationError",
+            Error::HTTPError => "HTTPError",
+            Error::TooManyFeatures => "TooManyFeatures",
+            Error::PointRequired => "PointRequired",
+            Error::DistanceFailed => "DistanceFailed",
+            Error::InvalidGeohashPoint => "InvalidGeohashPoint",
+            Error::NoInputGiven => "NoInputGiven",
+            Error::ShapefileReaderError(_) => "ShapefileReaderError",
+            Error::ProgramError(_) => "ProgramError",
+            Error::InvalidInput(_) => "InvalidInput",
+            Error::EntityTimeout(_) => "EntityTimeout",
+            Error::TooManyTimeouts(_) => "TooManyTimeouts",
+        }
+    }
+
+    /// The detail message, for errors which have one
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Error::AtInput(_, e) => e.message(),
+            Error::InvalidNumberFormat(m)
+            | Error::ShapefileReaderError(m)
+            | Error::ProgramError(m)
+            | Error::TooManyTimeouts(m)
+            | Error::InvalidInput(m) => Some(m),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(_: io::Error) -> Self {
+        Error::IOError
+    }
+}
+
+#[cfg(feature = "h3")]
+impl From<h3ron::Error> for Error {
+    fn from(e: h3ron::Error) -> Self {
+        Error::ProgramError(format!("Error in H3 core: {}", e))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(_: serde_json::Error) -> Self {
+        Error::JSONParseError
+    }
+}
+
+#[cfg(feature = "net")]
+impl From<reqwest::Error> for Error {
+    fn from(_: reqwest::Error) -> Self {
+        Error::HTTPError
+    }
+}
+
+impl From<GeozeroError> for Error {
+    fn from(e: GeozeroError) -> Self {
+        Error::ProgramError(format!("{}", e))
+    }
+}
+
+impl From<std::str::Utf8Error> for Error {
+    fn from(e: Utf8Error) -> Self {
+        Error::ProgramError(format!("{}", e))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::ProgramError(format!("SQLite error: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{truncate_input, Error};
+
+    #[test]
+    fn test_truncate_input() {
+        assert_eq!("9q5", truncate_input("9q5"));
+        let long = "é".repeat(250);
+        assert_eq!(format!("{}...", "é".repeat(200)), truncate_input(&long));
+    }
+
+    #[test]
+    fn test_at() {
+        let err = Error::InvalidWkt.at(3, 20, "POINT (1").at(4, 29, "9q5");
+        let location = err.location().unwrap();
+        assert_eq!(3, location.line);
+        assert_eq!(20, location.offset);
+        assert_eq!("POINT (1", location.input);
+        assert_eq!("InvalidWkt", err.kind());
+        assert!(matches!(err.cause(), Error::InvalidWkt));
+        assert!(Error::InvalidWkt.location().is_none());
+    }
+}
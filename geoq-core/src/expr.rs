@@ -0,0 +1,610 @@
+use crate::{area, buffer, centroid, coord_count, error::Error, length, simplify, style, units};
+use geo_types::Geometry;
+use serde_json::{Map, Number};
+
+// A small expression language used for computing property values, e.g.
+// area(geom) / 1e6
+// "census"
+// vertex_count(geom) * 2
+// props.population / (area(geom) / 1e6)
+//
+// Programs of it assign to the geometry or properties in turn, each
+// assignment seeing those before it:
+// geom = buffer(geom, 100); props.area = area(geom)
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Str(String),
+    Bool(bool),
+    Null,
+    Var(String),
+    Prop(String),
+    Call(String, Vec<Expr>),
+    Neg(Box<Expr>),
+    BinOp(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Num(f64),
+    Str(String),
+    Bool(bool),
+    Null,
+    Geom(Geometry<f64>),
+    // Arrays and objects from properties
+    Json(serde_json::Value),
+}
+
+pub struct Scope<'a> {
+    pub geom: &'a Geometry<f64>,
+    pub props: &'a Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Geom,
+    Prop(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub target: Target,
+    pub value: Expr,
+}
+
+pub const VARIABLES: [&str; 1] = ["geom"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+    Dot,
+    Assign,
+    Semicolon,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()))
+        {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit()
+                    || chars[i] == '.'
+                    || chars[i] == 'e'
+                    || chars[i] == 'E'
+                    || ((chars[i] == '-' || chars[i] == '+')
+                        && (chars[i - 1] == 'e' || chars[i - 1] == 'E')))
+            {
+                i += 1;
+            }
+            let raw: String = chars[start..i].iter().collect();
+            let num = raw.parse::<f64>().map_err(|_| {
+                Error::InvalidNumberFormat(format!("Invalid number in expression: {}", raw))
+            })?;
+            tokens.push(Token::Num(num));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(Error::InvalidInput(format!(
+                    "Unterminated string in expression: {}",
+                    input
+                )));
+            }
+            tokens.push(Token::Str(chars[start..i].iter().collect()));
+            i += 1;
+        } else if "+-*/".contains(c) {
+            tokens.push(Token::Op(c));
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else if c == '.' {
+            tokens.push(Token::Dot);
+            i += 1;
+        } else if c == '=' {
+            tokens.push(Token::Assign);
+            i += 1;
+        } else if c == ';' {
+            tokens.push(Token::Semicolon);
+            i += 1;
+        } else {
+            return Err(Error::InvalidInput(format!(
+                "Unexpected character '{}' in expression: {}",
+                c, input
+            )));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), Error> {
+        match self.next() {
+            Some(ref t) if *t == expected => Ok(()),
+            other => Err(Error::InvalidInput(format!(
+                "Expected {:?} in expression, got {:?}",
+                expected, other
+            ))),
+        }
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, Error> {
+        let mut lhs = self.term()?;
+        while let Some(Token::Op(c)) = self.peek() {
+            let op = match c {
+                '+' => Op::Add,
+                '-' => Op::Sub,
+                _ => break,
+            };
+            self.next();
+            let rhs = self.term()?;
+            lhs = Expr::BinOp(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<Expr, Error> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(c)) = self.peek() {
+            let op = match c {
+                '*' => Op::Mul,
+                '/' => Op::Div,
+                _ => break,
+            };
+            self.next();
+            let rhs = self.unary()?;
+            lhs = Expr::BinOp(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        if let Some(Token::Op('-')) = self.peek() {
+            self.next();
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr, Error> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::LParen) => {
+                let e = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(e)
+            }
+            Some(Token::Ident(name)) => {
+                if let Some(Token::LParen) = self.peek() {
+                    self.next();
+                    let mut args = Vec::new();
+                    if let Some(Token::RParen) = self.peek() {
+                        self.next();
+                        return Ok(Expr::Call(name, args));
+                    }
+                    loop {
+                        args.push(self.expr()?);
+                        match self.next() {
+                            Some(Token::Comma) => continue,
+                            Some(Token::RParen) => break,
+                            other => {
+                                return Err(Error::InvalidInput(format!(
+                                    "Expected ',' or ')' in function arguments, got {:?}",
+                                    other
+                                )))
+                            }
+                        }
+                    }
+                    Ok(Expr::Call(name, args))
+                } else if name == "props" && self.peek() == Some(&Token::Dot) {
+                    self.next();
+                    match self.next() {
+                        Some(Token::Ident(key)) | Some(Token::Str(key)) => Ok(Expr::Prop(key)),
+                        other => Err(Error::InvalidInput(format!(
+                            "Expected property name after props., got {:?}",
+                            other
+                        ))),
+                    }
+                } else {
+                    match name.as_str() {
+                        "true" => Ok(Expr::Bool(true)),
+                        "false" => Ok(Expr::Bool(false)),
+                        "null" => Ok(Expr::Null),
+                        _ => Ok(Expr::Var(name)),
+                    }
+                }
+            }
+            other => Err(Error::InvalidInput(format!(
+                "Unexpected token in expression: {:?}",
+                other
+            ))),
+        }
+    }
+
+    // assignment := ('geom' | 'props' '.' name) '=' expr
+    fn assignment(&mut self) -> Result<Assignment, Error> {
+        let target = match self.expr()? {
+            Expr::Var(name) if name == "geom" => Target::Geom,
+            Expr::Prop(key) => Target::Prop(key),
+            other => {
+                return Err(Error::InvalidInput(format!(
+                    "Can only assign to geom or props.<name>, not {:?}",
+                    other
+                )))
+            }
+        };
+        self.expect(Token::Assign)?;
+        let value = self.expr()?;
+        Ok(Assignment { target, value })
+    }
+}
+
+pub fn parse(input: &str) -> Result<Expr, Error> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.expr()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(Error::InvalidInput(format!(
+            "Unexpected trailing input in expression: {}",
+            input
+        )));
+    }
+    Ok(expr)
+}
+
+// Parses assignments separated by semicolons
+pub fn parse_program(input: &str) -> Result<Vec<Assignment>, Error> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let mut program = vec![];
+    while parser.peek().is_some() {
+        if parser.peek() == Some(&Token::Semicolon) {
+            parser.next();
+            continue;
+        }
+        program.push(parser.assignment()?);
+        match parser.next() {
+            None | Some(Token::Semicolon) => (),
+            other => {
+                return Err(Error::InvalidInput(format!(
+                    "Expected ';' between assignments, got {:?}",
+                    other
+                )))
+            }
+        }
+    }
+    if program.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "Expected assignments, e.g. props.area = area(geom): {}",
+            input
+        )));
+    }
+    Ok(program)
+}
+
+impl Expr {
+    // Names of all variables referenced by the expression
+    pub fn vars(&self) -> Vec<&str> {
+        match self {
+            Expr::Var(name) => vec![name.as_str()],
+            Expr::Call(_, args) => args.iter().flat_map(|a| a.vars()).collect(),
+            Expr::Neg(e) => e.vars(),
+            Expr::BinOp(_, l, r) => {
+                let mut vars = l.vars();
+                vars.extend(r.vars());
+                vars
+            }
+            _ => vec![],
+        }
+    }
+
+    pub fn eval(&self, scope: &Scope) -> Result<Value, Error> {
+        match self {
+            Expr::Num(n) => Ok(Value::Num(*n)),
+            Expr::Str(s) => Ok(Value::Str(s.clone())),
+            Expr::Bool(b) => Ok(Value::Bool(*b)),
+            Expr::Null => Ok(Value::Null),
+            Expr::Var(name) => match name.as_str() {
+                "geom" => Ok(Value::Geom(scope.geom.clone())),
+                _ => Err(Error::InvalidInput(format!(
+                    "Unknown variable in expression: {}",
+                    name
+                ))),
+            },
+            Expr::Prop(key) => Ok(scope.props.get(key).map_or(Value::Null, Value::from_json)),
+            Expr::Neg(e) => match e.eval(scope)? {
+                Value::Num(n) => Ok(Value::Num(-n)),
+                other => Err(Error::InvalidInput(format!(
+                    "Can't negate non-numeric value: {:?}",
+                    other
+                ))),
+            },
+            Expr::BinOp(op, l, r) => match (l.eval(scope)?, r.eval(scope)?) {
+                (Value::Num(a), Value::Num(b)) => Ok(Value::Num(match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                })),
+                (Value::Str(a), Value::Str(b)) if *op == Op::Add => Ok(Value::Str(a + &b)),
+                (a, b) => Err(Error::InvalidInput(format!(
+                    "Unsupported operands for {:?}: {:?}, {:?}",
+                    op, a, b
+                ))),
+            },
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|a| a.eval(scope))
+                    .collect::<Result<Vec<Value>, Error>>()?;
+                call(name, args)
+            }
+        }
+    }
+}
+
+fn geom_arg(name: &str, args: Vec<Value>) -> Result<Geometry<f64>, Error> {
+    match args.into_iter().next() {
+        Some(Value::Geom(g)) => Ok(g),
+        _ => Err(Error::InvalidInput(format!(
+            "Function {} expects a geometry argument, e.g. {}(geom)",
+            name, name
+        ))),
+    }
+}
+
+fn num_arg(name: &str, args: &[Value], i: usize) -> Result<f64, Error> {
+    match args.get(i) {
+        Some(Value::Num(n)) => Ok(*n),
+        _ => Err(Error::InvalidInput(format!(
+            "Function {} expects a number as argument {}",
+            name,
+            i + 1
+        ))),
+    }
+}
+
+// A distance in --units, or a string such as "5km"
+fn length_arg(name: &str, args: &[Value], i: usize) -> Result<f64, Error> {
+    match args.get(i) {
+        Some(Value::Num(n)) => Ok(units::to_meters(*n)),
+        Some(Value::Str(s)) => units::parse_length(s),
+        _ => Err(Error::InvalidInput(format!(
+            "Function {} expects a distance as argument {}",
+            name,
+            i + 1
+        ))),
+    }
+}
+
+fn call(name: &str, args: Vec<Value>) -> Result<Value, Error> {
+    match name {
+        "buffer" => {
+            let meters = length_arg(name, &args, 1)?;
+            Ok(Value::Geom(buffer::buffer(&geom_arg(name, args)?, meters)))
+        }
+        "simplify" => {
+            let epsilon = num_arg(name, &args, 1)?;
+            Ok(Value::Geom(simplify::simplify(
+                geom_arg(name, args)?,
+                epsilon,
+            )))
+        }
+        "centroid" => match centroid::centroid(&geom_arg(name, args)?) {
+            Some(p) => Ok(Value::Geom(Geometry::Point(p))),
+            None => Ok(Value::Null),
+        },
+        "area" => Ok(Value::Num(area::area(&geom_arg(name, args)?))),
+        "length" => Ok(Value::Num(length::length(&geom_arg(name, args)?))),
+        "vertex_count" => Ok(Value::Num(
+            coord_count::coord_count(&geom_arg(name, args)?) as f64
+        )),
+        "centroid_x" | "centroid_y" => {
+            let geom = geom_arg(name, args)?;
+            match centroid::centroid(&geom) {
+                Some(p) if name == "centroid_x" => Ok(Value::Num(p.x())),
+                Some(p) => Ok(Value::Num(p.y())),
+                None => Ok(Value::Null),
+            }
+        }
+        "color" => match args.first() {
+            Some(v) => Ok(Value::Str(style::category_color(&v.to_json()).to_string())),
+            None => Err(Error::InvalidInput(
+                "Function color expects a value to pick a color for, e.g. color(props.kind)"
+                    .to_string(),
+            )),
+        },
+        _ => Err(Error::InvalidInput(format!(
+            "Unknown function in expression: {}",
+            name
+        ))),
+    }
+}
+
+impl Value {
+    pub fn from_json(v: &serde_json::Value) -> Value {
+        match v {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Number(n) => n.as_f64().map_or(Value::Null, Value::Num),
+            serde_json::Value::String(s) => Value::Str(s.clone()),
+            other => Value::Json(other.clone()),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Num(n) => {
+                if n.fract() == 0.0 && n.abs() < 9007199254740992.0 {
+                    serde_json::Value::Number((*n as i64).into())
+                } else {
+                    Number::from_f64(*n)
+                        .map(serde_json::Value::Number)
+                        .unwrap_or(serde_json::Value::Null)
+                }
+            }
+            Value::Str(s) => serde_json::Value::String(s.clone()),
+            Value::Bool(b) => serde_json::Value::Bool(*b),
+            Value::Null => serde_json::Value::Null,
+            Value::Geom(g) => {
+                let gj = geojson::Geometry::new(geojson::Value::from(g));
+                serde_json::to_value(&gj).unwrap_or(serde_json::Value::Null)
+            }
+            Value::Json(v) => v.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::{parse, parse_program, Assignment, Expr, Scope, Target, Value};
+    use geo_types::{Geometry, Point, Polygon};
+    use serde_json::{json, Map};
+
+    fn eval(src: &str, geom: &Geometry<f64>) -> Value {
+        let props = Map::new();
+        parse(src)
+            .unwrap()
+            .eval(&Scope {
+                geom,
+                props: &props,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let g = Geometry::Point(Point::new(1.0, 2.0));
+        assert_eq!(Value::Num(7.0), eval("1 + 2 * 3", &g));
+        assert_eq!(Value::Num(9.0), eval("(1 + 2) * 3", &g));
+        assert_eq!(Value::Num(-0.5), eval("-1 / 2", &g));
+        assert_eq!(Value::Num(2000.0), eval("2e3", &g));
+        assert_eq!(Value::Str("ab".to_string()), eval("'a' + \"b\"", &g));
+    }
+
+    #[test]
+    fn test_geometry_functions() {
+        let poly = Geometry::Polygon(Polygon::new(
+            vec![[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0], [0.0, 0.0]].into(),
+            vec![],
+        ));
+        assert_eq!(Value::Num(5.0), eval("vertex_count(geom)", &poly));
+        assert_eq!(Value::Num(1.0), eval("centroid_x(geom)", &poly));
+        assert_eq!(Value::Num(1.0), eval("centroid_y(geom)", &poly));
+        match eval("area(geom) / 1e6", &poly) {
+            Value::Num(km2) => assert!(km2 > 49000.0 && km2 < 50000.0),
+            other => panic!("Expected number, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_props() {
+        let g = Geometry::Point(Point::new(1.0, 2.0));
+        let props = json!({"pop": 20, "name": "x", "tags": ["a"]});
+        let scope = Scope {
+            geom: &g,
+            props: props.as_object().unwrap(),
+        };
+        let eval = |src| parse(src).unwrap().eval(&scope).unwrap();
+        assert_eq!(Value::Num(10.0), eval("props.pop / 2"));
+        assert_eq!(Value::Str("x!".to_string()), eval("props.name + '!'"));
+        assert_eq!(Value::Json(json!(["a"])), eval("props.tags"));
+        assert_eq!(Value::Null, eval("props.missing"));
+        assert_eq!(Value::Num(0.5), eval(".5"));
+        assert_eq!(
+            Value::Geom(Geometry::Point(Point::new(1.0, 2.0))),
+            eval("centroid(geom)")
+        );
+    }
+
+    #[test]
+    fn test_program() {
+        assert_eq!(
+            vec![
+                Assignment {
+                    target: Target::Geom,
+                    value: parse("buffer(geom, 100)").unwrap(),
+                },
+                Assignment {
+                    target: Target::Prop("area".to_string()),
+                    value: parse("area(geom)").unwrap(),
+                },
+            ],
+            parse_program("geom = buffer(geom, 100); props.area = area(geom);").unwrap()
+        );
+        assert_eq!(
+            Expr::Num(1.0),
+            parse_program("props.'a b' = 1").unwrap()[0].value
+        );
+        assert!(parse_program("area = 1").is_err());
+        assert!(parse_program("props.a = 1 props.b = 2").is_err());
+        assert!(parse_program(";").is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse("area(geom").is_err());
+        assert!(parse("1 +").is_err());
+        assert!(parse("1 2").is_err());
+        assert_eq!(
+            vec!["geom", "pizza"],
+            parse("area(geom) + pizza").unwrap().vars()
+        );
+    }
+}
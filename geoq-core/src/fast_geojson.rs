@@ -0,0 +1,341 @@
+// A faster way to parse GeoJSON Features and Geometries. The geojson
+// crate builds a serde_json::Value for the whole input, including one for
+// every coordinate, and then converts it; here the input is read in one
+// pass, with coordinates going straight into positions. FeatureCollections
+// are read once, keeping each feature's text, and their features parsed
+// from that. Anything this doesn't handle exactly as the geojson crate
+// would (GeometryCollections, unexpected members) gives None, to be
+// parsed the usual way.
+use geojson::{feature::Id, Feature, GeoJson, Geometry, Value};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{value::RawValue, Map};
+use std::fmt;
+
+// Coordinates of any depth, which are read before the geometry's type
+// may be known
+enum Coords {
+    Position(Vec<f64>),
+    Nested(Vec<Coords>),
+}
+
+enum Item {
+    Number(f64),
+    Coords(Coords),
+}
+
+struct CoordsVisitor;
+
+impl<'de> Visitor<'de> for CoordsVisitor {
+    type Value = Coords;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of coordinates")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Coords, A::Error> {
+        let mut numbers = vec![];
+        let mut nested = vec![];
+        while let Some(item) = seq.next_element()? {
+            match item {
+                Item::Number(n) => numbers.push(n),
+                Item::Coords(c) => nested.push(c),
+            }
+        }
+        match (numbers.is_empty(), nested.is_empty()) {
+            (_, true) => Ok(Coords::Position(numbers)),
+            (true, false) => Ok(Coords::Nested(nested)),
+            _ => Err(de::Error::custom("mixed numbers and arrays")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Coords {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        d.deserialize_seq(CoordsVisitor)
+    }
+}
+
+struct ItemVisitor;
+
+impl<'de> Visitor<'de> for ItemVisitor {
+    type Value = Item;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number or an array of coordinates")
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Item, E> {
+        Ok(Item::Number(v as f64))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Item, E> {
+        Ok(Item::Number(v as f64))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Item, E> {
+        Ok(Item::Number(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Item, A::Error> {
+        CoordsVisitor.visit_seq(seq).map(Item::Coords)
+    }
+}
+
+impl<'de> Deserialize<'de> for Item {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        d.deserialize_any(ItemVisitor)
+    }
+}
+
+// An empty array fits at any depth
+fn position(c: Coords) -> Option<Vec<f64>> {
+    match c {
+        Coords::Position(p) => Some(p),
+        Coords::Nested(n) if n.is_empty() => Some(vec![]),
+        Coords::Nested(_) => None,
+    }
+}
+
+fn nested<T>(c: Coords, inner: fn(Coords) -> Option<T>) -> Option<Vec<T>> {
+    match c {
+        Coords::Nested(n) => n.into_iter().map(inner).collect(),
+        Coords::Position(p) if p.is_empty() => Some(vec![]),
+        Coords::Position(_) => None,
+    }
+}
+
+fn positions(c: Coords) -> Option<Vec<Vec<f64>>> {
+    nested(c, position)
+}
+
+fn rings(c: Coords) -> Option<Vec<Vec<Vec<f64>>>> {
+    nested(c, positions)
+}
+
+// The members of a Feature, Geometry or FeatureCollection object
+#[derive(Default)]
+struct Object {
+    kind: Option<String>,
+    coordinates: Option<Coords>,
+    features: Option<Vec<Box<RawValue>>>,
+    geometry: Option<Option<Box<Object>>>,
+    properties: Option<serde_json::Value>,
+    id: Option<serde_json::Value>,
+    bbox: Option<Vec<f64>>,
+    foreign: Map<String, serde_json::Value>,
+}
+
+struct ObjectVisitor;
+
+impl<'de> Visitor<'de> for ObjectVisitor {
+    type Value = Object;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a GeoJSON object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Object, A::Error> {
+        let mut o = Object::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "type" => o.kind = Some(map.next_value()?),
+                "coordinates" => o.coordinates = Some(map.next_value()?),
+                "features" => o.features = Some(map.next_value()?),
+                "geometry" => o.geometry = Some(map.next_value::<Option<Object>>()?.map(Box::new)),
+                "properties" => o.properties = Some(map.next_value()?),
+                "id" => o.id = Some(map.next_value()?),
+                "bbox" => o.bbox = Some(map.next_value()?),
+                _ => {
+                    o.foreign.insert(key, map.next_value()?);
+                }
+            }
+        }
+        Ok(o)
+    }
+}
+
+impl<'de> Deserialize<'de> for Object {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        d.deserialize_map(ObjectVisitor)
+    }
+}
+
+fn foreign(members: Map<String, serde_json::Value>) -> Option<Map<String, serde_json::Value>> {
+    if members.is_empty() {
+        None
+    } else {
+        Some(members)
+    }
+}
+
+fn geometry(o: Object) -> Option<Geometry> {
+    if o.geometry.is_some() || o.properties.is_some() || o.id.is_some() || o.features.is_some() {
+        return None;
+    }
+    let coordinates = o.coordinates?;
+    let value = match o.kind?.as_str() {
+        "Point" => Value::Point(position(coordinates)?),
+        "MultiPoint" => Value::MultiPoint(positions(coordinates)?),
+        "LineString" => Value::LineString(positions(coordinates)?),
+        "MultiLineString" => Value::MultiLineString(rings(coordinates)?),
+        "Polygon" => Value::Polygon(rings(coordinates)?),
+        "MultiPolygon" => Value::MultiPolygon(nested(coordinates, rings)?),
+        _ => return None,
+    };
+    Some(Geometry {
+        bbox: o.bbox,
+        value,
+        foreign_members: foreign(o.foreign),
+    })
+}
+
+fn feature(o: Object) -> Option<Feature> {
+    if o.coordinates.is_some() || o.features.is_some() {
+        return None;
+    }
+    let geometry = match o.geometry? {
+        Some(g) => Some(geometry(*g)?),
+        None => None,
+    };
+    let properties = match o.properties? {
+        serde_json::Value::Object(p) => Some(p),
+        serde_json::Value::Null => None,
+        _ => return None,
+    };
+    let id = match o.id {
+        Some(serde_json::Value::Number(n)) => Some(Id::Number(n)),
+        Some(serde_json::Value::String(s)) => Some(Id::String(s)),
+        Some(_) => return None,
+        None => None,
+    };
+    Some(Feature {
+        bbox: o.bbox,
+        geometry,
+        id,
+        properties,
+        foreign_members: foreign(o.foreign),
+    })
+}
+
+// A FeatureCollection's features, each along with its own text, and the
+// collection's foreign members
+pub struct Collection {
+    pub features: Vec<(String, Feature)>,
+    pub foreign_members: Option<Map<String, serde_json::Value>>,
+}
+
+pub enum Parsed {
+    GeoJson(GeoJson),
+    FeatureCollection(Collection),
+}
+
+// Features this can't read are parsed by the geojson crate, from their
+// own text rather than the whole collection's
+fn collection_feature(raw: &RawValue) -> Option<(String, Feature)> {
+    let text = raw.get();
+    let f = match serde_json::from_str::<Object>(text).ok() {
+        Some(o) if o.kind.as_deref() == Some("Feature") => feature(o),
+        _ => None,
+    };
+    let f = match f {
+        Some(f) => f,
+        None => match text.parse().ok()? {
+            GeoJson::Feature(f) => f,
+            _ => return None,
+        },
+    };
+    Some((text.to_string(), f))
+}
+
+fn collection(o: Object) -> Option<Collection> {
+    if o.coordinates.is_some() || o.geometry.is_some() || o.properties.is_some() || o.id.is_some() {
+        return None;
+    }
+    let features = o
+        .features?
+        .iter()
+        .map(|f| collection_feature(f))
+        .collect::<Option<_>>()?;
+    Some(Collection {
+        features,
+        foreign_members: foreign(o.foreign),
+    })
+}
+
+pub fn parse(raw: &str) -> Option<Parsed> {
+    let o: Object = serde_json::from_str(raw).ok()?;
+    match o.kind.as_deref() {
+        Some("Feature") => feature(o).map(|f| Parsed::GeoJson(GeoJson::Feature(f))),
+        Some("FeatureCollection") => collection(o).map(Parsed::FeatureCollection),
+        _ => geometry(o).map(|g| Parsed::GeoJson(GeoJson::Geometry(g))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Parsed};
+    use geojson::GeoJson;
+
+    fn parse_geojson(raw: &str) -> Option<GeoJson> {
+        match parse(raw)? {
+            Parsed::GeoJson(gj) => Some(gj),
+            Parsed::FeatureCollection(c) => {
+                Some(GeoJson::FeatureCollection(geojson::FeatureCollection {
+                    bbox: None,
+                    features: c.features.into_iter().map(|(_, f)| f).collect(),
+                    foreign_members: c.foreign_members,
+                }))
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_geojson() {
+        for raw in &[
+            r#"{"type":"Point","coordinates":[1,2.5]}"#,
+            r#"{"type":"LineString","coordinates":[[1,2],[3,4,5]],"bbox":[1,2,3,4]}"#,
+            r#"{"type":"MultiPolygon","coordinates":[[[[0,0],[1,0],[1,1],[0,0]]]],"crs":{"a":1}}"#,
+            r#"{"type":"Feature","geometry":null,"properties":null}"#,
+            r#"{"type":"Feature","id":"a","geometry":{"type":"Point","coordinates":[1,2],"x":1},"properties":{"b":[1,{"c":null}]},"bbox":[1,2,1,2],"other":true}"#,
+            r#"{"type":"Feature","id":12,"geometry":{"type":"MultiPoint","coordinates":[]},"properties":{}}"#,
+            r#"{"type":"FeatureCollection","features":[]}"#,
+            r#"{"type":"FeatureCollection","crs":{"a":1},"features":[{"type":"Feature","geometry":null,"properties":null},{"type":"Feature","geometry":{"type":"GeometryCollection","geometries":[]},"properties":{"a":1}}]}"#,
+            "{\"type\":\"Feature\",\"geometry\":{\"type\":\"Point\",\"coordinates\":[1,2]},\"properties\":{\"n\":\"\\u00e9\\n\"}}",
+        ] {
+            let expected: GeoJson = raw.parse().unwrap();
+            assert_eq!(Some(expected), parse_geojson(raw), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_feature_texts() {
+        let raw = r#"{"type":"FeatureCollection","features":[ {"type":"Feature", "geometry":null,"properties":null} ]}"#;
+        match parse(raw) {
+            Some(Parsed::FeatureCollection(c)) => assert_eq!(
+                r#"{"type":"Feature", "geometry":null,"properties":null}"#,
+                c.features[0].0
+            ),
+            _ => panic!("Expected a FeatureCollection"),
+        }
+    }
+
+    #[test]
+    fn test_falls_back() {
+        for raw in &[
+            r#"{"type":"GeometryCollection","geometries":[]}"#,
+            r#"{"type":"FeatureCollection","features":[{"type":"Point","coordinates":[1,2]}]}"#,
+            r#"{"type":"FeatureCollection","features":[],"properties":{}}"#,
+            r#"{"type":"Feature","geometry":null,"properties":null,"features":[]}"#,
+            r#"{"type":"Point","coordinates":["1",2]}"#,
+            r#"{"type":"Point"}"#,
+            r#"{"type":"Feature","geometry":null}"#,
+            r#"{"type":"Feature","geometry":null,"properties":[]}"#,
+            r#"{"type":"Feature","geometry":null,"properties":null,"id":true}"#,
+            r#"{"type":"Feature","geometry":{"type":"GeometryCollection","geometries":[]},"properties":null}"#,
+            r#"[1,2]"#,
+            r#"{"type":"Point","coordinates":[1,2]"#,
+        ] {
+            assert_eq!(None, parse_geojson(raw), "{}", raw);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::geoq::{fgb::index, geojson::fvec};
+use crate::{fgb::index, geojson::fvec};
 
 use super::columns;
 use super::hilbert::BBox;
@@ -3,6 +3,7 @@ use std::convert::TryInto;
 use geo::coords_iter;
 use geojson::{Feature, Value};
 
+/// A bounding box, as FlatGeobuf indexes store them.
 #[derive(Debug, Clone, PartialEq)]
 pub struct BBox {
     pub min_x: f64,
@@ -11,12 +12,15 @@ pub struct BBox {
     pub max_y: f64,
 }
 
+/// A feature along with its bounding box.
 #[derive(Debug)]
 pub struct BoundedFeature {
     pub feature: Feature,
     pub bbox: BBox,
 }
 
+/// A node of a FlatGeobuf index: a bounding box, and the offset of the
+/// feature or child node it bounds.
 #[derive(Debug, Clone)]
 pub struct IndexNode {
     pub offset: usize,
@@ -35,6 +39,7 @@ fn u64_from_bytes(bytes: &[u8]) -> Result<u64, &str> {
 }
 
 impl IndexNode {
+    /// Reads a node as it's stored in the index.
     pub fn from_bytes(bytes: &[u8]) -> Result<IndexNode, &str> {
         if bytes.len() < 40 {
             return Err("Not enough bytes for IndexNode");
@@ -56,6 +61,7 @@ impl IndexNode {
 }
 
 impl BBox {
+    /// The bounding box of a single point.
     pub fn new(x: f64, y: f64) -> BBox {
         BBox {
             min_x: x,
@@ -65,6 +71,7 @@ impl BBox {
         }
     }
 
+    /// A bounding box which any other expands to fill.
     pub fn empty() -> BBox {
         BBox {
             min_x: f64::INFINITY,
@@ -74,6 +81,7 @@ impl BBox {
         }
     }
 
+    /// Grows the bounding box to cover another.
     pub fn expand(&mut self, other: &BBox) {
         if other.min_x < self.min_x {
             self.min_x = other.min_x;
@@ -142,6 +150,7 @@ impl BBox {
         }
     }
 
+    /// Grows the bounding box to cover a feature's geometry.
     pub fn expand_feature(&mut self, feat: &geojson::Feature) {
         if feat.geometry.is_none() {
             return;
@@ -151,6 +160,7 @@ impl BBox {
         self.expand_geom(g);
     }
 
+    /// The bounding box of a feature's geometry, which must not be null.
     pub fn for_feature(feat: &geojson::Feature) -> BBox {
         let (x, y) = feat_coord(feat);
         let mut bb = BBox::new(x, y);
@@ -158,6 +168,7 @@ impl BBox {
         bb
     }
 
+    /// [min x, min y, max x, max y]
     pub fn to_vec(&self) -> Vec<f64> {
         vec![self.min_x, self.min_y, self.max_x, self.max_y]
     }
@@ -177,7 +188,9 @@ impl BBox {
         self.max_y - self.min_y
     }
 
-    fn hilbert_bbox(&self, extent: &BBox) -> u32 {
+    /// Position of the bounding box's center along a hilbert curve filling
+    /// the extent.
+    pub fn hilbert_bbox(&self, extent: &BBox) -> u32 {
         // calculate bbox center and scale to hilbert_max
         let (mid_x, mid_y) = self.center();
         let x = (HILBERT_MAX * (mid_x - extent.min_x) / extent.width()).floor() as u32;
@@ -208,6 +221,8 @@ fn coord(geom: &Value) -> (f64, f64) {
 
 const HILBERT_MAX: f64 = ((1 << 16u32) - 1) as f64;
 
+/// Sorts features along a hilbert curve over their extent, which is
+/// returned along with them. The features must have geometries.
 pub fn sort_with_extent(features: Vec<geojson::Feature>) -> (Vec<BoundedFeature>, BBox) {
     let (start_x, start_y) = features.first().map(|f| feat_coord(f)).unwrap();
     let mut extent = BBox::new(start_x, start_y);
@@ -1,11 +1,19 @@
-use crate::geoq::fgb::hilbert::IndexNode;
+//! Writing FlatGeobuf files, and the hilbert sort and packed R-tree index
+//! they're ordered by.
+use crate::fgb::hilbert::IndexNode;
 
+// Only the hilbert sort is left without the fgb feature
+#[cfg(feature = "fgb")]
 pub(crate) mod columns;
+#[cfg(feature = "fgb")]
 pub(crate) mod feature;
+#[cfg(feature = "fgb")]
 pub(crate) mod geometry;
+#[cfg(feature = "fgb")]
 pub(crate) mod header;
-pub(crate) mod hilbert;
+pub mod hilbert;
 pub(crate) mod index;
+#[cfg(feature = "fgb")]
 pub(crate) mod properties;
 
 // TODO
@@ -29,11 +37,14 @@ pub(crate) mod properties;
 //   - use (bbox, byte_offset) pairs for building index
 // 5.
 
-// Binary Layout
-// MB: Magic bytes (0x6667620366676201)
-// H: Header (variable size flatbuffer) (written as its own standalone flatbuffer)
-// I (optional): Static packed Hilbert R-tree index (static size custom buffer)
-// DATA: Features (each written as its own standalone flatbuffer?)
+/// The features as a FlatGeobuf file, sorted along a hilbert curve and
+/// indexed. The features must have geometries. Binary layout:
+///
+/// * Magic bytes (0x6667620366676201)
+/// * Header (its own standalone flatbuffer)
+/// * Static packed Hilbert R-tree index
+/// * Features (each its own standalone flatbuffer)
+#[cfg(feature = "fgb")]
 pub fn write(features: Vec<geojson::Feature>) -> Vec<u8> {
     // collect features into vector
     // read features to get header schema (Columns "table")
@@ -76,9 +87,9 @@ pub fn write(features: Vec<geojson::Feature>) -> Vec<u8> {
     buffer
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "fgb"))]
 mod tests {
-    use crate::geoq::{
+    use crate::{
         fgb::{
             hilbert::{self, IndexNode},
             index::{self, RTreeIndexMeta},
@@ -275,7 +286,11 @@ mod tests {
     fn test_countries_dataset() {
         use geozero::ProcessToJson;
 
-        let input_file = File::open("./tests/resources/countries.geojson").unwrap();
+        let input_file = File::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../tests/resources/countries.geojson"
+        ))
+        .unwrap();
         let mut input_buffer = BufReader::new(input_file);
 
         let mut features: Vec<geojson::Feature> = vec![];
@@ -311,7 +326,10 @@ mod tests {
         use std::fs::File;
         use std::io::{BufReader, BufWriter};
 
-        let source_path = "./tests/resources/alabama500.geojson";
+        let source_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../tests/resources/alabama500.geojson"
+        );
 
         let input_file = File::open(source_path).unwrap();
         let mut input_buffer = BufReader::new(input_file);
@@ -0,0 +1,176 @@
+use geo::algorithm::coords_iter::CoordsIter;
+use geo_types::Geometry;
+use serde_json::{Map, Value};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// 64-bit FNV-1a. Used instead of std's DefaultHasher because its output
+// is stable across platforms and Rust releases, so fingerprints can be
+// compared between runs.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, b| {
+        (hash ^ (*b as u64)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+pub fn hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+// Content-derived fingerprint of a feature's geometry and properties.
+// Properties are serialized with sorted keys, so key order in the input
+// does not affect the result.
+pub fn feature(geometry: Option<&geojson::Geometry>, properties: &Map<String, Value>) -> u64 {
+    let mut bytes = match geometry {
+        Some(g) => serde_json::to_vec(g).unwrap(),
+        None => b"null".to_vec(),
+    };
+    bytes.push(b'\n');
+    bytes.extend(serde_json::to_vec(properties).unwrap());
+    fnv1a(&bytes)
+}
+
+// Per-round shift amounts and sines of MD5
+const MD5_S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+// MD5, for fingerprints which match those made by other tools (e.g.
+// md5(ST_AsBinary(geom)) in PostGIS). Not for anything needing security.
+pub fn md5(data: &[u8]) -> [u8; 16] {
+    let mut h: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    for block in message.chunks(64) {
+        let mut m = [0u32; 16];
+        for (i, word) in block.chunks(4).enumerate() {
+            m[i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        let [mut a, mut b, mut c, mut d] = h;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(MD5_K[i]).wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(MD5_S[i]));
+        }
+        for (v, x) in h.iter_mut().zip(&[a, b, c, d]) {
+            *v = v.wrapping_add(*x);
+        }
+    }
+
+    let mut digest = [0u8; 16];
+    for (i, v) in h.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&v.to_le_bytes());
+    }
+    digest
+}
+
+// Vertices are compared at this many decimal places (about 10cm for
+// degrees), so tiny differences in precision don't change a simhash
+const SIMHASH_PRECISION: f64 = 1e6;
+
+// 64-bit simhash of a geometry's vertices. Unlike the other hashes,
+// geometries which share most of their vertices get hashes differing in
+// only a few bits, so near-duplicates can be found by Hamming distance.
+pub fn simhash(geom: &Geometry<f64>) -> u64 {
+    let mut weights = [0i64; 64];
+    for c in geom.coords_iter() {
+        let x = (c.x * SIMHASH_PRECISION).round() as i64;
+        let y = (c.y * SIMHASH_PRECISION).round() as i64;
+        let mut bytes = x.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&y.to_le_bytes());
+        let hash = fnv1a(&bytes);
+        for (bit, w) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *w += 1;
+            } else {
+                *w -= 1;
+            }
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| **w > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fingerprint::{feature, fnv1a, hex, md5, simhash};
+    use geo_types::{Geometry, LineString};
+    use serde_json::json;
+
+    #[test]
+    fn test_md5() {
+        let hex = |d: [u8; 16]| d.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        assert_eq!("d41d8cd98f00b204e9800998ecf8427e", hex(md5(b"")));
+        assert_eq!("900150983cd24fb0d6963f7d28e17f72", hex(md5(b"abc")));
+        assert_eq!(
+            "57edf4a22be3c955ac49da2e2107b67a",
+            hex(md5(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_simhash_near_duplicates() {
+        let line = |n: usize, bump: f64| {
+            let coords: Vec<(f64, f64)> = (0..n)
+                .map(|i| (i as f64 * 0.01, if i == 5 { bump } else { 0.0 }))
+                .collect();
+            Geometry::LineString(LineString::from(coords))
+        };
+        let a = simhash(&line(40, 0.0));
+        let b = simhash(&line(40, 0.5));
+        let c = simhash(&Geometry::LineString(LineString::from(vec![
+            (5.0, 5.0),
+            (6.0, 7.0),
+        ])));
+        assert_eq!(a, simhash(&line(40, 0.000_000_01)));
+        assert!((a ^ b).count_ones() < (a ^ c).count_ones());
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!("cbf29ce484222325", hex(fnv1a(b"")));
+        assert_eq!("af63dc4c8601ec8c", hex(fnv1a(b"a")));
+    }
+
+    #[test]
+    fn test_property_order_independent() {
+        let a = json!({"a": 1, "b": 2});
+        let b = json!({"b": 2, "a": 1});
+        assert_eq!(
+            feature(None, a.as_object().unwrap()),
+            feature(None, b.as_object().unwrap())
+        );
+    }
+}
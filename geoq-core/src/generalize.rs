@@ -0,0 +1,271 @@
+//! Generalizing geometries for display at a zoom level: simplifying them
+//! to a zoom's resolution, adding vertices to long edges, and clipping
+//! them to a bbox. These are the steps `geoq tiles build` takes on each
+//! feature, for applications which draw or tile `geo` geometries
+//! themselves.
+//!
+//! Geometries are in lon/lat unless noted. Tolerances for zooms are in
+//! tile units, the pixels of a tile `extent` units across (4096 for
+//! vector tiles, or 256 or 512 for raster tiles), so a tolerance means
+//! the same on screen at every zoom.
+//!
+//! # Example
+//!
+//! ```
+//! use geo_types::{line_string, Geometry, Rect};
+//! use geoq_core::generalize;
+//!
+//! let coast = Geometry::LineString(line_string![
+//!     (x: -122.5, y: 37.70), (x: -122.49, y: 37.7001), (x: -122.4, y: 37.71)
+//! ]);
+//! // At zoom 4, the middle vertex is well under a pixel off the line
+//! match generalize::simplify_for_zoom(&coast, 4, 1.0, 256) {
+//!     Geometry::LineString(ls) => assert_eq!(2, ls.0.len()),
+//!     _ => unreachable!(),
+//! }
+//!
+//! let bay = Rect::new((-122.45, 37.6), (-122.3, 37.9));
+//! assert!(generalize::clip(&coast, &bay).is_some());
+//! ```
+use crate::tiles::{self, profile::Profile};
+use geo::algorithm::{
+    bool_ops::BooleanOps, geodesic_distance::GeodesicDistance, map_coords::MapCoords,
+    simplify::Simplify,
+};
+use geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon, Rect,
+};
+
+/// Simplifies lines and polygons with the Douglas-Peucker algorithm,
+/// dropping vertices less than `tolerance` from the line through their
+/// neighbors, in the geometry's own units. Points are left as they are.
+pub fn simplify(geom: &Geometry<f64>, tolerance: f64) -> Geometry<f64> {
+    match geom {
+        Geometry::LineString(ls) => Geometry::LineString(ls.simplify(&tolerance)),
+        Geometry::MultiLineString(mls) => Geometry::MultiLineString(mls.simplify(&tolerance)),
+        Geometry::Polygon(p) => Geometry::Polygon(p.simplify(&tolerance)),
+        Geometry::MultiPolygon(mp) => Geometry::MultiPolygon(mp.simplify(&tolerance)),
+        Geometry::Rect(r) => Geometry::Polygon(r.to_polygon().simplify(&tolerance)),
+        Geometry::Triangle(t) => Geometry::Polygon(t.to_polygon().simplify(&tolerance)),
+        Geometry::GeometryCollection(gc) => Geometry::GeometryCollection(GeometryCollection(
+            gc.iter().map(|g| simplify(g, tolerance)).collect(),
+        )),
+        g => g.clone(),
+    }
+}
+
+/// Simplifies a lon/lat geometry for display at zoom `z`, dropping
+/// vertices less than `tolerance` tile units from the line through their
+/// neighbors, for tiles `extent` units across. The geometry is
+/// simplified in Web Mercator, where tiles are square, and its latitudes
+/// are clamped to those of tiles.
+pub fn simplify_for_zoom(
+    geom: &Geometry<f64>,
+    z: u8,
+    tolerance: f64,
+    extent: u32,
+) -> Geometry<f64> {
+    let world_tolerance = tolerance / ((1u64 << z) as f64 * extent as f64);
+    let world = geom.map_coords(tiles::to_world);
+    simplify(&world, world_tolerance).map_coords(tiles::from_world)
+}
+
+/// The simplification tolerance a tile profile (see `geoq tiles build
+/// --profile`) gives zoom `z`, or `default` if none of its rules covers
+/// the zoom or the rule has no tolerance.
+pub fn zoom_tolerance(profile: &Profile, z: u8, default: f64) -> f64 {
+    profile
+        .rule(z)
+        .and_then(|rule| rule.tolerance)
+        .unwrap_or(default)
+}
+
+// The length in meters of the segment between two coordinates
+type Measure<'a> = &'a dyn Fn(Coord<f64>, Coord<f64>) -> f64;
+
+pub(crate) fn geodesic_meters(a: Coord<f64>, b: Coord<f64>) -> f64 {
+    Point(a).geodesic_distance(&Point(b))
+}
+
+fn densify_line_string(ls: &LineString<f64>, max_meters: f64, meters: Measure) -> LineString<f64> {
+    let mut coords: Vec<Coord<f64>> = vec![];
+    for line in ls.lines() {
+        let steps = (meters(line.start, line.end) / max_meters).ceil().max(1.0) as usize;
+        for i in 0..steps {
+            let t = i as f64 / steps as f64;
+            coords.push(line.start + line.delta() * t);
+        }
+    }
+    coords.extend(ls.0.last());
+    LineString(coords)
+}
+
+fn densify_polygon(p: &Polygon<f64>, max_meters: f64, meters: Measure) -> Polygon<f64> {
+    Polygon::new(
+        densify_line_string(p.exterior(), max_meters, meters),
+        p.interiors()
+            .iter()
+            .map(|r| densify_line_string(r, max_meters, meters))
+            .collect(),
+    )
+}
+
+/// Adds vertices to lines and polygons so that consecutive vertices are
+/// at most `max_meters` apart, interpolating linearly in lon/lat. Lines
+/// are given as line strings and rects and triangles as polygons.
+pub fn densify(geom: &Geometry<f64>, max_meters: f64) -> Geometry<f64> {
+    densify_by(geom, max_meters, &geodesic_meters)
+}
+
+// Densifies geometries in any coordinates, interpolating linearly in
+// them, with segment lengths in meters given by `meters`
+pub(crate) fn densify_by(geom: &Geometry<f64>, max_meters: f64, meters: Measure) -> Geometry<f64> {
+    match geom {
+        Geometry::Line(l) => Geometry::LineString(densify_line_string(
+            &LineString::from(*l),
+            max_meters,
+            meters,
+        )),
+        Geometry::LineString(ls) => {
+            Geometry::LineString(densify_line_string(ls, max_meters, meters))
+        }
+        Geometry::MultiLineString(mls) => Geometry::MultiLineString(MultiLineString(
+            mls.iter()
+                .map(|ls| densify_line_string(ls, max_meters, meters))
+                .collect(),
+        )),
+        Geometry::Polygon(p) => Geometry::Polygon(densify_polygon(p, max_meters, meters)),
+        Geometry::MultiPolygon(mp) => Geometry::MultiPolygon(MultiPolygon(
+            mp.iter()
+                .map(|p| densify_polygon(p, max_meters, meters))
+                .collect(),
+        )),
+        Geometry::Rect(r) => {
+            Geometry::Polygon(densify_polygon(&r.to_polygon(), max_meters, meters))
+        }
+        Geometry::Triangle(t) => {
+            Geometry::Polygon(densify_polygon(&t.to_polygon(), max_meters, meters))
+        }
+        Geometry::GeometryCollection(gc) => Geometry::GeometryCollection(
+            gc.iter()
+                .map(|g| densify_by(g, max_meters, meters))
+                .collect(),
+        ),
+        Geometry::Point(_) | Geometry::MultiPoint(_) => geom.clone(),
+    }
+}
+
+fn contains(r: &Rect<f64>, c: &Coord<f64>) -> bool {
+    c.x >= r.min().x && c.x <= r.max().x && c.y >= r.min().y && c.y <= r.max().y
+}
+
+/// The part of a geometry inside a rect, or None if none of it is.
+/// Points are given as multipoints, lines as multilinestrings, and
+/// polygons as multipolygons, since clipping can split them; collections
+/// keep their members which overlap the rect.
+pub fn clip(geom: &Geometry<f64>, rect: &Rect<f64>) -> Option<Geometry<f64>> {
+    let clipped = match geom {
+        Geometry::Point(p) => Geometry::MultiPoint(MultiPoint(vec![*p])),
+        Geometry::MultiPoint(mp) => Geometry::MultiPoint(mp.clone()),
+        Geometry::Line(l) => Geometry::MultiLineString(MultiLineString(vec![(*l).into()])),
+        Geometry::LineString(ls) => Geometry::MultiLineString(MultiLineString(vec![ls.clone()])),
+        Geometry::MultiLineString(mls) => Geometry::MultiLineString(mls.clone()),
+        Geometry::Polygon(p) => Geometry::MultiPolygon(MultiPolygon(vec![p.clone()])),
+        Geometry::MultiPolygon(mp) => Geometry::MultiPolygon(mp.clone()),
+        Geometry::Rect(r) => Geometry::MultiPolygon(MultiPolygon(vec![r.to_polygon()])),
+        Geometry::Triangle(t) => Geometry::MultiPolygon(MultiPolygon(vec![t.to_polygon()])),
+        Geometry::GeometryCollection(gc) => {
+            let members: Vec<Geometry<f64>> = gc.iter().filter_map(|g| clip(g, rect)).collect();
+            return if members.is_empty() {
+                None
+            } else {
+                Some(Geometry::GeometryCollection(GeometryCollection(members)))
+            };
+        }
+    };
+    let clip_poly = rect.to_polygon();
+    let clipped = match clipped {
+        Geometry::MultiPoint(mp) => Geometry::MultiPoint(MultiPoint(
+            mp.0.into_iter().filter(|p| contains(rect, &p.0)).collect(),
+        )),
+        Geometry::MultiLineString(mls) => Geometry::MultiLineString(clip_poly.clip(&mls, false)),
+        Geometry::MultiPolygon(mp) => {
+            Geometry::MultiPolygon(mp.intersection(&MultiPolygon(vec![clip_poly])))
+        }
+        g => g,
+    };
+    let empty = match &clipped {
+        Geometry::MultiPoint(mp) => mp.0.is_empty(),
+        Geometry::MultiLineString(mls) => mls.0.is_empty(),
+        Geometry::MultiPolygon(mp) => mp.0.is_empty(),
+        _ => true,
+    };
+    if empty {
+        None
+    } else {
+        Some(clipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clip, densify, simplify_for_zoom, zoom_tolerance};
+    use crate::tiles::profile::Profile;
+    use geo_types::{Geometry, LineString, MultiLineString, MultiPoint, Point, Rect};
+
+    #[test]
+    fn test_densify() {
+        // About 111km per degree at the equator
+        let ls = LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 0.1)]);
+        let dense = densify(&Geometry::LineString(ls), 50_000.0);
+        assert_eq!(
+            Geometry::LineString(LineString::from(vec![
+                (0.0, 0.0),
+                (1.0 / 3.0, 0.0),
+                (2.0 / 3.0, 0.0),
+                (1.0, 0.0),
+                (1.0, 0.1)
+            ])),
+            dense
+        );
+    }
+
+    #[test]
+    fn test_simplify_for_zoom() {
+        // 0.01 degrees off the line is under a pixel at zoom 4 but many at
+        // zoom 14
+        let ls = Geometry::LineString(LineString::from(vec![(0.0, 0.0), (1.0, 0.01), (2.0, 0.0)]));
+        let vertices = |g: Geometry<f64>| match g {
+            Geometry::LineString(ls) => ls.0.len(),
+            _ => 0,
+        };
+        assert_eq!(2, vertices(simplify_for_zoom(&ls, 4, 1.0, 256)));
+        assert_eq!(3, vertices(simplify_for_zoom(&ls, 14, 1.0, 256)));
+    }
+
+    #[test]
+    fn test_zoom_tolerance() {
+        let profile =
+            Profile::parse(r#"[{"min_zoom": 0, "max_zoom": 4, "tolerance": 8}]"#).unwrap();
+        assert_eq!(8.0, zoom_tolerance(&profile, 2, 1.0));
+        assert_eq!(1.0, zoom_tolerance(&profile, 5, 1.0));
+    }
+
+    #[test]
+    fn test_clip() {
+        let rect = Rect::new((0.0, 0.0), (1.0, 1.0));
+        let ls = Geometry::LineString(LineString::from(vec![(-1.0, 0.5), (2.0, 0.5)]));
+        assert_eq!(
+            Some(Geometry::MultiLineString(MultiLineString(vec![
+                LineString::from(vec![(0.0, 0.5), (1.0, 0.5)])
+            ]))),
+            clip(&ls, &rect)
+        );
+        assert_eq!(
+            Some(Geometry::MultiPoint(MultiPoint(vec![Point::new(0.5, 0.5)]))),
+            clip(&Geometry::Point(Point::new(0.5, 0.5)), &rect)
+        );
+        assert_eq!(None, clip(&Geometry::Point(Point::new(2.0, 2.0)), &rect));
+    }
+}
@@ -0,0 +1,237 @@
+//! Geohash math: cells, children, neighbors and coverings.
+use crate::contains;
+use geo_types::{Coord, Geometry, LineString, Polygon};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::str;
+
+/// The digits of geohashes, in order.
+pub const BASE_32: [char; 32] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'j', 'k',
+    'm', 'n', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
+];
+
+/// The 32 geohashes one level below a geohash.
+pub fn children(gh: &String) -> Vec<String> {
+    BASE_32.iter().map(|c| format!("{}{}", gh, c)).collect()
+}
+
+/// The 8 geohashes around a geohash, clockwise from north, after the
+/// geohash itself with `include_self`.
+pub fn neighbors(gh: &String, include_self: bool) -> Vec<String> {
+    let mut output: Vec<String> = if include_self {
+        Vec::with_capacity(9)
+    } else {
+        Vec::with_capacity(8)
+    };
+
+    if include_self {
+        output.push(gh.clone());
+    }
+
+    let neighbs = geohash::neighbors(gh).unwrap();
+    output.push(neighbs.n);
+    output.push(neighbs.ne);
+    output.push(neighbs.e);
+    output.push(neighbs.se);
+    output.push(neighbs.s);
+    output.push(neighbs.sw);
+    output.push(neighbs.w);
+    output.push(neighbs.nw);
+    output
+}
+
+/// A geohash's cell, or the whole world for the empty geohash. None for
+/// invalid geohashes.
+pub fn bbox(gh: &str) -> Option<Polygon<f64>> {
+    if gh == "" {
+        let min = Coord::<f64>::from((-180.0, -90.0));
+        let max = Coord::<f64>::from((180.0, 90.0));
+        return Some(geo_types::Rect::new(min, max).to_polygon());
+    }
+    match geohash::decode_bbox(gh) {
+        Ok(rect) => {
+            let bl = rect.min();
+            let tr = rect.max();
+            let outer = LineString(vec![
+                Coord::from((bl.x, bl.y)),
+                Coord::from((tr.x, bl.y)),
+                Coord::from((tr.x, tr.y)),
+                Coord::from((bl.x, tr.y)),
+                Coord::from((bl.x, bl.y)),
+            ]);
+            Some(Polygon::new(outer, Vec::new()))
+        }
+        _ => None,
+    }
+}
+
+/// Geohashes of the given level which intersect the geometry.
+pub fn covering(geom: &Geometry<f64>, level: usize) -> Vec<String> {
+    use geo::algorithm::intersects::Intersects;
+    let mut ghs: Vec<String> = vec![];
+    let mut queue: Vec<String> = vec!["".to_string()];
+    while !queue.is_empty() {
+        let gh = queue.pop().unwrap();
+        match bbox(&gh) {
+            Some(poly) => {
+                if contains::contains(&poly, &geom) || poly.intersects(geom) {
+                    if gh.len() < level {
+                        queue.extend(children(&gh));
+                    } else {
+                        ghs.push(gh);
+                    }
+                }
+            }
+            None => (),
+        }
+    }
+    ghs
+}
+
+/// Cover a geometry with mixed-precision geohashes, using at most max_cells
+/// (except when the geometry intersects more than max_cells root cells).
+/// Cells along the geometry's edges are refined coarsest-first until the
+/// budget is used up, while cells entirely inside the geometry are kept whole.
+pub fn adaptive_covering(geom: &Geometry<f64>, max_cells: usize, max_level: usize) -> Vec<String> {
+    use geo::algorithm::intersects::Intersects;
+    let mut inner: Vec<String> = vec![];
+    let mut edges: BinaryHeap<Reverse<(usize, String)>> = BinaryHeap::new();
+    edges.push(Reverse((0, "".to_string())));
+
+    while let Some(Reverse((len, gh))) = edges.pop() {
+        if len >= max_level {
+            edges.push(Reverse((len, gh)));
+            break;
+        }
+        let mut child_inner = vec![];
+        let mut child_edges = vec![];
+        for child in children(&gh) {
+            if let Some(poly) = bbox(&child) {
+                let cell = Geometry::Polygon(poly.clone());
+                if contains::contains_any(geom, &cell) {
+                    child_inner.push(child);
+                } else if contains::contains(&poly, geom) || poly.intersects(geom) {
+                    child_edges.push(child);
+                }
+            }
+        }
+        let count = inner.len() + edges.len() + child_inner.len() + child_edges.len();
+        if !gh.is_empty() && count > max_cells {
+            edges.push(Reverse((len, gh)));
+            break;
+        }
+        inner.extend(child_inner);
+        edges.extend(child_edges.into_iter().map(|c| Reverse((len + 1, c))));
+    }
+
+    let mut cells: Vec<String> = inner;
+    cells.extend(edges.into_iter().map(|Reverse((_, gh))| gh));
+    cells.sort();
+    cells
+}
+
+/// Reduce a set of geohashes to the smallest set covering the same area,
+/// by dropping geohashes already covered by an ancestor and replacing
+/// complete sets of 32 siblings with their parent.
+pub fn compact(ghs: Vec<String>) -> Vec<String> {
+    let input: HashSet<String> = ghs.into_iter().collect();
+    let mut set: HashSet<String> = input
+        .iter()
+        .filter(|gh| (0..gh.len()).all(|i| !input.contains(&gh[0..i])))
+        .cloned()
+        .collect();
+
+    // Single characters are the largest cells; the 32 of them aren't
+    // merged into the empty geohash
+    let max_len = set.iter().map(|gh| gh.len()).max().unwrap_or(0);
+    for len in (2..=max_len).rev() {
+        let mut sibling_counts: HashMap<String, usize> = HashMap::new();
+        for gh in set.iter().filter(|gh| gh.len() == len) {
+            *sibling_counts
+                .entry(gh[0..len - 1].to_string())
+                .or_insert(0) += 1;
+        }
+        for (parent, count) in sibling_counts {
+            if count == BASE_32.len() {
+                for child in children(&parent) {
+                    set.remove(&child);
+                }
+                set.insert(parent);
+            }
+        }
+    }
+
+    let mut compacted: Vec<String> = set.into_iter().collect();
+    compacted.sort();
+    compacted
+}
+
+// Copied from https://github.com/tidwall/geohash-rs/blob/master/src/base32.rs
+// TODO: Move remaining geohash usage to this crate, but for now I just
+// wanted the encoding stuff without importing 2 separate geohash libs
+const BASE32_ENCODING: [u8; 32] = [
+    b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9', b'b', b'c', b'd', b'e', b'f', b'g',
+    b'h', b'j', b'k', b'm', b'n', b'p', b'q', b'r', b's', b't', b'u', b'v', b'w', b'x', b'y', b'z',
+];
+
+/// The low 60 bits of an integer as a 12 character geohash.
+pub fn encode_long(mut x: u64) -> String {
+    let mut bytes = [0u8; 12];
+    for i in 0..12 {
+        bytes[11 - i] = BASE32_ENCODING[x as usize & 0x1f];
+        x >>= 5;
+    }
+    str::from_utf8(&bytes).unwrap().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adaptive_covering, children, compact, covering};
+    use geo_types::{Geometry, Point, Polygon};
+
+    #[test]
+    fn test_compact() {
+        let mut ghs = children(&"9q5".to_string());
+        ghs.push("9q6".to_string());
+        ghs.push("9q6b".to_string());
+        ghs.push("9q7c".to_string());
+        assert_eq!(vec!["9q5", "9q6", "9q7c"], compact(ghs));
+    }
+
+    #[test]
+    fn test_compact_recursive() {
+        let ghs: Vec<String> = children(&"9q".to_string())
+            .iter()
+            .flat_map(children)
+            .collect();
+        assert_eq!(vec!["9q"], compact(ghs));
+        let roots = children(&"".to_string());
+        assert_eq!(roots, compact(roots.clone()));
+    }
+
+    #[test]
+    fn test_adaptive_covering() {
+        let poly = Geometry::Polygon(Polygon::new(
+            vec![
+                [-118.5, 33.8],
+                [-117.9, 33.8],
+                [-117.9, 34.3],
+                [-118.5, 34.3],
+                [-118.5, 33.8],
+            ]
+            .into(),
+            vec![],
+        ));
+        let cells = adaptive_covering(&poly, 64, 12);
+        assert!(cells.len() <= 64);
+        // Mixed precision, using fewer cells than a fixed-precision covering
+        let min_len = cells.iter().map(|c| c.len()).min().unwrap();
+        let max_len = cells.iter().map(|c| c.len()).max().unwrap();
+        assert!(min_len < max_len);
+        assert!(cells.len() < covering(&poly, max_len).len());
+
+        let point = Geometry::Point(Point::new(-118.2, 34.0));
+        assert_eq!(vec!["9q5cq"], adaptive_covering(&point, 1, 5));
+    }
+}
@@ -0,0 +1,165 @@
+//! Detecting the format of a line of input, before it's parsed.
+use crate::error::Error;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{
+    fmt,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+static LATLON: Lazy<Regex> = Lazy::new(|| Regex::new(r"^-?\d+\.?\d*[,\t]-?\d+\.?\d*$").unwrap());
+static GH: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^[0-9a-z--a--i--l--o]+$").unwrap());
+static TILE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\d+/\d+/\d+$").unwrap());
+// Quadkeys need a qk: prefix, since digits 0-3 alone are also geohashes
+static QUADKEY: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^qk:[0-3]*$").unwrap());
+static H3: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^0?[0-9a-f]{15,16}$").unwrap());
+static JSON: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{").unwrap());
+static WKT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?ix)^point|linestring|polygon|multipoint|multilinestring|multipolygon").unwrap()
+});
+// Lat/Lons with spaces around or in place of the separator, for --lenient
+static SPACED_LATLON: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(-?\d+\.?\d*)\s*[,\t ]\s*(-?\d+\.?\d*)$").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Parsing {
+    Default,
+    /// Rejects coordinates which are out of range, not finite, or have
+    /// the wrong number of positions for their geometry
+    Strict,
+    /// Coerces almost-valid inputs, such as JSON with trailing commas
+    /// or string coordinates, and arrays of features
+    Lenient,
+}
+
+// Set from the global --strict and --lenient flags
+static PARSING: AtomicU8 = AtomicU8::new(0);
+
+/// Sets how strictly input is parsed, as the global --strict and
+/// --lenient flags do.
+pub fn set_parsing(parsing: Parsing) {
+    PARSING.store(parsing as u8, Ordering::Relaxed);
+}
+
+/// How strictly input is being parsed.
+pub fn parsing() -> Parsing {
+    match PARSING.load(Ordering::Relaxed) {
+        1 => Parsing::Strict,
+        2 => Parsing::Lenient,
+        _ => Parsing::Default,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Input {
+    LatLon(String),
+    Geohash(String),
+    WKT(String),
+    GeoJSON(String),
+    H3(String),
+    Tile(String),
+    Quadkey(String),
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Input::LatLon(ref raw) => write!(f, "LatLon({})", raw),
+            Input::Geohash(ref raw) => write!(f, "Geohash({})", raw),
+            Input::WKT(ref raw) => write!(f, "WKT({})", raw),
+            Input::GeoJSON(ref raw) => write!(f, "GeoJSON({})", raw),
+            Input::H3(ref raw) => write!(f, "H3Cell({})", raw),
+            Input::Tile(ref raw) => write!(f, "Tile({})", raw),
+            Input::Quadkey(ref raw) => write!(f, "Quadkey({})", raw),
+        }
+    }
+}
+
+/// Recognizes the format of a line of input, without parsing it yet.
+pub fn read_line(line: String) -> Result<Input, Error> {
+    if LATLON.is_match(&line) {
+        Ok(Input::LatLon(line))
+    } else if TILE.is_match(&line) {
+        Ok(Input::Tile(line))
+    } else if QUADKEY.is_match(&line) {
+        Ok(Input::Quadkey(line))
+    } else if H3.is_match(&line) {
+        Ok(Input::H3(line))
+    } else if GH.is_match(&line) {
+        Ok(Input::Geohash(line))
+    } else if JSON.is_match(&line) {
+        Ok(Input::GeoJSON(line))
+    } else if WKT.is_match(&line) {
+        Ok(Input::WKT(line))
+    } else if let Some(c) = SPACED_LATLON
+        .captures(&line)
+        .filter(|_| parsing() == Parsing::Lenient)
+    {
+        Ok(Input::LatLon(format!("{},{}", &c[1], &c[2])))
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Unable to parse single-line input: {}",
+            line
+        )))
+    }
+}
+
+#[test]
+fn reading_input_formats() {
+    match read_line("12,34".to_string()) {
+        Ok(Input::LatLon(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    match read_line("12\t34".to_string()) {
+        Ok(Input::LatLon(_)) => assert!(true),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn spaced_latlon_format() {
+    assert!(SPACED_LATLON.is_match("34.1, -118.2"));
+    assert!(SPACED_LATLON.is_match("34.1 -118.2"));
+    assert!(!SPACED_LATLON.is_match("34.1,-118.2,5"));
+}
+
+#[test]
+fn h3_regex_format() {
+    assert!(H3.is_match("862749967ffffff"));
+    assert!(H3.is_match("8027ffffffffffff"));
+    // too short
+    assert!(!H3.is_match("8027fffffffff"));
+    // too long
+    assert!(!H3.is_match("8027fffffffffffff"));
+    // optional leading 0
+    assert!(H3.is_match("08027ffffffffffff"));
+}
+
+#[test]
+fn tile_and_quadkey_formats() {
+    match read_line("12/702/1635".to_string()) {
+        Ok(Input::Tile(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    match read_line("qk:0231".to_string()) {
+        Ok(Input::Quadkey(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    // Bare digits are geohashes, even when they're only 0-3
+    match read_line("01".to_string()) {
+        Ok(Input::Geohash(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    match read_line("0123".to_string()) {
+        Ok(Input::Geohash(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    match read_line("9q5".to_string()) {
+        Ok(Input::Geohash(_)) => assert!(true),
+        _ => assert!(false),
+    }
+    match read_line("862749967ffffff".to_string()) {
+        Ok(Input::H3(_)) => assert!(true),
+        _ => assert!(false),
+    }
+}
@@ -0,0 +1,48 @@
+// Points named by their feature ids, for commands which pair up the
+// points of two datasets and say which is which (matrix, los)
+use crate::{entity::Entity, error::Error};
+use geo_types::{Geometry, Point};
+use serde_json::{json, Value};
+
+// A point and what it's called in the output: its feature id, or else its
+// position in its dataset
+pub struct Labeled {
+    pub label: Value,
+    pub point: Point<f64>,
+}
+
+pub fn labeled(e: &Entity, index: usize) -> Result<Labeled, Error> {
+    match e.geom() {
+        Geometry::Point(point) => Ok(Labeled {
+            label: e.id().map_or_else(|| json!(index), |id| json!(id)),
+            point,
+        }),
+        _ => Err(Error::InvalidInput(format!(
+            "Expected a point: {}",
+            e.raw()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::labeled;
+    use crate::{entity, input};
+    use serde_json::json;
+
+    #[test]
+    fn test_labeled() {
+        let e = |raw: &str| {
+            let i = input::read_line(raw.to_string()).unwrap();
+            entity::from_input(i).unwrap().remove(0)
+        };
+        let l = labeled(
+            &e(r#"{"type":"Feature","id":"a","properties":{},"geometry":{"type":"Point","coordinates":[1,2]}}"#),
+            3,
+        )
+        .unwrap();
+        assert_eq!(json!("a"), l.label);
+        assert_eq!(json!(3), labeled(&e("34,-118"), 3).unwrap().label);
+        assert!(labeled(&e("9q5"), 0).is_err());
+    }
+}
@@ -0,0 +1,29 @@
+use geo::algorithm::geodesic_length::GeodesicLength;
+use geo_types::{Geometry, Polygon};
+
+fn poly_length(poly: &Polygon<f64>) -> f64 {
+    poly.exterior().geodesic_length()
+        + poly
+            .interiors()
+            .iter()
+            .map(|ring| ring.geodesic_length())
+            .sum::<f64>()
+}
+
+// Geodesic length in meters.
+// Polygons are measured by the length of their rings (perimeter),
+// and points have a length of 0.
+pub fn length(geom: &Geometry<f64>) -> f64 {
+    match *geom {
+        Geometry::Point(_) => 0.0,
+        Geometry::MultiPoint(_) => 0.0,
+        Geometry::Line(ref g) => g.geodesic_length(),
+        Geometry::LineString(ref g) => g.geodesic_length(),
+        Geometry::MultiLineString(ref g) => g.geodesic_length(),
+        Geometry::Polygon(ref g) => poly_length(g),
+        Geometry::Rect(ref g) => poly_length(&g.to_polygon()),
+        Geometry::Triangle(ref g) => poly_length(&g.to_polygon()),
+        Geometry::MultiPolygon(ref g) => g.0.iter().map(poly_length).sum(),
+        Geometry::GeometryCollection(ref gc) => gc.0.iter().map(length).sum(),
+    }
+}
@@ -0,0 +1,162 @@
+//! geoq's geometry handling, as a library: reading entities (Lat/Lon
+//! points, geohashes, H3 cells, WKT, GeoJSON, and binary formats) from
+//! lines of input, converting them between formats, reading and writing
+//! FlatGeobuf, geohash and tile math, and running work over stdin in
+//! parallel. The geoq command line tool is built on it.
+//!
+//! # Stability
+//!
+//! The API of these modules follows semver, so breaking changes to them
+//! come only with a new minor version while geoq-core is below 1.0:
+//!
+//! * [`entity`] - [`Entity`](entity::Entity), the geometries geoq reads,
+//!   and [`from_input`](entity::from_input), with conversions to and
+//!   from `geo` geometries
+//! * [`input`] - Detecting the format of a line of input
+//! * [`reader`] - Reading entities from stdin or any `BufRead`
+//! * [`par`] - Handling the entities of stdin in parallel, with output
+//!   kept in input order
+//! * [`fgb`] - Writing FlatGeobuf files, and the hilbert sort they use
+//! * [`geohash`] and [`tile`] - Geohash, z/x/y tile and quadkey math
+//! * [`generalize`] - Simplifying geometries for a zoom, densifying and
+//!   clipping them, on `geo` types
+//! * [`convert`] - Conversions of a single input, with no IO
+//! * `wasm` - JavaScript bindings for [`convert`], in wasm32 builds
+//! * [`error`] - The [`Error`](error::Error) all of these give
+//!
+//! The other modules are public for the geoq CLI, left out of these docs,
+//! and may change in any release.
+//!
+//! # Example
+//!
+//! ```
+//! use geoq_core::{convert, entity, input};
+//!
+//! let entities = input::read_line("9q5".to_string())
+//!     .and_then(entity::from_input)
+//!     .unwrap();
+//! assert_eq!(1, entities.len());
+//!
+//! assert_eq!("POINT(-118 34)", convert::to_wkt("34,-118").unwrap());
+//! ```
+//!
+//! Handling stdin in parallel, as geoq's commands do:
+//!
+//! ```no_run
+//! let opts = geoq_core::par::ParOptions::default();
+//! geoq_core::par::for_stdin_entity(&opts, |e| Ok(vec![e.wkt().to_string()])).unwrap();
+//! ```
+#![allow(unreachable_code)]
+#![allow(dead_code)]
+#![allow(unused_imports)]
+pub mod convert;
+pub mod entity;
+pub mod error;
+pub mod fgb;
+pub mod generalize;
+pub mod geohash;
+pub mod input;
+pub mod par;
+pub mod reader;
+pub mod tile;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+#[doc(hidden)]
+pub mod area;
+#[doc(hidden)]
+pub mod bbox;
+#[doc(hidden)]
+pub mod binary;
+#[doc(hidden)]
+pub mod buffer;
+#[doc(hidden)]
+pub mod centroid;
+#[doc(hidden)]
+pub mod checkpoint;
+#[doc(hidden)]
+pub mod contains;
+#[doc(hidden)]
+pub mod contour;
+#[doc(hidden)]
+pub mod coord_count;
+#[doc(hidden)]
+pub mod crs;
+#[doc(hidden)]
+pub mod csv;
+#[cfg(feature = "net")]
+#[doc(hidden)]
+pub mod dem;
+#[doc(hidden)]
+pub mod distance;
+#[doc(hidden)]
+pub mod expr;
+#[doc(hidden)]
+pub mod fast_geojson;
+#[doc(hidden)]
+pub mod fingerprint;
+mod geojson;
+#[doc(hidden)]
+pub mod labeled;
+#[doc(hidden)]
+pub mod length;
+#[doc(hidden)]
+pub mod lookup;
+#[cfg(feature = "net")]
+#[doc(hidden)]
+pub mod net;
+#[cfg(feature = "net")]
+#[doc(hidden)]
+pub mod nominatim;
+#[cfg(feature = "net")]
+#[doc(hidden)]
+pub mod object_store;
+#[cfg(not(feature = "net"))]
+#[doc(hidden)]
+#[path = "object_store/local.rs"]
+pub mod object_store;
+#[doc(hidden)]
+pub mod on_error;
+#[doc(hidden)]
+pub mod other;
+#[doc(hidden)]
+pub mod output;
+#[doc(hidden)]
+pub mod overlay;
+#[doc(hidden)]
+pub mod plot;
+#[doc(hidden)]
+pub mod png;
+#[doc(hidden)]
+pub mod prepared;
+#[doc(hidden)]
+pub mod progress;
+#[doc(hidden)]
+pub mod raster;
+#[doc(hidden)]
+pub mod report;
+#[doc(hidden)]
+pub mod rhumb;
+#[doc(hidden)]
+pub mod rings;
+#[cfg(feature = "net")]
+#[doc(hidden)]
+pub mod routing;
+#[doc(hidden)]
+pub mod shp;
+#[doc(hidden)]
+pub mod simplify;
+#[doc(hidden)]
+pub mod snap;
+#[doc(hidden)]
+pub mod style;
+#[doc(hidden)]
+pub mod tiles;
+#[doc(hidden)]
+pub mod timestamp;
+#[doc(hidden)]
+pub mod trajectory;
+#[doc(hidden)]
+pub mod union;
+#[doc(hidden)]
+pub mod units;
@@ -0,0 +1,133 @@
+// Tables of properties keyed by a column, read from CSV or JSON, for
+// attaching to features by key (props join)
+use crate::{csv, error::Error, other};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, io::Read};
+
+pub type Lookup = HashMap<String, Map<String, Value>>;
+
+// Keys are compared as text, so a property 6037 matches a CSV value 6037,
+// but the string "06037" only matches "06037"
+pub fn key_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// CSV values are numbers when they read back the same as numbers, so
+// codes with leading zeros like 06037 stay as text
+fn csv_value(field: String) -> Value {
+    match field.parse::<serde_json::Number>() {
+        Ok(n) if n.to_string() == field => Value::Number(n),
+        _ => Value::String(field),
+    }
+}
+
+fn csv_rows(text: &str) -> Result<Vec<Map<String, Value>>, Error> {
+    let mut records = csv::records(text.trim_start_matches('\u{feff}'))?.into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| Error::InvalidInput("Lookup CSV has no header row".to_string()))?;
+    Ok(records
+        .filter(|r| !csv::is_blank(r))
+        .map(|r| {
+            header
+                .iter()
+                .cloned()
+                .zip(r.into_iter().map(csv_value))
+                .collect()
+        })
+        .collect())
+}
+
+// A JSON array of objects, or an object per line
+fn json_rows(text: &str) -> Result<Vec<Map<String, Value>>, Error> {
+    let values: Vec<Value> = if text.trim_start().starts_with('[') {
+        serde_json::from_str(text)?
+    } else {
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?
+    };
+    values
+        .into_iter()
+        .map(|v| match v {
+            Value::Object(row) => Ok(row),
+            v => Err(Error::InvalidInput(format!(
+                "Expected an object per row of the lookup: {}",
+                v
+            ))),
+        })
+        .collect()
+}
+
+fn is_json(path: &str, text: &str) -> bool {
+    let path = path.to_lowercase();
+    if path.ends_with(".csv") {
+        false
+    } else if path.ends_with(".json") || path.ends_with(".jsonl") || path.ends_with(".ndjson") {
+        true
+    } else {
+        matches!(text.trim_start().chars().next(), Some('[') | Some('{'))
+    }
+}
+
+// Reads the rows of a lookup table, keyed by their `key` column. Rows
+// without the key are skipped, and later rows replace earlier ones with
+// the same key.
+pub fn parse(path: &str, text: &str, key: &str) -> Result<Lookup, Error> {
+    let rows = if is_json(path, text) {
+        json_rows(text)?
+    } else {
+        csv_rows(text)?
+    };
+    let mut lookup = HashMap::new();
+    for mut row in rows {
+        if let Some(k) = row.remove(key).as_ref().and_then(key_text) {
+            lookup.insert(k, row);
+        }
+    }
+    Ok(lookup)
+}
+
+pub fn read(path: &str, key: &str) -> Result<Lookup, Error> {
+    let mut text = String::new();
+    other::open(path)?.read_to_string(&mut text)?;
+    parse(path, &text, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_csv() {
+        let lookup = parse(
+            "t.csv",
+            "geoid,pop,name\n06037,10014009,Los Angeles\n",
+            "geoid",
+        )
+        .unwrap();
+        assert_eq!(
+            json!({"pop": 10014009, "name": "Los Angeles"}),
+            serde_json::Value::Object(lookup["06037"].clone())
+        );
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let rows = r#"[{"id": 1, "a": true}, {"id": "x", "a": null}, {"a": 2}]"#;
+        let lookup = parse("t.json", rows, "id").unwrap();
+        assert_eq!(2, lookup.len());
+        assert_eq!(json!(true), lookup["1"]["a"]);
+
+        let lines = "{\"id\": 1, \"a\": 1}\n\n{\"id\": 1, \"a\": 2}\n";
+        let lookup = parse("lookup", lines, "id").unwrap();
+        assert_eq!(json!(2), lookup["1"]["a"]);
+    }
+}
@@ -0,0 +1,179 @@
+// Shared IO for commands which make HTTP requests (geocoding, routing,
+// DEM tiles, OGC APIs and object stores). Requests run on one small tokio
+// runtime rather than each blocking a thread while it waits, failures
+// which may pass (connection errors, 429s and 5xxs) are retried with
+// exponential backoff, and batches of requests run concurrently. Retries
+// and concurrency are set by the global --http-retries and
+// --http-concurrency flags.
+use crate::error::Error;
+use futures_util::stream::{self, StreamExt};
+use once_cell::sync::OnceCell;
+use reqwest::{header::RETRY_AFTER, RequestBuilder, Response, StatusCode};
+use std::{
+    future::Future,
+    io::{self, Read},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use tokio::runtime::{self, Runtime};
+
+static RUNTIME: OnceCell<Runtime> = OnceCell::new();
+static CLIENT: OnceCell<reqwest::Client> = OnceCell::new();
+static RETRIES: AtomicUsize = AtomicUsize::new(3);
+static CONCURRENCY: AtomicUsize = AtomicUsize::new(8);
+
+// Waits before each retry start here and double, up to the maximum
+const FIRST_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+pub fn set_retries(retries: usize) {
+    RETRIES.store(retries, Ordering::Relaxed);
+}
+
+pub fn set_concurrency(requests: usize) {
+    CONCURRENCY.store(requests.max(1), Ordering::Relaxed);
+}
+
+pub fn concurrency() -> usize {
+    CONCURRENCY.load(Ordering::Relaxed)
+}
+
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("geoq-io")
+            .enable_all()
+            .build()
+            .expect("Failed to start IO runtime")
+    })
+}
+
+// The client shared by all requests, so connections are reused
+pub fn client() -> &'static reqwest::Client {
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .user_agent(concat!("geoq/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("Failed to build HTTP client")
+    })
+}
+
+// Runs a future on the IO runtime from ordinary (non-async) code,
+// such as a command's per-entity handler
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    runtime().block_on(future)
+}
+
+fn retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn backoff(attempt: u32) -> Duration {
+    FIRST_BACKOFF
+        .checked_mul(1 << attempt.min(16))
+        .unwrap_or(MAX_BACKOFF)
+        .min(MAX_BACKOFF)
+}
+
+// How long a response asks to be left before retrying, when it gives
+// Retry-After in seconds
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let secs = resp.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    let secs = secs.trim().parse::<u64>().ok()?;
+    Some(Duration::from_secs(secs).min(MAX_BACKOFF))
+}
+
+// Sends a request, retrying it while it fails in a way which may pass.
+// The last response is given back whatever its status, for callers to
+// check as they need.
+pub async fn send(request: RequestBuilder) -> Result<Response, Error> {
+    let retries = RETRIES.load(Ordering::Relaxed) as u32;
+    let mut attempt = 0;
+    loop {
+        // Requests with streamed bodies can't be copied, so are sent once
+        let req = match request.try_clone() {
+            Some(req) if attempt < retries => req,
+            _ => return Ok(request.send().await?),
+        };
+        let wait = match req.send().await {
+            Ok(resp) if retryable(resp.status()) => {
+                retry_after(&resp).unwrap_or_else(|| backoff(attempt))
+            }
+            Ok(resp) => return Ok(resp),
+            Err(e) if e.is_connect() || e.is_timeout() => backoff(attempt),
+            Err(e) => return Err(e.into()),
+        };
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+    }
+}
+
+pub async fn text(request: RequestBuilder) -> Result<(StatusCode, String), Error> {
+    let resp = send(request).await?;
+    Ok((resp.status(), resp.text().await?))
+}
+
+// Runs an async function over each item, with up to --http-concurrency
+// running at once, giving the results in the order of the items
+pub fn batch<T, F, Fut>(items: Vec<T>, f: F) -> Vec<Fut::Output>
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future,
+{
+    block_on(stream::iter(items).map(f).buffered(concurrency()).collect())
+}
+
+// A response body read as it downloads
+pub struct Body {
+    resp: Response,
+    chunk: bytes::Bytes,
+}
+
+impl Body {
+    pub fn new(resp: Response) -> Body {
+        Body {
+            resp,
+            chunk: bytes::Bytes::new(),
+        }
+    }
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match block_on(self.resp.chunk()) {
+                Ok(Some(chunk)) => self.chunk = chunk,
+                Ok(None) => return Ok(0),
+                Err(e) => return Err(io::Error::other(e)),
+            }
+        }
+        let n = buf.len().min(self.chunk.len());
+        buf[..n].copy_from_slice(&self.chunk.split_to(n));
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backoff, batch};
+    use std::time::Duration;
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(Duration::from_millis(250), backoff(0));
+        assert_eq!(Duration::from_millis(1000), backoff(2));
+        assert_eq!(Duration::from_secs(30), backoff(10));
+        assert_eq!(Duration::from_secs(30), backoff(100));
+    }
+
+    #[test]
+    fn test_batch_keeps_order() {
+        let results = batch((0..20u64).collect(), |i| async move {
+            // Later items finish first
+            tokio::time::sleep(Duration::from_millis(20 - i)).await;
+            i * 2
+        });
+        assert_eq!((0..20u64).map(|i| i * 2).collect::<Vec<_>>(), results);
+    }
+}
@@ -0,0 +1,159 @@
+// Client for the Nominatim geocoding API: https://nominatim.org/release-docs/develop/api/Overview/
+use crate::{error::Error, net};
+use serde_json::{json, Map, Value};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_ENDPOINT: &str = "https://nominatim.openstreetmap.org";
+
+pub struct Client {
+    endpoint: String,
+    // Minimum time between requests. The public Nominatim instance
+    // allows at most 1 request per second.
+    interval: Duration,
+    last_request: Option<Instant>,
+}
+
+// A geocoding result, in GeoJSON order
+pub struct Place {
+    pub lon: f64,
+    pub lat: f64,
+    pub bbox: Option<Vec<f64>>,
+    pub properties: Map<String, Value>,
+}
+
+impl Place {
+    fn from_json(result: &Value) -> Option<Place> {
+        let number = |v: &Value| match v {
+            Value::String(s) => s.parse::<f64>().ok(),
+            _ => v.as_f64(),
+        };
+        let lat = number(&result["lat"])?;
+        let lon = number(&result["lon"])?;
+        // Nominatim gives bounding boxes as [south, north, west, east]
+        let bbox = result["boundingbox"].as_array().and_then(|b| {
+            let b: Option<Vec<f64>> = b.iter().map(number).collect();
+            match b {
+                Some(b) if b.len() == 4 => Some(vec![b[2], b[0], b[3], b[1]]),
+                _ => None,
+            }
+        });
+        let mut properties = Map::new();
+        for key in &["display_name", "address", "osm_type", "osm_id"] {
+            if let Some(v) = result.get(*key) {
+                properties.insert(key.to_string(), v.clone());
+            }
+        }
+        Some(Place {
+            lon,
+            lat,
+            bbox,
+            properties,
+        })
+    }
+
+    // A Point Feature for the place, with the given properties
+    // followed by the place's address metadata
+    pub fn feature(&self, mut properties: Map<String, Value>) -> Value {
+        properties.extend(self.properties.clone());
+        let mut feature = json!({
+            "type": "Feature",
+            "properties": properties,
+            "geometry": {"type": "Point", "coordinates": [self.lon, self.lat]},
+        });
+        if let Some(bbox) = &self.bbox {
+            feature["bbox"] = json!(bbox);
+        }
+        feature
+    }
+}
+
+impl Client {
+    pub fn new(endpoint: &str, requests_per_second: f64) -> Result<Client, Error> {
+        if requests_per_second.is_nan() || requests_per_second <= 0.0 {
+            return Err(Error::InvalidNumberFormat(format!(
+                "Expected positive request rate: {}",
+                requests_per_second
+            )));
+        }
+        Ok(Client {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            interval: Duration::from_secs_f64(1.0 / requests_per_second),
+            last_request: None,
+        })
+    }
+
+    fn get(&mut self, path: &str, query: &[(&str, &str)]) -> Result<Value, Error> {
+        if let Some(last) = self.last_request {
+            let elapsed = last.elapsed();
+            if elapsed < self.interval {
+                thread::sleep(self.interval - elapsed);
+            }
+        }
+        self.last_request = Some(Instant::now());
+
+        let url = format!("{}/{}", self.endpoint, path);
+        let request = net::client()
+            .get(&url)
+            .query(&[("format", "jsonv2"), ("addressdetails", "1")])
+            .query(query);
+        let (status, body) = net::block_on(net::text(request))?;
+        if !status.is_success() {
+            return Err(Error::ProgramError(format!(
+                "Nominatim request to {} failed: {}",
+                url, status
+            )));
+        }
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    pub fn search(&mut self, query: &str, limit: usize) -> Result<Vec<Place>, Error> {
+        let limit = limit.to_string();
+        let results = self.get("search", &[("q", query), ("limit", &limit)])?;
+        match results.as_array() {
+            Some(results) => Ok(results.iter().filter_map(Place::from_json).collect()),
+            None => Err(Error::ProgramError(format!(
+                "Unexpected Nominatim search response: {}",
+                results
+            ))),
+        }
+    }
+
+    // The place nearest a point, with detail given by a zoom level from
+    // 3 (country) to 18 (building), or None if there's nothing there
+    pub fn reverse(&mut self, lon: f64, lat: f64, zoom: u8) -> Result<Option<Place>, Error> {
+        let (lon, lat, zoom) = (lon.to_string(), lat.to_string(), zoom.to_string());
+        let result = self.get("reverse", &[("lat", &lat), ("lon", &lon), ("zoom", &zoom)])?;
+        if result.get("error").is_some() {
+            return Ok(None);
+        }
+        Ok(Place::from_json(&result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Place;
+    use serde_json::{json, Map};
+
+    #[test]
+    fn test_place_from_json() {
+        let result = json!({
+            "lat": "38.8976633",
+            "lon": "-77.0365739",
+            "boundingbox": ["38.897", "38.898", "-77.037", "-77.036"],
+            "display_name": "White House, 1600, Pennsylvania Avenue Northwest",
+            "address": {"house_number": "1600", "road": "Pennsylvania Avenue Northwest"},
+            "importance": 0.9
+        });
+        let place = Place::from_json(&result).unwrap();
+        let feature = place.feature(Map::new());
+        assert_eq!(
+            json!([-77.0365739, 38.8976633]),
+            feature["geometry"]["coordinates"]
+        );
+        assert_eq!(json!([-77.037, 38.897, -77.036, 38.898]), feature["bbox"]);
+        assert_eq!("1600", feature["properties"]["address"]["house_number"]);
+        assert!(feature["properties"].get("importance").is_none());
+    }
+}
@@ -0,0 +1,36 @@
+// Stands in for object_store without the net feature: the same functions,
+// for local paths only. s3://, gs:// and http(s) URIs are refused rather
+// than read as (missing) local files.
+use crate::error::Error;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Seek};
+
+fn local(path: &str) -> Result<&str, Error> {
+    let remote = ["s3://", "gs://", "http://", "https://"];
+    if remote.iter().any(|scheme| path.starts_with(scheme)) {
+        return Err(Error::InvalidInput(format!(
+            "Can't read {} -- geoq was built without the net feature",
+            path
+        )));
+    }
+    Ok(path)
+}
+
+pub fn read(path: &str) -> Result<Vec<u8>, Error> {
+    Ok(fs::read(local(path)?)?)
+}
+
+pub fn write(path: &str, data: Vec<u8>) -> Result<(), Error> {
+    Ok(fs::write(local(path)?, data)?)
+}
+
+pub fn open_buffered(path: &str) -> Result<Box<dyn BufRead>, Error> {
+    Ok(Box::new(BufReader::new(File::open(local(path)?)?)))
+}
+
+pub trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
+pub fn open_seekable(path: &str) -> Result<Box<dyn ReadSeek>, Error> {
+    Ok(Box::new(BufReader::new(File::open(local(path)?)?)))
+}
@@ -0,0 +1,332 @@
+// Reads and writes files given either as local paths or as s3://bucket/key
+// and gs://bucket/key URIs.
+//
+// S3 requests are signed with AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY
+// (and AWS_SESSION_TOKEN) when set, in AWS_REGION, and go to
+// AWS_ENDPOINT_URL for S3-compatible stores. GCS requests use the XML
+// API, authorized by GOOGLE_OAUTH_ACCESS_TOKEN when set, and go to
+// STORAGE_EMULATOR_HOST if given. Without credentials, objects must be
+// public.
+use crate::{error::Error, net};
+use reqwest::{Method, Url};
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+
+pub mod sigv4;
+
+enum Auth {
+    Anonymous,
+    Aws {
+        credentials: sigv4::Credentials,
+        region: String,
+    },
+    Bearer(String),
+}
+
+// An object in S3 or GCS
+struct Object {
+    uri: String,
+    url: Url,
+    auth: Auth,
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+// Percent-encodes a key for use in a URL path, as S3 signing expects:
+// everything but unreserved characters and slashes
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+impl Object {
+    // None for local paths
+    fn parse(path: &str) -> Result<Option<Object>, Error> {
+        let (scheme, rest) = if let Some(rest) = path.strip_prefix("s3://") {
+            ("s3", rest)
+        } else if let Some(rest) = path.strip_prefix("gs://") {
+            ("gs", rest)
+        } else {
+            return Ok(None);
+        };
+        let (bucket, key) = match rest.find('/') {
+            Some(i) if i > 0 && i + 1 < rest.len() => (&rest[..i], &rest[i + 1..]),
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "Expected {}://bucket/key: {}",
+                    scheme, path
+                )))
+            }
+        };
+        let key = encode_key(key);
+        let (url, auth) = if scheme == "s3" {
+            let region = env_var("AWS_REGION")
+                .or_else(|| env_var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|| "us-east-1".to_string());
+            let url = match env_var("AWS_ENDPOINT_URL") {
+                Some(endpoint) => format!("{}/{}/{}", endpoint.trim_end_matches('/'), bucket, key),
+                None => format!("https://{}.s3.{}.amazonaws.com/{}", bucket, region, key),
+            };
+            let auth = match (
+                env_var("AWS_ACCESS_KEY_ID"),
+                env_var("AWS_SECRET_ACCESS_KEY"),
+            ) {
+                (Some(access_key), Some(secret_key)) => Auth::Aws {
+                    credentials: sigv4::Credentials {
+                        access_key,
+                        secret_key,
+                        session_token: env_var("AWS_SESSION_TOKEN"),
+                    },
+                    region,
+                },
+                _ => Auth::Anonymous,
+            };
+            (url, auth)
+        } else {
+            let endpoint = env_var("STORAGE_EMULATOR_HOST")
+                .unwrap_or_else(|| "https://storage.googleapis.com".to_string());
+            let url = format!("{}/{}/{}", endpoint.trim_end_matches('/'), bucket, key);
+            let auth = match env_var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+                Some(token) => Auth::Bearer(token),
+                None => Auth::Anonymous,
+            };
+            (url, auth)
+        };
+        let url = Url::parse(&url)
+            .map_err(|_| Error::InvalidInput(format!("Invalid object URL: {}", url)))?;
+        Ok(Some(Object {
+            uri: path.to_string(),
+            url,
+            auth,
+        }))
+    }
+
+    // Sends a request for the object, optionally for an inclusive range
+    // of its bytes, failing on error statuses
+    fn send(
+        &self,
+        method: Method,
+        range: Option<(u64, u64)>,
+        body: Option<Vec<u8>>,
+    ) -> Result<reqwest::Response, Error> {
+        let mut headers: Vec<(String, String)> = vec![];
+        if let Some((start, end)) = range {
+            headers.push(("range".to_string(), format!("bytes={}-{}", start, end)));
+        }
+        match &self.auth {
+            Auth::Anonymous => (),
+            Auth::Bearer(token) => {
+                headers.push(("authorization".to_string(), format!("Bearer {}", token)));
+            }
+            Auth::Aws {
+                credentials,
+                region,
+            } => {
+                let payload_hash = sigv4::hex(&sigv4::sha256(body.as_deref().unwrap_or_default()));
+                let host = match self.url.port() {
+                    Some(port) => format!("{}:{}", self.url.host_str().unwrap_or(""), port),
+                    None => self.url.host_str().unwrap_or("").to_string(),
+                };
+                headers.push(("host".to_string(), host));
+                headers.push(("x-amz-content-sha256".to_string(), payload_hash.clone()));
+                headers.push((
+                    "x-amz-date".to_string(),
+                    chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
+                ));
+                if let Some(token) = &credentials.session_token {
+                    headers.push(("x-amz-security-token".to_string(), token.clone()));
+                }
+                let auth = sigv4::authorization(
+                    credentials,
+                    region,
+                    method.as_str(),
+                    self.url.path(),
+                    "",
+                    &headers,
+                    &payload_hash,
+                );
+                headers.push(("authorization".to_string(), auth));
+            }
+        }
+
+        let mut req = net::client().request(method.clone(), self.url.clone());
+        for (k, v) in headers {
+            // reqwest sets the host itself
+            if k != "host" {
+                req = req.header(k.as_str(), v);
+            }
+        }
+        if let Some(body) = body {
+            req = req.body(body);
+        }
+        let resp = net::block_on(net::send(req))?;
+        if !resp.status().is_success() {
+            return Err(Error::ProgramError(format!(
+                "{} request failed with {}: {}",
+                method,
+                resp.status(),
+                self.uri
+            )));
+        }
+        Ok(resp)
+    }
+
+    fn len(&self) -> Result<u64, Error> {
+        let resp = self.send(Method::HEAD, None, None)?;
+        resp.headers()
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .ok_or_else(|| Error::ProgramError(format!("Missing Content-Length for: {}", self.uri)))
+    }
+}
+
+// Reads a whole file
+pub fn read(path: &str) -> Result<Vec<u8>, Error> {
+    match Object::parse(path)? {
+        None => Ok(fs::read(path)?),
+        Some(object) => {
+            let resp = object.send(Method::GET, None, None)?;
+            Ok(net::block_on(resp.bytes())?.to_vec())
+        }
+    }
+}
+
+// Creates or replaces a file
+pub fn write(path: &str, data: Vec<u8>) -> Result<(), Error> {
+    match Object::parse(path)? {
+        None => Ok(fs::write(path, data)?),
+        Some(object) => object.send(Method::PUT, None, Some(data)).map(|_| ()),
+    }
+}
+
+// A file to be read in order, such as lines of features. Objects are
+// streamed as they download.
+pub fn open_buffered(path: &str) -> Result<Box<dyn BufRead>, Error> {
+    match Object::parse(path)? {
+        None => Ok(Box::new(BufReader::new(File::open(path)?))),
+        Some(object) => Ok(Box::new(BufReader::new(net::Body::new(object.send(
+            Method::GET,
+            None,
+            None,
+        )?)))),
+    }
+}
+
+pub trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
+// A file to be read in parts, such as an indexed FlatGeobuf file.
+// Objects are read with range requests, so only the parts used are
+// downloaded.
+pub fn open_seekable(path: &str) -> Result<Box<dyn ReadSeek>, Error> {
+    match Object::parse(path)? {
+        None => Ok(Box::new(BufReader::new(File::open(path)?))),
+        Some(object) => {
+            let len = object.len()?;
+            Ok(Box::new(RangeReader {
+                object,
+                len,
+                pos: 0,
+                blocks: HashMap::new(),
+            }))
+        }
+    }
+}
+
+// Objects are requested in blocks of this many bytes, to avoid many
+// small requests for reads near one another
+const BLOCK_SIZE: u64 = 64 * 1024;
+// Blocks kept before the cache is cleared
+const MAX_BLOCKS: usize = 256;
+
+struct RangeReader {
+    object: Object,
+    len: u64,
+    pos: u64,
+    blocks: HashMap<u64, Vec<u8>>,
+}
+
+impl RangeReader {
+    fn block(&mut self, index: u64) -> io::Result<&Vec<u8>> {
+        if !self.blocks.contains_key(&index) {
+            if self.blocks.len() >= MAX_BLOCKS {
+                self.blocks.clear();
+            }
+            let start = index * BLOCK_SIZE;
+            let end = (start + BLOCK_SIZE).min(self.len) - 1;
+            let data = self
+                .object
+                .send(Method::GET, Some((start, end)), None)
+                .and_then(|resp| Ok(net::block_on(resp.bytes())?.to_vec()))
+                .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+            self.blocks.insert(index, data);
+        }
+        Ok(&self.blocks[&index])
+    }
+}
+
+impl Read for RangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let pos = self.pos;
+        let block = self.block(pos / BLOCK_SIZE)?;
+        let offset = (pos % BLOCK_SIZE) as usize;
+        if offset >= block.len() {
+            return Ok(0);
+        }
+        let n = buf.len().min(block.len() - offset);
+        buf[..n].copy_from_slice(&block[offset..offset + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for RangeReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(p) => p as i64,
+            SeekFrom::End(p) => self.len as i64 + p,
+            SeekFrom::Current(p) => self.pos as i64 + p,
+        };
+        if pos < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek before start of object",
+            ));
+        }
+        self.pos = pos as u64;
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_key, Object};
+
+    #[test]
+    fn test_parse() {
+        assert!(Object::parse("data/file.fgb").unwrap().is_none());
+        assert!(Object::parse("s3://bucket").is_err());
+        assert!(Object::parse("gs:///key").is_err());
+        let object = Object::parse("gs://bucket/dir/a b.fgb").unwrap().unwrap();
+        assert_eq!("/bucket/dir/a%20b.fgb", object.url.path());
+    }
+
+    #[test]
+    fn test_encode_key() {
+        assert_eq!("a/b%2Bc%C3%A9.json", encode_key("a/b+cé.json"));
+    }
+}
@@ -0,0 +1,110 @@
+// What to do with an input record which can't be parsed or processed,
+// set from the global --on-error and --error-file flags: stop with the
+// error, or skip the record and carry on, optionally writing it to a
+// sidecar file of JSON lines along with its error.
+use crate::{error::Error, report};
+use once_cell::sync::OnceCell;
+use serde_json::json;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Policy {
+    Abort,
+    Skip,
+    Report,
+}
+
+impl FromStr for Policy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "abort" => Ok(Policy::Abort),
+            "skip" => Ok(Policy::Skip),
+            "report" => Ok(Policy::Report),
+            _ => Err(Error::InvalidInput(format!(
+                "Expected abort, skip or report for --on-error: {}",
+                s
+            ))),
+        }
+    }
+}
+
+struct Config {
+    policy: Policy,
+    sidecar: Option<Mutex<BufWriter<File>>>,
+}
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+static SKIPPED: AtomicUsize = AtomicUsize::new(0);
+
+pub fn configure(policy: Policy, error_file: Option<&str>) -> Result<(), Error> {
+    let sidecar = match (policy, error_file) {
+        (Policy::Report, Some(path)) => Some(Mutex::new(BufWriter::new(File::create(path)?))),
+        (Policy::Report, None) => return Err(Error::MissingArgument),
+        _ => None,
+    };
+    let _ = CONFIG.set(Config { policy, sidecar });
+    Ok(())
+}
+
+pub fn policy() -> Policy {
+    CONFIG.get().map_or(Policy::Abort, |c| c.policy)
+}
+
+// Called with a record's input and the error it caused, which should
+// have its location. Gives the error back to abort with, or records the
+// record as skipped.
+pub fn handle(input: &str, err: Error) -> Result<(), Error> {
+    match CONFIG.get() {
+        Some(c) if c.policy != Policy::Abort => record(c.sidecar.as_ref(), input, &err),
+        _ => Err(err),
+    }
+}
+
+// Skips a record whatever the policy, for errors like timeouts which
+// shouldn't stop the run. Each one is reported as it happens.
+pub fn skip(input: &str, err: Error) -> Result<(), Error> {
+    if !report::json() {
+        if let Some(l) = err.location() {
+            let message = format!("Skipped line {} after {:?}", l.line, err.cause());
+            report::warning(&message, &l.input);
+        }
+    }
+    record(CONFIG.get().and_then(|c| c.sidecar.as_ref()), input, &err)
+}
+
+fn record(sidecar: Option<&Mutex<BufWriter<File>>>, input: &str, err: &Error) -> Result<(), Error> {
+    SKIPPED.fetch_add(1, Ordering::Relaxed);
+    report::skipped(err);
+    if let Some(sidecar) = sidecar {
+        let record = json!({
+            "error": format!("{:?}", err.cause()),
+            "line": err.location().map(|l| l.line),
+            "input": input,
+        });
+        let mut sidecar = sidecar.lock().unwrap();
+        writeln!(sidecar, "{}", record)?;
+    }
+    Ok(())
+}
+
+// Flushes the sidecar file and reports how many records were skipped
+pub fn finish() -> Result<(), Error> {
+    if let Some(sidecar) = CONFIG.get().and_then(|c| c.sidecar.as_ref()) {
+        sidecar.lock().unwrap().flush()?;
+    }
+    let skipped = SKIPPED.load(Ordering::Relaxed);
+    if skipped > 0 {
+        report::info(&format!("Skipped {} records with errors", skipped));
+    }
+    Ok(())
+}
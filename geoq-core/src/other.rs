@@ -0,0 +1,260 @@
+// A second dataset for commands which combine or compare their input with
+// another, such as diff, given with --other. It can be a local path, an
+// s3:// or gs:// URI or an http(s) URL, in any format geoq reads from stdin.
+// Features are read once, with an R-tree of their bounding boxes for
+// looking up those near a feature of the input. Past --other-max-memory
+// bytes, features are spilled to a temporary file and parsed again as
+// they're needed.
+#[cfg(feature = "net")]
+use crate::net;
+use crate::{
+    binary, entity, entity::Entity, error::Error, input, object_store, reader::Reader, shp,
+};
+use geo_types::Rect;
+use rstar::{RTree, RTreeObject, AABB};
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+static MAX_MEMORY: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+pub fn set_max_memory(bytes: usize) {
+    MAX_MEMORY.store(bytes, Ordering::Relaxed);
+}
+
+// Opens the dataset as it's written, whatever its format
+pub fn open(path: &str) -> Result<Box<dyn BufRead>, Error> {
+    if path == "-" {
+        return Err(Error::InvalidInput(
+            "The other dataset can't be read from stdin, which is the command's input -- give a path, or e.g. <(command) in bash".to_string(),
+        ));
+    }
+    #[cfg(feature = "net")]
+    if path.starts_with("http://") || path.starts_with("https://") {
+        let resp = net::block_on(net::send(net::client().get(path)))?;
+        if !resp.status().is_success() {
+            return Err(Error::InvalidInput(format!(
+                "Couldn't read {}: {}",
+                path,
+                resp.status()
+            )));
+        }
+        return Ok(Box::new(BufReader::new(net::Body::new(resp))));
+    }
+    object_store::open_buffered(path)
+}
+
+// Reads each entity of the dataset in order, detecting binary formats
+// (FlatGeobuf, GeoPackage, zipped shapefiles and WKB) as stdin does.
+// Local .shp paths are read as shapefiles
+pub fn for_entity<F>(path: &str, mut handler: F) -> Result<(), Error>
+where
+    F: FnMut(Entity) -> Result<(), Error>,
+{
+    // A bare .shp isn't one stream, but reads its .dbf and .shx too
+    if path.to_lowercase().ends_with(".shp") && !path.contains("://") {
+        return shp::for_feature(path, |feature| {
+            let raw = serde_json::to_string(&feature)?;
+            handler(Entity::GeoJsonFeature(raw, feature))
+        });
+    }
+    let mut f = open(path)?;
+    let lines = binary::read(&mut f)?;
+    let reader = match &lines {
+        Some(lines) => Reader::mapped(lines),
+        None => Reader::new(&mut f),
+    };
+    for e in reader {
+        handler(e?)?;
+    }
+    Ok(())
+}
+
+enum Stored {
+    Memory(Box<Entity>),
+    // The feature's text, at these offsets of the spill file
+    Spilled(usize, usize),
+}
+
+// A feature's bounding box in the index
+struct Bounds {
+    envelope: AABB<[f64; 2]>,
+    index: usize,
+}
+
+impl RTreeObject for Bounds {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
+}
+
+fn envelope(r: Rect<f64>) -> AABB<[f64; 2]> {
+    AABB::from_corners([r.min().x, r.min().y], [r.max().x, r.max().y])
+}
+
+// The spill file, mapped into memory with the mmap feature, or else read
+// from as features are needed
+#[cfg(feature = "mmap")]
+struct Spill(memmap2::Mmap);
+#[cfg(not(feature = "mmap"))]
+struct Spill(Mutex<File>);
+
+impl Spill {
+    #[cfg(feature = "mmap")]
+    fn new(file: File) -> Result<Spill, Error> {
+        Ok(Spill(unsafe { memmap2::Mmap::map(&file)? }))
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    fn new(file: File) -> Result<Spill, Error> {
+        Ok(Spill(Mutex::new(file)))
+    }
+
+    #[cfg(feature = "mmap")]
+    fn text(&self, start: usize, end: usize) -> Result<String, Error> {
+        Ok(String::from_utf8_lossy(&self.0[start..end]).into_owned())
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    fn text(&self, start: usize, end: usize) -> Result<String, Error> {
+        let mut file = self.0.lock().unwrap();
+        file.seek(SeekFrom::Start(start as u64))?;
+        let mut buf = vec![0; end - start];
+        file.read_exact(&mut buf)?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+}
+
+pub struct Other {
+    features: Vec<Stored>,
+    index: RTree<Bounds>,
+    spill: Option<Spill>,
+}
+
+impl Other {
+    pub fn read(path: &str) -> Result<Other, Error> {
+        let max_memory = MAX_MEMORY.load(Ordering::Relaxed);
+        let mut features = vec![];
+        let mut bounds = vec![];
+        let mut memory = 0;
+        let mut spill: Option<(BufWriter<File>, usize)> = None;
+        for_entity(path, |e| {
+            if !e.is_empty() {
+                bounds.push(Bounds {
+                    envelope: envelope(e.bbox()),
+                    index: features.len(),
+                });
+            }
+            let raw = e.raw();
+            memory += raw.len();
+            if memory <= max_memory {
+                features.push(Stored::Memory(Box::new(e)));
+                return Ok(());
+            }
+            let (file, len) = match spill.as_mut() {
+                Some(s) => s,
+                None => spill.get_or_insert((BufWriter::new(tempfile::tempfile()?), 0)),
+            };
+            file.write_all(raw.as_bytes())?;
+            features.push(Stored::Spilled(*len, *len + raw.len()));
+            *len += raw.len();
+            Ok(())
+        })?;
+        let spill = match spill {
+            Some((file, _)) => {
+                let file = file.into_inner().map_err(|e| e.into_error())?;
+                Some(Spill::new(file)?)
+            }
+            None => None,
+        };
+        Ok(Other {
+            features,
+            index: RTree::bulk_load(bounds),
+            spill,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    // The feature at an index, in the order they were read
+    pub fn get(&self, i: usize) -> Result<Cow<'_, Entity>, Error> {
+        match (&self.features[i], &self.spill) {
+            (Stored::Memory(e), _) => Ok(Cow::Borrowed(e)),
+            (Stored::Spilled(start, end), Some(spill)) => {
+                let raw = spill.text(*start, *end)?;
+                let mut entities = entity::from_input(input::read_line(raw)?)?;
+                Ok(Cow::Owned(entities.remove(0)))
+            }
+            (Stored::Spilled(..), None) => unreachable!("spilled feature without a spill file"),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Result<Cow<'_, Entity>, Error>> + '_ {
+        (0..self.len()).map(move |i| self.get(i))
+    }
+
+    // Indexes of the features whose bounding boxes intersect the given
+    // one, in the order they were read
+    pub fn near(&self, bbox: Rect<f64>) -> Vec<usize> {
+        let mut near: Vec<usize> = self
+            .index
+            .locate_in_envelope_intersecting(&envelope(bbox))
+            .map(|b| b.index)
+            .collect();
+        near.sort_unstable();
+        near
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{set_max_memory, Other};
+    use geo_types::{Coord, Rect};
+    use std::io::Write;
+
+    #[test]
+    fn test_read_and_spill() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "POINT (1 1)").unwrap();
+        writeln!(
+            file,
+            r#"{{"type":"FeatureCollection","features":[{{"type":"Feature","properties":{{"a":1}},"geometry":{{"type":"Point","coordinates":[5,5]}}}},{{"type":"Feature","properties":{{"a":2}},"geometry":null}}]}}"#
+        )
+        .unwrap();
+        writeln!(file, "LINESTRING (0 0, 10 10)").unwrap();
+        let path = file.path().to_str().unwrap();
+
+        // Everything after the first feature is spilled
+        set_max_memory(12);
+        let other = Other::read(path).unwrap();
+        set_max_memory(usize::MAX);
+        assert_eq!(4, other.len());
+        assert!(other.spill.is_some());
+        let raws: Vec<String> = other.iter().map(|e| e.unwrap().raw()).collect();
+        assert_eq!("POINT(1 1)", raws[0]);
+        assert!(raws[2].contains(r#""a":2"#));
+        assert!(raws[3].starts_with("LINESTRING"));
+
+        let bbox = Rect::new(Coord { x: 4.0, y: 4.0 }, Coord { x: 6.0, y: 6.0 });
+        assert_eq!(vec![1, 3], other.near(bbox));
+    }
+
+    #[test]
+    fn test_stdin_is_refused() {
+        assert!(Other::read("-").is_err());
+    }
+}
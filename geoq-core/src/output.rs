@@ -0,0 +1,468 @@
+// Output formats, for the global --format flag. Commands write GeoJSON,
+// and with another format each line of GeoJSON they write is converted on
+// its way out: to WKT, to binary WKB (geometries back to back, as geoq
+// reads them), or to CSV rows of a WKT column followed by the properties
+// of the first feature written, which are given in a header row. Other
+// output, such as measurements or cell ids, is written as it is.
+//
+// GeoJSON output can also be restyled by the global --pretty and
+// --compact flags: indented, as an RFC 8142 text sequence with each
+// object after a record separator, or with no whitespace at all. The
+// global --precision option rounds its coordinates to some decimal
+// places, before it's converted to another format.
+use crate::{
+    binary::wkb,
+    entity::{self, Entity},
+    error::Error,
+    input::Input,
+};
+use once_cell::sync::OnceCell;
+use serde::de::IgnoredAny;
+use serde_json::Value;
+use std::{
+    io::{self, Write},
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    GeoJson,
+    Wkt,
+    Wkb,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Format, Error> {
+        match s {
+            "geojson" => Ok(Format::GeoJson),
+            "wkt" => Ok(Format::Wkt),
+            "wkb" => Ok(Format::Wkb),
+            "csv" => Ok(Format::Csv),
+            _ => Err(Error::InvalidInput(format!(
+                "Unknown output format: {} -- expected geojson, wkt, wkb or csv",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    // As the command wrote it
+    Default,
+    Pretty,
+    Compact,
+}
+
+// Starts each object of a GeoJSON text sequence
+const RECORD_SEPARATOR: char = '\x1e';
+
+// Most decimal places --precision can keep; f64s have 15 to 17 digits
+pub const MAX_PRECISION: u8 = 15;
+// Coordinates are written as they are
+const FULL_PRECISION: u8 = u8::MAX;
+
+static FORMAT: AtomicU8 = AtomicU8::new(0);
+static STYLE: AtomicU8 = AtomicU8::new(0);
+static PRECISION: AtomicU8 = AtomicU8::new(FULL_PRECISION);
+// Property names of the CSV columns after wkt, once the header is written
+static COLUMNS: OnceCell<Vec<String>> = OnceCell::new();
+
+pub fn set_format(format: Format) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+pub fn format() -> Format {
+    match FORMAT.load(Ordering::Relaxed) {
+        1 => Format::Wkt,
+        2 => Format::Wkb,
+        3 => Format::Csv,
+        _ => Format::GeoJson,
+    }
+}
+
+pub fn set_style(style: Style) {
+    STYLE.store(style as u8, Ordering::Relaxed);
+}
+
+pub fn style() -> Style {
+    match STYLE.load(Ordering::Relaxed) {
+        1 => Style::Pretty,
+        2 => Style::Compact,
+        _ => Style::Default,
+    }
+}
+
+pub fn set_precision(digits: u8) {
+    PRECISION.store(digits, Ordering::Relaxed);
+}
+
+pub fn precision() -> Option<u8> {
+    match PRECISION.load(Ordering::Relaxed) {
+        FULL_PRECISION => None,
+        digits => Some(digits),
+    }
+}
+
+pub fn parse_precision(digits: &str) -> Result<u8, Error> {
+    match digits.parse::<u8>() {
+        Ok(d) if d <= MAX_PRECISION => Ok(d),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected a number of decimal places from 0 to {}: {}",
+            MAX_PRECISION, digits
+        ))),
+    }
+}
+
+// A number with at most `digits` decimal places, without trailing zeros
+fn round_number(number: &str, digits: u8) -> String {
+    let n = match number.parse::<f64>() {
+        Ok(n) => n,
+        Err(_) => return number.to_string(),
+    };
+    let mut out = format!("{:.*}", digits as usize, n);
+    if out.contains('.') {
+        out.truncate(out.trim_end_matches('0').trim_end_matches('.').len());
+    }
+    if out == "-0" {
+        out.remove(0);
+    }
+    out
+}
+
+// Rounds the numbers of "coordinates" and "bbox" members of JSON text,
+// leaving the rest of it as it was written
+fn round_coordinates(json: &str, digits: u8) -> String {
+    let mut out = String::with_capacity(json.len());
+    let mut depth = 0;
+    // Depth of the coordinates or bbox being rounded
+    let mut rounding: Option<usize> = None;
+    // The last string, and then the key of the value which follows it
+    let mut last_string = String::new();
+    let mut key: Option<String> = None;
+    let mut chars = json.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                last_string.clear();
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => {
+                            last_string.push(c);
+                            if let Some(e) = chars.next() {
+                                out.push(e);
+                                last_string.push(e);
+                            }
+                        }
+                        '"' => break,
+                        c => last_string.push(c),
+                    }
+                }
+                key = None;
+            }
+            ':' => {
+                out.push(c);
+                key = Some(std::mem::take(&mut last_string));
+            }
+            '{' | '[' => {
+                if rounding.is_none()
+                    && matches!(key.as_deref(), Some("coordinates") | Some("bbox"))
+                {
+                    rounding = Some(depth);
+                }
+                depth += 1;
+                key = None;
+                out.push(c);
+            }
+            '}' | ']' => {
+                depth -= 1;
+                if rounding == Some(depth) {
+                    rounding = None;
+                }
+                out.push(c);
+            }
+            '-' | '0'..='9' => {
+                let mut number = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !matches!(c, '0'..='9' | '.' | 'e' | 'E' | '+' | '-') {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                if rounding.is_some() {
+                    out.push_str(&round_number(&number, digits));
+                } else {
+                    out.push_str(&number);
+                }
+                key = None;
+            }
+            ',' => {
+                key = None;
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn indent(out: &mut String, depth: usize) {
+    out.push('\n');
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+// Rewrites valid JSON with no whitespace between tokens, or indented by
+// two spaces a level, keeping its members in order
+fn restyle(json: &str, pretty: bool) -> String {
+    let mut out = String::with_capacity(json.len());
+    let mut depth = 0;
+    let mut chars = json.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => (),
+                    }
+                }
+            }
+            '{' | '[' => {
+                out.push(c);
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                // Empty objects and arrays stay on one line
+                match chars.peek() {
+                    Some(&close) if close == '}' || close == ']' => {
+                        out.push(close);
+                        chars.next();
+                    }
+                    _ => {
+                        depth += 1;
+                        if pretty {
+                            indent(&mut out, depth);
+                        }
+                    }
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                if pretty {
+                    indent(&mut out, depth);
+                }
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                if pretty {
+                    indent(&mut out, depth);
+                }
+            }
+            ':' => {
+                out.push(c);
+                if pretty {
+                    out.push(' ');
+                }
+            }
+            c if c.is_whitespace() => (),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn is_json(line: &str) -> bool {
+    (line.starts_with('{') || line.starts_with('['))
+        && serde_json::from_str::<IgnoredAny>(line).is_ok()
+}
+
+fn write_geojson(out: &mut dyn Write, line: &str) -> io::Result<()> {
+    match style() {
+        Style::Pretty if is_json(line) => {
+            writeln!(out, "{}{}", RECORD_SEPARATOR, restyle(line, true))
+        }
+        Style::Compact if is_json(line) => writeln!(out, "{}", restyle(line, false)),
+        _ => writeln!(out, "{}", line),
+    }
+}
+
+// The entities of a line of GeoJSON output, or None for other output
+fn entities(line: &str) -> Option<Vec<Entity>> {
+    if !line.starts_with('{') {
+        return None;
+    }
+    entity::from_input(Input::GeoJSON(line.to_string())).ok()
+}
+
+// A CSV field, quoted if it needs to be
+pub fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => csv_field(s),
+        Some(v) => csv_field(&v.to_string()),
+    }
+}
+
+fn write_csv(out: &mut dyn Write, e: &Entity) -> io::Result<()> {
+    let props = e.geojson_properties();
+    let mut header = None;
+    let columns = COLUMNS.get_or_init(|| {
+        let columns: Vec<String> = props.keys().cloned().collect();
+        header = Some(columns.clone());
+        columns
+    });
+    if let Some(names) = header {
+        let fields: Vec<String> = names.iter().map(|n| csv_field(n)).collect();
+        write!(out, "wkt")?;
+        for f in fields {
+            write!(out, ",{}", f)?;
+        }
+        writeln!(out)?;
+    }
+    write!(out, "{}", csv_field(&e.wkt().to_string()))?;
+    for c in columns {
+        write!(out, ",{}", csv_value(props.get(c)))?;
+    }
+    writeln!(out)
+}
+
+fn write_entity(out: &mut dyn Write, e: &Entity, format: Format) -> io::Result<()> {
+    match format {
+        Format::GeoJson => write_geojson(out, &e.raw()),
+        Format::Wkt => writeln!(out, "{}", e.wkt()),
+        // Written from the GeoJSON itself, to keep any Z values
+        Format::Wkb => match e {
+            Entity::GeoJsonFeature(_, f) if f.geometry.is_some() => {
+                out.write_all(&wkb::write(f.geometry.as_ref().unwrap()))
+            }
+            Entity::GeoJsonGeometry(_, g) => out.write_all(&wkb::write(g)),
+            _ => out.write_all(&wkb::write(&e.geojson_geometry())),
+        },
+        Format::Csv => write_csv(out, e),
+    }
+}
+
+// Writes a line of a command's output in the --format
+pub fn write_line(out: &mut dyn Write, line: &str) -> io::Result<()> {
+    let rounded;
+    let line = match precision() {
+        Some(digits) if line.starts_with('{') => {
+            rounded = round_coordinates(line, digits);
+            rounded.as_str()
+        }
+        _ => line,
+    };
+    let format = format();
+    if format == Format::GeoJson {
+        return write_geojson(out, line);
+    }
+    match entities(line) {
+        Some(entities) => {
+            for e in &entities {
+                write_entity(out, e, format)?;
+            }
+            Ok(())
+        }
+        None => writeln!(out, "{}", line),
+    }
+}
+
+// Like println!, for commands which print their output directly
+pub fn print(line: &str) -> Result<(), Error> {
+    let stdout = io::stdout();
+    write_line(&mut stdout.lock(), line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        csv_field, entities, parse_precision, restyle, round_coordinates, round_number,
+        write_entity, Format,
+    };
+    use crate::binary::wkb;
+
+    fn write(line: &str, format: Format) -> Vec<u8> {
+        let mut out = vec![];
+        for e in entities(line).unwrap() {
+            write_entity(&mut out, &e, format).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_formats() {
+        let feature = r#"{"type":"Feature","properties":{"a":1},"geometry":{"type":"Point","coordinates":[1,2,3]}}"#;
+        assert_eq!(b"POINT(1 2)\n".to_vec(), write(feature, Format::Wkt));
+        let geom = wkb::read(&write(feature, Format::Wkb)).unwrap();
+        assert_eq!(geojson::Value::Point(vec![1.0, 2.0, 3.0]), geom.value);
+
+        let collection = r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[1,2]}},{"type":"Feature","properties":{},"geometry":null}]}"#;
+        assert_eq!(
+            b"POINT(1 2)\nGEOMETRYCOLLECTION EMPTY\n".to_vec(),
+            write(collection, Format::Wkt)
+        );
+        assert!(entities("12.5").is_none());
+        assert!(entities("9q5").is_none());
+    }
+
+    #[test]
+    fn test_round_coordinates() {
+        assert_eq!("1.23", round_number("1.23456", 2));
+        assert_eq!("1", round_number("1.0", 3));
+        assert_eq!("0", round_number("-0.0001", 2));
+        assert_eq!("-118", round_number("-118.24", 0));
+        assert_eq!("0.0001", round_number("1e-4", 5));
+        assert_eq!(
+            r#"{"type":"Feature","properties":{"coordinates":1.23456,"b":"[1.23456]"},"geometry":{"type":"LineString","coordinates":[[1.2346,-2],[3, 4.5]]},"bbox":[1.2346,-2,3,4.5]}"#,
+            round_coordinates(
+                r#"{"type":"Feature","properties":{"coordinates":1.23456,"b":"[1.23456]"},"geometry":{"type":"LineString","coordinates":[[1.23456,-2.0],[3, 4.5]]},"bbox":[1.23456,-2.0,3,4.5]}"#,
+                4
+            )
+        );
+        assert_eq!(Ok(0), parse_precision("0").map_err(|_| ()));
+        assert!(parse_precision("16").is_err());
+        assert!(parse_precision("-1").is_err());
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!("abc", csv_field("abc"));
+        assert_eq!("\"POINT(1 2),x\"", csv_field("POINT(1 2),x"));
+        assert_eq!("\"say \"\"hi\"\"\"", csv_field("say \"hi\""));
+    }
+
+    #[test]
+    fn test_restyle() {
+        let json = r#"{"type": "Feature", "properties": {"a b": "x, \"y\" {}", "c": []}, "geometry": null}"#;
+        assert_eq!(
+            r#"{"type":"Feature","properties":{"a b":"x, \"y\" {}","c":[]},"geometry":null}"#,
+            restyle(json, false)
+        );
+        assert_eq!(
+            "{\n  \"type\": \"Feature\",\n  \"properties\": {\n    \"a b\": \"x, \\\"y\\\" {}\",\n    \"c\": []\n  },\n  \"geometry\": null\n}",
+            restyle(json, true)
+        );
+    }
+}
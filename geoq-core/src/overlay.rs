@@ -0,0 +1,50 @@
+// Areas where the polygons of two geometries overlap, for apportioning
+// values from one set of zones to another. Geometries without polygons
+// overlap nothing.
+use crate::{area, buffer};
+use geo::algorithm::bool_ops::BooleanOps;
+use geo_types::{Geometry, MultiPolygon};
+
+// Geodesic area of the overlap, in square meters
+pub fn overlap_area(a: &Geometry<f64>, b: &Geometry<f64>) -> f64 {
+    let a = MultiPolygon(buffer::polygons(a));
+    let b = MultiPolygon(buffer::polygons(b));
+    if a.0.is_empty() || b.0.is_empty() {
+        return 0.0;
+    }
+    area::area(&Geometry::MultiPolygon(a.intersection(&b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::overlap_area;
+    use crate::area::area;
+    use geo_types::{Geometry, Point, Polygon};
+
+    fn square(x: f64, y: f64, size: f64) -> Geometry<f64> {
+        Geometry::Polygon(Polygon::new(
+            vec![
+                [x, y],
+                [x + size, y],
+                [x + size, y + size],
+                [x, y + size],
+                [x, y],
+            ]
+            .into(),
+            vec![],
+        ))
+    }
+
+    #[test]
+    fn test_overlap_area() {
+        let a = square(0.0, 0.0, 0.02);
+        let b = square(0.01, 0.0, 0.02);
+        let half = overlap_area(&a, &b) / area(&a);
+        assert!((half - 0.5).abs() < 1e-6, "{}", half);
+        assert_eq!(0.0, overlap_area(&a, &square(1.0, 1.0, 0.01)));
+        assert_eq!(
+            0.0,
+            overlap_area(&a, &Geometry::Point(Point::new(0.01, 0.01)))
+        );
+    }
+}
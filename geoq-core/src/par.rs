@@ -0,0 +1,999 @@
+//! Handling the entities of stdin on a pool of threads, with output kept
+//! in input order.
+use crate::{
+    binary,
+    entity::{self, Entity},
+    error::Error,
+    input, on_error, output, progress, reader,
+};
+use crossbeam_deque::{Injector, Stealer, Worker};
+use num_cpus;
+use std::io;
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    iter,
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{channel, Receiver, RecvError, RecvTimeoutError, Sender, TryRecvError},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// Lines handed to a worker at a time, by default.
+pub const DEFAULT_BATCH_SIZE: usize = 256;
+/// Lines read ahead of the output, at most, by default.
+pub const DEFAULT_BUFFER_LINES: usize = 5000;
+/// Handlers given up on by --timeout-per-entity can't be stopped, only left
+/// running. Once this many are, the run stops rather than pile up more.
+pub const MAX_STUCK_HANDLERS: usize = 32;
+/// Bytes of lines read ahead of the output, at most, by default.
+pub const DEFAULT_MAX_BUFFERED_BYTES: usize = 64 << 20;
+
+/// How input is handled in parallel. The CLI builds these from its global
+/// flags (--jobs, --batch-size and so on); library callers can start from
+/// [`ParOptions::new`] and its builder methods.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ParOptions {
+    /// Worker threads; 0 means one per CPU
+    pub jobs: usize,
+    pub batch_size: usize,
+    /// Lines read but not yet printed are kept within both limits, though
+    /// a single batch over them is always let through
+    pub buffer_lines: usize,
+    pub max_buffered_bytes: usize,
+    /// Print outputs as they're ready rather than in input order
+    pub unordered: bool,
+    /// Give up on an entity whose handler runs longer than this
+    pub entity_timeout: Option<Duration>,
+}
+
+impl Default for ParOptions {
+    fn default() -> ParOptions {
+        ParOptions {
+            jobs: 0,
+            batch_size: DEFAULT_BATCH_SIZE,
+            buffer_lines: DEFAULT_BUFFER_LINES,
+            max_buffered_bytes: DEFAULT_MAX_BUFFERED_BYTES,
+            unordered: false,
+            entity_timeout: None,
+        }
+    }
+}
+
+impl ParOptions {
+    /// The defaults: a worker per CPU, output in input order, and no
+    /// timeout. Options can be changed with the `with_` methods, or by
+    /// setting their fields.
+    pub fn new() -> ParOptions {
+        ParOptions::default()
+    }
+
+    /// Runs this many worker threads, or one per CPU for 0.
+    pub fn with_jobs(mut self, jobs: usize) -> ParOptions {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Hands lines to the workers in batches of this many.
+    pub fn with_batch_size(mut self, batch_size: usize) -> ParOptions {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Keeps at most this many lines read but not yet written.
+    pub fn with_buffer_lines(mut self, buffer_lines: usize) -> ParOptions {
+        self.buffer_lines = buffer_lines;
+        self
+    }
+
+    /// Keeps at most this many bytes of lines read but not yet written.
+    pub fn with_max_buffered_bytes(mut self, max_buffered_bytes: usize) -> ParOptions {
+        self.max_buffered_bytes = max_buffered_bytes;
+        self
+    }
+
+    /// Writes outputs as they're ready, rather than in input order.
+    pub fn with_unordered(mut self, unordered: bool) -> ParOptions {
+        self.unordered = unordered;
+        self
+    }
+
+    /// Gives up on an entity whose handler runs longer than this.
+    pub fn with_entity_timeout(mut self, timeout: Option<Duration>) -> ParOptions {
+        self.entity_timeout = timeout;
+        self
+    }
+
+    fn workers(&self) -> usize {
+        match self.jobs {
+            0 => num_cpus::get(),
+            n => n,
+        }
+    }
+}
+
+/// Parses a count for --jobs or --batch-size, described by `what`
+pub fn parse_count(what: &str, value: &str) -> Result<usize, Error> {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected a positive number of {}: {}",
+            what, value
+        ))),
+    }
+}
+
+/// Parses a number of bytes, with an optional K, M or G suffix
+pub fn parse_bytes(value: &str) -> Result<usize, Error> {
+    let (digits, scale) = match value.char_indices().last() {
+        Some((i, 'K')) | Some((i, 'k')) => (&value[..i], 1 << 10),
+        Some((i, 'M')) | Some((i, 'm')) => (&value[..i], 1 << 20),
+        Some((i, 'G')) | Some((i, 'g')) => (&value[..i], 1 << 30),
+        _ => (value, 1),
+    };
+    match digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(scale))
+    {
+        Some(n) if n > 0 => Ok(n),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected a positive number of bytes, like 4096 or 64M: {}",
+            value
+        ))),
+    }
+}
+
+/// Parses a duration like 30s, 500ms or 2m; a bare number is seconds
+pub fn parse_duration(value: &str) -> Result<Duration, Error> {
+    let (digits, unit_ms) = if let Some(d) = value.strip_suffix("ms") {
+        (d, 1)
+    } else if let Some(d) = value.strip_suffix('s') {
+        (d, 1000)
+    } else if let Some(d) = value.strip_suffix('m') {
+        (d, 60 * 1000)
+    } else {
+        (value, 1000)
+    };
+    match digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit_ms))
+    {
+        Some(ms) if ms > 0 => Ok(Duration::from_millis(ms)),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected a positive duration, like 30s or 500ms: {}",
+            value
+        ))),
+    }
+}
+
+// A line's number starting at 1, the byte offset it starts at, and where
+// its text is in its batch
+type Line = (usize, u64, Range<usize>);
+
+// Lines read together, whose text shares one buffer rather than each
+// line having its own
+struct Batch {
+    text: String,
+    lines: Vec<Line>,
+}
+
+// How much input a batch holds, for keeping buffered input in bounds
+#[derive(Clone, Copy, Default)]
+struct Size {
+    lines: usize,
+    bytes: usize,
+}
+
+impl Size {
+    fn of(batch: &Batch) -> Size {
+        Size {
+            lines: batch.lines.len(),
+            bytes: batch.text.len(),
+        }
+    }
+
+    fn fits(&self, other: Size, max: Size) -> bool {
+        self.lines + other.lines <= max.lines && self.bytes + other.bytes <= max.bytes
+    }
+}
+
+// Lines are handed out in batches to cut down on channel traffic. Each
+// batch has a sequence number, starting at 1, for putting outputs back
+// in input order.
+struct WorkerInput(usize, Size, Batch);
+
+/// What handlers give the printer: lines of text, which are written with
+/// a newline each, or raw bytes for binary formats, written as they are
+pub trait Output: Send + 'static {
+    fn write_to(&self, out: &mut dyn Write) -> io::Result<()>;
+}
+
+// Lines of GeoJSON are written in the --format
+impl Output for String {
+    fn write_to(&self, out: &mut dyn Write) -> io::Result<()> {
+        output::write_line(out, self)
+    }
+}
+
+impl Output for Vec<u8> {
+    fn write_to(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self)
+    }
+}
+
+// A batch's outputs, up to the error which stopped it if any
+type BatchOutput<O> = (Vec<O>, Option<Error>);
+
+// A batch's output, or the panic from handling it
+struct WorkerOutput<O>(usize, Size, thread::Result<BatchOutput<O>>);
+
+// Holds outputs which arrive ahead of their turn, releasing them in
+// sequence order starting from 1
+struct Reorder<T> {
+    next: usize,
+    pending: BTreeMap<usize, T>,
+}
+
+impl<T> Reorder<T> {
+    fn new() -> Reorder<T> {
+        Reorder {
+            next: 1,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    fn push(&mut self, seq: usize, item: T) {
+        self.pending.insert(seq, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let item = self.pending.remove(&self.next)?;
+        self.next += 1;
+        Some(item)
+    }
+}
+
+/// Input which can tell whether more is buffered, so batches can be cut
+/// short instead of waiting on a slow producer
+pub trait Input: BufRead {
+    fn buffered(&self) -> bool;
+    /// Appends the next line to buf, giving the number of bytes it took up
+    fn next_line(&mut self, buf: &mut String) -> Option<usize>;
+}
+
+// In-memory input, including mapped files, is split into lines in place
+impl Input for &[u8] {
+    fn buffered(&self) -> bool {
+        !self.is_empty()
+    }
+
+    fn next_line(&mut self, buf: &mut String) -> Option<usize> {
+        reader::split_line(self).map(|(len, line)| {
+            buf.push_str(line);
+            len
+        })
+    }
+}
+
+impl<R: Read> Input for BufReader<R> {
+    fn buffered(&self) -> bool {
+        !self.buffer().is_empty()
+    }
+
+    fn next_line(&mut self, buf: &mut String) -> Option<usize> {
+        reader::read_line_into(self, buf)
+    }
+}
+
+/// Lines of an [`Input`] along with the byte offsets they start at.
+pub struct LineReader<'a> {
+    reader: &'a mut dyn Input,
+    offset: u64,
+}
+
+impl<'a> LineReader<'a> {
+    /// Reads lines from the start of the input.
+    pub fn new(reader: &'a mut dyn Input) -> LineReader<'a> {
+        LineReader { reader, offset: 0 }
+    }
+
+    /// Whether more input is buffered, so reading won't wait.
+    pub fn buffered(&self) -> bool {
+        self.reader.buffered()
+    }
+
+    /// Appends the next line to buf, giving the offset it starts at.
+    pub fn read_into(&mut self, buf: &mut String) -> Option<u64> {
+        let len = self.reader.next_line(buf)?;
+        let offset = self.offset;
+        self.offset += len as u64;
+        Some(offset)
+    }
+}
+
+// Lines along with the byte offsets they start at
+impl<'a> Iterator for LineReader<'a> {
+    type Item = (u64, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        let offset = self.read_into(&mut line)?;
+        Some((offset, line))
+    }
+}
+
+fn handle_line<O, F>(n: usize, offset: u64, line: &str, handler: &mut F) -> Result<Vec<O>, Error>
+where
+    F: FnMut(Entity) -> Result<Vec<O>, Error>,
+{
+    let skip = |e: Error| {
+        let e = e.at(n, offset, line);
+        match e.cause() {
+            // Timeouts are skipped whatever --on-error says, until too
+            // many handlers are stuck to carry on
+            Error::EntityTimeout(_) => on_error::skip(line, e),
+            Error::TooManyTimeouts(_) => Err(e),
+            _ => on_error::handle(line, e),
+        }
+        .map(|_| vec![])
+    };
+    let entities = match input::read_line(line.to_string()).and_then(entity::from_input) {
+        Ok(entities) => entities,
+        Err(e) => return skip(e),
+    };
+    progress::entities(entities.len());
+    let mut results = Vec::new();
+    for e in entities {
+        results.extend(handler(e).or_else(skip)?);
+    }
+    Ok(results)
+}
+
+fn handle_batch<O, F>(batch: Batch, handler: &mut F) -> BatchOutput<O>
+where
+    F: FnMut(Entity) -> Result<Vec<O>, Error>,
+{
+    let mut results = Vec::new();
+    for (n, offset, range) in batch.lines {
+        match handle_line(n, offset, &batch.text[range], handler) {
+            Ok(lines) => results.extend(lines),
+            Err(e) => return (results, Some(e)),
+        }
+    }
+    (results, None)
+}
+
+/// Handles stdin's entities in parallel, writing the outputs to stdout in
+/// input order (unless `opts.unordered`). Errors from the handler are dealt
+/// with as --on-error says.
+pub fn for_stdin_entity<O: Output, F>(opts: &ParOptions, handler: F) -> Result<(), Error>
+where
+    F: Send + Sync + 'static + Fn(Entity) -> Result<Vec<O>, Error>,
+{
+    let out = BufWriter::with_capacity(reader::OUTPUT_BUF_SIZE, io::stdout());
+    for_stdin_entity_to(opts, out, handler).map(|_| ())
+}
+
+/// Handles stdin's entities in parallel, writing the outputs to the sink.
+/// Gives the sink back at the end.
+pub fn for_stdin_entity_to<O, W, F>(opts: &ParOptions, sink: W, handler: F) -> Result<W, Error>
+where
+    O: Output,
+    W: Write + Send + 'static,
+    F: Send + Sync + 'static + Fn(Entity) -> Result<Vec<O>, Error>,
+{
+    if let Some(map) = reader::map_stdin() {
+        return match binary::read(&mut &map[..])? {
+            Some(lines) => for_entity_par_to(opts, &mut &lines[..], sink, handler),
+            None => for_entity_par_to(opts, &mut &map[..], sink, handler),
+        };
+    }
+    let mut stdin_reader = BufReader::with_capacity(reader::OUTPUT_BUF_SIZE, io::stdin().lock());
+    match binary::read(&mut stdin_reader)? {
+        Some(lines) => for_entity_par_to(opts, &mut &lines[..], sink, handler),
+        None => for_entity_par_to(opts, &mut stdin_reader, sink, handler),
+    }
+}
+
+// Progress shared between the reader, the workers and the printer
+struct State {
+    // Input read but not yet printed, which the reader keeps under the
+    // buffer limits
+    buffered: Size,
+    // Set once all input is read
+    done: bool,
+    // Set once the printer stops, at the end or at the first error
+    stopped: bool,
+}
+
+struct Shared {
+    queue: Injector<WorkerInput>,
+    stealers: Vec<Stealer<WorkerInput>>,
+    state: Mutex<State>,
+    // Signalled when batches are queued, for idle workers
+    work: Condvar,
+    // Signalled when batches are printed, for the reader
+    space: Condvar,
+}
+
+impl Shared {
+    // Work from the worker's own deque, then the shared queue, then
+    // other workers' deques
+    fn find_task(&self, local: &Worker<WorkerInput>) -> Option<WorkerInput> {
+        local.pop().or_else(|| {
+            iter::repeat_with(|| {
+                self.queue
+                    .steal_batch_and_pop(local)
+                    .or_else(|| self.stealers.iter().map(|s| s.steal()).collect())
+            })
+            .find(|s| !s.is_retry())
+            .and_then(|s| s.success())
+        })
+    }
+
+    fn idle(&self) -> bool {
+        self.queue.is_empty() && self.stealers.iter().all(|s| s.is_empty())
+    }
+
+    fn finish(&self, f: impl FnOnce(&mut State)) {
+        f(&mut self.state.lock().unwrap());
+        self.work.notify_all();
+        self.space.notify_all();
+    }
+}
+
+type Call<O> = (Entity, Sender<thread::Result<Result<Vec<O>, Error>>>);
+
+// Runs a worker's handler calls on a helper thread, so one which runs
+// over the timeout can be given up on. The helper is left to finish it
+// in the background, and a new one takes its place. Helpers still running
+// after being given up on are counted across all workers, and past the
+// limit a timeout stops the run.
+struct Watchdog<O, F> {
+    timeout: Duration,
+    handler: Arc<F>,
+    helper: Option<Helper<O>>,
+    stuck: Arc<AtomicUsize>,
+    max_stuck: usize,
+}
+
+struct Helper<O> {
+    calls: Sender<Call<O>>,
+    // Set when given up on, under the lock so the helper can't finish
+    // between being marked and being counted
+    abandoned: Arc<Mutex<bool>>,
+}
+
+impl<O, F> Watchdog<O, F>
+where
+    O: Send + 'static,
+    F: Send + Sync + 'static + Fn(Entity) -> Result<Vec<O>, Error>,
+{
+    fn new(
+        timeout: Duration,
+        handler: Arc<F>,
+        stuck: Arc<AtomicUsize>,
+        max_stuck: usize,
+    ) -> Watchdog<O, F> {
+        Watchdog {
+            timeout,
+            handler,
+            helper: None,
+            stuck,
+            max_stuck,
+        }
+    }
+
+    fn spawn(&self) -> Helper<O> {
+        let (sender, receiver) = channel::<Call<O>>();
+        let handler = self.handler.clone();
+        let abandoned = Arc::new(Mutex::new(false));
+        let (flag, stuck) = (abandoned.clone(), self.stuck.clone());
+        thread::spawn(move || {
+            for (entity, result) in receiver {
+                let r = panic::catch_unwind(AssertUnwindSafe(|| handler(entity)));
+                if result.send(r).is_err() {
+                    break;
+                }
+            }
+            if *flag.lock().unwrap() {
+                stuck.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+        Helper {
+            calls: sender,
+            abandoned,
+        }
+    }
+
+    fn call(&mut self, entity: Entity) -> Result<Vec<O>, Error> {
+        let helper = match self.helper.take() {
+            Some(helper) => helper,
+            None => self.spawn(),
+        };
+        let (sender, receiver) = channel();
+        helper
+            .calls
+            .send((entity, sender))
+            .map_err(|_| Error::ProgramError("Entity handler thread stopped".to_string()))?;
+        match receiver.recv_timeout(self.timeout) {
+            Ok(result) => {
+                self.helper = Some(helper);
+                result.unwrap_or_else(|payload| panic::resume_unwind(payload))
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let stuck = {
+                    let mut abandoned = helper.abandoned.lock().unwrap();
+                    *abandoned = true;
+                    self.stuck.fetch_add(1, Ordering::SeqCst) + 1
+                };
+                if stuck >= self.max_stuck {
+                    Err(Error::TooManyTimeouts(format!(
+                        "{} entity handlers are still running past the {:?} timeout",
+                        stuck, self.timeout
+                    )))
+                } else {
+                    Err(Error::EntityTimeout(self.timeout))
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(Error::ProgramError(
+                "Entity handler thread stopped".to_string(),
+            )),
+        }
+    }
+}
+
+fn work<O, F>(
+    shared: &Shared,
+    local: Worker<WorkerInput>,
+    output: Sender<WorkerOutput<O>>,
+    handler: Arc<F>,
+    timeout: Option<Duration>,
+    stuck: Arc<AtomicUsize>,
+) where
+    O: Send + 'static,
+    F: Send + Sync + 'static + Fn(Entity) -> Result<Vec<O>, Error>,
+{
+    let mut watchdog =
+        timeout.map(|t| Watchdog::new(t, handler.clone(), stuck, MAX_STUCK_HANDLERS));
+    let mut call = |e: Entity| match &mut watchdog {
+        Some(w) => w.call(e),
+        None => handler(e),
+    };
+    loop {
+        if let Some(WorkerInput(seq, size, batch)) = shared.find_task(&local) {
+            // Panics are handed to the printer, to be raised in their turn
+            let result = panic::catch_unwind(AssertUnwindSafe(|| handle_batch(batch, &mut call)));
+            if output.send(WorkerOutput(seq, size, result)).is_err() {
+                return;
+            }
+            continue;
+        }
+        let state = shared.state.lock().unwrap();
+        if state.stopped || (state.done && shared.idle()) {
+            return;
+        }
+        if shared.idle() {
+            drop(shared.work.wait(state).unwrap());
+        }
+    }
+}
+
+fn print_batch<O: Output>(
+    shared: &Shared,
+    out: &mut dyn Write,
+    (size, result): (Size, thread::Result<BatchOutput<O>>),
+) -> Result<(), Error> {
+    let (outputs, err) = result.unwrap_or_else(|payload| panic::resume_unwind(payload));
+    for o in outputs {
+        o.write_to(out)?;
+    }
+    let mut state = shared.state.lock().unwrap();
+    state.buffered.lines -= size.lines;
+    state.buffered.bytes -= size.bytes;
+    drop(state);
+    shared.space.notify_one();
+    err.map_or(Ok(()), Err)
+}
+
+// Output is flushed whenever the printer would wait for more
+fn print_outputs<O: Output>(
+    shared: &Shared,
+    outputs: Receiver<WorkerOutput<O>>,
+    out: &mut dyn Write,
+    unordered: bool,
+) -> Result<(), Error> {
+    let mut reorder = Reorder::new();
+    loop {
+        let WorkerOutput(seq, size, result) = match outputs.try_recv() {
+            Ok(output) => output,
+            Err(TryRecvError::Empty) => {
+                out.flush()?;
+                match outputs.recv() {
+                    Ok(output) => output,
+                    Err(RecvError) => return Ok(()),
+                }
+            }
+            Err(TryRecvError::Disconnected) => return Ok(()),
+        };
+        if unordered {
+            print_batch(shared, out, (size, result))?;
+            continue;
+        }
+        reorder.push(seq, (size, result));
+        while let Some(result) = reorder.pop() {
+            print_batch(shared, out, result)?;
+        }
+    }
+}
+
+// Prints outputs until the workers are done or one fails, then flushes
+// whatever was printed
+fn print<O: Output>(
+    shared: &Shared,
+    outputs: Receiver<WorkerOutput<O>>,
+    out: &mut dyn Write,
+    unordered: bool,
+) -> Result<(), Error> {
+    let res = print_outputs(shared, outputs, out, unordered);
+    out.flush()?;
+    res
+}
+
+/// Handles each line's entities in parallel, writing the outputs to stdout
+/// in input order (unless `opts.unordered`).
+pub fn for_entity_par<O: Output, F>(
+    opts: &ParOptions,
+    input: &mut dyn Input,
+    handler: F,
+) -> Result<(), Error>
+where
+    F: Send + Sync + 'static + Fn(Entity) -> Result<Vec<O>, Error>,
+{
+    let out = BufWriter::with_capacity(reader::OUTPUT_BUF_SIZE, io::stdout());
+    for_entity_par_to(opts, input, out, handler).map(|_| ())
+}
+
+/// Handles each line's entities in parallel, writing the outputs to the
+/// sink in input order (unless --unordered). Gives the sink back at the end.
+pub fn for_entity_par_to<O, W, F>(
+    opts: &ParOptions,
+    input: &mut dyn Input,
+    sink: W,
+    handler: F,
+) -> Result<W, Error>
+where
+    O: Output,
+    W: Write + Send + 'static,
+    F: Send + Sync + 'static + Fn(Entity) -> Result<Vec<O>, Error>,
+{
+    let num_workers = opts.workers();
+    let batch_size = opts.batch_size;
+    let max_buffered = Size {
+        lines: opts.buffer_lines,
+        bytes: opts.max_buffered_bytes,
+    };
+    let locals: Vec<Worker<WorkerInput>> = (0..num_workers).map(|_| Worker::new_fifo()).collect();
+    let shared = Arc::new(Shared {
+        queue: Injector::new(),
+        stealers: locals.iter().map(|w| w.stealer()).collect(),
+        state: Mutex::new(State {
+            buffered: Size::default(),
+            done: false,
+            stopped: false,
+        }),
+        work: Condvar::new(),
+        space: Condvar::new(),
+    });
+    let (output_sender, output_receiver) = channel();
+    let handler = Arc::new(handler);
+    let stuck = Arc::new(AtomicUsize::new(0));
+
+    let workers: Vec<JoinHandle<()>> = locals
+        .into_iter()
+        .map(|local| {
+            let shared = shared.clone();
+            let output = output_sender.clone();
+            let handler = handler.clone();
+            let (timeout, stuck) = (opts.entity_timeout, stuck.clone());
+            thread::spawn(move || work(&shared, local, output, handler, timeout, stuck))
+        })
+        .collect();
+    drop(output_sender);
+
+    // The printer stops at the first error or panic, which then stops the
+    // reader and workers too
+    let printer_shared = shared.clone();
+    let unordered = opts.unordered;
+    let printer = thread::spawn(move || {
+        let mut sink = sink;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            print(&printer_shared, output_receiver, &mut sink, unordered)
+        }));
+        printer_shared.finish(|s| s.stopped = true);
+        result.map(|r| r.map(|_| sink))
+    });
+
+    let mut lines = LineReader::new(input);
+    let mut n = 0;
+    for seq in 1.. {
+        // A batch ends early when waiting for more input, so lines from a
+        // slow producer aren't held back
+        let mut batch = Batch {
+            text: String::new(),
+            lines: Vec::with_capacity(batch_size),
+        };
+        while batch.lines.len() < batch_size {
+            let start = batch.text.len();
+            match lines.read_into(&mut batch.text) {
+                Some(offset) => {
+                    n += 1;
+                    batch.lines.push((n, offset, start..batch.text.len()));
+                }
+                None => break,
+            }
+            if !lines.buffered() {
+                break;
+            }
+        }
+        if batch.lines.is_empty() {
+            break;
+        }
+        let size = Size::of(&batch);
+        let mut state = shared.state.lock().unwrap();
+        while state.buffered.lines > 0 && !state.buffered.fits(size, max_buffered) && !state.stopped
+        {
+            state = shared.space.wait(state).unwrap();
+        }
+        if state.stopped {
+            break;
+        }
+        state.buffered.lines += size.lines;
+        state.buffered.bytes += size.bytes;
+        shared.queue.push(WorkerInput(seq, size, batch));
+        drop(state);
+        shared.work.notify_one();
+    }
+    shared.finish(|s| s.done = true);
+
+    for w in workers {
+        w.join().expect("Worker panics are caught");
+    }
+    printer
+        .join()
+        .expect("Printer panics are caught")
+        .unwrap_or_else(|payload| panic::resume_unwind(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::par::{
+        for_entity_par, for_entity_par_to, parse_bytes, parse_count, parse_duration, ParOptions,
+        Reorder, Size, Watchdog, DEFAULT_BATCH_SIZE,
+    };
+    use crate::{entity, input};
+    use std::{
+        panic,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(4, parse_count("jobs", "4").unwrap());
+        assert!(parse_count("jobs", "0").is_err());
+        assert!(parse_count("jobs", "-1").is_err());
+        assert!(parse_count("jobs", "lots").is_err());
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(4096, parse_bytes("4096").unwrap());
+        assert_eq!(4096, parse_bytes("4K").unwrap());
+        assert_eq!(64 << 20, parse_bytes("64M").unwrap());
+        assert_eq!(2 << 30, parse_bytes("2g").unwrap());
+        assert!(parse_bytes("0").is_err());
+        assert!(parse_bytes("M").is_err());
+        assert!(parse_bytes("12T").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Duration::from_secs(30), parse_duration("30s").unwrap());
+        assert_eq!(Duration::from_secs(30), parse_duration("30").unwrap());
+        assert_eq!(Duration::from_millis(500), parse_duration("500ms").unwrap());
+        assert_eq!(Duration::from_secs(120), parse_duration("2m").unwrap());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("1h").is_err());
+    }
+
+    #[test]
+    fn test_watchdog() {
+        let stuck = Arc::new(AtomicUsize::new(0));
+        let mut watchdog = Watchdog::new(
+            Duration::from_millis(100),
+            Arc::new(|e: entity::Entity| {
+                if e.raw() == "1,1" {
+                    thread::sleep(Duration::from_millis(500));
+                }
+                Ok(vec![e.raw()])
+            }),
+            stuck.clone(),
+            2,
+        );
+        let mut call = |line: &str| {
+            let e = input::read_line(line.to_string())
+                .and_then(entity::from_input)
+                .unwrap()
+                .remove(0);
+            watchdog.call(e)
+        };
+        assert_eq!(vec!["0,0".to_string()], call("0,0").unwrap());
+        match call("1,1") {
+            Err(Error::EntityTimeout(t)) => assert_eq!(Duration::from_millis(100), t),
+            _ => panic!("Expected a timeout"),
+        }
+        // A new helper takes over from the one still stuck on 1,1
+        assert_eq!(vec!["2,2".to_string()], call("2,2").unwrap());
+        assert_eq!(1, stuck.load(Ordering::SeqCst));
+        // A second stuck helper reaches the limit
+        match call("1,1") {
+            Err(Error::TooManyTimeouts(_)) => {}
+            _ => panic!("Expected too many timeouts"),
+        }
+        // Helpers come off the count once they finish
+        thread::sleep(Duration::from_secs(1));
+        assert_eq!(0, stuck.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_size_fits() {
+        let max = Size {
+            lines: 10,
+            bytes: 100,
+        };
+        let half = Size {
+            lines: 5,
+            bytes: 50,
+        };
+        assert!(half.fits(half, max));
+        assert!(!half.fits(Size { lines: 6, bytes: 1 }, max));
+        assert!(!half.fits(
+            Size {
+                lines: 1,
+                bytes: 51
+            },
+            max
+        ));
+    }
+
+    #[test]
+    fn test_reorder() {
+        let mut reorder = Reorder::new();
+        reorder.push(2, "b");
+        reorder.push(3, "c");
+        assert_eq!(None, reorder.pop());
+        reorder.push(1, "a");
+        assert_eq!(Some("a"), reorder.pop());
+        assert_eq!(Some("b"), reorder.pop());
+        assert_eq!(Some("c"), reorder.pop());
+        assert_eq!(None, reorder.pop());
+        reorder.push(5, "e");
+        reorder.push(4, "d");
+        assert_eq!(Some("d"), reorder.pop());
+        assert_eq!(Some("e"), reorder.pop());
+    }
+
+    #[test]
+    fn test_par_entities() {
+        // Each input can produce many outputs, so workers send back
+        // Result(Vec<String>, Error) tagged with the input's line number,
+        // and the printer prints all of a line's outputs in its turn
+        let mut input = r#"34.2277,-118.2623
+{"type":"Polygon","coordinates":[[[-117.87231445312499,34.77997173591062],[-117.69653320312499,34.77997173591062],[-117.69653320312499,34.90170042871546],[-117.87231445312499,34.90170042871546],[-117.87231445312499,34.77997173591062]]]}
+{"type":"Polygon","coordinates":[[[-118.27880859375001,34.522398580663314],[-117.89154052734375,34.522398580663314],[-117.89154052734375,34.649025753526985],[-118.27880859375001,34.649025753526985],[-118.27880859375001,34.522398580663314]]]}
+"#.as_bytes();
+
+        // let mut input = "9q5\n9q4".as_bytes();
+        let res = for_entity_par(&ParOptions::default(), &mut input, move |entity| {
+            Ok(vec![format!("handling entity {}", entity).to_owned()])
+        });
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_par_error() {
+        let lines: Vec<String> = (0..20000)
+            .map(|i| format!("{},{}", i % 80, i % 170))
+            .collect();
+        let mut input = lines.join("\n").into_bytes();
+        input.extend(b"\nnot an entity\n");
+        // Errors are found whichever batch they land in
+        for size in &[1, 7, DEFAULT_BATCH_SIZE] {
+            let opts = ParOptions {
+                batch_size: *size,
+                ..ParOptions::default()
+            };
+            let res = for_entity_par(&opts, &mut &input[..], |_| Ok(Vec::<String>::new()));
+            assert_eq!(20001, res.unwrap_err().location().unwrap().line);
+        }
+
+        let opts = ParOptions::default();
+        let res = for_entity_par(&opts, &mut &input[..], |e| match e.raw().as_str() {
+            "5,5" => Err(Error::PointRequired),
+            _ => Ok(Vec::<String>::new()),
+        });
+        let err = res.unwrap_err();
+        assert_eq!(6, err.location().unwrap().line);
+        assert_eq!("PointRequired", err.kind());
+    }
+    #[test]
+    fn test_par_output() {
+        let lines: Vec<String> = (0..3000)
+            .map(|i| format!("{},{}", i % 80, i % 170))
+            .collect();
+        let input = lines.join("\n").into_bytes();
+
+        // Text outputs come back as lines, in input order
+        let opts = ParOptions::default();
+        let out =
+            for_entity_par_to(&opts, &mut &input[..], Vec::new(), |e| Ok(vec![e.raw()])).unwrap();
+        assert_eq!(lines.join("\n") + "\n", String::from_utf8(out).unwrap());
+
+        // Byte outputs are written as they are
+        let out = for_entity_par_to(&opts, &mut &input[..], Vec::new(), |e| {
+            Ok(vec![e.raw().into_bytes(), vec![0]])
+        })
+        .unwrap();
+        let expected: Vec<u8> = lines
+            .iter()
+            .flat_map(|l| l.bytes().chain(std::iter::once(0)))
+            .collect();
+        assert_eq!(expected, out);
+
+        // Batches over the buffer limits are let through one at a time
+        let opts = ParOptions {
+            buffer_lines: 1,
+            max_buffered_bytes: 1,
+            ..ParOptions::default()
+        };
+        let out =
+            for_entity_par_to(&opts, &mut &input[..], Vec::new(), |e| Ok(vec![e.raw()])).unwrap();
+        assert_eq!(lines.join("\n") + "\n", String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn test_par_panic() {
+        let lines: Vec<String> = (0..2000)
+            .map(|i| format!("{},{}", i % 80, i % 170))
+            .collect();
+        let input = lines.join("\n").into_bytes();
+        let res = panic::catch_unwind(|| {
+            for_entity_par(&ParOptions::default(), &mut &input[..], |e| {
+                match e.raw().as_str() {
+                    "5,5" => panic!("handler failed on 5,5"),
+                    _ => Ok(Vec::<String>::new()),
+                }
+            })
+        });
+        let payload = res.unwrap_err();
+        assert_eq!(
+            Some(&"handler failed on 5,5"),
+            payload.downcast_ref::<&str>()
+        );
+    }
+}
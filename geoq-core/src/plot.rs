@@ -0,0 +1,289 @@
+// Terminal map rendering. Geometries are drawn as outlines onto a grid of
+// dots in an equirectangular projection, then printed using braille
+// characters (2x4 dots per character) or plain ASCII (1 dot per character).
+use geo_types::{Coord, Geometry, LineString, Rect};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Dot {
+    Empty,
+    Graticule,
+    Data,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Style {
+    Braille,
+    Ascii,
+}
+
+impl Style {
+    // Dots per character cell, as (columns, rows)
+    fn cell(self) -> (usize, usize) {
+        match self {
+            Style::Braille => (2, 4),
+            Style::Ascii => (1, 2),
+        }
+    }
+}
+
+pub struct Canvas {
+    style: Style,
+    width: usize,
+    height: usize,
+    dots: Vec<Dot>,
+    extent: Rect<f64>,
+    // Horizontal scale factor for the middle latitude, so shapes
+    // away from the equator aren't stretched east-west
+    x_scale: f64,
+}
+
+// Padded extent, expanded around single points and flat lines
+// so that there's something to fit to
+fn padded(extent: Rect<f64>) -> Rect<f64> {
+    let min_span = 0.01;
+    let pad_x = (extent.width() * 0.02).max(min_span);
+    let pad_y = (extent.height() * 0.02).max(min_span);
+    Rect::new(
+        Coord {
+            x: extent.min().x - pad_x,
+            y: extent.min().y - pad_y,
+        },
+        Coord {
+            x: extent.max().x + pad_x,
+            y: extent.max().y + pad_y,
+        },
+    )
+}
+
+impl Canvas {
+    // A canvas at most max_cols x max_rows characters, fitted to the extent
+    pub fn new(extent: Rect<f64>, max_cols: usize, max_rows: usize, style: Style) -> Canvas {
+        let extent = padded(extent);
+        let x_scale = extent.center().y.to_radians().cos().max(0.1);
+        let (cell_w, cell_h) = style.cell();
+        let aspect = (extent.width() * x_scale) / extent.height();
+        // Character cells are about twice as tall as they are wide
+        let mut cols = max_cols.max(1);
+        let mut rows = ((cols as f64 / aspect / 2.0).ceil() as usize).max(1);
+        if rows > max_rows {
+            rows = max_rows.max(1);
+            cols = ((rows as f64 * aspect * 2.0).ceil() as usize).clamp(1, max_cols.max(1));
+        }
+        let (width, height) = (cols * cell_w, rows * cell_h);
+        Canvas {
+            style,
+            width,
+            height,
+            dots: vec![Dot::Empty; width * height],
+            extent,
+            x_scale,
+        }
+    }
+
+    fn project(&self, c: Coord<f64>) -> (f64, f64) {
+        let sx = self.width as f64 / (self.extent.width() * self.x_scale);
+        let sy = self.height as f64 / self.extent.height();
+        let s = sx.min(sy);
+        // Center the data along the axis with space left over
+        let off_x = (self.width as f64 - self.extent.width() * self.x_scale * s) / 2.0;
+        let off_y = (self.height as f64 - self.extent.height() * s) / 2.0;
+        (
+            off_x + (c.x - self.extent.min().x) * self.x_scale * s,
+            off_y + (self.extent.max().y - c.y) * s,
+        )
+    }
+
+    fn set(&mut self, x: i64, y: i64, dot: Dot) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            let i = y as usize * self.width + x as usize;
+            self.dots[i] = self.dots[i].max(dot);
+        }
+    }
+
+    fn line(&mut self, a: (f64, f64), b: (f64, f64), dot: Dot, dashed: bool) {
+        let (x0, y0) = (a.0.floor() as i64, a.1.floor() as i64);
+        let (x1, y1) = (b.0.floor() as i64, b.1.floor() as i64);
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).max(1);
+        for i in 0..=steps {
+            if dashed && i % 2 == 1 {
+                continue;
+            }
+            let t = i as f64 / steps as f64;
+            let x = x0 as f64 + (x1 - x0) as f64 * t;
+            let y = y0 as f64 + (y1 - y0) as f64 * t;
+            self.set(x.round() as i64, y.round() as i64, dot);
+        }
+    }
+
+    fn line_string(&mut self, ls: &LineString<f64>) {
+        let points: Vec<(f64, f64)> = ls.0.iter().map(|c| self.project(*c)).collect();
+        if points.len() == 1 {
+            self.set(points[0].0 as i64, points[0].1 as i64, Dot::Data);
+        }
+        for w in points.windows(2) {
+            self.line(w[0], w[1], Dot::Data, false);
+        }
+    }
+
+    pub fn draw(&mut self, geom: &Geometry<f64>) {
+        match geom {
+            Geometry::Point(p) => {
+                let (x, y) = self.project(p.0);
+                self.set(x as i64, y as i64, Dot::Data);
+            }
+            Geometry::MultiPoint(mp) => {
+                for p in mp.iter() {
+                    self.draw(&Geometry::Point(*p));
+                }
+            }
+            Geometry::Line(l) => self.line_string(&LineString::from(*l)),
+            Geometry::LineString(ls) => self.line_string(ls),
+            Geometry::MultiLineString(mls) => {
+                for ls in mls.iter() {
+                    self.line_string(ls);
+                }
+            }
+            Geometry::Polygon(p) => {
+                self.line_string(p.exterior());
+                for ring in p.interiors() {
+                    self.line_string(ring);
+                }
+            }
+            Geometry::MultiPolygon(mp) => {
+                for p in mp.iter() {
+                    self.draw(&Geometry::Polygon(p.clone()));
+                }
+            }
+            Geometry::Rect(r) => self.draw(&Geometry::Polygon(r.to_polygon())),
+            Geometry::Triangle(t) => self.draw(&Geometry::Polygon(t.to_polygon())),
+            Geometry::GeometryCollection(gc) => {
+                for g in gc.iter() {
+                    self.draw(g);
+                }
+            }
+        }
+    }
+
+    // Draws dashed meridians and parallels, returning the spacing in degrees
+    pub fn graticule(&mut self) -> f64 {
+        let step = graticule_step(self.extent.width().max(self.extent.height()));
+        let (min, max) = (self.extent.min(), self.extent.max());
+        let mut lon = (min.x / step).ceil() * step;
+        while lon <= max.x {
+            let a = self.project(Coord { x: lon, y: max.y });
+            let b = self.project(Coord { x: lon, y: min.y });
+            self.line(a, b, Dot::Graticule, true);
+            lon += step;
+        }
+        let mut lat = (min.y / step).ceil() * step;
+        while lat <= max.y {
+            let a = self.project(Coord { x: min.x, y: lat });
+            let b = self.project(Coord { x: max.x, y: lat });
+            self.line(a, b, Dot::Graticule, true);
+            lat += step;
+        }
+        step
+    }
+
+    pub fn extent(&self) -> Rect<f64> {
+        self.extent
+    }
+
+    pub fn render(&self) -> Vec<String> {
+        let (cell_w, cell_h) = self.style.cell();
+        let mut lines = vec![];
+        for row in 0..self.height / cell_h {
+            let mut line = String::new();
+            for col in 0..self.width / cell_w {
+                let dot = |dx: usize, dy: usize| {
+                    self.dots[(row * cell_h + dy) * self.width + col * cell_w + dx]
+                };
+                let c = match self.style {
+                    Style::Braille => {
+                        let mut bits = 0;
+                        for (i, (dx, dy)) in BRAILLE_DOTS.iter().enumerate() {
+                            if dot(*dx, *dy) != Dot::Empty {
+                                bits |= 1 << i;
+                            }
+                        }
+                        std::char::from_u32(0x2800 + bits).unwrap()
+                    }
+                    Style::Ascii => match dot(0, 0).max(dot(0, 1)) {
+                        Dot::Data => '#',
+                        Dot::Graticule => '.',
+                        Dot::Empty => ' ',
+                    },
+                };
+                line.push(c);
+            }
+            lines.push(line.trim_end().to_string());
+        }
+        lines
+    }
+}
+
+// Dot offsets within a braille character, in order of the
+// bits of its unicode code point
+const BRAILLE_DOTS: [(usize, usize); 8] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (1, 0),
+    (1, 1),
+    (1, 2),
+    (0, 3),
+    (1, 3),
+];
+
+// Round number of degrees giving a few graticule lines across the span
+fn graticule_step(span: f64) -> f64 {
+    let target = span / 4.0;
+    let magnitude = 10f64.powf(target.log10().floor());
+    for m in &[1.0, 2.0, 5.0, 10.0] {
+        if magnitude * m >= target {
+            return magnitude * m;
+        }
+    }
+    magnitude * 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{graticule_step, Canvas, Style};
+    use geo_types::{Coord, Geometry, LineString, Rect};
+
+    #[test]
+    fn test_graticule_step() {
+        assert_eq!(50.0, graticule_step(180.0));
+        assert_eq!(5.0, graticule_step(20.0));
+        assert_eq!(0.1, graticule_step(0.3));
+    }
+
+    #[test]
+    fn test_render_ascii() {
+        let extent = Rect::new(Coord { x: 0.0, y: 0.0 }, Coord { x: 10.0, y: 10.0 });
+        let mut canvas = Canvas::new(extent, 10, 5, Style::Ascii);
+        canvas.draw(&Geometry::LineString(LineString::from(vec![
+            (0.0, 5.0),
+            (10.0, 5.0),
+        ])));
+        let lines = canvas.render();
+        assert_eq!(5, lines.len());
+        assert_eq!("##########", lines[2]);
+        assert_eq!("", lines[0]);
+    }
+
+    #[test]
+    fn test_render_braille() {
+        let extent = Rect::new(Coord { x: 0.0, y: 0.0 }, Coord { x: 1.0, y: 1.0 });
+        let mut canvas = Canvas::new(extent, 2, 1, Style::Braille);
+        canvas.draw(&Geometry::LineString(LineString::from(vec![
+            (-1.0, 2.0),
+            (2.0, -1.0),
+        ])));
+        let lines = canvas.render();
+        assert_eq!(1, lines.len());
+        assert_eq!(2, lines[0].chars().count());
+        assert!(lines[0].chars().all(|c| c != '\u{2800}'));
+    }
+}
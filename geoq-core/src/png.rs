@@ -0,0 +1,187 @@
+// Minimal PNG decoder for raster tiles: 8-bit grayscale, RGB, RGBA and
+// paletted images without interlacing. https://www.w3.org/TR/png/
+use crate::error::Error;
+use flate2::read::ZlibDecoder;
+use std::convert::TryInto;
+use std::io::Read;
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    // Row-major RGB values
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    pub fn get(&self, x: usize, y: usize) -> [u8; 3] {
+        self.pixels[y * self.width + x]
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidInput(format!("Unsupported or invalid PNG: {}", message))
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// Reverses the per-scanline filters, returning the raw image bytes
+fn unfilter(data: &[u8], stride: usize, height: usize, bpp: usize) -> Result<Vec<u8>, Error> {
+    if data.len() < (stride + 1) * height {
+        return Err(invalid("truncated image data"));
+    }
+    let mut out = vec![0u8; stride * height];
+    for y in 0..height {
+        let filter = data[y * (stride + 1)];
+        let line = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (prev, cur) = out.split_at_mut(y * stride);
+        let prev = if y == 0 {
+            None
+        } else {
+            Some(&prev[(y - 1) * stride..])
+        };
+        let cur = &mut cur[..stride];
+        for i in 0..stride {
+            let a = if i >= bpp { cur[i - bpp] } else { 0 };
+            let b = prev.map_or(0, |p| p[i]);
+            let c = if i >= bpp {
+                prev.map_or(0, |p| p[i - bpp])
+            } else {
+                0
+            };
+            cur[i] = match filter {
+                0 => line[i],
+                1 => line[i].wrapping_add(a),
+                2 => line[i].wrapping_add(b),
+                3 => line[i].wrapping_add(((a as u16 + b as u16) / 2) as u8),
+                4 => line[i].wrapping_add(paeth(a, b, c)),
+                _ => return Err(invalid(&format!("unknown filter type {}", filter))),
+            };
+        }
+    }
+    Ok(out)
+}
+
+pub fn decode(data: &[u8]) -> Result<Image, Error> {
+    if !data.starts_with(SIGNATURE) {
+        return Err(invalid("missing signature"));
+    }
+    let mut pos = SIGNATURE.len();
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut compressed = vec![];
+    // Chunk CRCs aren't checked; corrupt data will fail to inflate
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data
+            .get(pos + 8..pos + 8 + len)
+            .ok_or_else(|| invalid("truncated chunk"))?;
+        match kind {
+            b"IHDR" if len >= 13 => header = Some(body),
+            b"PLTE" => palette = body,
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+    let header = header.ok_or_else(|| invalid("missing IHDR"))?;
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap()) as usize;
+    let (bit_depth, color_type, interlace) = (header[8], header[9], header[12]);
+    if bit_depth != 8 {
+        return Err(invalid(&format!("bit depth {}", bit_depth)));
+    }
+    if interlace != 0 {
+        return Err(invalid("interlaced"));
+    }
+    let bpp = match color_type {
+        0 => 1,
+        2 => 3,
+        3 => 1,
+        4 => 2,
+        6 => 4,
+        _ => return Err(invalid(&format!("color type {}", color_type))),
+    };
+
+    let mut inflated = vec![];
+    ZlibDecoder::new(&compressed[..])
+        .read_to_end(&mut inflated)
+        .map_err(|_| invalid("corrupt image data"))?;
+    let raw = unfilter(&inflated, width * bpp, height, bpp)?;
+    let pixels = raw
+        .chunks(bpp)
+        .map(|p| match color_type {
+            0 | 4 => Ok([p[0], p[0], p[0]]),
+            3 => palette
+                .get(p[0] as usize * 3..p[0] as usize * 3 + 3)
+                .map(|c| [c[0], c[1], c[2]])
+                .ok_or_else(|| invalid("palette index out of range")),
+            _ => Ok([p[0], p[1], p[2]]),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, SIGNATURE};
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::Write;
+
+    fn chunk(out: &mut Vec<u8>, kind: &[u8], body: &[u8]) {
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out.extend_from_slice(&[0, 0, 0, 0]);
+    }
+
+    // A 2x2 RGB image, with the second row using the Paeth filter
+    #[test]
+    fn test_decode_rgb() {
+        let mut header = vec![];
+        header.extend_from_slice(&2u32.to_be_bytes());
+        header.extend_from_slice(&2u32.to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        let rows = [0, 10, 20, 30, 40, 50, 60, 4, 1, 1, 1, 1, 1, 1];
+        let mut z = ZlibEncoder::new(vec![], Compression::default());
+        z.write_all(&rows).unwrap();
+
+        let mut png = SIGNATURE.to_vec();
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &z.finish().unwrap());
+        chunk(&mut png, b"IEND", &[]);
+
+        let image = decode(&png).unwrap();
+        assert_eq!((2, 2), (image.width, image.height));
+        assert_eq!([10, 20, 30], image.get(0, 0));
+        assert_eq!([40, 50, 60], image.get(1, 0));
+        assert_eq!([11, 21, 31], image.get(0, 1));
+        assert_eq!([41, 51, 61], image.get(1, 1));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(decode(b"GIF89a").is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use geo::algorithm::{bounding_rect::BoundingRect, intersects::Intersects};
+use geo_types::{Coord, Geometry, Line, LineString, Point, Polygon, Rect};
+use rstar::{RTree, RTreeObject, AABB};
+use std::collections::BTreeSet;
+
+// Query geometries prepared for testing many candidates against, as
+// filter intersects does with a large boundary. Their edges are kept in an
+// R-tree, so each candidate is compared only with the edges near it rather
+// than with every edge of the query. It's built once and shared by the
+// workers.
+pub struct Prepared {
+    edges: RTree<Edge>,
+    // Points of the query, and a vertex of each of its lines and polygons,
+    // to find queries lying wholly inside a candidate
+    vertices: RTree<[f64; 2]>,
+    bounds: Option<Rect<f64>>,
+}
+
+// An edge of a query line or polygon ring, with the index of its polygon
+struct Edge {
+    line: Line<f64>,
+    polygon: Option<usize>,
+}
+
+impl RTreeObject for Edge {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_corners(corner(self.line.start), corner(self.line.end))
+    }
+}
+
+fn corner(c: Coord<f64>) -> [f64; 2] {
+    [c.x, c.y]
+}
+
+fn envelope(r: Rect<f64>) -> AABB<[f64; 2]> {
+    AABB::from_corners(corner(r.min()), corner(r.max()))
+}
+
+// A geometry taken apart into its points, lines and polygons
+#[derive(Default)]
+struct Parts {
+    points: Vec<Point<f64>>,
+    lines: Vec<LineString<f64>>,
+    polygons: Vec<Polygon<f64>>,
+}
+
+impl Parts {
+    fn add(&mut self, geom: &Geometry<f64>) {
+        match geom {
+            Geometry::Point(p) => self.points.push(*p),
+            Geometry::MultiPoint(mp) => self.points.extend(mp.0.iter().copied()),
+            Geometry::Line(l) => self.lines.push(LineString::from(vec![l.start, l.end])),
+            Geometry::LineString(ls) => self.lines.push(ls.clone()),
+            Geometry::MultiLineString(mls) => self.lines.extend(mls.0.iter().cloned()),
+            Geometry::Polygon(p) => self.polygons.push(p.clone()),
+            Geometry::MultiPolygon(mp) => self.polygons.extend(mp.0.iter().cloned()),
+            Geometry::Rect(r) => self.polygons.push(r.to_polygon()),
+            Geometry::Triangle(t) => self.polygons.push(t.to_polygon()),
+            Geometry::GeometryCollection(gc) => gc.0.iter().for_each(|g| self.add(g)),
+        }
+    }
+
+    fn lines(&self) -> impl Iterator<Item = Line<f64>> + '_ {
+        let rings = self
+            .polygons
+            .iter()
+            .flat_map(|p| std::iter::once(p.exterior()).chain(p.interiors()));
+        self.lines.iter().chain(rings).flat_map(|ls| ls.lines())
+    }
+
+    // A vertex of each part, which lies inside anything containing it
+    fn vertices(&self) -> impl Iterator<Item = Coord<f64>> + '_ {
+        let points = self.points.iter().map(|p| p.0);
+        let lines = self.lines.iter().filter_map(|ls| ls.0.first().copied());
+        let polygons = self
+            .polygons
+            .iter()
+            .filter_map(|p| p.exterior().0.first().copied());
+        points.chain(lines).chain(polygons)
+    }
+}
+
+impl Prepared {
+    pub fn new(geoms: &[Geometry<f64>]) -> Prepared {
+        let mut parts = Parts::default();
+        geoms.iter().for_each(|g| parts.add(g));
+
+        let mut edges: Vec<Edge> = parts
+            .lines
+            .iter()
+            .flat_map(|ls| ls.lines())
+            .map(|line| Edge {
+                line,
+                polygon: None,
+            })
+            .collect();
+        for (i, p) in parts.polygons.iter().enumerate() {
+            for ring in std::iter::once(p.exterior()).chain(p.interiors()) {
+                edges.extend(ring.lines().map(|line| Edge {
+                    line,
+                    polygon: Some(i),
+                }));
+            }
+        }
+        let vertices: Vec<[f64; 2]> = parts.vertices().map(corner).collect();
+        let bounds = geoms
+            .iter()
+            .filter_map(|g| g.bounding_rect())
+            .reduce(|a, b| {
+                Rect::new(
+                    Coord {
+                        x: a.min().x.min(b.min().x),
+                        y: a.min().y.min(b.min().y),
+                    },
+                    Coord {
+                        x: a.max().x.max(b.max().x),
+                        y: a.max().y.max(b.max().y),
+                    },
+                )
+            });
+        Prepared {
+            edges: RTree::bulk_load(edges),
+            vertices: RTree::bulk_load(vertices),
+            bounds,
+        }
+    }
+
+    // Whether the point lies inside (or on) any query polygon, counting
+    // crossings of a ray east from it with each polygon's edges
+    fn covers(&self, p: Coord<f64>) -> bool {
+        let max_x = match self.bounds {
+            Some(b) if p.x <= b.max().x => b.max().x,
+            _ => return false,
+        };
+        let ray = AABB::from_corners([p.x, p.y], [max_x, p.y]);
+        let mut inside = BTreeSet::new();
+        for edge in self.edges.locate_in_envelope_intersecting(&ray) {
+            if edge.line.intersects(&Point(p)) {
+                return true;
+            }
+            let (a, b) = (edge.line.start, edge.line.end);
+            match edge.polygon {
+                Some(i) if (a.y > p.y) != (b.y > p.y) => {
+                    let x = a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y);
+                    if p.x < x && !inside.remove(&i) {
+                        inside.insert(i);
+                    }
+                }
+                _ => (),
+            }
+        }
+        !inside.is_empty()
+    }
+
+    pub fn intersects(&self, geom: &Geometry<f64>) -> bool {
+        let bbox = match (self.bounds, geom.bounding_rect()) {
+            (Some(q), Some(c)) if q.intersects(&c) => c,
+            _ => return false,
+        };
+        let mut parts = Parts::default();
+        parts.add(geom);
+
+        // Edges crossing or touching
+        let crossing = parts.lines().any(|line| {
+            let env = AABB::from_corners(corner(line.start), corner(line.end));
+            self.edges
+                .locate_in_envelope_intersecting(&env)
+                .any(|edge| edge.line.intersects(&line))
+        });
+        // Otherwise the candidate and query intersect only if one lies inside
+        // the other, and then so does each of its parts' vertices
+        crossing
+            || parts.vertices().any(|c| self.covers(c))
+            || self
+                .vertices
+                .locate_in_envelope_intersecting(&envelope(bbox))
+                .any(|v| geom.intersects(&Point::new(v[0], v[1])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Prepared;
+    use geo::algorithm::intersects::Intersects;
+    use geo_types::Geometry;
+    use std::{convert::TryInto, str::FromStr};
+
+    fn geom(wkt: &str) -> Geometry<f64> {
+        let mut wkt = wkt::Wkt::<f64>::from_str(wkt).unwrap();
+        wkt.items.remove(0).try_into().unwrap()
+    }
+
+    #[test]
+    fn test_intersects_like_geo() {
+        let queries = [
+            "MULTIPOLYGON (((0 0, 10 0, 10 10, 0 10, 0 0), (2 2, 8 2, 8 8, 2 8, 2 2)), ((20 0, 30 0, 25 10, 20 0)))",
+            "LINESTRING (0 0, 5 5, 10 0)",
+            "MULTIPOINT ((1 1), (25 3))",
+            "GEOMETRYCOLLECTION (POINT (40 40), POLYGON ((-5 -5, -1 -5, -1 -1, -5 -1, -5 -5)))",
+        ];
+        let candidates = [
+            "POINT (1 1)",
+            "POINT (5 5)",
+            "POINT (0 5)",
+            "POINT (25 3)",
+            "POINT (15 5)",
+            "POINT (40 40)",
+            "LINESTRING (3 3, 7 7)",
+            "LINESTRING (-1 5, 1 5)",
+            "LINESTRING (11 11, 19 19)",
+            "POLYGON ((3 3, 7 3, 7 7, 3 7, 3 3))",
+            "POLYGON ((-10 -10, 50 -10, 50 50, -10 50, -10 -10))",
+            "POLYGON ((24 2, 26 2, 26 4, 24 4, 24 2))",
+            "POLYGON ((11 1, 12 1, 12 2, 11 2, 11 1))",
+            "MULTIPOINT ((15 15), (-3 -3))",
+            "POLYGON ((-4 -4, -2 -4, -2 -2, -4 -2, -4 -4))",
+        ];
+        for q in queries.iter() {
+            let query = geom(q);
+            let prepared = Prepared::new(std::slice::from_ref(&query));
+            for c in candidates.iter() {
+                let candidate = geom(c);
+                assert_eq!(
+                    query.intersects(&candidate),
+                    prepared.intersects(&candidate),
+                    "{} intersects {}",
+                    q,
+                    c
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_query() {
+        let prepared = Prepared::new(&[]);
+        assert!(!prepared.intersects(&geom("POINT (1 1)")));
+    }
+}
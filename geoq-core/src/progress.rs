@@ -0,0 +1,119 @@
+// A progress bar on stderr for streaming commands, shown with the global
+// --progress flag. Readers count lines, bytes and entities as they go, and
+// a ticker thread draws them a few times a second. Nothing is drawn when
+// stderr isn't a terminal.
+use indicatif::{ProgressBar, ProgressStyle};
+use once_cell::sync::OnceCell;
+use std::{
+    fs,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::Duration,
+};
+
+static BAR: OnceCell<ProgressBar> = OnceCell::new();
+static LINES: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static ENTITIES: AtomicU64 = AtomicU64::new(0);
+
+const TICK: Duration = Duration::from_millis(100);
+
+// The size of stdin, when it's redirected from a file
+fn stdin_size() -> Option<u64> {
+    fs::metadata("/dev/stdin")
+        .ok()
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+}
+
+fn spinner_style() -> ProgressStyle {
+    ProgressStyle::default_spinner().template("{spinner} {elapsed_precise} {bytes} read, {msg}")
+}
+
+pub fn start() {
+    let bar = match stdin_size() {
+        Some(size) => {
+            let bar = ProgressBar::new(size);
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template(
+                        "{elapsed_precise} [{bar:30}] {bytes}/{total_bytes} ({percent}%) {msg}",
+                    )
+                    .progress_chars("=> "),
+            );
+            bar
+        }
+        None => ProgressBar::new_spinner().with_style(spinner_style()),
+    };
+    if BAR.set(bar).is_err() {
+        return;
+    }
+    thread::spawn(|| {
+        let bar = BAR.get().unwrap();
+        while !bar.is_finished() {
+            draw(bar);
+            thread::sleep(TICK);
+        }
+    });
+}
+
+// Binary inputs are converted to lines before they're read, so the bytes
+// read no longer line up with the size of stdin
+pub fn unknown_total() {
+    if let Some(bar) = BAR.get() {
+        bar.set_style(spinner_style());
+    }
+}
+
+pub fn line(bytes: usize) {
+    LINES.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+pub fn entities(count: usize) {
+    ENTITIES.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+fn summary(lines: u64, entities: u64, elapsed: Duration) -> String {
+    let rate = match elapsed.as_secs_f64() {
+        secs if secs > 0.0 => (lines as f64 / secs).round() as u64,
+        _ => 0,
+    };
+    format!("{} lines ({}/s), {} entities", lines, rate, entities)
+}
+
+fn draw(bar: &ProgressBar) {
+    bar.tick();
+    bar.set_position(BYTES.load(Ordering::Relaxed));
+    bar.set_message(summary(
+        LINES.load(Ordering::Relaxed),
+        ENTITIES.load(Ordering::Relaxed),
+        bar.elapsed(),
+    ));
+}
+
+// Draws the final counts, leaving them on stderr
+pub fn finish() {
+    if let Some(bar) = BAR.get() {
+        draw(bar);
+        bar.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::summary;
+    use std::time::Duration;
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            "1000 lines (500/s), 1200 entities",
+            summary(1000, 1200, Duration::from_secs(2))
+        );
+        assert_eq!(
+            "0 lines (0/s), 0 entities",
+            summary(0, 0, Duration::from_secs(0))
+        );
+    }
+}
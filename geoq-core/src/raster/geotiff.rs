@@ -0,0 +1,540 @@
+// GeoTIFF reader for the first image of classic (non-Big) TIFF files.
+// Supports strip and tile layouts, uncompressed, LZW, Deflate and
+// PackBits compression, and horizontal differencing.
+// TIFF 6.0: https://www.itu.int/itudoc/itu-t/com16/tiff-fx/docs/tiff6.pdf
+// GeoTIFF 1.1: https://docs.ogc.org/is/19-008r4/19-008r4.html
+use crate::{
+    error::Error,
+    object_store,
+    raster::{Crs, Raster},
+};
+use flate2::read::ZlibDecoder;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::Read;
+
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const STRIP_OFFSETS: u16 = 273;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+const PLANAR_CONFIGURATION: u16 = 284;
+const PREDICTOR: u16 = 317;
+const TILE_WIDTH: u16 = 322;
+const TILE_LENGTH: u16 = 323;
+const TILE_OFFSETS: u16 = 324;
+const TILE_BYTE_COUNTS: u16 = 325;
+const SAMPLE_FORMAT: u16 = 339;
+const MODEL_PIXEL_SCALE: u16 = 33550;
+const MODEL_TIEPOINT: u16 = 33922;
+const MODEL_TRANSFORMATION: u16 = 34264;
+const GEO_KEY_DIRECTORY: u16 = 34735;
+const GDAL_NODATA: u16 = 42113;
+
+const GT_MODEL_TYPE: u16 = 1024;
+const GT_RASTER_TYPE: u16 = 1025;
+const GEOGRAPHIC_TYPE: u16 = 2048;
+const PROJECTED_CS_TYPE: u16 = 3072;
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidInput(format!("Unsupported or invalid GeoTIFF: {}", message))
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8], Error> {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.data.get(offset..end))
+            .ok_or_else(|| invalid("unexpected end of file"))
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, Error> {
+        let b = self.bytes(offset, 2)?.try_into().unwrap();
+        Ok(if self.little_endian {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, Error> {
+        let b = self.bytes(offset, 4)?.try_into().unwrap();
+        Ok(if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, Error> {
+        let b = self.bytes(offset, 8)?.try_into().unwrap();
+        Ok(if self.little_endian {
+            u64::from_le_bytes(b)
+        } else {
+            u64::from_be_bytes(b)
+        })
+    }
+
+    // Numeric values of an IFD entry, or its text for ASCII entries
+    fn entry(&self, offset: usize) -> Result<(u16, Value), Error> {
+        let tag = self.u16(offset)?;
+        let kind = self.u16(offset + 2)?;
+        let count = self.u32(offset + 4)? as usize;
+        let size: usize = match kind {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => return Ok((tag, Value::Numbers(vec![]))),
+        };
+        let len = size
+            .checked_mul(count)
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+        // Values are stored inline when they fit in the offset field
+        let start = if len <= 4 {
+            offset + 8
+        } else {
+            self.u32(offset + 8)? as usize
+        };
+        // Checked before allocating for them, since the count comes from
+        // the file
+        let stored = self.bytes(start, len)?;
+        if kind == 2 {
+            let text = String::from_utf8_lossy(stored);
+            return Ok((tag, Value::Text(text.trim_end_matches('\0').to_string())));
+        }
+        let mut values = Vec::with_capacity(count);
+        for i in 0..count {
+            let at = start + i * size;
+            values.push(match kind {
+                1 | 7 => self.bytes(at, 1)?[0] as f64,
+                6 => self.bytes(at, 1)?[0] as i8 as f64,
+                3 => self.u16(at)? as f64,
+                8 => self.u16(at)? as i16 as f64,
+                4 => self.u32(at)? as f64,
+                9 => self.u32(at)? as i32 as f64,
+                5 => self.u32(at)? as f64 / self.u32(at + 4)? as f64,
+                10 => self.u32(at)? as i32 as f64 / self.u32(at + 4)? as i32 as f64,
+                11 => f32::from_bits(self.u32(at)?) as f64,
+                _ => f64::from_bits(self.u64(at)?),
+            });
+        }
+        Ok((tag, Value::Numbers(values)))
+    }
+}
+
+enum Value {
+    Numbers(Vec<f64>),
+    Text(String),
+}
+
+struct Tags(HashMap<u16, Value>);
+
+impl Tags {
+    fn numbers(&self, tag: u16) -> Option<&[f64]> {
+        match self.0.get(&tag) {
+            Some(Value::Numbers(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn number(&self, tag: u16) -> Option<f64> {
+        self.numbers(tag).and_then(|n| n.first().copied())
+    }
+
+    fn required(&self, tag: u16, name: &str) -> Result<&[f64], Error> {
+        self.numbers(tag)
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| invalid(&format!("missing {}", name)))
+    }
+}
+
+fn lzw_decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+    const CLEAR: usize = 256;
+    const END: usize = 257;
+    let mut out = vec![];
+    let mut table: Vec<Vec<u8>> = (0..=255u8).map(|b| vec![b]).collect();
+    table.push(vec![]);
+    table.push(vec![]);
+    let mut width = 9;
+    let mut prev: Option<usize> = None;
+    let (mut bits, mut nbits, mut pos) = (0u32, 0, 0);
+    loop {
+        while nbits < width {
+            if pos >= data.len() {
+                return Ok(out);
+            }
+            bits = (bits << 8) | data[pos] as u32;
+            nbits += 8;
+            pos += 1;
+        }
+        let code = ((bits >> (nbits - width)) & ((1 << width) - 1)) as usize;
+        nbits -= width;
+        if code == END {
+            return Ok(out);
+        }
+        if code == CLEAR {
+            table.truncate(258);
+            width = 9;
+            prev = None;
+            continue;
+        }
+        let entry = match prev {
+            _ if code < table.len() => table[code].clone(),
+            Some(p) if code == table.len() => {
+                let mut e = table[p].clone();
+                e.push(table[p][0]);
+                e
+            }
+            _ => return Err(invalid("corrupt LZW data")),
+        };
+        out.extend_from_slice(&entry);
+        if let Some(p) = prev {
+            let mut e = table[p].clone();
+            e.push(entry[0]);
+            table.push(e);
+        }
+        prev = Some(code);
+        // TIFF's LZW switches code width one code early
+        if table.len() + 1 >= 1 << width && width < 12 {
+            width += 1;
+        }
+    }
+}
+
+fn packbits_decode(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    let mut i = 0;
+    while i < data.len() {
+        let n = data[i] as i8;
+        i += 1;
+        if n >= 0 {
+            let end = (i + n as usize + 1).min(data.len());
+            out.extend_from_slice(&data[i..end]);
+            i = end;
+        } else if n != -128 && i < data.len() {
+            out.extend(std::iter::repeat_n(data[i], 1 + (-(n as i16)) as usize));
+            i += 1;
+        }
+    }
+    out
+}
+
+struct Layout {
+    width: usize,
+    height: usize,
+    samples_per_pixel: usize,
+    bytes_per_sample: usize,
+    sample_format: u16,
+    planar: bool,
+    chunk_width: usize,
+    chunk_height: usize,
+}
+
+impl Layout {
+    fn sample(&self, bytes: &[u8], little_endian: bool) -> f64 {
+        macro_rules! read {
+            ($t:ty) => {{
+                let b = bytes.try_into().unwrap();
+                if little_endian {
+                    <$t>::from_le_bytes(b)
+                } else {
+                    <$t>::from_be_bytes(b)
+                }
+            }};
+        }
+        match (self.sample_format, self.bytes_per_sample) {
+            (3, 4) => read!(f32) as f64,
+            (3, _) => read!(f64),
+            (2, 1) => bytes[0] as i8 as f64,
+            (2, 2) => read!(i16) as f64,
+            (2, 4) => read!(i32) as f64,
+            (2, _) => read!(i64) as f64,
+            (_, 1) => bytes[0] as f64,
+            (_, 2) => read!(u16) as f64,
+            (_, 4) => read!(u32) as f64,
+            (_, _) => read!(u64) as f64,
+        }
+    }
+
+    // Reverses horizontal differencing (predictor 2) in place
+    fn undo_predictor(&self, chunk: &mut [u8], little_endian: bool) {
+        let spp = if self.planar {
+            1
+        } else {
+            self.samples_per_pixel
+        };
+        let bps = self.bytes_per_sample;
+        for row in chunk.chunks_mut(self.chunk_width * spp * bps) {
+            for i in spp..row.len() / bps {
+                let (a, b) = ((i - spp) * bps, i * bps);
+                let prev = read_uint(&row[a..a + bps], little_endian);
+                let cur = read_uint(&row[b..b + bps], little_endian);
+                write_uint(&mut row[b..b + bps], cur.wrapping_add(prev), little_endian);
+            }
+        }
+    }
+}
+
+fn read_uint(bytes: &[u8], little_endian: bool) -> u64 {
+    let fold = |v: u64, b: &u8| (v << 8) | *b as u64;
+    if little_endian {
+        bytes.iter().rev().fold(0, fold)
+    } else {
+        bytes.iter().fold(0, fold)
+    }
+}
+
+// Writes the low bytes of a value, wrapping at the sample size
+fn write_uint(bytes: &mut [u8], v: u64, little_endian: bool) {
+    let n = bytes.len();
+    for k in 0..n {
+        let byte = (v >> (8 * k)) as u8;
+        if little_endian {
+            bytes[k] = byte;
+        } else {
+            bytes[n - 1 - k] = byte;
+        }
+    }
+}
+
+pub fn read(path: &str, band: usize) -> Result<Raster, Error> {
+    let data = object_store::read(path)?;
+    let little_endian = match data.get(0..4) {
+        Some(b"II*\0") => true,
+        Some(b"MM\0*") => false,
+        Some(b"II+\0") | Some(b"MM\0+") => return Err(invalid("BigTIFF files aren't supported")),
+        _ => return Err(invalid("missing TIFF header")),
+    };
+    let tiff = Tiff {
+        data: &data,
+        little_endian,
+    };
+    let ifd = tiff.u32(4)? as usize;
+    let mut tags = HashMap::new();
+    for i in 0..tiff.u16(ifd)? as usize {
+        let (tag, value) = tiff.entry(ifd + 2 + i * 12)?;
+        tags.insert(tag, value);
+    }
+    let tags = Tags(tags);
+
+    let width = tags.required(IMAGE_WIDTH, "ImageWidth")?[0] as usize;
+    let height = tags.required(IMAGE_LENGTH, "ImageLength")?[0] as usize;
+    let samples_per_pixel = tags.number(SAMPLES_PER_PIXEL).unwrap_or(1.0) as usize;
+    if band == 0 || band > samples_per_pixel {
+        return Err(Error::InvalidInput(format!(
+            "Invalid band: {} -- raster has {} band(s)",
+            band, samples_per_pixel
+        )));
+    }
+    let bits = tags.number(BITS_PER_SAMPLE).unwrap_or(1.0) as usize;
+    if ![8, 16, 32, 64].contains(&bits) {
+        return Err(invalid(&format!("{} bits per sample", bits)));
+    }
+    let tiled = tags.numbers(TILE_OFFSETS).is_some();
+    let layout = Layout {
+        width,
+        height,
+        samples_per_pixel,
+        bytes_per_sample: bits / 8,
+        sample_format: tags.number(SAMPLE_FORMAT).unwrap_or(1.0) as u16,
+        planar: tags.number(PLANAR_CONFIGURATION) == Some(2.0),
+        chunk_width: if tiled {
+            tags.required(TILE_WIDTH, "TileWidth")?[0] as usize
+        } else {
+            width
+        },
+        chunk_height: if tiled {
+            tags.required(TILE_LENGTH, "TileLength")?[0] as usize
+        } else {
+            (tags.number(ROWS_PER_STRIP).unwrap_or(height as f64) as usize).min(height)
+        },
+    };
+    let (offsets, counts) = if tiled {
+        (
+            tags.required(TILE_OFFSETS, "TileOffsets")?,
+            tags.required(TILE_BYTE_COUNTS, "TileByteCounts")?,
+        )
+    } else {
+        (
+            tags.required(STRIP_OFFSETS, "StripOffsets")?,
+            tags.required(STRIP_BYTE_COUNTS, "StripByteCounts")?,
+        )
+    };
+    let compression = tags.number(COMPRESSION).unwrap_or(1.0) as u16;
+    let predictor = tags.number(PREDICTOR).unwrap_or(1.0) as u16;
+    if predictor > 2 {
+        return Err(invalid("floating point predictor"));
+    }
+
+    let across = width.div_ceil(layout.chunk_width);
+    let down = height.div_ceil(layout.chunk_height);
+    let (pixel_stride, sample_offset, first_chunk) = if layout.planar {
+        (1, 0, (band - 1) * across * down)
+    } else {
+        (samples_per_pixel, band - 1, 0)
+    };
+    let mut values = vec![0.0; width * height];
+    for cy in 0..down {
+        for cx in 0..across {
+            let i = first_chunk + cy * across + cx;
+            let (offset, count) = match (offsets.get(i), counts.get(i)) {
+                (Some(o), Some(c)) => (*o as usize, *c as usize),
+                _ => return Err(invalid("missing strip or tile")),
+            };
+            let raw = tiff.bytes(offset, count)?;
+            let mut chunk = match compression {
+                1 => raw.to_vec(),
+                5 => lzw_decode(raw)?,
+                8 | 32946 => {
+                    let mut out = vec![];
+                    ZlibDecoder::new(raw)
+                        .read_to_end(&mut out)
+                        .map_err(|_| invalid("corrupt Deflate data"))?;
+                    out
+                }
+                32773 => packbits_decode(raw),
+                c => return Err(invalid(&format!("compression type {}", c))),
+            };
+            if predictor == 2 {
+                layout.undo_predictor(&mut chunk, little_endian);
+            }
+            let bps = layout.bytes_per_sample;
+            for y in 0..layout.chunk_height {
+                let row = cy * layout.chunk_height + y;
+                if row >= height {
+                    break;
+                }
+                for x in 0..layout.chunk_width {
+                    let col = cx * layout.chunk_width + x;
+                    if col >= width {
+                        break;
+                    }
+                    let at = ((y * layout.chunk_width + x) * pixel_stride + sample_offset) * bps;
+                    let bytes = chunk
+                        .get(at..at + bps)
+                        .ok_or_else(|| invalid("truncated strip or tile"))?;
+                    values[row * width + col] = layout.sample(bytes, little_endian);
+                }
+            }
+        }
+    }
+
+    let nodata = match tags.0.get(&GDAL_NODATA) {
+        Some(Value::Text(t)) => t.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    let (transform, crs) = georeference(&tags)?;
+    Ok(Raster {
+        width,
+        height,
+        values,
+        nodata,
+        transform,
+        crs,
+    })
+}
+
+fn georeference(tags: &Tags) -> Result<([f64; 6], Crs), Error> {
+    let mut keys = HashMap::new();
+    if let Some(dir) = tags.numbers(GEO_KEY_DIRECTORY) {
+        // Header of 4 shorts, then 4 per key. Keys stored in other
+        // tags (location != 0) are all strings or doubles we don't need.
+        for key in dir.get(4..).unwrap_or(&[]).chunks(4) {
+            if key.len() == 4 && key[1] == 0.0 {
+                keys.insert(key[0] as u16, key[3] as u16);
+            }
+        }
+    }
+    let crs = match (keys.get(&GT_MODEL_TYPE), keys.get(&PROJECTED_CS_TYPE)) {
+        (Some(1), Some(code)) => Crs::from_epsg(*code)?,
+        (Some(1), None) => return Err(invalid("projected CRS without an EPSG code")),
+        _ => match keys.get(&GEOGRAPHIC_TYPE) {
+            Some(code) if *code != 32767 => Crs::from_epsg(*code)?,
+            _ => Crs::Geographic,
+        },
+    };
+
+    let mut transform = if let Some(m) = tags.numbers(MODEL_TRANSFORMATION) {
+        if m.len() < 8 {
+            return Err(invalid("short ModelTransformation"));
+        }
+        [m[3], m[0], m[1], m[7], m[4], m[5]]
+    } else {
+        let tiepoint = tags.required(MODEL_TIEPOINT, "georeferencing (ModelTiepoint)")?;
+        let scale = tags.required(MODEL_PIXEL_SCALE, "ModelPixelScale")?;
+        if tiepoint.len() < 6 || scale.len() < 2 {
+            return Err(invalid("short ModelTiepoint or ModelPixelScale"));
+        }
+        let (i, j, x, y) = (tiepoint[0], tiepoint[1], tiepoint[3], tiepoint[4]);
+        [
+            x - i * scale[0],
+            scale[0],
+            0.0,
+            y + j * scale[1],
+            0.0,
+            -scale[1],
+        ]
+    };
+    // With PixelIsPoint, model coordinates refer to pixel centers
+    if keys.get(&GT_RASTER_TYPE) == Some(&2) {
+        transform[0] -= (transform[1] + transform[2]) / 2.0;
+        transform[3] -= (transform[4] + transform[5]) / 2.0;
+    }
+    Ok((transform, crs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lzw_decode, packbits_decode, Tiff};
+
+    #[test]
+    fn test_entry_count_past_end() {
+        // A LONG entry claiming 2^32 - 1 values at offset 0
+        let data = [
+            0x00, 0x01, 0x04, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let tiff = Tiff {
+            data: &data,
+            little_endian: true,
+        };
+        assert!(tiff.entry(0).is_err());
+    }
+
+    #[test]
+    fn test_lzw_decode() {
+        // Clear, "A", "B", then code 258 ("AB"), end of information,
+        // packed as 9-bit codes
+        let codes = [256u32, 65, 66, 258, 257];
+        let mut bits = 0u64;
+        for c in codes.iter() {
+            bits = (bits << 9) | *c as u64;
+        }
+        let nbits = 9 * codes.len();
+        let padded = bits << (48 - nbits);
+        let data: Vec<u8> = (0..6).map(|i| (padded >> (40 - 8 * i)) as u8).collect();
+        assert_eq!(b"ABAB".to_vec(), lzw_decode(&data).unwrap());
+    }
+
+    #[test]
+    fn test_packbits_decode() {
+        // Example from the TIFF 6.0 spec, section 9
+        let data = [
+            0xFE, 0xAA, 0x02, 0x80, 0x00, 0x2A, 0xFD, 0xAA, 0x03, 0x80, 0x00, 0x2A, 0x22, 0xF7,
+            0xAA,
+        ];
+        let expected = [
+            0xAA, 0xAA, 0xAA, 0x80, 0x00, 0x2A, 0xAA, 0xAA, 0xAA, 0xAA, 0x80, 0x00, 0x2A, 0x22,
+            0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA,
+        ];
+        assert_eq!(expected.to_vec(), packbits_decode(&data));
+    }
+}
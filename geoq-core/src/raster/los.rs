@@ -0,0 +1,96 @@
+// Line of sight over a DEM: whether the terrain between two points hides
+// one from the other. The sight line is sampled along the geodesic about
+// twice per pixel, and the terrain is raised by the earth's curvature
+// between the ends, less the usual 0.13 for atmospheric refraction, so
+// that far-off points can drop below the horizon.
+use super::Raster;
+use crate::buffer::EARTH_RADIUS;
+use geo::algorithm::{
+    geodesic_distance::GeodesicDistance, geodesic_intermediate::GeodesicIntermediate,
+};
+use geo_types::{Coord, Point};
+
+const REFRACTION: f64 = 0.13;
+
+#[derive(Debug, PartialEq)]
+pub enum Sight {
+    Visible,
+    // The first point along the line where the terrain rises above it
+    Blocked(Coord<f64>),
+    // Either end is off the raster or on nodata
+    Unknown,
+}
+
+// Whether `to` can be seen from `from`, with each end at some height in
+// meters above the terrain there. Samples off the raster or on nodata
+// along the way are taken not to block the view.
+pub fn sight(
+    raster: &Raster,
+    from: Coord<f64>,
+    to: Coord<f64>,
+    from_height: f64,
+    to_height: f64,
+) -> Sight {
+    let (start, end) = match (raster.sample(from), raster.sample(to)) {
+        (Some(a), Some(b)) => (a + from_height, b + to_height),
+        _ => return Sight::Unknown,
+    };
+    let (a, b) = (raster.to_pixel(from), raster.to_pixel(to));
+    let steps = ((b.x - a.x).hypot(b.y - a.y) * 2.0).ceil().max(1.0) as usize;
+    let length = Point(from).geodesic_distance(&Point(to));
+    let radius = EARTH_RADIUS / (1.0 - REFRACTION);
+    for i in 1..steps {
+        let f = i as f64 / steps as f64;
+        let p = Point(from).geodesic_intermediate(&Point(to), f).0;
+        if let Some(terrain) = raster.sample(p) {
+            // How far the surface here bulges above the chord between the ends
+            let d = length * f;
+            let bulge = d * (length - d) / (2.0 * radius);
+            if terrain + bulge > start + (end - start) * f {
+                return Sight::Blocked(p);
+            }
+        }
+    }
+    Sight::Visible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sight, Sight};
+    use crate::raster::{Crs, Raster};
+    use geo_types::Coord;
+
+    // 10x10 pixels of about 110m from 0,0.01 at the top left, flat at 0
+    // but for a north-south ridge of the given height in column 5
+    fn raster(ridge: f64) -> Raster {
+        Raster {
+            width: 10,
+            height: 10,
+            values: (0..100)
+                .map(|i| if i % 10 == 5 { ridge } else { 0.0 })
+                .collect(),
+            nodata: None,
+            transform: [0.0, 0.001, 0.0, 0.01, 0.0, -0.001],
+            crs: Crs::Geographic,
+        }
+    }
+
+    fn c(x: f64, y: f64) -> Coord<f64> {
+        Coord { x, y }
+    }
+
+    #[test]
+    fn test_sight() {
+        let (from, to) = (c(0.0005, 0.0055), c(0.0095, 0.0055));
+        assert_eq!(Sight::Visible, sight(&raster(0.0), from, to, 1.7, 0.0));
+        match sight(&raster(1.0), from, to, 1.7, 0.0) {
+            Sight::Blocked(p) => assert!(p.x >= 0.005 && p.x < 0.006),
+            s => panic!("Expected the ridge to block the view: {:?}", s),
+        }
+        assert_eq!(Sight::Visible, sight(&raster(1.0), from, to, 10.0, 0.0));
+        assert_eq!(
+            Sight::Unknown,
+            sight(&raster(0.0), from, c(0.02, 0.0055), 1.7, 0.0)
+        );
+    }
+}
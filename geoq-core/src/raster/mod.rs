@@ -0,0 +1,65 @@
+// Single-band rasters read from GeoTIFF files, with just enough
+// georeferencing to place pixels in WGS84 lon/lat: geographic,
+// Web Mercator and WGS84 UTM coordinate systems are supported.
+pub use crate::crs::Crs;
+use geo_types::Coord;
+
+pub mod geotiff;
+pub mod los;
+pub mod polygonize;
+pub mod zonal;
+
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    // Row-major values of the band that was read
+    pub values: Vec<f64>,
+    pub nodata: Option<f64>,
+    // Affine transform from pixel (column, row) to model coordinates,
+    // in GDAL order: x = t[0] + col * t[1] + row * t[2],
+    // y = t[3] + col * t[4] + row * t[5]
+    pub transform: [f64; 6],
+    pub crs: Crs,
+}
+
+impl Raster {
+    pub fn get(&self, col: usize, row: usize) -> f64 {
+        self.values[row * self.width + col]
+    }
+
+    pub fn is_nodata(&self, v: f64) -> bool {
+        v.is_nan() || self.nodata == Some(v)
+    }
+
+    // Value of the pixel containing a lon/lat, or None off the raster
+    // or for nodata
+    pub fn sample(&self, c: Coord<f64>) -> Option<f64> {
+        let p = self.to_pixel(c);
+        if !(p.x >= 0.0 && p.x < self.width as f64 && p.y >= 0.0 && p.y < self.height as f64) {
+            return None;
+        }
+        Some(self.get(p.x as usize, p.y as usize)).filter(|&v| !self.is_nodata(v))
+    }
+
+    // Lon/lat of a position in pixel space, where (0, 0) is the
+    // top left corner of the top left pixel
+    pub fn to_lon_lat(&self, col: f64, row: f64) -> Coord<f64> {
+        let t = &self.transform;
+        self.crs.to_lon_lat(Coord {
+            x: t[0] + col * t[1] + row * t[2],
+            y: t[3] + col * t[4] + row * t[5],
+        })
+    }
+
+    // Position in pixel space of a lon/lat, the inverse of to_lon_lat
+    pub fn to_pixel(&self, c: Coord<f64>) -> Coord<f64> {
+        let t = &self.transform;
+        let m = self.crs.from_lon_lat(c);
+        let (dx, dy) = (m.x - t[0], m.y - t[3]);
+        let det = t[1] * t[5] - t[2] * t[4];
+        Coord {
+            x: (t[5] * dx - t[2] * dy) / det,
+            y: (t[1] * dy - t[4] * dx) / det,
+        }
+    }
+}
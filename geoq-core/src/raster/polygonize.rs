@@ -0,0 +1,293 @@
+// Vectorizes a raster into polygons covering its 4-connected regions of
+// equal value. Each region's boundary is traced along pixel edges, keeping
+// the region on the right, then reversed so that exteriors run
+// counterclockwise and holes clockwise (as seen north-up), per RFC 7946.
+use crate::raster::Raster;
+use geo_types::{Coord, LineString, Polygon};
+use std::collections::{HashMap, HashSet};
+
+const NONE: u32 = u32::MAX;
+
+// Directions in pixel space, in clockwise order
+const RIGHT: u8 = 0;
+const DOWN: u8 = 1;
+const LEFT: u8 = 2;
+const UP: u8 = 3;
+
+fn step(v: (usize, usize), dir: u8) -> (usize, usize) {
+    match dir {
+        RIGHT => (v.0 + 1, v.1),
+        DOWN => (v.0, v.1 + 1),
+        LEFT => (v.0 - 1, v.1),
+        _ => (v.0, v.1 - 1),
+    }
+}
+
+// Labels 4-connected regions of equal value, leaving nodata as NONE
+fn label(raster: &Raster) -> (Vec<u32>, Vec<f64>) {
+    let (w, h) = (raster.width, raster.height);
+    let mut labels = vec![NONE; w * h];
+    let mut values = vec![];
+    let mut stack = vec![];
+    for start in 0..w * h {
+        let value = raster.values[start];
+        if labels[start] != NONE || raster.is_nodata(value) {
+            continue;
+        }
+        let l = values.len() as u32;
+        values.push(value);
+        labels[start] = l;
+        stack.push(start);
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % w, i / w);
+            let mut neighbors = vec![];
+            if x > 0 {
+                neighbors.push(i - 1);
+            }
+            if x + 1 < w {
+                neighbors.push(i + 1);
+            }
+            if y > 0 {
+                neighbors.push(i - w);
+            }
+            if y + 1 < h {
+                neighbors.push(i + w);
+            }
+            for n in neighbors {
+                if labels[n] == NONE && raster.values[n] == value {
+                    labels[n] = l;
+                    stack.push(n);
+                }
+            }
+        }
+    }
+    (labels, values)
+}
+
+// Outgoing boundary edges of one region, by start vertex
+type Edges = HashMap<(usize, usize), Vec<u8>>;
+
+fn boundary_edges(raster: &Raster, labels: &[u32], regions: usize) -> Vec<Edges> {
+    let (w, h) = (raster.width, raster.height);
+    let mut edges: Vec<Edges> = (0..regions).map(|_| HashMap::new()).collect();
+    for y in 0..h {
+        for x in 0..w {
+            let l = labels[y * w + x];
+            if l == NONE {
+                continue;
+            }
+            let other = |nx: Option<usize>, ny: Option<usize>| match (nx, ny) {
+                (Some(nx), Some(ny)) if nx < w && ny < h => labels[ny * w + nx] != l,
+                _ => true,
+            };
+            let region = &mut edges[l as usize];
+            if other(Some(x), y.checked_sub(1)) {
+                region.entry((x, y)).or_default().push(RIGHT);
+            }
+            if other(Some(x + 1), Some(y)) {
+                region.entry((x + 1, y)).or_default().push(DOWN);
+            }
+            if other(Some(x), Some(y + 1)) {
+                region.entry((x + 1, y + 1)).or_default().push(LEFT);
+            }
+            if other(x.checked_sub(1), Some(y)) {
+                region.entry((x, y + 1)).or_default().push(UP);
+            }
+        }
+    }
+    edges
+}
+
+// Follows edges from a starting one until returning to it. Where a region
+// touches itself diagonally at a vertex, there are two ways out of it.
+// Turning left keeps the rings on either side of the vertex apart, giving
+// an exterior and a hole which touch at a point, rather than a single
+// self-touching ring.
+fn trace(
+    edges: &Edges,
+    used: &mut HashSet<((usize, usize), u8)>,
+    start: ((usize, usize), u8),
+) -> Vec<(usize, usize)> {
+    let mut ring = vec![start.0];
+    let (mut v, mut dir) = start;
+    loop {
+        used.insert((v, dir));
+        v = step(v, dir);
+        let out = &edges[&v];
+        let next = [(dir + 3) % 4, dir, (dir + 1) % 4]
+            .iter()
+            .copied()
+            .find(|d| out.contains(d))
+            .unwrap();
+        if (v, next) == start {
+            ring.push(v);
+            return ring;
+        }
+        // Only corners are kept, dropping vertices along straight runs
+        if next != dir {
+            ring.push(v);
+        }
+        dir = next;
+    }
+}
+
+fn signed_area(ring: &[(usize, usize)]) -> f64 {
+    ring.windows(2)
+        .map(|w| w[0].0 as f64 * w[1].1 as f64 - w[1].0 as f64 * w[0].1 as f64)
+        .sum::<f64>()
+        / 2.0
+}
+
+// Polygons for each region, with its value, in order of each
+// region's first (top left) pixel. Rings are in pixel space.
+pub fn polygonize(raster: &Raster) -> Vec<(f64, Polygon<f64>)> {
+    let (labels, values) = label(raster);
+    let edges = boundary_edges(raster, &labels, values.len());
+    let mut polygons = vec![];
+    for (region, value) in edges.iter().zip(values) {
+        let mut used = HashSet::new();
+        let mut starts: Vec<((usize, usize), u8)> = region
+            .iter()
+            .flat_map(|(v, dirs)| dirs.iter().map(move |d| (*v, *d)))
+            .collect();
+        starts.sort_by_key(|(v, d)| (v.1, v.0, *d));
+        let mut exterior = None;
+        let mut holes = vec![];
+        for start in starts {
+            if used.contains(&start) {
+                continue;
+            }
+            let ring = trace(region, &mut used, start);
+            // Exteriors are traced clockwise as seen north-up (positive area
+            // with rows increasing downward), and holes counterclockwise
+            let ring_coords = LineString(
+                ring.iter()
+                    .rev()
+                    .map(|(x, y)| Coord {
+                        x: *x as f64,
+                        y: *y as f64,
+                    })
+                    .collect(),
+            );
+            if signed_area(&ring) > 0.0 {
+                exterior = Some(ring_coords);
+            } else {
+                holes.push(ring_coords);
+            }
+        }
+        if let Some(exterior) = exterior {
+            polygons.push((value, Polygon::new(exterior, holes)));
+        }
+    }
+    polygons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::polygonize;
+    use crate::raster::{Crs, Raster};
+    use geo_types::{LineString, Polygon};
+
+    fn raster(width: usize, values: Vec<f64>) -> Raster {
+        Raster {
+            width,
+            height: values.len() / width,
+            values,
+            nodata: Some(0.0),
+            transform: [0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+            crs: Crs::Geographic,
+        }
+    }
+
+    #[test]
+    fn test_polygonize_hole() {
+        let r = raster(3, vec![1.0, 1.0, 1.0, 1.0, 2.0, 1.0, 1.0, 1.0, 1.0]);
+        let polygons = polygonize(&r);
+        assert_eq!(2, polygons.len());
+        assert_eq!(
+            (
+                1.0,
+                Polygon::new(
+                    LineString::from(vec![
+                        (0.0, 0.0),
+                        (0.0, 3.0),
+                        (3.0, 3.0),
+                        (3.0, 0.0),
+                        (0.0, 0.0)
+                    ]),
+                    vec![LineString::from(vec![
+                        (1.0, 1.0),
+                        (2.0, 1.0),
+                        (2.0, 2.0),
+                        (1.0, 2.0),
+                        (1.0, 1.0)
+                    ])]
+                )
+            ),
+            polygons[0]
+        );
+        assert_eq!(
+            (
+                2.0,
+                Polygon::new(
+                    LineString::from(vec![
+                        (1.0, 1.0),
+                        (1.0, 2.0),
+                        (2.0, 2.0),
+                        (2.0, 1.0),
+                        (1.0, 1.0)
+                    ]),
+                    vec![]
+                )
+            ),
+            polygons[1]
+        );
+    }
+
+    // Diagonal pixels of the same value are separate regions, and the
+    // region wrapping around them touches itself at a vertex
+    #[test]
+    fn test_polygonize_diagonal() {
+        let r = raster(2, vec![1.0, 0.0, 0.0, 1.0]);
+        let polygons = polygonize(&r);
+        assert_eq!(2, polygons.len());
+        assert_eq!(
+            Polygon::new(
+                LineString::from(vec![
+                    (0.0, 0.0),
+                    (0.0, 1.0),
+                    (1.0, 1.0),
+                    (1.0, 0.0),
+                    (0.0, 0.0)
+                ]),
+                vec![]
+            ),
+            polygons[0].1
+        );
+
+        let r = raster(3, vec![2.0, 2.0, 2.0, 2.0, 1.0, 2.0, 2.0, 2.0, 1.0]);
+        let polygons = polygonize(&r);
+        assert_eq!(3, polygons.len());
+        assert_eq!(
+            Polygon::new(
+                LineString::from(vec![
+                    (0.0, 0.0),
+                    (0.0, 3.0),
+                    (2.0, 3.0),
+                    (2.0, 2.0),
+                    (3.0, 2.0),
+                    (3.0, 0.0),
+                    (0.0, 0.0)
+                ]),
+                vec![LineString::from(vec![
+                    (1.0, 1.0),
+                    (2.0, 1.0),
+                    (2.0, 2.0),
+                    (1.0, 2.0),
+                    (1.0, 1.0)
+                ])]
+            ),
+            polygons[0].1
+        );
+    }
+}
@@ -0,0 +1,110 @@
+// The pixels of a raster under a polygon, for zonal statistics. A pixel
+// is under the polygon if its center is, as GDAL and rasterstats decide
+// by default, so polygons smaller than a pixel may have none.
+use super::Raster;
+use crate::buffer;
+use geo_types::{Coord, Geometry};
+use std::ops::Range;
+
+// Pixels, of those from 0 to len, whose centers are from `from` to `to`
+fn centers(from: f64, to: f64, len: usize) -> Range<usize> {
+    let start = (from - 0.5).ceil().max(0.0) as usize;
+    let end = (to - 0.5).ceil().max(0.0) as usize;
+    start.min(len)..end.min(len)
+}
+
+// Values of the pixels whose centers are inside the geometry's polygons,
+// skipping nodata. Each row of pixel centers is scanned for where it
+// crosses the rings, and the pixels between each pair of crossings are
+// inside.
+pub fn values(raster: &Raster, geom: &Geometry<f64>) -> Vec<f64> {
+    let mut values = vec![];
+    for poly in buffer::polygons(geom) {
+        let rings: Vec<Vec<Coord<f64>>> = std::iter::once(poly.exterior())
+            .chain(poly.interiors())
+            .map(|ring| ring.0.iter().map(|&c| raster.to_pixel(c)).collect())
+            .collect();
+        let (min_y, max_y) = rings
+            .iter()
+            .flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), c| {
+                (min.min(c.y), max.max(c.y))
+            });
+        if !min_y.is_finite() {
+            continue;
+        }
+        for row in centers(min_y, max_y, raster.height) {
+            let y = row as f64 + 0.5;
+            let mut crossings: Vec<f64> = rings
+                .iter()
+                .flat_map(|ring| ring.windows(2))
+                .filter(|w| (w[0].y > y) != (w[1].y > y))
+                .map(|w| w[0].x + (y - w[0].y) * (w[1].x - w[0].x) / (w[1].y - w[0].y))
+                .collect();
+            crossings.sort_by(f64::total_cmp);
+            for pair in crossings.chunks_exact(2) {
+                for col in centers(pair[0], pair[1], raster.width) {
+                    let v = raster.get(col, row);
+                    if !raster.is_nodata(v) {
+                        values.push(v);
+                    }
+                }
+            }
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::values;
+    use crate::raster::{Crs, Raster};
+    use geo_types::{Geometry, Polygon};
+
+    // 4x4 pixels of 1 degree from 0,4 at the top left, numbered in
+    // row-major order, with 15 as nodata
+    fn raster() -> Raster {
+        Raster {
+            width: 4,
+            height: 4,
+            values: (0..16).map(f64::from).collect(),
+            nodata: Some(15.0),
+            transform: [0.0, 1.0, 0.0, 4.0, 0.0, -1.0],
+            crs: Crs::Geographic,
+        }
+    }
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Geometry<f64> {
+        Geometry::Polygon(Polygon::new(
+            vec![[x0, y0], [x1, y0], [x1, y1], [x0, y1], [x0, y0]].into(),
+            vec![],
+        ))
+    }
+
+    #[test]
+    fn test_values() {
+        // The top left 2x2 pixels
+        assert_eq!(
+            vec![0.0, 1.0, 4.0, 5.0],
+            values(&raster(), &rect(0.0, 2.0, 2.0, 4.0))
+        );
+        // Centers outside the polygon, and nodata, are left out
+        assert_eq!(
+            vec![10.0, 11.0, 14.0],
+            values(&raster(), &rect(1.6, -1.0, 9.0, 1.6))
+        );
+        // Smaller than a pixel, between centers
+        assert!(values(&raster(), &rect(0.1, 0.1, 0.4, 0.4)).is_empty());
+    }
+
+    #[test]
+    fn test_hole() {
+        let poly = Polygon::new(
+            vec![[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]].into(),
+            vec![vec![[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0], [1.0, 1.0]].into()],
+        );
+        let v = values(&raster(), &Geometry::Polygon(poly));
+        assert_eq!(11, v.len());
+        assert!(!v.contains(&5.0) && !v.contains(&10.0));
+    }
+}
@@ -0,0 +1,478 @@
+//! Reading the entities of stdin, or of any `BufRead`, one line at a time.
+extern crate geo_types;
+
+use crate::binary;
+use crate::entity::{self, Entity};
+use crate::error::Error;
+use crate::input;
+use crate::on_error;
+use crate::output;
+use crate::progress;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, StdinLock, Write};
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+use std::str;
+
+// Where a Reader's lines come from: a buffered reader, or a file mapped
+// into memory which lines are split out of in place
+enum Lines<'a> {
+    Buffered(&'a mut dyn BufRead),
+    Mapped(&'a [u8]),
+}
+
+impl<'a> Lines<'a> {
+    // Reads the next line into buf in place of the last one, so lines
+    // share its allocation
+    fn next(&mut self, buf: &mut String) -> Option<usize> {
+        buf.clear();
+        match self {
+            Lines::Buffered(reader) => read_line_into(&mut **reader, buf),
+            Lines::Mapped(data) => split_line(data).map(|(len, line)| {
+                buf.push_str(line);
+                len
+            }),
+        }
+    }
+}
+
+/// Reads the entities of each line of input in turn, keeping track of
+/// where in the input each came from.
+pub struct Reader<'a> {
+    lines: Lines<'a>,
+    entities: VecDeque<Entity>,
+    line: usize,
+    offset: u64,
+    next_offset: u64,
+    text: String,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads lines from a buffered reader.
+    pub fn new(reader: &'a mut dyn BufRead) -> Reader<'a> {
+        Reader::with_lines(Lines::Buffered(reader))
+    }
+
+    /// Reads lines straight from memory, without copying them first.
+    pub fn mapped(data: &'a [u8]) -> Reader<'a> {
+        Reader::with_lines(Lines::Mapped(data))
+    }
+
+    fn with_lines(lines: Lines<'a>) -> Reader<'a> {
+        Reader {
+            lines,
+            entities: VecDeque::new(),
+            line: 0,
+            offset: 0,
+            next_offset: 0,
+            text: String::new(),
+        }
+    }
+
+    /// The number of the line the last entity came from, starting at 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The line the last entity came from
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Adds the current line's location to an error
+    pub fn locate(&self, err: Error) -> Error {
+        err.at(self.line, self.offset, &self.text)
+    }
+}
+
+/// A line along with the number of bytes it took up, including its newline
+pub fn read_line_with_len(buf_read: &mut dyn BufRead) -> Option<(usize, String)> {
+    let mut buf = String::new();
+    read_line_into(buf_read, &mut buf).map(|n| (n, buf))
+}
+
+/// Appends the next line to buf, trimmed, giving the number of bytes it
+/// took up. Lets callers reuse one buffer rather than allocate each line.
+pub fn read_line_into(buf_read: &mut dyn BufRead, buf: &mut String) -> Option<usize> {
+    let start = buf.len();
+    match buf_read.read_line(buf) {
+        Ok(0) => None,
+        Ok(n) => {
+            progress::line(n);
+            let end = start + buf[start..].trim_end().len();
+            buf.truncate(end);
+            let leading = end - start - buf[start..].trim_start().len();
+            buf.drain(start..start + leading);
+            Some(n)
+        }
+        _ => {
+            buf.truncate(start);
+            None
+        }
+    }
+}
+
+/// Splits the next line off the front of data, along with the number of
+/// bytes it took up, without copying it. Like read_line_with_len, stops
+/// at invalid UTF-8.
+pub fn split_line<'a>(data: &mut &'a [u8]) -> Option<(usize, &'a str)> {
+    if data.is_empty() {
+        return None;
+    }
+    let len = memchr::memchr(b'\n', data).map_or(data.len(), |i| i + 1);
+    let (line, rest) = data.split_at(len);
+    *data = rest;
+    progress::line(len);
+    str::from_utf8(line).ok().map(|line| (len, line.trim()))
+}
+
+/// Stdin mapped into memory from wherever it's been read up to, when it's
+/// redirected from a non-empty regular file. The file mustn't change
+/// while it's being read.
+#[cfg(all(unix, feature = "mmap"))]
+pub fn map_stdin() -> Option<impl Deref<Target = [u8]>> {
+    use memmap2::MmapOptions;
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+    // Borrowed from stdin, which mustn't be closed when it's dropped
+    let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(io::stdin().as_raw_fd()) });
+    let metadata = file.metadata().ok()?;
+    let start = file.seek(SeekFrom::Current(0)).ok()?;
+    if !metadata.is_file() || start >= metadata.len() {
+        return None;
+    }
+    unsafe { MmapOptions::new().offset(start).map(&*file) }.ok()
+}
+
+/// Without the mmap feature, or off unix, stdin is always read as a stream
+#[cfg(not(all(unix, feature = "mmap")))]
+pub fn map_stdin() -> Option<impl Deref<Target = [u8]>> {
+    None::<Vec<u8>>
+}
+
+/// The next line, without its newline, or None at the end of the input.
+pub fn read_line(buf_read: &mut dyn BufRead) -> Option<String> {
+    read_line_with_len(buf_read).map(|(_, line)| line)
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = Result<Entity, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(entity) = self.entities.pop_front() {
+            return Some(Ok(entity));
+        }
+
+        while let Some(len) = self.lines.next(&mut self.text) {
+            self.line += 1;
+            self.offset = self.next_offset;
+            self.next_offset += len as u64;
+            match input::read_line(self.text.clone()).and_then(entity::from_input) {
+                Ok(e_vec) => {
+                    progress::entities(e_vec.len());
+                    self.entities.extend(e_vec);
+                    if let Some(e) = self.entities.pop_front() {
+                        return Some(Ok(e));
+                    }
+                }
+                Err(e) => {
+                    if let Err(e) = on_error::handle(&self.text, self.locate(e)) {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Output is written in chunks of this size
+pub const OUTPUT_BUF_SIZE: usize = 64 * 1024;
+
+// Stdin, which flushes buffered output before any read which might wait
+// for more input, so output isn't held back by a slow producer
+struct FlushOnWait<'a, W: Write> {
+    input: BufReader<StdinLock<'static>>,
+    out: &'a RefCell<W>,
+}
+
+impl<'a, W: Write> FlushOnWait<'a, W> {
+    fn flush_if_empty(&mut self) -> io::Result<()> {
+        if self.input.buffer().is_empty() {
+            self.out.borrow_mut().flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Read for FlushOnWait<'a, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.flush_if_empty()?;
+        self.input.read(buf)
+    }
+}
+
+impl<'a, W: Write> BufRead for FlushOnWait<'a, W> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.flush_if_empty()?;
+        self.input.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.input.consume(amt)
+    }
+}
+
+// Reads stdin, which may be in a binary format, with a Reader
+fn with_stdin<T, W, F>(out: &RefCell<W>, f: F) -> Result<T, Error>
+where
+    W: Write,
+    F: FnOnce(&mut Reader) -> T,
+{
+    // Files are read straight from memory; they never wait for input
+    if let Some(map) = map_stdin() {
+        return Ok(match binary::read(&mut &map[..])? {
+            Some(lines) => f(&mut Reader::mapped(&lines)),
+            None => f(&mut Reader::mapped(&map)),
+        });
+    }
+    let mut stdin_reader = FlushOnWait {
+        input: BufReader::with_capacity(OUTPUT_BUF_SIZE, io::stdin().lock()),
+        out,
+    };
+    Ok(match binary::read(&mut stdin_reader)? {
+        Some(lines) => f(&mut Reader::new(&mut &lines[..])),
+        None => f(&mut Reader::new(&mut stdin_reader)),
+    })
+}
+
+/// Hands the handler an iterator over stdin's entities, for commands which
+/// need more than one entity at a time.
+pub fn entities<F>(mut handler: F) -> Result<(), Error>
+where
+    F: FnMut(&mut dyn Iterator<Item = Result<Entity, Error>>) -> Result<(), Error>,
+{
+    with_stdin(&RefCell::new(io::sink()), |reader| handler(reader))?
+}
+
+fn for_entity_flushing<W, F>(out: &RefCell<W>, mut handler: F) -> Result<(), Error>
+where
+    W: Write,
+    F: FnMut(Entity) -> Result<(), Error>,
+{
+    with_stdin(out, |reader| {
+        while let Some(e_res) = reader.next() {
+            if let Err(e) = handler(e_res?) {
+                on_error::handle(reader.text(), reader.locate(e))?;
+            }
+        }
+        Ok(())
+    })?
+}
+
+/// Calls the handler with each entity of stdin in turn. Errors from the
+/// handler are dealt with as --on-error says.
+pub fn for_entity<F>(handler: F) -> Result<(), Error>
+where
+    F: FnMut(Entity) -> Result<(), Error>,
+{
+    for_entity_flushing(&RefCell::new(io::sink()), handler)
+}
+
+/// Like for_entity, for handlers which give back lines to print. Output
+/// is buffered, and flushed when reading would wait on stdin.
+pub fn for_entity_lines<F>(mut handler: F) -> Result<(), Error>
+where
+    F: FnMut(Entity) -> Result<Vec<String>, Error>,
+{
+    let stdout = io::stdout();
+    let out = RefCell::new(BufWriter::with_capacity(OUTPUT_BUF_SIZE, stdout.lock()));
+    let res = for_entity_flushing(&out, |e| {
+        let lines = handler(e)?;
+        let mut out = out.borrow_mut();
+        for l in lines {
+            output::write_line(&mut *out, &l)?;
+        }
+        Ok(())
+    });
+    out.borrow_mut().flush()?;
+    res
+}
+
+// The batching for for_entity_batches, writing the handler's lines to out.
+// An entity which can't be read ends the input, after the batch read
+// before it has been handled.
+fn write_batches<W, F>(
+    reader: &mut Reader,
+    out: &RefCell<W>,
+    size: usize,
+    handler: &mut F,
+) -> Result<(), Error>
+where
+    W: Write,
+    F: FnMut(Vec<Entity>) -> Vec<Result<Vec<String>, Error>>,
+{
+    let mut batch = vec![];
+    loop {
+        let mut failed = None;
+        let done = match reader.next() {
+            Some(Ok(e)) => {
+                // Kept to locate the entity's error, if it has one
+                batch.push((e, reader.line, reader.offset, reader.text.clone()));
+                false
+            }
+            Some(Err(e)) => {
+                failed = Some(e);
+                true
+            }
+            None => true,
+        };
+        if !done && batch.len() < size {
+            continue;
+        }
+        let (entities, places): (Vec<Entity>, Vec<_>) = batch
+            .drain(..)
+            .map(|(e, line, offset, text)| (e, (line, offset, text)))
+            .unzip();
+        let results = if entities.is_empty() {
+            vec![]
+        } else {
+            handler(entities)
+        };
+        for (res, (line, offset, text)) in results.into_iter().zip(places) {
+            match res {
+                Ok(lines) => {
+                    let mut out = out.borrow_mut();
+                    for l in lines {
+                        output::write_line(&mut *out, &l)?;
+                    }
+                }
+                Err(e) => on_error::handle(&text, e.at(line, offset, &text))?,
+            }
+        }
+        if let Some(e) = failed {
+            return Err(e);
+        }
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// Like for_entity_lines, for handlers which make slow requests for each
+/// entity and can run several at once. Entities are handed over in batches
+/// of up to `size`, and the handler gives back a result for each, in order.
+pub fn for_entity_batches<F>(size: usize, mut handler: F) -> Result<(), Error>
+where
+    F: FnMut(Vec<Entity>) -> Vec<Result<Vec<String>, Error>>,
+{
+    let stdout = io::stdout();
+    let out = RefCell::new(BufWriter::with_capacity(OUTPUT_BUF_SIZE, stdout.lock()));
+    let res = with_stdin(&out, |reader| {
+        write_batches(reader, &out, size, &mut handler)
+    })?;
+    out.borrow_mut().flush()?;
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{read_line_into, split_line, write_batches, Reader};
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_reading_empty_string() {
+        let mut pointer = "".as_bytes();
+        let reader = Reader::new(&mut pointer);
+        assert_eq!(0, reader.count());
+    }
+
+    #[test]
+    fn test_reading_single_line() {
+        let mut pointer = "9q5".as_bytes();
+        let reader = Reader::new(&mut pointer);
+        assert_eq!(1, reader.count());
+    }
+
+    #[test]
+    fn test_checking_character_for_single_line() {
+        let mut pointer = "9q5".as_bytes();
+        let mut reader = Reader::new(&mut pointer);
+        let gh = reader.next().unwrap().unwrap();
+        assert_eq!("9q5", gh.raw());
+    }
+
+    #[test]
+    fn test_reading_2_lines() {
+        let mut pointer = "9q5\n9q4".as_bytes();
+        let mut reader = Reader::new(&mut pointer);
+        let a = reader.next().unwrap();
+        let b = reader.next().unwrap();
+        assert_eq!("9q5", a.unwrap().raw());
+        assert_eq!("9q4", b.unwrap().raw());
+    }
+
+    #[test]
+    fn test_split_line() {
+        let mut data = "9q5\n  POINT (1 2) \r\n\n9q4".as_bytes();
+        assert_eq!(Some((4, "9q5")), split_line(&mut data));
+        assert_eq!(Some((16, "POINT (1 2)")), split_line(&mut data));
+        assert_eq!(Some((1, "")), split_line(&mut data));
+        assert_eq!(Some((3, "9q4")), split_line(&mut data));
+        assert_eq!(None, split_line(&mut data));
+        assert_eq!(None, split_line(&mut &b"\xff\n"[..]));
+    }
+
+    #[test]
+    fn test_read_line_into() {
+        let mut data = "9q5\n  POINT (1 2) \r\n".as_bytes();
+        let mut buf = "a".to_string();
+        assert_eq!(Some(4), read_line_into(&mut data, &mut buf));
+        assert_eq!(Some(16), read_line_into(&mut data, &mut buf));
+        assert_eq!("a9q5POINT (1 2)", buf);
+        assert_eq!(None, read_line_into(&mut data, &mut buf));
+        assert_eq!(None, read_line_into(&mut &b"\xff\n"[..], &mut buf));
+        assert_eq!("a9q5POINT (1 2)", buf);
+    }
+
+    #[test]
+    fn test_reading_mapped() {
+        let mut reader = Reader::mapped(b"9q5\nPOINT (1\n9q4\n");
+        assert_eq!("9q5", reader.next().unwrap().unwrap().raw());
+        let err = match reader.next() {
+            Some(Err(e)) => e,
+            _ => panic!("Expected an error for the second line"),
+        };
+        assert_eq!(4, err.location().unwrap().offset);
+        assert_eq!("9q4", reader.next().unwrap().unwrap().raw());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_error_location() {
+        let mut pointer = "9q5\nPOINT (1\n9q4".as_bytes();
+        let mut reader = Reader::new(&mut pointer);
+        assert!(reader.next().unwrap().is_ok());
+        let err = match reader.next() {
+            Some(Err(e)) => e,
+            _ => panic!("Expected an error for the second line"),
+        };
+        let location = err.location().unwrap();
+        assert_eq!(2, location.line);
+        assert_eq!(4, location.offset);
+        assert_eq!("POINT (1", location.input);
+    }
+
+    #[test]
+    fn test_batch_before_bad_line_is_handled() {
+        let mut pointer = "9q5\n9q4\nPOINT (1\n9q3".as_bytes();
+        let mut reader = Reader::new(&mut pointer);
+        let out = RefCell::new(vec![]);
+        let mut handler =
+            |es: Vec<crate::entity::Entity>| es.iter().map(|e| Ok(vec![e.raw()])).collect();
+        assert!(write_batches(&mut reader, &out, 10, &mut handler).is_err());
+        assert_eq!("9q5\n9q4\n", String::from_utf8(out.into_inner()).unwrap());
+    }
+}
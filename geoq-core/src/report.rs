@@ -0,0 +1,75 @@
+// Writes warnings and errors to stderr, as plain text or, with the
+// global --errors json flag, as one JSON object per line for tools to
+// consume:
+//
+//   {"level":"warning","kind":"InvalidGeoJSON","message":null,"line":12,"offset":340,"input":"{\"type\":..."}
+use crate::error::{truncate_input, Error};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+fn emit(level: &str, kind: &str, message: Option<&str>, err: Option<&Error>, input: Option<&str>) {
+    let mut report = json!({"level": level, "kind": kind, "message": message});
+    if let Some(location) = err.and_then(|e| e.location()) {
+        report["line"] = Value::from(location.line);
+        report["offset"] = Value::from(location.offset);
+        report["input"] = Value::from(location.input.as_str());
+    } else if let Some(input) = input {
+        report["input"] = Value::from(truncate_input(input));
+    }
+    eprintln!("{}", report);
+}
+
+// A problem with one input which doesn't stop the run, e.g. a place
+// with no geocoding results
+pub fn warning(message: &str, input: &str) {
+    if json() {
+        emit("warning", "Warning", Some(message), None, Some(input));
+    } else {
+        eprintln!("{}: {}", message, input);
+    }
+}
+
+// A note about the run as a whole
+pub fn info(message: &str) {
+    if json() {
+        emit("info", "Info", Some(message), None, None);
+    } else {
+        eprintln!("{}", message);
+    }
+}
+
+// An input skipped by --on-error. These are only counted in text output.
+pub fn skipped(err: &Error) {
+    if json() {
+        emit("warning", err.kind(), err.message(), Some(err), None);
+    }
+}
+
+// The error which stopped the run, with a prefix for text output
+pub fn fatal(prefix: &str, err: &Error) {
+    if json() {
+        emit("error", err.kind(), err.message(), Some(err), None);
+        return;
+    }
+    match err.location() {
+        Some(l) => eprintln!(
+            "{}: {:?}\n  at line {} (byte {}): {}",
+            prefix,
+            err.cause(),
+            l.line,
+            l.offset,
+            l.input
+        ),
+        None => eprintln!("{}: {:?}", prefix, err),
+    }
+}
@@ -0,0 +1,239 @@
+// Rhumb lines (loxodromes): paths of constant bearing, which cross every
+// meridian at the same angle and are straight on a Mercator chart. They're
+// what's steered in navigation, and a little longer than the great circle
+// between the same points. Worked on a sphere of the earth's mean radius,
+// after https://www.movable-type.co.uk/scripts/latlong.html.
+use crate::{buffer::EARTH_RADIUS, error::Error};
+use geo_types::{Coord, Geometry, LineString, Polygon};
+use std::f64::consts::{FRAC_PI_4, PI};
+
+// Difference of longitude in radians from a to b, the short way round
+fn delta_lon(a: Coord<f64>, b: Coord<f64>) -> f64 {
+    let d = (b.x - a.x).to_radians();
+    if d > PI {
+        d - 2.0 * PI
+    } else if d < -PI {
+        d + 2.0 * PI
+    } else {
+        d
+    }
+}
+
+// Difference of latitude in radians from a to b, and the same stretched
+// as on a Mercator chart
+fn delta_lat(a: Coord<f64>, b: Coord<f64>) -> (f64, f64) {
+    let (lat1, lat2) = (a.y.to_radians(), b.y.to_radians());
+    let stretched = ((lat2 / 2.0 + FRAC_PI_4).tan() / (lat1 / 2.0 + FRAC_PI_4).tan()).ln();
+    (lat2 - lat1, stretched)
+}
+
+// Ratio of the difference of latitude to its stretched difference, which
+// tends to the cosine of the latitude along an east-west line
+fn stretch(lat: f64, d_lat: f64, d_stretched: f64) -> f64 {
+    if d_stretched.abs() > 1e-12 {
+        d_lat / d_stretched
+    } else {
+        lat.cos()
+    }
+}
+
+/// Distance in meters from a to b along the rhumb line between them.
+pub fn distance(a: Coord<f64>, b: Coord<f64>) -> f64 {
+    let (d_lat, d_stretched) = delta_lat(a, b);
+    let q = stretch(a.y.to_radians(), d_lat, d_stretched);
+    d_lat.hypot(q * delta_lon(a, b)) * EARTH_RADIUS
+}
+
+/// Bearing in degrees clockwise from north, from 0 to 360, that's held
+/// all the way from a to b.
+pub fn bearing(a: Coord<f64>, b: Coord<f64>) -> f64 {
+    let (_, d_stretched) = delta_lat(a, b);
+    let degrees = delta_lon(a, b).atan2(d_stretched).to_degrees();
+    (degrees + 360.0) % 360.0
+}
+
+// Where a rhumb line reaches from a on a bearing in radians, through an
+// angle in radians at the earth's center, as long as it stays off the poles
+fn reach(a: Coord<f64>, theta: f64, angle: f64) -> Coord<f64> {
+    let lat = a.y + (angle * theta.cos()).to_degrees();
+    let b = Coord { x: a.x, y: lat };
+    let (d_lat, d_stretched) = delta_lat(a, b);
+    let q = stretch(a.y.to_radians(), d_lat, d_stretched);
+    let lon = a.x + (angle * theta.sin() / q).to_degrees();
+    Coord {
+        x: (lon + 540.0) % 360.0 - 180.0,
+        y: lat,
+    }
+}
+
+/// Where a rhumb line reaches from a, holding a bearing in degrees for a
+/// distance in meters. Lines due north or south go over a pole and come
+/// back down the meridian on the other side. On any other bearing a rhumb
+/// line spirals into the pole without crossing it, so reaching the pole is
+/// an error.
+pub fn destination(a: Coord<f64>, bearing: f64, meters: f64) -> Result<Coord<f64>, Error> {
+    let angle = meters / EARTH_RADIUS;
+    let theta = bearing.to_radians();
+    let lat = a.y + (angle * theta.cos()).to_degrees();
+    if lat.abs() < 90.0 {
+        return Ok(reach(a, theta, angle));
+    }
+    if theta.sin().abs() > 1e-12 {
+        return Err(Error::InvalidInput(format!(
+            "A rhumb line on bearing {} from {},{} spirals into the pole before {} meters",
+            bearing, a.y, a.x, meters
+        )));
+    }
+    // Latitude along the whole meridian circle, from -90 to 270, where
+    // past 90 is the far side of the pole
+    let around = (lat + 90.0).rem_euclid(360.0) - 90.0;
+    if around > 90.0 {
+        let x = a.x + 180.0;
+        Ok(Coord {
+            x: if x > 180.0 { x - 360.0 } else { x },
+            y: 180.0 - around,
+        })
+    } else {
+        Ok(Coord { x: a.x, y: around })
+    }
+}
+
+/// The rhumb line from a, holding a bearing in degrees for a distance in
+/// meters, with vertices at most `max_meters` apart. It fails where
+/// `destination` would.
+pub fn path(
+    a: Coord<f64>,
+    bearing: f64,
+    meters: f64,
+    max_meters: f64,
+) -> Result<LineString<f64>, Error> {
+    let steps = (meters / max_meters).ceil().max(1.0) as usize;
+    (0..=steps)
+        .map(|i| destination(a, bearing, meters * i as f64 / steps as f64))
+        .collect::<Result<Vec<_>, _>>()
+        .map(LineString)
+}
+
+/// The rhumb line from a to b, with vertices at most `max_meters` apart
+/// so that it keeps its course when drawn in lon/lat.
+pub fn line(a: Coord<f64>, b: Coord<f64>, max_meters: f64) -> LineString<f64> {
+    let (meters, bearing) = (distance(a, b), bearing(a, b));
+    let steps = (meters / max_meters).ceil().max(1.0) as usize;
+    let (theta, angle) = (bearing.to_radians(), meters / EARTH_RADIUS);
+    let mut coords = vec![a];
+    coords.extend((1..steps).map(|i| reach(a, theta, angle * i as f64 / steps as f64)));
+    coords.push(b);
+    LineString(coords)
+}
+
+fn line_string_length(ls: &LineString<f64>) -> f64 {
+    ls.lines().map(|l| distance(l.start, l.end)).sum()
+}
+
+fn poly_length(poly: &Polygon<f64>) -> f64 {
+    std::iter::once(poly.exterior())
+        .chain(poly.interiors())
+        .map(line_string_length)
+        .sum()
+}
+
+/// Length in meters of a geometry with rhumb lines between its vertices,
+/// measuring polygons by their perimeter, as `length::length` does along
+/// geodesics.
+pub fn length(geom: &Geometry<f64>) -> f64 {
+    match *geom {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => 0.0,
+        Geometry::Line(ref g) => distance(g.start, g.end),
+        Geometry::LineString(ref g) => line_string_length(g),
+        Geometry::MultiLineString(ref g) => g.0.iter().map(line_string_length).sum(),
+        Geometry::Polygon(ref g) => poly_length(g),
+        Geometry::Rect(ref g) => poly_length(&g.to_polygon()),
+        Geometry::Triangle(ref g) => poly_length(&g.to_polygon()),
+        Geometry::MultiPolygon(ref g) => g.0.iter().map(poly_length).sum(),
+        Geometry::GeometryCollection(ref gc) => gc.0.iter().map(length).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bearing, destination, distance, line, path, EARTH_RADIUS};
+    use geo_types::Coord;
+    use std::f64::consts::PI;
+
+    fn c(x: f64, y: f64) -> Coord<f64> {
+        Coord { x, y }
+    }
+
+    fn assert_near(expected: f64, actual: f64, tolerance: f64) {
+        assert!(
+            (expected - actual).abs() < tolerance,
+            "expected {} but got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_distance_and_bearing() {
+        // Along the equator and a meridian, rhumb lines are great circles
+        assert_near(111_195.08, distance(c(0.0, 0.0), c(1.0, 0.0)), 0.01);
+        assert_near(90.0, bearing(c(0.0, 0.0), c(1.0, 0.0)), 1e-9);
+        assert_near(180.0, bearing(c(0.0, 1.0), c(0.0, 0.0)), 1e-9);
+        // Dover to Calais, as worked on Movable Type
+        let (dover, calais) = (c(1.338, 51.127), c(1.853, 50.964));
+        assert_near(40_310.0, distance(dover, calais), 10.0);
+        assert_near(116.722, bearing(dover, calais), 0.001);
+        // The short way across the antimeridian
+        assert_near(270.0, bearing(c(-179.0, 0.0), c(179.0, 0.0)), 1e-9);
+        assert_near(222_390.16, distance(c(-179.0, 0.0), c(179.0, 0.0)), 0.01);
+    }
+
+    #[test]
+    fn test_destination() {
+        let (dover, calais) = (c(1.338, 51.127), c(1.853, 50.964));
+        let d = destination(dover, bearing(dover, calais), distance(dover, calais)).unwrap();
+        assert_near(calais.x, d.x, 1e-9);
+        assert_near(calais.y, d.y, 1e-9);
+        // East along a parallel, across the antimeridian
+        let d = destination(c(179.0, 60.0), 90.0, distance(c(0.0, 60.0), c(2.0, 60.0))).unwrap();
+        assert_near(-179.0, d.x, 1e-9);
+        assert_near(60.0, d.y, 1e-9);
+    }
+
+    #[test]
+    fn test_destination_past_pole() {
+        // Due north over the pole, and down the meridian opposite
+        let d = destination(c(0.0, 89.0), 0.0, 300_000.0).unwrap();
+        assert_near(180.0, d.x, 1e-9);
+        assert_near(88.302, d.y, 0.001);
+        let d = destination(c(10.0, -89.0), 180.0, 300_000.0).unwrap();
+        assert_near(-170.0, d.x, 1e-9);
+        assert_near(-88.302, d.y, 0.001);
+        // All the way round back to the start
+        let d = destination(c(10.0, 0.0), 0.0, 2.0 * PI * EARTH_RADIUS).unwrap();
+        assert_near(10.0, d.x, 1e-6);
+        assert_near(0.0, d.y, 1e-6);
+        // Any other bearing spirals into the pole
+        assert!(destination(c(0.0, 89.0), 45.0, 300_000.0).is_err());
+    }
+
+    #[test]
+    fn test_line() {
+        let ls = line(c(0.0, 0.0), c(10.0, 10.0), 200_000.0);
+        assert_eq!(9, ls.0.len());
+        assert_eq!((c(0.0, 0.0), c(10.0, 10.0)), (ls.0[0], ls.0[8]));
+        // Every step holds the same bearing
+        for l in ls.lines() {
+            assert_near(
+                bearing(c(0.0, 0.0), c(10.0, 10.0)),
+                bearing(l.start, l.end),
+                1e-6,
+            );
+        }
+        // Over the pole, where line would take the short rhumb line instead
+        let ls = path(c(0.0, 89.0), 0.0, 300_000.0, 100_000.0).unwrap();
+        assert_eq!(4, ls.0.len());
+        assert_near(0.0, ls.0[1].x, 1e-9);
+        assert_near(180.0, ls.0[3].x, 1e-9);
+    }
+}
@@ -0,0 +1,219 @@
+// Rebuilds polygons from their rings by where the rings lie, rather than
+// the order they're listed in. Shapefiles list all of a shape's rings
+// together, and data converted from them often has holes before their
+// shells, or islands in holes listed as holes. Each ring is nested in
+// the smallest ring containing it: rings nested an even number of deep
+// are shells and the rest are holes of the ring around them. Shells are
+// wound counterclockwise and holes clockwise, as RFC 7946 asks.
+//
+// Rings are GeoJSON positions, so Z and M values are kept.
+type Ring = Vec<Vec<f64>>;
+
+fn signed_area(ring: &Ring) -> f64 {
+    ring.windows(2)
+        .map(|w| w[0][0] * w[1][1] - w[1][0] * w[0][1])
+        .sum::<f64>()
+        / 2.0
+}
+
+struct Bounds {
+    min: (f64, f64),
+    max: (f64, f64),
+}
+
+impl Bounds {
+    fn of(ring: &Ring) -> Bounds {
+        let mut b = Bounds {
+            min: (f64::INFINITY, f64::INFINITY),
+            max: (f64::NEG_INFINITY, f64::NEG_INFINITY),
+        };
+        for p in ring {
+            b.min = (b.min.0.min(p[0]), b.min.1.min(p[1]));
+            b.max = (b.max.0.max(p[0]), b.max.1.max(p[1]));
+        }
+        b
+    }
+
+    fn contains(&self, other: &Bounds) -> bool {
+        self.min.0 <= other.min.0
+            && self.min.1 <= other.min.1
+            && self.max.0 >= other.max.0
+            && self.max.1 >= other.max.1
+    }
+}
+
+// Whether a point is inside a ring, or None if it's on its boundary
+fn inside(p: &[f64], ring: &Ring) -> Option<bool> {
+    let (x, y) = (p[0], p[1]);
+    let mut inside = false;
+    for w in ring.windows(2) {
+        let (x1, y1, x2, y2) = (w[0][0], w[0][1], w[1][0], w[1][1]);
+        let cross = (x2 - x1) * (y - y1) - (y2 - y1) * (x - x1);
+        if cross == 0.0 && x >= x1.min(x2) && x <= x1.max(x2) && y >= y1.min(y2) && y <= y1.max(y2)
+        {
+            return None;
+        }
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+    }
+    Some(inside)
+}
+
+// Whether one ring lies inside another, judged by the first of its
+// vertices that isn't on the other's boundary. Rings which touch only
+// at vertices are judged by the rest.
+fn within(ring: &Ring, other: &Ring) -> bool {
+    ring.iter().find_map(|p| inside(p, other)).unwrap_or(false)
+}
+
+fn wound(mut ring: Ring, counterclockwise: bool) -> Ring {
+    if (signed_area(&ring) > 0.0) != counterclockwise {
+        ring.reverse();
+    }
+    ring
+}
+
+// Polygons made from rings, as a Polygon if there's one shell or else a
+// MultiPolygon. Rings with fewer than 4 positions are dropped.
+pub fn rebuild(rings: Vec<Ring>) -> geojson::Value {
+    let mut rings: Vec<(f64, Bounds, Ring)> = rings
+        .into_iter()
+        .filter(|r| r.len() >= 4)
+        .map(|r| (signed_area(&r).abs(), Bounds::of(&r), r))
+        .collect();
+    // Larger rings first, so that each ring's possible parents come
+    // before it, nearest last
+    rings.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    // Each ring's parent, if any, and its depth
+    let mut parents: Vec<Option<usize>> = vec![];
+    let mut depths: Vec<usize> = vec![];
+    for (i, (_, bounds, ring)) in rings.iter().enumerate() {
+        let parent = (0..i).rev().find(|&j| {
+            let (_, outer_bounds, outer) = &rings[j];
+            outer_bounds.contains(bounds) && within(ring, outer)
+        });
+        depths.push(parent.map_or(0, |p| depths[p] + 1));
+        parents.push(parent);
+    }
+
+    // Shells, as indexes of polygons, and the rings of each polygon
+    let mut polygon_of: Vec<Option<usize>> = vec![None; rings.len()];
+    let mut polygons: Vec<Vec<Ring>> = vec![];
+    for (i, (_, _, ring)) in rings.into_iter().enumerate() {
+        if depths[i].is_multiple_of(2) {
+            polygon_of[i] = Some(polygons.len());
+            polygons.push(vec![wound(ring, true)]);
+        } else if let Some(p) = parents[i].and_then(|p| polygon_of[p]) {
+            polygons[p].push(wound(ring, false));
+        }
+    }
+    match polygons.len() {
+        1 => geojson::Value::Polygon(polygons.pop().unwrap()),
+        0 => geojson::Value::Polygon(vec![]),
+        _ => geojson::Value::MultiPolygon(polygons),
+    }
+}
+
+// The geometry with its polygons rebuilt. Other geometries are left as
+// they are.
+pub fn repair(value: geojson::Value) -> geojson::Value {
+    match value {
+        geojson::Value::Polygon(rings) => rebuild(rings),
+        geojson::Value::MultiPolygon(polygons) => match rebuild(polygons.concat()) {
+            geojson::Value::Polygon(p) => geojson::Value::MultiPolygon(vec![p]),
+            v => v,
+        },
+        geojson::Value::GeometryCollection(geoms) => geojson::Value::GeometryCollection(
+            geoms
+                .into_iter()
+                .map(|g| geojson::Geometry::new(repair(g.value)))
+                .collect(),
+        ),
+        v => v,
+    }
+}
+
+pub fn repair_feature(f: &mut geojson::Feature) {
+    if let Some(geom) = f.geometry.take() {
+        f.geometry = Some(geojson::Geometry {
+            value: repair(geom.value),
+            ..geom
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rebuild, repair};
+
+    fn square(x: f64, y: f64, size: f64) -> Vec<Vec<f64>> {
+        vec![
+            vec![x, y],
+            vec![x + size, y],
+            vec![x + size, y + size],
+            vec![x, y + size],
+            vec![x, y],
+        ]
+    }
+
+    fn reversed(mut ring: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+        ring.reverse();
+        ring
+    }
+
+    #[test]
+    fn test_hole_before_shell() {
+        let hole = square(1.0, 1.0, 1.0);
+        let shell = square(0.0, 0.0, 4.0);
+        assert_eq!(
+            geojson::Value::Polygon(vec![shell.clone(), reversed(hole.clone())]),
+            rebuild(vec![hole, shell])
+        );
+    }
+
+    #[test]
+    fn test_separate_shells() {
+        // Two shells listed as one polygon, as shapefiles do
+        let a = square(0.0, 0.0, 1.0);
+        let b = square(5.0, 5.0, 2.0);
+        assert_eq!(
+            geojson::Value::MultiPolygon(vec![vec![b.clone()], vec![a.clone()]]),
+            repair(geojson::Value::Polygon(vec![a, b]))
+        );
+    }
+
+    #[test]
+    fn test_island_in_hole() {
+        let shell = square(0.0, 0.0, 10.0);
+        let hole = square(2.0, 2.0, 6.0);
+        let island = square(4.0, 4.0, 2.0);
+        assert_eq!(
+            geojson::Value::MultiPolygon(vec![
+                vec![shell.clone(), reversed(hole.clone())],
+                vec![island.clone()]
+            ]),
+            repair(geojson::Value::MultiPolygon(vec![vec![
+                island,
+                reversed(shell),
+                hole
+            ]]))
+        );
+    }
+
+    #[test]
+    fn test_keeps_z() {
+        let ring: Vec<Vec<f64>> = square(0.0, 0.0, 1.0)
+            .into_iter()
+            .map(|mut p| {
+                p.push(7.0);
+                p
+            })
+            .collect();
+        match rebuild(vec![reversed(ring)]) {
+            geojson::Value::Polygon(p) => assert!(p[0].iter().all(|c| c[2] == 7.0)),
+            v => panic!("Expected a polygon: {:?}", v),
+        }
+    }
+}
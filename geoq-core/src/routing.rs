@@ -0,0 +1,340 @@
+// Clients for the OSRM (http://project-osrm.org/docs/v5.24.0/api/),
+// Valhalla (https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference/)
+// and openrouteservice (https://openrouteservice.org/dev/#/api-docs)
+// routing APIs
+use crate::{error::Error, net};
+use geo_types::{Coord, LineString};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Engine {
+    Osrm,
+    Valhalla,
+    Ors,
+}
+
+impl FromStr for Engine {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Engine, Error> {
+        match name {
+            "osrm" => Ok(Engine::Osrm),
+            "valhalla" => Ok(Engine::Valhalla),
+            "ors" => Ok(Engine::Ors),
+            _ => Err(Error::InvalidInput(format!(
+                "Unknown routing engine: {} -- expected osrm, valhalla or ors",
+                name
+            ))),
+        }
+    }
+}
+
+pub struct Route {
+    pub geometry: LineString<f64>,
+    // Seconds
+    pub duration: f64,
+    // Meters
+    pub distance: f64,
+}
+
+pub struct Client {
+    engine: Engine,
+    endpoint: String,
+    profile: String,
+    api_key: Option<String>,
+}
+
+// Valhalla calls its profiles "costing models", with their own names
+fn valhalla_costing(profile: &str) -> &str {
+    match profile {
+        "car" | "driving" => "auto",
+        "bike" | "cycling" => "bicycle",
+        "foot" | "walking" => "pedestrian",
+        _ => profile,
+    }
+}
+
+// openrouteservice profiles name the vehicle type as well as the mode
+fn ors_profile(profile: &str) -> &str {
+    match profile {
+        "car" | "driving" => "driving-car",
+        "bike" | "cycling" => "cycling-regular",
+        "foot" | "walking" => "foot-walking",
+        _ => profile,
+    }
+}
+
+// Error responses from Valhalla, other than for there being no route
+// between the locations (None)
+fn valhalla_error(body: &Value) -> Option<Error> {
+    let code = body["error_code"].as_i64()?;
+    // 442 and 443: no path between the locations.
+    // 171: no road near a location.
+    if code == 442 || code == 443 || code == 171 {
+        return None;
+    }
+    Some(Error::ProgramError(format!(
+        "Valhalla request failed: {}",
+        body["error"].as_str().unwrap_or(&body.to_string())
+    )))
+}
+
+// Decodes an encoded polyline (https://developers.google.com/maps/documentation/utilities/polylinealgorithm)
+// with the given number of decimal places. Valhalla uses 6.
+pub fn decode_polyline(encoded: &str, precision: i32) -> Result<LineString<f64>, Error> {
+    let factor = 10f64.powi(precision);
+    let mut coords = vec![];
+    let (mut lat, mut lon) = (0i64, 0i64);
+    let mut bytes = encoded.bytes();
+    let mut next = || -> Result<Option<i64>, Error> {
+        let (mut result, mut shift) = (0i64, 0);
+        loop {
+            let b = match bytes.next() {
+                Some(b) => b as i64 - 63,
+                None if shift == 0 => return Ok(None),
+                None => break,
+            };
+            if !(0..64).contains(&b) || shift > 60 {
+                return Err(Error::InvalidInput(format!(
+                    "Invalid encoded polyline: {}",
+                    encoded
+                )));
+            }
+            result |= (b & 0x1f) << shift;
+            shift += 5;
+            if b < 0x20 {
+                break;
+            }
+        }
+        Ok(Some(if result & 1 == 1 {
+            !(result >> 1)
+        } else {
+            result >> 1
+        }))
+    };
+    while let Some(dlat) = next()? {
+        let dlon = next()?
+            .ok_or_else(|| Error::InvalidInput(format!("Invalid encoded polyline: {}", encoded)))?;
+        lat += dlat;
+        lon += dlon;
+        coords.push(Coord {
+            x: lon as f64 / factor,
+            y: lat as f64 / factor,
+        });
+    }
+    Ok(LineString(coords))
+}
+
+impl Client {
+    pub fn new(engine: Engine, endpoint: &str, profile: &str) -> Client {
+        Client {
+            engine,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            profile: profile.to_string(),
+            api_key: None,
+        }
+    }
+
+    // Sent as the Authorization header, as openrouteservice requires
+    pub fn api_key(mut self, key: Option<&str>) -> Client {
+        self.api_key = key.map(|k| k.to_string());
+        self
+    }
+
+    // The fastest route through the waypoints in order, or None if the
+    // engine couldn't find one (e.g. between unconnected islands)
+    pub async fn route(&self, waypoints: &[Coord<f64>]) -> Result<Option<Route>, Error> {
+        match self.engine {
+            Engine::Osrm => self.osrm(waypoints).await,
+            Engine::Valhalla => self.valhalla(waypoints).await,
+            Engine::Ors => Err(Error::InvalidInput(
+                "Routing isn't supported with openrouteservice".to_string(),
+            )),
+        }
+    }
+
+    // Polygons of the area reachable from a point within each number of
+    // minutes, as (minutes, geometry), smallest first
+    pub async fn isochrone(
+        &self,
+        point: Coord<f64>,
+        minutes: &[f64],
+    ) -> Result<Vec<(f64, geojson::Geometry)>, Error> {
+        let (url, request) = match self.engine {
+            Engine::Valhalla => {
+                let contours: Vec<Value> = minutes.iter().map(|m| json!({ "time": m })).collect();
+                let request = json!({
+                    "locations": [{"lon": point.x, "lat": point.y}],
+                    "costing": valhalla_costing(&self.profile),
+                    "contours": contours,
+                    "polygons": true,
+                });
+                (format!("{}/isochrone", self.endpoint), request)
+            }
+            Engine::Ors => {
+                let range: Vec<f64> = minutes.iter().map(|m| m * 60.0).collect();
+                let request = json!({
+                    "locations": [[point.x, point.y]],
+                    "range": range,
+                    "range_type": "time",
+                });
+                let url = format!(
+                    "{}/v2/isochrones/{}",
+                    self.endpoint,
+                    ors_profile(&self.profile)
+                );
+                (url, request)
+            }
+            Engine::Osrm => {
+                return Err(Error::InvalidInput(
+                    "Isochrones aren't supported with OSRM".to_string(),
+                ))
+            }
+        };
+        let body = self.post(&url, &request).await?;
+        if self.engine == Engine::Valhalla {
+            if let Some(e) = valhalla_error(&body) {
+                return Err(e);
+            }
+        } else if let Some(error) = body.get("error") {
+            return Err(Error::ProgramError(format!(
+                "openrouteservice request failed: {}",
+                error["message"].as_str().unwrap_or(&error.to_string())
+            )));
+        }
+        let mut polygons = vec![];
+        for feature in body["features"].as_array().into_iter().flatten() {
+            // Valhalla gives the band in minutes, and openrouteservice
+            // in seconds
+            let band = match self.engine {
+                Engine::Valhalla => feature["properties"]["contour"].as_f64(),
+                _ => feature["properties"]["value"].as_f64().map(|s| s / 60.0),
+            };
+            let geometry: Option<geojson::Geometry> =
+                serde_json::from_value(feature["geometry"].clone())?;
+            if let (Some(band), Some(geometry)) = (band, geometry) {
+                polygons.push((band, geometry));
+            }
+        }
+        polygons.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Ok(polygons)
+    }
+
+    // POSTs a JSON request, returning the JSON response. Errors come with
+    // a JSON body describing them, so it's read whatever the status.
+    async fn post(&self, url: &str, request: &Value) -> Result<Value, Error> {
+        let mut req = net::client().post(url).json(request);
+        if let Some(key) = &self.api_key {
+            req = req.header("Authorization", key.as_str());
+        }
+        let (status, body) = net::text(req).await?;
+        serde_json::from_str(&body)
+            .map_err(|_| Error::ProgramError(format!("Request failed with {}: {}", status, url)))
+    }
+
+    async fn osrm(&self, waypoints: &[Coord<f64>]) -> Result<Option<Route>, Error> {
+        let coords: Vec<String> = waypoints
+            .iter()
+            .map(|c| format!("{},{}", c.x, c.y))
+            .collect();
+        let url = format!(
+            "{}/route/v1/{}/{}",
+            self.endpoint,
+            self.profile,
+            coords.join(";")
+        );
+        let request = net::client()
+            .get(&url)
+            .query(&[("overview", "full"), ("geometries", "geojson")]);
+        let (status, body) = net::text(request).await?;
+        // Errors come with a JSON body giving their code, so the body is
+        // read whatever the status
+        let body: Value = serde_json::from_str(&body).map_err(|_| {
+            Error::ProgramError(format!("OSRM request failed with {}: {}", status, url))
+        })?;
+        match body["code"].as_str() {
+            Some("Ok") => (),
+            Some("NoRoute") | Some("NoSegment") => return Ok(None),
+            _ => {
+                return Err(Error::ProgramError(format!(
+                    "OSRM request failed: {}",
+                    body["message"].as_str().unwrap_or(&body.to_string())
+                )))
+            }
+        }
+        let route = &body["routes"][0];
+        let geometry: geojson::Geometry = serde_json::from_value(route["geometry"].clone())?;
+        let geometry = match geometry.value {
+            geojson::Value::LineString(ls) => {
+                LineString(ls.iter().map(|p| Coord { x: p[0], y: p[1] }).collect())
+            }
+            _ => {
+                return Err(Error::ProgramError(format!(
+                    "Expected LineString route geometry from OSRM: {}",
+                    route["geometry"]
+                )))
+            }
+        };
+        Ok(Some(Route {
+            geometry,
+            duration: route["duration"].as_f64().unwrap_or(0.0),
+            distance: route["distance"].as_f64().unwrap_or(0.0),
+        }))
+    }
+
+    async fn valhalla(&self, waypoints: &[Coord<f64>]) -> Result<Option<Route>, Error> {
+        let locations: Vec<Value> = waypoints
+            .iter()
+            .map(|c| json!({"lon": c.x, "lat": c.y}))
+            .collect();
+        let request = json!({
+            "locations": locations,
+            "costing": valhalla_costing(&self.profile),
+            "units": "kilometers",
+        });
+        let body = self
+            .post(&format!("{}/route", self.endpoint), &request)
+            .await?;
+        if body.get("error_code").is_some() {
+            return match valhalla_error(&body) {
+                Some(e) => Err(e),
+                None => Ok(None),
+            };
+        }
+        let trip = &body["trip"];
+        // Each leg runs between consecutive locations, repeating the
+        // position where they meet
+        let mut coords: Vec<Coord<f64>> = vec![];
+        for leg in trip["legs"].as_array().into_iter().flatten() {
+            let shape = leg["shape"].as_str().unwrap_or("");
+            let line = decode_polyline(shape, 6)?;
+            let skip = if coords.last() == line.0.first() {
+                1
+            } else {
+                0
+            };
+            coords.extend(line.0.into_iter().skip(skip));
+        }
+        Ok(Some(Route {
+            geometry: LineString(coords),
+            duration: trip["summary"]["time"].as_f64().unwrap_or(0.0),
+            distance: trip["summary"]["length"].as_f64().unwrap_or(0.0) * 1000.0,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_polyline;
+    use geo_types::LineString;
+
+    #[test]
+    fn test_decode_polyline() {
+        assert_eq!(
+            LineString::from(vec![(-120.2, 38.5), (-120.95, 40.7), (-126.453, 43.252)]),
+            decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap()
+        );
+        assert!(decode_polyline("_p~iF", 5).is_err());
+    }
+}
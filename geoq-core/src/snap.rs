@@ -0,0 +1,88 @@
+// Nearest points on lines, for moving points such as GPS fixes onto a
+// road network. Distances are measured on a plane around the point, in
+// meters, which is close enough for the short distances points are
+// snapped over.
+use crate::buffer::EARTH_RADIUS;
+use geo_types::{Coord, Geometry, LineString};
+use std::f64::consts::PI;
+
+// The lines of a geometry: its line strings, or the rings of its
+// polygons. Points have none.
+fn lines(geom: &Geometry<f64>, out: &mut Vec<LineString<f64>>) {
+    match geom {
+        Geometry::Line(l) => out.push(LineString::from(vec![l.start, l.end])),
+        Geometry::LineString(ls) => out.push(ls.clone()),
+        Geometry::MultiLineString(mls) => out.extend(mls.0.iter().cloned()),
+        Geometry::Polygon(p) => {
+            out.push(p.exterior().clone());
+            out.extend(p.interiors().iter().cloned());
+        }
+        Geometry::MultiPolygon(mp) => {
+            mp.0.iter()
+                .for_each(|p| lines(&Geometry::Polygon(p.clone()), out))
+        }
+        Geometry::Rect(r) => out.push(r.to_polygon().exterior().clone()),
+        Geometry::Triangle(t) => out.push(t.to_polygon().exterior().clone()),
+        Geometry::GeometryCollection(gc) => gc.0.iter().for_each(|g| lines(g, out)),
+        Geometry::Point(_) | Geometry::MultiPoint(_) => (),
+    }
+}
+
+// The nearest point to p on the geometry's lines, and its distance from
+// p in meters, or None if the geometry has no lines
+pub fn nearest(p: Coord<f64>, geom: &Geometry<f64>) -> Option<(Coord<f64>, f64)> {
+    let y_scale = EARTH_RADIUS * PI / 180.0;
+    let x_scale = y_scale * p.y.to_radians().cos();
+    // Offsets from p in meters
+    let plane = |c: Coord<f64>| ((c.x - p.x) * x_scale, (c.y - p.y) * y_scale);
+
+    let mut ls = vec![];
+    lines(geom, &mut ls);
+    let mut best: Option<(Coord<f64>, f64)> = None;
+    for line in ls.iter().flat_map(|ls| ls.lines()) {
+        let (ax, ay) = plane(line.start);
+        let (bx, by) = plane(line.end);
+        let (dx, dy) = (bx - ax, by - ay);
+        let len2 = dx * dx + dy * dy;
+        let t = if len2 > 0.0 {
+            (-(ax * dx + ay * dy) / len2).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let distance = (ax + dx * t).hypot(ay + dy * t);
+        if best.is_none_or(|(_, d)| distance < d) {
+            let c = Coord {
+                x: line.start.x + (line.end.x - line.start.x) * t,
+                y: line.start.y + (line.end.y - line.start.y) * t,
+            };
+            best = Some((c, distance));
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::nearest;
+    use geo_types::{Coord, Geometry, LineString, Point};
+
+    #[test]
+    fn test_nearest() {
+        let road = Geometry::LineString(LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]));
+        let (c, d) = nearest(Coord { x: 0.5, y: 0.001 }, &road).unwrap();
+        assert_eq!(Coord { x: 0.5, y: 0.0 }, c);
+        assert!((d - 111.195).abs() < 0.01, "{}", d);
+
+        // Past the end of the line, the nearest point is its end
+        let (c, _) = nearest(Coord { x: 2.0, y: 2.0 }, &road).unwrap();
+        assert_eq!(Coord { x: 1.0, y: 1.0 }, c);
+
+        assert_eq!(
+            None,
+            nearest(
+                Coord { x: 0.0, y: 0.0 },
+                &Geometry::Point(Point::new(0.0, 0.0))
+            )
+        );
+    }
+}
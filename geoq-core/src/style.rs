@@ -0,0 +1,287 @@
+// Styling of features with simplestyle-spec properties, which map
+// previews such as geojson.io draw features with:
+// https://github.com/mapbox/simplestyle-spec/tree/master/1.1.0
+use crate::{
+    error::Error,
+    expr::{self, Expr, Scope},
+    fingerprint,
+};
+use geo_types::Geometry;
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+pub const KEYS: [&str; 10] = [
+    "title",
+    "description",
+    "marker-size",
+    "marker-symbol",
+    "marker-color",
+    "stroke",
+    "stroke-opacity",
+    "stroke-width",
+    "fill",
+    "fill-opacity",
+];
+
+// Tableau 10, whose colors are easy to tell apart
+const CATEGORY_COLORS: [&str; 10] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+    "#9c755f", "#bab0ac",
+];
+
+// A color for a category, the same for equal values in every run
+pub fn category_color(value: &Value) -> &'static str {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    };
+    CATEGORY_COLORS[(fingerprint::fnv1a(text.as_bytes()) % CATEGORY_COLORS.len() as u64) as usize]
+}
+
+// Palettes for geoq style, by name. Categorical palettes are of distinct
+// colors, and sequential ones run from low to high values.
+const PALETTES: [(&str, bool, &[&str]); 6] = [
+    ("tableau10", false, &CATEGORY_COLORS),
+    (
+        "set1",
+        false,
+        &[
+            "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#ffff33", "#a65628", "#f781bf",
+            "#999999",
+        ],
+    ),
+    (
+        "viridis",
+        true,
+        &[
+            "#440154", "#482878", "#3e4989", "#31688e", "#26828e", "#1f9e89", "#35b779", "#6ece58",
+            "#b5de2b", "#fde725",
+        ],
+    ),
+    (
+        "magma",
+        true,
+        &[
+            "#000004", "#180f3d", "#440f76", "#721f81", "#9e2f7f", "#cd4071", "#f1605d", "#fd9668",
+            "#feca8d", "#fcfdbf",
+        ],
+    ),
+    (
+        "blues",
+        true,
+        &[
+            "#f7fbff", "#deebf7", "#c6dbef", "#9ecae1", "#6baed6", "#4292c6", "#2171b5", "#08519c",
+            "#08306b",
+        ],
+    ),
+    (
+        "reds",
+        true,
+        &[
+            "#fff5f0", "#fee0d2", "#fcbba1", "#fc9272", "#fb6a4a", "#ef3b2c", "#cb181d", "#a50f15",
+            "#67000d",
+        ],
+    ),
+];
+
+pub const PALETTE_NAMES: [&str; 6] = ["tableau10", "set1", "viridis", "magma", "blues", "reds"];
+
+// Reads #rrggbb or #rgb
+fn parse_color(s: &str) -> Result<[u8; 3], Error> {
+    let invalid = || Error::InvalidInput(format!("Expected a color as #rrggbb or #rgb: {}", s));
+    let hex = s.trim().strip_prefix('#').ok_or_else(invalid)?;
+    let digits: Vec<u8> = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()
+        .ok_or_else(invalid)?;
+    match digits.as_slice() {
+        [r, g, b] => Ok([r * 17, g * 17, b * 17]),
+        [r1, r2, g1, g2, b1, b2] => Ok([r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2]),
+        _ => Err(invalid()),
+    }
+}
+
+fn hex_color(c: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2])
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    colors: Vec<[u8; 3]>,
+    sequential: bool,
+}
+
+// A palette's name, or its colors separated by commas. Palettes given
+// as colors are treated as categorical.
+impl FromStr for Palette {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((_, sequential, colors)) = PALETTES.iter().find(|(name, _, _)| *name == s) {
+            return Ok(Palette {
+                colors: colors
+                    .iter()
+                    .map(|c| parse_color(c))
+                    .collect::<Result<_, _>>()?,
+                sequential: *sequential,
+            });
+        }
+        if !s.starts_with('#') {
+            return Err(Error::InvalidInput(format!(
+                "Unknown palette: {} -- expected one of {}, or colors like #fee0d2,#de2d26",
+                s,
+                PALETTE_NAMES.join(", ")
+            )));
+        }
+        Ok(Palette {
+            colors: s.split(',').map(parse_color).collect::<Result<_, _>>()?,
+            sequential: false,
+        })
+    }
+}
+
+impl Palette {
+    // The color a fraction of the way along the palette, blending
+    // between its neighboring colors
+    pub fn interpolate(&self, t: f64) -> String {
+        let last = self.colors.len() - 1;
+        let pos = t.clamp(0.0, 1.0) * last as f64;
+        let i = (pos.floor() as usize).min(last);
+        let (a, b) = (self.colors[i], self.colors[(i + 1).min(last)]);
+        let f = pos - i as f64;
+        let mut c = [0; 3];
+        for k in 0..3 {
+            c[k] = (a[k] as f64 + (b[k] as f64 - a[k] as f64) * f).round() as u8;
+        }
+        hex_color(c)
+    }
+
+    // The color of the i-th of n categories. Categorical palettes repeat
+    // past their last color, and sequential ones are spread over them.
+    pub fn category(&self, i: usize, n: usize) -> String {
+        if self.sequential {
+            self.interpolate(if n > 1 {
+                i as f64 / (n - 1) as f64
+            } else {
+                0.0
+            })
+        } else {
+            hex_color(self.colors[i % self.colors.len()])
+        }
+    }
+}
+
+// Sets the style properties for drawing a feature in a color: fill and
+// stroke for polygons, stroke for lines, and marker-color for points
+pub fn set_color(geom: &Geometry<f64>, color: &str, props: &mut Map<String, Value>) {
+    let keys: &[&str] = match geom {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => &["marker-color"],
+        Geometry::Line(_) | Geometry::LineString(_) | Geometry::MultiLineString(_) => &["stroke"],
+        Geometry::Polygon(_)
+        | Geometry::MultiPolygon(_)
+        | Geometry::Rect(_)
+        | Geometry::Triangle(_) => &["fill", "stroke"],
+        Geometry::GeometryCollection(_) => &["marker-color", "stroke", "fill"],
+    };
+    for k in keys {
+        props.insert(k.to_string(), Value::String(color.to_string()));
+    }
+}
+
+// A style property computed from each feature, from a KEY:EXPRESSION
+// argument such as fill:color(props.landuse)
+pub struct Rule {
+    key: String,
+    value: Expr,
+}
+
+impl std::str::FromStr for Rule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s.split_once(':').ok_or_else(|| {
+            Error::InvalidInput(format!("Expected a style as KEY:EXPRESSION: {}", s))
+        })?;
+        let key = key.trim();
+        if !KEYS.contains(&key) {
+            return Err(Error::InvalidInput(format!(
+                "Unknown style property: {} -- expected one of {}",
+                key,
+                KEYS.join(", ")
+            )));
+        }
+        Ok(Rule {
+            key: key.to_string(),
+            value: expr::parse(value)?,
+        })
+    }
+}
+
+// Sets each rule's property, leaving those whose expression gives null
+pub fn apply(
+    rules: &[Rule],
+    geom: &Geometry<f64>,
+    props: &mut Map<String, Value>,
+) -> Result<(), Error> {
+    for rule in rules {
+        let value = rule.value.eval(&Scope { geom, props })?.to_json();
+        if !value.is_null() {
+            props.insert(rule.key.clone(), value);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, category_color, Palette, Rule};
+    use geo_types::{Geometry, Point};
+    use serde_json::json;
+
+    #[test]
+    fn test_category_color() {
+        assert_eq!(
+            category_color(&json!("park")),
+            category_color(&json!("park"))
+        );
+        assert!(category_color(&json!(3)).starts_with('#'));
+    }
+
+    #[test]
+    fn test_apply() {
+        let rules: Vec<Rule> = [
+            "fill:color(props.kind)",
+            "stroke-width: 2 * 3",
+            "title:props.none",
+        ]
+        .iter()
+        .map(|r| r.parse().unwrap())
+        .collect();
+        let mut props = json!({"kind": "park"}).as_object().unwrap().clone();
+        apply(&rules, &Geometry::Point(Point::new(0.0, 0.0)), &mut props).unwrap();
+        assert_eq!(json!(category_color(&json!("park"))), props["fill"]);
+        assert_eq!(json!(6), props["stroke-width"]);
+        assert_eq!(None, props.get("title"));
+
+        assert!("fill".parse::<Rule>().is_err());
+        assert!("color:\"#fff\"".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_palette() {
+        let viridis: Palette = "viridis".parse().unwrap();
+        assert_eq!("#440154", viridis.interpolate(0.0));
+        assert_eq!("#fde725", viridis.interpolate(1.0));
+        assert_eq!("#fde725", viridis.interpolate(2.0));
+        assert_eq!("#440154", viridis.category(0, 3));
+        assert_eq!("#fde725", viridis.category(2, 3));
+
+        let custom: Palette = "#000,#ffffff".parse().unwrap();
+        assert_eq!("#808080", custom.interpolate(0.5));
+        assert_eq!("#000000", custom.category(2, 3));
+
+        assert!("rainbow".parse::<Palette>().is_err());
+        assert!("#00g".parse::<Palette>().is_err());
+    }
+}
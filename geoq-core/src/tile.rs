@@ -0,0 +1,277 @@
+//! z/x/y Web Mercator tiles and their quadkeys.
+use crate::{bbox, contains, error::Error};
+use geo_types::{Coord, Geometry, Point, Polygon, Rect};
+use std::{f64::consts::PI, fmt, str::FromStr};
+
+/// Web Mercator is undefined at the poles, so latitudes are clamped to
+/// the square extent used by slippy map tiles
+pub const MAX_LAT: f64 = 85.051_128_779_806_59;
+/// Deepest zoom whose x and y fit a u32.
+pub const MAX_ZOOM: u8 = 31;
+
+/// A slippy map tile at zoom z, counting x east from the antimeridian
+/// and y south from the top of the map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tile {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.z, self.x, self.y)
+    }
+}
+
+impl FromStr for Tile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Tile, Error> {
+        let invalid = || {
+            Error::InvalidInput(format!(
+                "Invalid tile: {} -- expected z/x/y, e.g. 12/654/1583",
+                s
+            ))
+        };
+        let pieces: Vec<&str> = s.trim().split('/').collect();
+        if pieces.len() != 3 {
+            return Err(invalid());
+        }
+        let z = pieces[0].parse::<u8>().map_err(|_| invalid())?;
+        let x = pieces[1].parse::<u32>().map_err(|_| invalid())?;
+        let y = pieces[2].parse::<u32>().map_err(|_| invalid())?;
+        Tile::new(z, x, y).ok_or_else(invalid)
+    }
+}
+
+fn tiles_at_zoom(z: u8) -> u64 {
+    1u64 << z
+}
+
+fn lon_to_x(lon: f64, z: u8) -> u32 {
+    let n = tiles_at_zoom(z) as f64;
+    let x = ((lon + 180.0) / 360.0 * n).floor();
+    x.clamp(0.0, n - 1.0) as u32
+}
+
+fn lat_to_y(lat: f64, z: u8) -> u32 {
+    let n = tiles_at_zoom(z) as f64;
+    let lat = lat.clamp(-MAX_LAT, MAX_LAT).to_radians();
+    let y = ((1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n).floor();
+    y.clamp(0.0, n - 1.0) as u32
+}
+
+fn x_to_lon(x: u32, z: u8) -> f64 {
+    x as f64 / tiles_at_zoom(z) as f64 * 360.0 - 180.0
+}
+
+fn y_to_lat(y: u32, z: u8) -> f64 {
+    let n = PI - 2.0 * PI * y as f64 / tiles_at_zoom(z) as f64;
+    n.sinh().atan().to_degrees()
+}
+
+/// The zoom, or an error if it's past [`MAX_ZOOM`].
+pub fn validate_zoom(z: u8) -> Result<u8, Error> {
+    if z > MAX_ZOOM {
+        Err(Error::InvalidInput(format!(
+            "Invalid zoom: {} -- expected number from 0 to {}",
+            z, MAX_ZOOM
+        )))
+    } else {
+        Ok(z)
+    }
+}
+
+impl Tile {
+    /// The tile, if x and y are within the zoom's grid.
+    pub fn new(z: u8, x: u32, y: u32) -> Option<Tile> {
+        let n = tiles_at_zoom(z);
+        if z <= MAX_ZOOM && (x as u64) < n && (y as u64) < n {
+            Some(Tile { z, x, y })
+        } else {
+            None
+        }
+    }
+
+    /// The tile at zoom z containing a lon/lat point.
+    pub fn for_point(p: &Point<f64>, z: u8) -> Tile {
+        Tile {
+            z,
+            x: lon_to_x(p.x(), z),
+            y: lat_to_y(p.y(), z),
+        }
+    }
+
+    /// The tile's extent in lon/lat.
+    pub fn bbox(&self) -> Rect<f64> {
+        Rect::new(
+            Coord {
+                x: x_to_lon(self.x, self.z),
+                y: y_to_lat(self.y + 1, self.z),
+            },
+            Coord {
+                x: x_to_lon(self.x + 1, self.z),
+                y: y_to_lat(self.y, self.z),
+            },
+        )
+    }
+
+    /// The tile's extent as a polygon.
+    pub fn polygon(&self) -> Polygon<f64> {
+        self.bbox().to_polygon()
+    }
+
+    /// The tile one zoom up containing this one, or None at zoom 0.
+    pub fn parent(&self) -> Option<Tile> {
+        if self.z == 0 {
+            None
+        } else {
+            Some(Tile {
+                z: self.z - 1,
+                x: self.x / 2,
+                y: self.y / 2,
+            })
+        }
+    }
+
+    /// Bing Maps quadkey: one base-4 digit per zoom level, from the
+    /// most significant bit of x (1) and y (2)
+    pub fn quadkey(&self) -> String {
+        (1..=self.z)
+            .rev()
+            .map(|i| {
+                let mask = 1 << (i - 1);
+                let mut digit = 0;
+                if self.x & mask != 0 {
+                    digit += 1;
+                }
+                if self.y & mask != 0 {
+                    digit += 2;
+                }
+                std::char::from_digit(digit, 4).unwrap()
+            })
+            .collect()
+    }
+
+    /// The tile named by a Bing Maps quadkey.
+    pub fn from_quadkey(quadkey: &str) -> Result<Tile, Error> {
+        let quadkey = quadkey.trim();
+        if quadkey.len() > MAX_ZOOM as usize {
+            return Err(Error::InvalidInput(format!(
+                "Invalid quadkey: {} -- longer than max zoom {}",
+                quadkey, MAX_ZOOM
+            )));
+        }
+        let mut tile = Tile { z: 0, x: 0, y: 0 };
+        for c in quadkey.chars() {
+            let digit = c.to_digit(4).ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Invalid quadkey: {} -- expected digits 0-3",
+                    quadkey
+                ))
+            })?;
+            tile = Tile {
+                z: tile.z + 1,
+                x: tile.x * 2 + (digit & 1),
+                y: tile.y * 2 + (digit >> 1),
+            };
+        }
+        Ok(tile)
+    }
+
+    /// The four tiles one zoom down within this one.
+    pub fn children(&self) -> Vec<Tile> {
+        let (z, x, y) = (self.z + 1, self.x * 2, self.y * 2);
+        vec![
+            Tile { z, x, y },
+            Tile { z, x: x + 1, y },
+            Tile { z, x, y: y + 1 },
+            Tile {
+                z,
+                x: x + 1,
+                y: y + 1,
+            },
+        ]
+    }
+}
+
+/// Tiles at zoom z intersecting the geometry, in row-major order
+pub fn covering(geom: &Geometry<f64>, z: u8) -> Vec<Tile> {
+    use geo::algorithm::intersects::Intersects;
+    let rect = bbox::bbox(geom);
+    let (min_x, max_x) = (lon_to_x(rect.min().x, z), lon_to_x(rect.max().x, z));
+    let (min_y, max_y) = (lat_to_y(rect.max().y, z), lat_to_y(rect.min().y, z));
+    let mut tiles = vec![];
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let tile = Tile { z, x, y };
+            let poly = tile.polygon();
+            if poly.intersects(geom) || contains::contains(&poly, geom) {
+                tiles.push(tile);
+            }
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{covering, Tile};
+    use geo_types::{Geometry, LineString, Point};
+
+    #[test]
+    fn test_for_point() {
+        let p = Point::new(-118.2437, 34.0522);
+        assert_eq!(Tile { z: 0, x: 0, y: 0 }, Tile::for_point(&p, 0));
+        assert_eq!(
+            Tile {
+                z: 12,
+                x: 702,
+                y: 1635
+            },
+            Tile::for_point(&p, 12)
+        );
+        let edge = Point::new(180.0, -90.0);
+        assert_eq!(Tile { z: 1, x: 1, y: 1 }, Tile::for_point(&edge, 1));
+    }
+
+    #[test]
+    fn test_bbox_and_parse() {
+        let t: Tile = "1/0/0".parse().unwrap();
+        let bbox = t.bbox();
+        assert_eq!(-180.0, bbox.min().x);
+        assert_eq!(0.0, bbox.min().y);
+        assert_eq!(0.0, bbox.max().x);
+        assert!((bbox.max().y - super::MAX_LAT).abs() < 1e-9);
+        assert_eq!("1/0/0", t.to_string());
+        assert!("1/2/0".parse::<Tile>().is_err());
+        assert!("1/0".parse::<Tile>().is_err());
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let t = Tile { z: 3, x: 5, y: 2 };
+        for c in t.children() {
+            assert_eq!(Some(t), c.parent());
+        }
+        assert_eq!(None, Tile { z: 0, x: 0, y: 0 }.parent());
+    }
+
+    #[test]
+    fn test_covering() {
+        let line = Geometry::LineString(LineString::from(vec![(-10.0, 10.0), (10.0, 10.0)]));
+        let tiles: Vec<String> = covering(&line, 2).iter().map(|t| t.to_string()).collect();
+        assert_eq!(vec!["2/1/1", "2/2/1"], tiles);
+    }
+
+    #[test]
+    fn test_quadkey() {
+        let t = Tile { z: 3, x: 3, y: 5 };
+        assert_eq!("213", t.quadkey());
+        assert_eq!(t, Tile::from_quadkey("213").unwrap());
+        assert_eq!("", Tile { z: 0, x: 0, y: 0 }.quadkey());
+        assert_eq!(Tile { z: 0, x: 0, y: 0 }, Tile::from_quadkey("").unwrap());
+        assert!(Tile::from_quadkey("014").is_err());
+    }
+}
@@ -0,0 +1,129 @@
+// MBTiles 1.3: https://github.com/mapbox/mbtiles-spec/blob/master/1.3/spec.md
+use crate::{
+    error::Error,
+    tile::{self, Tile},
+    tiles::{gunzip, gzip, Metadata},
+};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde_json::json;
+use std::path::Path;
+
+pub struct Writer {
+    conn: Connection,
+}
+
+impl Writer {
+    pub fn create(path: &Path) -> Result<Writer, Error> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE metadata (name TEXT, value TEXT);
+             CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+             CREATE UNIQUE INDEX tile_index ON tiles (zoom_level, tile_column, tile_row);
+             BEGIN;",
+        )?;
+        Ok(Writer { conn })
+    }
+
+    fn write_metadata(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        self.conn.execute(
+            "INSERT INTO metadata (name, value) VALUES (?1, ?2)",
+            params![name, value],
+        )?;
+        Ok(())
+    }
+}
+
+impl super::Writer for Writer {
+    // Tile data is gzipped, as MBTiles readers expect for pbf tiles.
+    // Rows are stored in the TMS scheme, with y increasing northward.
+    fn write_tile(&mut self, t: Tile, data: &[u8]) -> Result<(), Error> {
+        let data = gzip(data)?;
+        self.conn.execute(
+            "INSERT INTO tiles (zoom_level, tile_column, tile_row, tile_data) VALUES (?1, ?2, ?3, ?4)",
+            params![t.z, t.x, tms_row(t), data],
+        )?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>, metadata: &Metadata) -> Result<(), Error> {
+        self.write_metadata("name", &metadata.name)?;
+        self.write_metadata("format", "pbf")?;
+        self.write_metadata("type", "overlay")?;
+        self.write_metadata("minzoom", &metadata.min_zoom.to_string())?;
+        self.write_metadata("maxzoom", &metadata.max_zoom.to_string())?;
+        if let Some(b) = metadata.bounds {
+            let center = b.center();
+            self.write_metadata(
+                "bounds",
+                &format!("{},{},{},{}", b.min().x, b.min().y, b.max().x, b.max().y),
+            )?;
+            self.write_metadata(
+                "center",
+                &format!("{},{},{}", center.x, center.y, metadata.min_zoom),
+            )?;
+        }
+        let json = json!({ "vector_layers": metadata.vector_layers });
+        self.write_metadata("json", &json.to_string())?;
+        self.conn.execute_batch("COMMIT;")?;
+        Ok(())
+    }
+}
+
+pub struct Reader {
+    conn: Connection,
+}
+
+impl Reader {
+    pub fn open(path: &Path) -> Result<Reader, Error> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Reader { conn })
+    }
+}
+
+fn tms_row(t: Tile) -> u64 {
+    (1u64 << t.z) - 1 - t.y as u64
+}
+
+impl super::Reader for Reader {
+    fn tile(&mut self, t: Tile) -> Result<Option<Vec<u8>>, Error> {
+        let data: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT tile_data FROM tiles WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+                params![t.z, t.x, tms_row(t)],
+                |row| row.get(0),
+            )
+            .optional()?;
+        data.map(gunzip).transpose()
+    }
+
+    fn tiles(&mut self) -> Result<Vec<Tile>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT zoom_level, tile_column, tile_row FROM tiles")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, u8>(0)?,
+                row.get::<_, u32>(1)?,
+                row.get::<_, u64>(2)?,
+            ))
+        })?;
+        let mut tiles = vec![];
+        for row in rows {
+            let (z, x, row) = row?;
+            let y = tile::validate_zoom(z)
+                .ok()
+                .and_then(|z| ((1u64 << z) - 1).checked_sub(row));
+            match y.and_then(|y| Tile::new(z, x, y as u32)) {
+                Some(t) => tiles.push(t),
+                None => {
+                    return Err(Error::InvalidInput(format!(
+                        "Invalid tile in MBTiles archive: {}/{}/{} (TMS)",
+                        z, x, row
+                    )))
+                }
+            }
+        }
+        Ok(tiles)
+    }
+}
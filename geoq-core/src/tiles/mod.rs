@@ -0,0 +1,528 @@
+// Vector tile pyramid generation: features are projected to Web Mercator,
+// simplified once per zoom in that zoom's pixel space, then clipped to each
+// tile they touch and encoded as MVT.
+use crate::{
+    entity::Entity,
+    error::Error,
+    generalize,
+    tile::{self, Tile},
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use geo::algorithm::{area::Area, euclidean_length::EuclideanLength, map_coords::MapCoords};
+use geo_types::{
+    Coord, Geometry, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon, Rect,
+};
+use profile::Profile;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+#[cfg(feature = "sqlite")]
+pub mod mbtiles;
+pub mod mvt;
+pub mod pmtiles;
+pub mod profile;
+
+pub const DEFAULT_EXTENT: u32 = 4096;
+pub const DEFAULT_BUFFER: u32 = 64;
+
+#[derive(Clone)]
+pub struct Options {
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    pub layer: String,
+    pub extent: u32,
+    // Pixels (in extent units) included around each tile, so that
+    // lines and polygon edges don't show seams at tile boundaries
+    pub buffer: u32,
+    // Simplification tolerance, in extent units
+    pub tolerance: f64,
+    // Tolerances, size thresholds and properties for particular zooms
+    pub profile: Profile,
+}
+
+impl Options {
+    pub fn new(min_zoom: u8, max_zoom: u8, layer: &str) -> Options {
+        Options {
+            min_zoom,
+            max_zoom,
+            layer: layer.to_string(),
+            extent: DEFAULT_EXTENT,
+            buffer: DEFAULT_BUFFER,
+            tolerance: 1.0,
+            profile: Profile::default(),
+        }
+    }
+}
+
+// Archive formats which tiles can be written to. Tiles are given
+// uncompressed, in the order they're built.
+pub trait Writer {
+    fn write_tile(&mut self, t: Tile, data: &[u8]) -> Result<(), Error>;
+    fn finish(self: Box<Self>, metadata: &Metadata) -> Result<(), Error>;
+}
+
+pub fn gzip(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut gz = GzEncoder::new(vec![], Compression::default());
+    gz.write_all(data)?;
+    Ok(gz.finish()?)
+}
+
+// Decompresses gzipped data, passing through anything else,
+// since archives don't always compress their tiles
+pub fn gunzip(data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        let mut out = vec![];
+        GzDecoder::new(data.as_slice()).read_to_end(&mut out)?;
+        Ok(out)
+    } else {
+        Ok(data)
+    }
+}
+
+pub trait Reader {
+    // Uncompressed tile data, or None if the archive has no such tile
+    fn tile(&mut self, t: Tile) -> Result<Option<Vec<u8>>, Error>;
+    fn tiles(&mut self) -> Result<Vec<Tile>, Error>;
+}
+
+// Opens an MBTiles or PMTiles archive, detected from its magic bytes
+pub fn open(path: &Path) -> Result<Box<dyn Reader>, Error> {
+    let mut magic = [0; 7];
+    File::open(path)?.read_exact(&mut magic)?;
+    if &magic == b"PMTiles" {
+        Ok(Box::new(pmtiles::Reader::open(path)?))
+    } else if &magic == b"SQLite " {
+        #[cfg(feature = "sqlite")]
+        return Ok(Box::new(mbtiles::Reader::open(path)?));
+        #[cfg(not(feature = "sqlite"))]
+        return Err(Error::InvalidInput(format!(
+            "Can't read MBTiles archive {} -- geoq was built without the sqlite feature",
+            path.display()
+        )));
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Unrecognized tile archive: {} -- expected MBTiles or PMTiles",
+            path.display()
+        )))
+    }
+}
+
+pub(crate) fn from_world(c: Coord<f64>) -> Coord<f64> {
+    Coord {
+        x: c.x * 360.0 - 180.0,
+        y: (PI * (1.0 - 2.0 * c.y)).sinh().atan().to_degrees(),
+    }
+}
+
+// Converts a geometry in a tile's coordinates (0 to extent) to lon/lat
+pub fn unproject(geom: &Geometry<f64>, t: Tile, extent: u32) -> Geometry<f64> {
+    let n = (1u64 << t.z) as f64;
+    let extent = extent as f64;
+    geom.map_coords(|c| {
+        from_world(Coord {
+            x: (t.x as f64 + c.x / extent) / n,
+            y: (t.y as f64 + c.y / extent) / n,
+        })
+    })
+}
+
+pub struct Metadata {
+    pub name: String,
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    pub bounds: Option<Rect<f64>>,
+    // TileJSON vector_layers, describing the layer's fields
+    pub vector_layers: Value,
+}
+
+impl Metadata {
+    pub fn new(source: &Source, opts: &Options) -> Metadata {
+        Metadata {
+            name: opts.layer.clone(),
+            min_zoom: opts.min_zoom,
+            max_zoom: opts.max_zoom,
+            bounds: source.bounds(),
+            vector_layers: json!([{
+                "id": opts.layer,
+                "fields": source.fields(),
+                "minzoom": opts.min_zoom,
+                "maxzoom": opts.max_zoom,
+            }]),
+        }
+    }
+}
+
+// A single-type piece of an input feature, in world Mercator
+// coordinates from 0 to 1 with y increasing southward
+struct Part {
+    feature: usize,
+    geom: Geometry<f64>,
+    // Area of polygons or length of lines, in world coordinates
+    size: f64,
+}
+
+fn size(geom: &Geometry<f64>) -> f64 {
+    match geom {
+        Geometry::MultiLineString(mls) => mls.euclidean_length(),
+        Geometry::MultiPolygon(mp) => mp.unsigned_area(),
+        _ => 0.0,
+    }
+}
+
+pub struct Feature {
+    id: Option<u64>,
+    props: Map<String, Value>,
+}
+
+pub struct Source {
+    features: Vec<Feature>,
+    parts: Vec<Part>,
+    bounds: Option<Rect<f64>>,
+}
+
+pub(crate) fn to_world(c: Coord<f64>) -> Coord<f64> {
+    let lat = c.y.clamp(-tile::MAX_LAT, tile::MAX_LAT).to_radians();
+    Coord {
+        x: (c.x + 180.0) / 360.0,
+        y: (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0,
+    }
+}
+
+// MVT features hold one geometry type each, so collections
+// are split into separate point, line, and polygon parts
+fn split(
+    geom: Geometry<f64>,
+    points: &mut Vec<Point<f64>>,
+    lines: &mut Vec<LineString<f64>>,
+    polys: &mut Vec<Polygon<f64>>,
+) {
+    match geom {
+        Geometry::Point(p) => points.push(p),
+        Geometry::MultiPoint(mp) => points.extend(mp.0),
+        Geometry::Line(l) => lines.push(LineString::from(l)),
+        Geometry::LineString(ls) => lines.push(ls),
+        Geometry::MultiLineString(mls) => lines.extend(mls.0),
+        Geometry::Polygon(p) => polys.push(p),
+        Geometry::MultiPolygon(mp) => polys.extend(mp.0),
+        Geometry::Rect(r) => polys.push(r.to_polygon()),
+        Geometry::Triangle(t) => polys.push(t.to_polygon()),
+        Geometry::GeometryCollection(gc) => {
+            for g in gc {
+                split(g, points, lines, polys);
+            }
+        }
+    }
+}
+
+fn expand(bounds: Option<Rect<f64>>, r: Rect<f64>) -> Rect<f64> {
+    match bounds {
+        None => r,
+        Some(b) => Rect::new(
+            Coord {
+                x: b.min().x.min(r.min().x),
+                y: b.min().y.min(r.min().y),
+            },
+            Coord {
+                x: b.max().x.max(r.max().x),
+                y: b.max().y.max(r.max().y),
+            },
+        ),
+    }
+}
+
+impl Source {
+    pub fn new() -> Source {
+        Source {
+            features: vec![],
+            parts: vec![],
+            bounds: None,
+        }
+    }
+
+    pub fn add(&mut self, e: &Entity) {
+        if e.is_empty() {
+            return;
+        }
+        let id = e.id().and_then(|id| match id {
+            geojson::feature::Id::Number(n) => n.as_u64(),
+            _ => None,
+        });
+        self.bounds = Some(expand(self.bounds, e.bbox()));
+        self.features.push(Feature {
+            id,
+            props: e.geojson_properties(),
+        });
+        let feature = self.features.len() - 1;
+
+        let (mut points, mut lines, mut polys) = (vec![], vec![], vec![]);
+        split(e.geom(), &mut points, &mut lines, &mut polys);
+        let mut geoms = vec![];
+        if !points.is_empty() {
+            geoms.push(Geometry::MultiPoint(MultiPoint(points)));
+        }
+        if !lines.is_empty() {
+            geoms.push(Geometry::MultiLineString(MultiLineString(lines)));
+        }
+        if !polys.is_empty() {
+            geoms.push(Geometry::MultiPolygon(MultiPolygon(polys)));
+        }
+        for geom in geoms {
+            let geom = geom.map_coords(to_world);
+            self.parts.push(Part {
+                feature,
+                size: size(&geom),
+                geom,
+            });
+        }
+    }
+
+    // Lon/lat bounds of all features, clamped to the Web Mercator extent
+    pub fn bounds(&self) -> Option<Rect<f64>> {
+        self.bounds.map(|b| {
+            Rect::new(
+                Coord {
+                    x: b.min().x.max(-180.0),
+                    y: b.min().y.max(-tile::MAX_LAT),
+                },
+                Coord {
+                    x: b.max().x.min(180.0),
+                    y: b.max().y.min(tile::MAX_LAT),
+                },
+            )
+        })
+    }
+
+    // Property names and their MVT field types ("String", "Number",
+    // or "Boolean"), for the vector_layers metadata
+    pub fn fields(&self) -> BTreeMap<String, &'static str> {
+        let mut fields = BTreeMap::new();
+        for f in &self.features {
+            for (k, v) in &f.props {
+                let t = match v {
+                    Value::Null => continue,
+                    Value::Number(_) => "Number",
+                    Value::Bool(_) => "Boolean",
+                    _ => "String",
+                };
+                let existing = fields.entry(k.clone()).or_insert(t);
+                if *existing != t {
+                    *existing = "String";
+                }
+            }
+        }
+        fields
+    }
+}
+
+impl Default for Source {
+    fn default() -> Source {
+        Source::new()
+    }
+}
+
+// A part clipped to one tile's buffered bounds, still in world coordinates
+struct Piece {
+    feature: usize,
+    size: f64,
+    tile: Tile,
+    geom: Geometry<f64>,
+    bbox: Rect<f64>,
+}
+
+fn bounding_rect(geom: &Geometry<f64>) -> Option<Rect<f64>> {
+    use geo::algorithm::bounding_rect::BoundingRect;
+    geom.bounding_rect()
+}
+
+// Tile bounds in world coordinates, expanded by the buffer
+fn buffered_bounds(t: Tile, opts: &Options) -> Rect<f64> {
+    let n = (1u64 << t.z) as f64;
+    let b = opts.buffer as f64 / opts.extent as f64;
+    Rect::new(
+        Coord {
+            x: (t.x as f64 - b) / n,
+            y: (t.y as f64 - b) / n,
+        },
+        Coord {
+            x: (t.x as f64 + 1.0 + b) / n,
+            y: (t.y as f64 + 1.0 + b) / n,
+        },
+    )
+}
+
+fn contains(r: &Rect<f64>, c: &Coord<f64>) -> bool {
+    c.x >= r.min().x && c.x <= r.max().x && c.y >= r.min().y && c.y <= r.max().y
+}
+
+fn within(inner: &Rect<f64>, outer: &Rect<f64>) -> bool {
+    contains(outer, &inner.min()) && contains(outer, &inner.max())
+}
+
+fn overlaps(a: &Rect<f64>, b: &Rect<f64>) -> bool {
+    a.min().x <= b.max().x
+        && b.min().x <= a.max().x
+        && a.min().y <= b.max().y
+        && b.min().y <= a.max().y
+}
+
+fn clip(geom: &Geometry<f64>, bbox: &Rect<f64>, clip_rect: &Rect<f64>) -> Option<Geometry<f64>> {
+    if !overlaps(bbox, clip_rect) {
+        return None;
+    }
+    if within(bbox, clip_rect) {
+        return Some(geom.clone());
+    }
+    generalize::clip(geom, clip_rect)
+}
+
+// Converts a piece to its tile's coordinates, simplified to the
+// tile's resolution
+fn tile_geometry(piece: &Piece, tolerance: f64, opts: &Options) -> Geometry<f64> {
+    let n = (1u64 << piece.tile.z) as f64;
+    let extent = opts.extent as f64;
+    let (x, y) = (piece.tile.x as f64, piece.tile.y as f64);
+    let projected = piece.geom.map_coords(|c| Coord {
+        x: (c.x * n - x) * extent,
+        y: (c.y * n - y) * extent,
+    });
+    generalize::simplify(&projected, tolerance)
+}
+
+// Clips each piece to the children of its tile. Descending the tile
+// tree this way only visits tiles which the features actually touch,
+// and each clip works on the already-clipped parent piece.
+fn descend(pieces: Vec<Piece>, opts: &Options) -> Vec<Piece> {
+    let mut children = vec![];
+    for piece in pieces {
+        for tile in piece.tile.children() {
+            if let Some(geom) = clip(&piece.geom, &piece.bbox, &buffered_bounds(tile, opts)) {
+                if let Some(bbox) = bounding_rect(&geom) {
+                    children.push(Piece {
+                        feature: piece.feature,
+                        size: piece.size,
+                        tile,
+                        geom,
+                        bbox,
+                    });
+                }
+            }
+        }
+    }
+    children
+}
+
+// Whether the whole of a piece's feature part is smaller than the rule
+// allows, at the piece's zoom
+fn too_small(piece: &Piece, rule: &profile::Rule, opts: &Options) -> bool {
+    let scale = (1u64 << piece.tile.z) as f64 * opts.extent as f64;
+    match piece.geom {
+        Geometry::MultiLineString(_) => piece.size * scale < rule.min_length,
+        Geometry::MultiPolygon(_) => piece.size * scale * scale < rule.min_area,
+        _ => false,
+    }
+}
+
+fn add_piece(layer: &mut mvt::Layer, source: &Source, piece: &Piece, opts: &Options) {
+    let feature = &source.features[piece.feature];
+    match opts.profile.rule(piece.tile.z) {
+        None => {
+            layer.add_feature(
+                feature.id,
+                &feature.props,
+                &tile_geometry(piece, opts.tolerance, opts),
+            );
+        }
+        Some(rule) if !too_small(piece, rule, opts) => {
+            let tolerance = rule.tolerance.unwrap_or(opts.tolerance);
+            layer.add_feature(
+                feature.id,
+                &rule.properties(&feature.props),
+                &tile_geometry(piece, tolerance, opts),
+            );
+        }
+        Some(_) => (),
+    }
+}
+
+// Encodes a single tile, clipping each feature directly to it. Returns
+// None if no features fall within the tile.
+pub fn build_tile(source: &Source, t: Tile, opts: &Options) -> Option<Vec<u8>> {
+    let bounds = buffered_bounds(t, opts);
+    let mut layer = mvt::Layer::new(&opts.layer, opts.extent);
+    for part in &source.parts {
+        let bbox = match bounding_rect(&part.geom) {
+            Some(b) => b,
+            None => continue,
+        };
+        if let Some(geom) = clip(&part.geom, &bbox, &bounds) {
+            let piece = Piece {
+                feature: part.feature,
+                size: part.size,
+                tile: t,
+                bbox: bounding_rect(&geom).unwrap_or(bbox),
+                geom,
+            };
+            add_piece(&mut layer, source, &piece, opts);
+        }
+    }
+    if layer.is_empty() {
+        None
+    } else {
+        Some(mvt::encode_tile(&[layer]))
+    }
+}
+
+// Lon/lat bounds of a tile, expanded by the buffer
+pub fn buffered_lon_lat_bounds(t: Tile, opts: &Options) -> Rect<f64> {
+    let b = buffered_bounds(t, opts);
+    Rect::new(from_world(b.min()), from_world(b.max()))
+}
+
+// Encode each non-empty tile from min_zoom to max_zoom, passing the
+// uncompressed MVT bytes to the handler in z/x/y order
+pub fn build<F>(source: &Source, opts: &Options, mut handler: F) -> Result<(), Error>
+where
+    F: FnMut(Tile, Vec<u8>) -> Result<(), Error>,
+{
+    let mut pieces: Vec<Piece> = source
+        .parts
+        .iter()
+        .filter_map(|part| {
+            bounding_rect(&part.geom).map(|bbox| Piece {
+                feature: part.feature,
+                size: part.size,
+                tile: Tile { z: 0, x: 0, y: 0 },
+                geom: part.geom.clone(),
+                bbox,
+            })
+        })
+        .collect();
+    for z in 0..=opts.max_zoom {
+        if z >= opts.min_zoom {
+            let mut layers: BTreeMap<(u32, u32), mvt::Layer> = BTreeMap::new();
+            for piece in &pieces {
+                add_piece(
+                    layers
+                        .entry((piece.tile.x, piece.tile.y))
+                        .or_insert_with(|| mvt::Layer::new(&opts.layer, opts.extent)),
+                    source,
+                    piece,
+                    opts,
+                );
+            }
+            for ((x, y), layer) in layers {
+                if !layer.is_empty() {
+                    handler(Tile { z, x, y }, mvt::encode_tile(&[layer]))?;
+                }
+            }
+        }
+        if z < opts.max_zoom {
+            pieces = descend(pieces, opts);
+        }
+    }
+    Ok(())
+}
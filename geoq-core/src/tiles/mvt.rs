@@ -0,0 +1,663 @@
+// Minimal Mapbox Vector Tile (v2.1) encoder.
+// Spec: https://github.com/mapbox/vector-tile-spec/tree/master/2.1
+use crate::error::Error;
+use geo_types::{
+    Coord, Geometry, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon,
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+const WIRE_VARINT: u32 = 0;
+const WIRE_FIXED64: u32 = 1;
+const WIRE_BYTES: u32 = 2;
+
+const CMD_MOVE_TO: u32 = 1;
+const CMD_LINE_TO: u32 = 2;
+const CMD_CLOSE_PATH: u32 = 7;
+
+const GEOM_POINT: u64 = 1;
+const GEOM_LINESTRING: u64 = 2;
+const GEOM_POLYGON: u64 = 3;
+
+fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn write_tag(buf: &mut Vec<u8>, field: u32, wire_type: u32) {
+    write_varint(buf, ((field << 3) | wire_type) as u64);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_tag(buf, field, WIRE_BYTES);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn write_packed(buf: &mut Vec<u8>, field: u32, values: &[u32]) {
+    let mut packed = vec![];
+    for v in values {
+        write_varint(&mut packed, *v as u64);
+    }
+    write_bytes(buf, field, &packed);
+}
+
+fn zigzag(v: i64) -> u32 {
+    ((v << 1) ^ (v >> 63)) as u32
+}
+
+fn command(id: u32, count: usize) -> u32 {
+    (id & 0x7) | ((count as u32) << 3)
+}
+
+// Tracks the cursor position, since MVT coordinates are deltas
+// from the previous point, across all parts of a feature
+struct GeomEncoder {
+    cursor: (i64, i64),
+    out: Vec<u32>,
+}
+
+impl GeomEncoder {
+    fn new() -> GeomEncoder {
+        GeomEncoder {
+            cursor: (0, 0),
+            out: vec![],
+        }
+    }
+
+    fn point(&mut self, (x, y): (i64, i64)) {
+        self.out.push(zigzag(x - self.cursor.0));
+        self.out.push(zigzag(y - self.cursor.1));
+        self.cursor = (x, y);
+    }
+
+    fn points(&mut self, points: &[(i64, i64)]) {
+        self.out.push(command(CMD_MOVE_TO, points.len()));
+        for p in points {
+            self.point(*p);
+        }
+    }
+
+    fn path(&mut self, points: &[(i64, i64)], close: bool) {
+        self.out.push(command(CMD_MOVE_TO, 1));
+        self.point(points[0]);
+        self.out.push(command(CMD_LINE_TO, points.len() - 1));
+        for p in &points[1..] {
+            self.point(*p);
+        }
+        if close {
+            self.out.push(command(CMD_CLOSE_PATH, 1));
+        }
+    }
+}
+
+fn round(c: &Coord<f64>) -> (i64, i64) {
+    (c.x.round() as i64, c.y.round() as i64)
+}
+
+fn line_points(ls: &LineString<f64>) -> Vec<(i64, i64)> {
+    let mut points: Vec<(i64, i64)> = ls.0.iter().map(round).collect();
+    points.dedup();
+    points
+}
+
+// Twice the signed area. Positive is clockwise, since tile y points down
+fn ring_area(points: &[(i64, i64)]) -> i64 {
+    let mut area = 0;
+    for i in 0..points.len() {
+        let (x1, y1) = points[i];
+        let (x2, y2) = points[(i + 1) % points.len()];
+        area += x1 * y2 - x2 * y1;
+    }
+    area
+}
+
+// Closing point removed, and wound clockwise for exterior rings
+// or counter-clockwise for interior rings, as the spec requires
+fn ring_points(ring: &LineString<f64>, exterior: bool) -> Option<Vec<(i64, i64)>> {
+    let mut points = line_points(ring);
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    let area = ring_area(&points);
+    if points.len() < 3 || area == 0 {
+        return None;
+    }
+    if (area > 0) != exterior {
+        points.reverse();
+    }
+    Some(points)
+}
+
+fn encode_polygon(enc: &mut GeomEncoder, poly: &Polygon<f64>) {
+    if let Some(exterior) = ring_points(poly.exterior(), true) {
+        enc.path(&exterior, true);
+        for interior in poly.interiors() {
+            if let Some(points) = ring_points(interior, false) {
+                enc.path(&points, true);
+            }
+        }
+    }
+}
+
+// Geometry in tile coordinates, as (type, encoded commands).
+// Returns None for geometries which are empty after rounding
+// to integer coordinates.
+fn encode_geometry(geom: &Geometry<f64>) -> Option<(u64, Vec<u32>)> {
+    let mut enc = GeomEncoder::new();
+    let geom_type = match geom {
+        Geometry::Point(p) => {
+            enc.points(&[round(&p.0)]);
+            GEOM_POINT
+        }
+        Geometry::MultiPoint(mp) => {
+            let points: Vec<(i64, i64)> = mp.0.iter().map(|p| round(&p.0)).collect();
+            if !points.is_empty() {
+                enc.points(&points);
+            }
+            GEOM_POINT
+        }
+        Geometry::LineString(ls) => {
+            let points = line_points(ls);
+            if points.len() > 1 {
+                enc.path(&points, false);
+            }
+            GEOM_LINESTRING
+        }
+        Geometry::MultiLineString(mls) => {
+            for ls in mls.0.iter() {
+                let points = line_points(ls);
+                if points.len() > 1 {
+                    enc.path(&points, false);
+                }
+            }
+            GEOM_LINESTRING
+        }
+        Geometry::Polygon(poly) => {
+            encode_polygon(&mut enc, poly);
+            GEOM_POLYGON
+        }
+        Geometry::MultiPolygon(mp) => {
+            for poly in mp.0.iter() {
+                encode_polygon(&mut enc, poly);
+            }
+            GEOM_POLYGON
+        }
+        _ => return None,
+    };
+    if enc.out.is_empty() {
+        None
+    } else {
+        Some((geom_type, enc.out))
+    }
+}
+
+fn encode_value(v: &Value) -> Vec<u8> {
+    let mut buf = vec![];
+    match v {
+        Value::String(s) => write_bytes(&mut buf, 1, s.as_bytes()),
+        Value::Bool(b) => {
+            write_tag(&mut buf, 7, WIRE_VARINT);
+            write_varint(&mut buf, *b as u64);
+        }
+        Value::Number(n) if n.is_i64() => {
+            write_tag(&mut buf, 6, WIRE_VARINT);
+            let i = n.as_i64().unwrap();
+            write_varint(&mut buf, ((i << 1) ^ (i >> 63)) as u64);
+        }
+        Value::Number(n) => {
+            write_tag(&mut buf, 3, WIRE_FIXED64);
+            buf.extend_from_slice(&n.as_f64().unwrap_or(0.0).to_le_bytes());
+        }
+        // Nested values have no MVT representation, so are stored as JSON strings
+        other => write_bytes(&mut buf, 1, other.to_string().as_bytes()),
+    }
+    buf
+}
+
+pub struct Layer {
+    pub name: String,
+    pub extent: u32,
+    features: Vec<Vec<u8>>,
+    keys: Vec<String>,
+    key_index: HashMap<String, u32>,
+    values: Vec<Vec<u8>>,
+    value_index: HashMap<Vec<u8>, u32>,
+}
+
+impl Layer {
+    pub fn new(name: &str, extent: u32) -> Layer {
+        Layer {
+            name: name.to_string(),
+            extent,
+            features: vec![],
+            keys: vec![],
+            key_index: HashMap::new(),
+            values: vec![],
+            value_index: HashMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    fn tags(&mut self, props: &Map<String, Value>) -> Vec<u32> {
+        let mut tags = vec![];
+        for (k, v) in props {
+            if v.is_null() {
+                continue;
+            }
+            let next_key = self.keys.len() as u32;
+            let key = *self.key_index.entry(k.clone()).or_insert(next_key);
+            if key == next_key {
+                self.keys.push(k.clone());
+            }
+            let encoded = encode_value(v);
+            let next_value = self.values.len() as u32;
+            let value = *self
+                .value_index
+                .entry(encoded.clone())
+                .or_insert(next_value);
+            if value == next_value {
+                self.values.push(encoded);
+            }
+            tags.push(key);
+            tags.push(value);
+        }
+        tags
+    }
+
+    // Geometry must already be in tile coordinates (0 to extent, y down).
+    // Returns false if the geometry was empty once rounded to the tile grid.
+    pub fn add_feature(
+        &mut self,
+        id: Option<u64>,
+        props: &Map<String, Value>,
+        geom: &Geometry<f64>,
+    ) -> bool {
+        let (geom_type, commands) = match encode_geometry(geom) {
+            Some(g) => g,
+            None => return false,
+        };
+        let tags = self.tags(props);
+        let mut buf = vec![];
+        if let Some(id) = id {
+            write_tag(&mut buf, 1, WIRE_VARINT);
+            write_varint(&mut buf, id);
+        }
+        if !tags.is_empty() {
+            write_packed(&mut buf, 2, &tags);
+        }
+        write_tag(&mut buf, 3, WIRE_VARINT);
+        write_varint(&mut buf, geom_type);
+        write_packed(&mut buf, 4, &commands);
+        self.features.push(buf);
+        true
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        write_tag(&mut buf, 15, WIRE_VARINT);
+        write_varint(&mut buf, 2);
+        write_bytes(&mut buf, 1, self.name.as_bytes());
+        for f in &self.features {
+            write_bytes(&mut buf, 2, f);
+        }
+        for k in &self.keys {
+            write_bytes(&mut buf, 3, k.as_bytes());
+        }
+        for v in &self.values {
+            write_bytes(&mut buf, 4, v);
+        }
+        write_tag(&mut buf, 5, WIRE_VARINT);
+        write_varint(&mut buf, self.extent as u64);
+        buf
+    }
+}
+
+pub fn encode_tile(layers: &[Layer]) -> Vec<u8> {
+    let mut buf = vec![];
+    for layer in layers.iter().filter(|l| !l.is_empty()) {
+        write_bytes(&mut buf, 3, &layer.encode());
+    }
+    buf
+}
+
+fn invalid() -> Error {
+    Error::InvalidInput("Invalid vector tile data".to_string())
+}
+
+// Protobuf fields of a single message, as (field number, value)
+enum Field<'a> {
+    Varint(u64),
+    Fixed64([u8; 8]),
+    Fixed32([u8; 4]),
+    Bytes(&'a [u8]),
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64, Error> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = *buf.get(*pos).ok_or_else(invalid)?;
+        *pos += 1;
+        v |= ((b & 0x7f) as u64) << shift;
+        if b < 0x80 {
+            return Ok(v);
+        }
+    }
+    Err(invalid())
+}
+
+fn take<'a>(buf: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8], Error> {
+    let bytes = buf.get(*pos..*pos + n).ok_or_else(invalid)?;
+    *pos += n;
+    Ok(bytes)
+}
+
+fn read_fields(buf: &[u8]) -> Result<Vec<(u32, Field<'_>)>, Error> {
+    let mut pos = 0;
+    let mut fields = vec![];
+    while pos < buf.len() {
+        let tag = read_varint(buf, &mut pos)?;
+        let field = match tag & 0x7 {
+            0 => Field::Varint(read_varint(buf, &mut pos)?),
+            1 => Field::Fixed64(take(buf, &mut pos, 8)?.try_into().unwrap()),
+            2 => {
+                let len = read_varint(buf, &mut pos)? as usize;
+                Field::Bytes(take(buf, &mut pos, len)?)
+            }
+            5 => Field::Fixed32(take(buf, &mut pos, 4)?.try_into().unwrap()),
+            _ => return Err(invalid()),
+        };
+        fields.push(((tag >> 3) as u32, field));
+    }
+    Ok(fields)
+}
+
+fn read_packed(buf: &[u8]) -> Result<Vec<u32>, Error> {
+    let mut pos = 0;
+    let mut values = vec![];
+    while pos < buf.len() {
+        values.push(read_varint(buf, &mut pos)? as u32);
+    }
+    Ok(values)
+}
+
+fn unzigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+fn decode_value(buf: &[u8]) -> Result<Value, Error> {
+    let mut value = Value::Null;
+    for (field, v) in read_fields(buf)? {
+        value = match (field, v) {
+            (1, Field::Bytes(b)) => Value::String(String::from_utf8_lossy(b).to_string()),
+            (2, Field::Fixed32(b)) => Value::from(f32::from_le_bytes(b) as f64),
+            (3, Field::Fixed64(b)) => Value::from(f64::from_le_bytes(b)),
+            (4, Field::Varint(v)) => Value::from(v as i64),
+            (5, Field::Varint(v)) => Value::from(v),
+            (6, Field::Varint(v)) => Value::from(unzigzag(v)),
+            (7, Field::Varint(v)) => Value::Bool(v != 0),
+            _ => continue,
+        };
+    }
+    Ok(value)
+}
+
+// Rings and paths in tile coordinates, split at each MoveTo
+fn decode_paths(commands: &[u32]) -> Result<Vec<Vec<Coord<f64>>>, Error> {
+    let mut paths: Vec<Vec<Coord<f64>>> = vec![];
+    let (mut x, mut y) = (0i64, 0i64);
+    let mut i = 0;
+    while i < commands.len() {
+        let (id, count) = (commands[i] & 0x7, (commands[i] >> 3) as usize);
+        i += 1;
+        match id {
+            CMD_MOVE_TO | CMD_LINE_TO => {
+                for _ in 0..count {
+                    let dx = *commands.get(i).ok_or_else(invalid)?;
+                    let dy = *commands.get(i + 1).ok_or_else(invalid)?;
+                    i += 2;
+                    x += unzigzag(dx as u64);
+                    y += unzigzag(dy as u64);
+                    let c = Coord {
+                        x: x as f64,
+                        y: y as f64,
+                    };
+                    match paths.last_mut() {
+                        Some(path) if id == CMD_LINE_TO => path.push(c),
+                        _ => paths.push(vec![c]),
+                    }
+                }
+            }
+            CMD_CLOSE_PATH => {
+                if let Some(path) = paths.last_mut() {
+                    if let Some(first) = path.first().cloned() {
+                        path.push(first);
+                    }
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(paths)
+}
+
+fn decode_geometry(geom_type: u64, commands: &[u32]) -> Result<Geometry<f64>, Error> {
+    let paths = decode_paths(commands)?;
+    match geom_type {
+        GEOM_POINT => {
+            let points: Vec<Point<f64>> = paths.into_iter().flatten().map(Point).collect();
+            if points.len() == 1 {
+                Ok(Geometry::Point(points[0]))
+            } else {
+                Ok(Geometry::MultiPoint(MultiPoint(points)))
+            }
+        }
+        GEOM_LINESTRING => {
+            let mut lines: Vec<LineString<f64>> = paths.into_iter().map(LineString).collect();
+            if lines.len() == 1 {
+                Ok(Geometry::LineString(lines.pop().unwrap()))
+            } else {
+                Ok(Geometry::MultiLineString(MultiLineString(lines)))
+            }
+        }
+        GEOM_POLYGON => {
+            // Each clockwise ring starts a new polygon, and is followed
+            // by that polygon's counter-clockwise interior rings
+            let mut polys: Vec<Polygon<f64>> = vec![];
+            for path in paths {
+                let points: Vec<(i64, i64)> = path.iter().map(round).collect();
+                let ring = LineString(path);
+                match polys.last_mut() {
+                    Some(poly) if ring_area(&points) < 0 => poly.interiors_push(ring),
+                    _ => polys.push(Polygon::new(ring, vec![])),
+                }
+            }
+            if polys.len() == 1 {
+                Ok(Geometry::Polygon(polys.pop().unwrap()))
+            } else {
+                Ok(Geometry::MultiPolygon(MultiPolygon(polys)))
+            }
+        }
+        _ => Err(invalid()),
+    }
+}
+
+pub struct DecodedFeature {
+    pub id: Option<u64>,
+    pub props: Map<String, Value>,
+    // In tile coordinates, from 0 to the layer's extent
+    pub geom: Geometry<f64>,
+}
+
+pub struct DecodedLayer {
+    pub name: String,
+    pub extent: u32,
+    pub features: Vec<DecodedFeature>,
+}
+
+fn decode_feature(buf: &[u8], keys: &[String], values: &[Value]) -> Result<DecodedFeature, Error> {
+    let mut id = None;
+    let mut props = Map::new();
+    let mut geom_type = 0;
+    let mut commands = vec![];
+    for (field, v) in read_fields(buf)? {
+        match (field, v) {
+            (1, Field::Varint(v)) => id = Some(v),
+            (2, Field::Bytes(b)) => {
+                for pair in read_packed(b)?.chunks(2) {
+                    if let [k, v] = pair {
+                        let key = keys.get(*k as usize).ok_or_else(invalid)?;
+                        let value = values.get(*v as usize).ok_or_else(invalid)?;
+                        props.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            (3, Field::Varint(v)) => geom_type = v,
+            (4, Field::Bytes(b)) => commands = read_packed(b)?,
+            _ => {}
+        }
+    }
+    Ok(DecodedFeature {
+        id,
+        props,
+        geom: decode_geometry(geom_type, &commands)?,
+    })
+}
+
+fn decode_layer(buf: &[u8]) -> Result<DecodedLayer, Error> {
+    let fields = read_fields(buf)?;
+    let mut name = String::new();
+    let mut extent = 4096;
+    let mut keys = vec![];
+    let mut values = vec![];
+    for (field, v) in &fields {
+        match (field, v) {
+            (1, Field::Bytes(b)) => name = String::from_utf8_lossy(b).to_string(),
+            (3, Field::Bytes(b)) => keys.push(String::from_utf8_lossy(b).to_string()),
+            (4, Field::Bytes(b)) => values.push(decode_value(b)?),
+            (5, Field::Varint(v)) => extent = *v as u32,
+            _ => {}
+        }
+    }
+    let mut features = vec![];
+    for (field, v) in &fields {
+        if let (2, Field::Bytes(b)) = (field, v) {
+            features.push(decode_feature(b, &keys, &values)?);
+        }
+    }
+    Ok(DecodedLayer {
+        name,
+        extent,
+        features,
+    })
+}
+
+pub fn decode_tile(data: &[u8]) -> Result<Vec<DecodedLayer>, Error> {
+    let mut layers = vec![];
+    for (field, v) in read_fields(data)? {
+        if let (3, Field::Bytes(b)) = (field, v) {
+            layers.push(decode_layer(b)?);
+        }
+    }
+    Ok(layers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_tile, encode_geometry, encode_tile, zigzag, Layer};
+    use geo_types::{Geometry, LineString, Point, Polygon};
+    use serde_json::json;
+
+    #[test]
+    fn test_zigzag() {
+        assert_eq!(0, zigzag(0));
+        assert_eq!(1, zigzag(-1));
+        assert_eq!(2, zigzag(1));
+        assert_eq!(50, zigzag(25));
+    }
+
+    // Examples from the MVT spec, section 4.3.5
+    #[test]
+    fn test_encode_spec_examples() {
+        let point = Geometry::Point(Point::new(25.0, 17.0));
+        assert_eq!(Some((1, vec![9, 50, 34])), encode_geometry(&point));
+
+        let line = Geometry::LineString(LineString::from(vec![
+            (2.0, 2.0),
+            (2.0, 10.0),
+            (10.0, 10.0),
+        ]));
+        assert_eq!(
+            Some((2, vec![9, 4, 4, 18, 0, 16, 16, 0])),
+            encode_geometry(&line)
+        );
+
+        let poly = Geometry::Polygon(Polygon::new(
+            LineString::from(vec![(3.0, 6.0), (8.0, 12.0), (20.0, 34.0), (3.0, 6.0)]),
+            vec![],
+        ));
+        assert_eq!(
+            Some((3, vec![9, 6, 12, 18, 10, 12, 24, 44, 15])),
+            encode_geometry(&poly)
+        );
+    }
+
+    #[test]
+    fn test_decode_roundtrip() {
+        let mut layer = Layer::new("roads", 4096);
+        let props = json!({"name": "Main St", "lanes": 2, "speed": 35.5, "oneway": true});
+        let poly = Geometry::Polygon(Polygon::new(
+            LineString::from(vec![
+                (0.0, 0.0),
+                (10.0, 0.0),
+                (10.0, 10.0),
+                (0.0, 10.0),
+                (0.0, 0.0),
+            ]),
+            vec![LineString::from(vec![
+                (2.0, 2.0),
+                (2.0, 8.0),
+                (8.0, 8.0),
+                (8.0, 2.0),
+                (2.0, 2.0),
+            ])],
+        ));
+        layer.add_feature(Some(7), props.as_object().unwrap(), &poly);
+        let line = Geometry::LineString(LineString::from(vec![(2.0, 2.0), (2.0, 10.0)]));
+        layer.add_feature(None, props.as_object().unwrap(), &line);
+
+        let layers = decode_tile(&encode_tile(&[layer])).unwrap();
+        assert_eq!(1, layers.len());
+        assert_eq!("roads", layers[0].name);
+        assert_eq!(4096, layers[0].extent);
+        let features = &layers[0].features;
+        assert_eq!(2, features.len());
+        assert_eq!(Some(7), features[0].id);
+        assert_eq!(
+            &props,
+            &serde_json::Value::Object(features[0].props.clone())
+        );
+        match &features[0].geom {
+            Geometry::Polygon(p) => {
+                assert_eq!(5, p.exterior().0.len());
+                assert_eq!(1, p.interiors().len());
+            }
+            g => panic!("Expected polygon, got {:?}", g),
+        }
+        assert_eq!(None, features[1].id);
+        assert_eq!(line, features[1].geom);
+    }
+
+    #[test]
+    fn test_degenerate_geometries() {
+        let line = Geometry::LineString(LineString::from(vec![(2.1, 2.1), (2.2, 1.9)]));
+        assert_eq!(None, encode_geometry(&line));
+    }
+}
@@ -0,0 +1,506 @@
+// PMTiles v3: https://github.com/protomaps/PMTiles/blob/main/spec/v3/spec.md
+//
+// Layout: header | root directory | metadata | leaf directories | tile data.
+// Tiles are buffered in memory so that they can be written in tile id
+// (hilbert) order, which lets identical tiles (e.g. ocean) share data.
+use crate::{
+    error::Error,
+    tile::{self, Tile},
+    tiles::{gunzip, gzip, Metadata},
+};
+use serde_json::json;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const HEADER_SIZE: usize = 127;
+// Readers fetch the header and root directory in one initial request
+const ROOT_SIZE: usize = 16384;
+
+const COMPRESSION_NONE: u8 = 1;
+const COMPRESSION_GZIP: u8 = 2;
+const TILE_TYPE_MVT: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    pub tile_id: u64,
+    pub offset: u64,
+    pub length: u32,
+    // Number of consecutive tile ids sharing this data. 0 for leaf
+    // directory entries.
+    pub run_length: u32,
+}
+
+fn rotate(n: u32, x: u32, y: u32, rx: u32, ry: u32) -> (u32, u32) {
+    if ry == 0 {
+        if rx != 0 {
+            return (n - 1 - y, n - 1 - x);
+        }
+        return (y, x);
+    }
+    (x, y)
+}
+
+// Tiles are numbered along a hilbert curve within each zoom,
+// after all the tiles of lower zooms
+pub fn tile_id(t: Tile) -> u64 {
+    let mut acc = ((1u64 << (t.z as u64 * 2)) - 1) / 3;
+    let n = 1u32.checked_shl(t.z as u32).unwrap_or(0);
+    let (mut x, mut y) = (t.x, t.y);
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s != 0) as u32;
+        let ry = (y & s != 0) as u32;
+        acc += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+        let (nx, ny) = rotate(n, x, y, rx, ry);
+        x = nx;
+        y = ny;
+        s /= 2;
+    }
+    acc
+}
+
+// Inverse of tile_id
+pub fn tile_for_id(id: u64) -> Option<Tile> {
+    let mut acc = 0;
+    for z in 0..=tile::MAX_ZOOM {
+        let count = 1u64 << (z as u64 * 2);
+        if id < acc + count {
+            let mut d = id - acc;
+            let (mut x, mut y) = (0, 0);
+            let mut s = 1;
+            while s < (1u64 << z) as u32 {
+                let rx = (1 & (d / 2)) as u32;
+                let ry = (1 & (d ^ rx as u64)) as u32;
+                let (nx, ny) = rotate(s, x, y, rx, ry);
+                x = nx + s * rx;
+                y = ny + s * ry;
+                d /= 4;
+                s *= 2;
+            }
+            return Tile::new(z, x, y);
+        }
+        acc += count;
+    }
+    None
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+pub fn serialize_directory(entries: &[Entry]) -> Vec<u8> {
+    let mut buf = vec![];
+    write_varint(&mut buf, entries.len() as u64);
+    let mut last_id = 0;
+    for e in entries {
+        write_varint(&mut buf, e.tile_id - last_id);
+        last_id = e.tile_id;
+    }
+    for e in entries {
+        write_varint(&mut buf, e.run_length as u64);
+    }
+    for e in entries {
+        write_varint(&mut buf, e.length as u64);
+    }
+    for (i, e) in entries.iter().enumerate() {
+        // 0 means the data directly follows the previous entry's
+        if i > 0 && e.offset == entries[i - 1].offset + entries[i - 1].length as u64 {
+            write_varint(&mut buf, 0);
+        } else {
+            write_varint(&mut buf, e.offset + 1);
+        }
+    }
+    buf
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64, Error> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = *buf.get(*pos).ok_or_else(invalid)?;
+        *pos += 1;
+        v |= ((b & 0x7f) as u64) << shift;
+        if b < 0x80 {
+            return Ok(v);
+        }
+    }
+    Err(invalid())
+}
+
+fn invalid() -> Error {
+    Error::InvalidInput("Invalid PMTiles archive".to_string())
+}
+
+pub fn deserialize_directory(buf: &[u8]) -> Result<Vec<Entry>, Error> {
+    let mut pos = 0;
+    let n = read_varint(buf, &mut pos)? as usize;
+    // Each entry takes at least a byte for each of its four fields, so a
+    // count past that is corrupt, and isn't allocated for
+    if n > (buf.len() - pos) / 4 {
+        return Err(invalid());
+    }
+    let mut entries = vec![
+        Entry {
+            tile_id: 0,
+            offset: 0,
+            length: 0,
+            run_length: 0,
+        };
+        n
+    ];
+    let mut last_id = 0;
+    for e in entries.iter_mut() {
+        last_id += read_varint(buf, &mut pos)?;
+        e.tile_id = last_id;
+    }
+    for e in entries.iter_mut() {
+        e.run_length = read_varint(buf, &mut pos)? as u32;
+    }
+    for e in entries.iter_mut() {
+        e.length = read_varint(buf, &mut pos)? as u32;
+    }
+    for i in 0..n {
+        let v = read_varint(buf, &mut pos)?;
+        entries[i].offset = match v {
+            0 if i > 0 => entries[i - 1].offset + entries[i - 1].length as u64,
+            0 => return Err(invalid()),
+            v => v - 1,
+        };
+    }
+    Ok(entries)
+}
+
+// Gzipped root directory and leaf directories. Entries are split
+// into leaves only when the root wouldn't fit in the initial fetch.
+fn build_directories(entries: &[Entry]) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let root = gzip(&serialize_directory(entries))?;
+    if HEADER_SIZE + root.len() <= ROOT_SIZE {
+        return Ok((root, vec![]));
+    }
+    let mut leaf_size = 4096;
+    loop {
+        let mut leaves = vec![];
+        let mut root_entries = vec![];
+        for chunk in entries.chunks(leaf_size) {
+            let leaf = gzip(&serialize_directory(chunk))?;
+            root_entries.push(Entry {
+                tile_id: chunk[0].tile_id,
+                offset: leaves.len() as u64,
+                length: leaf.len() as u32,
+                run_length: 0,
+            });
+            leaves.extend(leaf);
+        }
+        let root = gzip(&serialize_directory(&root_entries))?;
+        if HEADER_SIZE + root.len() <= ROOT_SIZE {
+            return Ok((root, leaves));
+        }
+        leaf_size *= 2;
+    }
+}
+
+fn e7(degrees: f64) -> i32 {
+    (degrees * 10_000_000.0).round() as i32
+}
+
+pub struct Writer {
+    path: PathBuf,
+    tiles: Vec<(u64, Vec<u8>)>,
+}
+
+impl Writer {
+    pub fn create(path: &Path) -> Result<Writer, Error> {
+        // Create the file up front, so bad paths fail before tiling
+        File::create(path)?;
+        Ok(Writer {
+            path: path.to_path_buf(),
+            tiles: vec![],
+        })
+    }
+}
+
+impl super::Writer for Writer {
+    fn write_tile(&mut self, t: Tile, data: &[u8]) -> Result<(), Error> {
+        self.tiles.push((tile_id(t), gzip(data)?));
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>, metadata: &Metadata) -> Result<(), Error> {
+        self.tiles.sort_by_key(|(id, _)| *id);
+
+        let mut data: Vec<u8> = vec![];
+        let mut entries: Vec<Entry> = vec![];
+        let mut offsets: HashMap<u64, (u64, u32)> = HashMap::new();
+        for (id, tile) in &self.tiles {
+            let mut hasher = DefaultHasher::new();
+            tile.hash(&mut hasher);
+            let hash = hasher.finish();
+            let existing = offsets.get(&hash).copied().filter(|(offset, length)| {
+                &data[*offset as usize..*offset as usize + *length as usize] == tile.as_slice()
+            });
+            let (offset, length) = match existing {
+                Some(o) => o,
+                None => {
+                    let o = (data.len() as u64, tile.len() as u32);
+                    data.extend_from_slice(tile);
+                    offsets.insert(hash, o);
+                    o
+                }
+            };
+            match entries.last_mut() {
+                Some(last)
+                    if last.offset == offset && last.tile_id + last.run_length as u64 == *id =>
+                {
+                    last.run_length += 1
+                }
+                _ => entries.push(Entry {
+                    tile_id: *id,
+                    offset,
+                    length,
+                    run_length: 1,
+                }),
+            }
+        }
+
+        let (root, leaves) = build_directories(&entries)?;
+        let mut json = json!({
+            "name": metadata.name,
+            "format": "pbf",
+            "type": "overlay",
+            "vector_layers": metadata.vector_layers,
+        });
+        if let Some(b) = metadata.bounds {
+            json["bounds"] = json!([b.min().x, b.min().y, b.max().x, b.max().y]);
+        }
+        let meta = gzip(json.to_string().as_bytes())?;
+
+        let root_offset = HEADER_SIZE as u64;
+        let meta_offset = root_offset + root.len() as u64;
+        let leaves_offset = meta_offset + meta.len() as u64;
+        let data_offset = leaves_offset + leaves.len() as u64;
+
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(b"PMTiles");
+        header.push(3);
+        for v in &[
+            root_offset,
+            root.len() as u64,
+            meta_offset,
+            meta.len() as u64,
+            leaves_offset,
+            leaves.len() as u64,
+            data_offset,
+            data.len() as u64,
+            self.tiles.len() as u64,
+            entries.len() as u64,
+            offsets.len() as u64,
+        ] {
+            header.extend_from_slice(&v.to_le_bytes());
+        }
+        // Clustered, since tile data is in tile id order
+        header.push(1);
+        header.push(COMPRESSION_GZIP);
+        header.push(COMPRESSION_GZIP);
+        header.push(TILE_TYPE_MVT);
+        header.push(metadata.min_zoom);
+        header.push(metadata.max_zoom);
+        let (min, max, center) = match metadata.bounds {
+            Some(b) => (b.min(), b.max(), b.center()),
+            None => Default::default(),
+        };
+        for v in &[e7(min.x), e7(min.y), e7(max.x), e7(max.y)] {
+            header.extend_from_slice(&v.to_le_bytes());
+        }
+        header.push(metadata.min_zoom);
+        header.extend_from_slice(&e7(center.x).to_le_bytes());
+        header.extend_from_slice(&e7(center.y).to_le_bytes());
+
+        let mut out = BufWriter::new(File::create(&self.path)?);
+        for section in &[header, root, meta, leaves, data] {
+            out.write_all(section)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+struct Header {
+    root_offset: u64,
+    root_length: u64,
+    leaves_offset: u64,
+    data_offset: u64,
+    internal_compression: u8,
+    tile_compression: u8,
+}
+
+pub struct Reader {
+    file: File,
+    header: Header,
+}
+
+impl Reader {
+    pub fn open(path: &Path) -> Result<Reader, Error> {
+        let mut file = File::open(path)?;
+        let mut buf = [0; HEADER_SIZE];
+        file.read_exact(&mut buf)?;
+        if &buf[0..7] != b"PMTiles" || buf[7] != 3 {
+            return Err(Error::InvalidInput(format!(
+                "Unsupported PMTiles archive: {} -- only version 3 is supported",
+                path.display()
+            )));
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(buf[i..i + 8].try_into().unwrap());
+        let header = Header {
+            root_offset: u64_at(8),
+            root_length: u64_at(16),
+            leaves_offset: u64_at(40),
+            data_offset: u64_at(56),
+            internal_compression: buf[97],
+            tile_compression: buf[98],
+        };
+        Ok(Reader { file, header })
+    }
+
+    fn read(&mut self, offset: u64, length: u64) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![0; length as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn decompress(compression: u8, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        match compression {
+            0 | COMPRESSION_NONE | COMPRESSION_GZIP => gunzip(data),
+            c => Err(Error::InvalidInput(format!(
+                "Unsupported PMTiles compression type: {}",
+                c
+            ))),
+        }
+    }
+
+    fn directory(&mut self, offset: u64, length: u64) -> Result<Vec<Entry>, Error> {
+        let data = self.read(offset, length)?;
+        deserialize_directory(&Reader::decompress(self.header.internal_compression, data)?)
+    }
+
+    fn collect_tiles(
+        &mut self,
+        offset: u64,
+        length: u64,
+        tiles: &mut Vec<Tile>,
+    ) -> Result<(), Error> {
+        for e in self.directory(offset, length)? {
+            if e.run_length == 0 {
+                let leaf_offset = self.header.leaves_offset + e.offset;
+                self.collect_tiles(leaf_offset, e.length as u64, tiles)?;
+            } else {
+                for id in e.tile_id..e.tile_id + e.run_length as u64 {
+                    tiles.push(tile_for_id(id).ok_or_else(invalid)?);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl super::Reader for Reader {
+    fn tile(&mut self, t: Tile) -> Result<Option<Vec<u8>>, Error> {
+        let id = tile_id(t);
+        let (mut offset, mut length) = (self.header.root_offset, self.header.root_length);
+        // The spec allows at most 3 levels of leaf directories
+        for _ in 0..4 {
+            let entries = self.directory(offset, length)?;
+            let i = match entries.binary_search_by_key(&id, |e| e.tile_id) {
+                Ok(i) => i,
+                Err(0) => return Ok(None),
+                Err(i) => i - 1,
+            };
+            let e = entries[i];
+            if e.run_length == 0 {
+                offset = self.header.leaves_offset + e.offset;
+                length = e.length as u64;
+            } else if id < e.tile_id + e.run_length as u64 {
+                let data = self.read(self.header.data_offset + e.offset, e.length as u64)?;
+                return Reader::decompress(self.header.tile_compression, data).map(Some);
+            } else {
+                return Ok(None);
+            }
+        }
+        Err(invalid())
+    }
+
+    fn tiles(&mut self) -> Result<Vec<Tile>, Error> {
+        let mut tiles = vec![];
+        let (offset, length) = (self.header.root_offset, self.header.root_length);
+        self.collect_tiles(offset, length, &mut tiles)?;
+        Ok(tiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deserialize_directory, serialize_directory, tile_for_id, tile_id, Entry};
+    use crate::tile::Tile;
+
+    #[test]
+    fn test_tile_id() {
+        // Examples from the PMTiles spec
+        assert_eq!(0, tile_id(Tile { z: 0, x: 0, y: 0 }));
+        assert_eq!(1, tile_id(Tile { z: 1, x: 0, y: 0 }));
+        assert_eq!(2, tile_id(Tile { z: 1, x: 0, y: 1 }));
+        assert_eq!(3, tile_id(Tile { z: 1, x: 1, y: 1 }));
+        assert_eq!(4, tile_id(Tile { z: 1, x: 1, y: 0 }));
+        assert_eq!(5, tile_id(Tile { z: 2, x: 0, y: 0 }));
+        assert_eq!(
+            19078479,
+            tile_id(Tile {
+                z: 12,
+                x: 3423,
+                y: 1763
+            })
+        );
+        for id in &[0, 1, 2, 3, 4, 5, 19078479, 1 << 40] {
+            assert_eq!(*id, tile_id(tile_for_id(*id).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_serialize_directory() {
+        let entries = vec![
+            Entry {
+                tile_id: 0,
+                offset: 0,
+                length: 10,
+                run_length: 1,
+            },
+            Entry {
+                tile_id: 2,
+                offset: 10,
+                length: 5,
+                run_length: 2,
+            },
+            Entry {
+                tile_id: 5,
+                offset: 0,
+                length: 10,
+                run_length: 1,
+            },
+        ];
+        let bytes = serialize_directory(&entries);
+        assert_eq!(vec![3, 0, 2, 3, 1, 2, 1, 10, 5, 10, 1, 0, 1], bytes);
+        assert_eq!(entries, deserialize_directory(&bytes).unwrap());
+        // A count of entries far past what the directory holds
+        let huge = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0, 0, 0, 0,
+        ];
+        assert!(deserialize_directory(&huge).is_err());
+    }
+}
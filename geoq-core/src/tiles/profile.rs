@@ -0,0 +1,194 @@
+// Per-zoom generalization for tiles build, read from a JSON profile like
+//
+//   [{"min_zoom": 0, "max_zoom": 5, "tolerance": 8, "min_area": 64,
+//     "properties": ["name"]},
+//    {"min_zoom": 6, "max_zoom": 9, "tolerance": 2, "min_length": 4}]
+//
+// Lengths are in tile units (--extent of a tile, 4096 by default) at the
+// zoom being built, and areas in square tile units. Each zoom uses the
+// first rule covering it; zooms no rule covers keep every feature and
+// property, simplified at the default tolerance.
+use crate::error::Error;
+use serde_json::{Map, Value};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    // Simplification tolerance, replacing Options.tolerance
+    pub tolerance: Option<f64>,
+    // Polygons smaller than this are left out
+    pub min_area: f64,
+    // Lines shorter than this are left out
+    pub min_length: f64,
+    // Properties to keep, or all of them if not given
+    pub properties: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    rules: Vec<Rule>,
+}
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidInput(format!("Invalid tile profile: {}", msg))
+}
+
+fn number(rule: &Map<String, Value>, key: &str) -> Result<Option<f64>, Error> {
+    match rule.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Number(n)) if n.as_f64().is_some_and(|n| n >= 0.0) => Ok(n.as_f64()),
+        Some(v) => Err(invalid(format!(
+            "expected a non-negative number for {}: {}",
+            key, v
+        ))),
+    }
+}
+
+fn zoom(rule: &Map<String, Value>, key: &str) -> Result<u8, Error> {
+    match rule.get(key).and_then(|v| v.as_u64()) {
+        Some(z) if z <= crate::tile::MAX_ZOOM as u64 => Ok(z as u8),
+        _ => Err(invalid(format!(
+            "expected a zoom from 0 to {} for {}: {}",
+            crate::tile::MAX_ZOOM,
+            key,
+            Value::Object(rule.clone())
+        ))),
+    }
+}
+
+fn properties(rule: &Map<String, Value>) -> Result<Option<Vec<String>>, Error> {
+    match rule.get("properties") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Array(names)) => names
+            .iter()
+            .map(|n| match n {
+                Value::String(s) => Ok(s.clone()),
+                n => Err(invalid(format!("expected a property name: {}", n))),
+            })
+            .collect::<Result<_, _>>()
+            .map(Some),
+        Some(v) => Err(invalid(format!(
+            "expected an array of property names: {}",
+            v
+        ))),
+    }
+}
+
+fn rule(value: &Value) -> Result<Rule, Error> {
+    let rule = match value {
+        Value::Object(rule) => rule,
+        v => return Err(invalid(format!("expected an object per rule: {}", v))),
+    };
+    const KEYS: [&str; 6] = [
+        "min_zoom",
+        "max_zoom",
+        "tolerance",
+        "min_area",
+        "min_length",
+        "properties",
+    ];
+    if let Some(k) = rule.keys().find(|k| !KEYS.contains(&k.as_str())) {
+        return Err(invalid(format!(
+            "unknown key {} -- expected {}",
+            k,
+            KEYS.join(", ")
+        )));
+    }
+    let (min_zoom, max_zoom) = (zoom(rule, "min_zoom")?, zoom(rule, "max_zoom")?);
+    if min_zoom > max_zoom {
+        return Err(invalid(format!(
+            "min_zoom {} is greater than max_zoom {}",
+            min_zoom, max_zoom
+        )));
+    }
+    Ok(Rule {
+        min_zoom,
+        max_zoom,
+        tolerance: number(rule, "tolerance")?,
+        min_area: number(rule, "min_area")?.unwrap_or(0.0),
+        min_length: number(rule, "min_length")?.unwrap_or(0.0),
+        properties: properties(rule)?,
+    })
+}
+
+impl Profile {
+    pub fn parse(text: &str) -> Result<Profile, Error> {
+        match serde_json::from_str(text)? {
+            Value::Array(rules) => Ok(Profile {
+                rules: rules.iter().map(rule).collect::<Result<_, _>>()?,
+            }),
+            v => Err(invalid(format!("expected an array of rules: {}", v))),
+        }
+    }
+
+    pub fn read(path: &str) -> Result<Profile, Error> {
+        Profile::parse(&std::fs::read_to_string(path)?)
+    }
+
+    // The rule for a zoom, if any
+    pub fn rule(&self, z: u8) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .find(|r| r.min_zoom <= z && z <= r.max_zoom)
+    }
+}
+
+impl Rule {
+    // A feature's properties, limited to those the rule keeps
+    pub fn properties(&self, props: &Map<String, Value>) -> Map<String, Value> {
+        match &self.properties {
+            Some(keep) => props
+                .iter()
+                .filter(|(k, _)| keep.contains(k))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            None => props.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profile;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse() {
+        let profile = Profile::parse(
+            r#"[{"min_zoom": 0, "max_zoom": 4, "tolerance": 8, "min_area": 64, "properties": ["name"]},
+                {"min_zoom": 3, "max_zoom": 9, "min_length": 2}]"#,
+        )
+        .unwrap();
+        let low = profile.rule(3).unwrap();
+        assert_eq!(Some(8.0), low.tolerance);
+        assert_eq!(64.0, low.min_area);
+        let props = json!({"name": "a", "pop": 1});
+        assert_eq!(
+            json!({"name": "a"}),
+            serde_json::Value::Object(low.properties(props.as_object().unwrap()))
+        );
+        let mid = profile.rule(5).unwrap();
+        assert_eq!(
+            (None, 2.0, None),
+            (mid.tolerance, mid.min_length, mid.properties.clone())
+        );
+        assert!(profile.rule(10).is_none());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for text in [
+            r#"{"min_zoom": 0, "max_zoom": 4}"#,
+            r#"[{"min_zoom": 0}]"#,
+            r#"[{"min_zoom": 5, "max_zoom": 4}]"#,
+            r#"[{"min_zoom": 0, "max_zoom": 4, "tolerence": 2}]"#,
+            r#"[{"min_zoom": 0, "max_zoom": 4, "min_area": -1}]"#,
+            r#"[{"min_zoom": 0, "max_zoom": 4, "properties": "name"}]"#,
+        ]
+        .iter()
+        {
+            assert!(Profile::parse(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::error::Error;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EpochUnit {
+    Seconds,
+    Millis,
+}
+
+fn from_epoch(n: f64, unit: EpochUnit) -> Option<DateTime<Utc>> {
+    let millis = match unit {
+        EpochUnit::Seconds => n * 1000.0,
+        EpochUnit::Millis => n,
+    };
+    if !millis.is_finite() {
+        return None;
+    }
+    let millis = millis.round() as i64;
+    Utc.timestamp_opt(
+        millis.div_euclid(1000),
+        (millis.rem_euclid(1000) * 1_000_000) as u32,
+    )
+    .single()
+}
+
+// Accepts RFC 3339 timestamps, date-times without an offset (treated as UTC),
+// plain dates (midnight UTC), and numeric epoch times.
+pub fn parse_str(s: &str, unit: EpochUnit) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    for fmt in &[
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Some(Utc.from_utc_datetime(&dt));
+        }
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return d.and_hms_opt(0, 0, 0).map(|dt| Utc.from_utc_datetime(&dt));
+    }
+    s.parse::<f64>().ok().and_then(|n| from_epoch(n, unit))
+}
+
+// Like parse_str, but for an exclusive upper bound: a plain date is the end
+// of that day (midnight UTC at the start of the next), so the day is kept.
+pub fn parse_end(s: &str, unit: EpochUnit) -> Option<DateTime<Utc>> {
+    match NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d") {
+        Ok(d) => d
+            .succ_opt()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|dt| Utc.from_utc_datetime(&dt)),
+        Err(_) => parse_str(s, unit),
+    }
+}
+
+pub fn parse_value(v: &Value, unit: EpochUnit) -> Option<DateTime<Utc>> {
+    match v {
+        Value::Number(n) => n.as_f64().and_then(|n| from_epoch(n, unit)),
+        Value::String(s) => parse_str(s, unit),
+        _ => None,
+    }
+}
+
+// RFC 3339 in UTC, with fractional seconds only when there are some
+pub fn format(t: &DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+// Seconds from a to b
+pub fn seconds_between(a: &DateTime<Utc>, b: &DateTime<Utc>) -> f64 {
+    (*b - *a).num_milliseconds() as f64 / 1000.0
+}
+
+// The time a number of seconds after t
+pub fn add_seconds(t: &DateTime<Utc>, seconds: f64) -> DateTime<Utc> {
+    *t + chrono::Duration::milliseconds((seconds * 1000.0).round() as i64)
+}
+
+// Parses a length of time such as 90s, 5min, 1.5h or 2d as seconds. A
+// bare number is seconds. Minutes are min rather than m, which is meters
+// to units::parse_length.
+pub fn parse_seconds(s: &str) -> Result<f64, Error> {
+    let s = s.trim();
+    let split = s
+        .rfind(|c: char| !c.is_ascii_alphabetic())
+        .map_or(0, |i| i + 1);
+    let (number, suffix) = s.split_at(split);
+    let unit = match suffix {
+        "ms" => 0.001,
+        "" | "s" | "sec" | "secs" => 1.0,
+        "min" | "mins" => 60.0,
+        "h" | "hr" | "hrs" => 3600.0,
+        "d" | "day" | "days" => 86400.0,
+        _ => f64::NAN,
+    };
+    match number.trim().parse::<f64>() {
+        Ok(n) if n * unit > 0.0 && (n * unit).is_finite() => Ok(n * unit),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected a positive length of time, like 90s, 5min or 2h: {}",
+            s
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format, parse_end, parse_seconds, parse_str, parse_value, EpochUnit};
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let expected = utc("2023-01-02T03:04:05Z");
+        for s in &[
+            "2023-01-02T03:04:05Z",
+            "2023-01-02T05:04:05+02:00",
+            "2023-01-02T03:04:05",
+            "2023-01-02 03:04:05.000",
+            "1672628645",
+        ] {
+            assert_eq!(Some(expected), parse_str(s, EpochUnit::Seconds), "{}", s);
+        }
+        assert_eq!(
+            Some(utc("2023-01-02T00:00:00Z")),
+            parse_str("2023-01-02", EpochUnit::Seconds)
+        );
+        assert_eq!(None, parse_str("yesterday", EpochUnit::Seconds));
+    }
+
+    #[test]
+    fn test_parse_end() {
+        assert_eq!(
+            Some(utc("2023-01-03T00:00:00Z")),
+            parse_end("2023-01-02", EpochUnit::Seconds)
+        );
+        assert_eq!(
+            Some(utc("2023-01-02T03:04:05Z")),
+            parse_end("2023-01-02T03:04:05Z", EpochUnit::Seconds)
+        );
+        assert_eq!(None, parse_end("yesterday", EpochUnit::Seconds));
+    }
+
+    #[test]
+    fn test_parse_epoch_values() {
+        let expected = utc("2023-01-02T03:04:05.5Z");
+        assert_eq!(
+            Some(expected),
+            parse_value(&json!(1672628645.5), EpochUnit::Seconds)
+        );
+        assert_eq!(
+            Some(expected),
+            parse_value(&json!(1672628645500u64), EpochUnit::Millis)
+        );
+        assert_eq!(None, parse_value(&json!(null), EpochUnit::Seconds));
+    }
+
+    #[test]
+    fn test_parse_seconds() {
+        assert_eq!(300.0, parse_seconds("5min").unwrap());
+        assert_eq!(5400.0, parse_seconds("1.5h").unwrap());
+        assert_eq!(10.0, parse_seconds("10").unwrap());
+        assert_eq!(0.25, parse_seconds("250ms").unwrap());
+        assert!(parse_seconds("0s").is_err());
+        assert!(parse_seconds("10m").is_err());
+        assert!(parse_seconds("5 fortnights").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let t = utc("2023-01-02T03:04:05Z");
+        assert_eq!("2023-01-02T03:04:05Z", format(&t));
+        assert_eq!(
+            "2023-01-02T03:04:05.500Z",
+            format(&utc("2023-01-02T03:04:05.5Z"))
+        );
+    }
+}
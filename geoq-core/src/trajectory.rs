@@ -0,0 +1,424 @@
+// Tracks built from timestamped points, such as GPS fixes. A track is a
+// LineString feature with the time of each of its points in a coordTimes
+// property, as togeojson gives for GPX tracks.
+use crate::{
+    error::Error,
+    timestamp::{self, EpochUnit},
+    units,
+};
+use chrono::{DateTime, Utc};
+use geo::algorithm::geodesic_distance::GeodesicDistance;
+use geo_types::{Coord, Geometry, LineString, Point};
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+
+pub const TIMES_KEY: &str = "coordTimes";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fix {
+    pub coord: Coord<f64>,
+    pub time: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    pub fixes: Vec<Fix>,
+}
+
+fn meters(a: &Fix, b: &Fix) -> f64 {
+    Point(a.coord).geodesic_distance(&Point(b.coord))
+}
+
+// Splits fixes, sorted by time, into tracks wherever more than max_gap
+// seconds pass between one and the next. Tracks of a single fix are left
+// out, having no line to draw.
+pub fn split(fixes: Vec<Fix>, max_gap: Option<f64>) -> Vec<Track> {
+    let mut tracks = vec![];
+    let mut current: Vec<Fix> = vec![];
+    for fix in fixes {
+        let gap = current
+            .last()
+            .map(|last| timestamp::seconds_between(&last.time, &fix.time));
+        if gap.is_some_and(|gap| max_gap.is_some_and(|max| gap > max)) {
+            tracks.push(Track {
+                fixes: std::mem::take(&mut current),
+            });
+        }
+        current.push(fix);
+    }
+    tracks.push(Track { fixes: current });
+    tracks.retain(|t| t.fixes.len() > 1);
+    tracks
+}
+
+// Reads a speed such as 0.5m/s, 30km/h or 20mph as meters per second. A
+// bare number is in --units per second.
+pub fn parse_speed(s: &str) -> Result<f64, Error> {
+    let invalid = || {
+        Error::InvalidNumberFormat(format!(
+            "Expected a speed, like 0.5m/s, 30km/h or 20mph: {}",
+            s
+        ))
+    };
+    let s = s.trim();
+    let (length, time) = match s.split_once('/') {
+        Some((length, time)) => (length, time.trim()),
+        None => match s.strip_suffix("mph") {
+            Some(n) => return Ok(units::parse_length(&format!("{}mi", n))? / 3600.0),
+            None => (s, "s"),
+        },
+    };
+    let meters = units::parse_length(length).map_err(|_| invalid())?;
+    // A unit of time alone is one of it
+    let seconds = if time.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        timestamp::parse_seconds(&format!("1{}", time))
+    } else {
+        timestamp::parse_seconds(time)
+    }
+    .map_err(|_| invalid())?;
+    Ok(meters / seconds)
+}
+
+impl Track {
+    // Reads a track from a LineString and its coordTimes
+    pub fn from_geojson(geom: &Geometry<f64>, props: &Map<String, Value>) -> Result<Track, Error> {
+        let coords = match geom {
+            Geometry::LineString(ls) => &ls.0,
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "Expected a track as a LineString with {} -- see geoq trajectory",
+                    TIMES_KEY
+                )))
+            }
+        };
+        let times = match props.get(TIMES_KEY) {
+            Some(Value::Array(times)) if times.len() == coords.len() => times,
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "Expected a track's {} property to have a time for each of its {} points",
+                    TIMES_KEY,
+                    coords.len()
+                )))
+            }
+        };
+        let fixes = coords
+            .iter()
+            .zip(times)
+            .map(|(coord, time)| {
+                timestamp::parse_value(time, EpochUnit::Seconds)
+                    .map(|time| Fix {
+                        coord: *coord,
+                        time,
+                    })
+                    .ok_or_else(|| {
+                        Error::InvalidInput(format!("Invalid time in {}: {}", TIMES_KEY, time))
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Track { fixes })
+    }
+
+    // Geodesic length in meters
+    pub fn distance(&self) -> f64 {
+        self.fixes.windows(2).map(|w| meters(&w[0], &w[1])).sum()
+    }
+
+    // Seconds from the first fix to the last
+    pub fn duration(&self) -> f64 {
+        match (self.fixes.first(), self.fixes.last()) {
+            (Some(first), Some(last)) => timestamp::seconds_between(&first.time, &last.time),
+            _ => 0.0,
+        }
+    }
+
+    // The fastest speed between two fixes, in meters per second
+    fn max_speed(&self) -> Option<f64> {
+        self.fixes
+            .windows(2)
+            .filter_map(|w| {
+                let seconds = timestamp::seconds_between(&w[0].time, &w[1].time);
+                if seconds > 0.0 {
+                    Some(meters(&w[0], &w[1]) / seconds)
+                } else {
+                    None
+                }
+            })
+            .fold(None, |max: Option<f64>, s| {
+                Some(max.map_or(s, |m| m.max(s)))
+            })
+    }
+
+    pub fn geometry(&self) -> Geometry<f64> {
+        Geometry::LineString(LineString(self.fixes.iter().map(|f| f.coord).collect()))
+    }
+
+    // The track's times, start and end, duration in seconds, and distance
+    // and speeds in --units and --units per second
+    pub fn properties(&self) -> Map<String, Value> {
+        let mut props = Map::new();
+        let duration = self.duration();
+        let distance = units::from_meters(self.distance());
+        if let (Some(first), Some(last)) = (self.fixes.first(), self.fixes.last()) {
+            props.insert(
+                "start_time".to_string(),
+                json!(timestamp::format(&first.time)),
+            );
+            props.insert("end_time".to_string(), json!(timestamp::format(&last.time)));
+        }
+        props.insert("duration".to_string(), json!(duration));
+        props.insert("distance".to_string(), json!(distance));
+        let speed = if duration > 0.0 {
+            json!(distance / duration)
+        } else {
+            Value::Null
+        };
+        props.insert("speed".to_string(), speed);
+        props.insert(
+            "max_speed".to_string(),
+            json!(self.max_speed().map(units::from_meters)),
+        );
+        props.insert(
+            TIMES_KEY.to_string(),
+            self.fixes
+                .iter()
+                .map(|f| json!(timestamp::format(&f.time)))
+                .collect(),
+        );
+        props
+    }
+}
+
+// How far apart to place the points of a resampled track
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interval {
+    Seconds(f64),
+    Meters(f64),
+}
+
+// A length of time such as 10s or 5min, or a distance such as 100m or
+// 0.5mi. Either needs its unit, since a bare number could be both.
+impl FromStr for Interval {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.ends_with(|c: char| c.is_ascii_alphabetic()) {
+            if let Ok(seconds) = timestamp::parse_seconds(s) {
+                return Ok(Interval::Seconds(seconds));
+            }
+            if let Ok(meters) = units::parse_length(s) {
+                if meters > 0.0 {
+                    return Ok(Interval::Meters(meters));
+                }
+            }
+        }
+        Err(Error::InvalidInput(format!(
+            "Expected a positive interval of time or distance, like 10s, 5min, 100m or 1mi: {}",
+            s
+        )))
+    }
+}
+
+// The fix a fraction of the way from a to b
+fn between(a: &Fix, b: &Fix, f: f64) -> Fix {
+    Fix {
+        coord: Coord {
+            x: a.coord.x + (b.coord.x - a.coord.x) * f,
+            y: a.coord.y + (b.coord.y - a.coord.y) * f,
+        },
+        time: timestamp::add_seconds(&a.time, timestamp::seconds_between(&a.time, &b.time) * f),
+    }
+}
+
+// The track's positions at every interval of time or distance from its
+// start, placed along the straight line between the fixes on either side
+pub fn resample(track: &Track, interval: Interval) -> Track {
+    let fixes = &track.fixes;
+    let (every, measures): (f64, Vec<f64>) = match interval {
+        Interval::Seconds(every) => (
+            every,
+            fixes
+                .iter()
+                .map(|f| timestamp::seconds_between(&fixes[0].time, &f.time))
+                .collect(),
+        ),
+        Interval::Meters(every) => {
+            let mut total = 0.0;
+            let mut measures = vec![0.0];
+            for w in fixes.windows(2) {
+                total += meters(&w[0], &w[1]);
+                measures.push(total);
+            }
+            (every, measures)
+        }
+    };
+    let end = match measures.last() {
+        Some(end) if fixes.len() > 1 => *end,
+        _ => return track.clone(),
+    };
+
+    let mut out = vec![];
+    let mut j = 0;
+    for k in 0..=(end / every).floor() as usize {
+        let target = k as f64 * every;
+        while j + 2 < fixes.len() && measures[j + 1] < target {
+            j += 1;
+        }
+        let span = measures[j + 1] - measures[j];
+        let f = if span > 0.0 {
+            (target - measures[j]) / span
+        } else {
+            0.0
+        };
+        out.push(between(&fixes[j], &fixes[j + 1], f));
+    }
+    Track { fixes: out }
+}
+
+pub enum Segment {
+    Moving(Track),
+    Stopped(Track),
+}
+
+// Splits a track into the stretches where it moves and where it stops.
+// It stops where it goes no faster than stop_speed (in meters per second)
+// between fixes for at least stop_duration seconds. A stop shares its
+// first and last fixes with the moving stretches on either side.
+pub fn segments(track: &Track, stop_speed: f64, stop_duration: f64) -> Vec<Segment> {
+    let fixes = &track.fixes;
+    let seconds = |a: usize, b: usize| timestamp::seconds_between(&fixes[a].time, &fixes[b].time);
+    let slow: Vec<bool> = (1..fixes.len())
+        .map(|i| meters(&fixes[i - 1], &fixes[i]) <= stop_speed * seconds(i - 1, i))
+        .collect();
+
+    // The first and last fixes of each stop
+    let mut stops = vec![];
+    let mut i = 0;
+    while i < slow.len() {
+        if slow[i] {
+            let start = i;
+            while i < slow.len() && slow[i] {
+                i += 1;
+            }
+            if seconds(start, i) >= stop_duration {
+                stops.push((start, i));
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut out = vec![];
+    let mut start = 0;
+    let part = |a: usize, b: usize| Track {
+        fixes: fixes[a..=b].to_vec(),
+    };
+    for (a, b) in stops {
+        if a > start {
+            out.push(Segment::Moving(part(start, a)));
+        }
+        out.push(Segment::Stopped(part(a, b)));
+        start = b;
+    }
+    if start + 1 < fixes.len() {
+        out.push(Segment::Moving(part(start, fixes.len() - 1)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_speed, resample, segments, split, Fix, Interval, Segment};
+    use chrono::{TimeZone, Utc};
+    use geo_types::Coord;
+    use serde_json::json;
+
+    fn fix(x: f64, seconds: i64) -> Fix {
+        Fix {
+            coord: Coord { x, y: 0.0 },
+            time: Utc.timestamp_opt(1_672_531_200 + seconds, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        let fixes = vec![fix(0.0, 0), fix(0.001, 10), fix(0.002, 20), fix(0.1, 1000)];
+        let tracks = split(fixes.clone(), Some(300.0));
+        assert_eq!(1, tracks.len());
+        assert_eq!(3, tracks[0].fixes.len());
+        assert_eq!(20.0, tracks[0].duration());
+        assert!((tracks[0].distance() - 222.64).abs() < 0.01);
+
+        let tracks = split(fixes, None);
+        assert_eq!(1, tracks.len());
+        assert_eq!(4, tracks[0].fixes.len());
+    }
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(0.5, parse_speed("0.5m/s").unwrap());
+        assert_eq!(10.0, parse_speed("36km/h").unwrap());
+        assert_eq!(1609.344 / 3600.0, parse_speed("1mph").unwrap());
+        assert_eq!(1.0, parse_speed("60m/min").unwrap());
+        assert_eq!(2.0, parse_speed("2").unwrap());
+        assert!(parse_speed("fast").is_err());
+        assert!(parse_speed("5m/0s").is_err());
+    }
+
+    #[test]
+    fn test_segments() {
+        // Moves for 20s, waits 5 minutes, then moves again
+        let track = &split(
+            vec![
+                fix(0.0, 0),
+                fix(0.001, 10),
+                fix(0.002, 20),
+                fix(0.002, 120),
+                fix(0.002, 320),
+                fix(0.003, 330),
+            ],
+            None,
+        )[0];
+        let kinds: Vec<(&str, usize)> = segments(track, 0.5, 180.0)
+            .iter()
+            .map(|s| match s {
+                Segment::Moving(t) => ("moving", t.fixes.len()),
+                Segment::Stopped(t) => ("stopped", t.fixes.len()),
+            })
+            .collect();
+        assert_eq!(vec![("moving", 3), ("stopped", 3), ("moving", 2)], kinds);
+
+        assert_eq!(1, segments(track, 0.5, 600.0).len());
+    }
+
+    #[test]
+    fn test_resample() {
+        let track = &split(vec![fix(0.0, 0), fix(0.001, 10), fix(0.003, 30)], None)[0];
+        let every_5s = resample(track, Interval::Seconds(5.0));
+        assert_eq!(7, every_5s.fixes.len());
+        assert_eq!(fix(0.0005, 5), every_5s.fixes[1]);
+        assert_eq!(fix(0.0015, 15), every_5s.fixes[3]);
+
+        let every_100m = resample(track, "100m".parse().unwrap());
+        assert_eq!(4, every_100m.fixes.len());
+        assert!((every_100m.distance() - 300.0).abs() < 0.01);
+
+        assert_eq!(Interval::Seconds(600.0), "10min".parse().unwrap());
+        assert!("10".parse::<Interval>().is_err());
+        assert!("-5m".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn test_properties() {
+        let track = &split(vec![fix(0.0, 0), fix(0.001, 10)], None)[0];
+        let props = track.properties();
+        assert_eq!(json!("2023-01-01T00:00:00Z"), props["start_time"]);
+        assert_eq!(json!("2023-01-01T00:00:10Z"), props["end_time"]);
+        assert_eq!(json!(10.0), props["duration"]);
+        assert_eq!(props["speed"], props["max_speed"]);
+        assert_eq!(
+            json!(["2023-01-01T00:00:00Z", "2023-01-01T00:00:10Z"]),
+            props["coordTimes"]
+        );
+    }
+}
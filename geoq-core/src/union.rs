@@ -0,0 +1,19 @@
+use geo::algorithm::bool_ops::BooleanOps;
+use geo_types::MultiPolygon;
+
+// Union pairs of polygons until one remains, which keeps the
+// intermediate geometries small compared to unioning one at a time.
+pub fn cascaded_union(mut polys: Vec<MultiPolygon<f64>>) -> Option<MultiPolygon<f64>> {
+    while polys.len() > 1 {
+        let mut next = Vec::with_capacity(polys.len() / 2 + 1);
+        let mut iter = polys.into_iter();
+        while let Some(a) = iter.next() {
+            match iter.next() {
+                Some(b) => next.push(a.union(&b)),
+                None => next.push(a),
+            }
+        }
+        polys = next;
+    }
+    polys.pop()
+}
@@ -0,0 +1,163 @@
+use crate::error::Error;
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+// Units for distances given to and measurements given by commands, set
+// from the global --units flag. Lengths are kept in meters and areas in
+// square meters inside geoq, and converted only on the way in or out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Meters,
+    Kilometers,
+    Miles,
+    Feet,
+    Acres,
+    Hectares,
+}
+
+impl FromStr for Unit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "m" | "meter" | "meters" => Ok(Unit::Meters),
+            "km" | "kilometer" | "kilometers" => Ok(Unit::Kilometers),
+            "mi" | "mile" | "miles" => Ok(Unit::Miles),
+            "ft" | "foot" | "feet" => Ok(Unit::Feet),
+            "ac" | "acre" | "acres" => Ok(Unit::Acres),
+            "ha" | "hectare" | "hectares" => Ok(Unit::Hectares),
+            _ => Err(Error::InvalidInput(format!(
+                "Unknown units: {} -- expected meters, kilometers, miles, feet, acres or hectares",
+                s
+            ))),
+        }
+    }
+}
+
+impl Unit {
+    pub fn is_area(self) -> bool {
+        matches!(self, Unit::Acres | Unit::Hectares)
+    }
+
+    // Meters in one of a unit of length, or square meters in one of a unit
+    // of area
+    fn size(self) -> f64 {
+        match self {
+            Unit::Meters => 1.0,
+            Unit::Kilometers => 1000.0,
+            Unit::Miles => 1609.344,
+            Unit::Feet => 0.3048,
+            Unit::Acres => 4_046.856_422_4,
+            Unit::Hectares => 10_000.0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Unit::Meters => "meters",
+            Unit::Kilometers => "kilometers",
+            Unit::Miles => "miles",
+            Unit::Feet => "feet",
+            Unit::Acres => "acres",
+            Unit::Hectares => "hectares",
+        }
+    }
+}
+
+static UNITS: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_units(unit: Unit) {
+    UNITS.store(unit as u8, Ordering::Relaxed);
+}
+
+pub fn units() -> Unit {
+    match UNITS.load(Ordering::Relaxed) {
+        1 => Unit::Kilometers,
+        2 => Unit::Miles,
+        3 => Unit::Feet,
+        4 => Unit::Acres,
+        5 => Unit::Hectares,
+        _ => Unit::Meters,
+    }
+}
+
+// The unit lengths are given in. Areal --units leave lengths in meters.
+pub fn length_unit() -> Unit {
+    match units() {
+        u if u.is_area() => Unit::Meters,
+        u => u,
+    }
+}
+
+// Converts a length in --units to meters
+pub fn to_meters(length: f64) -> f64 {
+    length * length_unit().size()
+}
+
+// Converts a length in meters to --units
+pub fn from_meters(meters: f64) -> f64 {
+    meters / length_unit().size()
+}
+
+// Converts an area in square meters to --units, or to the square of a
+// unit of length, giving the converted area and the name of its units
+pub fn from_square_meters(square_meters: f64) -> (f64, String) {
+    match units() {
+        u if u.is_area() => (square_meters / u.size(), u.name().to_string()),
+        u => (
+            square_meters / (u.size() * u.size()),
+            format!("square {}", u.name()),
+        ),
+    }
+}
+
+// Reads a length such as 5km, 30mi or 100 as meters. Lengths with no
+// suffix are in --units.
+pub fn parse_length(s: &str) -> Result<f64, Error> {
+    let s = s.trim();
+    let split = s
+        .rfind(|c: char| !c.is_ascii_alphabetic())
+        .map_or(0, |i| i + 1);
+    let (number, suffix) = s.split_at(split);
+    let unit = match suffix {
+        "" => length_unit(),
+        suffix => suffix.parse()?,
+    };
+    if unit.is_area() {
+        return Err(Error::InvalidInput(format!(
+            "Expected a length, not an area: {}",
+            s
+        )));
+    }
+    let n: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| Error::InvalidNumberFormat(format!("Expected a length: {}", s)))?;
+    Ok(n * unit.size())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_square_meters, parse_length, Unit};
+
+    #[test]
+    fn test_parse_length() {
+        assert_eq!(5000.0, parse_length("5km").unwrap());
+        assert_eq!(30.0 * 1609.344, parse_length("30mi").unwrap());
+        assert_eq!(-3.048, parse_length("-10 ft").unwrap());
+        assert_eq!(100.0, parse_length("100").unwrap());
+        assert_eq!(1000.0, parse_length("1e3m").unwrap());
+        assert!(parse_length("5ha").is_err());
+        assert!(parse_length("5 parsecs").is_err());
+        assert!(parse_length("km").is_err());
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!(Unit::Hectares, "ha".parse().unwrap());
+        assert!("furlongs".parse::<Unit>().is_err());
+        assert_eq!((1.0, "square meters".to_string()), from_square_meters(1.0));
+    }
+}
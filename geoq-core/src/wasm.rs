@@ -0,0 +1,83 @@
+//! JavaScript bindings for the conversions in convert, built for
+//! wasm32-unknown-unknown with wasm-pack:
+//!
+//!   wasm-pack build geoq-core -- --no-default-features
+//!
+//! Errors are thrown as JS Errors, with the kind of geoq error and its
+//! message.
+use crate::{convert, error::Error};
+use wasm_bindgen::prelude::*;
+
+fn js_error(e: Error) -> JsError {
+    match e.message() {
+        Some(message) => JsError::new(&format!("{}: {}", e.kind(), message)),
+        None => JsError::new(e.kind()),
+    }
+}
+
+#[wasm_bindgen(js_name = inputType)]
+pub fn input_type(input: &str) -> Result<String, JsError> {
+    convert::input_type(input)
+        .map(|t| t.to_string())
+        .map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = toGeoJson)]
+pub fn to_geojson(input: &str) -> Result<String, JsError> {
+    convert::to_geojson(input).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = toWkt)]
+pub fn to_wkt(input: &str) -> Result<String, JsError> {
+    convert::to_wkt(input).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = toWkb)]
+pub fn to_wkb(input: &str) -> Result<Vec<u8>, JsError> {
+    convert::to_wkb(input).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = fromWkb)]
+pub fn from_wkb(data: &[u8]) -> Result<String, JsError> {
+    convert::from_wkb(data).map_err(js_error)
+}
+
+#[cfg(feature = "fgb")]
+#[wasm_bindgen(js_name = toFgb)]
+pub fn to_fgb(input: &str) -> Result<Vec<u8>, JsError> {
+    convert::to_fgb(input).map_err(js_error)
+}
+
+/// [min lon, min lat, max lon, max lat]
+#[wasm_bindgen]
+pub fn bbox(input: &str) -> Result<Vec<f64>, JsError> {
+    convert::bbox(input).map(|b| b.to_vec()).map_err(js_error)
+}
+
+#[wasm_bindgen]
+pub fn hilbert(input: &str, extent: &[f64]) -> Result<u32, JsError> {
+    let extent = match *extent {
+        [min_x, min_y, max_x, max_y] => [min_x, min_y, max_x, max_y],
+        _ => {
+            return Err(JsError::new(
+                "Expected an extent of [min lon, min lat, max lon, max lat]",
+            ))
+        }
+    };
+    convert::hilbert(input, extent).map_err(js_error)
+}
+
+#[wasm_bindgen]
+pub fn geohash(lat: f64, lon: f64, level: usize) -> Result<String, JsError> {
+    convert::geohash(lat, lon, level).map_err(js_error)
+}
+
+#[wasm_bindgen]
+pub fn tile(lat: f64, lon: f64, zoom: u8) -> Result<String, JsError> {
+    convert::tile(lat, lon, zoom).map_err(js_error)
+}
+
+#[wasm_bindgen]
+pub fn quadkey(tile: &str) -> Result<String, JsError> {
+    convert::quadkey(tile).map_err(js_error)
+}
@@ -0,0 +1,67 @@
+// Counts heap allocations, for `geoq bench` to report how many each line
+// of input takes. With the bench feature this is geoq's global allocator:
+// it passes everything on to the system allocator, adding to two counters
+// as it goes. Other builds use the system allocator directly, and nothing
+// is counted.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub struct Counting;
+
+static COUNT: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+
+fn record(size: usize) {
+    COUNT.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(size as u64, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    // A reallocation counts as allocating its new size
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+// Allocations made so far, across all threads
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub count: u64,
+    pub bytes: u64,
+}
+
+// None unless built with the bench feature
+pub fn stats() -> Option<Stats> {
+    if !cfg!(feature = "bench") {
+        return None;
+    }
+    Some(Stats {
+        count: COUNT.load(Ordering::Relaxed),
+        bytes: BYTES.load(Ordering::Relaxed),
+    })
+}
+
+impl Stats {
+    // Allocations made between an earlier snapshot and this one
+    pub fn since(self, earlier: Stats) -> Stats {
+        Stats {
+            count: self.count - earlier.count,
+            bytes: self.bytes - earlier.bytes,
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Adding subcommands to geoq from another crate. A [`Command`] gives its
+//! name and clap arguments, and handles the entities of stdin with the
+//! same parsing, parallelism and output formats as geoq's own commands.
+//! Pass commands to [`main_with`](crate::main_with) in place of
+//! `geoq::main`:
+//!
+//! ```no_run
+//! use clap::{Arg, ArgMatches};
+//! use geoq::geoq::{
+//!     command::{Command, EntityStream, OutputSink},
+//!     error::Error,
+//! };
+//!
+//! // geoq vertices --min 10: the number of vertices of each geometry
+//! struct Vertices;
+//!
+//! impl Command for Vertices {
+//!     fn name(&self) -> &'static str {
+//!         "vertices"
+//!     }
+//!
+//!     fn about(&self) -> &'static str {
+//!         "Count the vertices of each geometry"
+//!     }
+//!
+//!     fn args(&self) -> Vec<Arg<'static, 'static>> {
+//!         vec![Arg::with_name("min").long("min").takes_value(true)]
+//!     }
+//!
+//!     fn run(&self, matches: &ArgMatches, entities: EntityStream, out: &mut OutputSink) -> Result<(), Error> {
+//!         let min: usize = matches.value_of("min").unwrap_or("0").parse().unwrap();
+//!         entities.par_map(out, move |e| {
+//!             let n = geoq::geoq::coord_count::coord_count(&e.geom());
+//!             Ok(if n >= min { vec![n.to_string()] } else { vec![] })
+//!         })
+//!     }
+//! }
+//!
+//! fn main() {
+//!     geoq::main_with(vec![Box::new(Vertices)]);
+//! }
+//! ```
+use crate::geoq::{
+    entity::Entity,
+    error::Error,
+    output,
+    par::{self, ParOptions},
+    reader,
+};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::{
+    io::{self, BufWriter, Write},
+    mem,
+};
+
+pub trait Command: Send + Sync {
+    // The subcommand's name, which must differ from geoq's own commands
+    // and the other extensions
+    fn name(&self) -> &'static str;
+
+    // One line of help, shown in geoq --help
+    fn about(&self) -> &'static str {
+        ""
+    }
+
+    fn args(&self) -> Vec<Arg<'static, 'static>> {
+        vec![]
+    }
+
+    fn run(
+        &self,
+        matches: &ArgMatches,
+        entities: EntityStream,
+        out: &mut OutputSink,
+    ) -> Result<(), Error>;
+}
+
+// The command's clap subcommand, for adding to geoq's
+pub fn subcommand(command: &dyn Command) -> App<'static, 'static> {
+    SubCommand::with_name(command.name())
+        .about(command.about())
+        .args(&command.args())
+}
+
+// The entities of stdin, read in any format geoq reads and with input
+// errors handled as --on-error says
+pub struct EntityStream {
+    par: ParOptions,
+}
+
+impl EntityStream {
+    pub(crate) fn stdin(par: ParOptions) -> EntityStream {
+        EntityStream { par }
+    }
+
+    // Handles each entity in turn
+    pub fn for_each<F>(self, handler: F) -> Result<(), Error>
+    where
+        F: FnMut(Entity) -> Result<(), Error>,
+    {
+        reader::for_entity(handler)
+    }
+
+    // Handles entities in parallel, as geoq's per-entity commands do,
+    // writing the lines the handler gives for each to the output in input
+    // order (unless --unordered)
+    pub fn par_map<F>(self, out: &mut OutputSink, handler: F) -> Result<(), Error>
+    where
+        F: Send + Sync + 'static + Fn(Entity) -> Result<Vec<String>, Error>,
+    {
+        // The writer is lent to the parallel printer, and given back after
+        let sink = mem::replace(&mut out.out, Box::new(io::sink()));
+        out.out = par::for_stdin_entity_to(&self.par, sink, handler)?;
+        Ok(())
+    }
+}
+
+// Stdout, written in the --format and style given to geoq
+pub struct OutputSink {
+    out: Box<dyn Write + Send>,
+}
+
+impl OutputSink {
+    pub(crate) fn stdout() -> OutputSink {
+        OutputSink {
+            out: Box::new(BufWriter::with_capacity(
+                reader::OUTPUT_BUF_SIZE,
+                io::stdout(),
+            )),
+        }
+    }
+
+    // Writes a line of output, such as a GeoJSON feature
+    pub fn write(&mut self, line: &str) -> Result<(), Error> {
+        output::write_line(&mut self.out, line)?;
+        Ok(())
+    }
+
+    pub(crate) fn flush(&mut self) -> Result<(), Error> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, EntityStream, OutputSink};
+    use crate::geoq::error::Error;
+    use clap::{Arg, ArgMatches};
+
+    struct Echo;
+
+    impl Command for Echo {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn args(&self) -> Vec<Arg<'static, 'static>> {
+            vec![Arg::with_name("times").long("times").takes_value(true)]
+        }
+
+        fn run(&self, _: &ArgMatches, _: EntityStream, _: &mut OutputSink) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    struct Named(&'static str);
+
+    impl Command for Named {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn run(&self, _: &ArgMatches, _: EntityStream, _: &mut OutputSink) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_app_with() {
+        let commands: Vec<Box<dyn Command>> = vec![Box::new(Echo)];
+        let matches = crate::app_with(&commands)
+            .unwrap()
+            .get_matches_from_safe(vec!["geoq", "echo", "--times", "2"])
+            .unwrap();
+        assert_eq!(
+            Some("2"),
+            matches
+                .subcommand_matches("echo")
+                .and_then(|m| m.value_of("times"))
+        );
+        assert!(crate::app()
+            .get_matches_from_safe(vec!["geoq", "echo"])
+            .is_err());
+
+        // Names can't be taken twice, or from geoq's own commands
+        let twice: Vec<Box<dyn Command>> = vec![Box::new(Echo), Box::new(Echo)];
+        assert!(crate::app_with(&twice).is_err());
+        assert!(crate::app_with(&[Box::new(Named("wkt")) as Box<dyn Command>]).is_err());
+        assert!(crate::app_with(&[Box::new(Named("help")) as Box<dyn Command>]).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use crate::geoq::{entity::Entity, error::Error, expr, output, reader, union::cascaded_union};
+use clap::ArgMatches;
+use geo_types::{Geometry, GeometryCollection, MultiPolygon};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+enum Key {
+    Id,
+    Property(String),
+}
+
+#[derive(Clone, Copy)]
+enum Agg {
+    Sum,
+    Min,
+    Max,
+    Mean,
+}
+
+impl Agg {
+    fn name(&self) -> &'static str {
+        match self {
+            Agg::Sum => "sum",
+            Agg::Min => "min",
+            Agg::Max => "max",
+            Agg::Mean => "mean",
+        }
+    }
+}
+
+struct Stat {
+    n: usize,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Stat {
+    fn new() -> Stat {
+        Stat {
+            n: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn add(&mut self, v: f64) {
+        self.n += 1;
+        self.sum += v;
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+    }
+
+    fn value(&self, agg: Agg) -> Value {
+        let v = match agg {
+            Agg::Sum => Some(self.sum),
+            _ if self.n == 0 => None,
+            Agg::Min => Some(self.min),
+            Agg::Max => Some(self.max),
+            Agg::Mean => Some(self.sum / self.n as f64),
+        };
+        v.map(|v| expr::Value::Num(v).to_json())
+            .unwrap_or(Value::Null)
+    }
+}
+
+struct Group {
+    key: Value,
+    count: usize,
+    stats: Vec<Stat>,
+    polygons: Vec<MultiPolygon<f64>>,
+    others: Vec<Geometry<f64>>,
+}
+
+fn parse_key(matches: &ArgMatches) -> Result<Key, Error> {
+    match matches.value_of("by").unwrap() {
+        "id" => Ok(Key::Id),
+        by if by.starts_with("property:") && by.len() > "property:".len() => {
+            Ok(Key::Property(by["property:".len()..].to_string()))
+        }
+        by => Err(Error::InvalidInput(format!(
+            "Invalid group key: {} -- expected id or property:<name>",
+            by
+        ))),
+    }
+}
+
+fn parse_aggs(matches: &ArgMatches) -> Vec<(Agg, String)> {
+    let mut aggs = vec![];
+    for agg in &[Agg::Sum, Agg::Min, Agg::Max, Agg::Mean] {
+        if let Some(props) = matches.values_of(agg.name()) {
+            for p in props {
+                aggs.push((*agg, p.to_string()));
+            }
+        }
+    }
+    aggs
+}
+
+fn key_value(key: &Key, e: &Entity, props: &Map<String, Value>) -> Value {
+    match key {
+        Key::Id => e
+            .id()
+            .and_then(|id| serde_json::to_value(&id).ok())
+            .unwrap_or(Value::Null),
+        Key::Property(name) => props.get(name).cloned().unwrap_or(Value::Null),
+    }
+}
+
+fn add_geom(group: &mut Group, geom: Geometry<f64>) {
+    match geom {
+        Geometry::Polygon(p) => group.polygons.push(MultiPolygon(vec![p])),
+        Geometry::MultiPolygon(mp) => group.polygons.push(mp),
+        Geometry::GeometryCollection(gc) => {
+            for g in gc {
+                add_geom(group, g);
+            }
+        }
+        g => group.others.push(g),
+    }
+}
+
+fn dissolve(group: Group) -> Geometry<f64> {
+    let union = cascaded_union(group.polygons).map(Geometry::MultiPolygon);
+    match (union, group.others.len()) {
+        (Some(u), 0) => u,
+        (None, 1) => group.others.into_iter().next().unwrap(),
+        (u, _) => {
+            let mut geoms: Vec<Geometry<f64>> = u.into_iter().collect();
+            geoms.extend(group.others);
+            Geometry::GeometryCollection(GeometryCollection(geoms))
+        }
+    }
+}
+
+fn key_name(key: &Key) -> &str {
+    match key {
+        Key::Id => "id",
+        Key::Property(name) => name,
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let key = parse_key(matches)?;
+    let aggs = parse_aggs(matches);
+    let union_geometry = matches.is_present("union-geometry");
+
+    let mut groups: Vec<Group> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    reader::for_entity(|e| {
+        let props = e.geojson_properties();
+        let k = key_value(&key, &e, &props);
+        let i = *index.entry(k.to_string()).or_insert_with(|| {
+            groups.push(Group {
+                key: k.clone(),
+                count: 0,
+                stats: aggs.iter().map(|_| Stat::new()).collect(),
+                polygons: vec![],
+                others: vec![],
+            });
+            groups.len() - 1
+        });
+        let group = &mut groups[i];
+        group.count += 1;
+        for ((_, prop), stat) in aggs.iter().zip(group.stats.iter_mut()) {
+            if let Some(v) = props.get(prop).and_then(|v| v.as_f64()) {
+                stat.add(v);
+            }
+        }
+        if union_geometry && !e.is_empty() {
+            add_geom(group, e.geom());
+        }
+        Ok(())
+    })?;
+
+    for group in groups {
+        let mut props = Map::new();
+        props.insert(key_name(&key).to_string(), group.key.clone());
+        props.insert("count".to_string(), Value::from(group.count));
+        for ((agg, prop), stat) in aggs.iter().zip(group.stats.iter()) {
+            props.insert(format!("{}_{}", prop, agg.name()), stat.value(*agg));
+        }
+        let geometry = if union_geometry {
+            Some(geojson::Geometry::new(geojson::Value::from(&dissolve(
+                group,
+            ))))
+        } else {
+            None
+        };
+        let feature = geojson::Feature {
+            bbox: None,
+            geometry,
+            id: None,
+            properties: Some(props),
+            foreign_members: None,
+        };
+        output::print(&serde_json::to_string(&feature)?)?;
+    }
+    Ok(())
+}
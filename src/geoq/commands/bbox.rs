@@ -1,8 +1,55 @@
-use crate::geoq::{bbox, bbox::BBoxToPoly, error::Error, par, reader};
+use crate::geoq::{
+    bbox,
+    bbox::BBoxToPoly,
+    commands::tile::for_arg_or_line,
+    entity,
+    error::Error,
+    input, output,
+    par::{self, ParOptions},
+    reader,
+};
 use clap::ArgMatches;
 use geo_types::Rect;
 
-pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+// Applies f to each bbox, given as an argument or read from STDIN one per
+// line. Bbox strings give bbox strings, and entities the bbox polygons of
+// their envelopes, as for geoq bbox. Bboxes f gives nothing for are
+// left out.
+fn for_bbox<F>(matches: &ArgMatches, f: F) -> Result<(), Error>
+where
+    F: Fn(&Rect<f64>) -> Option<Rect<f64>>,
+{
+    for_arg_or_line(matches, "bbox", |line| {
+        if let Ok(r) = bbox::parse(line) {
+            return Ok(f(&r).iter().map(bbox::format).collect());
+        }
+        let mut out = vec![];
+        for e in entity::from_input(input::read_line(line.to_string())?)? {
+            if let Some(r) = f(&e.bbox()) {
+                let gj = geojson::Geometry::new(geojson::Value::from(&r.to_polygon_geoq()));
+                out.push(serde_json::to_string(&gj)?);
+            }
+        }
+        Ok(out)
+    })
+}
+
+fn expand(matches: &ArgMatches) -> Result<(), Error> {
+    let margin = bbox::parse_margin(matches.value_of("margin").unwrap())?;
+    for_bbox(matches, |r| bbox::expand(r, margin))
+}
+
+fn intersect(matches: &ArgMatches) -> Result<(), Error> {
+    let other = bbox::parse(matches.value_of("with").unwrap())?;
+    for_bbox(matches, |r| bbox::intersect(r, &other))
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("expand", Some(m)) => return expand(m),
+        ("intersect", Some(m)) => return intersect(m),
+        _ => (),
+    }
     let embed = matches.is_present("embed");
     let all = matches.is_present("all");
 
@@ -26,12 +73,11 @@ pub fn run(matches: &ArgMatches) -> Result<(), Error> {
             Some(bbox) => {
                 let poly = bbox.to_polygon_geoq();
                 let gj = geojson::Geometry::new(geojson::Value::from(&poly));
-                println!("{}", serde_json::to_string(&gj).unwrap());
-                Ok(())
+                output::print(&serde_json::to_string(&gj).unwrap())
             }
         }
     } else {
-        par::for_stdin_entity(move |e| {
+        par::for_stdin_entity(par_opts, move |e| {
             let bbox: Rect<f64> = e.bbox();
 
             if embed {
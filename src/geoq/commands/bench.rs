@@ -0,0 +1,224 @@
+// Throughput benchmarks for comparing machines and releases. Each input
+// file is timed three ways: parsing its lines into entities, parsing and
+// handling them as a command would (in this process, on one thread), and
+// running the command end to end (as a separate geoq process, with its
+// usual threads, reading the file on stdin). The best of several runs
+// is reported.
+use crate::geoq::{
+    allocations::{self, Stats},
+    commands::{centroid, geojson_cmd, wkt},
+    entity::{self, Entity},
+    error::Error,
+    input, reader,
+};
+use clap::ArgMatches;
+use std::{
+    env, fs,
+    fs::File,
+    hint::black_box,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+type Handler = fn(Entity) -> Result<Vec<String>, Error>;
+
+// Commands whose handlers can be run on their own, in this process
+fn handler(command: &str) -> Option<Handler> {
+    match command {
+        "wkt" => Some(wkt::handle),
+        "centroid" => Some(centroid::handle),
+        "gj geom" => Some(geojson_cmd::geom_lines),
+        "gj f" => Some(geojson_cmd::feature_lines),
+        _ => None,
+    }
+}
+
+struct Stage {
+    name: &'static str,
+    elapsed: Duration,
+    // Allocations in a single run, when they were made in this process
+    // and counted (with the bench feature)
+    allocations: Option<Stats>,
+}
+
+// Parses each line of the input, handing its entities to the handler if
+// there is one, and gives back the number of lines
+fn parse(data: &[u8], handler: Option<Handler>) -> Result<usize, Error> {
+    let mut rest = data;
+    let (mut lines, mut offset) = (0, 0);
+    while let Some((len, line)) = reader::split_line(&mut rest) {
+        lines += 1;
+        let located = |e: Error| e.at(lines, offset, line);
+        let entities = input::read_line(line.to_string())
+            .and_then(entity::from_input)
+            .map_err(located)?;
+        for e in entities {
+            match handler {
+                Some(handle) => {
+                    black_box(handle(e).map_err(located)?);
+                }
+                None => {
+                    black_box(e);
+                }
+            }
+        }
+        offset += len as u64;
+    }
+    Ok(lines)
+}
+
+fn best_of<F>(iterations: usize, mut run: F) -> Result<Duration, Error>
+where
+    F: FnMut() -> Result<(), Error>,
+{
+    let mut best = Duration::MAX;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        run()?;
+        best = best.min(start.elapsed());
+    }
+    Ok(best)
+}
+
+fn in_process(
+    name: &'static str,
+    data: &[u8],
+    handler: Option<Handler>,
+    iterations: usize,
+) -> Result<Stage, Error> {
+    let before = allocations::stats();
+    parse(data, handler)?;
+    let allocations = allocations::stats()
+        .zip(before)
+        .map(|(now, b)| now.since(b));
+    let elapsed = best_of(iterations, || parse(data, handler).map(|_| ()))?;
+    Ok(Stage {
+        name,
+        elapsed,
+        allocations,
+    })
+}
+
+fn end_to_end(path: &str, command: &[&str], iterations: usize) -> Result<Stage, Error> {
+    let exe = env::current_exe()?;
+    let elapsed = best_of(iterations, || {
+        let status = Command::new(&exe)
+            .args(command)
+            .stdin(File::open(path)?)
+            .stdout(Stdio::null())
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::ProgramError(format!(
+                "geoq {} failed with {}",
+                command.join(" "),
+                status
+            )))
+        }
+    })?;
+    Ok(Stage {
+        name: "end-to-end",
+        elapsed,
+        allocations: None,
+    })
+}
+
+fn row(stage: &Stage, lines: usize) -> String {
+    let secs = stage.elapsed.as_secs_f64();
+    let rate = if secs > 0.0 {
+        (lines as f64 / secs).round() as u64
+    } else {
+        0
+    };
+    let per_line = |n: u64| format!("{:.1}", n as f64 / lines.max(1) as f64);
+    let (count, bytes) = match stage.allocations {
+        Some(a) => (per_line(a.count), per_line(a.bytes)),
+        None => ("-".to_string(), "-".to_string()),
+    };
+    format!(
+        "{:<14}{:>12}{:>14}{:>14}{:>12.3}",
+        stage.name, rate, count, bytes, secs
+    )
+}
+
+fn read_iterations(matches: &ArgMatches) -> Result<usize, Error> {
+    let iterations = matches.value_of("iterations").unwrap();
+    match iterations.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected positive number of iterations: {}",
+            iterations
+        ))),
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let path = matches.value_of("input").unwrap();
+    let command: Vec<&str> = matches
+        .value_of("command")
+        .unwrap()
+        .split_whitespace()
+        .collect();
+    let iterations = read_iterations(matches)?;
+    let data = fs::read(path)?;
+
+    let lines = parse(&data, None)?;
+    let mut stages = vec![in_process("parse", &data, None, iterations)?];
+    match handler(&command.join(" ")) {
+        Some(h) => stages.push(in_process("parse+handle", &data, Some(h), iterations)?),
+        None => eprintln!(
+            "Skipping parse+handle: geoq {} can only be run end to end",
+            command.join(" ")
+        ),
+    }
+    stages.push(end_to_end(path, &command, iterations)?);
+
+    println!(
+        "{}: {} lines, {} bytes, best of {}",
+        path,
+        lines,
+        data.len(),
+        iterations
+    );
+    println!(
+        "{:<14}{:>12}{:>14}{:>14}{:>12}",
+        "stage", "lines/s", "allocs/line", "bytes/line", "seconds"
+    );
+    for stage in &stages {
+        println!("{}", row(stage, lines));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, row, Stage};
+    use crate::geoq::allocations::Stats;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse() {
+        let data = b"POINT (1 2)\n9q5\n{\"type\":\"Point\",\"coordinates\":[1,2]}\n";
+        assert_eq!(3, parse(data, None).unwrap());
+        let err = parse(b"9q5\nPOINT (1\n", Some(super::handler("wkt").unwrap())).unwrap_err();
+        assert_eq!(2, err.location().unwrap().line);
+        assert_eq!(4, err.location().unwrap().offset);
+    }
+
+    #[test]
+    fn test_row() {
+        let stage = Stage {
+            name: "parse",
+            elapsed: Duration::from_millis(500),
+            allocations: Some(Stats {
+                count: 30,
+                bytes: 1000,
+            }),
+        };
+        assert_eq!(
+            "parse                   40           1.5          50.0       0.500",
+            row(&stage, 20)
+        );
+    }
+}
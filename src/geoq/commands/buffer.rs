@@ -0,0 +1,40 @@
+// Buffers geometries by a distance, geodesically for lon/lat data or in
+// the coordinates' own units with --planar
+use crate::geoq::{
+    buffer,
+    error::Error,
+    par::{self, ParOptions},
+    units,
+};
+use clap::ArgMatches;
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let distance = matches.value_of("distance").unwrap();
+    let planar = matches.is_present("planar");
+    let distance = if planar {
+        distance.parse::<f64>().map_err(|_| {
+            Error::InvalidNumberFormat(format!(
+                "Expected a distance in the coordinates' units: {}",
+                distance
+            ))
+        })?
+    } else {
+        units::parse_length(distance)?
+    };
+    par::for_stdin_entity(par_opts, move |e| {
+        let geom = e.geom();
+        let buffered = if planar {
+            buffer::planar_buffer(&geom, distance)
+        } else {
+            buffer::buffer(&geom, distance)
+        };
+        let feature = geojson::Feature {
+            bbox: None,
+            geometry: Some(geojson::Geometry::new(geojson::Value::from(&buffered))),
+            id: e.id(),
+            properties: Some(e.geojson_properties()),
+            foreign_members: e.foreign_members(),
+        };
+        Ok(vec![serde_json::to_string(&feature)?])
+    })
+}
@@ -0,0 +1,45 @@
+// Concatenates datasets of any formats geoq reads into one stream of
+// GeoJSON features, all with the same property keys
+use crate::geoq::{error::Error, other, output};
+use clap::ArgMatches;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let files: Vec<&str> = matches.values_of("files").unwrap().collect();
+    let source = matches.value_of("source");
+
+    // The union of the files' property keys, in the order they're first
+    // seen. Files are read twice rather than held in memory, so that
+    // large ones can be combined
+    let mut keys: Vec<String> = vec![];
+    let mut seen: HashSet<String> = HashSet::new();
+    for path in &files {
+        other::for_entity(path, |e| {
+            for k in e.geojson_properties().keys() {
+                if !seen.contains(k) {
+                    seen.insert(k.clone());
+                    keys.push(k.clone());
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    for path in &files {
+        other::for_entity(path, |e| {
+            let mut feature = e.geojson_feature();
+            let mut given = feature.properties.take().unwrap_or_default();
+            let mut props = Map::new();
+            for k in &keys {
+                props.insert(k.clone(), given.remove(k).unwrap_or(Value::Null));
+            }
+            if let Some(s) = source {
+                props.insert(s.to_string(), Value::String(path.to_string()));
+            }
+            feature.properties = Some(props);
+            output::print(&serde_json::to_string(&feature)?)
+        })?;
+    }
+    Ok(())
+}
@@ -1,22 +1,32 @@
-use crate::geoq::{self, error::Error, reader};
+use crate::geoq::{self, entity::Entity, error::Error, reader, report};
 use geo_types::{Geometry, Point};
 use geojson;
 use serde_json;
 
-fn gj_point(point: Point<f64>) -> String {
+fn gj_point(point: Point<f64>) -> geojson::Geometry {
     let geom = Geometry::Point(point);
-    let gj = geojson::Geometry::new(geojson::Value::from(&geom));
-    serde_json::to_string(&gj).unwrap()
+    geojson::Geometry::new(geojson::Value::from(&geom))
 }
 
-pub fn run() -> Result<(), Error> {
-    reader::for_entity(|e| {
-        let raw = e.raw();
-        let g = e.geom();
-        match geoq::centroid::centroid(&g) {
-            Some(point) => println!("{}", gj_point(point)),
-            None => eprintln!("Could not calculate centroid for geom: {}", raw),
+// The entity's centroid, warning of entities which have none
+pub fn centroid_geometry(e: &Entity) -> Option<geojson::Geometry> {
+    let g = e.geom();
+    match geoq::centroid::centroid(&g) {
+        Some(point) => Some(gj_point(point)),
+        None => {
+            report::warning("Could not calculate centroid for geom", &e.raw());
+            None
         }
-        Ok(())
-    })
+    }
+}
+
+pub fn handle(e: Entity) -> Result<Vec<String>, Error> {
+    Ok(centroid_geometry(&e)
+        .map(|g| serde_json::to_string(&g).unwrap())
+        .into_iter()
+        .collect())
+}
+
+pub fn run() -> Result<(), Error> {
+    reader::for_entity_lines(handle)
 }
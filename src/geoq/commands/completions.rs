@@ -0,0 +1,13 @@
+use crate::geoq::error::Error;
+use clap::{ArgMatches, Shell};
+use std::io;
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let shell: Shell = matches
+        .value_of("shell")
+        .unwrap()
+        .parse()
+        .map_err(Error::InvalidInput)?;
+    crate::app().gen_completions_to("geoq", shell, &mut io::stdout());
+    Ok(())
+}
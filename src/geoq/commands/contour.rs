@@ -0,0 +1,144 @@
+use crate::geoq::{
+    commands::raster::value_json,
+    contour::{delaunay, Contours, Levels, Vertex},
+    entity::Entity,
+    error::Error,
+    output,
+    raster::geotiff,
+    reader,
+};
+use clap::ArgMatches;
+use geo_types::{Coord, Geometry};
+use serde_json::Map;
+use std::collections::HashSet;
+
+fn read_f64(matches: &ArgMatches, arg: &str) -> Result<f64, Error> {
+    let v = matches.value_of(arg).unwrap();
+    v.parse::<f64>()
+        .map_err(|_| Error::InvalidNumberFormat(format!("Expected number for --{}: {}", arg, v)))
+}
+
+fn read_levels(matches: &ArgMatches) -> Result<Levels, Error> {
+    let interval = read_f64(matches, "interval")?;
+    if !(interval > 0.0 && interval.is_finite()) {
+        return Err(Error::InvalidInput(format!(
+            "Contour interval must be positive: {}",
+            interval
+        )));
+    }
+    Ok(Levels {
+        base: read_f64(matches, "base")?,
+        interval,
+    })
+}
+
+// Pixel centers are joined into two triangles per cell, leaving out
+// triangles touching nodata
+fn raster_contours(matches: &ArgMatches, path: &str, levels: Levels) -> Result<Contours, Error> {
+    let band = matches.value_of("band").unwrap();
+    let band = band
+        .parse::<usize>()
+        .map_err(|_| Error::InvalidNumberFormat(format!("Expected band number: {}", band)))?;
+    let raster = geotiff::read(path, band)?;
+    let mut contours = Contours::new(levels);
+    let vertex = |col: usize, row: usize| -> Option<Vertex> {
+        let z = raster.get(col, row);
+        if raster.is_nodata(z) {
+            None
+        } else {
+            let position = raster.to_lon_lat(col as f64 + 0.5, row as f64 + 0.5);
+            Some((row * raster.width + col, position, z))
+        }
+    };
+    for row in 0..raster.height.saturating_sub(1) {
+        for col in 0..raster.width.saturating_sub(1) {
+            let tl = vertex(col, row);
+            let tr = vertex(col + 1, row);
+            let br = vertex(col + 1, row + 1);
+            let bl = vertex(col, row + 1);
+            if let (Some(tl), Some(tr), Some(br)) = (tl, tr, br) {
+                contours.add_triangle([tl, tr, br]);
+            }
+            if let (Some(tl), Some(br), Some(bl)) = (tl, br, bl) {
+                contours.add_triangle([tl, br, bl]);
+            }
+        }
+    }
+    Ok(contours)
+}
+
+// Height of a point: from a property if given, otherwise its Z coordinate
+fn point_z(e: &Entity, z_property: Option<&str>) -> Option<f64> {
+    if let Some(prop) = z_property {
+        return e.geojson_properties().get(prop).and_then(|v| v.as_f64());
+    }
+    let value = match e {
+        Entity::GeoJsonFeature(_, f) => f.geometry.as_ref().map(|g| &g.value),
+        Entity::GeoJsonGeometry(_, g) => Some(&g.value),
+        _ => None,
+    };
+    match value {
+        Some(geojson::Value::Point(p)) if p.len() > 2 => Some(p[2]),
+        _ => None,
+    }
+}
+
+// Scattered points are interpolated over their Delaunay triangulation
+fn point_contours(matches: &ArgMatches, levels: Levels) -> Result<Contours, Error> {
+    let z_property = matches.value_of("z-property");
+    let mut positions: Vec<Coord<f64>> = vec![];
+    let mut heights: Vec<f64> = vec![];
+    let mut seen = HashSet::new();
+    reader::for_entity(|e| {
+        let position = match e.geom() {
+            Geometry::Point(p) => p.0,
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "Contour input must be points: {}",
+                    e.raw()
+                )))
+            }
+        };
+        let z = point_z(&e, z_property).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "Expected point with Z coordinate or elevation property: {}",
+                e.raw()
+            ))
+        })?;
+        // Only the first of several points at the same position is used
+        if seen.insert((position.x.to_bits(), position.y.to_bits())) {
+            positions.push(position);
+            heights.push(z);
+        }
+        Ok(())
+    })?;
+    let mut contours = Contours::new(levels);
+    for t in delaunay(&positions) {
+        let vertex = |i: usize| (i, positions[i], heights[i]);
+        contours.add_triangle([vertex(t[0]), vertex(t[1]), vertex(t[2])]);
+    }
+    Ok(contours)
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let levels = read_levels(matches)?;
+    let contours = match matches.value_of("raster") {
+        Some(path) => raster_contours(matches, path, levels)?,
+        None => point_contours(matches, levels)?,
+    };
+    for (level, line) in contours.lines() {
+        let mut properties = Map::new();
+        properties.insert("elevation".to_string(), value_json(level));
+        let feature = geojson::Feature {
+            bbox: None,
+            geometry: Some(geojson::Geometry::new(geojson::Value::from(
+                &Geometry::LineString(line),
+            ))),
+            id: None,
+            properties: Some(properties),
+            foreign_members: None,
+        };
+        output::print(&serde_json::to_string(&feature).unwrap())?;
+    }
+    Ok(())
+}
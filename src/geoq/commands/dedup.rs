@@ -0,0 +1,79 @@
+use crate::geoq::{entity::Entity, error::Error, reader};
+use clap::ArgMatches;
+use geo::algorithm::map_coords::MapCoords;
+use geo_types::Geometry;
+use std::collections::HashSet;
+
+enum Key {
+    Geometry(Option<f64>),
+    Id,
+    Property(String),
+}
+
+fn parse_key(matches: &ArgMatches) -> Result<Key, Error> {
+    let tolerance = match matches.value_of("tolerance") {
+        None => None,
+        Some(t) => match t.parse::<f64>() {
+            Ok(t) if t > 0.0 => Some(t),
+            _ => {
+                return Err(Error::InvalidNumberFormat(format!(
+                    "Invalid tolerance: {} -- must be a positive number, e.g. 1e-7",
+                    t
+                )))
+            }
+        },
+    };
+    match matches.value_of("by").unwrap_or("geometry") {
+        "geometry" => Ok(Key::Geometry(tolerance)),
+        "id" => Ok(Key::Id),
+        by if by.starts_with("property:") && by.len() > "property:".len() => {
+            Ok(Key::Property(by["property:".len()..].to_string()))
+        }
+        by => Err(Error::InvalidInput(format!(
+            "Invalid dedup key: {} -- expected geometry, id, or property:<name>",
+            by
+        ))),
+    }
+}
+
+fn snap(geom: &Geometry<f64>, tolerance: f64) -> Geometry<f64> {
+    geom.map_coords(|c| geo_types::Coord {
+        x: (c.x / tolerance).round() * tolerance,
+        y: (c.y / tolerance).round() * tolerance,
+    })
+}
+
+// The key as JSON bytes. Whole keys are kept rather than hashes of them,
+// so that a hash collision can't drop a distinct feature. Features which
+// don't have the requested key (e.g. no id) are never considered
+// duplicates.
+fn key_bytes(key: &Key, e: &Entity) -> Option<Vec<u8>> {
+    match key {
+        Key::Geometry(tolerance) => {
+            let geom = match tolerance {
+                Some(t) => snap(&e.geom(), *t),
+                None => e.geom(),
+            };
+            let gj = geojson::Geometry::new(geojson::Value::from(&geom));
+            Some(serde_json::to_vec(&gj).unwrap())
+        }
+        Key::Id => e.id().map(|id| serde_json::to_vec(&id).unwrap()),
+        Key::Property(name) => e
+            .geojson_properties()
+            .get(name)
+            .map(|v| serde_json::to_vec(v).unwrap()),
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let key = parse_key(matches)?;
+    let mut seen: HashSet<Vec<u8>> = HashSet::new();
+    reader::for_entity_lines(|e| match key_bytes(&key, &e) {
+        Some(bytes) => Ok(if seen.insert(bytes) {
+            vec![e.into_raw()]
+        } else {
+            vec![]
+        }),
+        None => Ok(vec![e.into_raw()]),
+    })
+}
@@ -0,0 +1,78 @@
+// Moves each input point a distance on a bearing, along the great circle
+// that sets off on it or, with --rhumb, along the rhumb line that holds it
+// the whole way. With --line, the path there is written instead.
+use crate::geoq::{
+    entity::Entity,
+    error::Error,
+    par::{self, ParOptions},
+    rhumb, units,
+};
+use clap::ArgMatches;
+use geo::algorithm::{
+    haversine_destination::HaversineDestination, haversine_intermediate::HaversineIntermediate,
+};
+use geo_types::{Geometry, LineString, Point};
+
+struct Options {
+    bearing: f64,
+    meters: f64,
+    rhumb: bool,
+    // Longest segment of --line paths, in meters
+    line: Option<f64>,
+}
+
+fn destination(p: Point<f64>, opts: &Options) -> Result<Geometry<f64>, Error> {
+    if opts.rhumb {
+        return Ok(match opts.line {
+            Some(max) => Geometry::LineString(rhumb::path(p.0, opts.bearing, opts.meters, max)?),
+            None => Geometry::Point(Point(rhumb::destination(p.0, opts.bearing, opts.meters)?)),
+        });
+    }
+    let to = p.haversine_destination(opts.bearing, opts.meters);
+    Ok(match opts.line {
+        Some(max) => Geometry::LineString(LineString::from(
+            p.haversine_intermediate_fill(&to, max, true),
+        )),
+        None => Geometry::Point(to),
+    })
+}
+
+fn handle(e: &Entity, opts: &Options) -> Result<String, Error> {
+    let p = match e.geom() {
+        Geometry::Point(p) => p,
+        _ => {
+            return Err(Error::InvalidInput(format!(
+                "Only points can be moved to a destination: {}",
+                e.raw()
+            )))
+        }
+    };
+    let geom = destination(p, opts)?;
+    let mut feature = e.geojson_feature();
+    feature.geometry = Some(geojson::Geometry::new(geojson::Value::from(&geom)));
+    Ok(serde_json::to_string(&feature)?)
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let bearing = matches.value_of("bearing").unwrap();
+    let bearing = bearing.parse::<f64>().map_err(|_| {
+        Error::InvalidNumberFormat(format!("Expected bearing in degrees: {}", bearing))
+    })?;
+    let line = if matches.is_present("line") {
+        Some(units::parse_length(matches.value_of("densify").unwrap())?)
+    } else {
+        None
+    };
+    if line.is_some_and(|max| max <= 0.0) {
+        return Err(Error::InvalidInput(
+            "Expected a positive --densify distance".to_string(),
+        ));
+    }
+    let opts = Options {
+        bearing,
+        meters: units::parse_length(matches.value_of("distance").unwrap())?,
+        rhumb: matches.is_present("rhumb"),
+        line,
+    };
+    par::for_stdin_entity(par_opts, move |e| Ok(vec![handle(&e, &opts)?]))
+}
@@ -0,0 +1,89 @@
+use crate::geoq::{entity::Entity, error::Error, other, output, reader};
+use clap::ArgMatches;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+enum Key {
+    Id,
+    Property(String),
+}
+
+fn parse_key(matches: &ArgMatches) -> Result<Key, Error> {
+    match matches.value_of("key").unwrap_or("id") {
+        "id" => Ok(Key::Id),
+        k if k.starts_with("property:") && k.len() > "property:".len() => {
+            Ok(Key::Property(k["property:".len()..].to_string()))
+        }
+        k => Err(Error::InvalidInput(format!(
+            "Invalid diff key: {} -- expected id or property:<name>",
+            k
+        ))),
+    }
+}
+
+fn key_for(key: &Key, e: &Entity) -> Result<String, Error> {
+    let k = match key {
+        Key::Id => e.id().map(|id| serde_json::to_string(&id).unwrap()),
+        Key::Property(name) => e
+            .geojson_properties()
+            .get(name)
+            .map(|v| serde_json::to_string(v).unwrap()),
+    };
+    k.ok_or_else(|| Error::InvalidInput(format!("Feature has no diff key: {}", e.raw())))
+}
+
+fn change(mut feature: geojson::Feature, change: &str, changed: Vec<&str>) -> String {
+    let members = feature.foreign_members.get_or_insert_with(Map::new);
+    members.insert("change".to_string(), Value::String(change.to_string()));
+    if !changed.is_empty() {
+        members.insert("changed".to_string(), json!(changed));
+    }
+    serde_json::to_string(&feature).unwrap()
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let key = parse_key(matches)?;
+    let include_unchanged = matches.is_present("unchanged");
+    let path = matches.value_of("other").ok_or(Error::MissingArgument)?;
+
+    let mut old: HashMap<String, geojson::Feature> = HashMap::new();
+    let mut old_order: Vec<String> = Vec::new();
+    other::for_entity(path, |e| {
+        let k = key_for(&key, &e)?;
+        if old.insert(k.clone(), e.geojson_feature()).is_none() {
+            old_order.push(k);
+        }
+        Ok(())
+    })?;
+
+    reader::for_entity_lines(|e| {
+        let k = key_for(&key, &e)?;
+        let new = e.geojson_feature();
+        Ok(match old.remove(&k) {
+            None => vec![change(new, "added", vec![])],
+            Some(prev) => {
+                let mut changed = vec![];
+                if prev.geometry != new.geometry {
+                    changed.push("geometry");
+                }
+                if prev.properties != new.properties {
+                    changed.push("properties");
+                }
+                if !changed.is_empty() {
+                    vec![change(new, "modified", changed)]
+                } else if include_unchanged {
+                    vec![change(new, "unchanged", vec![])]
+                } else {
+                    vec![]
+                }
+            }
+        })
+    })?;
+
+    for k in old_order {
+        if let Some(prev) = old.remove(&k) {
+            output::print(&change(prev, "removed", vec![]))?;
+        }
+    }
+    Ok(())
+}
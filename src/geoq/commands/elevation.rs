@@ -0,0 +1,117 @@
+use crate::geoq::{dem::Dem, entity::Entity, error::Error, generalize::densify, reader, tile};
+use clap::ArgMatches;
+use geo::algorithm::geodesic_distance::GeodesicDistance;
+use geo_types::{Coord, Geometry, LineString, MultiLineString, MultiPolygon, Point, Polygon};
+use serde_json::json;
+
+// Visits each position of a GeoJSON geometry in order
+fn for_position<F>(value: &mut geojson::Value, f: &mut F) -> Result<(), Error>
+where
+    F: FnMut(&mut Vec<f64>) -> Result<(), Error>,
+{
+    match value {
+        geojson::Value::Point(p) => f(p)?,
+        geojson::Value::MultiPoint(ps) | geojson::Value::LineString(ps) => {
+            for p in ps.iter_mut() {
+                f(p)?;
+            }
+        }
+        geojson::Value::MultiLineString(lines) | geojson::Value::Polygon(lines) => {
+            for p in lines.iter_mut().flatten() {
+                f(p)?;
+            }
+        }
+        geojson::Value::MultiPolygon(polys) => {
+            for p in polys.iter_mut().flatten().flatten() {
+                f(p)?;
+            }
+        }
+        geojson::Value::GeometryCollection(geoms) => {
+            for g in geoms.iter_mut() {
+                for_position(&mut g.value, f)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Options<'a> {
+    z: bool,
+    densify: Option<f64>,
+    property: &'a str,
+}
+
+fn elevation(dem: &mut Dem, e: &Entity, opts: &Options) -> Result<String, Error> {
+    let mut feature = e.geojson_feature();
+    if feature.geometry.is_none() {
+        return Ok(serde_json::to_string(&feature).unwrap());
+    }
+    let geom = match opts.densify {
+        Some(m) => densify(&e.geom(), m),
+        None => e.geom(),
+    };
+    let mut value = geojson::Value::from(&geom);
+    let mut points = vec![];
+    for_position(&mut value, &mut |p| {
+        points.push((p[0], p[1]));
+        Ok(())
+    })?;
+    dem.prefetch(&points)?;
+    let mut samples = vec![];
+    for_position(&mut value, &mut |p| {
+        // Rounded to the centimeter, beyond the precision of the data
+        let elevation = dem.sample(p[0], p[1])?.map(|e| (e * 100.0).round() / 100.0);
+        if let (true, Some(e)) = (opts.z, elevation) {
+            p.truncate(2);
+            p.push(e);
+        }
+        samples.push(elevation);
+        Ok(())
+    })?;
+
+    if opts.z || opts.densify.is_some() {
+        feature.geometry = Some(geojson::Geometry::new(value));
+    }
+    if !opts.z {
+        let elevation = match geom {
+            Geometry::Point(_) => json!(samples[0]),
+            _ => json!(samples),
+        };
+        let mut props = e.geojson_properties();
+        props.insert(opts.property.to_string(), elevation);
+        feature.properties = Some(props);
+    }
+    Ok(serde_json::to_string(&feature).unwrap())
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let zoom = matches.value_of("zoom").unwrap();
+    let zoom = match zoom.parse::<u8>() {
+        Ok(z) => tile::validate_zoom(z)?,
+        Err(_) => {
+            return Err(Error::InvalidNumberFormat(format!(
+                "Expected valid tile zoom: {}",
+                zoom
+            )))
+        }
+    };
+    let densify = match matches.value_of("densify") {
+        Some(m) => match m.parse::<f64>() {
+            Ok(m) if m > 0.0 => Some(m),
+            _ => {
+                return Err(Error::InvalidNumberFormat(format!(
+                    "Expected positive densify distance in meters: {}",
+                    m
+                )))
+            }
+        },
+        None => None,
+    };
+    let opts = Options {
+        z: matches.is_present("z"),
+        densify,
+        property: matches.value_of("property").unwrap(),
+    };
+    let mut dem = Dem::new(matches.value_of("dem").unwrap(), zoom);
+    reader::for_entity_lines(|e| Ok(vec![elevation(&mut dem, &e, &opts)?]))
+}
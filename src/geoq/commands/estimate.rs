@@ -0,0 +1,175 @@
+// Predicts how big an export of the input will be, and how much memory
+// writing it takes, by converting a sample of its features and scaling
+// up. The sample is the first --sample features, so the estimate is only
+// as good as they are representative of the rest.
+use crate::geoq::{
+    allocations,
+    binary::wkb,
+    coord_count::coord_count,
+    entity::Entity,
+    error::Error,
+    fgb, par, reader,
+    tiles::{self, gzip},
+};
+use clap::ArgMatches;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+// Rows buffered per Parquet row group, as GDAL writes by default
+const PARQUET_ROW_GROUP: usize = 65536;
+
+// Sizes for the sample, in bytes
+struct Estimate {
+    // Of the written output
+    output: usize,
+    // Of what's held in memory for the sample's features while writing
+    memory: usize,
+}
+
+// Heap bytes allocated by a closure, as a measure of what the value it
+// gives holds on to. Allocations are only counted with the bench feature;
+// otherwise the size of the sample's input stands in.
+fn allocated<T, F: FnOnce() -> T>(sample: &[Entity], f: F) -> (T, usize) {
+    let before = allocations::stats();
+    let value = f();
+    let bytes = match allocations::stats().zip(before) {
+        Some((now, before)) => now.since(before).bytes as usize,
+        None => sample.iter().map(|e| e.raw().len()).sum(),
+    };
+    (value, bytes)
+}
+
+// FlatGeobuf files are built in memory from all the features at once,
+// with the features' buffers copied into the file after the index
+fn estimate_fgb(sample: &[Entity]) -> Estimate {
+    let (features, held) = allocated(sample, || {
+        sample
+            .iter()
+            .map(|e| e.geojson_feature())
+            .collect::<Vec<_>>()
+    });
+    let output = fgb::write(features).len();
+    Estimate {
+        output,
+        memory: held + 2 * output,
+    }
+}
+
+// Tiles are built from all the features at once, and gzipped as the
+// archives store them. The memory includes the tiles, which PMTiles
+// output keeps until it's finished.
+fn estimate_mvt(sample: &[Entity], opts: &tiles::Options) -> Result<Estimate, Error> {
+    let (source, held) = allocated(sample, || {
+        let mut source = tiles::Source::new();
+        for e in sample {
+            source.add(e);
+        }
+        source
+    });
+    let mut output = 0;
+    tiles::build(&source, opts, |_, data| {
+        output += gzip(&data)?.len();
+        Ok(())
+    })?;
+    Ok(Estimate {
+        output,
+        memory: held + output,
+    })
+}
+
+fn parquet_value(v: &Value, out: &mut Vec<u8>) {
+    match v {
+        Value::Null => {}
+        Value::Bool(b) => out.push(*b as u8),
+        Value::Number(n) => out.extend(n.as_f64().unwrap_or(0.0).to_le_bytes()),
+        Value::String(s) => {
+            out.extend((s.len() as u32).to_le_bytes());
+            out.extend(s.as_bytes());
+        }
+        v => out.extend(v.to_string().as_bytes()),
+    }
+}
+
+// Parquet stores a WKB geometry column and a column for each property,
+// compressed column by column. Gzip stands in for the writer's codec,
+// so the output estimate is rough. Memory is the uncompressed columns,
+// though only a row group's worth of them is held at once.
+fn estimate_parquet(sample: &[Entity]) -> Result<Estimate, Error> {
+    let mut columns: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    let mut geometry = vec![];
+    for e in sample {
+        geometry.extend(wkb::write(&e.geojson_geometry()));
+        for (k, v) in e.geojson_properties() {
+            parquet_value(&v, columns.entry(k).or_default());
+        }
+    }
+    let mut output = gzip(&geometry)?.len();
+    let mut memory = geometry.len();
+    for column in columns.values() {
+        output += gzip(column)?.len();
+        memory += column.len();
+    }
+    Ok(Estimate { output, memory })
+}
+
+fn scale(bytes: usize, factor: f64) -> u64 {
+    (bytes as f64 * factor).round() as u64
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let format = matches.value_of("export").unwrap();
+    let sample_size = par::parse_count("features to sample", matches.value_of("sample").unwrap())?;
+    let given_total = matches
+        .value_of("features")
+        .map(|n| par::parse_count("features", n))
+        .transpose()?;
+    let opts = tiles::Options::new(
+        super::tiles::read_zoom_arg(matches, "min-zoom")?,
+        super::tiles::read_zoom_arg(matches, "max-zoom")?,
+        "features",
+    );
+
+    // The rest of the input is only counted, unless --features says how
+    // many there are
+    let mut sample = vec![];
+    let mut total = 0;
+    reader::entities(|entities| {
+        for e in entities {
+            let e = e?;
+            total += 1;
+            if sample.len() < sample_size {
+                sample.push(e);
+            } else if given_total.is_some() {
+                break;
+            }
+        }
+        Ok(())
+    })?;
+    if sample.is_empty() {
+        return Err(Error::InvalidInput(
+            "No features to estimate from".to_string(),
+        ));
+    }
+    let total = given_total.unwrap_or(total);
+    let factor = total as f64 / sample.len() as f64;
+    let coordinates: usize = sample.iter().map(|e| coord_count(&e.geom())).sum();
+
+    // Parquet writers only hold a row group's features at once
+    let (estimate, held) = match format {
+        "fgb" => (estimate_fgb(&sample), total),
+        "mvt" => (estimate_mvt(&sample, &opts)?, total),
+        _ => (estimate_parquet(&sample)?, total.min(PARQUET_ROW_GROUP)),
+    };
+    println!(
+        "{}",
+        json!({
+            "format": format,
+            "features": total,
+            "sampled": sample.len(),
+            "coordinates": scale(coordinates, factor),
+            "output_bytes": scale(estimate.output, factor),
+            "memory_bytes": scale(estimate.memory, held as f64 / sample.len() as f64),
+        })
+    );
+    Ok(())
+}
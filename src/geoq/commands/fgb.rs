@@ -1,39 +1,37 @@
-use crate::geoq::{error::Error, fgb, reader::Reader};
+use crate::geoq::{checkpoint::Checkpoint, error::Error, fgb, object_store, output, reader, rings};
 use clap::ArgMatches;
 use flatgeobuf::FgbReader;
-use std::fs::File;
-use std::io::{self, BufReader};
-use std::path::Path;
 
-fn stdin_features() -> Result<Vec<geojson::Feature>, Error> {
+fn stdin_features(checkpoint: Option<&mut Checkpoint>) -> Result<Vec<geojson::Feature>, Error> {
     let mut input_features: Vec<geojson::Feature> = Vec::new();
-
-    let stdin = io::stdin();
-    let mut stdin_reader = stdin.lock();
-    let reader = Reader::new(&mut stdin_reader);
-
-    for e_res in reader {
-        match e_res {
-            Err(e) => return Err(e),
-            Ok(e) => {
-                input_features.push(e.geojson_feature());
-            }
-        }
+    let handler = |e: crate::geoq::entity::Entity| {
+        input_features.push(e.geojson_feature());
+        Ok(())
+    };
+    match checkpoint {
+        Some(c) => c.read_entities(handler)?,
+        None => reader::for_entity(handler)?,
     }
     Ok(input_features)
 }
 
-fn write(path: &str) -> Result<(), Error> {
-    let feats = stdin_features()?;
-    let buffer = fgb::write(feats);
-    let res = std::fs::write(Path::new(path), buffer);
-    match res {
-        Ok(_) => Ok(()),
-        Err(_) => Err(Error::ProgramError(format!(
-            "Error writing flatgeobuf data to file {}",
-            path
-        ))),
+fn write(path: &str, checkpoint: Option<&str>, repair_rings: bool) -> Result<(), Error> {
+    let mut checkpoint = match checkpoint {
+        Some(c) => Some(Checkpoint::open(c, "fgb write")?),
+        None => None,
+    };
+    let mut feats = stdin_features(checkpoint.as_mut())?;
+    if repair_rings {
+        feats.iter_mut().for_each(rings::repair_feature);
     }
+    write_features(path, feats)?;
+    checkpoint.map_or(Ok(()), |c| c.finish())
+}
+
+pub fn write_features(path: &str, feats: Vec<geojson::Feature>) -> Result<(), Error> {
+    let buffer = fgb::write(feats);
+    object_store::write(path, buffer)
+        .map_err(|_| Error::ProgramError(format!("Error writing flatgeobuf data to file {}", path)))
 }
 
 use flatgeobuf::*;
@@ -41,7 +39,7 @@ use geozero::geojson::GeoJsonWriter;
 use geozero::ToJson;
 
 fn read(path: &str, bbox: Option<&str>) -> Result<(), Error> {
-    let mut file = BufReader::new(File::open(path)?);
+    let mut file = object_store::open_seekable(path)?;
     let mut fgb = FgbReader::open(&mut file)?;
 
     if let Some(bbox) = bbox {
@@ -66,15 +64,18 @@ fn read(path: &str, bbox: Option<&str>) -> Result<(), Error> {
     let mut json_data: Vec<u8> = Vec::new();
     let mut json = GeoJsonWriter::new(&mut json_data);
     fgb.process_features(&mut json)?;
-    println!("{}", std::str::from_utf8(&json_data)?);
-    Ok(())
+    output::print(std::str::from_utf8(&json_data)?)
 }
 
 pub fn run(m: &ArgMatches) -> Result<(), Error> {
     match m.subcommand() {
         ("write", Some(args)) => {
             let path: &str = args.value_of("path").unwrap();
-            write(path)
+            write(
+                path,
+                args.value_of("checkpoint"),
+                args.is_present("repair-rings"),
+            )
         }
         ("read", Some(args)) => {
             let path: &str = args.value_of("path").unwrap();
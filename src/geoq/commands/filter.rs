@@ -1,18 +1,30 @@
-use crate::geoq::{self, error::Error, par, reader::Reader};
+use crate::geoq::{
+    self,
+    entity::Entity,
+    error::Error,
+    other,
+    par::{self, ParOptions},
+    prepared::Prepared,
+    reader::Reader,
+    timestamp::{self, EpochUnit},
+    units,
+};
+use chrono::{DateTime, Utc};
 use clap::ArgMatches;
 use geo_types::{Geometry, Polygon};
-use std::{fs::File, io::BufReader};
+use std::io::BufReader;
 
 fn read_query_geoms(matches: &ArgMatches) -> Result<Vec<Geometry<f64>>, Error> {
     let f = matches.value_of("query-file");
     let q = matches.value_of("query");
     match (f, q) {
         (Some(path), None) => {
-            let f = File::open(path)?;
-            let mut f = BufReader::new(f);
-            let reader = Reader::new(&mut f);
-            let entities = reader.into_iter().collect::<Result<Vec<_>, _>>()?;
-            Ok(entities.into_iter().map(|e| e.geom()).collect())
+            let mut geoms = vec![];
+            other::for_entity(path, |e| {
+                geoms.push(e.geom());
+                Ok(())
+            })?;
+            Ok(geoms)
         }
         (None, Some(q)) => {
             let q_buff = q.as_bytes();
@@ -28,24 +40,15 @@ fn read_query_geoms(matches: &ArgMatches) -> Result<Vec<Geometry<f64>>, Error> {
     }
 }
 
-fn intersects(matches: &ArgMatches, negate: bool) -> Result<(), Error> {
-    use geo::algorithm::intersects::Intersects;
-    let query_geoms = read_query_geoms(matches)?;
-    par::for_stdin_entity(move |entity| {
-        let output = entity.raw();
-        let geom = entity.geom();
-        let is_match: bool = query_geoms
-            .iter()
-            .any(|ref query_geom| query_geom.intersects(&geom));
-        if is_match ^ negate {
-            Ok(vec![output])
-        } else {
-            Ok(vec![])
-        }
-    })
+// Whether an entity matches the filter, before any --negate
+pub type Predicate = Box<dyn Fn(&Entity) -> Result<bool, Error> + Send + Sync>;
+
+fn intersects(matches: &ArgMatches) -> Result<Predicate, Error> {
+    let query = Prepared::new(&read_query_geoms(matches)?);
+    Ok(Box::new(move |entity| Ok(query.intersects(&entity.geom()))))
 }
 
-fn contains(matches: &ArgMatches, negate: bool) -> Result<(), Error> {
+fn contains(matches: &ArgMatches) -> Result<Predicate, Error> {
     let query_geoms = read_query_geoms(matches)?;
     let query_polygons: Vec<Polygon<f64>> = query_geoms
         .into_iter()
@@ -59,55 +62,136 @@ fn contains(matches: &ArgMatches, negate: bool) -> Result<(), Error> {
     if query_polygons.is_empty() {
         Err(Error::PolygonRequired)
     } else {
-        par::for_stdin_entity(move |entity| {
-            let output = entity.raw();
+        Ok(Box::new(move |entity| {
             let geom = entity.geom();
-            let is_match = query_polygons
+            Ok(query_polygons
                 .iter()
-                .any(|ref query_poly| geoq::contains::contains(query_poly, &geom));
-            if is_match ^ negate {
-                Ok(vec![output])
-            } else {
-                Ok(vec![])
-            }
-        })
+                .any(|query_poly| geoq::contains::contains(query_poly, &geom)))
+        }))
     }
 }
 
-fn dwithin(matches: &ArgMatches, negate: bool) -> Result<(), Error> {
+fn dwithin(matches: &ArgMatches) -> Result<Predicate, Error> {
     let query_geoms = read_query_geoms(matches)?;
-    let rad_arg = matches.value_of("radius").unwrap();
-    let radius: f64 = rad_arg
-        .parse()
-        .map_err(|_| Error::InvalidNumberFormat(format!("Invalid Radius: {}", rad_arg)))?;
+    let radius = units::parse_length(matches.value_of("radius").unwrap())?;
 
     if query_geoms.is_empty() {
         Err(Error::NoInputGiven)
     } else {
-        par::for_stdin_entity(move |entity| {
-            let output = entity.raw();
+        Ok(Box::new(move |entity| {
             let geom = entity.geom();
             let point = match geom {
                 Geometry::Point(p) => Ok(p),
                 _ => Err(Error::PointRequired),
             }?;
-            let is_match = query_geoms.iter().any(|ref query_geom| {
+            Ok(query_geoms.iter().any(|query_geom| {
                 let dist = geoq::distance::distance(&point, query_geom);
                 match dist {
                     Some(d) => d < radius,
                     None => false,
                 }
-            });
-            if is_match ^ negate {
-                Ok(vec![output])
-            } else {
-                Ok(vec![])
-            }
+            }))
+        }))
+    }
+}
+
+fn geometry(matches: &ArgMatches) -> Result<Predicate, Error> {
+    // --non-empty is the default when neither flag is given
+    let want_empty = matches.is_present("empty");
+    Ok(Box::new(move |entity| Ok(entity.is_empty() == want_empty)))
+}
+
+// The GeoJSON type of a geometry
+fn type_name(geom: &Geometry<f64>) -> &'static str {
+    match geom {
+        Geometry::Point(_) => "Point",
+        Geometry::Line(_) | Geometry::LineString(_) => "LineString",
+        Geometry::Polygon(_) | Geometry::Rect(_) | Geometry::Triangle(_) => "Polygon",
+        Geometry::MultiPoint(_) => "MultiPoint",
+        Geometry::MultiLineString(_) => "MultiLineString",
+        Geometry::MultiPolygon(_) => "MultiPolygon",
+        Geometry::GeometryCollection(_) => "GeometryCollection",
+    }
+}
+
+const TYPES: &[&str] = &[
+    "Point",
+    "LineString",
+    "Polygon",
+    "MultiPoint",
+    "MultiLineString",
+    "MultiPolygon",
+    "GeometryCollection",
+];
+
+fn geometry_type(matches: &ArgMatches) -> Result<Predicate, Error> {
+    let types = matches
+        .values_of("type")
+        .unwrap()
+        .map(|t| {
+            TYPES
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(t))
+                .copied()
+                .ok_or_else(|| {
+                    Error::InvalidInput(format!(
+                        "Unknown geometry type: {} -- expected one of {}",
+                        t,
+                        TYPES.join(", ")
+                    ))
+                })
         })
+        .collect::<Result<Vec<&str>, Error>>()?;
+    Ok(Box::new(move |entity| {
+        Ok(types.contains(&type_name(&entity.geom())))
+    }))
+}
+
+fn time_bound(
+    matches: &ArgMatches,
+    name: &str,
+    parse: fn(&str, EpochUnit) -> Option<DateTime<Utc>>,
+    unit: EpochUnit,
+) -> Result<Option<DateTime<Utc>>, Error> {
+    match matches.value_of(name) {
+        None => Ok(None),
+        Some(raw) => match parse(raw, unit) {
+            Some(t) => Ok(Some(t)),
+            None => Err(Error::InvalidInput(format!(
+                "Invalid --{} time: {} -- expected an ISO 8601 date or time, or epoch number",
+                name, raw
+            ))),
+        },
     }
 }
 
-pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+fn time(matches: &ArgMatches) -> Result<Predicate, Error> {
+    let unit = if matches.is_present("epoch-millis") {
+        EpochUnit::Millis
+    } else {
+        EpochUnit::Seconds
+    };
+    let after = time_bound(matches, "after", timestamp::parse_str, unit)?;
+    // A plain --before date keeps the day it names
+    let before = time_bound(matches, "before", timestamp::parse_end, unit)?;
+    let property = matches.value_of("property").unwrap().to_string();
+    Ok(Box::new(move |entity| {
+        Ok(
+            match entity
+                .geojson_properties()
+                .get(&property)
+                .and_then(|v| timestamp::parse_value(v, unit))
+            {
+                Some(t) => after.iter().all(|a| t >= *a) && before.iter().all(|b| t < *b),
+                None => false,
+            },
+        )
+    }))
+}
+
+// The filter's predicate, including any --negate, for `geoq pipe` stages
+// as well as for the filter command
+pub fn predicate(matches: &ArgMatches) -> Result<Predicate, Error> {
     // allow --negate to be passed either before or after the subcommand
     // geoq filter --negate intersects
     // OR
@@ -118,10 +202,25 @@ pub fn run(matches: &ArgMatches) -> Result<(), Error> {
         .or(matches.subcommand().1.and_then(|m| (*m).args.get("negate")))
         .is_some();
 
-    match matches.subcommand() {
-        ("intersects", Some(m)) => intersects(m, negate),
-        ("contains", Some(m)) => contains(m, negate),
-        ("dwithin", Some(m)) => dwithin(m, negate),
-        _ => Err(Error::UnknownCommand),
-    }
+    let predicate = match matches.subcommand() {
+        ("intersects", Some(m)) => intersects(m)?,
+        ("contains", Some(m)) => contains(m)?,
+        ("dwithin", Some(m)) => dwithin(m)?,
+        ("geometry", Some(m)) => geometry(m)?,
+        ("type", Some(m)) => geometry_type(m)?,
+        ("time", Some(m)) => time(m)?,
+        _ => return Err(Error::UnknownCommand),
+    };
+    Ok(Box::new(move |entity| Ok(predicate(entity)? ^ negate)))
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let predicate = predicate(matches)?;
+    par::for_stdin_entity(par_opts, move |entity| {
+        if predicate(&entity)? {
+            Ok(vec![entity.into_raw()])
+        } else {
+            Ok(vec![])
+        }
+    })
 }
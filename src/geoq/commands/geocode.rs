@@ -0,0 +1,129 @@
+use crate::geoq::{
+    commands::tile::for_arg_or_line, config, csv, error::Error, nominatim, output, reader, report,
+};
+use clap::ArgMatches;
+use serde_json::{json, Map, Value};
+use std::io;
+
+pub fn client(matches: &ArgMatches) -> Result<nominatim::Client, Error> {
+    let rate = matches.value_of("rate").unwrap();
+    let rate = rate.parse::<f64>().map_err(|_| {
+        Error::InvalidNumberFormat(format!("Expected numeric request rate: {}", rate))
+    })?;
+    let endpoint = config::value_of(matches, "endpoint", "geocoder_endpoint").unwrap();
+    nominatim::Client::new(&endpoint, rate)
+}
+
+fn read_limit(matches: &ArgMatches) -> Result<usize, Error> {
+    let limit = matches.value_of("limit").unwrap();
+    match limit.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected positive result limit: {}",
+            limit
+        ))),
+    }
+}
+
+// Point Features for each result, or none (with a warning) if the
+// query didn't match anything
+fn geocode(
+    client: &mut nominatim::Client,
+    query: &str,
+    limit: usize,
+    properties: Map<String, Value>,
+) -> Result<Vec<String>, Error> {
+    let places = client.search(query, limit)?;
+    if places.is_empty() {
+        report::warning("No geocoding results for", query);
+    }
+    Ok(places
+        .iter()
+        .map(|p| p.feature(properties.clone()).to_string())
+        .collect())
+}
+
+fn for_query(matches: &ArgMatches) -> Result<(), Error> {
+    let mut client = client(matches)?;
+    let limit = read_limit(matches)?;
+    for_arg_or_line(matches, "query", |q| {
+        let mut properties = Map::new();
+        properties.insert("query".to_string(), json!(q));
+        geocode(&mut client, q, limit, properties)
+    })
+}
+
+fn for_property(matches: &ArgMatches, key: &str) -> Result<(), Error> {
+    let mut client = client(matches)?;
+    let limit = read_limit(matches)?;
+    reader::for_entity(|e| {
+        let properties = e.geojson_properties();
+        let query = match properties.get(key) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => {
+                report::warning(
+                    &format!("Feature has no '{}' property to geocode", key),
+                    &e.raw(),
+                );
+                return Ok(());
+            }
+            Some(v) => v.to_string(),
+        };
+        for f in geocode(&mut client, &query, limit, properties)? {
+            output::print(&f.to_string())?;
+        }
+        Ok(())
+    })
+}
+
+// Fields of a single CSV line, with RFC 4180 quoting.
+// Quoted fields spanning several lines aren't supported.
+fn for_csv_column(matches: &ArgMatches, column: &str) -> Result<(), Error> {
+    let mut client = client(matches)?;
+    let limit = read_limit(matches)?;
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let header = match csv::read_record(&mut input)? {
+        Some(header) => header,
+        None => return Err(Error::NoInputGiven),
+    };
+    let index = header.iter().position(|h| h == column).ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "CSV column '{}' not found in header: {}",
+            column,
+            header.join(",")
+        ))
+    })?;
+    while let Some(fields) = csv::read_record(&mut input)? {
+        if csv::is_blank(&fields) {
+            continue;
+        }
+        let query = fields.get(index).map(|q| q.trim()).unwrap_or("");
+        if query.is_empty() {
+            report::warning(
+                &format!("CSV row has no '{}' value to geocode", column),
+                &fields.join(","),
+            );
+            continue;
+        }
+        let properties: Map<String, Value> = header
+            .iter()
+            .cloned()
+            .zip(fields.iter().map(|f| json!(f)))
+            .collect();
+        for f in geocode(&mut client, query, limit, properties)? {
+            output::print(&f.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    if let Some(key) = matches.value_of("property") {
+        for_property(matches, key)
+    } else if let Some(column) = matches.value_of("csv-column") {
+        for_csv_column(matches, column)
+    } else {
+        for_query(matches)
+    }
+}
@@ -1,5 +1,12 @@
-use crate::geoq::{self, entity::Entity, error::Error, par};
+use crate::geoq::{
+    self,
+    entity::Entity,
+    error::Error,
+    par::{self, ParOptions},
+    reader,
+};
 use clap::ArgMatches;
+use serde_json::{json, Map};
 use std::io::{self, prelude::*};
 
 fn read_level(matches: &ArgMatches) -> Result<usize, Error> {
@@ -20,10 +27,10 @@ fn read_level(matches: &ArgMatches) -> Result<usize, Error> {
     Ok(level_parsed.unwrap())
 }
 
-fn point(matches: &ArgMatches) -> Result<(), Error> {
+fn point(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     let level = read_level(matches)?;
 
-    par::for_stdin_entity(move |e| match e.geom() {
+    par::for_stdin_entity(par_opts, move |e| match e.geom() {
         geo_types::Geometry::Point(p) => match geohash::encode(p.0, level) {
             Ok(gh) => Ok(vec![gh]),
             _ => Err(Error::InvalidGeohashPoint),
@@ -32,10 +39,10 @@ fn point(matches: &ArgMatches) -> Result<(), Error> {
     })
 }
 
-fn covering(matches: &ArgMatches) -> Result<(), Error> {
+fn covering(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     let level = read_level(matches)?;
     let include_original = matches.is_present("original");
-    par::for_stdin_entity(move |e| {
+    par::for_stdin_entity(par_opts, move |e| {
         if include_original {
             let mut results = vec![e.raw()];
             let g = e.geom();
@@ -48,21 +55,89 @@ fn covering(matches: &ArgMatches) -> Result<(), Error> {
     })
 }
 
-fn children() -> Result<(), Error> {
-    par::for_stdin_entity(|e| match e {
+fn cover(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let max_cells = match matches.value_of("max-cells").unwrap().parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => {
+            return Err(Error::InvalidNumberFormat(format!(
+                "Expected positive --max-cells: {}",
+                matches.value_of("max-cells").unwrap()
+            )))
+        }
+    };
+    let max_level = match matches.value_of("max-level").unwrap().parse::<usize>() {
+        Ok(n) if (1..=12).contains(&n) => n,
+        _ => {
+            return Err(Error::InvalidNumberFormat(format!(
+                "Expected --max-level between 1 and 12: {}",
+                matches.value_of("max-level").unwrap()
+            )))
+        }
+    };
+    par::for_stdin_entity(par_opts, move |e| {
+        let g = e.geom();
+        Ok(geoq::geohash::adaptive_covering(&g, max_cells, max_level))
+    })
+}
+
+fn children(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, |e| match e {
         Entity::Geohash(ref raw) => Ok(geoq::geohash::children(raw)),
         _ => Err(Error::NotImplemented),
     })
 }
 
-fn neighbors(matches: &ArgMatches) -> Result<(), Error> {
+fn neighbors(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     let exclude = matches.is_present("exclude");
-    par::for_stdin_entity(move |e| match e {
+    par::for_stdin_entity(par_opts, move |e| match e {
         Entity::Geohash(ref raw) => Ok(geoq::geohash::neighbors(raw, !exclude)),
         _ => Err(Error::NotImplemented),
     })
 }
 
+fn decode(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let as_bbox = matches.is_present("bbox");
+    par::for_stdin_entity(par_opts, move |e| match e {
+        Entity::Geohash(ref raw) => {
+            let (center, lon_err, lat_err) =
+                geohash::decode(raw).map_err(|_| Error::InvalidInput(raw.clone()))?;
+            let geom = if as_bbox {
+                geo_types::Geometry::Polygon(geoq::geohash::bbox(raw).unwrap())
+            } else {
+                geo_types::Geometry::Point(geo_types::Point(center))
+            };
+            let mut props = Map::new();
+            props.insert("geohash".to_string(), json!(raw));
+            props.insert("lat_err".to_string(), json!(lat_err));
+            props.insert("lon_err".to_string(), json!(lon_err));
+            let feature = geojson::Feature {
+                bbox: None,
+                geometry: Some(geojson::Geometry::new(geojson::Value::from(&geom))),
+                id: None,
+                properties: Some(props),
+                foreign_members: None,
+            };
+            Ok(vec![serde_json::to_string(&feature)?])
+        }
+        _ => Err(Error::NotImplemented),
+    })
+}
+
+fn compact() -> Result<(), Error> {
+    let mut ghs: Vec<String> = vec![];
+    reader::for_entity(|e| match e {
+        Entity::Geohash(raw) => {
+            ghs.push(raw);
+            Ok(())
+        }
+        _ => Err(Error::NotImplemented),
+    })?;
+    for gh in geoq::geohash::compact(ghs) {
+        println!("{}", gh);
+    }
+    Ok(())
+}
+
 fn roots() -> Result<(), Error> {
     for c in geoq::geohash::BASE_32.iter() {
         println!("{}", c);
@@ -89,12 +164,15 @@ fn encode_long() -> Result<(), Error> {
     Ok(())
 }
 
-pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     match matches.subcommand() {
-        ("point", Some(m)) => point(m),
-        ("children", Some(_)) => children(),
-        ("neighbors", Some(m)) => neighbors(m),
-        ("covering", Some(m)) => covering(m),
+        ("point", Some(m)) => point(m, par_opts),
+        ("children", Some(_)) => children(par_opts),
+        ("neighbors", Some(m)) => neighbors(m, par_opts),
+        ("covering", Some(m)) => covering(m, par_opts),
+        ("cover", Some(m)) => cover(m, par_opts),
+        ("compact", Some(_)) => compact(),
+        ("decode", Some(m)) => decode(m, par_opts),
         ("roots", Some(_)) => roots(),
         ("encode-long", Some(_)) => encode_long(),
         _ => Err(Error::UnknownCommand),
@@ -1,48 +1,58 @@
-use crate::geoq::{error::Error, par, reader::Reader};
+use crate::geoq::{
+    entity::Entity,
+    error::Error,
+    output,
+    par::{self, ParOptions},
+    reader, rings,
+};
 use clap::ArgMatches;
 use geojson::GeoJson;
-use std::io;
 
-fn geom() -> Result<(), Error> {
-    par::for_stdin_entity(|e| {
-        let gj_geom = e.geojson_geometry();
-        Ok(vec![serde_json::to_string(&gj_geom).unwrap()])
-    })
+pub fn geom_lines(e: Entity) -> Result<Vec<String>, Error> {
+    let gj_geom = e.geojson_geometry();
+    Ok(vec![serde_json::to_string(&gj_geom).unwrap()])
+}
+
+pub fn feature_lines(e: Entity) -> Result<Vec<String>, Error> {
+    let f = e.geojson_feature();
+    Ok(vec![serde_json::to_string(&f).unwrap()])
 }
 
-fn feature() -> Result<(), Error> {
-    par::for_stdin_entity(|e| {
-        let f = e.geojson_feature();
-        Ok(vec![serde_json::to_string(&f).unwrap()])
+fn geom(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, geom_lines)
+}
+
+fn feature(repair_rings: bool, par_opts: &ParOptions) -> Result<(), Error> {
+    if !repair_rings {
+        return par::for_stdin_entity(par_opts, feature_lines);
+    }
+    par::for_stdin_entity(par_opts, |e| {
+        let mut f = e.geojson_feature();
+        rings::repair_feature(&mut f);
+        Ok(vec![serde_json::to_string(&f)?])
     })
 }
 
 fn feature_collection() -> Result<(), Error> {
     let mut features: Vec<geojson::Feature> = Vec::new();
 
-    let stdin = io::stdin();
-    let mut stdin_reader = stdin.lock();
-    let reader = Reader::new(&mut stdin_reader);
-    for e_res in reader {
-        match e_res {
-            Err(e) => return Err(e),
-            Ok(e) => features.push(e.geojson_feature()),
-        }
-    }
+    reader::for_entity(|e| {
+        features.push(e.geojson_feature());
+        Ok(())
+    })?;
 
     let fc = geojson::FeatureCollection {
         bbox: None,
         features: features,
         foreign_members: None,
     };
-    println!("{}", GeoJson::from(fc).to_string());
-    Ok(())
+    output::print(&GeoJson::from(fc).to_string())
 }
 
-pub fn run(gj: &ArgMatches) -> Result<(), Error> {
+pub fn run(gj: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     match gj.subcommand() {
-        ("geom", Some(_)) => geom(),
-        ("f", Some(_)) => feature(),
+        ("geom", Some(_)) => geom(par_opts),
+        ("f", Some(m)) => feature(m.is_present("repair-rings"), par_opts),
         ("fc", Some(_)) => feature_collection(),
         _ => Err(Error::UnknownCommand),
     }
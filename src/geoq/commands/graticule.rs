@@ -0,0 +1,131 @@
+// Lines of latitude and longitude, as a reference layer for plots and
+// maps of other features
+use crate::geoq::{bbox, error::Error, output};
+use clap::ArgMatches;
+use geo_types::{Coord, Geometry, LineString, Rect};
+use serde_json::{json, Map, Value};
+
+fn positive(matches: &ArgMatches, name: &str) -> Result<f64, Error> {
+    let value = matches.value_of(name).unwrap();
+    match value.parse::<f64>() {
+        Ok(v) if v > 0.0 => Ok(v),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected a positive number of degrees for --{}: {}",
+            name, value
+        ))),
+    }
+}
+
+// Multiples of the interval from min to max
+fn stops(min: f64, max: f64, interval: f64) -> Vec<f64> {
+    let first = (min / interval).ceil() as i64;
+    let last = (max / interval).floor() as i64;
+    (first..=last).map(|i| i as f64 * interval).collect()
+}
+
+// Points from a to b at most step apart, so the line curves as it
+// should once projected. Each is its own multiple of the step from a,
+// rather than a sum of steps, so they don't drift off round values.
+fn densify(a: Coord<f64>, b: Coord<f64>, step: f64) -> LineString<f64> {
+    let len = (b.x - a.x).abs().max((b.y - a.y).abs());
+    if len == 0.0 {
+        return LineString(vec![a, b]);
+    }
+    let (dx, dy) = ((b.x - a.x) / len, (b.y - a.y) / len);
+    let n = ((len / step).ceil() as usize).max(1);
+    (0..n)
+        .map(|i| {
+            let d = i as f64 * step;
+            Coord {
+                x: a.x + dx * d,
+                y: a.y + dy * d,
+            }
+        })
+        .chain(std::iter::once(b))
+        .collect()
+}
+
+fn label(value: f64, pos: char, neg: char) -> String {
+    match value {
+        v if v > 0.0 => format!("{}°{}", v, pos),
+        v if v < 0.0 => format!("{}°{}", -v, neg),
+        _ => "0°".to_string(),
+    }
+}
+
+fn feature(geom: LineString<f64>, props: Value) -> Result<String, Error> {
+    let properties: Map<String, Value> = match props {
+        Value::Object(props) => props,
+        _ => Map::new(),
+    };
+    let feature = geojson::Feature {
+        bbox: None,
+        geometry: Some(geojson::Geometry::new(geojson::Value::from(
+            &Geometry::LineString(geom),
+        ))),
+        id: None,
+        properties: Some(properties),
+        foreign_members: None,
+    };
+    Ok(serde_json::to_string(&feature)?)
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let interval = positive(matches, "interval")?;
+    let step = positive(matches, "step")?;
+    let bounds: Rect<f64> = bbox::parse(matches.value_of("bbox").unwrap())?;
+    let (min, max) = (bounds.min(), bounds.max());
+
+    for lat in stops(min.y, max.y, interval) {
+        let line = densify(Coord { x: min.x, y: lat }, Coord { x: max.x, y: lat }, step);
+        let props = json!({"kind": "parallel", "value": lat, "label": label(lat, 'N', 'S')});
+        output::print(&feature(line, props)?)?;
+    }
+    for lon in stops(min.x, max.x, interval) {
+        let line = densify(Coord { x: lon, y: min.y }, Coord { x: lon, y: max.y }, step);
+        let props = json!({"kind": "meridian", "value": lon, "label": label(lon, 'E', 'W')});
+        output::print(&feature(line, props)?)?;
+    }
+    if matches.is_present("frame") {
+        let corners = [
+            min,
+            Coord { x: max.x, y: min.y },
+            max,
+            Coord { x: min.x, y: max.y },
+            min,
+        ];
+        let frame: LineString<f64> = corners
+            .windows(2)
+            .flat_map(|c| densify(c[0], c[1], step).0.into_iter().skip(1))
+            .collect();
+        let frame: LineString<f64> = std::iter::once(min).chain(frame.0).collect();
+        output::print(&feature(frame, json!({"kind": "frame"}))?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::densify;
+    use geo_types::Coord;
+
+    #[test]
+    fn test_densify() {
+        let line = densify(Coord { x: 10.0, y: -90.0 }, Coord { x: 10.0, y: 90.0 }, 0.1);
+        assert_eq!(1801, line.0.len());
+        for (i, c) in line.0.iter().enumerate() {
+            assert_eq!(
+                Coord {
+                    x: 10.0,
+                    y: -90.0 + i as f64 * 0.1
+                },
+                *c
+            );
+        }
+        let line = densify(Coord { x: 2.0, y: 0.0 }, Coord { x: 0.0, y: 0.0 }, 0.75);
+        assert_eq!(
+            vec![(2.0, 0.0), (1.25, 0.0), (0.5, 0.0), (0.0, 0.0)],
+            line.0.iter().map(|c| (c.x, c.y)).collect::<Vec<_>>()
+        );
+    }
+}
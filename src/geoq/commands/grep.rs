@@ -0,0 +1,59 @@
+use crate::geoq::{
+    error::Error,
+    par::{self, ParOptions},
+};
+use clap::ArgMatches;
+use regex::Regex;
+use serde_json::Value;
+
+enum Pattern {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn is_match(&self, s: &str) -> bool {
+        match self {
+            Pattern::Substring(p) => s.contains(p.as_str()),
+            Pattern::Regex(r) => r.is_match(s),
+        }
+    }
+}
+
+fn value_matches(pattern: &Pattern, v: &Value) -> bool {
+    match v {
+        Value::Null => false,
+        Value::String(s) => pattern.is_match(s),
+        Value::Array(vals) => vals.iter().any(|v| value_matches(pattern, v)),
+        Value::Object(o) => o.values().any(|v| value_matches(pattern, v)),
+        other => pattern.is_match(&other.to_string()),
+    }
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let raw_pattern = matches.value_of("pattern").ok_or(Error::MissingArgument)?;
+    let pattern = if matches.is_present("regex") {
+        let re = Regex::new(raw_pattern)
+            .map_err(|e| Error::InvalidInput(format!("Invalid regex: {} -- {}", raw_pattern, e)))?;
+        Pattern::Regex(re)
+    } else {
+        Pattern::Substring(raw_pattern.to_string())
+    };
+    let property = matches.value_of("property").map(|p| p.to_string());
+
+    par::for_stdin_entity(par_opts, move |e| {
+        let props = e.geojson_properties();
+        let is_match = match property {
+            Some(ref key) => props
+                .get(key)
+                .map(|v| value_matches(&pattern, v))
+                .unwrap_or(false),
+            None => props.values().any(|v| value_matches(&pattern, v)),
+        };
+        if is_match {
+            Ok(vec![e.into_raw()])
+        } else {
+            Ok(vec![])
+        }
+    })
+}
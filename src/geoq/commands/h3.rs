@@ -1,4 +1,11 @@
-use crate::geoq::{self, bbox::BBoxToPoly, entity::Entity, error::Error, par};
+use crate::geoq::{
+    self,
+    bbox::BBoxToPoly,
+    entity::Entity,
+    error::Error,
+    par::{self, ParOptions},
+    reader,
+};
 use clap::ArgMatches;
 use geo::{
     prelude::{Centroid, Contains, Intersects},
@@ -40,10 +47,10 @@ fn read_resolution(matches: &ArgMatches) -> Result<u8, Error> {
     parse_resolution(resolution_str)
 }
 
-fn point(matches: &ArgMatches) -> Result<(), Error> {
+fn point(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     let resolution = read_resolution(matches)?;
 
-    par::for_stdin_entity(move |e| match e.geom() {
+    par::for_stdin_entity(par_opts, move |e| match e.geom() {
         geo_types::Geometry::Point(p) => cell_at_res(p, resolution).map(|c| vec![c.to_string()]),
         _ => Err(Error::InvalidInput(
             "Input for 'geoq h3 point' should be a Point geometry".to_string(),
@@ -76,14 +83,14 @@ fn cell_children(cell: H3Cell, _res: Option<u8>) -> Result<Vec<String>, Error> {
         .map(|cells| cells.iter().map(|c| c.to_string()).collect())
 }
 
-fn parent(matches: &ArgMatches) -> Result<(), Error> {
+fn parent(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     let resolution = match read_resolution(matches) {
         Ok(res) => Some(res),
         Err(Error::MissingArgument) => None,
         err => return err.map(|_| ()),
     };
 
-    par::for_stdin_entity(move |e| match e {
+    par::for_stdin_entity(par_opts, move |e| match e {
         Entity::H3(cell) => {
             let cell_res = cell.resolution();
             if resolution.is_none() && cell_res == 0 {
@@ -113,8 +120,8 @@ fn parent(matches: &ArgMatches) -> Result<(), Error> {
     })
 }
 
-fn resolution() -> Result<(), Error> {
-    par::for_stdin_entity(move |e| match e {
+fn resolution(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, move |e| match e {
         Entity::H3(cell) => Ok(vec![cell.resolution().to_string()]),
         _ => Err(Error::InvalidInput(format!(
             "Input for 'geoq h3 resolution' should be a hexadecimal h3 cell. Got: {}",
@@ -123,14 +130,14 @@ fn resolution() -> Result<(), Error> {
     })
 }
 
-fn children(matches: &ArgMatches) -> Result<(), Error> {
+fn children(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     let resolution = match read_resolution(matches) {
         Ok(res) => Some(res),
         Err(Error::MissingArgument) => None,
         err => return err.map(|_| ()),
     };
 
-    par::for_stdin_entity(move |e| match e {
+    par::for_stdin_entity(par_opts, move |e| match e {
         Entity::H3(cell) => cell_children(cell, resolution),
         _ => Err(Error::InvalidInput(format!(
             "Input for 'geoq h3 children' should be a hexadecimal h3 cell. Got: {}",
@@ -139,6 +146,60 @@ fn children(matches: &ArgMatches) -> Result<(), Error> {
     })
 }
 
+fn boundary(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, move |e| match e {
+        Entity::H3(_) => Ok(vec![serde_json::to_string(&e.geojson_feature())?]),
+        _ => Err(Error::InvalidInput(format!(
+            "Input for 'geoq h3 boundary' should be a hexadecimal h3 cell. Got: {}",
+            e
+        ))),
+    })
+}
+
+fn read_cells() -> Result<Vec<H3Cell>, Error> {
+    let mut cells = Vec::<H3Cell>::new();
+    reader::for_entity(|e| match e {
+        Entity::H3(cell) => {
+            cells.push(cell);
+            Ok(())
+        }
+        _ => Err(Error::InvalidInput(format!(
+            "Input for 'geoq h3 compact' should be hexadecimal h3 cells. Got: {}",
+            e
+        ))),
+    })?;
+    Ok(cells)
+}
+
+fn compact() -> Result<(), Error> {
+    let mut cells = read_cells()?;
+    cells.sort();
+    cells.dedup();
+    let compacted = h3ron::compact_cells(&cells)?;
+    for cell in compacted.iter() {
+        println!("{}", cell.to_string());
+    }
+    Ok(())
+}
+
+fn uncompact(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let resolution = read_resolution(matches)?;
+    par::for_stdin_entity(par_opts, move |e| match e {
+        Entity::H3(cell) if cell.resolution() == resolution => Ok(vec![cell.to_string()]),
+        Entity::H3(cell) if cell.resolution() < resolution => cell_children(cell, Some(resolution)),
+        Entity::H3(cell) => Err(Error::InvalidInput(format!(
+            "Can't uncompact cell {} at res {} to coarser res {}",
+            cell.to_string(),
+            cell.resolution(),
+            resolution
+        ))),
+        _ => Err(Error::InvalidInput(format!(
+            "Input for 'geoq h3 uncompact' should be a hexadecimal h3 cell. Got: {}",
+            e
+        ))),
+    })
+}
+
 fn cell_at_res(p: Point<f64>, res: u8) -> Result<H3Cell, Error> {
     H3Cell::from_point(p, res).map_err(|e| {
         Error::InvalidInput(format!(
@@ -159,8 +220,8 @@ fn coord_cell(c: Coord<f64>, res: u8) -> Result<H3Cell, Error> {
     })
 }
 
-fn hierarchy() -> Result<(), Error> {
-    par::for_stdin_entity(move |e| match e.geom() {
+fn hierarchy(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, move |e| match e.geom() {
         geo_types::Geometry::Point(p) => {
             let res: Result<Vec<String>, Error> = (0..=15)
                 .map(|res| cell_at_res(p, res).map(|c| c.to_string()))
@@ -173,8 +234,8 @@ fn hierarchy() -> Result<(), Error> {
     })
 }
 
-fn from_str() -> Result<(), Error> {
-    par::for_stdin_entity(move |e| match e {
+fn from_str(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, move |e| match e {
         Entity::H3(cell) => Ok(vec![cell.h3index().to_string()]),
         other => Err(Error::InvalidInput(format!(
             "geoq h3 from-str requires H3 cell strings as inputs -- got {}",
@@ -258,11 +319,11 @@ fn cell_disk(cell: H3Cell, radius: u32) -> Result<Vec<String>, Error> {
         })
 }
 
-fn grid_disk(matches: &ArgMatches) -> Result<(), Error> {
+fn grid_disk(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     let radius_opt = read_radius(matches)?;
     let radius = radius_opt.unwrap_or(1);
 
-    par::for_stdin_entity(move |e| match e {
+    par::for_stdin_entity(par_opts, move |e| match e {
         Entity::H3(cell) => cell_disk(cell, radius),
         other => Err(Error::InvalidInput(format!(
             "geoq h3 grid-disk requires H3 cell strings as inputs -- got {}",
@@ -502,12 +563,12 @@ fn geom_cells(g: &geo_types::Geometry<f64>, res: u8) -> Result<Vec<H3Cell>, Erro
     }
 }
 
-fn covering(matches: &ArgMatches) -> Result<(), Error> {
+fn covering(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     let res = read_resolution(matches)?;
     let include_original = matches.is_present("original");
-    let compact = matches.is_present("compact");
+    let compact = matches.is_present("compact-cells");
 
-    par::for_stdin_entity(move |e| {
+    par::for_stdin_entity(par_opts, move |e| {
         let mut results = if include_original {
             vec![e.raw()]
         } else {
@@ -547,17 +608,20 @@ fn covering(matches: &ArgMatches) -> Result<(), Error> {
     })
 }
 
-pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     match matches.subcommand() {
-        ("point", Some(m)) => point(m),
-        ("children", Some(m)) => children(m),
-        ("parent", Some(m)) => parent(m),
-        ("hierarchy", _) => hierarchy(),
-        ("from-str", _) => from_str(),
+        ("point", Some(m)) => point(m, par_opts),
+        ("children", Some(m)) => children(m, par_opts),
+        ("parent", Some(m)) => parent(m, par_opts),
+        ("hierarchy", _) => hierarchy(par_opts),
+        ("from-str", _) => from_str(par_opts),
         ("to-str", _) => to_str(),
-        ("grid-disk", Some(m)) => grid_disk(m),
-        ("resolution", _) => resolution(),
-        ("covering", Some(m)) => covering(m),
+        ("grid-disk", Some(m)) => grid_disk(m, par_opts),
+        ("resolution", _) => resolution(par_opts),
+        ("covering", Some(m)) => covering(m, par_opts),
+        ("boundary", _) => boundary(par_opts),
+        ("compact", _) => compact(),
+        ("uncompact", Some(m)) => uncompact(m, par_opts),
         _ => Err(Error::UnknownCommand),
     }
 }
@@ -0,0 +1,79 @@
+// Stable fingerprints of features' geometries, for deduplicating across
+// runs, as cache keys, or to check that two exports match
+use crate::geoq::{
+    binary::wkb,
+    centroid,
+    entity::Entity,
+    error::Error,
+    fingerprint,
+    par::{self, ParOptions},
+};
+use clap::ArgMatches;
+use geo_types::Coord;
+
+enum Algorithm {
+    // The geohash of the centroid, at a level
+    GeohashCentroid(usize),
+    Md5Wkb,
+    Simhash,
+}
+
+fn algorithm(matches: &ArgMatches) -> Result<Algorithm, Error> {
+    match matches.value_of("algorithm").unwrap() {
+        "geohash-centroid" => {
+            let level = matches.value_of("level").unwrap();
+            match level.parse::<usize>() {
+                Ok(l) if (1..=12).contains(&l) => Ok(Algorithm::GeohashCentroid(l)),
+                _ => Err(Error::InvalidNumberFormat(format!(
+                    "Expected a geohash level between 1 and 12: {}",
+                    level
+                ))),
+            }
+        }
+        "md5-wkb" => Ok(Algorithm::Md5Wkb),
+        "simhash" => Ok(Algorithm::Simhash),
+        a => Err(Error::InvalidInput(format!(
+            "Unknown hash algorithm: {} -- expected geohash-centroid, md5-wkb or simhash",
+            a
+        ))),
+    }
+}
+
+fn hash(algorithm: &Algorithm, e: &Entity) -> Result<String, Error> {
+    match algorithm {
+        Algorithm::GeohashCentroid(level) => match centroid::centroid(&e.geom()) {
+            Some(p) => geohash::encode(Coord { x: p.x(), y: p.y() }, *level)
+                .map_err(|err| Error::InvalidInput(format!("Can't encode geohash: {:?}", err))),
+            None => Err(Error::InvalidInput(format!(
+                "Can't hash the centroid of an empty geometry: {}",
+                e.raw()
+            ))),
+        },
+        Algorithm::Md5Wkb => {
+            let bytes = wkb::write(&e.geojson_geometry());
+            Ok(fingerprint::md5(&bytes)
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect())
+        }
+        Algorithm::Simhash => Ok(fingerprint::hex(fingerprint::simhash(&e.geom()))),
+    }
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let algorithm = algorithm(matches)?;
+    let as_property = matches.value_of("as_property").map(|p| p.to_string());
+    par::for_stdin_entity(par_opts, move |e| {
+        let hash = hash(&algorithm, &e)?;
+        match &as_property {
+            Some(name) => {
+                let mut feature = e.geojson_feature();
+                if let Some(props) = feature.properties.as_mut() {
+                    props.insert(name.clone(), hash.into());
+                }
+                Ok(vec![serde_json::to_string(&feature).unwrap()])
+            }
+            None => Ok(vec![hash]),
+        }
+    })
+}
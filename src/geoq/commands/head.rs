@@ -0,0 +1,23 @@
+use crate::geoq::{error::Error, output, reader};
+use clap::ArgMatches;
+
+pub fn parse_count(matches: &ArgMatches, name: &str) -> Result<usize, Error> {
+    let raw = matches.value_of(name).unwrap_or("10");
+    raw.parse::<usize>().map_err(|_| {
+        Error::InvalidNumberFormat(format!(
+            "Invalid count: {} -- must be a non-negative integer",
+            raw
+        ))
+    })
+}
+
+// Stops reading input once enough features have been output
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let count = parse_count(matches, "count")?;
+    reader::entities(|entities| {
+        for e in entities.take(count) {
+            output::print(&e?.into_raw())?;
+        }
+        Ok(())
+    })
+}
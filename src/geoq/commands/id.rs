@@ -0,0 +1,58 @@
+use crate::geoq::{
+    entity::Entity,
+    error::Error,
+    fingerprint,
+    par::{self, ParOptions},
+    reader,
+};
+use clap::ArgMatches;
+use geojson::feature::Id;
+
+fn with_id(e: &Entity, id: Id) -> String {
+    let mut feature = e.geojson_feature();
+    feature.id = Some(id);
+    serde_json::to_string(&feature).unwrap()
+}
+
+fn index() -> Result<(), Error> {
+    let mut i: u64 = 0;
+    reader::for_entity_lines(|e| {
+        let line = with_id(&e, Id::Number(i.into()));
+        i += 1;
+        Ok(vec![line])
+    })
+}
+
+fn uuid(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, |e| {
+        let id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+        Ok(vec![with_id(&e, Id::String(id))])
+    })
+}
+
+fn hash(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, |e| {
+        let feature = e.geojson_feature();
+        let hash = fingerprint::feature(feature.geometry.as_ref(), &e.geojson_properties());
+        Ok(vec![with_id(&e, Id::String(fingerprint::hex(hash)))])
+    })
+}
+
+fn assign(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    match matches.value_of("strategy").unwrap_or("index") {
+        "index" => index(),
+        "uuid" => uuid(par_opts),
+        "hash" => hash(par_opts),
+        other => Err(Error::InvalidInput(format!(
+            "Unknown id strategy: {} -- expected index, uuid, or hash",
+            other
+        ))),
+    }
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("assign", Some(m)) => assign(m, par_opts),
+        _ => Err(Error::UnknownCommand),
+    }
+}
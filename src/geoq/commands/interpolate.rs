@@ -0,0 +1,59 @@
+// Reapportions counts, such as population, from the polygons of the input
+// to the target zones of another dataset, in proportion to the area of
+// each source polygon that falls in each zone. This assumes what's counted
+// is spread evenly over each source polygon.
+use crate::geoq::{area, error::Error, other::Other, output, overlay, reader};
+use clap::ArgMatches;
+use serde_json::{json, Map, Value};
+
+// A source's value for a property, as a number. Missing and null values
+// count as 0, and numbers written as strings are accepted.
+fn number(props: &Map<String, Value>, name: &str, raw: &str) -> Result<f64, Error> {
+    let invalid = || Error::InvalidInput(format!("Expected a number for {}: {}", name, raw));
+    match props.get(name) {
+        None | Some(Value::Null) => Ok(0.0),
+        Some(Value::Number(n)) => n.as_f64().ok_or_else(invalid),
+        Some(Value::String(s)) => s.trim().parse().map_err(|_| invalid()),
+        Some(_) => Err(invalid()),
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let names: Vec<&str> = matches.values_of("value").unwrap().collect();
+    let targets = Other::read(matches.value_of("other").unwrap())?;
+
+    // Each target's share of each value
+    let mut totals = vec![vec![0.0; names.len()]; targets.len()];
+    reader::for_entity(|e| {
+        let geom = e.geom();
+        let source_area = area::area(&geom);
+        if source_area == 0.0 {
+            return Ok(());
+        }
+        let props = e.geojson_properties();
+        let values = names
+            .iter()
+            .map(|name| number(&props, name, &e.raw()))
+            .collect::<Result<Vec<f64>, Error>>()?;
+        for i in targets.near(e.bbox()) {
+            let share = overlay::overlap_area(&geom, &targets.get(i)?.geom()) / source_area;
+            if share > 0.0 {
+                for (total, v) in totals[i].iter_mut().zip(&values) {
+                    *total += v * share;
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    for (target, totals) in targets.iter().zip(totals) {
+        let mut feature = target?.geojson_feature();
+        if let Some(props) = feature.properties.as_mut() {
+            for (name, total) in names.iter().zip(totals) {
+                props.insert(name.to_string(), json!(total));
+            }
+        }
+        output::print(&serde_json::to_string(&feature)?)?;
+    }
+    Ok(())
+}
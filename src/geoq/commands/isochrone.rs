@@ -0,0 +1,64 @@
+use crate::geoq::{
+    commands::raster::value_json,
+    entity::Entity,
+    error::Error,
+    net, reader, report,
+    routing::{Client, Engine},
+};
+use clap::ArgMatches;
+use geo_types::Geometry;
+
+fn read_minutes(matches: &ArgMatches) -> Result<Vec<f64>, Error> {
+    let minutes = matches.value_of("minutes").unwrap();
+    minutes
+        .split(',')
+        .map(|m| match m.trim().parse::<f64>() {
+            Ok(m) if m > 0.0 && m.is_finite() => Ok(m),
+            _ => Err(Error::InvalidNumberFormat(format!(
+                "Expected comma-separated positive minutes: {}",
+                minutes
+            ))),
+        })
+        .collect()
+}
+
+async fn isochrones(client: &Client, minutes: &[f64], e: Entity) -> Result<Vec<String>, Error> {
+    let point = match e.geom() {
+        Geometry::Point(p) => p.0,
+        _ => {
+            return Err(Error::InvalidInput(format!(
+                "Expected Point to get isochrones for: {}",
+                e.raw()
+            )))
+        }
+    };
+    let polygons = client.isochrone(point, minutes).await?;
+    if polygons.is_empty() {
+        report::warning("No isochrones found for", &e.raw());
+    }
+    Ok(polygons
+        .into_iter()
+        .map(|(band, geometry)| {
+            let mut feature = e.geojson_feature();
+            let mut properties = e.geojson_properties();
+            properties.insert("minutes".to_string(), value_json(band));
+            feature.properties = Some(properties);
+            feature.geometry = Some(geometry);
+            serde_json::to_string(&feature).unwrap()
+        })
+        .collect())
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let minutes = read_minutes(matches)?;
+    let engine: Engine = matches.value_of("engine").unwrap().parse()?;
+    let client = Client::new(
+        engine,
+        matches.value_of("endpoint").unwrap(),
+        matches.value_of("profile").unwrap(),
+    )
+    .api_key(matches.value_of("api-key"));
+    reader::for_entity_batches(net::concurrency(), |entities| {
+        net::batch(entities, |e| isochrones(&client, &minutes, e))
+    })
+}
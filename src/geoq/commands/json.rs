@@ -1,11 +1,11 @@
-use crate::geoq::error::Error;
+use crate::geoq::{error::Error, output, reader};
 use clap::ArgMatches;
 use geo_types::{Geometry, Point};
 use geojson::GeoJson;
 use serde_json;
 use serde_json::{json, Map, Value};
 use std::convert::TryInto;
-use std::io::{self, BufRead};
+use std::io;
 
 pub fn find_number(
     v: &Map<String, Value>,
@@ -163,31 +163,39 @@ pub fn find_geometry(v: &Map<String, Value>) -> Option<(Geom, Vec<&'static str>)
 
 fn munge() -> Result<(), Error> {
     let stdin = io::stdin();
-    for l in stdin.lock().lines() {
-        let line = l?;
-        let v: Value = serde_json::from_str(&line)?;
-        match v {
-            Value::Object(mut o) => match find_geometry(&o) {
-                Some((geom, geomified_keys)) => {
-                    for k in geomified_keys {
-                        o.remove(k);
-                    }
-                    let gj_geom = geojson::Geometry::new(geojson::Value::from(&geom));
-                    let geojson = json!({
-                        "type": "Feature",
-                        "properties": Value::Object(o),
-                        "geometry": gj_geom
-                    });
-                    let json_str = serde_json::to_string(&geojson)?;
-                    println!("{}", json_str)
-                }
-                _ => {
-                    eprintln!("Couldn't guess GeoJSON Feature from JSON");
-                    return Err(Error::InvalidJSONType);
+    let mut stdin_reader = stdin.lock();
+    let (mut n, mut offset) = (0, 0);
+    while let Some((len, line)) = reader::read_line_with_len(&mut stdin_reader) {
+        n += 1;
+        let start = offset;
+        offset += len as u64;
+        munge_line(&line).map_err(|e| e.at(n, start, &line))?;
+    }
+    Ok(())
+}
+
+fn munge_line(line: &str) -> Result<(), Error> {
+    let v: Value = serde_json::from_str(line)?;
+    match v {
+        Value::Object(mut o) => match find_geometry(&o) {
+            Some((geom, geomified_keys)) => {
+                for k in geomified_keys {
+                    o.remove(k);
                 }
-            },
-            _ => return Err(Error::InvalidJSONType),
-        }
+                let gj_geom = geojson::Geometry::new(geojson::Value::from(&geom));
+                let geojson = json!({
+                    "type": "Feature",
+                    "properties": Value::Object(o),
+                    "geometry": gj_geom
+                });
+                output::print(&serde_json::to_string(&geojson)?)?;
+            }
+            _ => {
+                eprintln!("Couldn't guess GeoJSON Feature from JSON");
+                return Err(Error::InvalidJSONType);
+            }
+        },
+        _ => return Err(Error::InvalidJSONType),
     }
     Ok(())
 }
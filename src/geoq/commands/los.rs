@@ -0,0 +1,69 @@
+// Line of sight from each point of the input to each point of another
+// dataset over the terrain of a DEM, such as which summits can see which
+// towers. The targets and the DEM are held in memory, and the input
+// points are read a line at a time.
+use super::raster::{read_band, read_nodata};
+use crate::geoq::{
+    error::Error,
+    labeled::{labeled, Labeled},
+    other, output,
+    raster::{geotiff, los},
+    reader, units,
+};
+use clap::ArgMatches;
+use geo_types::{Geometry, LineString};
+use serde_json::{json, Map, Value};
+
+fn sight_feature(from: &Labeled, to: &Labeled, sight: los::Sight) -> geojson::Feature {
+    let (visible, obstruction) = match sight {
+        los::Sight::Visible => (json!(true), Value::Null),
+        los::Sight::Blocked(c) => (json!(false), json!([c.x, c.y])),
+        los::Sight::Unknown => (Value::Null, Value::Null),
+    };
+    let mut properties = Map::new();
+    properties.insert("from".to_string(), from.label.clone());
+    properties.insert("to".to_string(), to.label.clone());
+    properties.insert("visible".to_string(), visible);
+    properties.insert("obstruction".to_string(), obstruction);
+    let line = LineString(vec![from.point.0, to.point.0]);
+    geojson::Feature {
+        bbox: None,
+        geometry: Some(geojson::Geometry::new(geojson::Value::from(
+            &Geometry::LineString(line),
+        ))),
+        id: None,
+        properties: Some(properties),
+        foreign_members: None,
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let mut raster = geotiff::read(matches.value_of("dem").unwrap(), read_band(matches)?)?;
+    read_nodata(matches, &mut raster)?;
+    let observer_height = units::parse_length(matches.value_of("observer-height").unwrap())?;
+    let target_height = units::parse_length(matches.value_of("target-height").unwrap())?;
+
+    let mut targets = vec![];
+    other::for_entity(matches.value_of("other").unwrap(), |e| {
+        targets.push(labeled(&e, targets.len())?);
+        Ok(())
+    })?;
+
+    let mut index = 0;
+    reader::for_entity(|e| {
+        let observer = labeled(&e, index)?;
+        index += 1;
+        for target in &targets {
+            let sight = los::sight(
+                &raster,
+                observer.point.0,
+                target.point.0,
+                observer_height,
+                target_height,
+            );
+            let feature = sight_feature(&observer, target, sight);
+            output::print(&serde_json::to_string(&feature)?)?;
+        }
+        Ok(())
+    })
+}
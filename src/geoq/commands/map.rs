@@ -1,4 +1,5 @@
-use crate::geoq::{browser_open, error::Error, reader::Reader};
+use crate::geoq::{browser_open, error::Error, reader::Reader, style};
+use clap::ArgMatches;
 use geojson::GeoJson;
 use percent_encoding::{utf8_percent_encode, DEFAULT_ENCODE_SET};
 use std::{
@@ -18,7 +19,10 @@ fn timestamp() -> u64 {
         .as_secs()
 }
 
-pub fn run() -> Result<(), Error> {
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let styles = matches
+        .values_of("style")
+        .map_or(Ok(vec![]), |s| s.map(|s| s.parse()).collect())?;
     let stdin = io::stdin();
     let mut stdin_reader = stdin.lock();
     let reader = Reader::new(&mut stdin_reader);
@@ -27,7 +31,15 @@ pub fn run() -> Result<(), Error> {
 
     for e_res in reader {
         match e_res {
-            Ok(entity) => features.push(entity.geojson_feature()),
+            Ok(entity) => {
+                let mut feature = entity.geojson_feature();
+                if !styles.is_empty() {
+                    let mut props = entity.geojson_properties();
+                    style::apply(&styles, &entity.geom(), &mut props)?;
+                    feature.properties = Some(props);
+                }
+                features.push(feature)
+            }
             Err(e) => return Err(e),
         }
     }
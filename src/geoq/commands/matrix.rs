@@ -0,0 +1,70 @@
+// Distances between every point of the input and every point of another
+// dataset, for small routing and assignment problems. Both point sets are
+// held in memory, and the matrix is written a row at a time.
+use crate::geoq::{error::Error, labeled::labeled, other, output, reader, rhumb, units};
+use clap::ArgMatches;
+use geo::algorithm::{geodesic_distance::GeodesicDistance, haversine_distance::HaversineDistance};
+use geo_types::Point;
+use serde_json::{json, Value};
+
+fn csv_label(label: &Value) -> String {
+    match label {
+        Value::String(s) => output::csv_field(s),
+        v => v.to_string(),
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let metric = matches.value_of("metric").unwrap();
+    let max_distance = matches
+        .value_of("max-distance")
+        .map(units::parse_length)
+        .transpose()?;
+    let json = matches.is_present("json");
+
+    let mut rows = vec![];
+    reader::for_entity(|e| {
+        rows.push(labeled(&e, rows.len())?);
+        Ok(())
+    })?;
+    let mut columns = vec![];
+    other::for_entity(matches.value_of("other").unwrap(), |e| {
+        columns.push(labeled(&e, columns.len())?);
+        Ok(())
+    })?;
+
+    // Distances past --max-distance are left out, as empty CSV fields
+    let distance = |a: &Point<f64>, b: &Point<f64>| {
+        let meters = match metric {
+            "haversine" => a.haversine_distance(b),
+            "rhumb" => rhumb::distance(a.0, b.0),
+            _ => a.geodesic_distance(b),
+        };
+        match max_distance {
+            Some(max) if meters > max => None,
+            _ => Some(units::from_meters(meters)),
+        }
+    };
+
+    if !json {
+        let header: Vec<String> = columns.iter().map(|c| csv_label(&c.label)).collect();
+        output::print(&format!("id,{}", header.join(",")))?;
+    }
+    for row in &rows {
+        let distances = columns.iter().map(|c| distance(&row.point, &c.point));
+        if json {
+            for (column, d) in columns.iter().zip(distances) {
+                if let Some(d) = d {
+                    let pair = json!({"from": row.label, "to": column.label, "distance": d});
+                    output::print(&pair.to_string())?;
+                }
+            }
+        } else {
+            let fields: Vec<String> = distances
+                .map(|d| d.map_or_else(String::new, |d| d.to_string()))
+                .collect();
+            output::print(&format!("{},{}", csv_label(&row.label), fields.join(",")))?;
+        }
+    }
+    Ok(())
+}
@@ -1,72 +1,203 @@
-use crate::geoq::{coord_count, distance, entity, error::Error, input, par};
+use crate::geoq::{
+    area, coord_count, distance,
+    entity::{self, Entity},
+    error::Error,
+    input, length,
+    par::{self, ParOptions},
+    rhumb, units,
+};
 use clap::ArgMatches;
-use geo_types::Geometry;
+use geo::algorithm::bearing::Bearing;
+use geo_types::{Geometry, Point};
 use serde_json::json;
 
-fn distance(matches: &ArgMatches) -> Result<(), Error> {
-    match matches.value_of("query") {
-        Some(q) => {
-            let query_input = input::read_line(q.to_string())?;
-            let mut query_entities = entity::from_input(query_input)?;
-            if query_entities.is_empty() {
-                Err(Error::UnknownEntityFormat)
-            } else if query_entities.len() > 1 {
-                Err(Error::TooManyFeatures)
-            } else {
-                match query_entities.remove(0).geom() {
-                    Geometry::Point(query_point) => par::for_stdin_entity(move |entity| {
-                        let output = entity.raw();
-                        let geom = entity.geom();
+// How each measurement is written out
+enum Output {
+    // The bare number, followed by the input when `with_input` is given
+    Number { with_input: bool },
+    // {"id", "units", "value"}, for --json
+    Json,
+    // The input as a GeoJSON Feature with the measurement in its properties,
+    // for --as-property
+    Property(String),
+}
 
-                        let dist = distance::distance(&query_point, &geom);
+// Numbers as Rust formats them, so whole distances are written as e.g. 0
+// rather than JSON's 0.0
+fn number(value: &serde_json::Value) -> String {
+    value
+        .as_f64()
+        .map_or_else(|| value.to_string(), |n| n.to_string())
+}
 
-                        match dist {
-                            Some(d) => Ok(vec![format!("{}\t{}", d, output)]),
-                            None => {
-                                eprintln!("Couldn't calculate distance between <query>: {:?} and <input>: {}", query_point, output);
-                                Err(Error::DistanceFailed)
-                            }
-                        }
-                    }),
-                    _ => {
-                        eprintln!(
-                            "<query> argument for measuing distance must be a Point. Got: {}",
-                            q
-                        );
-                        Err(Error::PointRequired)
-                    }
+impl Output {
+    fn from_args(matches: &ArgMatches, with_input: bool) -> Output {
+        if matches.is_present("json") {
+            Output::Json
+        } else if let Some(name) = matches.value_of("as_property") {
+            Output::Property(name.to_string())
+        } else {
+            Output::Number { with_input }
+        }
+    }
+
+    fn line(&self, e: &Entity, value: serde_json::Value, units: &str) -> String {
+        match self {
+            Output::Number { with_input: true } => format!("{}\t{}", number(&value), e.raw()),
+            Output::Number { with_input: false } => number(&value),
+            // The id is the feature's own id, or null for inputs without one
+            Output::Json => json!({ "id": e.id(), "value": value, "units": units }).to_string(),
+            Output::Property(name) => {
+                let mut feature = e.geojson_feature();
+                if let Some(props) = feature.properties.as_mut() {
+                    props.insert(name.clone(), value);
                 }
+                serde_json::to_string(&feature).unwrap()
             }
         }
-        _ => Err(Error::MissingArgument),
     }
 }
 
-fn coords(matches: &ArgMatches) -> Result<(), Error> {
-    let gj = matches.is_present("geojson");
-    par::for_stdin_entity(move |e| {
-        let dupe = e.clone();
-        let geom = e.geom();
-        let count = coord_count::coord_count(&geom);
-        if gj {
-            let mut feature = dupe.geojson_feature();
-            match feature.properties.as_mut() {
-                Some(props) => {
-                    props.insert("coord_count".to_string(), json!(count));
-                }
-                None => (),
+// Measures each input entity with `f`, giving `units` with --json
+fn measure<F>(output: Output, units: String, f: F, par_opts: &ParOptions) -> Result<(), Error>
+where
+    F: Fn(&Entity) -> Result<serde_json::Value, Error> + Send + Sync + 'static,
+{
+    par::for_stdin_entity(par_opts, move |e| {
+        let value = f(&e)?;
+        Ok(vec![output.line(&e, value, &units)])
+    })
+}
+
+fn query_point(q: &str) -> Result<Point<f64>, Error> {
+    let query_input = input::read_line(q.to_string())?;
+    let mut query_entities = entity::from_input(query_input)?;
+    if query_entities.is_empty() {
+        Err(Error::UnknownEntityFormat)
+    } else if query_entities.len() > 1 {
+        Err(Error::TooManyFeatures)
+    } else {
+        match query_entities.remove(0).geom() {
+            Geometry::Point(query_point) => Ok(query_point),
+            _ => {
+                eprintln!(
+                    "<query> argument for measuing distance must be a Point. Got: {}",
+                    q
+                );
+                Err(Error::PointRequired)
             }
-            Ok(vec![serde_json::to_string(&feature).unwrap()])
-        } else {
-            Ok(vec![format!("{}", count)])
         }
-    })
+    }
+}
+
+fn distance(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    match matches.value_of("query") {
+        Some(q) => {
+            let query_point = query_point(q)?;
+            let output = Output::from_args(matches, true);
+            let unit = units::length_unit().name().to_string();
+            let rhumb = matches.is_present("rhumb");
+            measure(
+                output,
+                unit,
+                move |entity| {
+                    let d = if rhumb {
+                        distance::rhumb_distance(&query_point, &entity.geom())
+                    } else {
+                        distance::distance(&query_point, &entity.geom())
+                    };
+                    match d {
+                        Some(d) => Ok(json!(units::from_meters(d))),
+                        None => {
+                            eprintln!(
+                                "Couldn't calculate distance between <query>: {:?} and <input>: {}",
+                                query_point,
+                                entity.raw()
+                            );
+                            Err(Error::DistanceFailed)
+                        }
+                    }
+                },
+                par_opts,
+            )
+        }
+        _ => Err(Error::MissingArgument),
+    }
+}
+
+fn coords(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let output = if matches.is_present("geojson") {
+        Output::Property("coord_count".to_string())
+    } else {
+        Output::from_args(matches, false)
+    };
+    measure(
+        output,
+        "coordinates".to_string(),
+        |e| Ok(json!(coord_count::coord_count(&e.geom()))),
+        par_opts,
+    )
+}
+
+fn area(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let output = Output::from_args(matches, false);
+    let (_, unit) = units::from_square_meters(0.0);
+    measure(
+        output,
+        unit,
+        |e| Ok(json!(units::from_square_meters(area::area(&e.geom())).0)),
+        par_opts,
+    )
+}
+
+fn length(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let output = Output::from_args(matches, false);
+    let unit = units::length_unit().name().to_string();
+    let rhumb = matches.is_present("rhumb");
+    measure(
+        output,
+        unit,
+        move |e| {
+            let meters = if rhumb {
+                rhumb::length(&e.geom())
+            } else {
+                length::length(&e.geom())
+            };
+            Ok(json!(units::from_meters(meters)))
+        },
+        par_opts,
+    )
+}
+
+// The bearing from each input point to the query point, in degrees
+// clockwise from north from 0 to 360: the initial bearing of the great
+// circle, or with --rhumb the constant bearing of the rhumb line
+fn bearing(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let query_point = query_point(matches.value_of("query").unwrap())?;
+    let output = Output::from_args(matches, true);
+    let rhumb = matches.is_present("rhumb");
+    measure(
+        output,
+        "degrees".to_string(),
+        move |entity| match entity.geom() {
+            Geometry::Point(p) if rhumb => Ok(json!(rhumb::bearing(p.0, query_point.0))),
+            Geometry::Point(p) => Ok(json!((p.bearing(query_point) + 360.0) % 360.0)),
+            _ => Err(Error::InvalidInput(format!(
+                "Only points have a bearing: {}",
+                entity.raw()
+            ))),
+        },
+        par_opts,
+    )
 }
 
-pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
     match matches.subcommand() {
-        ("distance", Some(m)) => distance(m),
-        ("coord-count", Some(m)) => coords(m),
+        ("distance", Some(m)) => distance(m, par_opts),
+        ("coord-count", Some(m)) => coords(m, par_opts),
+        ("area", Some(m)) => area(m, par_opts),
+        ("length", Some(m)) => length(m, par_opts),
+        ("bearing", Some(m)) => bearing(m, par_opts),
         _ => Err(Error::UnknownCommand),
     }
 }
@@ -1,16 +1,61 @@
+pub mod aggregate;
 pub mod bbox;
+pub mod bench;
+pub mod buffer;
+pub mod cat;
 pub mod centroid;
+pub mod completions;
+pub mod contour;
+pub mod dedup;
+pub mod destination;
+pub mod diff;
+pub mod elevation;
+pub mod estimate;
 pub mod fgb;
 pub mod filter;
+pub mod geocode;
 pub mod geohash;
 pub mod geojson_cmd;
+pub mod graticule;
+pub mod grep;
 pub mod h3;
+pub mod hash;
+pub mod head;
+pub mod id;
+pub mod interpolate;
+pub mod isochrone;
 pub mod json;
+pub mod los;
 pub mod map;
+pub mod matrix;
 pub mod measure;
+pub mod ogcapi;
+pub mod partition;
+pub mod pipe;
+pub mod plot;
+pub mod props;
+pub mod quadkey;
+pub mod raster;
 pub mod read;
+pub mod reproject;
+pub mod reverse_geocode;
+pub mod route;
+pub mod sample;
+pub mod schema;
+pub mod serve;
 pub mod shp;
 pub mod simplify;
+pub mod skip;
+pub mod snap;
 pub mod snip;
+pub mod sort;
+pub mod split_output;
+pub mod style;
+pub mod tail;
+pub mod tile;
+pub mod tiles;
+pub mod trajectory;
+pub mod transform;
 pub mod whereami;
 pub mod wkt;
+pub mod zonal;
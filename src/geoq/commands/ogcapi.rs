@@ -0,0 +1,177 @@
+// Reads features from OGC API - Features (https://ogcapi.ogc.org/features/)
+// collections, following their "next" links, and from WFS 2.0 GetFeature
+// requests, paging with startIndex
+use crate::geoq::{error::Error, net, output};
+use clap::ArgMatches;
+use reqwest::Url;
+use serde_json::Value;
+
+fn parse_url(url: &str) -> Result<Url, Error> {
+    Url::parse(url).map_err(|_| Error::InvalidInput(format!("Invalid URL: {}", url)))
+}
+
+// Sets query parameters, replacing any existing ones with the same
+// name (compared case-insensitively, as WFS parameter names are)
+fn set_query(url: &mut Url, params: &[(&str, String)]) {
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| !params.iter().any(|(p, _)| k.eq_ignore_ascii_case(p)))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    let mut pairs = url.query_pairs_mut();
+    pairs.clear();
+    for (k, v) in kept {
+        pairs.append_pair(&k, &v);
+    }
+    for (k, v) in params {
+        pairs.append_pair(k, v);
+    }
+}
+
+fn is_wfs(url: &Url) -> bool {
+    url.query_pairs()
+        .any(|(k, v)| k.eq_ignore_ascii_case("service") && v.eq_ignore_ascii_case("wfs"))
+}
+
+fn get_page(url: &Url) -> Result<Value, Error> {
+    let request = net::client()
+        .get(url.clone())
+        .header("Accept", "application/geo+json, application/json");
+    let (status, body) = net::block_on(net::text(request))?;
+    if !status.is_success() {
+        return Err(Error::ProgramError(format!(
+            "Request failed with {}: {}",
+            status, url
+        )));
+    }
+    Ok(serde_json::from_str(&body)?)
+}
+
+// Prints a page's features, returning how many there were, up to the
+// number still wanted
+fn print_features(page: &Value, remaining: &mut Option<usize>) -> Result<usize, Error> {
+    let features = page["features"].as_array().map(|f| &f[..]).unwrap_or(&[]);
+    let n = remaining.map_or(features.len(), |r| r.min(features.len()));
+    for f in &features[..n] {
+        output::print(&f.to_string())?;
+    }
+    if let Some(r) = remaining {
+        *r -= n;
+    }
+    Ok(features.len())
+}
+
+fn next_link(page: &Value, url: &Url) -> Option<Url> {
+    page["links"]
+        .as_array()?
+        .iter()
+        .find(|l| l["rel"] == "next")
+        .and_then(|l| l["href"].as_str())
+        .and_then(|href| url.join(href).ok())
+}
+
+fn read_ogcapi(
+    mut url: Url,
+    page_size: usize,
+    bbox: Option<&str>,
+    mut remaining: Option<usize>,
+) -> Result<(), Error> {
+    // A collection URL is read through its items
+    if !url.path().trim_end_matches('/').ends_with("/items") {
+        let path = format!("{}/items", url.path().trim_end_matches('/'));
+        url.set_path(&path);
+    }
+    let mut params = vec![("limit", page_size.to_string())];
+    if let Some(bbox) = bbox {
+        params.push(("bbox", bbox.to_string()));
+    }
+    set_query(&mut url, &params);
+    let mut next = Some(url);
+    while let Some(url) = next {
+        if remaining == Some(0) {
+            break;
+        }
+        let page = get_page(&url)?;
+        if print_features(&page, &mut remaining)? == 0 {
+            break;
+        }
+        next = next_link(&page, &url);
+    }
+    Ok(())
+}
+
+fn read_wfs(
+    mut url: Url,
+    page_size: usize,
+    bbox: Option<&str>,
+    mut remaining: Option<usize>,
+) -> Result<(), Error> {
+    let mut params = vec![
+        ("version", "2.0.0".to_string()),
+        ("request", "GetFeature".to_string()),
+        ("outputFormat", "application/json".to_string()),
+        ("count", page_size.to_string()),
+    ];
+    if let Some(bbox) = bbox {
+        params.push(("bbox", format!("{},urn:ogc:def:crs:OGC:1.3:CRS84", bbox)));
+    }
+    set_query(&mut url, &params);
+    let mut start = 0;
+    while remaining != Some(0) {
+        let mut page_url = url.clone();
+        set_query(&mut page_url, &[("startIndex", start.to_string())]);
+        let page = get_page(&page_url)?;
+        let n = print_features(&page, &mut remaining)?;
+        start += n;
+        // Servers may return fewer features than asked for, so paging
+        // stops at an empty page, or once all matches have been read
+        let matched = page["numberMatched"].as_u64().map(|m| m as usize);
+        if n == 0 || matched.is_some_and(|m| start >= m) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn read(matches: &ArgMatches) -> Result<(), Error> {
+    let url = parse_url(matches.value_of("url").unwrap())?;
+    let page_size = matches.value_of("page-size").unwrap();
+    let page_size = match page_size.parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => {
+            return Err(Error::InvalidNumberFormat(format!(
+                "Expected positive page size: {}",
+                page_size
+            )))
+        }
+    };
+    let remaining = match matches.value_of("max-features") {
+        None => None,
+        Some(m) => Some(m.parse::<usize>().map_err(|_| {
+            Error::InvalidNumberFormat(format!("Expected number of features: {}", m))
+        })?),
+    };
+    let bbox = matches.value_of("bbox");
+    if let Some(bbox) = bbox {
+        let valid = bbox.split(',').count() == 4
+            && bbox.split(',').all(|v| v.trim().parse::<f64>().is_ok());
+        if !valid {
+            return Err(Error::InvalidInput(format!(
+                "Expected bbox as min_lon,min_lat,max_lon,max_lat: {}",
+                bbox
+            )));
+        }
+    }
+    if is_wfs(&url) {
+        read_wfs(url, page_size, bbox, remaining)
+    } else {
+        read_ogcapi(url, page_size, bbox, remaining)
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("read", Some(m)) => read(m),
+        _ => Err(Error::UnknownCommand),
+    }
+}
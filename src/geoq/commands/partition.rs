@@ -0,0 +1,119 @@
+use crate::geoq::{centroid, entity::Entity, error::Error, geohash, reader};
+use clap::ArgMatches;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Partition files are closed and later reopened for appending once
+// this many are open, to stay under the process's file descriptor limit
+const MAX_OPEN_FILES: usize = 256;
+
+#[derive(Clone, Copy)]
+enum Assign {
+    Centroid,
+    Duplicate,
+}
+
+fn parse_level(matches: &ArgMatches) -> Result<usize, Error> {
+    let by = matches.value_of("by").unwrap();
+    let invalid = || {
+        Error::InvalidInput(format!(
+            "Invalid partition key: {} -- expected geohash:<level>, e.g. geohash:5",
+            by
+        ))
+    };
+    if !by.starts_with("geohash:") {
+        return Err(invalid());
+    }
+    match by["geohash:".len()..].parse::<usize>() {
+        Ok(level) if (1..=12).contains(&level) => Ok(level),
+        _ => Err(invalid()),
+    }
+}
+
+fn cells(e: &Entity, level: usize, assign: Assign) -> Result<Vec<String>, Error> {
+    let geom = e.geom();
+    match assign {
+        Assign::Centroid => match centroid::centroid(&geom) {
+            Some(p) => Ok(vec![
+                ::geohash::encode(p.0, level).map_err(|_| Error::InvalidGeohashPoint)?
+            ]),
+            None => Ok(vec![]),
+        },
+        Assign::Duplicate => {
+            let mut cells = geohash::covering(&geom, level);
+            cells.sort();
+            Ok(cells)
+        }
+    }
+}
+
+struct Partitions {
+    dir: PathBuf,
+    open: HashMap<String, BufWriter<File>>,
+    counts: BTreeMap<String, usize>,
+}
+
+impl Partitions {
+    fn path(&self, cell: &str) -> PathBuf {
+        self.dir.join(format!("{}.geojson", cell))
+    }
+
+    fn write(&mut self, cell: &str, line: &str) -> Result<(), Error> {
+        if !self.open.contains_key(cell) {
+            if self.open.len() >= MAX_OPEN_FILES {
+                for (_, mut w) in self.open.drain() {
+                    w.flush()?;
+                }
+            }
+            // Truncate files left from earlier runs the first time a
+            // cell is seen, and append when reopening it
+            let file = if self.counts.contains_key(cell) {
+                OpenOptions::new().append(true).open(self.path(cell))?
+            } else {
+                File::create(self.path(cell))?
+            };
+            self.open.insert(cell.to_string(), BufWriter::new(file));
+        }
+        writeln!(self.open.get_mut(cell).unwrap(), "{}", line)?;
+        *self.counts.entry(cell.to_string()).or_insert(0) += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<PathBuf>, Error> {
+        for (_, mut w) in self.open.drain() {
+            w.flush()?;
+        }
+        Ok(self.counts.keys().map(|cell| self.path(cell)).collect())
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let level = parse_level(matches)?;
+    let assign = match matches.value_of("assign") {
+        Some("duplicate") => Assign::Duplicate,
+        _ => Assign::Centroid,
+    };
+    let dir = Path::new(matches.value_of("out-dir").unwrap());
+    fs::create_dir_all(dir)?;
+
+    let mut partitions = Partitions {
+        dir: dir.to_path_buf(),
+        open: HashMap::new(),
+        counts: BTreeMap::new(),
+    };
+    reader::for_entity(|e| {
+        // Written as GeoJSON whatever the input format, to match the files'
+        // extension
+        let feature = serde_json::to_string(&e.geojson_feature())?;
+        for cell in cells(&e, level, assign)? {
+            partitions.write(&cell, &feature)?;
+        }
+        Ok(())
+    })?;
+    for path in partitions.finish()? {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,262 @@
+// Chains commands in one process: `geoq pipe 'simplify 0.001 | centroid'`
+// hands the entities each stage gives on to the next, rather than writing
+// them out as GeoJSON for another geoq to parse again. Each stage is read
+// as its command line would be, so takes the same arguments. The stages
+// run over the input in parallel like a single command, unless the
+// pipeline ends in `fgb write`, which collects the features to write.
+use crate::geoq::{
+    commands::{centroid, fgb, filter, simplify, transform},
+    entity::Entity,
+    error::Error,
+    expr,
+    par::{self, ParOptions},
+    reader,
+};
+use clap::ArgMatches;
+use std::{fs, iter};
+
+// An entity between stages. Those no stage has changed are written out
+// just as they were read.
+struct Item {
+    entity: Entity,
+    changed: bool,
+}
+
+impl Item {
+    fn feature(f: geojson::Feature) -> Item {
+        Item {
+            entity: Entity::GeoJsonFeature(String::new(), f),
+            changed: true,
+        }
+    }
+
+    fn geometry(g: geojson::Geometry) -> Item {
+        Item {
+            entity: Entity::GeoJsonGeometry(String::new(), g),
+            changed: true,
+        }
+    }
+
+    fn into_line(self) -> String {
+        match (self.changed, self.entity) {
+            (true, Entity::GeoJsonFeature(_, f)) => serde_json::to_string(&f).unwrap(),
+            (true, Entity::GeoJsonGeometry(_, g)) => serde_json::to_string(&g).unwrap(),
+            (_, e) => e.into_raw(),
+        }
+    }
+}
+
+type Stage = Box<dyn Fn(Item) -> Result<Vec<Item>, Error> + Send + Sync>;
+
+// Where the last stage's entities go
+enum Sink {
+    Stdout,
+    Fgb(String),
+}
+
+// Splits a pipeline into its stages' words, with words quoted as a shell
+// would quote them
+fn split_stages(pipeline: &str) -> Result<Vec<Vec<String>>, Error> {
+    let mut stages = vec![];
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in pipeline.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'') | (None, '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, '|') => {
+                words.extend(word.take());
+                stages.push(std::mem::take(&mut words));
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(Error::InvalidInput(format!(
+            "Unterminated quote in pipeline: {}",
+            pipeline
+        )));
+    }
+    words.extend(word);
+    stages.push(words);
+    if stages.iter().any(|s| s.is_empty()) {
+        return Err(Error::InvalidInput(format!(
+            "Empty stage in pipeline: {}",
+            pipeline
+        )));
+    }
+    Ok(stages)
+}
+
+// A pipeline file has a stage per line, which may be written as a YAML
+// list, and # comments
+fn read_file(text: &str) -> String {
+    let stages: Vec<&str> = text
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && *l != "---")
+        .map(|l| l.strip_prefix('-').map_or(l, |l| l.trim_start()))
+        .map(|l| match (l.chars().next(), l.chars().last()) {
+            (Some(q), Some(e)) if l.len() > 1 && (q == '"' || q == '\'') && q == e => {
+                &l[1..l.len() - 1]
+            }
+            _ => l,
+        })
+        .collect();
+    stages.join(" | ")
+}
+
+fn stage(matches: &ArgMatches) -> Result<Stage, Error> {
+    match matches.subcommand() {
+        ("centroid", Some(_)) => Ok(Box::new(|item: Item| {
+            Ok(centroid::centroid_geometry(&item.entity)
+                .map(Item::geometry)
+                .into_iter()
+                .collect())
+        })),
+        ("simplify", Some(m)) => {
+            let (epsilon, target) = simplify::read_args(m)?;
+            Ok(Box::new(move |item: Item| {
+                let f = simplify::simplify_feature(&item.entity, epsilon, target);
+                Ok(vec![Item::feature(f)])
+            }))
+        }
+        ("filter", Some(m)) => {
+            let predicate = filter::predicate(m)?;
+            Ok(Box::new(move |item: Item| {
+                if predicate(&item.entity)? {
+                    Ok(vec![item])
+                } else {
+                    Ok(vec![])
+                }
+            }))
+        }
+        ("transform", Some(m)) => {
+            let program = expr::parse_program(m.value_of("program").unwrap())?;
+            Ok(Box::new(move |item: Item| {
+                let f = transform::transform_feature(&item.entity, &program)?;
+                Ok(vec![Item::feature(f)])
+            }))
+        }
+        (name, _) => Err(Error::InvalidInput(format!(
+            "geoq {} can't be a pipe stage -- expected centroid, simplify, filter, transform, or fgb write at the end",
+            name
+        ))),
+    }
+}
+
+fn sink(matches: &ArgMatches) -> Option<Result<Sink, Error>> {
+    match matches.subcommand() {
+        ("fgb", Some(m)) => match m.subcommand() {
+            ("write", Some(w)) if w.is_present("checkpoint") => Some(Err(Error::InvalidInput(
+                "fgb write --checkpoint can't be used in a pipe".to_string(),
+            ))),
+            ("write", Some(w)) => Some(Ok(Sink::Fgb(w.value_of("path").unwrap().to_string()))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse(pipeline: &str) -> Result<(Vec<Stage>, Sink), Error> {
+    let words = split_stages(pipeline)?;
+    let last = words.len() - 1;
+    let mut stages = vec![];
+    let mut out = Sink::Stdout;
+    for (i, words) in words.into_iter().enumerate() {
+        let args = iter::once("geoq".to_string()).chain(words);
+        let matches = crate::app()
+            .get_matches_from_safe(args)
+            .map_err(|e| Error::InvalidInput(e.message))?;
+        match sink(&matches) {
+            Some(s) if i == last => out = s?,
+            Some(_) => {
+                return Err(Error::InvalidInput(
+                    "fgb write must be the last stage of a pipe".to_string(),
+                ))
+            }
+            None => stages.push(stage(&matches)?),
+        }
+    }
+    Ok((stages, out))
+}
+
+fn apply(stages: &[Stage], entity: Entity) -> Result<Vec<Item>, Error> {
+    let mut items = vec![Item {
+        entity,
+        changed: false,
+    }];
+    for stage in stages {
+        let mut next = vec![];
+        for item in items {
+            next.extend(stage(item)?);
+        }
+        items = next;
+    }
+    Ok(items)
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let pipeline = match matches.value_of("file") {
+        Some(path) => read_file(&fs::read_to_string(path)?),
+        None => matches.value_of("pipeline").unwrap().to_string(),
+    };
+    let (stages, out) = parse(&pipeline)?;
+    match out {
+        Sink::Stdout => par::for_stdin_entity(par_opts, move |e| {
+            Ok(apply(&stages, e)?
+                .into_iter()
+                .map(Item::into_line)
+                .collect())
+        }),
+        Sink::Fgb(path) => {
+            let mut features = vec![];
+            reader::for_entity(|e| {
+                for item in apply(&stages, e)? {
+                    features.push(item.entity.geojson_feature());
+                }
+                Ok(())
+            })?;
+            fgb::write_features(&path, features)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_file, split_stages};
+
+    #[test]
+    fn test_split_stages() {
+        assert_eq!(
+            vec![
+                vec!["simplify", "0.001"],
+                vec!["filter", "intersects", "POINT (1 2)"],
+                vec!["centroid"],
+            ],
+            split_stages("simplify 0.001|filter intersects 'POINT (1 2)' | centroid").unwrap()
+        );
+        assert_eq!(
+            vec![vec!["a", "", "b|c"]],
+            split_stages("a '' \"b|c\"").unwrap()
+        );
+        assert!(split_stages("centroid | ").is_err());
+        assert!(split_stages("filter intersects 'POINT (1 2)").is_err());
+    }
+
+    #[test]
+    fn test_read_file() {
+        let file = "---\n# Clean up parcels\n- simplify 0.001\n- \"filter geometry --non-empty\"\n\n- fgb write parcels.fgb\n";
+        assert_eq!(
+            "simplify 0.001 | filter geometry --non-empty | fgb write parcels.fgb",
+            read_file(file)
+        );
+        assert_eq!("centroid", read_file("centroid\n"));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::geoq::{
+    error::Error,
+    plot::{Canvas, Style},
+    reader,
+};
+use clap::ArgMatches;
+use geo_types::{Coord, Geometry, Rect};
+
+fn read_size(matches: &ArgMatches, name: &str, default: usize) -> Result<usize, Error> {
+    match matches.value_of(name) {
+        None => Ok(default),
+        Some(s) => match s.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(Error::InvalidNumberFormat(format!(
+                "Expected positive integer for --{}: {}",
+                name, s
+            ))),
+        },
+    }
+}
+
+// Terminal width from $COLUMNS, which most shells set
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse::<usize>().ok())
+        .filter(|c| *c > 0)
+        .unwrap_or(80)
+}
+
+fn expand(extent: Option<Rect<f64>>, r: Rect<f64>) -> Rect<f64> {
+    match extent {
+        None => r,
+        Some(e) => Rect::new(
+            Coord {
+                x: e.min().x.min(r.min().x),
+                y: e.min().y.min(r.min().y),
+            },
+            Coord {
+                x: e.max().x.max(r.max().x),
+                y: e.max().y.max(r.max().y),
+            },
+        ),
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let width = read_size(matches, "width", terminal_width())?;
+    let height = read_size(matches, "height", 30)?;
+    let style = if matches.is_present("ascii") {
+        Style::Ascii
+    } else {
+        Style::Braille
+    };
+
+    let mut geoms: Vec<Geometry<f64>> = vec![];
+    let mut extent: Option<Rect<f64>> = None;
+    reader::for_entity(|e| {
+        if !e.is_empty() {
+            extent = Some(expand(extent, e.bbox()));
+            geoms.push(e.geom());
+        }
+        Ok(())
+    })?;
+    let extent = extent.ok_or(Error::NoInputGiven)?;
+
+    let mut canvas = Canvas::new(extent, width, height, style);
+    let step = if matches.is_present("graticule") {
+        Some(canvas.graticule())
+    } else {
+        None
+    };
+    for g in &geoms {
+        canvas.draw(g);
+    }
+    for line in canvas.render() {
+        println!("{}", line);
+    }
+    let e = canvas.extent();
+    match step {
+        Some(step) => println!(
+            "extent: {:.4},{:.4},{:.4},{:.4}  graticule: {}°",
+            e.min().x,
+            e.min().y,
+            e.max().x,
+            e.max().y,
+            step
+        ),
+        None => println!(
+            "extent: {:.4},{:.4},{:.4},{:.4}",
+            e.min().x,
+            e.min().y,
+            e.max().x,
+            e.max().y
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,366 @@
+use crate::geoq::{
+    error::Error,
+    expr::{self, Expr, Scope},
+    lookup,
+    par::{self, ParOptions},
+    reader,
+};
+use clap::ArgMatches;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+const MISSING: &str = "(missing)";
+
+fn parse_renames(matches: &ArgMatches) -> Result<Vec<(String, String)>, Error> {
+    match matches.values_of("rename") {
+        None => Ok(vec![]),
+        Some(renames) => renames
+            .map(|r| {
+                let pieces: Vec<&str> = r.splitn(2, ':').collect();
+                if pieces.len() != 2 || pieces[0].is_empty() || pieces[1].is_empty() {
+                    Err(Error::InvalidInput(format!(
+                        "Invalid rename: {} -- expected format old_name:new_name",
+                        r
+                    )))
+                } else {
+                    Ok((pieces[0].to_string(), pieces[1].to_string()))
+                }
+            })
+            .collect(),
+    }
+}
+
+pub fn select_props(
+    props: &Map<String, Value>,
+    keys: &[String],
+    renames: &[(String, String)],
+) -> Map<String, Value> {
+    let mut selected = Map::new();
+    for k in keys {
+        if let Some(v) = props.get(k) {
+            let name = renames
+                .iter()
+                .find(|(from, _)| from == k)
+                .map(|(_, to)| to.clone())
+                .unwrap_or_else(|| k.clone());
+            selected.insert(name, v.clone());
+        }
+    }
+    selected
+}
+
+fn select(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let keys: Vec<String> = matches
+        .value_of("keys")
+        .ok_or(Error::MissingArgument)?
+        .split(',')
+        .filter(|k| !k.is_empty())
+        .map(|k| k.to_string())
+        .collect();
+    let renames = parse_renames(matches)?;
+
+    par::for_stdin_entity(par_opts, move |e| {
+        let mut feature = e.geojson_feature();
+        feature.properties = Some(select_props(&e.geojson_properties(), &keys, &renames));
+        Ok(vec![serde_json::to_string(&feature).unwrap()])
+    })
+}
+
+// Values are expressions evaluated per-feature. A bare word (e.g.
+// source=census) is kept as a literal string, as is an empty value; any
+// other value which doesn't parse, or which uses unknown variables, is an
+// error rather than being written out as its own text.
+fn parse_assignment(raw: &str) -> Result<(String, Expr), Error> {
+    let pieces: Vec<&str> = raw.splitn(2, '=').collect();
+    if pieces.len() != 2 || pieces[0].is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "Invalid property assignment: {} -- expected format key=value",
+            raw
+        )));
+    }
+    let (key, value) = (pieces[0], pieces[1]);
+    if value.trim().is_empty() {
+        return Ok((key.to_string(), Expr::Str(value.to_string())));
+    }
+    let invalid = |problem: String| {
+        Error::InvalidInput(format!(
+            "{} in {} -- quote the value to set it as text, e.g. {}='\"...\"'",
+            problem, raw, key
+        ))
+    };
+    let value = match expr::parse(value) {
+        Ok(Expr::Var(name)) if !expr::VARIABLES.contains(&name.as_str()) => {
+            Expr::Str(value.to_string())
+        }
+        Ok(e) => match e.vars().into_iter().find(|v| !expr::VARIABLES.contains(v)) {
+            Some(v) => return Err(invalid(format!("Unknown variable {}", v))),
+            None => e,
+        },
+        Err(_) => return Err(invalid("Invalid expression".to_string())),
+    };
+    Ok((key.to_string(), value))
+}
+
+fn set(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let assignments: Vec<(String, Expr)> = matches
+        .values_of("assignments")
+        .ok_or(Error::MissingArgument)?
+        .map(parse_assignment)
+        .collect::<Result<_, _>>()?;
+
+    par::for_stdin_entity(par_opts, move |e| {
+        let mut feature = e.geojson_feature();
+        let geom = e.geom();
+        let mut props = e.geojson_properties();
+        for (key, value) in assignments.iter() {
+            let scope = Scope {
+                geom: &geom,
+                props: &props,
+            };
+            let value = value.eval(&scope)?.to_json();
+            props.insert(key.clone(), value);
+        }
+        feature.properties = Some(props);
+        Ok(vec![serde_json::to_string(&feature).unwrap()])
+    })
+}
+
+fn values(matches: &ArgMatches) -> Result<(), Error> {
+    let key = matches.value_of("key").unwrap();
+    let top = match matches.value_of("top") {
+        None => None,
+        Some(t) => match t.parse::<usize>() {
+            Ok(t) => Some(t),
+            Err(_) => {
+                return Err(Error::InvalidNumberFormat(format!(
+                    "Invalid --top: {} -- must be a non-negative integer",
+                    t
+                )))
+            }
+        },
+    };
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    reader::for_entity(|e| {
+        let value = match e.geojson_properties().get(key) {
+            Some(v) => v.to_string(),
+            None => MISSING.to_string(),
+        };
+        *counts.entry(value).or_insert(0) += 1;
+        Ok(())
+    })?;
+
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    for (value, count) in counts.into_iter().take(top.unwrap_or(usize::MAX)) {
+        println!("{}\t{}", count, value);
+    }
+    Ok(())
+}
+
+// Features are matched on their `on` property, or their id if they have
+// no such property
+fn join(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let on = matches.value_of("on").unwrap().to_string();
+    let table = lookup::read(matches.value_of("lookup").unwrap(), &on)?;
+    let drop_unmatched = matches.is_present("drop-unmatched");
+
+    par::for_stdin_entity(par_opts, move |e| {
+        let mut props = e.geojson_properties();
+        let key = match props.get(&on) {
+            Some(v) => lookup::key_text(v),
+            None => e
+                .id()
+                .and_then(|id| lookup::key_text(&serde_json::to_value(id).unwrap())),
+        };
+        match key.and_then(|k| table.get(&k)) {
+            Some(row) => {
+                props.extend(row.clone());
+                let mut feature = e.geojson_feature();
+                feature.properties = Some(props);
+                Ok(vec![serde_json::to_string(&feature).unwrap()])
+            }
+            None if drop_unmatched => Ok(vec![]),
+            None => Ok(vec![e.into_raw()]),
+        }
+    })
+}
+
+#[derive(Clone, Copy)]
+enum KeyCase {
+    Snake,
+    Camel,
+    Kebab,
+    Lower,
+    Upper,
+}
+
+fn key_case(case: &str) -> Result<KeyCase, Error> {
+    match case {
+        "snake_case" => Ok(KeyCase::Snake),
+        "camelCase" => Ok(KeyCase::Camel),
+        "kebab-case" => Ok(KeyCase::Kebab),
+        "lower" => Ok(KeyCase::Lower),
+        "upper" => Ok(KeyCase::Upper),
+        c => Err(Error::InvalidInput(format!(
+            "Unknown key case: {} -- expected snake_case, camelCase, kebab-case, lower or upper",
+            c
+        ))),
+    }
+}
+
+// Splits a key into lowercase words at punctuation, spaces, and changes of
+// case, so "Median HH Income", "medianHHIncome" and "MEDIAN_HH_INCOME" all
+// give median, hh, income
+fn key_words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+        let next = chars.get(i + 1);
+        let boundary = match prev {
+            // fooBar, or the R of HTTPRequest
+            Some(p) if c.is_uppercase() => {
+                p.is_lowercase() || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            }
+            _ => false,
+        };
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn normalize_key(key: &str, case: KeyCase) -> String {
+    let words = key_words(key);
+    match case {
+        KeyCase::Snake => words.join("_"),
+        KeyCase::Kebab => words.join("-"),
+        KeyCase::Lower => key.trim().to_lowercase(),
+        KeyCase::Upper => key.trim().to_uppercase(),
+        KeyCase::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                let mut chars = w.chars();
+                match chars.next() {
+                    Some(first) if i > 0 => first.to_uppercase().chain(chars).collect(),
+                    _ => w.clone(),
+                }
+            })
+            .collect(),
+    }
+}
+
+// Keys which normalize to the same name are an error, rather than one
+// of their values being dropped
+fn normalize(
+    props: Map<String, Value>,
+    case: Option<KeyCase>,
+    trim: bool,
+    empty_as_null: bool,
+) -> Result<Map<String, Value>, Error> {
+    let mut normalized = Map::new();
+    let mut originals: HashMap<String, String> = HashMap::new();
+    for (k, v) in props {
+        let key = match case {
+            Some(case) => normalize_key(&k, case),
+            None => k.clone(),
+        };
+        if let Some(other) = originals.insert(key.clone(), k.clone()) {
+            return Err(Error::InvalidInput(format!(
+                "Keys '{}' and '{}' both normalize to '{}'",
+                other, k, key
+            )));
+        }
+        let v = match v {
+            Value::String(s) if trim => Value::String(s.trim().to_string()),
+            v => v,
+        };
+        let v = match v {
+            Value::String(s) if empty_as_null && s.is_empty() => Value::Null,
+            v => v,
+        };
+        normalized.insert(key, v);
+    }
+    Ok(normalized)
+}
+
+fn normalize_props(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let case = matches.value_of("keys").map(key_case).transpose()?;
+    let trim = matches.is_present("trim");
+    let empty_as_null = matches.is_present("empty-as-null");
+    par::for_stdin_entity(par_opts, move |e| {
+        let mut feature = e.geojson_feature();
+        feature.properties = Some(normalize(
+            e.geojson_properties(),
+            case,
+            trim,
+            empty_as_null,
+        )?);
+        Ok(vec![serde_json::to_string(&feature).unwrap()])
+    })
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("select", Some(m)) => select(m, par_opts),
+        ("set", Some(m)) => set(m, par_opts),
+        ("values", Some(m)) => values(m),
+        ("join", Some(m)) => join(m, par_opts),
+        ("normalize", Some(m)) => normalize_props(m, par_opts),
+        _ => Err(Error::UnknownCommand),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize, normalize_key, KeyCase};
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_key() {
+        for key in [
+            "Median HH Income",
+            "medianHHIncome",
+            "MEDIAN_HH_INCOME",
+            " median-hh income ",
+        ]
+        .iter()
+        {
+            assert_eq!("median_hh_income", normalize_key(key, KeyCase::Snake));
+        }
+        assert_eq!(
+            "medianHhIncome",
+            normalize_key("Median HH Income", KeyCase::Camel)
+        );
+        assert_eq!("pop-2020", normalize_key("Pop 2020", KeyCase::Kebab));
+        assert_eq!("pop2020", normalize_key("Pop2020", KeyCase::Snake));
+        assert_eq!("NAME", normalize_key("Name", KeyCase::Upper));
+    }
+
+    #[test]
+    fn test_normalize_values() {
+        let props = json!({"Name": "  Oak St ", "Note": " ", "Count": 3});
+        let props = props.as_object().unwrap().clone();
+        assert_eq!(
+            json!({"name": "Oak St", "note": null, "count": 3}),
+            serde_json::Value::Object(normalize(props, Some(KeyCase::Snake), true, true).unwrap())
+        );
+        let props = json!({"b_x": 1, "B X": 2});
+        let props = props.as_object().unwrap().clone();
+        assert!(normalize(props, Some(KeyCase::Snake), false, false).is_err());
+    }
+}
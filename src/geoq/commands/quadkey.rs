@@ -0,0 +1,39 @@
+use crate::geoq::{
+    commands::tile::{for_arg_or_line, read_zoom},
+    error::Error,
+    par::{self, ParOptions},
+    tile::Tile,
+};
+use clap::ArgMatches;
+
+fn encode(matches: &ArgMatches) -> Result<(), Error> {
+    for_arg_or_line(matches, "tile", |t| {
+        let tile: Tile = t.parse()?;
+        Ok(vec![tile.quadkey()])
+    })
+}
+
+fn decode(matches: &ArgMatches) -> Result<(), Error> {
+    for_arg_or_line(matches, "quadkey", |q| {
+        Ok(vec![Tile::from_quadkey(q)?.to_string()])
+    })
+}
+
+fn point(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let zoom = read_zoom(matches)?;
+    par::for_stdin_entity(par_opts, move |e| match e.geom() {
+        geo_types::Geometry::Point(p) => Ok(vec![Tile::for_point(&p, zoom).quadkey()]),
+        _ => Err(Error::InvalidInput(
+            "Input for 'geoq quadkey point' should be a Point geometry".to_string(),
+        )),
+    })
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("encode", Some(m)) => encode(m),
+        ("decode", Some(m)) => decode(m),
+        ("point", Some(m)) => point(m, par_opts),
+        _ => Err(Error::UnknownCommand),
+    }
+}
@@ -0,0 +1,64 @@
+use crate::geoq::{
+    error::Error,
+    output,
+    raster::{geotiff, polygonize, Raster},
+};
+use clap::ArgMatches;
+use geo::algorithm::map_coords::MapCoords;
+use geo_types::Geometry;
+use serde_json::{json, Map, Value};
+
+// Integral values (as in most categorical rasters) are written as integers
+pub fn value_json(v: f64) -> Value {
+    if v.fract() == 0.0 && v.abs() < 2f64.powi(53) {
+        json!(v as i64)
+    } else {
+        json!(v)
+    }
+}
+
+pub fn read_band(matches: &ArgMatches) -> Result<usize, Error> {
+    let band = matches.value_of("band").unwrap();
+    band.parse::<usize>()
+        .map_err(|_| Error::InvalidNumberFormat(format!("Expected band number: {}", band)))
+}
+
+// Sets the raster's nodata value from --nodata, if given, overriding the
+// file's own
+pub fn read_nodata(matches: &ArgMatches, raster: &mut Raster) -> Result<(), Error> {
+    if let Some(nodata) = matches.value_of("nodata") {
+        raster.nodata = Some(nodata.parse::<f64>().map_err(|_| {
+            Error::InvalidNumberFormat(format!("Expected numeric nodata value: {}", nodata))
+        })?);
+    }
+    Ok(())
+}
+
+fn polygonize(matches: &ArgMatches) -> Result<(), Error> {
+    let path = matches.value_of("path").unwrap();
+    let mut raster = geotiff::read(path, read_band(matches)?)?;
+    read_nodata(matches, &mut raster)?;
+    for (value, polygon) in polygonize::polygonize(&raster) {
+        let polygon = polygon.map_coords(|c| raster.to_lon_lat(c.x, c.y));
+        let mut properties = Map::new();
+        properties.insert("value".to_string(), value_json(value));
+        let feature = geojson::Feature {
+            bbox: None,
+            geometry: Some(geojson::Geometry::new(geojson::Value::from(
+                &Geometry::Polygon(polygon),
+            ))),
+            id: None,
+            properties: Some(properties),
+            foreign_members: None,
+        };
+        output::print(&serde_json::to_string(&feature).unwrap())?;
+    }
+    Ok(())
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("polygonize", Some(m)) => polygonize(m),
+        _ => Err(Error::UnknownCommand),
+    }
+}
@@ -1,5 +1,8 @@
-use crate::geoq::{error::Error, par};
+use crate::geoq::{
+    error::Error,
+    par::{self, ParOptions},
+};
 
-pub fn run() -> Result<(), Error> {
-    par::for_stdin_entity(|e| Ok(vec![format!("{}", e)]))
+pub fn run(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, |e| Ok(vec![format!("{}", e)]))
 }
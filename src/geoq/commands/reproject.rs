@@ -0,0 +1,36 @@
+// Converts features' coordinates between coordinate reference systems,
+// optionally densifying them first so that long segments keep their
+// shape
+use crate::geoq::{
+    crs,
+    crs::Crs,
+    error::Error,
+    par::{self, ParOptions},
+    units,
+};
+use clap::ArgMatches;
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let from: Crs = matches.value_of("from").unwrap().parse()?;
+    let to: Crs = matches.value_of("to").unwrap().parse()?;
+    let densify = matches
+        .value_of("densify")
+        .map(units::parse_length)
+        .transpose()?;
+    if densify.is_some_and(|d| d <= 0.0) {
+        return Err(Error::InvalidInput(
+            "--densify must be a positive distance".to_string(),
+        ));
+    }
+    par::for_stdin_entity(par_opts, move |e| {
+        let geom = crs::transform(&e.geom(), from, to, densify);
+        let feature = geojson::Feature {
+            bbox: None,
+            geometry: Some(geojson::Geometry::new(geojson::Value::from(&geom))),
+            id: e.id(),
+            properties: Some(e.geojson_properties()),
+            foreign_members: e.foreign_members(),
+        };
+        Ok(vec![serde_json::to_string(&feature)?])
+    })
+}
@@ -0,0 +1,115 @@
+use crate::geoq::{
+    bbox, centroid,
+    commands::geocode,
+    contains,
+    entity::Entity,
+    error::Error,
+    other,
+    par::{self, ParOptions},
+    reader, report,
+};
+use clap::ArgMatches;
+use geo::algorithm::intersects::Intersects;
+use geo_types::{Geometry, Point, Rect};
+use serde_json::{Map, Value};
+
+// Inputs are looked up by their centroid, so lines and polygons
+// get the place at their middle
+fn lookup_point(e: &Entity) -> Option<Point<f64>> {
+    match e.geom() {
+        Geometry::Point(p) => Some(p),
+        geom => centroid::centroid(&geom),
+    }
+}
+
+fn output(e: &Entity, properties: Map<String, Value>) -> String {
+    let mut feature = e.geojson_feature();
+    let mut props = e.geojson_properties();
+    props.extend(properties);
+    feature.properties = Some(props);
+    serde_json::to_string(&feature).unwrap()
+}
+
+fn read_zoom(matches: &ArgMatches) -> Result<u8, Error> {
+    let zoom = matches.value_of("zoom").unwrap();
+    match zoom.parse::<u8>() {
+        Ok(z) if z <= 18 => Ok(z),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected Nominatim zoom between 0 and 18: {}",
+            zoom
+        ))),
+    }
+}
+
+fn nominatim(matches: &ArgMatches) -> Result<(), Error> {
+    let mut client = geocode::client(matches)?;
+    let zoom = read_zoom(matches)?;
+    reader::for_entity_lines(|e| {
+        let place = match lookup_point(&e) {
+            Some(p) => client.reverse(p.x(), p.y(), zoom)?,
+            None => None,
+        };
+        match place {
+            Some(place) => Ok(vec![output(&e, place.properties)]),
+            None => {
+                report::warning("No reverse geocoding result for", &e.raw());
+                Ok(vec![output(&e, Map::new())])
+            }
+        }
+    })
+}
+
+struct Boundary {
+    bbox: Rect<f64>,
+    geom: Geometry<f64>,
+    properties: Map<String, Value>,
+}
+
+fn read_boundaries(path: &str, fields: &Option<Vec<String>>) -> Result<Vec<Boundary>, Error> {
+    let mut boundaries = vec![];
+    other::for_entity(path, |e| {
+        let geom = e.geom();
+        if !matches!(geom, Geometry::Polygon(_) | Geometry::MultiPolygon(_)) {
+            return Ok(());
+        }
+        let mut properties = e.geojson_properties();
+        if let Some(fields) = fields {
+            properties.retain(|k, _| fields.contains(k));
+        }
+        boundaries.push(Boundary {
+            bbox: bbox::bbox(&geom),
+            geom,
+            properties,
+        });
+        Ok(())
+    })?;
+    Ok(boundaries)
+}
+
+// Properties of every boundary containing the point, in file order,
+// so later (e.g. smaller, more detailed) boundaries take precedence
+fn offline(matches: &ArgMatches, path: &str, par_opts: &ParOptions) -> Result<(), Error> {
+    let fields: Option<Vec<String>> = matches
+        .value_of("fields")
+        .map(|f| f.split(',').map(|k| k.trim().to_string()).collect());
+    let boundaries = read_boundaries(path, &fields)?;
+    par::for_stdin_entity(par_opts, move |e| {
+        let mut properties = Map::new();
+        if let Some(p) = lookup_point(&e) {
+            let point = Geometry::Point(p);
+            for b in boundaries.iter() {
+                if b.bbox.intersects(&p) && contains::contains_any(&b.geom, &point) {
+                    properties.extend(b.properties.clone());
+                }
+            }
+        }
+        Ok(vec![output(&e, properties)])
+    })
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    match matches.value_of("boundaries") {
+        Some(path) => offline(matches, path, par_opts),
+        None => nominatim(matches),
+    }
+}
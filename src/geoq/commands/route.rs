@@ -0,0 +1,63 @@
+use crate::geoq::{
+    entity::Entity,
+    error::Error,
+    net, reader, report,
+    routing::{Client, Engine},
+};
+use clap::ArgMatches;
+use geo_types::{Coord, Geometry};
+use serde_json::json;
+
+// Waypoints of an input geometry: the vertices of a LineString or the
+// points of a MultiPoint, in order
+fn waypoints(geom: &Geometry<f64>) -> Option<Vec<Coord<f64>>> {
+    let coords: Vec<Coord<f64>> = match geom {
+        Geometry::Line(l) => vec![l.start, l.end],
+        Geometry::LineString(ls) => ls.0.clone(),
+        Geometry::MultiPoint(mp) => mp.iter().map(|p| p.0).collect(),
+        _ => return None,
+    };
+    if coords.len() < 2 {
+        None
+    } else {
+        Some(coords)
+    }
+}
+
+async fn route(client: &Client, e: Entity) -> Result<Vec<String>, Error> {
+    let waypoints = waypoints(&e.geom()).ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "Expected LineString or MultiPoint with at least 2 points to route between: {}",
+            e.raw()
+        ))
+    })?;
+    match client.route(&waypoints).await? {
+        None => {
+            report::warning("No route found for", &e.raw());
+            Ok(vec![])
+        }
+        Some(route) => {
+            let mut feature = e.geojson_feature();
+            let mut properties = e.geojson_properties();
+            properties.insert("duration".to_string(), json!(route.duration));
+            properties.insert("distance".to_string(), json!(route.distance));
+            feature.properties = Some(properties);
+            feature.geometry = Some(geojson::Geometry::new(geojson::Value::from(
+                &Geometry::LineString(route.geometry),
+            )));
+            Ok(vec![serde_json::to_string(&feature).unwrap()])
+        }
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let engine: Engine = matches.value_of("engine").unwrap().parse()?;
+    let client = Client::new(
+        engine,
+        matches.value_of("endpoint").unwrap(),
+        matches.value_of("profile").unwrap(),
+    );
+    reader::for_entity_batches(net::concurrency(), |entities| {
+        net::batch(entities, |e| route(&client, e))
+    })
+}
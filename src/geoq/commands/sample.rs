@@ -0,0 +1,43 @@
+use crate::geoq::{error::Error, reader};
+use clap::ArgMatches;
+use rand::{rngs::StdRng, FromEntropy, Rng, SeedableRng};
+
+fn parse_fraction(matches: &ArgMatches) -> Result<f64, Error> {
+    let raw = matches.value_of("fraction").unwrap();
+    match raw.parse::<f64>() {
+        Ok(f) if (0.0..=1.0).contains(&f) => Ok(f),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Invalid fraction: {} -- must be a number between 0 and 1",
+            raw
+        ))),
+    }
+}
+
+fn parse_seed(matches: &ArgMatches) -> Result<Option<u64>, Error> {
+    match matches.value_of("seed") {
+        None => Ok(None),
+        Some(raw) => raw.parse::<u64>().map(Some).map_err(|_| {
+            Error::InvalidNumberFormat(format!(
+                "Invalid seed: {} -- must be a non-negative integer",
+                raw
+            ))
+        }),
+    }
+}
+
+// Each feature is kept independently with probability --fraction,
+// so the output size is approximate but input is streamed.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let fraction = parse_fraction(matches)?;
+    let mut rng = match parse_seed(matches)? {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    reader::for_entity_lines(|e| {
+        if rng.gen_bool(fraction) {
+            Ok(vec![e.into_raw()])
+        } else {
+            Ok(vec![])
+        }
+    })
+}
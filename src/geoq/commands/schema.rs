@@ -0,0 +1,117 @@
+// Summarizes the properties of a stream of features: the types each key
+// holds, how often it's null or missing, how many distinct values it has
+// and a few examples, for choosing column types before exporting
+use crate::geoq::{error::Error, par, reader};
+use clap::ArgMatches;
+use serde_json::{json, Map, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+// Types as they'd be stored in a column, so that integers are told apart
+// from other numbers
+fn type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[derive(Default)]
+struct Column {
+    types: HashMap<&'static str, usize>,
+    nulls: usize,
+    // Hashes of the non-null values, rather than the values, to count them
+    // without keeping every distinct string
+    distinct: HashSet<u64>,
+    examples: Vec<Value>,
+}
+
+impl Column {
+    fn add(&mut self, v: &Value, max_examples: usize) {
+        *self.types.entry(type_name(v)).or_insert(0) += 1;
+        if v.is_null() {
+            self.nulls += 1;
+            return;
+        }
+        let text = v.to_string();
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        if self.distinct.insert(hasher.finish()) && self.examples.len() < max_examples {
+            self.examples.push(v.clone());
+        }
+    }
+
+    fn summary(&self, key: &str, features: usize) -> Value {
+        let present: usize = self.types.values().sum();
+        let missing = features - present;
+        let types: Map<String, Value> = self
+            .types
+            .iter()
+            .filter(|(t, _)| **t != "null")
+            .map(|(t, n)| (t.to_string(), json!(n)))
+            .collect();
+        json!({
+            "key": key,
+            "types": types,
+            "nulls": self.nulls,
+            "missing": missing,
+            "null_rate": (self.nulls + missing) as f64 / features as f64,
+            "distinct": self.distinct.len(),
+            "examples": self.examples,
+        })
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let max_examples = par::parse_count("examples", matches.value_of("examples").unwrap())?;
+
+    // Keys in the order they're first seen
+    let mut keys: Vec<String> = vec![];
+    let mut columns: HashMap<String, Column> = HashMap::new();
+    let mut features = 0;
+    reader::for_entity(|e| {
+        features += 1;
+        for (k, v) in e.geojson_properties() {
+            if !columns.contains_key(&k) {
+                keys.push(k.clone());
+            }
+            columns.entry(k).or_default().add(&v, max_examples);
+        }
+        Ok(())
+    })?;
+
+    for k in &keys {
+        println!("{}", columns[k].summary(k, features));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Column;
+    use serde_json::json;
+
+    #[test]
+    fn test_summary() {
+        let mut column = Column::default();
+        for v in &[json!(1), json!(2.5), json!(1), json!(null), json!("x")] {
+            column.add(v, 2);
+        }
+        let summary = column.summary("k", 6);
+        assert_eq!(
+            json!({"integer": 2, "number": 1, "string": 1}),
+            summary["types"]
+        );
+        assert_eq!(1, summary["nulls"]);
+        assert_eq!(1, summary["missing"]);
+        assert_eq!(json!(2.0 / 6.0), summary["null_rate"]);
+        assert_eq!(3, summary["distinct"]);
+        assert_eq!(json!([1, 2.5]), summary["examples"]);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::geoq::{
+    commands::tiles::read_buffer,
+    entity,
+    error::Error,
+    input::Input,
+    tile::Tile,
+    tiles::{self, Options},
+};
+use clap::ArgMatches;
+use flatgeobuf::FgbReader;
+use geozero::geojson::GeoJsonWriter;
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Connections are dropped if a request doesn't arrive in this long, so
+// idle clients don't hold on to a thread
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: &'static str, body: &str) -> Response {
+        Response {
+            status,
+            content_type: "text/plain",
+            body: format!("{}\n", body).into_bytes(),
+        }
+    }
+}
+
+// Features from the FlatGeobuf file whose bboxes intersect the tile,
+// found via the file's spatial index
+fn query_tile(path: &Path, t: Tile, opts: &Options) -> Result<tiles::Source, Error> {
+    let bounds = tiles::buffered_lon_lat_bounds(t, opts);
+    let mut file = BufReader::new(File::open(path)?);
+    let mut fgb = FgbReader::open(&mut file)?;
+    fgb.select_bbox(
+        bounds.min().x,
+        bounds.min().y,
+        bounds.max().x,
+        bounds.max().y,
+    )?;
+    let mut json_data: Vec<u8> = Vec::new();
+    let mut json = GeoJsonWriter::new(&mut json_data);
+    fgb.process_features(&mut json)?;
+
+    let mut source = tiles::Source::new();
+    let json = String::from_utf8_lossy(&json_data).to_string();
+    for e in entity::from_input(Input::GeoJSON(json))? {
+        source.add(&e);
+    }
+    Ok(source)
+}
+
+fn tile_response(path: &Path, t: Tile, opts: &Options) -> Result<Response, Error> {
+    let source = query_tile(path, t, opts)?;
+    match tiles::build_tile(&source, t, opts) {
+        Some(data) => Ok(Response {
+            status: "200 OK",
+            content_type: "application/vnd.mapbox-vector-tile",
+            body: data,
+        }),
+        None => Ok(Response {
+            status: "204 No Content",
+            content_type: "application/vnd.mapbox-vector-tile",
+            body: vec![],
+        }),
+    }
+}
+
+fn tilejson(host: &str, opts: &Options) -> Response {
+    let body = json!({
+        "tilejson": "3.0.0",
+        "tiles": [format!("http://{}/{{z}}/{{x}}/{{y}}.mvt", host)],
+        "vector_layers": [{ "id": opts.layer, "fields": {} }],
+    });
+    Response {
+        status: "200 OK",
+        content_type: "application/json",
+        body: body.to_string().into_bytes(),
+    }
+}
+
+fn route(path: &Path, target: &str, host: &str, opts: &Options) -> Response {
+    if target == "/tiles.json" {
+        return tilejson(host, opts);
+    }
+    let t = match target
+        .strip_prefix('/')
+        .and_then(|t| t.strip_suffix(".mvt").or_else(|| t.strip_suffix(".pbf")))
+    {
+        Some(t) => t,
+        None => return Response::text("404 Not Found", "Not found"),
+    };
+    match t.parse::<Tile>() {
+        Err(_) => Response::text("404 Not Found", "Not found"),
+        Ok(t) => match tile_response(path, t, opts) {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("Error building tile {}: {:?}", t, e);
+                Response::text("500 Internal Server Error", &format!("{:?}", e))
+            }
+        },
+    }
+}
+
+// The host is the request's Host header, as the client reached the server,
+// or else the address it's listening on
+fn handle(mut stream: TcpStream, path: &Path, bound: &str, opts: &Options) -> Result<(), Error> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut host = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+    }
+    let host = host.as_deref().unwrap_or(bound);
+
+    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    let response = match parts.as_slice() {
+        ["GET", target, _] => route(path, target, host, opts),
+        _ => Response::text("405 Method Not Allowed", "Only GET is supported"),
+    };
+    eprintln!("{} {}", request_line.trim(), response.status);
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn fgb(matches: &ArgMatches) -> Result<(), Error> {
+    let path: PathBuf = PathBuf::from(matches.value_of("path").unwrap());
+    // Fail at startup, rather than on every request, for unreadable files
+    // or files without a spatial index
+    let mut file = BufReader::new(File::open(&path)?);
+    let header_ok = FgbReader::open(&mut file)?.header().index_node_size() > 0;
+    if !header_ok {
+        return Err(Error::InvalidInput(format!(
+            "{} has no spatial index -- rewrite it with 'geoq fgb write'",
+            path.display()
+        )));
+    }
+
+    let mut opts = Options::new(0, 0, matches.value_of("layer").unwrap());
+    opts.buffer = read_buffer(matches)?;
+
+    let listener = TcpListener::bind(format!(
+        "{}:{}",
+        matches.value_of("host").unwrap(),
+        matches.value_of("port").unwrap()
+    ))?;
+    // As bound, with the port chosen for --port 0
+    let host = listener.local_addr()?.to_string();
+    let opts = Arc::new(opts);
+    let path = Arc::new(path);
+    let host = Arc::new(host);
+    eprintln!("Serving tiles at http://{}/{{z}}/{{x}}/{{y}}.mvt", host);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let (path, host, opts) = (path.clone(), host.clone(), opts.clone());
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &path, &host, &opts) {
+                eprintln!("Error handling request: {:?}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("fgb", Some(m)) => fgb(m),
+        _ => Err(Error::UnknownCommand),
+    }
+}
@@ -1,45 +1,65 @@
-use crate::geoq::{coord_count, error::Error, par, simplify};
+use crate::geoq::{
+    coord_count,
+    entity::Entity,
+    error::Error,
+    par::{self, ParOptions},
+    simplify,
+};
 use clap::ArgMatches;
 use std::str::FromStr;
 
 const MAX_ITERS: i32 = 20;
 
-fn simplify(epsilon: f64, coords_target: Option<usize>) -> Result<(), Error> {
-    par::for_stdin_entity(move |e| {
-        let props = e.geojson_properties();
-        let geom = e.geom();
-        let simplified = match coords_target {
-            None => simplify::simplify(geom, epsilon),
-            Some(target) => {
-                if coord_count::coord_count(&geom) <= target {
-                    geom
-                } else {
-                    let mut eps = epsilon;
-                    let mut simp = geom;
-                    let mut iters = 0;
-                    while coord_count::coord_count(&simp) > target && iters < MAX_ITERS {
-                        simp = simplify::simplify(simp, eps);
-                        eps = eps * 2.0;
-                        iters += 1;
-                    }
-                    simp
+// The entity as a Feature with its geometry simplified
+pub fn simplify_feature(
+    e: &Entity,
+    epsilon: f64,
+    coords_target: Option<usize>,
+) -> geojson::Feature {
+    let props = e.geojson_properties();
+    let geom = e.geom();
+    let simplified = match coords_target {
+        None => simplify::simplify(geom, epsilon),
+        Some(target) => {
+            if coord_count::coord_count(&geom) <= target {
+                geom
+            } else {
+                let mut eps = epsilon;
+                let mut simp = geom;
+                let mut iters = 0;
+                while coord_count::coord_count(&simp) > target && iters < MAX_ITERS {
+                    simp = simplify::simplify(simp, eps);
+                    eps *= 2.0;
+                    iters += 1;
                 }
+                simp
             }
-        };
+        }
+    };
 
-        let gj_geom = geojson::Geometry::new(geojson::Value::from(&simplified));
-        let feature = geojson::Feature {
-            bbox: None,
-            geometry: Some(gj_geom),
-            id: None,
-            properties: Some(props),
-            foreign_members: None,
-        };
+    let gj_geom = geojson::Geometry::new(geojson::Value::from(&simplified));
+    geojson::Feature {
+        bbox: None,
+        geometry: Some(gj_geom),
+        id: None,
+        properties: Some(props),
+        foreign_members: e.foreign_members(),
+    }
+}
+
+fn simplify(
+    epsilon: f64,
+    coords_target: Option<usize>,
+    par_opts: &ParOptions,
+) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, move |e| {
+        let feature = simplify_feature(&e, epsilon, coords_target);
         Ok(vec![serde_json::to_string(&feature).unwrap()])
     })
 }
 
-pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+// The epsilon and any --to-coord-count target
+pub fn read_args(matches: &ArgMatches) -> Result<(f64, Option<usize>), Error> {
     let eps = match matches.value_of("epsilon") {
         Some(arg) => match f64::from_str(arg) {
             Ok(eps) => Ok(eps),
@@ -70,5 +90,10 @@ pub fn run(matches: &ArgMatches) -> Result<(), Error> {
         _ => None,
     };
 
-    eps.and_then(|eps| simplify(eps, target))
+    eps.map(|eps| (eps, target))
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let (eps, target) = read_args(matches)?;
+    simplify(eps, target, par_opts)
 }
@@ -0,0 +1,12 @@
+use crate::geoq::{commands::head::parse_count, error::Error, output, reader};
+use clap::ArgMatches;
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let count = parse_count(matches, "count")?;
+    reader::entities(|entities| {
+        for e in entities.skip(count) {
+            output::print(&e?.into_raw())?;
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,56 @@
+// Moves points onto the nearest line of another dataset, such as GPS
+// fixes onto a road network
+use crate::geoq::{
+    bbox::{self, Margin},
+    error::Error,
+    other::Other,
+    par::{self, ParOptions},
+    snap, units,
+};
+use clap::ArgMatches;
+use geo_types::{Geometry, Rect};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let max_distance = units::parse_length(matches.value_of("max-distance").unwrap())?;
+    let other = Arc::new(Other::read(matches.value_of("other").unwrap())?);
+    par::for_stdin_entity(par_opts, move |e| {
+        let p = match e.geom() {
+            Geometry::Point(p) => p.0,
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "Only points can be snapped: {}",
+                    e.raw()
+                )))
+            }
+        };
+        let search = bbox::expand(&Rect::new(p, p), Margin::Meters(max_distance));
+        let mut best: Option<(geo_types::Coord<f64>, f64, usize)> = None;
+        for i in search.map(|r| other.near(r)).unwrap_or_default() {
+            if let Some((c, d)) = snap::nearest(p, &other.get(i)?.geom()) {
+                if d <= max_distance && best.is_none_or(|(_, best, _)| d < best) {
+                    best = Some((c, d, i));
+                }
+            }
+        }
+
+        let mut feature = e.geojson_feature();
+        let mut props = e.geojson_properties();
+        match best {
+            Some((c, d, i)) => {
+                let geom = Geometry::Point(c.into());
+                feature.geometry = Some(geojson::Geometry::new(geojson::Value::from(&geom)));
+                props.insert("snap_distance".to_string(), json!(units::from_meters(d)));
+                let id = other.get(i)?.id().map_or(Value::Null, |id| json!(id));
+                props.insert("snap_id".to_string(), id);
+            }
+            None => {
+                props.insert("snap_distance".to_string(), Value::Null);
+                props.insert("snap_id".to_string(), Value::Null);
+            }
+        }
+        feature.properties = Some(props);
+        Ok(vec![serde_json::to_string(&feature)?])
+    })
+}
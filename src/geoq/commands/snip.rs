@@ -1,4 +1,4 @@
-use crate::geoq::{browser_open, error::Error, reader::Reader};
+use crate::geoq::{browser_open, error::Error, net, reader::Reader};
 use geojson::GeoJson;
 use std::io;
 
@@ -27,13 +27,13 @@ pub fn run() -> Result<(), Error> {
 
     if fc_json.len() < SNIP_LIMIT {
         let client = reqwest::Client::builder()
-            .redirect(reqwest::RedirectPolicy::none())
+            .redirect(reqwest::redirect::Policy::none())
             .build()?;
-        let resp = client
+        let request = client
             .post("https://contour.app/scratchpad")
             .header(reqwest::header::CONTENT_TYPE, "application/json")
-            .body(fc_json)
-            .send()?;
+            .body(fc_json);
+        let resp = net::block_on(net::send(request))?;
         if let Some(loc) = resp.headers().get(reqwest::header::LOCATION) {
             let url = loc.to_str().unwrap();
             eprintln!("Opening Contour Scratchpad:\n{}", url);
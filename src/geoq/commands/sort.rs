@@ -0,0 +1,278 @@
+use crate::geoq::{
+    area, entity::Entity, error::Error, fgb::hilbert::BBox, length, output, reader::Reader,
+};
+use clap::ArgMatches;
+use serde_json::{json, Value};
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+};
+
+const DEFAULT_CHUNK_SIZE: usize = 100_000;
+
+enum By {
+    Hilbert,
+    Area,
+    Length,
+    Property(String),
+}
+
+struct Record {
+    key: Value,
+    seq: u64,
+    raw: String,
+}
+
+fn parse_by(matches: &ArgMatches) -> Result<By, Error> {
+    match matches.value_of("by").unwrap_or("hilbert") {
+        "hilbert" => Ok(By::Hilbert),
+        "area" => Ok(By::Area),
+        "length" => Ok(By::Length),
+        by if by.starts_with("property:") && by.len() > "property:".len() => {
+            Ok(By::Property(by["property:".len()..].to_string()))
+        }
+        by => Err(Error::InvalidInput(format!(
+            "Invalid sort key: {} -- expected hilbert, area, length, or property:<name>",
+            by
+        ))),
+    }
+}
+
+fn entity_bbox(e: &Entity) -> BBox {
+    let rect = e.bbox();
+    BBox {
+        min_x: rect.min().x,
+        min_y: rect.min().y,
+        max_x: rect.max().x,
+        max_y: rect.max().y,
+    }
+}
+
+fn sort_key(by: &By, extent: &Option<BBox>, e: &Entity) -> Value {
+    match by {
+        By::Hilbert => match extent {
+            Some(extent) => json!(entity_bbox(e).hilbert_bbox(extent)),
+            None => Value::Null,
+        },
+        By::Area => json!(area::area(&e.geom())),
+        By::Length => json!(length::length(&e.geom())),
+        By::Property(name) => e
+            .geojson_properties()
+            .get(name)
+            .cloned()
+            .unwrap_or(Value::Null),
+    }
+}
+
+// Numbers sort before strings, and booleans after them.
+// Missing, null, and non-scalar values sort last.
+fn key_rank(v: &Value) -> u8 {
+    match v {
+        Value::Number(_) => 0,
+        Value::String(_) => 1,
+        Value::Bool(_) => 2,
+        _ => 3,
+    }
+}
+
+fn compare_keys(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => key_rank(a).cmp(&key_rank(b)),
+    }
+}
+
+// Reversing only reverses values of the same kind, so missing values
+// still sort last. Ties keep their input order, even when reversed
+fn compare(a: &Record, b: &Record, reverse: bool) -> Ordering {
+    let ord = compare_keys(&a.key, &b.key);
+    let ord = if reverse { ord.reverse() } else { ord };
+    key_rank(&a.key)
+        .cmp(&key_rank(&b.key))
+        .then(ord)
+        .then(a.seq.cmp(&b.seq))
+}
+
+fn write_run(chunk: &mut Vec<Record>, reverse: bool) -> Result<BufReader<File>, Error> {
+    chunk.sort_by(|a, b| compare(a, b, reverse));
+    let mut file = tempfile::tempfile()?;
+    {
+        let mut w = BufWriter::new(&mut file);
+        for r in chunk.drain(..) {
+            writeln!(w, "{}", json!([r.key, r.seq, r.raw]))?;
+        }
+        w.flush()?;
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok(BufReader::new(file))
+}
+
+fn read_record(run: &mut BufReader<File>) -> Result<Option<Record>, Error> {
+    let mut line = String::new();
+    if run.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    match serde_json::from_str::<Value>(&line)? {
+        Value::Array(mut fields) if fields.len() == 3 => {
+            let raw = fields.pop().unwrap();
+            let seq = fields.pop().unwrap();
+            let key = fields.pop().unwrap();
+            Ok(Some(Record {
+                key,
+                seq: seq.as_u64().unwrap_or(0),
+                raw: raw.as_str().unwrap_or("").to_string(),
+            }))
+        }
+        _ => Err(Error::ProgramError(
+            "Invalid record in sort temp file".to_string(),
+        )),
+    }
+}
+
+struct HeapItem {
+    record: Record,
+    run: usize,
+    reverse: bool,
+}
+
+impl PartialEq for HeapItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapItem {}
+
+impl PartialOrd for HeapItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapItem {
+    // BinaryHeap is a max-heap, so invert to pop the smallest record first
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(&other.record, &self.record, self.reverse)
+    }
+}
+
+fn merge_runs(mut runs: Vec<BufReader<File>>, reverse: bool) -> Result<(), Error> {
+    let mut heap = BinaryHeap::new();
+    for (i, run) in runs.iter_mut().enumerate() {
+        if let Some(record) = read_record(run)? {
+            heap.push(HeapItem {
+                record,
+                run: i,
+                reverse,
+            });
+        }
+    }
+    while let Some(item) = heap.pop() {
+        output::print(&item.record.raw)?;
+        if let Some(record) = read_record(&mut runs[item.run])? {
+            heap.push(HeapItem {
+                record,
+                run: item.run,
+                reverse,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn sort_entities(
+    entities: &mut dyn Iterator<Item = Result<Entity, Error>>,
+    by: &By,
+    extent: &Option<BBox>,
+    chunk_size: usize,
+    reverse: bool,
+) -> Result<(), Error> {
+    let mut chunk: Vec<Record> = Vec::new();
+    let mut runs: Vec<BufReader<File>> = Vec::new();
+    for (seq, e) in entities.enumerate() {
+        let e = e?;
+        chunk.push(Record {
+            key: sort_key(by, extent, &e),
+            seq: seq as u64,
+            raw: e.raw(),
+        });
+        if chunk.len() >= chunk_size {
+            runs.push(write_run(&mut chunk, reverse)?);
+        }
+    }
+
+    if runs.is_empty() {
+        chunk.sort_by(|a, b| compare(a, b, reverse));
+        for r in chunk {
+            output::print(&r.raw)?;
+        }
+        Ok(())
+    } else {
+        if !chunk.is_empty() {
+            runs.push(write_run(&mut chunk, reverse)?);
+        }
+        merge_runs(runs, reverse)
+    }
+}
+
+// Hilbert keys are relative to the extent of the whole dataset,
+// so the input is spooled to a temp file while the extent is computed,
+// then read again to generate keys.
+fn spool_with_extent(
+    entities: &mut dyn Iterator<Item = Result<Entity, Error>>,
+) -> Result<(BufReader<File>, Option<BBox>), Error> {
+    let mut file = tempfile::tempfile()?;
+    let mut extent: Option<BBox> = None;
+    {
+        let mut w = BufWriter::new(&mut file);
+        for e in entities {
+            let e = e?;
+            let bbox = entity_bbox(&e);
+            match extent {
+                Some(ref mut ext) => ext.expand(&bbox),
+                None => extent = Some(bbox),
+            }
+            writeln!(w, "{}", e.raw())?;
+        }
+        w.flush()?;
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok((BufReader::new(file), extent))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let by = parse_by(matches)?;
+    let reverse = matches.is_present("reverse");
+    let chunk_size = match matches.value_of("chunk-size") {
+        None => DEFAULT_CHUNK_SIZE,
+        Some(c) => match c.parse::<usize>() {
+            Ok(c) if c > 0 => c,
+            _ => {
+                return Err(Error::InvalidNumberFormat(format!(
+                    "Invalid chunk size: {} -- must be a positive integer",
+                    c
+                )))
+            }
+        },
+    };
+
+    let stdin = io::stdin();
+    let mut stdin_reader = stdin.lock();
+    let mut reader = Reader::new(&mut stdin_reader);
+
+    match by {
+        By::Hilbert => {
+            let (mut spooled, extent) = spool_with_extent(&mut reader)?;
+            let mut spooled_reader = Reader::new(&mut spooled);
+            sort_entities(&mut spooled_reader, &by, &extent, chunk_size, reverse)
+        }
+        _ => sort_entities(&mut reader, &by, &None, chunk_size, reverse),
+    }
+}
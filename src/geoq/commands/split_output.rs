@@ -0,0 +1,162 @@
+// Splits a stream of features into numbered files of at most a number of
+// features or bytes each, for loading into systems with per-file limits
+use crate::geoq::{error::Error, par, reader};
+use clap::ArgMatches;
+use serde_json::json;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// A file name template with one printf-style number, such as
+// part-%04d.geojsonl
+struct Template {
+    prefix: String,
+    width: usize,
+    suffix: String,
+}
+
+impl Template {
+    fn parse(s: &str) -> Result<Template, Error> {
+        let invalid = || {
+            Error::InvalidInput(format!(
+                "Expected a file name with one %d or %0Nd for the part number, e.g. part-%04d.geojsonl: {}",
+                s
+            ))
+        };
+        let start = s.find('%').ok_or_else(invalid)?;
+        let rest = &s[start + 1..];
+        let end = rest.find('d').ok_or_else(invalid)?;
+        let spec = &rest[..end];
+        let width = match spec {
+            "" => 0,
+            spec if spec.starts_with('0') => spec.parse().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        };
+        let suffix = &rest[end + 1..];
+        if suffix.contains('%') {
+            return Err(invalid());
+        }
+        Ok(Template {
+            prefix: s[..start].to_string(),
+            width,
+            suffix: suffix.to_string(),
+        })
+    }
+
+    fn path(&self, n: usize) -> PathBuf {
+        PathBuf::from(format!(
+            "{}{:0width$}{}",
+            self.prefix,
+            n,
+            self.suffix,
+            width = self.width
+        ))
+    }
+}
+
+struct Part {
+    path: PathBuf,
+    out: BufWriter<File>,
+    features: usize,
+    bytes: usize,
+}
+
+impl Part {
+    fn create(path: PathBuf) -> Result<Part, Error> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(Part {
+            out: BufWriter::new(File::create(&path)?),
+            path,
+            features: 0,
+            bytes: 0,
+        })
+    }
+
+    fn finish(mut self) -> Result<serde_json::Value, Error> {
+        self.out.flush()?;
+        println!("{}", self.path.display());
+        Ok(json!({
+            "path": self.path.display().to_string(),
+            "features": self.features,
+            "bytes": self.bytes,
+        }))
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let max_features = matches
+        .value_of("max-features")
+        .map(|n| par::parse_count("features per file", n))
+        .transpose()?;
+    let max_bytes = matches
+        .value_of("max-bytes")
+        .map(par::parse_bytes)
+        .transpose()?;
+    let template = Template::parse(matches.value_of("out").unwrap())?;
+    let manifest_path = match matches.value_of("manifest") {
+        Some(path) => PathBuf::from(path),
+        None => template
+            .path(0)
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join("manifest.json"),
+    };
+
+    let mut parts = vec![];
+    let mut current: Option<Part> = None;
+    reader::for_entity(|e| {
+        let line = e.into_raw();
+        let len = line.len() + 1;
+        // Parts are only ever cut between features, so a part holds at
+        // least one even if that feature alone is over --max-bytes
+        let full = current.as_ref().is_some_and(|p| {
+            max_features.is_some_and(|max| p.features >= max)
+                || max_bytes.is_some_and(|max| p.bytes + len > max)
+        });
+        if full {
+            parts.push(current.take().unwrap().finish()?);
+        }
+        let part = match current.as_mut() {
+            Some(part) => part,
+            None => current.insert(Part::create(template.path(parts.len() + 1))?),
+        };
+        writeln!(part.out, "{}", line)?;
+        part.features += 1;
+        part.bytes += len;
+        Ok(())
+    })?;
+    if let Some(part) = current {
+        parts.push(part.finish()?);
+    }
+
+    let manifest = json!({
+        "features": parts.iter().map(|p| p["features"].as_u64().unwrap()).sum::<u64>(),
+        "parts": parts,
+    });
+    fs::write(
+        &manifest_path,
+        format!("{}\n", serde_json::to_string_pretty(&manifest)?),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_template() {
+        let t = Template::parse("out/part-%04d.geojsonl").unwrap();
+        assert_eq!(PathBuf::from("out/part-0012.geojsonl"), t.path(12));
+        assert_eq!(
+            PathBuf::from("p12.json"),
+            Template::parse("p%d.json").unwrap().path(12)
+        );
+        assert!(Template::parse("part.geojsonl").is_err());
+        assert!(Template::parse("part-%4d.geojsonl").is_err());
+        assert!(Template::parse("%d-%d").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+// Colors features by a property with simplestyle-spec properties, so they
+// show up styled in geojson.io (geoq map) and other GeoJSON viewers
+use crate::geoq::{
+    entity::Entity,
+    error::Error,
+    lookup, output, reader,
+    style::{self, Palette},
+};
+use clap::ArgMatches;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+// Colors for each feature's value, or None for features without one.
+// Numbers are placed along the palette between the smallest and largest,
+// and other values each get a color of their own, in sorted order.
+fn colors(values: &[Value], palette: &Palette, numeric: bool) -> Vec<Option<String>> {
+    if numeric {
+        let numbers: Vec<f64> = values.iter().filter_map(|v| v.as_f64()).collect();
+        let min = numbers.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = numbers.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        values
+            .iter()
+            .map(|v| {
+                v.as_f64().map(|n| {
+                    let t = if max > min {
+                        (n - min) / (max - min)
+                    } else {
+                        0.0
+                    };
+                    palette.interpolate(t)
+                })
+            })
+            .collect()
+    } else {
+        let text = |v: &Value| lookup::key_text(v).unwrap_or_else(|| v.to_string());
+        let categories: Vec<String> = values
+            .iter()
+            .filter(|v| !v.is_null())
+            .map(text)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        values
+            .iter()
+            .map(|v| match v {
+                Value::Null => None,
+                v => {
+                    let i = categories.binary_search(&text(v)).unwrap();
+                    Some(palette.category(i, categories.len()))
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let property = matches.value_of("color-by").unwrap();
+    let palette: Option<Palette> = matches.value_of("palette").map(str::parse).transpose()?;
+    let mut entities: Vec<Entity> = vec![];
+    let mut values = vec![];
+    reader::for_entity(|e| {
+        values.push(
+            e.geojson_properties()
+                .remove(property)
+                .unwrap_or(Value::Null),
+        );
+        entities.push(e);
+        Ok(())
+    })?;
+
+    let numeric = !matches.is_present("categorical")
+        && values.iter().any(|v| !v.is_null())
+        && values.iter().all(|v| v.is_null() || v.is_number());
+    let palette: Palette = match palette {
+        Some(p) => p,
+        None if numeric => "viridis".parse()?,
+        None => "tableau10".parse()?,
+    };
+
+    for (e, color) in entities.iter().zip(colors(&values, &palette, numeric)) {
+        let mut feature = e.geojson_feature();
+        if let Some(color) = color {
+            let mut props = e.geojson_properties();
+            style::set_color(&e.geom(), &color, &mut props);
+            feature.properties = Some(props);
+        }
+        output::print(&serde_json::to_string(&feature)?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use crate::geoq::{commands::head::parse_count, error::Error, output, reader};
+use clap::ArgMatches;
+use std::collections::VecDeque;
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let count = parse_count(matches, "count")?;
+    // Grows as entities arrive rather than up front, since --count can be
+    // far larger than the input
+    let mut last: VecDeque<String> = VecDeque::new();
+    reader::for_entity(|e| {
+        if count > 0 {
+            if last.len() == count {
+                last.pop_front();
+            }
+            last.push_back(e.raw());
+        }
+        Ok(())
+    })?;
+    for raw in last {
+        output::print(&raw)?;
+    }
+    Ok(())
+}
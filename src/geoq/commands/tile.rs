@@ -0,0 +1,128 @@
+use crate::geoq::{
+    error::Error,
+    output,
+    par::{self, ParOptions},
+    reader,
+    tile::{self, Tile},
+};
+use clap::ArgMatches;
+use serde_json::{json, Map};
+use std::io;
+
+pub fn read_zoom(matches: &ArgMatches) -> Result<u8, Error> {
+    let zoom_str = matches.value_of("zoom").ok_or(Error::MissingArgument)?;
+    match zoom_str.parse::<u8>() {
+        Ok(z) => tile::validate_zoom(z),
+        Err(_) => Err(Error::InvalidNumberFormat(format!(
+            "Expected valid tile zoom: {}",
+            zoom_str
+        ))),
+    }
+}
+
+// Values are read from the given positional argument if present,
+// otherwise from STDIN, one per line
+pub fn for_arg_or_line<F>(matches: &ArgMatches, arg: &str, mut handler: F) -> Result<(), Error>
+where
+    F: FnMut(&str) -> Result<Vec<String>, Error>,
+{
+    if let Some(value) = matches.value_of(arg) {
+        for out in handler(value)? {
+            output::print(&out)?;
+        }
+        return Ok(());
+    }
+    let stdin = io::stdin();
+    let mut stdin_reader = stdin.lock();
+    let (mut n, mut offset) = (0, 0);
+    while let Some((len, line)) = reader::read_line_with_len(&mut stdin_reader) {
+        n += 1;
+        let start = offset;
+        offset += len as u64;
+        if line.is_empty() {
+            continue;
+        }
+        for out in handler(&line).map_err(|e| e.at(n, start, &line))? {
+            output::print(&out)?;
+        }
+    }
+    Ok(())
+}
+
+fn for_tile<F>(matches: &ArgMatches, mut handler: F) -> Result<(), Error>
+where
+    F: FnMut(Tile) -> Result<Vec<String>, Error>,
+{
+    for_arg_or_line(matches, "tile", |t| handler(t.parse()?))
+}
+
+fn for_point(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let zoom = read_zoom(matches)?;
+    par::for_stdin_entity(par_opts, move |e| match e.geom() {
+        geo_types::Geometry::Point(p) => Ok(vec![Tile::for_point(&p, zoom).to_string()]),
+        _ => Err(Error::InvalidInput(
+            "Input for 'geoq tile for-point' should be a Point geometry".to_string(),
+        )),
+    })
+}
+
+fn cover(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let zoom = read_zoom(matches)?;
+    par::for_stdin_entity(par_opts, move |e| {
+        Ok(tile::covering(&e.geom(), zoom)
+            .iter()
+            .map(|t| t.to_string())
+            .collect())
+    })
+}
+
+fn bbox(matches: &ArgMatches) -> Result<(), Error> {
+    for_tile(matches, |t| {
+        let geom = geo_types::Geometry::Polygon(t.polygon());
+        let mut props = Map::new();
+        props.insert("tile".to_string(), json!(t.to_string()));
+        let feature = geojson::Feature {
+            bbox: None,
+            geometry: Some(geojson::Geometry::new(geojson::Value::from(&geom))),
+            id: None,
+            properties: Some(props),
+            foreign_members: None,
+        };
+        Ok(vec![serde_json::to_string(&feature)?])
+    })
+}
+
+fn children(matches: &ArgMatches) -> Result<(), Error> {
+    for_tile(matches, |t| {
+        if t.z >= tile::MAX_ZOOM {
+            Err(Error::InvalidInput(format!(
+                "Can't get children for tile {} at max zoom {}",
+                t,
+                tile::MAX_ZOOM
+            )))
+        } else {
+            Ok(t.children().iter().map(|c| c.to_string()).collect())
+        }
+    })
+}
+
+fn parent(matches: &ArgMatches) -> Result<(), Error> {
+    for_tile(matches, |t| match t.parent() {
+        Some(p) => Ok(vec![p.to_string()]),
+        None => Err(Error::InvalidInput(format!(
+            "Can't get parent for zoom 0 tile {}",
+            t
+        ))),
+    })
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("for-point", Some(m)) => for_point(m, par_opts),
+        ("cover", Some(m)) => cover(m, par_opts),
+        ("bbox", Some(m)) => bbox(m),
+        ("children", Some(m)) => children(m),
+        ("parent", Some(m)) => parent(m),
+        _ => Err(Error::UnknownCommand),
+    }
+}
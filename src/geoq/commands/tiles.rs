@@ -0,0 +1,201 @@
+use crate::geoq::{
+    checkpoint::Checkpoint,
+    commands::tile::for_arg_or_line,
+    error::Error,
+    reader,
+    tile::{self, Tile},
+    tiles::{self, mbtiles, mvt, pmtiles},
+};
+use clap::ArgMatches;
+use serde_json::{json, Map};
+use std::path::Path;
+
+pub(crate) fn read_zoom_arg(matches: &ArgMatches, name: &str) -> Result<u8, Error> {
+    let zoom_str = matches.value_of(name).ok_or(Error::MissingArgument)?;
+    match zoom_str.parse::<u8>() {
+        Ok(z) => tile::validate_zoom(z),
+        Err(_) => Err(Error::InvalidNumberFormat(format!(
+            "Expected valid tile zoom for --{}: {}",
+            name, zoom_str
+        ))),
+    }
+}
+
+// The --buffer around tiles, in tile units
+pub fn read_buffer(matches: &ArgMatches) -> Result<u32, Error> {
+    let buffer = matches.value_of("buffer").ok_or(Error::MissingArgument)?;
+    match buffer.parse::<u32>() {
+        Ok(b) if b <= tiles::DEFAULT_EXTENT => Ok(b),
+        _ => Err(Error::InvalidNumberFormat(format!(
+            "Expected a --buffer from 0 to {} tile units: {}",
+            tiles::DEFAULT_EXTENT,
+            buffer
+        ))),
+    }
+}
+
+fn read_options(matches: &ArgMatches) -> Result<tiles::Options, Error> {
+    let min_zoom = read_zoom_arg(matches, "min-zoom")?;
+    let max_zoom = read_zoom_arg(matches, "max-zoom")?;
+    if min_zoom > max_zoom {
+        return Err(Error::InvalidInput(format!(
+            "--min-zoom {} is greater than --max-zoom {}",
+            min_zoom, max_zoom
+        )));
+    }
+    let mut opts = tiles::Options::new(min_zoom, max_zoom, matches.value_of("layer").unwrap());
+    opts.buffer = read_buffer(matches)?;
+    if let Some(path) = matches.value_of("profile") {
+        opts.profile = tiles::profile::Profile::read(path)?;
+    }
+    Ok(opts)
+}
+
+fn read_source(checkpoint: Option<&mut Checkpoint>) -> Result<tiles::Source, Error> {
+    let mut source = tiles::Source::new();
+    let handler = |e: crate::geoq::entity::Entity| {
+        source.add(&e);
+        Ok(())
+    };
+    match checkpoint {
+        Some(c) => c.read_entities(handler)?,
+        None => reader::for_entity(handler)?,
+    }
+    Ok(source)
+}
+
+// Archive format from --archive, or else from the output file extension
+fn open_writer(matches: &ArgMatches, out: &Path) -> Result<Box<dyn tiles::Writer>, Error> {
+    let format = match matches.value_of("archive") {
+        Some(f) => f,
+        None if out.extension().is_some_and(|e| e == "pmtiles") => "pmtiles",
+        None => "mbtiles",
+    };
+    match format {
+        "pmtiles" => Ok(Box::new(pmtiles::Writer::create(out)?)),
+        "mbtiles" => Ok(Box::new(mbtiles::Writer::create(out)?)),
+        f => Err(Error::InvalidInput(format!(
+            "Unknown tile archive format: {} -- expected mbtiles or pmtiles",
+            f
+        ))),
+    }
+}
+
+fn build(matches: &ArgMatches) -> Result<(), Error> {
+    let opts = read_options(matches)?;
+    let out = Path::new(matches.value_of("out").unwrap());
+    let mut checkpoint = match matches.value_of("checkpoint") {
+        Some(c) => Some(Checkpoint::open(c, "tiles build")?),
+        None => None,
+    };
+    // An interrupted run's output is rebuilt from the checkpoint
+    let resuming = checkpoint.as_ref().is_some_and(|c| c.resuming());
+    if out.exists() {
+        if !matches.is_present("force") && !resuming {
+            return Err(Error::InvalidInput(format!(
+                "Output file {} already exists -- use --force to overwrite it",
+                out.display()
+            )));
+        }
+        std::fs::remove_file(out)?;
+    }
+
+    let source = read_source(checkpoint.as_mut())?;
+    let mut writer = open_writer(matches, out)?;
+    match checkpoint {
+        None => tiles::build(&source, &opts, |t, data| writer.write_tile(t, &data))?,
+        Some(mut checkpoint) => {
+            checkpoint.replay_tiles(|t, data| writer.write_tile(t, &data))?;
+            build_from(&source, &opts, &mut checkpoint, writer.as_mut())?;
+            writer.finish(&tiles::Metadata::new(&source, &opts))?;
+            return checkpoint.finish();
+        }
+    }
+    writer.finish(&tiles::Metadata::new(&source, &opts))
+}
+
+// Builds the zoom levels after those the checkpoint has finished,
+// recording each as it's finished
+fn build_from(
+    source: &tiles::Source,
+    opts: &tiles::Options,
+    checkpoint: &mut Checkpoint,
+    writer: &mut dyn tiles::Writer,
+) -> Result<(), Error> {
+    let min_zoom = checkpoint
+        .zoom()
+        .map_or(opts.min_zoom, |z| opts.min_zoom.max(z + 1));
+    if min_zoom > opts.max_zoom {
+        return Ok(());
+    }
+    let remaining = tiles::Options {
+        min_zoom,
+        ..opts.clone()
+    };
+    let mut zoom = min_zoom;
+    tiles::build(source, &remaining, |t, data| {
+        for z in zoom..t.z {
+            checkpoint.finish_zoom(z)?;
+        }
+        zoom = t.z;
+        checkpoint.spool_tile(t, &data)?;
+        writer.write_tile(t, &data)
+    })?;
+    checkpoint.finish_zoom(opts.max_zoom)
+}
+
+fn open_archive(matches: &ArgMatches) -> Result<Box<dyn tiles::Reader>, Error> {
+    tiles::open(Path::new(matches.value_of("archive").unwrap()))
+}
+
+fn decode(t: Tile, data: &[u8]) -> Result<Vec<String>, Error> {
+    let mut features = vec![];
+    for layer in mvt::decode_tile(data)? {
+        for f in layer.features {
+            let geom = tiles::unproject(&f.geom, t, layer.extent);
+            let mut foreign = Map::new();
+            foreign.insert("layer".to_string(), json!(layer.name));
+            let feature = geojson::Feature {
+                bbox: None,
+                geometry: Some(geojson::Geometry::new(geojson::Value::from(&geom))),
+                id: f.id.map(|id| geojson::feature::Id::Number(id.into())),
+                properties: Some(f.props),
+                foreign_members: Some(foreign),
+            };
+            features.push(serde_json::to_string(&feature)?);
+        }
+    }
+    Ok(features)
+}
+
+fn read(matches: &ArgMatches) -> Result<(), Error> {
+    let mut archive = open_archive(matches)?;
+    for_arg_or_line(matches, "tile", |t| {
+        let t: Tile = t.parse()?;
+        match archive.tile(t)? {
+            Some(data) => decode(t, &data),
+            None => Err(Error::InvalidInput(format!(
+                "Tile {} not found in archive",
+                t
+            ))),
+        }
+    })
+}
+
+fn list(matches: &ArgMatches) -> Result<(), Error> {
+    let mut tiles = open_archive(matches)?.tiles()?;
+    tiles.sort();
+    for t in tiles {
+        println!("{}", t);
+    }
+    Ok(())
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("build", Some(m)) => build(m),
+        ("read", Some(m)) => read(m),
+        ("list", Some(m)) => list(m),
+        _ => Err(Error::UnknownCommand),
+    }
+}
@@ -0,0 +1,162 @@
+// Builds tracks from timestamped points, such as GPS fixes from a fleet
+// of devices, and analyzes them
+use crate::geoq::{
+    error::Error,
+    lookup, output,
+    par::{self, ParOptions},
+    reader,
+    timestamp::{self, EpochUnit},
+    trajectory::{self, Fix, Interval, Segment, Track},
+};
+use clap::ArgMatches;
+use geo_types::{Coord, Geometry, Point};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+fn geojson_feature(geom: &Geometry<f64>, props: Map<String, Value>) -> Result<String, Error> {
+    let feature = geojson::Feature {
+        bbox: None,
+        geometry: Some(geojson::Geometry::new(geojson::Value::from(geom))),
+        id: None,
+        properties: Some(props),
+        foreign_members: None,
+    };
+    Ok(serde_json::to_string(&feature)?)
+}
+
+fn feature(track: &Track, mut props: Map<String, Value>) -> Result<String, Error> {
+    props.extend(track.properties());
+    geojson_feature(&track.geometry(), props)
+}
+
+fn build(matches: &ArgMatches) -> Result<(), Error> {
+    let group_by = matches.value_of("group-by");
+    let order_by = matches.value_of("order-by").unwrap();
+    let max_gap = matches
+        .value_of("max-gap")
+        .map(timestamp::parse_seconds)
+        .transpose()?;
+    let unit = if matches.is_present("epoch-millis") {
+        EpochUnit::Millis
+    } else {
+        EpochUnit::Seconds
+    };
+
+    // Each group's value and fixes, in the order groups are first seen
+    let mut groups: Vec<(Value, Vec<Fix>)> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    reader::for_entity(|e| {
+        let coord = match e.geom() {
+            Geometry::Point(p) => p.0,
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "Tracks can only be built from points: {}",
+                    e.raw()
+                )))
+            }
+        };
+        let props = e.geojson_properties();
+        let time = props
+            .get(order_by)
+            .and_then(|v| timestamp::parse_value(v, unit))
+            .ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Missing or invalid {} time -- expected an ISO 8601 time or epoch number: {}",
+                    order_by,
+                    e.raw()
+                ))
+            })?;
+        let group = group_by
+            .and_then(|g| props.get(g).cloned())
+            .unwrap_or(Value::Null);
+        let key = lookup::key_text(&group).unwrap_or_else(|| group.to_string());
+        let i = *index.entry(key).or_insert_with(|| {
+            groups.push((group, vec![]));
+            groups.len() - 1
+        });
+        groups[i].1.push(Fix { coord, time });
+        Ok(())
+    })?;
+
+    for (group, mut fixes) in groups {
+        fixes.sort_by_key(|f| f.time);
+        for track in trajectory::split(fixes, max_gap) {
+            let mut props = Map::new();
+            if let Some(g) = group_by {
+                props.insert(g.to_string(), group.clone());
+            }
+            output::print(&feature(&track, props)?)?;
+        }
+    }
+    Ok(())
+}
+
+// Where a track stopped: the middle of its fixes while stopped, with the
+// times it arrived and left and the seconds it stayed
+fn stop(track: &Track, mut props: Map<String, Value>) -> Result<String, Error> {
+    let n = track.fixes.len() as f64;
+    let center = Coord {
+        x: track.fixes.iter().map(|f| f.coord.x).sum::<f64>() / n,
+        y: track.fixes.iter().map(|f| f.coord.y).sum::<f64>() / n,
+    };
+    if let (Some(first), Some(last)) = (track.fixes.first(), track.fixes.last()) {
+        props.insert(
+            "start_time".to_string(),
+            json!(timestamp::format(&first.time)),
+        );
+        props.insert("end_time".to_string(), json!(timestamp::format(&last.time)));
+    }
+    props.insert("duration".to_string(), json!(track.duration()));
+    geojson_feature(&Geometry::Point(Point(center)), props)
+}
+
+fn analyze(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let stop_speed = trajectory::parse_speed(matches.value_of("stop-speed").unwrap())?;
+    let stop_duration = timestamp::parse_seconds(matches.value_of("stop-duration").unwrap())?;
+    par::for_stdin_entity(par_opts, move |e| {
+        let mut props = e.geojson_properties();
+        let track = Track::from_geojson(&e.geom(), &props)?;
+        // The track's own measurements don't hold for its parts
+        for k in track.properties().keys() {
+            props.remove(k);
+        }
+        let mut out = vec![];
+        for segment in trajectory::segments(&track, stop_speed, stop_duration) {
+            let mut props = props.clone();
+            out.push(match segment {
+                Segment::Moving(track) => {
+                    props.insert("state".to_string(), json!("moving"));
+                    feature(&track, props)?
+                }
+                Segment::Stopped(track) => {
+                    props.insert("state".to_string(), json!("stopped"));
+                    stop(&track, props)?
+                }
+            });
+        }
+        Ok(out)
+    })
+}
+
+fn resample(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let interval: Interval = matches.value_of("every").unwrap().parse()?;
+    par::for_stdin_entity(par_opts, move |e| {
+        let mut props = e.geojson_properties();
+        let track = trajectory::resample(&Track::from_geojson(&e.geom(), &props)?, interval);
+        if track.fixes.len() < 2 {
+            return Ok(vec![]);
+        }
+        for k in track.properties().keys() {
+            props.remove(k);
+        }
+        Ok(vec![feature(&track, props)?])
+    })
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("analyze", Some(m)) => analyze(m, par_opts),
+        ("resample", Some(m)) => resample(m, par_opts),
+        _ => build(matches),
+    }
+}
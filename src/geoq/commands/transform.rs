@@ -0,0 +1,46 @@
+use crate::geoq::{
+    entity::Entity,
+    error::Error,
+    expr::{self, Assignment, Scope, Target, Value},
+    par::{self, ParOptions},
+};
+use clap::ArgMatches;
+
+// The entity as a Feature, with the program's assignments made to its
+// geometry and properties in turn
+pub fn transform_feature(e: &Entity, program: &[Assignment]) -> Result<geojson::Feature, Error> {
+    let mut geom = e.geom();
+    let mut props = e.geojson_properties();
+    for assignment in program {
+        let value = assignment.value.eval(&Scope {
+            geom: &geom,
+            props: &props,
+        })?;
+        match &assignment.target {
+            Target::Geom => match value {
+                Value::Geom(g) => geom = g,
+                other => {
+                    return Err(Error::InvalidInput(format!(
+                        "Can't assign {:?} to geom -- expected a geometry",
+                        other
+                    )))
+                }
+            },
+            Target::Prop(key) => {
+                props.insert(key.clone(), value.to_json());
+            }
+        }
+    }
+    let mut feature = e.geojson_feature();
+    feature.geometry = Some(geojson::Geometry::new(geojson::Value::from(&geom)));
+    feature.properties = Some(props);
+    Ok(feature)
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let program = expr::parse_program(matches.value_of("program").unwrap())?;
+    par::for_stdin_entity(par_opts, move |e| {
+        let feature = transform_feature(&e, &program)?;
+        Ok(vec![serde_json::to_string(&feature).unwrap()])
+    })
+}
@@ -1,23 +1,17 @@
-use crate::geoq::error::Error;
+use crate::geoq::{error::Error, net};
 use geo_types::Geometry;
 use geo_types::Point;
 use serde_json;
 
 fn get_ip_geolocation() -> Result<(), Error> {
-    let resp = reqwest::get("http://ip-api.com/json");
+    let resp = net::block_on(net::text(net::client().get("http://ip-api.com/json")));
     if let Err(e) = resp {
         eprintln!("Error fetching IP geolocation: {:?}", e);
         return Err(Error::IPGeolocationError);
     }
 
-    let body = resp.unwrap().text();
-
-    if let Err(e) = body {
-        eprintln!("Error reading geolocation response: {:?}", e);
-        return Err(Error::IPGeolocationError);
-    }
-
-    let json_res = serde_json::from_str(&body.unwrap());
+    let (_, body) = resp.unwrap();
+    let json_res = serde_json::from_str(&body);
 
     if let Err(e) = json_res {
         eprintln!("Error reading geolocation response: {:?}", e);
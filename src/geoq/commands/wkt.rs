@@ -1,5 +1,13 @@
-use crate::geoq::{error::Error, par};
+use crate::geoq::{
+    entity::Entity,
+    error::Error,
+    par::{self, ParOptions},
+};
 
-pub fn run() -> Result<(), Error> {
-    par::for_stdin_entity(|e| Ok(vec![format!("{}", e.wkt())]))
+pub fn handle(e: Entity) -> Result<Vec<String>, Error> {
+    Ok(vec![format!("{}", e.wkt())])
+}
+
+pub fn run(par_opts: &ParOptions) -> Result<(), Error> {
+    par::for_stdin_entity(par_opts, handle)
 }
@@ -0,0 +1,101 @@
+// Summarizes the pixels of a raster under each input polygon, such as the
+// mean elevation of each parcel, as properties of the polygon's feature
+use super::raster::{read_band, read_nodata, value_json};
+use crate::geoq::{
+    error::Error,
+    par::{self, ParOptions},
+    raster::{geotiff, zonal},
+};
+use clap::ArgMatches;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const STATS: &[&str] = &["count", "sum", "mean", "min", "max", "median", "stddev"];
+
+fn read_stats(stats: &str) -> Result<Vec<String>, Error> {
+    stats
+        .split(',')
+        .map(|s| {
+            let s = s.trim();
+            if STATS.contains(&s) {
+                Ok(s.to_string())
+            } else {
+                Err(Error::InvalidInput(format!(
+                    "Unknown statistic: {} -- expected some of {}",
+                    s,
+                    STATS.join(",")
+                )))
+            }
+        })
+        .collect()
+}
+
+// A statistic of the values, or null if there are none
+fn stat(name: &str, values: &[f64]) -> Value {
+    if name == "count" {
+        return json!(values.len());
+    }
+    if values.is_empty() {
+        return Value::Null;
+    }
+    let n = values.len() as f64;
+    let sum: f64 = values.iter().sum();
+    let mean = sum / n;
+    match name {
+        "sum" => value_json(sum),
+        "mean" => json!(mean),
+        "min" => value_json(values.iter().copied().fold(f64::INFINITY, f64::min)),
+        "max" => value_json(values.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        "median" => {
+            let mut sorted = values.to_vec();
+            sorted.sort_by(f64::total_cmp);
+            let mid = sorted.len() / 2;
+            if sorted.len().is_multiple_of(2) {
+                json!((sorted[mid - 1] + sorted[mid]) / 2.0)
+            } else {
+                value_json(sorted[mid])
+            }
+        }
+        // Of the population of pixels, as rasterstats gives
+        _ => json!((values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt()),
+    }
+}
+
+pub fn run(matches: &ArgMatches, par_opts: &ParOptions) -> Result<(), Error> {
+    let stats = read_stats(matches.value_of("stats").unwrap())?;
+    let mut raster = geotiff::read(matches.value_of("raster").unwrap(), read_band(matches)?)?;
+    read_nodata(matches, &mut raster)?;
+    let raster = Arc::new(raster);
+
+    par::for_stdin_entity(par_opts, move |e| {
+        let values = zonal::values(&raster, &e.geom());
+        let mut feature = e.geojson_feature();
+        if let Some(props) = feature.properties.as_mut() {
+            for name in &stats {
+                props.insert(name.clone(), stat(name, &values));
+            }
+        }
+        Ok(vec![serde_json::to_string(&feature)?])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_stats, stat};
+    use serde_json::json;
+
+    #[test]
+    fn test_stats() {
+        let values = [4.0, 1.0, 3.0, 2.0];
+        assert_eq!(json!(4), stat("count", &values));
+        assert_eq!(json!(10), stat("sum", &values));
+        assert_eq!(json!(2.5), stat("mean", &values));
+        assert_eq!(json!(1), stat("min", &values));
+        assert_eq!(json!(2.5), stat("median", &values));
+        assert_eq!(json!(1.25f64.sqrt()), stat("stddev", &values));
+        assert_eq!(json!(0), stat("count", &[]));
+        assert!(stat("mean", &[]).is_null());
+        assert!(read_stats("mean, max").is_ok());
+        assert!(read_stats("mean,mode").is_err());
+    }
+}
@@ -0,0 +1,194 @@
+// Defaults for options, from GEOQ_* environment variables and from a
+// config file at $GEOQ_CONFIG, or else geoq/config.toml in
+// $XDG_CONFIG_HOME (~/.config by default). The file is TOML, with
+// string, number or boolean values, and [sections] whose name prefixes
+// their keys, so
+//
+//   jobs = 4
+//   on_error = "skip"
+//
+//   [geocoder]
+//   endpoint = "https://nominatim.example.com"
+//
+// sets the same defaults as GEOQ_JOBS=4, GEOQ_ON_ERROR=skip and
+// GEOQ_GEOCODER_ENDPOINT=https://nominatim.example.com. Options given on
+// the command line come first, then the environment, then the file.
+use crate::geoq::{error::Error, report};
+use clap::ArgMatches;
+use once_cell::sync::OnceCell;
+use std::{collections::HashMap, env, fs, io, path::PathBuf};
+
+// Keys which may be set, named after their options
+const KEYS: &[&str] = &[
+    "jobs",
+    "batch_size",
+    "buffer_lines",
+    "max_buffered_bytes",
+    "timeout_per_entity",
+    "unordered",
+    "progress",
+    "format",
+    "precision",
+    "pretty",
+    "compact",
+    "errors",
+    "on_error",
+    "error_file",
+    "strict",
+    "lenient",
+    "strip_foreign",
+    "http_retries",
+    "http_concurrency",
+    "other_max_memory",
+    "geocoder_endpoint",
+    "units",
+];
+
+static CONFIG: OnceCell<HashMap<String, String>> = OnceCell::new();
+
+fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("GEOQ_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("geoq").join("config.toml"))
+}
+
+// A setting's value as the options take it
+fn setting(key: &str, value: toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(s) => Ok(s),
+        toml::Value::Integer(i) => Ok(i.to_string()),
+        toml::Value::Float(f) => Ok(f.to_string()),
+        toml::Value::Boolean(b) => Ok(b.to_string()),
+        _ => Err(format!("Expected a string, number or boolean for {}", key)),
+    }
+}
+
+fn parse(text: &str) -> Result<HashMap<String, String>, String> {
+    let table: toml::value::Table = toml::from_str(text).map_err(|e| e.to_string())?;
+    let mut settings = HashMap::new();
+    for (name, value) in table {
+        let entries = match value {
+            toml::Value::Table(section) => section
+                .into_iter()
+                .map(|(key, value)| (format!("{}_{}", name, key), value))
+                .collect(),
+            value => vec![(name, value)],
+        };
+        for (key, value) in entries {
+            let key = key.replace('-', "_");
+            let value = setting(&key, value)?;
+            settings.insert(key, value);
+        }
+    }
+    Ok(settings)
+}
+
+// Reads the config file, if there is one, warning of any keys which
+// aren't settings
+pub fn load() -> Result<(), Error> {
+    let path = match path() {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    let settings = match fs::read_to_string(&path) {
+        Ok(text) => parse(&text).map_err(|e| {
+            Error::InvalidInput(format!("Invalid config file {}: {}", path.display(), e))
+        })?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+        Err(e) => return Err(e.into()),
+    };
+    for key in settings.keys() {
+        if !KEYS.contains(&key.as_str()) {
+            report::warning(
+                &format!("Unknown setting in config file {}", path.display()),
+                key,
+            );
+        }
+    }
+    let _ = CONFIG.set(settings);
+    Ok(())
+}
+
+// The default for a setting, from the environment or config file
+fn default(key: &str) -> Option<String> {
+    let var = format!("GEOQ_{}", key.to_uppercase());
+    env::var(var)
+        .ok()
+        .or_else(|| CONFIG.get()?.get(key).cloned())
+}
+
+// The value of an option given on the command line, or else the setting
+// for it, or else the option's own default
+pub fn value_of(matches: &ArgMatches, arg: &str, key: &str) -> Option<String> {
+    if matches.occurrences_of(arg) > 0 {
+        return matches.value_of(arg).map(|v| v.to_string());
+    }
+    default(key).or_else(|| matches.value_of(arg).map(|v| v.to_string()))
+}
+
+// An option named the same as its setting
+pub fn value(matches: &ArgMatches, arg: &str) -> Option<String> {
+    value_of(matches, arg, &arg.replace('-', "_"))
+}
+
+// A flag given on the command line, or set to true or false
+pub fn flag(matches: &ArgMatches, arg: &str) -> Result<bool, Error> {
+    if matches.is_present(arg) {
+        return Ok(true);
+    }
+    let key = arg.replace('-', "_");
+    match default(&key).as_deref() {
+        None | Some("false") => Ok(false),
+        Some("true") => Ok(true),
+        Some(v) => Err(Error::InvalidInput(format!(
+            "Expected true or false for {}: {}",
+            key, v
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn test_parse() {
+        let settings = parse(
+            r#"
+# Shared defaults
+jobs = 4 # for CI
+on-error = "skip"
+error_file = 'errors # today.jsonl'
+pretty = true
+http_retries = 2.5
+
+[geocoder]
+endpoint = "https://example.com/\"nominatim\""
+"#,
+        )
+        .unwrap();
+        assert_eq!(6, settings.len());
+        assert_eq!("4", settings["jobs"]);
+        assert_eq!("skip", settings["on_error"]);
+        assert_eq!("errors # today.jsonl", settings["error_file"]);
+        assert_eq!("true", settings["pretty"]);
+        assert_eq!("2.5", settings["http_retries"]);
+        assert_eq!(
+            "https://example.com/\"nominatim\"",
+            settings["geocoder_endpoint"]
+        );
+
+        assert!(parse("jobs").unwrap_err().contains("line 1"));
+        assert!(parse("\n[geocoder").unwrap_err().contains("line 2"));
+        assert!(parse("format = \"csv").is_err());
+        assert!(parse("jobs = [4]").unwrap_err().contains("jobs"));
+        assert!(parse("[geocoder.nominatim]\nendpoint = \"x\"")
+            .unwrap_err()
+            .contains("geocoder_nominatim"));
+    }
+}
@@ -1,20 +1,10 @@
 #![allow(unreachable_code)]
 #![allow(dead_code)]
 #![allow(unused_imports)]
-pub mod bbox;
+pub use geoq_core::*;
+pub mod allocations;
 pub mod browser_open;
-pub mod centroid;
+pub mod command;
 pub mod commands;
-pub mod contains;
-pub mod coord_count;
-pub mod distance;
-pub mod entity;
-pub mod error;
-pub mod fgb;
-pub mod geohash;
-mod geojson;
-pub mod input;
-pub mod par;
-pub mod reader;
-pub mod simplify;
+pub mod config;
 pub mod text;
@@ -24,6 +24,13 @@ Geoq reads the following geospatial text formats:
 - WKT (Well-Known Text)
 - GeoJSON
 - Geohashes (Base32-encoded)
+- H3 cells, as hex strings
+- Slippy map tiles, as z/x/y (e.g. 12/702/1635)
+- Bing Maps quadkeys, with a qk: prefix (e.g. qk:0231)
+
+Geohashes, H3 cells, tiles and quadkeys are read as the
+polygons of their cells. Quadkeys need the qk: prefix,
+since strings of the digits 0-3 are also geohashes.
 
 Values must be submitted **1 per line**. GeoJSON data
 in particular is often pretty-printed by other tools, so
@@ -44,6 +51,15 @@ handling is given to GeoJSON 'FeatureCollections'. These
 will be "unrolled" by Geoq's processing, and treated as
 if their features had been passed individually.
 
+Non-standard GeoJSON members on Features and Geometries
+(e.g. "crs", or custom keys) are kept when geoq rewrites
+features, e.g. with 'geoq gj f' or 'geoq props set'.
+Pass --strip-foreign to drop them instead. Members of a
+FeatureCollection are copied to each of its features as
+they're unrolled, unless a feature has its own. Commands
+which filter features output their input unchanged, so are
+not affected by --strip-foreign.
+
 You can also use this command ('geoq read') to test out
 input formats and see how geoq reads the values you feed it:
 
@@ -92,8 +108,8 @@ Query Feature must be a POINT, and can be provided as
 Lat/Lon, WKT, or GeoJSON.
 ";
 
-pub const DISTANCE_AFTER_HELP: &str = r"
-Gives the distance in meters between each input Feature and the given QUERY Feature.
+pub const DISTANCE_AFTER_HELP: &str = r#"
+Gives the distance in meters (or --units) between each input Feature and the given QUERY Feature.
 
 Output is given in the format:
 
@@ -110,7 +126,14 @@ Distances will be given between the QUERY point and the closest
 point on each input Feature.
 
 Distance for Features that cover the QUERY point (like a Polygon containing the point) will be 0.
-";
+
+With --rhumb, distances are measured along the rhumb line of constant
+bearing to the closest point, rather than along the geodesic.
+
+With --json, each distance is given as a JSON object instead:
+
+{"id":"stop-1","units":"meters","value":12.3}
+"#;
 
 pub const FILTER_AFTER_HELP: &str = r"
 Select features based on geospatial predicates
@@ -140,9 +163,61 @@ Example:
 
 geoq filter dwithin --radius 100 9q5
 
+geoq filter dwithin --radius 5km 9q5
+
+geoq filter dwithin --radius 3 --units mi 9q5
+
 Note: Currently only Point geometries are supported as candidates.
 ";
 
+pub const FILTER_TYPE_AFTER_HELP: &str = r#"
+Types are GeoJSON geometry types: Point, LineString, Polygon,
+MultiPoint, MultiLineString, MultiPolygon or GeometryCollection, in any
+case. A Polygon isn't a MultiPolygon, so give both to keep either.
+
+Example:
+
+geoq filter type Polygon MultiPolygon < parcels.geojson
+"#;
+
+pub const FILTER_GEOMETRY_AFTER_HELP: &str = r#"
+Features with a null geometry ("geometry": null), or with a geometry
+containing no coordinates (e.g. an empty GeometryCollection or
+LineString), are considered empty.
+
+Example:
+
+geoq filter geometry --non-empty
+
+geoq filter geometry --empty
+"#;
+
+pub const FILTER_TIME_AFTER_HELP: &str = r#"
+Keeps features whose --property timestamp falls in the range
+[--after, --before): --after is inclusive and --before is exclusive,
+so consecutive ranges don't overlap.
+
+Timestamps (both property values and the --after / --before arguments)
+may be:
+
+* ISO 8601 / RFC 3339 date-times, e.g. 2023-01-02T03:04:05Z or
+  2023-01-02T05:04:05+02:00
+* Date-times without an offset, e.g. 2023-01-02 03:04:05, treated as UTC
+* Dates, e.g. 2023-01-02, treated as midnight UTC
+* Epoch numbers (JSON numbers or numeric strings), in seconds, or
+  in milliseconds with --epoch-millis
+
+A plain date given to --before means the end of that day, so the whole
+day is kept: --after 2023-01-01 --before 2023-01-31 is all of January.
+
+Features with a missing or unparseable timestamp are dropped
+(or kept, with --negate).
+
+Example:
+
+geoq filter time --property timestamp --after 2023-01-01 --before 2023-06-30
+"#;
+
 pub const SIMPLIFY_ABOUT: &str = "Simplify geometries.";
 
 pub const SIMPLIFY_AFTER_HELP: &str = r"Reads features from STDIN.
@@ -175,8 +250,49 @@ pub const MEASURE_COORDS_ABOUT: &str =
     "Count number of total coordinates/vertices in each feature. Use --geojson to get results embedded in a GeoJSON Feature as a property.";
 pub const MEASURE_COORDS_GEOJSON_ARG_HELP: &str =
     "Give result as an embedded property in a GeoJSON feature, rather than as a single number.";
+pub const MEASURE_AS_PROPERTY_ARG_HELP: &str = "Write each input back out as a GeoJSON Feature with the result set as the property NAME, rather than as a single number.";
+pub const MEASURE_AREA_ABOUT: &str =
+    "Give the approximate geodesic area of each feature in square meters. Points and lines have an area of 0.";
+pub const MEASURE_LENGTH_ABOUT: &str =
+    "Give the geodesic length of each feature in meters. Polygons are measured by their perimeter.";
+pub const MEASURE_RHUMB_ARG_HELP: &str = "Measure along rhumb lines of constant bearing, as steered at sea, rather than along geodesics or great circles.";
+pub const MEASURE_BEARING_ABOUT: &str =
+    "Give the bearing in degrees from each input point to a QUERY point.";
+pub const BEARING_QUERY_ARG_HELP: &str = r"
+Point to take the bearing to, given as Lat/Lon, WKT, or GeoJSON.
+";
+pub const MEASURE_JSON_ARG_HELP: &str = r#"Give each result as a JSON object, {"id": ..., "value": ..., "units": ...}, rather than as a bare number. The id is the input feature's id, or null if it has none."#;
 
 pub const BBOX_AFTER_HELP: &str = "Generate bbox for a geometry";
+pub const BBOX_EXPAND_AFTER_HELP: &str = r#"
+Bboxes are given as min_lon,min_lat,max_lon,max_lat, and come out the
+same way. Entities read from STDIN give the expanded bbox of their
+envelope, as a polygon.
+
+A percentage moves the west and east edges by that share of the bbox's
+width, and the south and north edges by that share of its height. A
+distance moves each edge by at least that many meters on the ground:
+longitudes are widened for the latitude farthest from the equator.
+Negative margins shrink bboxes, and bboxes shrunk to nothing are left
+out. Bboxes stop at the poles and the antimeridian.
+
+Examples:
+
+$ geoq bbox expand 10% 0,0,10,20
+-1,-2,11,22
+$ echo 9q5 | geoq bbox expand 500m
+$ geoq bbox --all < points.geojson | geoq bbox expand 2mi
+"#;
+pub const BBOX_INTERSECT_AFTER_HELP: &str = r#"
+Gives the part of each bbox (or entity's envelope) within the other
+bbox, in the same form as for geoq bbox expand. Bboxes which don't
+overlap it are left out.
+
+Example:
+
+$ geoq bbox intersect -125,32,-114,42 -120,40,-110,50
+-120,40,-114,42
+"#;
 
 pub const H3_POLYFILL_HELP: &str = r"
 If min-res and max-res are different, this will be a 'compacted' set
@@ -193,3 +309,1515 @@ polygonToCells in the core library.
 
 Min Res must be greater than Max Res.
 ";
+
+pub const PROPS_ABOUT: &str = "Select, rename, and otherwise reshape feature properties.";
+pub const PROPS_SELECT_ABOUT: &str =
+    "Keep only the given property keys on each feature, optionally renaming them.";
+pub const PROPS_SELECT_AFTER_HELP: &str = r#"
+Reads features from STDIN and outputs them as GeoJSON Features
+with properties trimmed to the requested keys. Keys missing from
+a feature are skipped.
+
+Keys can be renamed with --rename old_name:new_name, which may be
+given multiple times. Renames refer to the original key name.
+
+Example:
+
+$ echo '{"type":"Feature","properties":{"name":"LA","population":3900000,"area":1302},"geometry":{"type":"Point","coordinates":[-118.2,34.0]}}' | geoq props select name,population --rename population:pop
+{"geometry":{"coordinates":[-118.2,34.0],"type":"Point"},"properties":{"name":"LA","pop":3900000},"type":"Feature"}
+"#;
+
+pub const PROPS_SET_ABOUT: &str =
+    "Set properties on each feature from literals or computed values.";
+pub const PROPS_SET_AFTER_HELP: &str = r#"
+Each argument is a key=value assignment. Values may be:
+
+* Literals: numbers, true, false, null, or quoted strings
+* Expressions using + - * / and parentheses
+* Geometry functions applied to the feature geometry ('geom'):
+  - area(geom)         Approximate geodesic area in square meters
+  - length(geom)       Geodesic length (or perimeter) in meters
+  - centroid_x(geom)   Centroid longitude
+  - centroid_y(geom)   Centroid latitude
+  - vertex_count(geom) Number of coordinates
+
+Properties of the feature can be read as props.<name>.
+
+A bare word, such as census, is stored as a string. Other text must be
+quoted, e.g. name='"New York"': a value which isn't a valid expression,
+or which uses an unknown variable, is an error.
+
+Example:
+
+$ echo 9q5 | geoq props set area_km2='area(geom)/1e6' source=census
+"#;
+
+pub const PROPS_VALUES_ABOUT: &str = "Count distinct values of a property.";
+pub const PROPS_VALUES_AFTER_HELP: &str = r#"
+Reads all features from STDIN and prints a frequency table of the
+values of the given property, one per line as:
+
+<count><TAB><value>
+
+Values are printed as JSON, so strings are quoted and can be told apart
+from numbers, booleans, and null. Features without the property are
+counted as (missing).
+
+Lines are ordered by descending count; values with equal counts are
+ordered by their JSON text.
+
+Example:
+
+$ geoq props values landuse --top 20 < parcels.geojson
+"#;
+
+pub const PROPS_JOIN_ABOUT: &str =
+    "Merge columns from a CSV or JSON lookup table into feature properties, matched by key.";
+pub const PROPS_JOIN_AFTER_HELP: &str = r#"
+Reads the lookup table, then each feature from STDIN, adding the
+columns of the row whose --on column matches the feature's property of
+the same name (or the feature's id, if it has no such property). Other
+columns of the row replace properties of the same name.
+
+The lookup can be CSV with a header row, a JSON array of objects, or a
+JSON object per line. CSV values which read as numbers become numbers,
+except those like 06037 which wouldn't be written the same way, so codes
+with leading zeros stay as text and still match. Keys are compared as
+text.
+
+Features with no matching row are passed through unchanged, or left out
+with --drop-unmatched.
+
+Example:
+
+$ geoq props join --lookup acs_income.csv --on GEOID < tracts.geojson
+"#;
+
+pub const PROPS_NORMALIZE_ABOUT: &str =
+    "Standardize property key case and clean up string values, so features from different sources share one schema.";
+pub const PROPS_NORMALIZE_AFTER_HELP: &str = r#"
+With --keys, keys are split into words at spaces, punctuation and
+changes of case, then joined in the given case, so "Median HH Income",
+"medianHHIncome" and "MEDIAN_HH_INCOME" all become median_hh_income
+with --keys snake_case. lower and upper only change the letters' case.
+
+If two keys of a feature would become the same, the feature is an
+error, handled as --on-error says, rather than losing one of the values.
+
+--trim and --empty-as-null only change top-level string values.
+
+Example:
+
+$ geoq props normalize --keys snake_case --trim --empty-as-null < parcels.geojson
+"#;
+
+pub const GREP_ABOUT: &str = "Select features whose property values match a text pattern.";
+pub const GREP_AFTER_HELP: &str = r#"
+Reads features from STDIN and outputs those with at least one
+property value matching the given pattern. By default the pattern
+is matched as a plain substring; use --regex for regular expressions
+(https://docs.rs/regex/ syntax).
+
+Non-string values (numbers, booleans) are matched against their
+JSON text, and nested arrays and objects are searched recursively.
+
+Use --property to restrict the search to a single property key.
+
+Examples:
+
+geoq grep 'Main St' --property address
+
+geoq grep --regex '^(CA|NV)$' --property state
+"#;
+
+pub const ID_ASSIGN_ABOUT: &str = "Write an id onto each feature";
+pub const ID_ASSIGN_AFTER_HELP: &str = r#"
+Outputs each input as a GeoJSON Feature with its top-level "id"
+member set, replacing any existing id.
+
+Strategies:
+
+* index - Sequential number based on position in the input, starting at 0
+* uuid  - Random (v4) UUID
+* hash  - Content-derived fingerprint of the feature's geometry and
+          properties (64-bit FNV-1a, hex-encoded). Identical features
+          always get the same id, across runs and machines.
+
+Example:
+
+geoq id assign --strategy hash
+"#;
+
+pub const DEDUP_ABOUT: &str = "Drop duplicate features from a stream.";
+pub const DEDUP_AFTER_HELP: &str = r#"
+Outputs the first occurrence of each feature, comparing features by:
+
+* geometry        - Geometry coordinates (the default)
+* id              - The GeoJSON Feature "id" member
+* property:<name> - The value of the given property
+
+Features which don't have the requested key (e.g. no id) are always kept.
+
+With --tolerance, coordinates are snapped to a grid of the given size
+(in coordinate units, usually degrees) before comparing geometries,
+so near-identical geometries are treated as duplicates.
+
+Each distinct key is held in memory for the comparison, so memory use
+grows with the number of distinct features (and, for --by geometry,
+with their size).
+
+Examples:
+
+geoq dedup --tolerance 1e-7
+
+geoq dedup --by property:osm_id
+"#;
+
+pub const DIFF_ABOUT: &str =
+    "Compare features (from STDIN) against a previous version (from --other)";
+pub const DIFF_AFTER_HELP: &str = r#"
+Features are matched between the two inputs by their GeoJSON "id"
+member (the default), or by a property with --key property:<name>.
+Every feature in both inputs must have the key.
+
+Outputs a change stream of GeoJSON Features, each with a top-level
+"change" member:
+
+* added    - Feature only in STDIN
+* modified - Feature in both, with a "changed" member listing
+             "geometry" and/or "properties"
+* removed  - Feature only in --other (printed at the end, in its
+             original form)
+
+Unchanged features are omitted unless --unchanged is given.
+
+The --other file is held in memory.
+
+Example:
+
+$ geoq diff --other old.geojsonl < new.geojsonl | jq -c 'select(.change == "modified")'
+"#;
+
+pub const SORT_ABOUT: &str = "Sort features by a spatial or attribute key.";
+pub const SORT_AFTER_HELP: &str = r#"
+Sort keys:
+
+* hilbert         - Position of each feature's bbox center along a
+                    Hilbert curve over the extent of the whole input
+                    (the same ordering used for FlatGeobuf indexes).
+                    Improves spatial locality, e.g. before tiling.
+* area            - Geodesic area in square meters
+* length          - Geodesic length in meters
+* property:<name> - Value of the given property. Numbers sort before
+                    strings; features missing the property sort last,
+                    even with --reverse.
+
+The sort is stable, so features with equal keys keep their input order.
+
+Inputs larger than --chunk-size features are sorted in chunks which
+are written to temp files and merged, so memory use stays bounded.
+Sorting by hilbert spools the whole input to a temp file first, since
+keys depend on the extent of the full dataset.
+
+Example:
+
+geoq sort --by property:population --reverse
+"#;
+
+pub const HEAD_ABOUT: &str = "Output the first N features.";
+pub const HEAD_AFTER_HELP: &str = r#"
+Unlike the unix head command, this counts features rather than lines,
+so it works with multi-line or multi-feature inputs like
+FeatureCollections (each feature is output on its own line).
+
+Input is not read past the last requested feature.
+
+Example:
+
+geoq head -n 100 < big.geojson
+"#;
+
+pub const TAIL_ABOUT: &str = "Output the last N features.";
+pub const TAIL_AFTER_HELP: &str = r#"
+Counts features rather than lines, like `geoq head`.
+Only the last N features are kept in memory.
+
+Example:
+
+geoq tail -n 100 < big.geojson
+"#;
+
+pub const SKIP_ABOUT: &str = "Skip the first N features and output the rest.";
+pub const SKIP_AFTER_HELP: &str = r#"
+Counts features rather than lines, like `geoq head`.
+
+Example:
+
+geoq skip 1000 < big.geojson | geoq head -n 1000
+"#;
+
+pub const SAMPLE_ABOUT: &str = "Output a random sample of features.";
+pub const SAMPLE_AFTER_HELP: &str = r#"
+Each feature is kept independently with probability --fraction,
+so input is streamed and the size of the output is approximate.
+
+Pass --seed to get the same sample for the same input across runs.
+
+Example:
+
+geoq sample --fraction 0.01 --seed 42 < big.geojson
+"#;
+
+pub const AGGREGATE_ABOUT: &str = "Group features by a key and aggregate their properties.";
+pub const AGGREGATE_AFTER_HELP: &str = r#"
+Outputs one feature per distinct value of --by, in the order groups
+are first seen. Features missing the key are grouped together under null.
+
+Output properties are:
+
+* The group key (named after the property, or "id")
+* count - Number of features in the group
+* <name>_sum, <name>_min, <name>_max, <name>_mean - For each
+  property given with --sum, --min, --max, or --mean
+
+Aggregations skip values which aren't numbers. --min, --max, and --mean
+give null when a group has no numeric values for the property.
+
+Without --union-geometry, output features have a null geometry.
+With --union-geometry, polygons in each group are dissolved into
+a single MultiPolygon. Other geometry types are kept as-is, combined with
+the polygons into a GeometryCollection.
+
+Groups are kept in memory, so memory use grows with the number of
+distinct keys (and with the size of geometries when using --union-geometry).
+
+Examples:
+
+geoq aggregate --by property:state --sum population --union-geometry
+geoq aggregate --by property:type --mean speed --max speed
+"#;
+
+pub const GH_COMPACT_AFTER_HELP: &str = r#"
+Reads all geohashes from STDIN and outputs the smallest set of
+geohashes covering exactly the same area:
+
+* Geohashes contained in another input geohash are dropped
+* Complete groups of all 32 children of a geohash are replaced
+  by their parent, repeatedly, so output may mix precisions
+
+Output is sorted. This is useful for shrinking coverings, e.g.:
+
+$ geoq gh covering 6 < polygon.geojson | geoq gh compact
+"#;
+
+pub const GH_DECODE_AFTER_HELP: &str = r#"
+Outputs a GeoJSON Feature for each geohash, with properties:
+
+* geohash - The input geohash
+* lat_err - Half the height of the geohash cell, in degrees
+* lon_err - Half the width of the geohash cell, in degrees
+
+The center point +/- these errors gives the geohash's bounds.
+
+The geometry is the center point of the geohash, or its
+bounding box polygon with --bbox.
+
+Example:
+
+$ echo 9q5 | geoq gh decode
+{"geometry":{"coordinates":[-118.828125,34.453125],"type":"Point"},"properties":{"geohash":"9q5","lat_err":0.703125,"lon_err":0.703125},"type":"Feature"}
+"#;
+
+pub const GH_COVER_AFTER_HELP: &str = r#"
+Unlike 'geoq gh covering', which uses a single fixed precision,
+this chooses precisions adaptively: geohashes entirely inside the
+entity are kept coarse, while geohashes along its edges are split
+into finer children, coarsest first, for as long as the result stays
+within --max-cells.
+
+The output for each entity is its sorted list of geohashes,
+which together cover the entity.
+
+Example:
+
+$ geoq gh cover --max-cells 256 < polygon.geojson
+"#;
+
+pub const TILE_ABOUT: &str = "Work with slippy map (z/x/y) tiles.";
+pub const TILE_AFTER_HELP: &str = r#"
+Tiles use the XYZ scheme common to web maps (OpenStreetMap, Google,
+Mapbox, etc.): Web Mercator, with tile 0/0/0 covering the world and
+y increasing southward. Latitudes beyond +/-85.0511 are clamped.
+
+Tiles are written and read as z/x/y strings, e.g. 12/702/1635.
+
+Examples:
+
+$ echo 34.0522,-118.2437 | geoq tile for-point 12
+12/702/1635
+
+$ geoq tile bbox 12/702/1635
+
+$ echo 12/702/1635 | geoq tile parent
+11/351/817
+"#;
+
+pub const QUADKEY_ABOUT: &str = "Work with Bing Maps quadkeys.";
+pub const QUADKEY_AFTER_HELP: &str = r#"
+Quadkeys identify the same Web Mercator tiles as z/x/y slippy map tiles
+(see 'geoq tile --help'), as a string of base-4 digits, one per zoom level.
+Each quadkey is a prefix of the quadkeys of all of its descendant tiles.
+
+Examples:
+
+$ geoq quadkey encode 3/3/5
+213
+
+$ echo 213 | geoq quadkey decode
+3/3/5
+
+$ echo 34.0522,-118.2437 | geoq quadkey point 12
+"#;
+
+pub const TILES_ABOUT: &str = "Generate vector tile archives.";
+pub const TILE_BUFFER_ARG_HELP: &str =
+    "Margin around each tile, in tile units (a tile is 4096 across), within which features are kept rather than clipped away, so strokes and labels aren't cut off at tile edges";
+
+pub const TILES_BUILD_AFTER_HELP: &str = r#"
+Reads GeoJSON features from STDIN and writes a Mapbox Vector Tile
+pyramid for every zoom from --min-zoom to --max-zoom into a single
+archive file:
+
+* MBTiles (the default): an SQLite database, for tile servers
+* PMTiles (--archive pmtiles, or an output path ending in .pmtiles): a
+  v3 archive which map clients can read directly from static hosting
+  such as S3, via HTTP range requests
+
+For each zoom, geometries are simplified to that zoom's resolution,
+clipped to each tile they touch, and encoded in a single layer along
+with their properties. Tiles with no features are omitted.
+
+Clipping leaves a margin of --buffer tile units (64 by default, of 4096)
+around each tile, so that lines and polygon outlines drawn across a
+tile edge don't show seams, and points just outside the tile are still
+there for labels and symbols which overlap it. Use a larger buffer for
+wide strokes or large labels, or 0 to clip exactly to the tile.
+
+All features are read into memory before tiles are written.
+
+With --profile profile.json, low zooms can be generalized further. The
+profile is an array of rules for ranges of zooms, for example:
+
+[{"min_zoom": 0, "max_zoom": 5, "tolerance": 16, "min_area": 256,
+  "properties": ["name", "kind"]},
+ {"min_zoom": 6, "max_zoom": 9, "tolerance": 4, "min_length": 8}]
+
+* tolerance: simplification tolerance, replacing the default of 1
+* min_area: polygons smaller than this are left out
+* min_length: lines shorter than this are left out
+* properties: the properties to keep, instead of all of them
+
+Lengths are in tile units (a tile is 4096 across) and areas in square
+tile units, at the zoom being built, so the same rule drops fewer
+features as you zoom in. Sizes are of whole features, not of the part
+within a tile. Each zoom uses the first rule covering it, and zooms
+without a rule are built as usual.
+
+With --checkpoint state.json, progress is recorded as the input is read
+and as each zoom level is finished. If the build is interrupted, running
+it again with the same checkpoint and input carries on from there, and
+the checkpoint files are removed once it finishes. The input must be
+newline-delimited, and is best redirected from a file, which is read
+from where the last run stopped rather than from the start.
+
+Example:
+
+$ geoq tiles build --min-zoom 0 --max-zoom 12 --out tiles.mbtiles < features.geojson
+$ geoq tiles build --max-zoom 10 --out tiles.pmtiles < features.geojson
+$ geoq tiles build --max-zoom 16 --out tiles.pmtiles --checkpoint build.json < features.geojson
+$ geoq tiles build --max-zoom 14 --profile profile.json --out tiles.pmtiles < features.geojson
+"#;
+
+pub const TILES_READ_AFTER_HELP: &str = r#"
+Outputs each feature in the tile as a GeoJSON Feature, with geometries
+converted from tile coordinates back to lon/lat. The name of the layer
+each feature came from is given in a "layer" member on the Feature.
+
+The archive format (MBTiles or PMTiles) is detected automatically.
+
+Examples:
+
+$ geoq tiles read tiles.pmtiles 12/702/1635
+
+$ geoq tiles list tiles.mbtiles | head | geoq tiles read tiles.mbtiles
+"#;
+
+pub const PARTITION_ABOUT: &str = "Split features into one file per geohash cell.";
+pub const PARTITION_AFTER_HELP: &str = r#"
+Each feature is written as a GeoJSON Feature, whatever its input
+format, to <out-dir>/<geohash>.geojson, one feature per line. Files are streamed, so the input doesn't need
+to fit in memory, and the partitions can then be processed in parallel.
+
+Features spanning several cells go to the cell containing their
+centroid by default, so each feature lands in exactly one partition.
+With --assign duplicate they're written to every cell they intersect.
+
+Partition files that already exist in the output directory are
+overwritten. The paths of the written files are printed to STDOUT.
+
+Example:
+
+$ geoq partition --by geohash:3 --out-dir parts/ < features.geojson | xargs -P 8 -n 1 ./process.sh
+"#;
+
+pub const SPLIT_OUTPUT_ABOUT: &str =
+    "Split features into numbered files of at most a number of features or bytes each.";
+pub const SPLIT_OUTPUT_AFTER_HELP: &str = r#"
+Each feature is written, as given, to the current part file, one per
+line, until the part holds --max-features features or another would
+take it past --max-bytes; then the next part is started. Files are
+numbered from 1 in the --out file name, and streamed, so the input
+doesn't need to fit in memory. Features are never split across files,
+so a feature bigger than --max-bytes gets a part of its own.
+
+When done, a manifest listing each part's path, number of features and
+size in bytes, and the total number of features, is written as JSON to
+--manifest. The paths of the parts are printed to STDOUT as they're
+finished. Files that already exist are overwritten.
+
+Example:
+
+$ geoq split-output --max-features 100000 --out parts/part-%04d.geojsonl < features.geojson
+"#;
+
+pub const CAT_ABOUT: &str =
+    "Combine datasets of mixed formats into one stream of features with the same properties.";
+pub const CAT_AFTER_HELP: &str = r#"
+Reads each file in turn, in any format geoq reads from STDIN (GeoJSON,
+WKT, Shapefile, FlatGeobuf, GeoPackage and so on), from a local path,
+an s3:// or gs:// URI or an http(s) URL, and writes their features to
+STDOUT as GeoJSON, one per line.
+
+Every feature is given the same property keys: all those found in any
+of the files, with null for those its own file lacks. Values are kept
+as they are, so a key can hold numbers in one file and strings in
+another. Each file is read twice, once to find the keys and once to
+write the features, so they don't need to fit in memory.
+
+With --source, each feature also gets a property holding the path of
+the file it came from.
+
+Example:
+
+$ geoq cat --source source parcels.shp roads.geojson buildings.fgb > combined.geojsonl
+"#;
+
+pub const SCHEMA_ABOUT: &str =
+    "Summarize the property keys of features: their types, null rates and values.";
+pub const SCHEMA_AFTER_HELP: &str = r#"
+Reads all features from STDIN and prints a JSON line for each property
+key, in the order keys are first seen, with:
+
+types      the number of features holding each type of value: boolean,
+           integer, number (non-integer), string, array or object
+nulls      the number of features where it's null
+missing    the number of features without it
+null_rate  the fraction of features where it's null or missing
+distinct   the number of distinct non-null values
+examples   the first --examples distinct non-null values
+
+A key with more than one type will need a wider column, such as a
+double for integers and numbers, or JSON, when exported to
+FlatGeobuf, Parquet or a SQL table. Values are counted by hash, so
+distinct counts on keys with very many values are close, not exact.
+
+Example:
+
+$ geoq schema < parcels.geojson | jq -c 'select(.null_rate > 0.5)'
+"#;
+
+pub const ESTIMATE_ABOUT: &str =
+    "Estimate the size of an export, and the memory it takes, from a sample of features.";
+pub const ESTIMATE_AFTER_HELP: &str = r#"
+Converts the first --sample features from STDIN to the given export
+format and scales up the results to the whole input, printing a JSON
+object with:
+
+features      the number of input features
+sampled       the number of features converted
+coordinates   the estimated number of coordinates in the input
+output_bytes  the estimated size of the output
+memory_bytes  the estimated memory taken by the features and output
+              while writing them. The features' memory is measured
+              when geoq is built with --features bench, and otherwise
+              taken to be the size of their input.
+
+The formats are estimated as written by:
+
+fgb      'geoq fgb write', which builds the whole file in memory
+mvt      'geoq tiles build' from --min-zoom to --max-zoom, with tiles
+         gzipped as in MBTiles and PMTiles. Memory includes the tiles,
+         which PMTiles output holds until it's finished.
+parquet  a GeoParquet writer, with WKB geometries and a column for
+         each property. Columns are gzipped as a stand-in for the
+         writer's compression, so expect the real size to differ.
+         Memory is one uncompressed row group of 65536 features.
+
+The rest of the input is read to count its features, which can be
+skipped by giving their number with --features. The sample is the
+start of the input, so if the data is sorted (e.g. by area or region)
+and its start isn't like the rest, shuffle it first, or check a few
+samples.
+
+Examples:
+
+$ geoq estimate fgb < parcels.geojson
+$ head -n 50000 parcels.geojsonl | geoq estimate mvt --max-zoom 14 --features 12000000
+"#;
+
+pub const BUFFER_ABOUT: &str = "Buffer geometries by a distance.";
+pub const BUFFER_AFTER_HELP: &str = r#"
+Replaces each geometry with the area within the distance of it, as a
+Polygon or MultiPolygon, keeping the feature's id and properties. A
+negative distance shrinks polygons by that much, and leaves nothing of
+points and lines.
+
+Geometries are taken to be lon/lat and buffered geodesically: each is
+projected with an azimuthal equidistant projection centered on it,
+buffered in meters and projected back, so buffers stay round at any
+latitude. The distance is in --units unless it gives its own, e.g.
+500ft or 2km. This is accurate for features up to a few hundred
+kilometers across, since distances stretch away from the center.
+
+With --planar, the coordinates are buffered as they are, and the
+distance is in their units, for data in a projected coordinate system.
+
+Examples:
+
+$ geoq buffer 500m < stops.geojson
+$ geoq buffer -- -10m < parcels.geojson
+$ geoq buffer --planar 25 < parcels_utm.geojson
+"#;
+
+pub const REPROJECT_ABOUT: &str = "Convert coordinates between coordinate reference systems.";
+pub const REPROJECT_AFTER_HELP: &str = r#"
+Converts the coordinates of each feature from the --from CRS to the --to
+CRS, keeping its id and properties. Supported are geographic lon/lat
+(EPSG:4326 or other 4xxx codes), Web Mercator (EPSG:3857) and WGS84 UTM
+zones (EPSG:326xx north and 327xx south). Latitudes beyond those of
+web map tiles, about 85.05 degrees, are clamped in Web Mercator.
+
+A straight segment in one CRS is usually curved in another, but only
+its ends are converted, so long segments cut corners: a line along a
+parallel, for instance, bows away from it in UTM. Give --densify to
+first split segments so that none is longer than that distance (in
+--units unless it gives its own, e.g. 1km), keeping the converted
+lines close to the path they took.
+
+Examples:
+
+$ geoq reproject --to EPSG:3857 < parcels.geojson
+$ geoq reproject --from EPSG:32618 --to EPSG:32617 --densify 1km < pipelines.geojson
+"#;
+
+pub const MATRIX_ABOUT: &str = "Distances between every pair of points of two datasets.";
+pub const MATRIX_AFTER_HELP: &str = r#"
+Reads points from STDIN, the rows of the matrix, and from the --other
+dataset, its columns, and writes the distance between each pair in the
+--units (meters by default). Points are labeled with their feature ids,
+or their position in their dataset, from 0, if they have none.
+
+By default the matrix is written as CSV, with a header of the --other
+points' labels and a row for each input point. With --json, it's written
+in long form instead, as one {"from", "to", "distance"} object per pair.
+
+With --max-distance, pairs further apart are left out: empty in CSV, and
+not written at all with --json.
+
+Both datasets are held in memory, so this is meant for hundreds or
+thousands of points rather than millions.
+
+Examples:
+
+$ printf '34,-118\n35,-118\n' > depots.txt
+$ printf '34,-118\n35,-118\n' | geoq --units km matrix --other depots.txt
+id,0,1
+0,0,110.93145952287868
+1,110.93145952287868,0
+
+$ geoq matrix --other depots.geojsonl --max-distance 50km --json < stops.geojsonl
+"#;
+
+pub const INTERPOLATE_ABOUT: &str =
+    "Reapportion counts from the input polygons to other zones by area of overlap.";
+pub const INTERPOLATE_AFTER_HELP: &str = r#"
+Reads source polygons from STDIN and writes each of the target zones
+from --other as a GeoJSON Feature, with a property for each --value:
+the sum, over the sources it overlaps, of the source's value times the
+fraction of the source's area inside the zone.
+
+This is areal interpolation for counts, such as population from census
+tracts to school districts. It assumes each count is spread evenly over
+its source polygon, so the total is kept when the zones cover the
+sources, and zones overlapping no source get 0. Missing and null values
+count as 0.
+
+Areas are geodesic, in square meters. The target zones are held in
+memory, with a spatial index, while the sources are streamed.
+
+Examples:
+
+$ geoq interpolate --other districts.geojsonl --value population < tracts.geojsonl
+
+$ geoq interpolate -o districts.geojsonl -v population -v households < tracts.geojsonl
+"#;
+
+pub const ZONAL_ABOUT: &str = "Summarize the pixels of a raster under each polygon.";
+pub const ZONAL_AFTER_HELP: &str = r#"
+Reads polygons from STDIN and writes each as a GeoJSON Feature with a
+property for each of the --stats of the raster's pixels under it:
+
+* count - Number of pixels
+* sum, mean, min, max - Of the pixels' values
+* median - Middle value, or the mean of the middle two
+* stddev - Population standard deviation
+
+A pixel is under a polygon if its center is, so polygons smaller than a
+pixel may have none, and get a count of 0 and null for the rest. Nodata
+pixels are left out. The raster is a GeoTIFF, read whole into memory,
+in any coordinate system 'geoq raster' supports.
+
+Examples:
+
+$ geoq zonal --raster dem.tif --stats mean,min,max < parcels.geojsonl
+
+$ geoq zonal -r landcover.tif -s count,median --nodata 0 < counties.geojsonl
+"#;
+
+pub const LOS_ABOUT: &str = "Check which points can see which others over a DEM's terrain.";
+pub const LOS_AFTER_HELP: &str = r#"
+Reads observer points from STDIN and writes a GeoJSON Feature for each
+pair of an observer and a point of the --other dataset: the sight line
+between them, with properties
+
+* from, to - The points' feature ids, or their position in their
+  dataset, from 0, if they have none
+* visible - Whether the terrain leaves the target in view, or null if
+  either point is off the DEM or on nodata
+* obstruction - The [lon, lat] of the first terrain along the line that
+  blocks the view, or null
+
+The DEM is a GeoTIFF of elevations in meters, read whole into memory, in
+any coordinate system 'geoq raster' supports. It's sampled along the
+geodesic between each pair about twice per pixel; pixels along the way
+that are nodata or off the DEM don't block the view. Over long lines,
+the earth's curvature is allowed for, with the usual 0.13 coefficient of
+atmospheric refraction.
+
+Examples:
+
+$ geoq los --dem dem.tif --other targets.geojsonl < observers.geojsonl
+
+$ geoq los --dem dem.tif -o towers.geojsonl --target-height 30m < summits.geojsonl \
+  | geoq grep true --property visible
+"#;
+
+pub const PLOT_ABOUT: &str = "Draw features as a map in the terminal.";
+pub const PLOT_AFTER_HELP: &str = r#"
+Reads all entities from STDIN and draws them, fitted to their combined
+extent, using braille characters (or plain ASCII with --ascii). Points
+are drawn as dots, and lines and polygons as outlines. The extent is
+printed below the map, as minLon,minLat,maxLon,maxLat.
+
+Handy for checking data over SSH, where 'geoq map' can't open a browser.
+
+Example:
+
+$ geoq plot --graticule < counties.geojson
+"#;
+
+pub const SERVE_ABOUT: &str = "Serve data over HTTP.";
+pub const SERVE_FGB_AFTER_HELP: &str = r#"
+Serves Mapbox Vector Tiles at /{z}/{x}/{y}.mvt, built on request from the
+features in a FlatGeobuf file. Each request looks up the features near
+the tile using the file's spatial index, then clips and encodes them
+the same way as 'geoq tiles build'. Empty tiles get a 204 response.
+
+A TileJSON description is served at /tiles.json, for map clients
+such as MapLibre. Its tile URLs use the host the client asked for, so
+they work when listening on 0.0.0.0.
+
+The file must have a spatial index, as written by 'geoq fgb write'.
+
+Example:
+
+$ geoq fgb write data.fgb < data.geojson
+$ geoq serve fgb data.fgb --port 8080
+"#;
+
+pub const GEOCODE_ABOUT: &str = "Look up coordinates for addresses using Nominatim.";
+pub const GEOCODE_AFTER_HELP: &str = r#"
+Outputs a Point Feature for each match, with the query and the
+place's display_name, address, osm_type and osm_id as properties.
+Queries with no match are reported on STDERR and skipped.
+
+Addresses are read from the positional argument, or from STDIN one per
+line. Use --property to geocode a property of each input feature, or
+--csv-column to geocode a column of CSV input; the feature's properties
+(or the row's columns) are kept alongside the address metadata.
+
+Requests are limited to --rate per second. The public server at
+nominatim.openstreetmap.org allows 1 per second, so use --endpoint to
+point at your own instance for bulk geocoding.
+
+Examples:
+
+$ geoq geocode "1600 Pennsylvania Ave, Washington DC"
+$ geoq geocode --csv-column address < stores.csv
+$ geoq geocode --property location --endpoint http://localhost:8080 < events.geojson
+"#;
+
+pub const REVERSE_GEOCODE_ABOUT: &str = "Attach place names and addresses to points.";
+pub const REVERSE_GEOCODE_AFTER_HELP: &str = r#"
+Looks up the place at each input point, and outputs the input as a
+Feature with the place's properties added. Lines and polygons are
+looked up by their centroid.
+
+By default, points are sent to a Nominatim server, which adds the
+display_name, address (including country and country_code), osm_type
+and osm_id properties. Requests are limited to --rate per second.
+Points with no result are passed through unchanged, with a warning on
+STDERR.
+
+With --boundaries, points are instead matched offline against a file of
+polygon features, such as Natural Earth admin boundaries. The properties
+of every boundary containing the point are added, in file order, so
+later boundaries take precedence for shared keys. Use --fields to pick
+which ones.
+
+Examples:
+
+$ geoq reverse-geocode < photos.geojson
+$ geoq reverse-geocode --boundaries countries.geojson --fields name,iso_a2 < points.geojson
+"#;
+
+pub const ELEVATION_ABOUT: &str = "Sample elevations from DEM tiles.";
+pub const ELEVATION_AFTER_HELP: &str = r#"
+Looks up the elevation in meters of each vertex of each input feature,
+from Terrarium-encoded PNG tiles such as the AWS Terrain Tiles dataset.
+Values are interpolated between neighboring pixels.
+
+By default the elevation is written to the "elevation" property: a
+number for Points, or an array with one value per vertex for other
+geometries. With --z, elevations are written as the Z coordinate of
+each vertex instead. Vertices outside the DEM's coverage get null
+(or are left 2D with --z).
+
+Use --densify to sample lines and polygons at regular intervals, rather
+than only at their existing vertices, e.g. for elevation profiles.
+
+Tiles are read from --dem, either a directory laid out as
+{z}/{x}/{y}.png, or a template path or URL. Downloaded tiles are cached
+for the duration of the command.
+
+Examples:
+
+$ geoq elevation --dem ./dem < peaks.geojson
+$ geoq elevation --z --densify 30 \
+    --dem "https://s3.amazonaws.com/elevation-tiles-prod/terrarium/{z}/{x}/{y}.png" < trail.geojson
+"#;
+
+pub const RASTER_ABOUT: &str = "Work with GeoTIFF rasters.";
+pub const RASTER_POLYGONIZE_AFTER_HELP: &str = r#"
+Outputs a Polygon Feature for each connected region of pixels sharing
+the same value, with that value as its "value" property. Pixels are
+connected through their edges, not their corners. Nodata pixels are
+skipped.
+
+Intended for categorical rasters such as landcover or zoning, where
+regions are large. Continuous data (e.g. elevation) gives a polygon
+per pixel.
+
+Rasters must be in geographic coordinates, Web Mercator (EPSG:3857) or
+WGS84 UTM (EPSG:326xx/327xx), and are reprojected to lon/lat.
+
+Example:
+
+$ geoq raster polygonize landcover.tif --band 1 > landcover.geojson
+"#;
+
+pub const CONTOUR_ABOUT: &str = "Generate contour lines from elevation data.";
+pub const CONTOUR_AFTER_HELP: &str = r#"
+Outputs a LineString Feature for each contour line, with its level as
+the "elevation" property. Levels are spaced --interval apart, offset
+from --base (default 0).
+
+With --raster, contours a GeoTIFF DEM, interpolating between pixel
+centers. Nodata pixels are left out, so lines end at gaps in the data.
+The raster's coordinate system is handled as for `geoq raster`.
+
+Otherwise, reads Points with elevations from STDIN, either as their Z
+coordinate or from the property given by --z-property, and contours
+the surface of triangles joining them (a Delaunay triangulation).
+
+Closed contours start and end at the same position. Lines follow each
+crossed pixel or triangle, so pipe them through `geoq simplify` for a
+lighter overlay.
+
+Examples:
+
+$ geoq contour --interval 10 --raster dem.tif > contours.geojson
+$ geoq contour --interval 5 --z-property depth < soundings.geojson
+"#;
+
+pub const ROUTE_ABOUT: &str = "Find routes between points using OSRM or Valhalla.";
+pub const ROUTE_AFTER_HELP: &str = r#"
+Reads LineStrings or MultiPoints from STDIN, and routes through their
+points in order: a 2-point line gives the route from its first point
+to its second. Outputs the route as a LineString Feature, keeping the
+input's properties, with added "duration" (in seconds) and "distance"
+(in meters) properties.
+
+Routes come from an OSRM (--engine osrm, the default) or Valhalla
+(--engine valhalla) server at --endpoint. The --profile is passed to
+OSRM as-is, and mapped to a Valhalla costing model (car: auto, bike:
+bicycle, foot: pedestrian).
+
+Inputs with no route between their points (e.g. separated by water)
+are skipped, with a warning on STDERR.
+
+Examples:
+
+$ echo 'LINESTRING (-77.03 38.89, -77.01 38.91)' | geoq route --endpoint http://localhost:5000
+$ geoq route --engine valhalla --profile bike --endpoint http://localhost:8002 < trips.geojson
+"#;
+
+pub const ISOCHRONE_ABOUT: &str = "Get areas reachable from points within travel times.";
+pub const ISOCHRONE_AFTER_HELP: &str = r#"
+Reads Points from STDIN, and outputs a Polygon Feature for each travel
+time in --minutes, covering the area reachable from the point within
+that time. Features keep the input's properties, with the time band
+added as the "minutes" property, and are output from the smallest band
+to the largest.
+
+Isochrones come from a Valhalla (--engine valhalla, the default) or
+openrouteservice (--engine ors) server at --endpoint. The --profile is
+mapped to the engine's own name for it (car is Valhalla's auto and
+openrouteservice's driving-car), or passed as-is otherwise. The hosted
+openrouteservice API requires --api-key.
+
+Examples:
+
+$ echo 'POINT (-77.03 38.89)' | geoq isochrone --minutes 10,20,30 --endpoint http://localhost:8002
+$ geoq isochrone --minutes 15 --engine ors --profile foot \
+    --endpoint https://api.openrouteservice.org --api-key $ORS_KEY < stations.geojson
+"#;
+
+pub const OGCAPI_ABOUT: &str = "Read features from OGC API - Features and WFS servers.";
+pub const OGCAPI_READ_AFTER_HELP: &str = r#"
+Requests pages of features from a server until there are none left,
+outputting each feature as a line of GeoJSON.
+
+For OGC API - Features, give the URL of a collection (or of its items).
+Pages are followed through the "next" links of each response.
+
+URLs with a service=WFS parameter are read as WFS 2.0 GetFeature
+requests, and should give the layer as typeNames. Features are
+requested as GeoJSON, in pages of --page-size using startIndex. Other
+parameters in the URL (e.g. srsName or a filter) are kept.
+
+Examples:
+
+$ geoq ogcapi read https://example.com/ogcapi/collections/parcels
+$ geoq ogcapi read --bbox -77.1,38.8,-76.9,39.0 \
+    "https://example.com/geoserver/wfs?service=WFS&typeNames=roads:centerlines"
+"#;
+
+pub const BENCH_ABOUT: &str = "Measure geoq's throughput on an input file.";
+pub const BENCH_AFTER_HELP: &str = r#"
+Runs a file of input lines through geoq in three stages, reporting
+lines per second for each:
+
+parse         parsing lines into entities
+parse+handle  parsing, then handling each entity as --command does,
+              on a single thread (for wkt, centroid, gj geom and gj f)
+end-to-end    running geoq --command with the file on stdin and its
+              output discarded, as a separate process using all of
+              its usual threads
+
+The in-process stages also report heap allocations per line, both the
+number made and the bytes they asked for, when geoq is built with
+--features bench. Other builds don't count allocations, and show -.
+Each stage is run --iterations times, and the fastest is reported.
+
+Use it to compare machines, or to check a new release for performance
+regressions against the same input.
+
+Examples:
+
+$ geoq bench --input parcels.geojsonl
+$ geoq bench --input parcels.geojsonl --command "gj f" --iterations 5
+"#;
+
+pub const PIPE_ABOUT: &str = "Chain geoq commands in one process.";
+pub const PIPE_AFTER_HELP: &str = r#"
+Runs input from STDIN through a pipeline of geoq commands, separated by
+|, as if each were piped into the next. Entities are handed from stage
+to stage as they are, without being written out as GeoJSON and parsed
+again, and only the last stage's output is written.
+
+Each stage takes the same arguments as its command. Stages can be:
+
+centroid
+simplify
+filter (any of its predicates)
+transform
+fgb write, as the last stage, to write the output to a FlatGeobuf file
+
+With --file, the stages are read from a file instead, one per line.
+Lines may be written as a YAML list, and lines starting with # are
+ignored.
+
+Examples:
+
+$ geoq pipe 'simplify 0.001 | filter type Polygon | fgb write out.fgb' < parcels.geojson
+$ geoq pipe 'simplify 0.001 | filter geometry --non-empty | centroid' < parcels.geojson
+$ geoq pipe "filter intersects 'POLYGON ((...))' | fgb write clipped.fgb" < parcels.geojson
+
+$ cat pipeline.yaml
+- simplify 0.001
+- filter geometry --non-empty
+- fgb write parcels.fgb
+$ geoq pipe --file pipeline.yaml < parcels.geojson
+"#;
+
+pub const TRANSFORM_ABOUT: &str =
+    "Compute new geometries and properties for features with expressions.";
+pub const TRANSFORM_AFTER_HELP: &str = r#"
+Runs a program of assignments, separated by ;, on each feature from
+STDIN. Each assignment sets the geometry (geom) or a property
+(props.<name>, or props.'<name>' for names with spaces), and sees the
+assignments made before it.
+
+Expressions are as for props set, and can also read properties as
+props.<name> (null when missing). Functions:
+
+  - area(geom)           Approximate geodesic area in square meters
+  - length(geom)         Geodesic length (or perimeter) in meters
+  - centroid_x(geom)     Centroid longitude
+  - centroid_y(geom)     Centroid latitude
+  - vertex_count(geom)   Number of coordinates
+  - buffer(geom, meters) The area within a distance of the geometry,
+                         approximated for distances of up to a few
+                         hundred km. Negative distances shrink polygons.
+                         The distance is in --units, or can be given
+                         with its own, like "500ft".
+  - centroid(geom)       Centroid point
+  - simplify(geom, eps)  Geometry simplified as by geoq simplify
+  - color(value)         A color for a category, the same for equal
+                         values, e.g. color(props.landuse)
+
+Examples:
+
+$ geoq transform 'geom = buffer(geom, 100); props.area = area(geom)' < stops.geojson
+$ geoq transform 'props.density = props.pop / (area(geom) / 1e6)' < tracts.geojson
+"#;
+
+pub const GJ_GEOM_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 9q5 | geoq gj geom
+{"coordinates":[[[-119.53125,33.75],[-118.125,33.75],[-118.125,35.15625],[-119.53125,35.15625],[-119.53125,33.75]]],"type":"Polygon"}
+"#;
+
+pub const GJ_F_AFTER_HELP: &str = r#"
+With --repair-rings, polygons are rebuilt from their rings by which
+contain which, as 'geoq fgb write --repair-rings' does.
+
+Examples:
+
+$ echo 'POINT (1 2)' | geoq gj f
+{"geometry":{"coordinates":[1.0,2.0],"type":"Point"},"properties":{},"type":"Feature"}
+
+$ echo 'POLYGON ((1 1, 1 2, 2 2, 2 1, 1 1), (0 0, 3 0, 3 3, 0 3, 0 0))' | geoq gj f --repair-rings
+{"geometry":{"coordinates":[[[0.0,0.0],[3.0,0.0],[3.0,3.0],[0.0,3.0],[0.0,0.0]],[[1.0,1.0],[1.0,2.0],[2.0,2.0],[2.0,1.0],[1.0,1.0]]],"type":"Polygon"},"properties":{},"type":"Feature"}
+"#;
+
+pub const GJ_FC_AFTER_HELP: &str = r#"
+Reads all of STDIN before writing the collection.
+
+Example:
+
+$ cat points.wkt | geoq gj fc > points.geojson
+"#;
+
+pub const GH_POINT_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 34.0522,-118.2437 | geoq gh point 5
+9q5ct
+"#;
+
+pub const GH_COVERING_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 9q5 | geoq gh covering 2
+9q
+9m
+
+$ cat county.geojson | geoq gh covering 5 --original | geoq map
+"#;
+
+pub const GH_CHILDREN_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 9q5 | geoq gh children | head -3
+9q50
+9q51
+9q52
+"#;
+
+pub const GH_ROOTS_AFTER_HELP: &str = r#"
+Example:
+
+$ geoq gh roots | head -3
+0
+1
+2
+"#;
+
+pub const GH_ENCODE_LONG_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 4064984913515641 | geoq gh encode-long
+03mj2n6wm53t
+"#;
+
+pub const GH_NEIGHBORS_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 9q5 | geoq gh neighbors --exclude | head -3
+9q7
+9qk
+9qh
+"#;
+
+pub const FILTER_INTERSECTS_AFTER_HELP: &str = r#"
+Examples:
+
+$ cat parcels.geojson | geoq filter intersects 9q5
+
+$ cat parcels.geojson | geoq filter intersects --query-file flood-zones.geojson
+
+The query's edges are indexed once before reading input, so large queries
+like country boundaries stay fast to test each feature against.
+"#;
+
+pub const FILTER_CONTAINS_AFTER_HELP: &str = r#"
+Examples:
+
+$ cat stops.geojson | geoq filter contains 'POLYGON ((-118.3 34, -118.2 34, -118.2 34.1, -118.3 34.1, -118.3 34))'
+
+$ cat stops.geojson | geoq filter contains --negate --query-file city.geojson
+"#;
+
+pub const MEASURE_COORDS_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 'LINESTRING (0 0, 1 1, 2 0)' | geoq measure coord-count
+3
+
+$ echo 'LINESTRING (0 0, 1 1, 2 0)' | geoq measure coord-count --json
+{"id":null,"units":"coordinates","value":3}
+
+$ cat parcels.geojson | geoq measure coord-count --as-property vertices
+"#;
+
+pub const MEASURE_AREA_AFTER_HELP: &str = r#"
+Examples:
+
+$ echo 'POLYGON ((0 0, 1 0, 1 1, 0 1, 0 0))' | geoq measure area
+12391399902.071104
+
+$ cat parcels.geojson | geoq measure area --as-property area_m2
+"#;
+
+pub const MEASURE_LENGTH_AFTER_HELP: &str = r#"
+Examples:
+
+$ echo 'LINESTRING (0 0, 1 0)' | geoq measure length
+111319.49079327357
+
+$ cat trails.geojson | geoq measure length --as-property length_m
+
+With --rhumb, segments are measured as rhumb lines of constant bearing,
+as a course plotted on a Mercator chart is:
+
+$ echo 'LINESTRING (-5.7 50.07, -3 58.64)' | geoq measure length --rhumb
+"#;
+
+pub const MEASURE_BEARING_AFTER_HELP: &str = r#"
+Bearings are in degrees clockwise from north, from 0 up to 360. By
+default each is the initial bearing of the great circle to the QUERY
+point, which changes along the way; with --rhumb it's the constant
+bearing of the rhumb line, which is held the whole way.
+
+As with 'measure distance', each bearing is followed by the input.
+
+Examples:
+
+$ echo 'POINT (1.338 51.127)' | geoq measure bearing 'POINT (1.853 50.964)' --rhumb
+116.72185980258678	POINT(1.338 51.127)
+
+$ geoq measure bearing 40.7,-74 --as-property bearing < ports.geojsonl
+"#;
+
+pub const DESTINATION_ABOUT: &str =
+    "Move points a distance on a bearing, along great circles or rhumb lines.";
+pub const DESTINATION_AFTER_HELP: &str = r#"
+Reads points from STDIN and writes each as a GeoJSON Feature at the
+point reached by going --distance (in --units, or with a suffix such as
+km) on --bearing, in degrees clockwise from north.
+
+By default the point sets off on the bearing along a great circle, whose
+bearing changes along the way. With --rhumb it holds the bearing the
+whole way instead, along a rhumb line, as a ship steering a constant
+course does; rhumb lines are straight on a Mercator chart. Due north or
+south, a rhumb line goes over the pole and down the other side, but on
+any other bearing it spirals into the pole without crossing it, so a
+--distance which would reach the pole is an error.
+
+With --line, the path from each point to its destination is written as
+a LineString instead, with vertices at most --densify apart so that it
+keeps its shape when drawn in lon/lat.
+
+Both are worked on a sphere of the earth's mean radius.
+
+Examples:
+
+$ echo 'POINT (0 0)' | geoq destination --bearing 45 --distance 100km --rhumb
+
+$ geoq destination --bearing 270 --distance 500mi --rhumb --line < ports.geojsonl | geoq map
+"#;
+
+pub const SHP_AFTER_HELP: &str = r#"
+A shape's rings are sorted into polygons by which contain which, since
+shapefiles list all of a shape's rings together: a shape with several
+outer rings becomes a MultiPolygon, and holes go with the shell around
+them whatever order they're listed in.
+
+Example:
+
+$ geoq shp counties.shp > counties.geojson
+"#;
+
+pub const FGB_WRITE_AFTER_HELP: &str = r#"
+With --repair-rings, each polygon's rings are sorted out by which
+contain which before it's written: a ring inside a shell becomes its
+hole, whatever order they were listed in, rings nested deeper become
+shells of their own, and shells are wound counterclockwise and holes
+clockwise. Use it for data converted from shapefiles by tools which
+kept their rings in order.
+
+Examples:
+
+$ cat parcels.geojson | geoq fgb write parcels.fgb
+
+$ cat parcels.geojson | geoq fgb write --repair-rings parcels.fgb
+
+$ cat parcels.geojson | geoq fgb write s3://bucket/parcels.fgb
+"#;
+
+pub const FGB_READ_AFTER_HELP: &str = r#"
+Example:
+
+$ geoq fgb read parcels.fgb --bbox -118.5,33.7,-117.9,34.3
+"#;
+
+pub const H3_POINT_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 34.0522,-118.2437 | geoq h3 point 7
+8729a1d75ffffff
+"#;
+
+pub const H3_HIERARCHY_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 34.0522,-118.2437 | geoq h3 hierarchy | head -3
+8029fffffffffff
+8129bffffffffff
+8229a7fffffffff
+"#;
+
+pub const H3_TO_STR_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 608718350972878847 | geoq h3 to-str
+8729a1d75ffffff
+"#;
+
+pub const H3_FROM_STR_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 8729a1d75ffffff | geoq h3 from-str
+608718350972878847
+"#;
+
+pub const H3_CHILDREN_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 8729a1d75ffffff | geoq h3 children | head -2
+8829a1d751fffff
+8829a1d753fffff
+"#;
+
+pub const H3_PARENT_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 8729a1d75ffffff | geoq h3 parent
+8629a1d77ffffff
+"#;
+
+pub const H3_RESOLUTION_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 8729a1d75ffffff | geoq h3 resolution
+7
+"#;
+
+pub const H3_GRID_DISK_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 8729a1d75ffffff | geoq h3 grid-disk 2 | geoq h3 boundary | geoq map
+"#;
+
+pub const H3_COVERING_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 9q5 | geoq h3 covering 4 --compact-cells | head -3
+8429a57ffffffff
+8429a13ffffffff
+8429ac5ffffffff
+"#;
+
+pub const H3_BOUNDARY_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 8729a1d75ffffff | geoq h3 boundary | geoq map
+"#;
+
+pub const H3_COMPACT_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 8729a1d75ffffff | geoq h3 children | geoq h3 compact
+8729a1d75ffffff
+"#;
+
+pub const H3_UNCOMPACT_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 8629a1d77ffffff | geoq h3 uncompact 7 | head -2
+8729a1d70ffffff
+8729a1d71ffffff
+"#;
+
+pub const TILES_LIST_AFTER_HELP: &str = r#"
+Example:
+
+$ geoq tiles list parcels.pmtiles | geoq tile bbox | geoq map
+"#;
+
+pub const WKT_AFTER_HELP: &str = r#"
+Example:
+
+$ echo 9q5 | geoq wkt
+POLYGON((-119.53125 33.75,-118.125 33.75,-118.125 35.15625,-119.53125 35.15625,-119.53125 33.75))
+"#;
+
+pub const MAP_AFTER_HELP: &str = r##"
+Opens the features from STDIN on geojson.io in a web browser.
+
+Features are drawn with the simplestyle-spec properties they have,
+such as marker-color, stroke, stroke-width, fill and fill-opacity.
+Each --style KEY:EXPRESSION sets one of these properties on every
+feature from an expression, as for geoq transform, so that e.g.
+categories can be told apart. Expressions giving null leave the
+property as it was. The color(value) function picks a distinct color
+for each value.
+
+Style properties: title, description, marker-size, marker-symbol,
+marker-color, stroke, stroke-opacity, stroke-width, fill, fill-opacity
+
+Examples:
+
+$ echo 9q5 | geoq gh neighbors | geoq map
+$ geoq map --style 'fill:color(props.landuse)' --style 'fill-opacity:0.8' < parcels.geojson
+$ geoq map --style 'stroke:"#e15759"' --style 'stroke-width:props.lanes' < roads.geojson
+"##;
+
+pub const SNAP_TO_ABOUT: &str =
+    "Move points onto the nearest line of another dataset, such as GPS fixes onto roads.";
+pub const SNAP_TO_AFTER_HELP: &str = r#"
+Reads points from STDIN, and moves each onto the nearest point of the
+lines (or polygon outlines) in --other, if one is within
+--max-distance. The distance moved (in --units) is set as the property
+snap_distance, and the id of the line's feature as snap_id. Points with
+no line close enough are left where they are, with both set to null.
+
+The other dataset is read once into an index, and can be in any format
+geoq reads. See "Second Datasets" in the README.
+
+Example:
+
+$ geoq snap-to --other roads.geojsonl --max-distance 50m < fixes.geojson
+"#;
+
+pub const TRAJECTORY_ABOUT: &str =
+    "Build LineString tracks from timestamped points, such as GPS fixes.";
+pub const TRAJECTORY_AFTER_HELP: &str = r#"
+Reads points from STDIN, groups them by the --group-by property, and
+joins each group's points into a LineString in order of their --order-by
+times. Timestamps can be ISO 8601 times or epoch numbers, as for
+geoq filter time. With --max-gap, a group's track is split wherever
+more time than that passes between points, and tracks of a single point
+are left out.
+
+Each track has the group's --group-by value, and the properties:
+
+start_time, end_time - the times of its first and last points
+duration             - seconds from the first point to the last
+distance             - its geodesic length, in --units
+speed, max_speed     - its average speed, and the fastest between two
+                       points, in --units per second
+coordTimes           - the time of each of its points, as togeojson
+                       gives for GPX tracks
+
+Example:
+
+$ geoq trajectory --group-by device_id --order-by timestamp --max-gap 5min < fixes.geojson
+"#;
+
+pub const TRAJECTORY_ANALYZE_AFTER_HELP: &str = r#"
+Reads tracks from STDIN, as built by geoq trajectory: LineStrings with
+the time of each point in a coordTimes property. Each track is split
+into the stretches where it moves, output as tracks with the same
+properties as geoq trajectory gives, and the places it stops, output as
+points in the middle of where it waited. A track stops where it goes no
+faster than --stop-speed for at least --stop-duration.
+
+Each part has the track's other properties, a state of "moving" or
+"stopped", and its start_time, end_time and duration (in seconds). For
+stops, the duration is how long the track dwelled there.
+
+Example:
+
+$ geoq trajectory --group-by device_id --order-by timestamp < fixes.geojson | \
+    geoq trajectory analyze --stop-speed 0.5m/s --stop-duration 3min
+"#;
+
+pub const TRAJECTORY_RESAMPLE_AFTER_HELP: &str = r#"
+Reads tracks from STDIN, as built by geoq trajectory: LineStrings with
+the time of each point in a coordTimes property. Each is replaced with
+a track of its positions every --every from its start, found along the
+straight line between the points before and after. Intervals of time
+place points at even times, such as every 10s, and intervals of distance
+at even distances along the track, such as every 100m. Either kind of
+interval needs its unit; minutes are min, as m is meters.
+
+Resampled tracks have the same properties as geoq trajectory gives,
+along with the input track's others. Tracks shorter than one interval
+are left out.
+
+Example:
+
+$ geoq trajectory resample --every 10s < tracks.geojson
+"#;
+
+pub const GRATICULE_ABOUT: &str =
+    "Output lines of latitude and longitude, as a reference layer for plots and maps.";
+pub const GRATICULE_AFTER_HELP: &str = r#"
+Outputs a LineString feature for each parallel and meridian at a
+multiple of --interval degrees within --bbox: first the parallels from
+south to north, then the meridians from west to east. Each has the
+properties kind (parallel or meridian), value (its latitude or
+longitude) and label, e.g. 30°N.
+
+With --frame, the outline of the bbox follows, with kind frame.
+
+Examples:
+
+$ (geoq graticule --interval 30 --frame; cat countries.geojson) | geoq map
+$ geoq graticule --interval 1 --bbox=-125,32,-114,42
+"#;
+
+pub const STYLE_ABOUT: &str =
+    "Color features by a property with simplestyle properties, for quick styled GeoJSON to share or view with geoq map.";
+pub const STYLE_AFTER_HELP: &str = r##"
+Sets fill and stroke on polygons, stroke on lines, and marker-color on
+points, from the value of the --color-by property. Features without the
+property (or with null) are left as they are.
+
+When every value is a number, colors run along the palette from the
+smallest value to the largest. Otherwise, or with --categorical, each
+distinct value gets a color of its own, assigned in sorted order.
+
+All features are read into memory before any are written.
+
+Palettes:
+
+  tableau10, set1       Distinct colors for categories. Past the last
+                        color, they repeat.
+  viridis, magma,       Colors from low to high, for numbers. With
+  blues, reds           categories, they're spread across the palette.
+
+A list of colors like #1b9e77,#d95f02 is used in turn for categories,
+or blended between for numbers.
+
+Examples:
+
+$ geoq style --color-by landuse < parcels.geojson | geoq map
+$ geoq style --color-by density --palette magma < tracts.geojson > styled.geojson
+$ geoq style -c zone --palette '#e41a1c,#377eb8,#4daf4a' < zones.geojson
+"##;
+
+pub const HASH_ABOUT: &str =
+    "Output a stable fingerprint of each feature's geometry, e.g. for deduplicating across runs or as cache keys.";
+pub const HASH_AFTER_HELP: &str = r#"
+Algorithms:
+
+  md5-wkb           MD5 of the geometry as WKB, in hex. Equal only for
+                    exactly equal geometries, and matches e.g.
+                    md5(ST_AsBinary(geom)) in PostGIS.
+  geohash-centroid  Geohash of the centroid, at --level. Geometries
+                    close together share a hash, or its prefix.
+  simhash           64-bit simhash of the vertices, in hex. Geometries
+                    sharing most of their vertices get hashes differing
+                    in only a few bits.
+
+Examples:
+
+$ echo 'POINT (1 2)' | geoq hash
+4ddc678d472071b63dd260ae7d7cd0eb
+
+$ echo 'POINT (-122.4 37.8)' | geoq hash --algorithm geohash-centroid --level 6
+9q8zn9
+
+$ cat parcels.geojson | geoq hash --algorithm simhash --as-property simhash
+"#;
+
+pub const COMPLETIONS_ABOUT: &str = "Generate shell completions for geoq.";
+pub const COMPLETIONS_AFTER_HELP: &str = r#"
+Writes a completion script for the given shell to STDOUT.
+
+Examples:
+
+Bash, in ~/.bashrc:
+$ source <(geoq completions bash)
+
+Zsh, with a directory of your own on $fpath:
+$ geoq completions zsh > ~/.zfunc/_geoq
+
+Fish:
+$ geoq completions fish > ~/.config/fish/completions/geoq.fish
+"#;
@@ -0,0 +1,2328 @@
+#![feature(try_blocks)]
+pub mod geoq;
+use geoq::command::{self, Command, EntityStream, OutputSink};
+use geoq::commands;
+use geoq::config;
+use geoq::entity;
+use geoq::error::Error;
+use geoq::input::{self, Parsing};
+use geoq::net;
+use geoq::on_error::{self, Policy};
+use geoq::other;
+use geoq::output::{self, Style};
+use geoq::par;
+use geoq::progress;
+use geoq::report;
+use geoq::text;
+use geoq::units;
+
+use clap::{App, AppSettings, Arg, ArgMatches, Shell, SubCommand};
+use std::process;
+
+#[cfg(feature = "bench")]
+#[global_allocator]
+static ALLOCATOR: geoq::allocations::Counting = geoq::allocations::Counting;
+
+// Parallelism for the per-entity commands, from the global flags
+fn par_options(matches: &ArgMatches) -> Result<par::ParOptions, Error> {
+    let mut opts = par::ParOptions::new().with_unordered(config::flag(matches, "unordered")?);
+    if let Some(jobs) = config::value(matches, "jobs") {
+        opts.jobs = par::parse_count("jobs", &jobs)?;
+    }
+    if let Some(size) = config::value(matches, "batch-size") {
+        opts.batch_size = par::parse_count("lines per batch", &size)?;
+    }
+    if let Some(lines) = config::value(matches, "buffer-lines") {
+        opts.buffer_lines = par::parse_count("buffered lines", &lines)?;
+    }
+    if let Some(bytes) = config::value(matches, "max-buffered-bytes") {
+        opts.max_buffered_bytes = par::parse_bytes(&bytes)?;
+    }
+    if let Some(timeout) = config::value(matches, "timeout-per-entity") {
+        opts.entity_timeout = Some(par::parse_duration(&timeout)?);
+    }
+    Ok(opts)
+}
+
+fn run(matches: ArgMatches, extensions: &[Box<dyn Command>]) -> Result<(), Error> {
+    config::load()?;
+    match config::value(&matches, "errors").as_deref() {
+        Some("json") => report::set_json(true),
+        Some("text") | None => report::set_json(false),
+        Some(e) => {
+            return Err(Error::InvalidInput(format!(
+                "Unknown error format: {} -- expected text or json",
+                e
+            )))
+        }
+    }
+    entity::set_strip_foreign(config::flag(&matches, "strip-foreign")?);
+    let par_opts = par_options(&matches)?;
+    if let Some(retries) = config::value(&matches, "http-retries") {
+        net::set_retries(retries.parse::<usize>().map_err(|_| {
+            Error::InvalidNumberFormat(format!("Expected a number of retries: {}", retries))
+        })?);
+    }
+    if let Some(requests) = config::value(&matches, "http-concurrency") {
+        net::set_concurrency(par::parse_count("concurrent requests", &requests)?);
+    }
+    if let Some(bytes) = config::value(&matches, "other-max-memory") {
+        other::set_max_memory(par::parse_bytes(&bytes)?);
+    }
+    if let Some(unit) = config::value(&matches, "units") {
+        units::set_units(unit.parse()?);
+    }
+    if let Some(format) = config::value(&matches, "format") {
+        output::set_format(format.parse()?);
+    }
+    if let Some(digits) = config::value(&matches, "precision") {
+        output::set_precision(output::parse_precision(&digits)?);
+    }
+    output::set_style(if config::flag(&matches, "pretty")? {
+        Style::Pretty
+    } else if config::flag(&matches, "compact")? {
+        Style::Compact
+    } else {
+        Style::Default
+    });
+    if config::flag(&matches, "progress")? {
+        progress::start();
+    }
+    input::set_parsing(if config::flag(&matches, "strict")? {
+        Parsing::Strict
+    } else if config::flag(&matches, "lenient")? {
+        Parsing::Lenient
+    } else {
+        Parsing::Default
+    });
+    let policy: Policy = config::value(&matches, "on-error").unwrap().parse()?;
+    on_error::configure(policy, config::value(&matches, "error-file").as_deref())?;
+    let res = match matches.subcommand() {
+        ("wkt", Some(_)) => commands::wkt::run(&par_opts),
+        ("read", Some(_)) => commands::read::run(&par_opts),
+        ("gj", Some(m)) => commands::geojson_cmd::run(m, &par_opts),
+        ("gh", Some(m)) => commands::geohash::run(m, &par_opts),
+        ("map", Some(m)) => commands::map::run(m),
+        ("snip", Some(_)) => commands::snip::run(),
+        ("filter", Some(m)) => commands::filter::run(m, &par_opts),
+        ("json", Some(m)) => commands::json::run(m),
+        ("centroid", Some(_)) => commands::centroid::run(),
+        ("whereami", Some(_)) => commands::whereami::run(),
+        ("simplify", Some(m)) => commands::simplify::run(m, &par_opts),
+        ("measure", Some(m)) => commands::measure::run(m, &par_opts),
+        ("bbox", Some(m)) => commands::bbox::run(m, &par_opts),
+        ("shp", Some(m)) => commands::shp::run(m),
+        ("fgb", Some(m)) => commands::fgb::run(m),
+        ("h3", Some(m)) => commands::h3::run(m, &par_opts),
+        ("props", Some(m)) => commands::props::run(m, &par_opts),
+        ("grep", Some(m)) => commands::grep::run(m, &par_opts),
+        ("id", Some(m)) => commands::id::run(m, &par_opts),
+        ("hash", Some(m)) => commands::hash::run(m, &par_opts),
+        ("style", Some(m)) => commands::style::run(m),
+        ("graticule", Some(m)) => commands::graticule::run(m),
+        ("snap-to", Some(m)) => commands::snap::run(m, &par_opts),
+        ("trajectory", Some(m)) => commands::trajectory::run(m, &par_opts),
+        ("dedup", Some(m)) => commands::dedup::run(m),
+        ("diff", Some(m)) => commands::diff::run(m),
+        ("sort", Some(m)) => commands::sort::run(m),
+        ("head", Some(m)) => commands::head::run(m),
+        ("tail", Some(m)) => commands::tail::run(m),
+        ("skip", Some(m)) => commands::skip::run(m),
+        ("sample", Some(m)) => commands::sample::run(m),
+        ("aggregate", Some(m)) => commands::aggregate::run(m),
+        ("tile", Some(m)) => commands::tile::run(m, &par_opts),
+        ("quadkey", Some(m)) => commands::quadkey::run(m, &par_opts),
+        ("tiles", Some(m)) => commands::tiles::run(m),
+        ("partition", Some(m)) => commands::partition::run(m),
+        ("split-output", Some(m)) => commands::split_output::run(m),
+        ("cat", Some(m)) => commands::cat::run(m),
+        ("schema", Some(m)) => commands::schema::run(m),
+        ("estimate", Some(m)) => commands::estimate::run(m),
+        ("buffer", Some(m)) => commands::buffer::run(m, &par_opts),
+        ("reproject", Some(m)) => commands::reproject::run(m, &par_opts),
+        ("matrix", Some(m)) => commands::matrix::run(m),
+        ("destination", Some(m)) => commands::destination::run(m, &par_opts),
+        ("interpolate", Some(m)) => commands::interpolate::run(m),
+        ("zonal", Some(m)) => commands::zonal::run(m, &par_opts),
+        ("los", Some(m)) => commands::los::run(m),
+        ("plot", Some(m)) => commands::plot::run(m),
+        ("serve", Some(m)) => commands::serve::run(m),
+        ("geocode", Some(m)) => commands::geocode::run(m),
+        ("reverse-geocode", Some(m)) => commands::reverse_geocode::run(m, &par_opts),
+        ("elevation", Some(m)) => commands::elevation::run(m),
+        ("raster", Some(m)) => commands::raster::run(m),
+        ("contour", Some(m)) => commands::contour::run(m),
+        ("route", Some(m)) => commands::route::run(m),
+        ("isochrone", Some(m)) => commands::isochrone::run(m),
+        ("ogcapi", Some(m)) => commands::ogcapi::run(m),
+        ("bench", Some(m)) => commands::bench::run(m),
+        ("pipe", Some(m)) => commands::pipe::run(m, &par_opts),
+        ("transform", Some(m)) => commands::transform::run(m, &par_opts),
+        ("completions", Some(m)) => commands::completions::run(m),
+        (name, Some(m)) => match extensions.iter().find(|c| c.name() == name) {
+            Some(c) => {
+                let mut out = OutputSink::stdout();
+                c.run(m, EntityStream::stdin(par_opts), &mut out)
+                    .and_then(|_| out.flush())
+            }
+            None => Err(Error::UnknownCommand),
+        },
+        _ => Err(Error::UnknownCommand),
+    };
+    progress::finish();
+    let finished = on_error::finish();
+    res.and(finished)
+}
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub fn app() -> App<'static, 'static> {
+    let repair_rings = Arg::with_name("repair-rings")
+        .long("repair-rings")
+        .help("Rebuild polygons from their rings by which contain which, fixing holes listed before shells and wrong winding");
+
+    let geojson = SubCommand::with_name("gj")
+        .about("Output features as GeoJSON")
+        .subcommand(
+            SubCommand::with_name("geom")
+                .about("Output entity as a GeoJSON geometry")
+                .after_help(text::GJ_GEOM_AFTER_HELP),
+        )
+        .subcommand(
+            SubCommand::with_name("f")
+                .about("Output entity as a GeoJSON Feature")
+                .after_help(text::GJ_F_AFTER_HELP)
+                .arg(repair_rings.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("fc")
+                .about("Collect all given entities into a GeoJSON Feature Collection")
+                .after_help(text::GJ_FC_AFTER_HELP),
+        );
+
+    let geohash = SubCommand::with_name("gh")
+        .about("Work with geohashes")
+        .subcommand(
+            SubCommand::with_name("point")
+                .about("Output base 32 Geohash for a given Lat,Lon").after_help(text::GH_POINT_AFTER_HELP)
+                .arg(
+                    Arg::with_name("level")
+                        .help("Characters of geohash precision")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("covering")
+                .about("Output the set of geohashes at the given level which covers the given entity.").after_help(text::GH_COVERING_AFTER_HELP)
+                .arg(
+                    Arg::with_name("level")
+                        .help("Characters of geohash precision")
+                        .required(true)
+                        .index(1),
+                ).arg(Arg::with_name("original")
+                      .long("original")
+                      .short("o")
+                      .help("Also print the query entity in the output.\nUseful for mapping a geometry along with its covering Geohashes.")),
+        )
+        .subcommand(SubCommand::with_name("compact")
+                    .about("Reduce a set of geohashes to the minimal mixed-precision set covering the same area")
+                    .after_help(text::GH_COMPACT_AFTER_HELP))
+        .subcommand(SubCommand::with_name("decode")
+                    .about("Decode geohashes to their center point, with error bounds")
+                    .after_help(text::GH_DECODE_AFTER_HELP)
+                    .arg(Arg::with_name("bbox")
+                         .long("bbox")
+                         .help("Output each geohash's bounding box polygon instead of its center point")))
+        .subcommand(SubCommand::with_name("cover")
+                    .about("Cover the given entity with mixed-precision geohashes, within a cell budget")
+                    .after_help(text::GH_COVER_AFTER_HELP)
+                    .arg(Arg::with_name("max-cells")
+                         .long("max-cells")
+                         .takes_value(true)
+                         .default_value("1024")
+                         .help("Maximum number of geohashes to output per entity"))
+                    .arg(Arg::with_name("max-level")
+                         .long("max-level")
+                         .takes_value(true)
+                         .default_value("12")
+                         .help("Finest geohash precision to use")))
+        .subcommand(SubCommand::with_name("children").about("Get children for the given geohash").after_help(text::GH_CHILDREN_AFTER_HELP))
+        .subcommand(SubCommand::with_name("roots").about("List the Base32 Geohash root characters").after_help(text::GH_ROOTS_AFTER_HELP))
+        .subcommand(SubCommand::with_name("encode-long").about("Convert a 64 bit geohash from Base 10 numeric representation to Base 32.").after_help(text::GH_ENCODE_LONG_AFTER_HELP))
+        .subcommand(SubCommand::with_name("neighbors")
+                    .about("Get neighbors of the given Geohash").after_help(text::GH_NEIGHBORS_AFTER_HELP)
+                    .arg(Arg::with_name("exclude")
+                         .long("exclude")
+                         .short("e")
+                         .help("Exclude the given geohash from its neighbors.\nBy default it will be included in the output,\ngiving a 3x3 grid centered on the provided geohash.")));
+
+    let filter = SubCommand::with_name("filter")
+        .about("Select features based on geospatial predicates")
+        .after_help(text::FILTER_AFTER_HELP)
+        .arg(Arg::with_name("query-file")
+             .help("Input file for reading query feature(s).")
+             .takes_value(true)
+             .global(true)
+             .long("query-file")
+             .short("q"))
+        .arg(Arg::with_name("negate")
+             .help("Negate the filter, so intersects becomes 'not intersects', etc.")
+             .global(true)
+             .long("negate")
+             .short("n"))
+        .subcommand(
+            SubCommand::with_name("intersects")
+                .about("Output only entities (from STDIN) which intersect a QUERY entity (as command-line ARG)").after_help(text::FILTER_INTERSECTS_AFTER_HELP)
+                .arg(Arg::with_name("query")
+                     .help("Entity to check intersections.\nMust be Lat/Lon, Geohash, WKT, or GeoJSON.")
+                     .index(1))
+        )
+        .subcommand(
+            SubCommand::with_name("contains")
+                .about("Output only entities (from STDIN) which fall within a QUERY entity (as command-line ARG)").after_help(text::FILTER_CONTAINS_AFTER_HELP)
+                .arg(
+                    Arg::with_name("query")
+                        .help("Entity to check intersections.\nMust be Geohash, WKT, or GeoJSON.\nMust be a POLYGON or MULTIPOLYGON.")
+                        .index(1)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("dwithin")
+                .about("Output only points (from STDIN) which fall within a QUERY entity (as command-line ARG)")
+                .after_help(text::FILTER_DWITHIN_AFTER_HELP)
+                .arg(
+                    Arg::with_name("query")
+                        .help("Feature(s) to check intersections.\nMust be Geohash, WKT, or GeoJSON.")
+                        .index(1)
+                )
+                .arg(
+                    Arg::with_name("radius")
+                        .help("Radius, in meters or --units, or with units like 5km")
+                        .takes_value(true)
+                        .required(true)
+                        .long("radius")
+                        .short("r")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("geometry")
+                .about("Output only entities (from STDIN) with non-empty (or empty) geometries")
+                .after_help(text::FILTER_GEOMETRY_AFTER_HELP)
+                .arg(Arg::with_name("non-empty")
+                     .long("non-empty")
+                     .conflicts_with("empty")
+                     .help("Keep features with non-null, non-empty geometries (default)"))
+                .arg(Arg::with_name("empty")
+                     .long("empty")
+                     .help("Keep only features with null or empty geometries, e.g. for auditing"))
+        )
+        .subcommand(
+            SubCommand::with_name("type")
+                .about("Output only entities (from STDIN) with geometries of the given GeoJSON types")
+                .after_help(text::FILTER_TYPE_AFTER_HELP)
+                .arg(Arg::with_name("type")
+                     .help("Geometry types to keep, e.g. Polygon MultiPolygon")
+                     .required(true)
+                     .multiple(true)
+                     .index(1))
+        )
+        .subcommand(
+            SubCommand::with_name("time")
+                .about("Output only entities (from STDIN) with a timestamp property in the given range")
+                .after_help(text::FILTER_TIME_AFTER_HELP)
+                .arg(Arg::with_name("property")
+                     .long("property")
+                     .short("p")
+                     .takes_value(true)
+                     .required(true)
+                     .help("Property containing each feature's timestamp"))
+                .arg(Arg::with_name("after")
+                     .long("after")
+                     .takes_value(true)
+                     .help("Keep features at or after this time"))
+                .arg(Arg::with_name("before")
+                     .long("before")
+                     .takes_value(true)
+                     .help("Keep features strictly before this time"))
+                .arg(Arg::with_name("epoch-millis")
+                     .long("epoch-millis")
+                     .help("Interpret numeric timestamps as milliseconds rather than seconds since the epoch"))
+        );
+
+    let json = SubCommand::with_name("json")
+        .about("Best-guess conversions from geo-oriented JSON to GeoJSON")
+        .subcommand(
+            SubCommand::with_name("munge")
+                .about("Attempt to convert arbitrary JSON to a GeoJSON Feature.")
+                .after_help(text::JSON_MUNGE_AFTER_HELP),
+        );
+
+    let read = SubCommand::with_name("read")
+        .about("Information about reading inputs with geoq")
+        .after_help(text::READ_AFTER_HELP);
+
+    let centroid = SubCommand::with_name("centroid")
+        .about(text::CENTROID_ABOUT)
+        .after_help(text::CENTROID_AFTER_HELP);
+
+    let whereami = SubCommand::with_name("whereami")
+        .about(text::WHEREAMI_ABOUT)
+        .after_help(text::WHEREAMI_AFTER_HELP);
+
+    // Output options shared by the measure subcommands
+    let measure_json = Arg::with_name("json")
+        .long("json")
+        .help(text::MEASURE_JSON_ARG_HELP);
+    let measure_as_property = Arg::with_name("as_property")
+        .long("as-property")
+        .takes_value(true)
+        .value_name("NAME")
+        .conflicts_with("json")
+        .help(text::MEASURE_AS_PROPERTY_ARG_HELP);
+    let measure_rhumb = Arg::with_name("rhumb")
+        .long("rhumb")
+        .help(text::MEASURE_RHUMB_ARG_HELP);
+
+    let measure = SubCommand::with_name("measure")
+        .about(text::MEASURE_ABOUT)
+        .subcommand(
+            SubCommand::with_name("distance")
+                .about(text::DISTANCE_ABOUT)
+                .after_help(text::DISTANCE_AFTER_HELP)
+                .arg(
+                    Arg::with_name("query")
+                        .help(text::DISTANCE_QUERY_ARG_HELP)
+                        .required(true)
+                        .index(1),
+                )
+                .arg(measure_json.clone())
+                .arg(measure_as_property.clone())
+                .arg(measure_rhumb.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("bearing")
+                .about(text::MEASURE_BEARING_ABOUT)
+                .after_help(text::MEASURE_BEARING_AFTER_HELP)
+                .arg(
+                    Arg::with_name("query")
+                        .help(text::BEARING_QUERY_ARG_HELP)
+                        .required(true)
+                        .index(1),
+                )
+                .arg(measure_json.clone())
+                .arg(measure_as_property.clone())
+                .arg(measure_rhumb.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("coord-count")
+                .about(text::MEASURE_COORDS_ABOUT)
+                .after_help(text::MEASURE_COORDS_AFTER_HELP)
+                .arg(
+                    Arg::with_name("geojson")
+                        .long("geojson")
+                        .required(false)
+                        .takes_value(false)
+                        .conflicts_with_all(&["json", "as_property"])
+                        .help(text::MEASURE_COORDS_GEOJSON_ARG_HELP),
+                )
+                .arg(measure_json.clone())
+                .arg(measure_as_property.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("area")
+                .about(text::MEASURE_AREA_ABOUT)
+                .after_help(text::MEASURE_AREA_AFTER_HELP)
+                .arg(measure_json.clone())
+                .arg(measure_as_property.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("length")
+                .about(text::MEASURE_LENGTH_ABOUT)
+                .after_help(text::MEASURE_LENGTH_AFTER_HELP)
+                .arg(measure_json)
+                .arg(measure_as_property)
+                .arg(measure_rhumb),
+        );
+
+    let destination = SubCommand::with_name("destination")
+        .about(text::DESTINATION_ABOUT)
+        .after_help(text::DESTINATION_AFTER_HELP)
+        .arg(
+            Arg::with_name("bearing")
+                .long("bearing")
+                .takes_value(true)
+                .required(true)
+                .allow_hyphen_values(true)
+                .help("Bearing to set off on, in degrees clockwise from north"),
+        )
+        .arg(
+            Arg::with_name("distance")
+                .long("distance")
+                .takes_value(true)
+                .required(true)
+                .help("Distance to go, e.g. 18.52km or 10mi"),
+        )
+        .arg(
+            Arg::with_name("rhumb")
+                .long("rhumb")
+                .help("Hold the bearing the whole way along a rhumb line, rather than following a great circle"),
+        )
+        .arg(
+            Arg::with_name("line")
+                .long("line")
+                .help("Write the path from each point to its destination as a LineString"),
+        )
+        .arg(
+            Arg::with_name("densify")
+                .long("densify")
+                .takes_value(true)
+                .default_value("10km")
+                .help("Longest segment of --line paths, which keeps them on course when drawn in lon/lat"),
+        );
+
+    let simplify = SubCommand::with_name("simplify")
+        .about(text::SIMPLIFY_ABOUT)
+        .after_help(text::SIMPLIFY_AFTER_HELP)
+        .arg(
+            Arg::with_name("epsilon")
+                .help(text::SIMPLIFY_EPSILON_ARG_HELP)
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("to_coord_count")
+                .long("to-coord-count")
+                .required(false)
+                .takes_value(true)
+                .help(text::SIMPLIFY_TO_COORD_COUNT_ARG_HELP),
+        );
+
+    let bbox = SubCommand::with_name("bbox")
+        .about("Generate bounding boxes for geometries")
+        .arg(Arg::with_name("embed").long("embed").short("e").help(
+            "Print inputs as GeoJSON features and include the bbox in the GeoJSON bbox field",
+        ))
+        .arg(
+            Arg::with_name("all")
+                .long("all")
+                .short("a")
+                .help("Give a single bbox for all input geometries rather than 1 bbox per input"),
+        )
+        .after_help(text::BBOX_AFTER_HELP)
+        .subcommand(
+            SubCommand::with_name("expand")
+                .setting(AppSettings::AllowLeadingHyphen)
+                .about("Move the edges of bboxes outward by a margin")
+                .after_help(text::BBOX_EXPAND_AFTER_HELP)
+                .arg(
+                    Arg::with_name("margin")
+                        .help("Margin, as a percentage of the bbox's size like 10%, or a distance like 500m, 2mi, or a number in --units")
+                        .required(true)
+                        .allow_hyphen_values(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("bbox")
+                        .help("Bbox as min_lon,min_lat,max_lon,max_lat. If not given, bboxes or entities are read from STDIN, one per line")
+                        .allow_hyphen_values(true)
+                        .index(2),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("intersect")
+                .setting(AppSettings::AllowLeadingHyphen)
+                .about("Give the overlap of bboxes with another")
+                .after_help(text::BBOX_INTERSECT_AFTER_HELP)
+                .arg(
+                    Arg::with_name("with")
+                        .help("Bbox to intersect with, as min_lon,min_lat,max_lon,max_lat")
+                        .required(true)
+                        .allow_hyphen_values(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("bbox")
+                        .help("Bbox as min_lon,min_lat,max_lon,max_lat. If not given, bboxes or entities are read from STDIN, one per line")
+                        .allow_hyphen_values(true)
+                        .index(2),
+                ),
+        );
+
+    let shp = SubCommand::with_name("shp")
+        .about("Read a shapefile and convert to GeoJSON")
+        .after_help(text::SHP_AFTER_HELP)
+        .arg(
+            Arg::with_name("path")
+                .help("output file, e.g. data.fgb")
+                .required(true)
+                .index(1),
+        );
+
+    let fgb = SubCommand::with_name("fgb")
+        .about("Reading and Writing FlatGeoBuf")
+        .subcommand(
+            SubCommand::with_name("write")
+                .about("Write GeoJSON data to a binary flatgeobuf file").after_help(text::FGB_WRITE_AFTER_HELP)
+                .arg(
+                    Arg::with_name("path")
+                        .help("output path, or s3:// or gs:// URI")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("checkpoint")
+                        .long("checkpoint")
+                        .takes_value(true)
+                        .help("State file recording progress, to resume from if the command is run again after being interrupted"),
+                )
+                .arg(repair_rings),
+        )
+        .subcommand(
+            SubCommand::with_name("read")
+                .about("Read a binary flatgeobuf file to GeoJSON").after_help(text::FGB_READ_AFTER_HELP)
+                .arg(
+                    Arg::with_name("path")
+                        .help("input path or s3:// or gs:// URI of .fgb file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("bbox")
+                        .allow_hyphen_values(true)
+                        .long("bbox")
+                        .required(false)
+                        .takes_value(true)
+                        .help("Comma-separated bounding box: minLon,minLat,maxLon,maxLat"),
+                ),
+        );
+
+    let h3 = SubCommand::with_name("h3")
+        .about("Work with H3")
+        .subcommand(
+            SubCommand::with_name("point")
+                .about("Output hexadecimal encoded Cell ID for a given Lat,Lon at requested resolution").after_help(text::H3_POINT_AFTER_HELP)
+                .arg(
+                    Arg::with_name("resolution")
+                        .help("H3 cell resolution (0-15)")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("hierarchy")
+                .about("Output all hexadecimal encoded Cells for a given Lat,Lon, from res 0 to 15").after_help(text::H3_HIERARCHY_AFTER_HELP)
+        )
+        .subcommand(
+            SubCommand::with_name("to-str")
+                .about("Convert an h3 numeric index (64-bit integer representation) to its hexadecimal string representation").after_help(text::H3_TO_STR_AFTER_HELP)
+        ).subcommand(
+            SubCommand::with_name("from-str")
+                .about("Convert an h3 string index (15-character hexadecimal representation) to its 64-bit integer numeric representation").after_help(text::H3_FROM_STR_AFTER_HELP)
+        ).subcommand(
+            SubCommand::with_name("children")
+                .about("Get children for given cells at given resolution. If no resolution is given, the cell's resolution + 1 is used.").after_help(text::H3_CHILDREN_AFTER_HELP)
+                .arg(
+                    Arg::with_name("resolution")
+                        .help("H3 cell resolution (0-15)")
+                        .index(1),
+                )
+        ).subcommand(
+            SubCommand::with_name("parent")
+                .about("Get parent (or ancestor) for given cells at given resolution. If no resolution is given, the cell's resolution - 1 (immediate parent) is used.").after_help(text::H3_PARENT_AFTER_HELP)
+                .arg(
+                    Arg::with_name("resolution")
+                        .help("H3 cell resolution (0-15)")
+                        .index(1),
+                )
+        ).subcommand(SubCommand::with_name("resolution").about("Get resolution for an H3 cell").after_help(text::H3_RESOLUTION_AFTER_HELP))
+        .subcommand(
+            SubCommand::with_name("grid-disk")
+                .about("Get disk of given radius around given cells. Default radius is 1.").after_help(text::H3_GRID_DISK_AFTER_HELP)
+                .arg(
+                    Arg::with_name("radius")
+                        .help("https://h3geo.org/docs/api/traversal/#griddisk")
+                        .index(1),
+                )
+        ).subcommand(
+            SubCommand::with_name("covering")
+                .about("Generate set of H3 cells covering a geometry.").after_help(text::H3_COVERING_AFTER_HELP)
+                .arg(
+                    Arg::with_name("resolution")
+                        .help("H3 cell resolution (0-15)")
+                        .index(1),
+                )
+                .arg(Arg::with_name("original")
+                     .long("original")
+                     .short("o")
+                     .help("Also print the query entity in the output.\nUseful for mapping a geometry along with its covering H3 Cells."))
+                .arg(Arg::with_name("compact-cells")
+                     .long("compact-cells")
+                     .short("c")
+                     .help("Compact the covering, replacing complete sets of children with their parent cells"))
+        ).subcommand(
+            SubCommand::with_name("boundary")
+                .about("Output the boundary polygon of given cells as GeoJSON Features").after_help(text::H3_BOUNDARY_AFTER_HELP)
+        ).subcommand(
+            SubCommand::with_name("compact")
+                .about("Compact a set of cells, replacing complete sets of children with their parent cells, recursively").after_help(text::H3_COMPACT_AFTER_HELP)
+        ).subcommand(
+            SubCommand::with_name("uncompact")
+                .about("Expand compacted cells into their descendants at the given resolution").after_help(text::H3_UNCOMPACT_AFTER_HELP)
+                .arg(
+                    Arg::with_name("resolution")
+                        .help("H3 cell resolution (0-15)")
+                        .required(true)
+                        .index(1),
+                )
+        );
+
+    let props = SubCommand::with_name("props")
+        .about(text::PROPS_ABOUT)
+        .subcommand(
+            SubCommand::with_name("select")
+                .about(text::PROPS_SELECT_ABOUT)
+                .after_help(text::PROPS_SELECT_AFTER_HELP)
+                .arg(
+                    Arg::with_name("keys")
+                        .help("Comma-separated list of property keys to keep")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("rename")
+                        .long("rename")
+                        .short("r")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help(
+                            "Rename a selected key, given as old_name:new_name. May be repeated.",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set")
+                .about(text::PROPS_SET_ABOUT)
+                .after_help(text::PROPS_SET_AFTER_HELP)
+                .arg(
+                    Arg::with_name("assignments")
+                        .help("One or more key=value assignments")
+                        .required(true)
+                        .multiple(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("values")
+                .about(text::PROPS_VALUES_ABOUT)
+                .after_help(text::PROPS_VALUES_AFTER_HELP)
+                .arg(
+                    Arg::with_name("key")
+                        .help("Property key to count values of")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("top")
+                        .long("top")
+                        .short("n")
+                        .takes_value(true)
+                        .help("Only output the N most frequent values"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("join")
+                .about(text::PROPS_JOIN_ABOUT)
+                .after_help(text::PROPS_JOIN_AFTER_HELP)
+                .arg(
+                    Arg::with_name("lookup")
+                        .long("lookup")
+                        .short("l")
+                        .takes_value(true)
+                        .required(true)
+                        .help("CSV or JSON table of properties to merge, as a path or URL"),
+                )
+                .arg(
+                    Arg::with_name("on")
+                        .long("on")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Key column of the lookup, matched with the property of the same name"),
+                )
+                .arg(
+                    Arg::with_name("drop-unmatched")
+                        .long("drop-unmatched")
+                        .help("Leave out features with no row in the lookup, rather than passing them through unchanged"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("normalize")
+                .about(text::PROPS_NORMALIZE_ABOUT)
+                .after_help(text::PROPS_NORMALIZE_AFTER_HELP)
+                .arg(
+                    Arg::with_name("keys")
+                        .long("keys")
+                        .takes_value(true)
+                        .possible_values(&["snake_case", "camelCase", "kebab-case", "lower", "upper"])
+                        .help("Rewrite property keys in this case"),
+                )
+                .arg(
+                    Arg::with_name("trim")
+                        .long("trim")
+                        .help("Trim whitespace from the ends of string values"),
+                )
+                .arg(
+                    Arg::with_name("empty-as-null")
+                        .long("empty-as-null")
+                        .help("Replace empty string values with null (after --trim, so blank values too)"),
+                ),
+        );
+
+    let grep = SubCommand::with_name("grep")
+        .about(text::GREP_ABOUT)
+        .after_help(text::GREP_AFTER_HELP)
+        .arg(
+            Arg::with_name("pattern")
+                .help("Text (or regex, with --regex) to search for")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("property")
+                .long("property")
+                .short("p")
+                .takes_value(true)
+                .help("Only search values of this property key"),
+        )
+        .arg(
+            Arg::with_name("regex")
+                .long("regex")
+                .short("E")
+                .help("Treat the pattern as a regular expression"),
+        );
+
+    let id = SubCommand::with_name("id")
+        .about("Work with feature ids")
+        .subcommand(
+            SubCommand::with_name("assign")
+                .about(text::ID_ASSIGN_ABOUT)
+                .after_help(text::ID_ASSIGN_AFTER_HELP)
+                .arg(
+                    Arg::with_name("strategy")
+                        .long("strategy")
+                        .short("s")
+                        .takes_value(true)
+                        .possible_values(&["index", "uuid", "hash"])
+                        .default_value("index")
+                        .help("How to generate ids"),
+                ),
+        );
+
+    let dedup = SubCommand::with_name("dedup")
+        .about(text::DEDUP_ABOUT)
+        .after_help(text::DEDUP_AFTER_HELP)
+        .arg(
+            Arg::with_name("by")
+                .long("by")
+                .takes_value(true)
+                .default_value("geometry")
+                .help("Key to deduplicate on: geometry, id, or property:<name>"),
+        )
+        .arg(
+            Arg::with_name("tolerance")
+                .long("tolerance")
+                .short("t")
+                .takes_value(true)
+                .help("Snap coordinates to a grid of this size before comparing geometries"),
+        );
+
+    let diff = SubCommand::with_name("diff")
+        .about(text::DIFF_ABOUT)
+        .after_help(text::DIFF_AFTER_HELP)
+        .arg(
+            Arg::with_name("other")
+                .long("other")
+                .short("o")
+                .takes_value(true)
+                .required(true)
+                .help("Path or URL of the previous version of the data to compare against"),
+        )
+        .arg(
+            Arg::with_name("key")
+                .long("key")
+                .short("k")
+                .takes_value(true)
+                .default_value("id")
+                .help("Key used to match features: id or property:<name>"),
+        )
+        .arg(
+            Arg::with_name("unchanged")
+                .long("unchanged")
+                .help("Also output features which did not change"),
+        );
+
+    let sort = SubCommand::with_name("sort")
+        .about(text::SORT_ABOUT)
+        .after_help(text::SORT_AFTER_HELP)
+        .arg(
+            Arg::with_name("by")
+                .long("by")
+                .takes_value(true)
+                .default_value("hilbert")
+                .help("Sort key: hilbert, area, length, or property:<name>"),
+        )
+        .arg(
+            Arg::with_name("reverse")
+                .long("reverse")
+                .short("r")
+                .help("Sort in descending order"),
+        )
+        .arg(
+            Arg::with_name("chunk-size")
+                .long("chunk-size")
+                .takes_value(true)
+                .help("Number of features to sort in memory before spilling to temp files (default 100000)"),
+        );
+
+    let head = SubCommand::with_name("head")
+        .about(text::HEAD_ABOUT)
+        .after_help(text::HEAD_AFTER_HELP)
+        .arg(
+            Arg::with_name("count")
+                .short("n")
+                .long("count")
+                .takes_value(true)
+                .default_value("10")
+                .help("Number of features to output"),
+        );
+
+    let tail = SubCommand::with_name("tail")
+        .about(text::TAIL_ABOUT)
+        .after_help(text::TAIL_AFTER_HELP)
+        .arg(
+            Arg::with_name("count")
+                .short("n")
+                .long("count")
+                .takes_value(true)
+                .default_value("10")
+                .help("Number of features to output"),
+        );
+
+    let skip = SubCommand::with_name("skip")
+        .about(text::SKIP_ABOUT)
+        .after_help(text::SKIP_AFTER_HELP)
+        .arg(
+            Arg::with_name("count")
+                .index(1)
+                .required(true)
+                .help("Number of features to skip"),
+        );
+
+    let sample = SubCommand::with_name("sample")
+        .about(text::SAMPLE_ABOUT)
+        .after_help(text::SAMPLE_AFTER_HELP)
+        .arg(
+            Arg::with_name("fraction")
+                .long("fraction")
+                .short("f")
+                .takes_value(true)
+                .required(true)
+                .help("Probability of keeping each feature, between 0 and 1"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .takes_value(true)
+                .help("Random seed, for reproducible samples"),
+        );
+
+    let aggregate = SubCommand::with_name("aggregate")
+        .about(text::AGGREGATE_ABOUT)
+        .after_help(text::AGGREGATE_AFTER_HELP)
+        .arg(
+            Arg::with_name("by")
+                .long("by")
+                .takes_value(true)
+                .required(true)
+                .help("Key to group features by: id or property:<name>"),
+        )
+        .arg(
+            Arg::with_name("sum")
+                .long("sum")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Numeric property to sum within each group"),
+        )
+        .arg(
+            Arg::with_name("min")
+                .long("min")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Numeric property to take the minimum of within each group"),
+        )
+        .arg(
+            Arg::with_name("max")
+                .long("max")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Numeric property to take the maximum of within each group"),
+        )
+        .arg(
+            Arg::with_name("mean")
+                .long("mean")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Numeric property to average within each group"),
+        )
+        .arg(
+            Arg::with_name("union-geometry")
+                .long("union-geometry")
+                .help("Dissolve the geometries of each group into one"),
+        );
+
+    let tile = SubCommand::with_name("tile")
+        .about(text::TILE_ABOUT)
+        .after_help(text::TILE_AFTER_HELP)
+        .subcommand(
+            SubCommand::with_name("for-point")
+                .about("Output the z/x/y tile containing each Point at the given zoom")
+                .arg(
+                    Arg::with_name("zoom")
+                        .help("Tile zoom level (0-31)")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cover")
+                .about("Output the z/x/y tiles at the given zoom intersecting each geometry")
+                .arg(
+                    Arg::with_name("zoom")
+                        .help("Tile zoom level (0-31)")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bbox")
+                .about("Output the bounding polygon of z/x/y tiles as GeoJSON")
+                .arg(
+                    Arg::with_name("tile")
+                        .help(
+                            "Tile as z/x/y. If not given, tiles are read from STDIN, one per line",
+                        )
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("children")
+                .about("Output the 4 children of z/x/y tiles at the next zoom")
+                .arg(
+                    Arg::with_name("tile")
+                        .help(
+                            "Tile as z/x/y. If not given, tiles are read from STDIN, one per line",
+                        )
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("parent")
+                .about("Output the parent of z/x/y tiles at the previous zoom")
+                .arg(
+                    Arg::with_name("tile")
+                        .help(
+                            "Tile as z/x/y. If not given, tiles are read from STDIN, one per line",
+                        )
+                        .index(1),
+                ),
+        );
+
+    let quadkey = SubCommand::with_name("quadkey")
+        .about(text::QUADKEY_ABOUT)
+        .after_help(text::QUADKEY_AFTER_HELP)
+        .subcommand(
+            SubCommand::with_name("encode")
+                .about("Convert z/x/y tiles to quadkeys")
+                .arg(
+                    Arg::with_name("tile")
+                        .help("Tile as z/x/y. If not given, tiles are read from STDIN, one per line")
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("decode")
+                .about("Convert quadkeys to z/x/y tiles")
+                .arg(
+                    Arg::with_name("quadkey")
+                        .help("Quadkey to decode. If not given, quadkeys are read from STDIN, one per line")
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("point")
+                .about("Output the quadkey of the tile containing each Point at the given level")
+                .arg(
+                    Arg::with_name("zoom")
+                        .help("Quadkey level (0-31), i.e. number of digits")
+                        .required(true)
+                        .index(1),
+                ),
+        );
+
+    let tile_buffer = Arg::with_name("buffer")
+        .long("buffer")
+        .takes_value(true)
+        .default_value("64")
+        .help(text::TILE_BUFFER_ARG_HELP);
+
+    let tiles = SubCommand::with_name("tiles")
+        .about(text::TILES_ABOUT)
+        .subcommand(
+            SubCommand::with_name("build")
+                .about("Build a vector tile pyramid from GeoJSON features into an MBTiles or PMTiles file")
+                .after_help(text::TILES_BUILD_AFTER_HELP)
+                .arg(
+                    Arg::with_name("out")
+                        .long("out")
+                        .short("o")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Output path, e.g. tiles.mbtiles or tiles.pmtiles"),
+                )
+                .arg(
+                    Arg::with_name("archive")
+                        .long("archive")
+                        .takes_value(true)
+                        .possible_values(&["mbtiles", "pmtiles"])
+                        .help("Archive format. Defaults to pmtiles for .pmtiles output paths, otherwise mbtiles"),
+                )
+                .arg(
+                    Arg::with_name("min-zoom")
+                        .long("min-zoom")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Lowest zoom level to generate"),
+                )
+                .arg(
+                    Arg::with_name("max-zoom")
+                        .long("max-zoom")
+                        .takes_value(true)
+                        .default_value("12")
+                        .help("Highest zoom level to generate"),
+                )
+                .arg(
+                    Arg::with_name("layer")
+                        .long("layer")
+                        .short("l")
+                        .takes_value(true)
+                        .default_value("geoq")
+                        .help("Name of the vector tile layer"),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .short("f")
+                        .help("Overwrite the output file if it already exists"),
+                )
+                .arg(
+                    Arg::with_name("checkpoint")
+                        .long("checkpoint")
+                        .takes_value(true)
+                        .help("State file recording progress, to resume from if the command is run again after being interrupted"),
+                )
+                .arg(tile_buffer.clone())
+                .arg(
+                    Arg::with_name("profile")
+                        .long("profile")
+                        .takes_value(true)
+                        .help("JSON file of per-zoom simplification tolerances, minimum feature sizes and properties to keep"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("read")
+                .about("Decode tiles from an MBTiles or PMTiles archive to GeoJSON features")
+                .after_help(text::TILES_READ_AFTER_HELP)
+                .arg(
+                    Arg::with_name("archive")
+                        .help("Path to .mbtiles or .pmtiles file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("tile")
+                        .help(
+                            "Tile as z/x/y. If not given, tiles are read from STDIN, one per line",
+                        )
+                        .index(2),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("List the z/x/y tiles in an MBTiles or PMTiles archive").after_help(text::TILES_LIST_AFTER_HELP)
+                .arg(
+                    Arg::with_name("archive")
+                        .help("Path to .mbtiles or .pmtiles file")
+                        .required(true)
+                        .index(1),
+                ),
+        );
+
+    let partition = SubCommand::with_name("partition")
+        .about(text::PARTITION_ABOUT)
+        .after_help(text::PARTITION_AFTER_HELP)
+        .arg(
+            Arg::with_name("by")
+                .long("by")
+                .takes_value(true)
+                .required(true)
+                .help("Partition cells, as geohash:<level>, e.g. geohash:5"),
+        )
+        .arg(
+            Arg::with_name("out-dir")
+                .long("out-dir")
+                .short("o")
+                .takes_value(true)
+                .required(true)
+                .help("Directory to write partition files to. Created if missing"),
+        )
+        .arg(
+            Arg::with_name("assign")
+                .long("assign")
+                .takes_value(true)
+                .possible_values(&["centroid", "duplicate"])
+                .default_value("centroid")
+                .help("Write features spanning several cells to the cell containing their centroid, or duplicate them into every cell they intersect"),
+        );
+
+    let split_output = SubCommand::with_name("split-output")
+        .about(text::SPLIT_OUTPUT_ABOUT)
+        .after_help(text::SPLIT_OUTPUT_AFTER_HELP)
+        .arg(
+            Arg::with_name("max-features")
+                .long("max-features")
+                .short("n")
+                .takes_value(true)
+                .required_unless("max-bytes")
+                .help("Most features to write to each file"),
+        )
+        .arg(
+            Arg::with_name("max-bytes")
+                .long("max-bytes")
+                .takes_value(true)
+                .help("Most bytes to write to each file, e.g. 64M or 1G"),
+        )
+        .arg(
+            Arg::with_name("out")
+                .long("out")
+                .short("o")
+                .takes_value(true)
+                .default_value("part-%04d.geojsonl")
+                .help("File name for each part, with %d or %0Nd for its number, counting from 1"),
+        )
+        .arg(
+            Arg::with_name("manifest")
+                .long("manifest")
+                .takes_value(true)
+                .help("Where to write the manifest (default: manifest.json beside the parts)"),
+        );
+
+    let cat = SubCommand::with_name("cat")
+        .about(text::CAT_ABOUT)
+        .after_help(text::CAT_AFTER_HELP)
+        .arg(
+            Arg::with_name("files")
+                .multiple(true)
+                .required(true)
+                .help("Paths or URLs of the datasets to combine, in any format geoq reads"),
+        )
+        .arg(
+            Arg::with_name("source")
+                .long("source")
+                .takes_value(true)
+                .help("Property to record each feature's file in"),
+        );
+
+    let schema = SubCommand::with_name("schema")
+        .about(text::SCHEMA_ABOUT)
+        .after_help(text::SCHEMA_AFTER_HELP)
+        .arg(
+            Arg::with_name("examples")
+                .long("examples")
+                .short("n")
+                .takes_value(true)
+                .default_value("3")
+                .help("Number of distinct example values to give for each key"),
+        );
+
+    let estimate = SubCommand::with_name("estimate")
+        .about(text::ESTIMATE_ABOUT)
+        .after_help(text::ESTIMATE_AFTER_HELP)
+        .arg(
+            Arg::with_name("export")
+                .required(true)
+                .index(1)
+                .possible_values(&["fgb", "mvt", "parquet"])
+                .help("Export format to estimate for"),
+        )
+        .arg(
+            Arg::with_name("sample")
+                .long("sample")
+                .short("n")
+                .takes_value(true)
+                .default_value("10000")
+                .help("Number of features, from the start of the input, to convert"),
+        )
+        .arg(
+            Arg::with_name("features")
+                .long("features")
+                .takes_value(true)
+                .help("Total number of features, if known, to stop reading after the sample"),
+        )
+        .arg(
+            Arg::with_name("min-zoom")
+                .long("min-zoom")
+                .takes_value(true)
+                .default_value("0")
+                .help("Lowest zoom level of tiles, for mvt"),
+        )
+        .arg(
+            Arg::with_name("max-zoom")
+                .long("max-zoom")
+                .takes_value(true)
+                .default_value("12")
+                .help("Highest zoom level of tiles, for mvt"),
+        );
+
+    let buffer =
+        SubCommand::with_name("buffer")
+            .about(text::BUFFER_ABOUT)
+            .after_help(text::BUFFER_AFTER_HELP)
+            .arg(Arg::with_name("distance").required(true).index(1).help(
+                "Distance to buffer by, e.g. 100 or 5km; negative (after --) to shrink polygons",
+            ))
+            .arg(
+                Arg::with_name("planar")
+                    .long("planar")
+                    .help("Buffer in the coordinates' own units, for projected data"),
+            );
+
+    let reproject = SubCommand::with_name("reproject")
+        .about(text::REPROJECT_ABOUT)
+        .after_help(text::REPROJECT_AFTER_HELP)
+        .arg(
+            Arg::with_name("from")
+                .long("from")
+                .takes_value(true)
+                .default_value("EPSG:4326")
+                .help("CRS of the input, as an EPSG code"),
+        )
+        .arg(
+            Arg::with_name("to")
+                .long("to")
+                .takes_value(true)
+                .required(true)
+                .help("CRS to convert to, as an EPSG code"),
+        )
+        .arg(
+            Arg::with_name("densify")
+                .long("densify")
+                .takes_value(true)
+                .help("Split segments longer than this distance before converting, e.g. 1km"),
+        );
+
+    let matrix = SubCommand::with_name("matrix")
+        .about(text::MATRIX_ABOUT)
+        .after_help(text::MATRIX_AFTER_HELP)
+        .arg(
+            Arg::with_name("other")
+                .long("other")
+                .short("o")
+                .takes_value(true)
+                .required(true)
+                .help("Path or URL of the points to measure to, the matrix's columns"),
+        )
+        .arg(
+            Arg::with_name("metric")
+                .long("metric")
+                .takes_value(true)
+                .possible_values(&["geodesic", "haversine", "rhumb"])
+                .default_value("geodesic")
+                .help("Distance on the WGS84 ellipsoid, the faster great-circle distance on a sphere, or the distance along a rhumb line of constant bearing"),
+        )
+        .arg(
+            Arg::with_name("max-distance")
+                .long("max-distance")
+                .takes_value(true)
+                .help("Leave out distances longer than this, e.g. 5km, for a sparse matrix"),
+        )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Write a JSON object per pair of points instead of a CSV table"),
+        );
+
+    let interpolate = SubCommand::with_name("interpolate")
+        .about(text::INTERPOLATE_ABOUT)
+        .after_help(text::INTERPOLATE_AFTER_HELP)
+        .arg(
+            Arg::with_name("other")
+                .long("other")
+                .short("o")
+                .takes_value(true)
+                .required(true)
+                .help("Path or URL of the target zones to reapportion values to"),
+        )
+        .arg(
+            Arg::with_name("value")
+                .long("value")
+                .short("v")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(true)
+                .help("Numeric property of the input polygons to reapportion; may be repeated"),
+        );
+
+    let zonal = SubCommand::with_name("zonal")
+        .about(text::ZONAL_ABOUT)
+        .after_help(text::ZONAL_AFTER_HELP)
+        .arg(
+            Arg::with_name("raster")
+                .long("raster")
+                .short("r")
+                .takes_value(true)
+                .required(true)
+                .help("Path or URL of the GeoTIFF to summarize"),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .long("stats")
+                .short("s")
+                .takes_value(true)
+                .default_value("count,mean,min,max")
+                .help("Comma-separated statistics: count, sum, mean, min, max, median, stddev"),
+        )
+        .arg(
+            Arg::with_name("band")
+                .long("band")
+                .short("b")
+                .takes_value(true)
+                .default_value("1")
+                .help("Band to read, starting from 1"),
+        )
+        .arg(
+            Arg::with_name("nodata")
+                .long("nodata")
+                .takes_value(true)
+                .help("Value of pixels to leave out, overriding the file's own nodata value"),
+        );
+
+    let los = SubCommand::with_name("los")
+        .about(text::LOS_ABOUT)
+        .after_help(text::LOS_AFTER_HELP)
+        .arg(
+            Arg::with_name("dem")
+                .long("dem")
+                .takes_value(true)
+                .required(true)
+                .help("Path or URL of the GeoTIFF of elevations in meters"),
+        )
+        .arg(
+            Arg::with_name("other")
+                .long("other")
+                .short("o")
+                .takes_value(true)
+                .required(true)
+                .help("Path or URL of the target points to look for"),
+        )
+        .arg(
+            Arg::with_name("observer-height")
+                .long("observer-height")
+                .takes_value(true)
+                .default_value("1.7m")
+                .help("Height of the observers' eyes above the terrain, e.g. 30m for a tower"),
+        )
+        .arg(
+            Arg::with_name("target-height")
+                .long("target-height")
+                .takes_value(true)
+                .default_value("0m")
+                .help("Height above the terrain of the point to see on each target"),
+        )
+        .arg(
+            Arg::with_name("band")
+                .long("band")
+                .short("b")
+                .takes_value(true)
+                .default_value("1")
+                .help("Band to read, starting from 1"),
+        )
+        .arg(
+            Arg::with_name("nodata")
+                .long("nodata")
+                .takes_value(true)
+                .help("Value of pixels to leave out, overriding the file's own nodata value"),
+        );
+
+    let plot = SubCommand::with_name("plot")
+        .about(text::PLOT_ABOUT)
+        .after_help(text::PLOT_AFTER_HELP)
+        .arg(
+            Arg::with_name("width")
+                .long("width")
+                .short("w")
+                .takes_value(true)
+                .help("Maximum width in characters (default: $COLUMNS, or 80)"),
+        )
+        .arg(
+            Arg::with_name("height")
+                .long("height")
+                .takes_value(true)
+                .help("Maximum height in lines (default 30)"),
+        )
+        .arg(
+            Arg::with_name("graticule")
+                .long("graticule")
+                .short("g")
+                .help("Draw lines of longitude and latitude at round intervals"),
+        )
+        .arg(
+            Arg::with_name("ascii")
+                .long("ascii")
+                .help("Draw with plain ASCII characters instead of braille"),
+        );
+
+    let serve = SubCommand::with_name("serve")
+        .about(text::SERVE_ABOUT)
+        .subcommand(
+            SubCommand::with_name("fgb")
+                .about("Serve vector tiles from a FlatGeobuf file, querying its spatial index per tile")
+                .after_help(text::SERVE_FGB_AFTER_HELP)
+                .arg(
+                    Arg::with_name("path")
+                        .help("Path to .fgb file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("port")
+                        .long("port")
+                        .short("p")
+                        .takes_value(true)
+                        .default_value("8000")
+                        .help("Port to listen on. Use 0 for any free port, which is printed at startup"),
+                )
+                .arg(
+                    Arg::with_name("host")
+                        .long("host")
+                        .takes_value(true)
+                        .default_value("127.0.0.1")
+                        .help("Address to listen on. Use 0.0.0.0 to accept connections from other machines"),
+                )
+                .arg(
+                    Arg::with_name("layer")
+                        .long("layer")
+                        .short("l")
+                        .takes_value(true)
+                        .default_value("geoq")
+                        .help("Name of the vector tile layer"),
+                )
+                .arg(tile_buffer),
+        );
+
+    let geocode = SubCommand::with_name("geocode")
+        .about(text::GEOCODE_ABOUT)
+        .after_help(text::GEOCODE_AFTER_HELP)
+        .arg(
+            Arg::with_name("query")
+                .help("Address or place name to geocode. Read from STDIN, one per line, if not given")
+                .index(1)
+                .conflicts_with_all(&["property", "csv-column"]),
+        )
+        .arg(
+            Arg::with_name("property")
+                .long("property")
+                .short("p")
+                .takes_value(true)
+                .conflicts_with("csv-column")
+                .help("Geocode the value of this property for each feature from STDIN"),
+        )
+        .arg(
+            Arg::with_name("csv-column")
+                .long("csv-column")
+                .takes_value(true)
+                .help("Read CSV with a header row from STDIN, and geocode the value of this column for each row"),
+        )
+        .arg(
+            Arg::with_name("endpoint")
+                .long("endpoint")
+                .takes_value(true)
+                .default_value(geoq::nominatim::DEFAULT_ENDPOINT)
+                .help("Base URL of the Nominatim server"),
+        )
+        .arg(
+            Arg::with_name("rate")
+                .long("rate")
+                .takes_value(true)
+                .default_value("1")
+                .help("Maximum requests per second. The public Nominatim server allows 1"),
+        )
+        .arg(
+            Arg::with_name("limit")
+                .long("limit")
+                .takes_value(true)
+                .default_value("1")
+                .help("Maximum number of results per query"),
+        );
+
+    let reverse_geocode = SubCommand::with_name("reverse-geocode")
+        .about(text::REVERSE_GEOCODE_ABOUT)
+        .after_help(text::REVERSE_GEOCODE_AFTER_HELP)
+        .arg(
+            Arg::with_name("boundaries")
+                .long("boundaries")
+                .short("b")
+                .takes_value(true)
+                .help("Look up points offline in a file of boundary polygons (e.g. countries or admin regions) instead of Nominatim"),
+        )
+        .arg(
+            Arg::with_name("fields")
+                .long("fields")
+                .takes_value(true)
+                .requires("boundaries")
+                .help("Comma-separated boundary properties to attach. Defaults to all of them"),
+        )
+        .arg(
+            Arg::with_name("endpoint")
+                .long("endpoint")
+                .takes_value(true)
+                .default_value(geoq::nominatim::DEFAULT_ENDPOINT)
+                .help("Base URL of the Nominatim server"),
+        )
+        .arg(
+            Arg::with_name("rate")
+                .long("rate")
+                .takes_value(true)
+                .default_value("1")
+                .help("Maximum requests per second. The public Nominatim server allows 1"),
+        )
+        .arg(
+            Arg::with_name("zoom")
+                .long("zoom")
+                .takes_value(true)
+                .default_value("18")
+                .help("Level of detail for Nominatim results, from 3 (country) to 18 (building)"),
+        );
+
+    let elevation = SubCommand::with_name("elevation")
+        .about(text::ELEVATION_ABOUT)
+        .after_help(text::ELEVATION_AFTER_HELP)
+        .arg(
+            Arg::with_name("dem")
+                .long("dem")
+                .takes_value(true)
+                .required(true)
+                .help("Directory of Terrarium {z}/{x}/{y}.png tiles, or a path or URL template containing {z}, {x} and {y}"),
+        )
+        .arg(
+            Arg::with_name("zoom")
+                .long("zoom")
+                .takes_value(true)
+                .default_value("12")
+                .help("Zoom level of DEM tiles to sample"),
+        )
+        .arg(
+            Arg::with_name("z")
+                .long("z")
+                .help("Write elevations as Z coordinates instead of a property"),
+        )
+        .arg(
+            Arg::with_name("densify")
+                .long("densify")
+                .takes_value(true)
+                .help("Add vertices to lines and polygons at most this many meters apart before sampling"),
+        )
+        .arg(
+            Arg::with_name("property")
+                .long("property")
+                .short("p")
+                .takes_value(true)
+                .default_value("elevation")
+                .help("Property to write elevations to"),
+        );
+
+    let raster = SubCommand::with_name("raster")
+        .about(text::RASTER_ABOUT)
+        .subcommand(
+            SubCommand::with_name("polygonize")
+                .about("Convert regions of equal value in a GeoTIFF to polygons")
+                .after_help(text::RASTER_POLYGONIZE_AFTER_HELP)
+                .arg(
+                    Arg::with_name("path")
+                        .help("Path to GeoTIFF file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("band")
+                        .long("band")
+                        .short("b")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Band to read, starting from 1"),
+                )
+                .arg(
+                    Arg::with_name("nodata")
+                        .long("nodata")
+                        .takes_value(true)
+                        .help("Value to skip. Defaults to the file's nodata value, if it has one"),
+                ),
+        );
+
+    let contour = SubCommand::with_name("contour")
+        .about(text::CONTOUR_ABOUT)
+        .after_help(text::CONTOUR_AFTER_HELP)
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .short("i")
+                .takes_value(true)
+                .required(true)
+                .help("Elevation difference between contour lines"),
+        )
+        .arg(
+            Arg::with_name("base")
+                .long("base")
+                .takes_value(true)
+                .default_value("0")
+                .help(
+                    "Elevation of one contour line, from which others are spaced by the interval",
+                ),
+        )
+        .arg(
+            Arg::with_name("raster")
+                .long("raster")
+                .takes_value(true)
+                .help("GeoTIFF DEM to contour, instead of points from STDIN"),
+        )
+        .arg(
+            Arg::with_name("band")
+                .long("band")
+                .short("b")
+                .takes_value(true)
+                .default_value("1")
+                .help("Band of the DEM to read with --raster, starting from 1"),
+        )
+        .arg(
+            Arg::with_name("z-property")
+                .long("z-property")
+                .takes_value(true)
+                .conflicts_with("raster")
+                .help("Property holding each point's elevation, rather than its Z coordinate"),
+        );
+
+    let route = SubCommand::with_name("route")
+        .about(text::ROUTE_ABOUT)
+        .after_help(text::ROUTE_AFTER_HELP)
+        .arg(
+            Arg::with_name("endpoint")
+                .long("endpoint")
+                .takes_value(true)
+                .required(true)
+                .help("Base URL of the routing server, e.g. http://localhost:5000"),
+        )
+        .arg(
+            Arg::with_name("engine")
+                .long("engine")
+                .takes_value(true)
+                .possible_values(&["osrm", "valhalla"])
+                .default_value("osrm")
+                .help("Routing API served at the endpoint"),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .takes_value(true)
+                .default_value("car")
+                .help("Travel mode, e.g. car, bike or foot"),
+        );
+
+    let isochrone = SubCommand::with_name("isochrone")
+        .about(text::ISOCHRONE_ABOUT)
+        .after_help(text::ISOCHRONE_AFTER_HELP)
+        .arg(
+            Arg::with_name("minutes")
+                .long("minutes")
+                .short("m")
+                .takes_value(true)
+                .required(true)
+                .help("Comma-separated travel times to get areas for, e.g. 10,20,30"),
+        )
+        .arg(
+            Arg::with_name("endpoint")
+                .long("endpoint")
+                .takes_value(true)
+                .required(true)
+                .help("Base URL of the routing server, e.g. http://localhost:8002"),
+        )
+        .arg(
+            Arg::with_name("engine")
+                .long("engine")
+                .takes_value(true)
+                .possible_values(&["valhalla", "ors"])
+                .default_value("valhalla")
+                .help("Routing API served at the endpoint"),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .takes_value(true)
+                .default_value("car")
+                .help("Travel mode, e.g. car, bike or foot"),
+        )
+        .arg(
+            Arg::with_name("api-key")
+                .long("api-key")
+                .takes_value(true)
+                .help("API key, sent as the Authorization header"),
+        );
+
+    let bench = SubCommand::with_name("bench")
+        .about(text::BENCH_ABOUT)
+        .after_help(text::BENCH_AFTER_HELP)
+        .arg(
+            Arg::with_name("input")
+                .long("input")
+                .short("i")
+                .takes_value(true)
+                .required(true)
+                .help("File of input lines to benchmark with"),
+        )
+        .arg(
+            Arg::with_name("command")
+                .long("command")
+                .short("c")
+                .takes_value(true)
+                .default_value("wkt")
+                .help("geoq command to benchmark, with its arguments, e.g. \"gj f\" or \"simplify 0.01\""),
+        )
+        .arg(
+            Arg::with_name("iterations")
+                .long("iterations")
+                .short("n")
+                .takes_value(true)
+                .default_value("3")
+                .help("Times to run each benchmark, reporting the fastest"),
+        );
+
+    let pipe = SubCommand::with_name("pipe")
+        .about(text::PIPE_ABOUT)
+        .after_help(text::PIPE_AFTER_HELP)
+        .arg(
+            Arg::with_name("pipeline")
+                .help("Stages separated by |, e.g. 'simplify 0.001 | centroid'")
+                .required_unless("file")
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("file")
+                .long("file")
+                .short("f")
+                .takes_value(true)
+                .conflicts_with("pipeline")
+                .help("Read the stages from a file, one per line or as a YAML list"),
+        );
+
+    let completions = SubCommand::with_name("completions")
+        .about(text::COMPLETIONS_ABOUT)
+        .after_help(text::COMPLETIONS_AFTER_HELP)
+        .arg(
+            Arg::with_name("shell")
+                .help("Shell to complete geoq commands in")
+                .required(true)
+                .possible_values(&Shell::variants())
+                .index(1),
+        );
+
+    let hash = SubCommand::with_name("hash")
+        .about(text::HASH_ABOUT)
+        .after_help(text::HASH_AFTER_HELP)
+        .arg(
+            Arg::with_name("algorithm")
+                .long("algorithm")
+                .short("a")
+                .takes_value(true)
+                .possible_values(&["geohash-centroid", "md5-wkb", "simhash"])
+                .default_value("md5-wkb")
+                .help("How to fingerprint each geometry"),
+        )
+        .arg(
+            Arg::with_name("level")
+                .long("level")
+                .short("l")
+                .takes_value(true)
+                .default_value("12")
+                .help("Geohash level for geohash-centroid, from 1 to 12"),
+        )
+        .arg(
+            Arg::with_name("as_property")
+                .long("as-property")
+                .takes_value(true)
+                .value_name("NAME")
+                .help("Write each input back out as a GeoJSON Feature with its hash set as the property NAME"),
+        );
+
+    let style = SubCommand::with_name("style")
+        .about(text::STYLE_ABOUT)
+        .after_help(text::STYLE_AFTER_HELP)
+        .arg(
+            Arg::with_name("color-by")
+                .long("color-by")
+                .short("c")
+                .takes_value(true)
+                .required(true)
+                .value_name("PROPERTY")
+                .help("Property to color features by"),
+        )
+        .arg(
+            Arg::with_name("palette")
+                .long("palette")
+                .short("p")
+                .takes_value(true)
+                .help("Palette name, or colors separated by commas, e.g. #1b9e77,#d95f02,#7570b3. Defaults to viridis for numbers and tableau10 otherwise"),
+        )
+        .arg(
+            Arg::with_name("categorical")
+                .long("categorical")
+                .help("Give each distinct value its own color, even if the values are numbers"),
+        );
+
+    let graticule = SubCommand::with_name("graticule")
+        .about(text::GRATICULE_ABOUT)
+        .after_help(text::GRATICULE_AFTER_HELP)
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .short("i")
+                .takes_value(true)
+                .default_value("10")
+                .help("Degrees between lines"),
+        )
+        .arg(
+            Arg::with_name("bbox")
+                .long("bbox")
+                .takes_value(true)
+                .default_value("-180,-90,180,90")
+                .allow_hyphen_values(true)
+                .help("Extent of the lines, as min_lon,min_lat,max_lon,max_lat"),
+        )
+        .arg(
+            Arg::with_name("step")
+                .long("step")
+                .takes_value(true)
+                .default_value("1")
+                .help("Greatest distance in degrees between the points of each line, so they curve when projected"),
+        )
+        .arg(
+            Arg::with_name("frame")
+                .long("frame")
+                .help("Also output the outline of the extent"),
+        );
+
+    let snap_to = SubCommand::with_name("snap-to")
+        .about(text::SNAP_TO_ABOUT)
+        .after_help(text::SNAP_TO_AFTER_HELP)
+        .arg(
+            Arg::with_name("other")
+                .long("other")
+                .short("o")
+                .takes_value(true)
+                .required(true)
+                .help("Path or URL of the lines to snap to, e.g. a road network"),
+        )
+        .arg(
+            Arg::with_name("max-distance")
+                .long("max-distance")
+                .short("d")
+                .takes_value(true)
+                .default_value("50m")
+                .help("Farthest to move a point, e.g. 50m or 100ft, or a number in --units"),
+        );
+
+    let trajectory = SubCommand::with_name("trajectory")
+        .about(text::TRAJECTORY_ABOUT)
+        .after_help(text::TRAJECTORY_AFTER_HELP)
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("analyze")
+                .about("Split tracks into the stretches where they move and the places they stop")
+                .after_help(text::TRAJECTORY_ANALYZE_AFTER_HELP)
+                .arg(
+                    Arg::with_name("stop-speed")
+                        .long("stop-speed")
+                        .takes_value(true)
+                        .default_value("0.5m/s")
+                        .help("Count a track as stopped while it goes no faster than this, e.g. 0.5m/s, 2km/h or 1mph"),
+                )
+                .arg(
+                    Arg::with_name("stop-duration")
+                        .long("stop-duration")
+                        .takes_value(true)
+                        .default_value("3min")
+                        .help("Shortest stop to count, e.g. 90s or 3min; slower stretches shorter than this count as moving"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("resample")
+                .about("Place the points of tracks at regular intervals of time or distance")
+                .after_help(text::TRAJECTORY_RESAMPLE_AFTER_HELP)
+                .arg(
+                    Arg::with_name("every")
+                        .long("every")
+                        .short("e")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Interval of time, e.g. 10s or 5min, or of distance, e.g. 100m or 0.5mi"),
+                ),
+        )
+        .arg(
+            Arg::with_name("order-by")
+                .long("order-by")
+                .short("t")
+                .takes_value(true)
+                .required(true)
+                .help("Property containing each point's timestamp"),
+        )
+        .arg(
+            Arg::with_name("group-by")
+                .long("group-by")
+                .short("g")
+                .takes_value(true)
+                .help("Property identifying each point's device or vehicle, building a track per value"),
+        )
+        .arg(
+            Arg::with_name("max-gap")
+                .long("max-gap")
+                .takes_value(true)
+                .help("Start a new track after a gap of more than this long between points, e.g. 5min or 1h"),
+        )
+        .arg(
+            Arg::with_name("epoch-millis")
+                .long("epoch-millis")
+                .help("Interpret numeric timestamps as milliseconds rather than seconds since the epoch"),
+        );
+
+    let transform = SubCommand::with_name("transform")
+        .about(text::TRANSFORM_ABOUT)
+        .after_help(text::TRANSFORM_AFTER_HELP)
+        .arg(
+            Arg::with_name("program")
+                .help("Assignments separated by ;, e.g. 'props.area = area(geom)'")
+                .required(true)
+                .index(1),
+        );
+
+    let ogcapi = SubCommand::with_name("ogcapi")
+        .about(text::OGCAPI_ABOUT)
+        .subcommand(
+            SubCommand::with_name("read")
+                .about("Read all features of an OGC API - Features collection or WFS layer")
+                .after_help(text::OGCAPI_READ_AFTER_HELP)
+                .arg(
+                    Arg::with_name("url")
+                        .help("Collection URL, or WFS GetFeature URL")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("page-size")
+                        .long("page-size")
+                        .takes_value(true)
+                        .default_value("1000")
+                        .help("Number of features to request at a time"),
+                )
+                .arg(
+                    Arg::with_name("max-features")
+                        .long("max-features")
+                        .takes_value(true)
+                        .help("Stop after reading this many features"),
+                )
+                .arg(
+                    Arg::with_name("bbox")
+                        .long("bbox")
+                        .takes_value(true)
+                        .help("Only read features within min_lon,min_lat,max_lon,max_lat"),
+                ),
+        );
+
+    App::new("geoq")
+        .version(VERSION)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .about("geoq - GeoSpatial utility belt")
+        .after_help(text::MAIN_AFTER_HELP)
+        .arg(
+            Arg::with_name("strip-foreign")
+                .long("strip-foreign")
+                .global(true)
+                .help(
+                    "Drop non-standard GeoJSON members (e.g. \"crs\") from features built by geoq",
+                ),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .global(true)
+                .conflicts_with("lenient")
+                .help("Reject out-of-range or non-finite coordinates and malformed geometries"),
+        )
+        .arg(Arg::with_name("lenient").long("lenient").global(true).help(
+            "Coerce trailing commas, string coordinates, arrays of features and spaced Lat/Lons",
+        ))
+        .arg(
+            Arg::with_name("on-error")
+                .long("on-error")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["abort", "skip", "report"])
+                .default_value("abort")
+                .help("Stop at the first bad input, skip bad inputs, or skip them and write them to --error-file"),
+        )
+        .arg(
+            Arg::with_name("error-file")
+                .long("error-file")
+                .global(true)
+                .takes_value(true)
+                .required_if("on-error", "report")
+                .help("File to write inputs skipped by --on-error report to, as JSON lines with their errors"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["geojson", "wkt", "wkb", "csv"])
+                .help("Write GeoJSON output as GeoJSON (the default), WKT, binary WKB, or CSV with a wkt column and one per property"),
+        )
+        .arg(
+            Arg::with_name("precision")
+                .long("precision")
+                .global(true)
+                .takes_value(true)
+                .value_name("DIGITS")
+                .help("Round coordinates of GeoJSON output, and of the formats converted from it, to this many decimal places (0 to 15)"),
+        )
+        .arg(
+            Arg::with_name("pretty")
+                .long("pretty")
+                .global(true)
+                .conflicts_with("compact")
+                .help("Indent GeoJSON output, writing each object after an RS character (RFC 8142)"),
+        )
+        .arg(
+            Arg::with_name("compact")
+                .long("compact")
+                .global(true)
+                .help("Write GeoJSON output with no whitespace"),
+        )
+        .arg(
+            Arg::with_name("errors")
+                .long("errors")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .help("Write errors and warnings to stderr as text, or as JSON objects, one per line"),
+        )
+        .arg(
+            Arg::with_name("other-max-memory")
+                .long("other-max-memory")
+                .global(true)
+                .takes_value(true)
+                .value_name("BYTES")
+                .help("Memory for the features of a second dataset, as with diff --other, past which they're spilled to a temporary file (e.g. 512M; default: no limit)"),
+        )
+        .arg(
+            Arg::with_name("units")
+                .long("units")
+                .global(true)
+                .takes_value(true)
+                .value_name("UNITS")
+                .help("Units for distances given to and measured by commands: meters (default), kilometers, miles, feet, or acres or hectares for areas"),
+        )
+        .arg(
+            Arg::with_name("unordered")
+                .long("unordered")
+                .global(true)
+                .help("Print results of parallel commands as soon as they're ready, rather than in input order"),
+        )
+        .arg(
+            Arg::with_name("jobs")
+                .short("j")
+                .long("jobs")
+                .global(true)
+                .takes_value(true)
+                .env("GEOQ_JOBS")
+                .help("Number of worker threads for parallel commands (default: one per CPU)"),
+        )
+        .arg(
+            Arg::with_name("batch-size")
+                .long("batch-size")
+                .global(true)
+                .takes_value(true)
+                .help("Number of lines handed to each worker thread at a time by parallel commands (default: 256)"),
+        )
+        .arg(
+            Arg::with_name("buffer-lines")
+                .long("buffer-lines")
+                .global(true)
+                .takes_value(true)
+                .help("Most input lines parallel commands read ahead of their output (default: 5000)"),
+        )
+        .arg(
+            Arg::with_name("max-buffered-bytes")
+                .long("max-buffered-bytes")
+                .global(true)
+                .takes_value(true)
+                .help("Most bytes of input parallel commands read ahead of their output, e.g. 512M (default: 64M)"),
+        )
+        .arg(
+            Arg::with_name("timeout-per-entity")
+                .long("timeout-per-entity")
+                .global(true)
+                .takes_value(true)
+                .help("Skip and report entities which parallel commands take longer than this to handle, e.g. 30s or 500ms. A handler can't be stopped, so one that's timed out keeps running in the background until it's done; geoq stops with an error once 32 are running at a time"),
+        )
+        .arg(
+            Arg::with_name("progress")
+                .long("progress")
+                .global(true)
+                .help("Show lines, entities and bytes read so far on stderr"),
+        )
+        .arg(
+            Arg::with_name("http-retries")
+                .long("http-retries")
+                .global(true)
+                .takes_value(true)
+                .help("Times to retry HTTP requests which fail with connection errors, 429s or 5xxs, backing off between them (default: 3)"),
+        )
+        .arg(
+            Arg::with_name("http-concurrency")
+                .long("http-concurrency")
+                .global(true)
+                .takes_value(true)
+                .help("Most HTTP requests network commands like route and elevation make at once (default: 8)"),
+        )
+        .subcommand(SubCommand::with_name("wkt").about("Output features as Well-Known Text").after_help(text::WKT_AFTER_HELP))
+        .subcommand(
+            SubCommand::with_name("map")
+                .about("View features on a map using geojson.io")
+                .after_help(text::MAP_AFTER_HELP)
+                .arg(
+                    Arg::with_name("style")
+                        .long("style")
+                        .short("s")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("KEY:EXPRESSION")
+                        .help("Set a simplestyle property, such as fill or marker-color, on each feature from an expression"),
+                ),
+        )
+        .subcommand(read)
+        .subcommand(geohash)
+        .subcommand(geojson)
+        .subcommand(json)
+        .subcommand(filter)
+        .subcommand(centroid)
+        .subcommand(whereami)
+        .subcommand(measure)
+        .subcommand(simplify)
+        .subcommand(bbox)
+        .subcommand(shp)
+        .subcommand(fgb)
+        .subcommand(h3)
+        .subcommand(props)
+        .subcommand(grep)
+        .subcommand(id)
+        .subcommand(hash)
+        .subcommand(style)
+        .subcommand(graticule)
+        .subcommand(snap_to)
+        .subcommand(trajectory)
+        .subcommand(dedup)
+        .subcommand(diff)
+        .subcommand(sort)
+        .subcommand(head)
+        .subcommand(tail)
+        .subcommand(skip)
+        .subcommand(sample)
+        .subcommand(aggregate)
+        .subcommand(tile)
+        .subcommand(quadkey)
+        .subcommand(tiles)
+        .subcommand(partition)
+        .subcommand(split_output)
+        .subcommand(cat)
+        .subcommand(schema)
+        .subcommand(estimate)
+        .subcommand(buffer)
+        .subcommand(reproject)
+        .subcommand(matrix)
+        .subcommand(destination)
+        .subcommand(interpolate)
+        .subcommand(zonal)
+        .subcommand(los)
+        .subcommand(plot)
+        .subcommand(serve)
+        .subcommand(geocode)
+        .subcommand(reverse_geocode)
+        .subcommand(elevation)
+        .subcommand(raster)
+        .subcommand(contour)
+        .subcommand(route)
+        .subcommand(isochrone)
+        .subcommand(ogcapi)
+        .subcommand(bench)
+        .subcommand(pipe)
+        .subcommand(transform)
+        .subcommand(completions)
+}
+
+// geoq's app with subcommands added from other crates, whose names can't
+// be taken already
+pub fn app_with(extensions: &[Box<dyn Command>]) -> Result<App<'static, 'static>, Error> {
+    let mut app = app();
+    for c in extensions {
+        let name = c.name();
+        // clap 2 only lists subcommands through its parser, and adds help
+        // itself when parsing
+        let taken = name == "help" || app.p.subcommands().any(|s| s.get_name() == name);
+        if taken {
+            return Err(Error::InvalidInput(format!(
+                "There's already a geoq command named {}",
+                name
+            )));
+        }
+        app = app.subcommand(command::subcommand(c.as_ref()));
+    }
+    Ok(app)
+}
+
+pub fn main() {
+    main_with(vec![])
+}
+
+// Runs geoq with extra subcommands, for crates which build their own geoq
+// binary. See geoq::command.
+pub fn main_with(extensions: Vec<Box<dyn Command>>) {
+    let res = app_with(&extensions).and_then(|app| run(app.get_matches(), &extensions));
+    if let Err(e) = res {
+        report::fatal("Application error", &e);
+        process::exit(1);
+    }
+}
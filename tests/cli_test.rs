@@ -43,7 +43,7 @@ fn invalid_wkt() {
         .with_args(&["read"])
         .stdin(input)
         .stderr()
-        .is("Application error: InvalidWkt")
+        .is("Application error: InvalidWkt\n  at line 1 (byte 0): Polygon ((30 10, 10 30, 40 40, 30 10)")
         .fails()
         .unwrap();
 }
@@ -76,6 +76,33 @@ POINT(78 56)
         .unwrap();
 }
 
+#[test]
+fn reads_cell_ids_as_polygons() {
+    let input = "1/1/0\nqk:1\n";
+    let output = r#"POLYGON((0 0,0 85.0511287798066,180 85.0511287798066,180 0,0 0))
+POLYGON((0 0,0 85.0511287798066,180 85.0511287798066,180 0,0 0))
+"#;
+    Assert::main_binary()
+        .with_args(&["wkt"])
+        .stdin(input)
+        .stdout()
+        .is(output)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["gj", "geom"])
+        .stdin("0/0/0")
+        .stdout()
+        .contains(r#""type":"Polygon""#)
+        .unwrap();
+    // Digits 0-3 without the qk: prefix are still geohashes
+    Assert::main_binary()
+        .with_args(&["wkt"])
+        .stdin("01\n")
+        .stdout()
+        .is("POLYGON((-180 -84.375,-168.75 -84.375,-168.75 -78.75,-180 -78.75,-180 -84.375))")
+        .unwrap();
+}
+
 #[test]
 fn outputs_geojson_geoms() {
     let input = r#"12,34
@@ -377,9 +404,9 @@ s7p
 wcc
 {"type":"Feature","properties":{"a": "b"},"geometry":{"type":"Point","coordinates":[125.6, 10.1]}}
 wcc
-{"geometry":{"coordinates":[34.0,12.0],"type":"Point"},"properties":{},"type":"Feature"}
+{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[34.0,12.0]}}
 sf0
-{"geometry":{"coordinates":[78.0,56.0],"type":"Point"},"properties":{},"type":"Feature"}
+{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[78.0,56.0]}}
 v9z
 "#;
 
@@ -756,3 +783,3509 @@ z
         .is(output)
         .unwrap();
 }
+
+#[test]
+fn props_select() {
+    let input = r#"{"type":"Feature","properties":{"name":"LA","population":3900000,"area":1302},"geometry":{"type":"Point","coordinates":[-118.2,34.0]}}
+{"type":"Feature","properties":{"name":"SF"},"geometry":{"type":"Point","coordinates":[-122.4,37.8]}}
+"#;
+
+    let output = r#"{"geometry":{"coordinates":[-118.2,34.0],"type":"Point"},"properties":{"name":"LA","pop":3900000},"type":"Feature"}
+{"geometry":{"coordinates":[-122.4,37.8],"type":"Point"},"properties":{"name":"SF"},"type":"Feature"}
+"#;
+
+    Assert::main_binary()
+        .with_args(&[
+            "props",
+            "select",
+            "name,population",
+            "--rename",
+            "population:pop",
+        ])
+        .stdin(input)
+        .stdout()
+        .is(output)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["props", "select", "name", "--rename", "name"])
+        .stdin(input)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn props_set() {
+    let input = r#"{"type":"Feature","properties":{"name":"a"},"geometry":{"type":"Polygon","coordinates":[[[0,0],[2,0],[2,2],[0,2],[0,0]]]}}
+"#;
+
+    let output = r#"{"geometry":{"coordinates":[[[0.0,0.0],[2.0,0.0],[2.0,2.0],[0.0,2.0],[0.0,0.0]]],"type":"Polygon"},"properties":{"cx":1,"name":"a","source":"census","verts":5},"type":"Feature"}
+"#;
+
+    Assert::main_binary()
+        .with_args(&[
+            "props",
+            "set",
+            "cx=centroid_x(geom)",
+            "verts=vertex_count(geom)",
+            "source=census",
+        ])
+        .stdin(input)
+        .stdout()
+        .is(output)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["props", "set", "area=area(pizza)"])
+        .stdin(input)
+        .fails()
+        .stderr()
+        .contains("Unknown variable pizza")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["props", "set", "area_km2=area(geom/1e6"])
+        .stdin(input)
+        .fails()
+        .stderr()
+        .contains("Invalid expression")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["props", "set", r#"city="New York""#])
+        .stdin(input)
+        .stdout()
+        .contains(r#""city":"New York""#)
+        .unwrap();
+}
+
+#[test]
+fn filter_type() {
+    let input =
+        "POINT (1 1)\nMULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)))\nPOLYGON ((0 0, 1 0, 1 1, 0 0))\n";
+    Assert::main_binary()
+        .with_args(&["filter", "type", "polygon", "MultiPolygon"])
+        .stdin(input)
+        .stdout()
+        .is("MULTIPOLYGON(((0 0,1 0,1 1,0 0)))\nPOLYGON((0 0,1 0,1 1,0 0))")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["filter", "type", "Polygon", "--negate"])
+        .stdin(input)
+        .stdout()
+        .is("POINT(1 1)\nMULTIPOLYGON(((0 0,1 0,1 1,0 0)))")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["filter", "type", "Square"])
+        .stdin(input)
+        .fails()
+        .stderr()
+        .contains("Unknown geometry type: Square")
+        .unwrap();
+}
+
+#[test]
+fn filter_geometry_empty() {
+    let input = r#"{"type":"Feature","properties":{"a":1},"geometry":null}
+{"type":"Feature","properties":{"a":2},"geometry":{"type":"Point","coordinates":[1,2]}}
+{"type":"GeometryCollection","geometries":[]}
+9q5
+"#;
+
+    let non_empty = r#"{"type":"Feature","properties":{"a":2},"geometry":{"type":"Point","coordinates":[1,2]}}
+9q5
+"#;
+
+    let empty = r#"{"type":"Feature","properties":{"a":1},"geometry":null}
+{"type":"GeometryCollection","geometries":[]}
+"#;
+
+    Assert::main_binary()
+        .with_args(&["filter", "geometry", "--non-empty"])
+        .stdin(input)
+        .stdout()
+        .is(non_empty)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["filter", "geometry", "--empty"])
+        .stdin(input)
+        .stdout()
+        .is(empty)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["filter", "geometry", "--negate"])
+        .stdin(input)
+        .stdout()
+        .is(empty)
+        .unwrap();
+}
+
+#[test]
+fn grep_properties() {
+    let input = r#"{"type":"Feature","properties":{"address":"12 Main St","zip":90210},"geometry":{"type":"Point","coordinates":[1,2]}}
+{"type":"Feature","properties":{"address":"9 Elm St","note":"near Main St"},"geometry":{"type":"Point","coordinates":[3,4]}}
+{"type":"Feature","properties":{"address":"1 Oak Ave","zip":10001},"geometry":{"type":"Point","coordinates":[5,6]}}
+"#;
+
+    let lines: Vec<&str> = input.lines().collect();
+
+    Assert::main_binary()
+        .with_args(&["grep", "Main St"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n", lines[0], lines[1]).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["grep", "Main St", "--property", "address"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n", lines[0]).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["grep", "--regex", "^\\d{5}$", "-p", "zip"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n", lines[0], lines[2]).as_str())
+        .unwrap();
+}
+
+#[test]
+fn id_assign() {
+    let input = "12,34\n9q5\n12,34\n";
+
+    let output = r#"{"geometry":{"coordinates":[34.0,12.0],"type":"Point"},"id":0,"properties":{},"type":"Feature"}
+{"geometry":{"coordinates":[[[-119.53125,33.75],[-118.125,33.75],[-118.125,35.15625],[-119.53125,35.15625],[-119.53125,33.75]]],"type":"Polygon"},"id":1,"properties":{},"type":"Feature"}
+{"geometry":{"coordinates":[34.0,12.0],"type":"Point"},"id":2,"properties":{},"type":"Feature"}
+"#;
+
+    Assert::main_binary()
+        .with_args(&["id", "assign"])
+        .stdin(input)
+        .stdout()
+        .is(output)
+        .unwrap();
+
+    let hashed = r#"{"geometry":{"coordinates":[34.0,12.0],"type":"Point"},"id":"be25b03246df15ac","properties":{},"type":"Feature"}"#;
+    Assert::main_binary()
+        .with_args(&["id", "assign", "--strategy", "hash"])
+        .stdin("12,34\n")
+        .stdout()
+        .is(hashed)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["id", "assign", "--strategy", "uuid"])
+        .stdin("12,34\n")
+        .stdout()
+        .contains(r#""id":""#)
+        .unwrap();
+}
+
+#[test]
+fn dedup() {
+    let input = r#"{"type":"Feature","id":1,"properties":{"name":"a"},"geometry":{"type":"Point","coordinates":[1,2]}}
+{"type":"Feature","id":2,"properties":{"name":"a"},"geometry":{"type":"Point","coordinates":[1.00000001,2]}}
+{"type":"Feature","id":1,"properties":{"name":"b"},"geometry":{"type":"Point","coordinates":[3,4]}}
+"#;
+    let lines: Vec<&str> = input.lines().collect();
+
+    Assert::main_binary()
+        .with_args(&["dedup"])
+        .stdin(input)
+        .stdout()
+        .is(input)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["dedup", "--tolerance", "1e-6"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n", lines[0], lines[2]).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["dedup", "--by", "id"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n", lines[0], lines[1]).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["dedup", "--by", "property:name"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n", lines[0], lines[2]).as_str())
+        .unwrap();
+}
+
+#[test]
+fn diff() {
+    let input = r#"{"type":"Feature","id":1,"properties":{"name":"a"},"geometry":{"type":"Point","coordinates":[1,2]}}
+{"type":"Feature","id":2,"properties":{"name":"b"},"geometry":{"type":"Point","coordinates":[3,5]}}
+{"type":"Feature","id":3,"properties":{"name":"C"},"geometry":{"type":"Point","coordinates":[5,7]}}
+{"type":"Feature","id":5,"properties":{"name":"e"},"geometry":{"type":"Point","coordinates":[9,10]}}
+"#;
+
+    let output = r#"{"change":"modified","changed":["geometry"],"geometry":{"coordinates":[3.0,5.0],"type":"Point"},"id":2,"properties":{"name":"b"},"type":"Feature"}
+{"change":"modified","changed":["geometry","properties"],"geometry":{"coordinates":[5.0,7.0],"type":"Point"},"id":3,"properties":{"name":"C"},"type":"Feature"}
+{"change":"added","geometry":{"coordinates":[9.0,10.0],"type":"Point"},"id":5,"properties":{"name":"e"},"type":"Feature"}
+{"change":"removed","geometry":{"coordinates":[7.0,8.0],"type":"Point"},"id":4,"properties":{"name":"d"},"type":"Feature"}
+"#;
+
+    Assert::main_binary()
+        .with_args(&["diff", "--other", "./tests/resources/diff_old.geojsonl"])
+        .stdin(input)
+        .stdout()
+        .is(output)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&[
+            "diff",
+            "--other",
+            "./tests/resources/diff_old.geojsonl",
+            "--key",
+            "property:name",
+        ])
+        .stdin(input)
+        .stdout()
+        .contains(r#"{"change":"removed","geometry":{"coordinates":[5.0,6.0],"type":"Point"},"id":3,"properties":{"name":"c"},"type":"Feature"}"#)
+        .unwrap();
+}
+
+#[test]
+fn sort_features() {
+    let input = r#"{"type":"Feature","properties":{"n":3},"geometry":{"type":"Point","coordinates":[10,10]}}
+{"type":"Feature","properties":{"n":"b"},"geometry":{"type":"Point","coordinates":[-10,-10]}}
+{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[-10,10]}}
+{"type":"Feature","properties":{"n":1},"geometry":{"type":"Point","coordinates":[10,-10]}}
+"#;
+    let lines: Vec<&str> = input.lines().collect();
+    let by_prop = format!("{}\n{}\n{}\n{}\n", lines[3], lines[0], lines[1], lines[2]);
+
+    Assert::main_binary()
+        .with_args(&["sort", "--by", "property:n"])
+        .stdin(input)
+        .stdout()
+        .is(by_prop.as_str())
+        .unwrap();
+
+    // Spilling to multiple temp file chunks gives the same result
+    Assert::main_binary()
+        .with_args(&["sort", "--by", "property:n", "--chunk-size", "1"])
+        .stdin(input)
+        .stdout()
+        .is(by_prop.as_str())
+        .unwrap();
+
+    // Reversed within each kind of value; missing values still sort last
+    Assert::main_binary()
+        .with_args(&["sort", "--by", "property:n", "--reverse"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n{}\n{}\n", lines[0], lines[3], lines[1], lines[2]).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["sort", "--by", "hilbert"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n{}\n{}\n", lines[1], lines[2], lines[0], lines[3]).as_str())
+        .unwrap();
+}
+
+#[test]
+fn filter_passes_input_through() {
+    // Matching features are output as written, not re-serialized
+    let input = r#"{ "type": "Feature", "properties": {"b": 1, "a": 2}, "geometry": {"type": "Point", "coordinates": [1.50, 2]} }
+{"type":"FeatureCollection","features":[ {"properties":null,"type":"Feature","geometry":{"coordinates":[3,4],"type":"Point"}} ]}
+"#;
+    Assert::main_binary()
+        .with_args(&["filter", "geometry"])
+        .stdin(input)
+        .stdout()
+        .is(r#"{ "type": "Feature", "properties": {"b": 1, "a": 2}, "geometry": {"type": "Point", "coordinates": [1.50, 2]} }
+{"properties":null,"type":"Feature","geometry":{"coordinates":[3,4],"type":"Point"}}"#)
+        .unwrap();
+}
+
+#[test]
+fn head_tail_skip_sample() {
+    let input = r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{"n":0},"geometry":{"type":"Point","coordinates":[0,0]}},{"type":"Feature","properties":{"n":1},"geometry":{"type":"Point","coordinates":[1,1]}},{"type":"Feature","properties":{"n":2},"geometry":{"type":"Point","coordinates":[2,2]}}]}"#;
+    let f0 = r#"{"type":"Feature","properties":{"n":0},"geometry":{"type":"Point","coordinates":[0,0]}}"#;
+    let f1 = r#"{"type":"Feature","properties":{"n":1},"geometry":{"type":"Point","coordinates":[1,1]}}"#;
+    let f2 = r#"{"type":"Feature","properties":{"n":2},"geometry":{"type":"Point","coordinates":[2,2]}}"#;
+
+    Assert::main_binary()
+        .with_args(&["head", "-n", "2"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n", f0, f1).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["tail", "-n", "2"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n", f1, f2).as_str())
+        .unwrap();
+
+    // A count far beyond the input doesn't reserve room for it
+    Assert::main_binary()
+        .with_args(&["tail", "-n", "18446744073709551615"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n{}\n", f0, f1, f2).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["skip", "1"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n", f1, f2).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["sample", "--fraction", "1"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n{}\n", f0, f1, f2).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["sample", "--fraction", "0", "--seed", "1"])
+        .stdin(input)
+        .stdout()
+        .is("")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["sample", "--fraction", "1.5"])
+        .stdin(input)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn aggregate() {
+    let input = r#"{"type":"Feature","properties":{"s":"a","p":1},"geometry":{"type":"Polygon","coordinates":[[[0,0],[1,0],[1,1],[0,1],[0,0]]]}}
+{"type":"Feature","properties":{"s":"b","p":"x"},"geometry":{"type":"Point","coordinates":[5,5]}}
+{"type":"Feature","properties":{"s":"a","p":2.5},"geometry":{"type":"Polygon","coordinates":[[[1,0],[2,0],[2,1],[1,1],[1,0]]]}}
+"#;
+
+    Assert::main_binary()
+        .with_args(&["aggregate", "--by", "property:s", "--sum", "p", "--max", "p"])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"geometry":null,"properties":{"count":2,"p_max":2.5,"p_sum":3.5,"s":"a"},"type":"Feature"}
+{"geometry":null,"properties":{"count":1,"p_max":null,"p_sum":0,"s":"b"},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["aggregate", "--by", "property:s", "--union-geometry"])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[[[1.0,0.0],[2.0,0.0],[2.0,1.0],[1.0,1.0],[0.0,1.0],[0.0,0.0],[1.0,0.0]]]],"type":"MultiPolygon"},"properties":{"count":2,"s":"a"},"type":"Feature"}
+{"geometry":{"coordinates":[5.0,5.0],"type":"Point"},"properties":{"count":1,"s":"b"},"type":"Feature"}"#)
+        .unwrap();
+}
+
+#[test]
+fn filter_time() {
+    let input = r#"{"type":"Feature","properties":{"t":"2022-12-31T23:59:59Z"},"geometry":{"type":"Point","coordinates":[0,0]}}
+{"type":"Feature","properties":{"t":"2023-01-01"},"geometry":{"type":"Point","coordinates":[0,0]}}
+{"type":"Feature","properties":{"t":1685577600},"geometry":{"type":"Point","coordinates":[0,0]}}
+{"type":"Feature","properties":{"t":"2023-07-01T00:00:00"},"geometry":{"type":"Point","coordinates":[0,0]}}
+{"type":"Feature","properties":{"t":"someday"},"geometry":{"type":"Point","coordinates":[0,0]}}
+"#;
+    let lines: Vec<&str> = input.lines().collect();
+
+    Assert::main_binary()
+        .with_args(&[
+            "filter",
+            "time",
+            "-p",
+            "t",
+            "--after",
+            "2023-01-01",
+            "--before",
+            "2023-06-30",
+        ])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n", lines[1], lines[2]).as_str())
+        .unwrap();
+
+    // A plain --before date keeps the whole day
+    Assert::main_binary()
+        .with_args(&["filter", "time", "-p", "t", "--before", "2023-07-01"])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n{}\n{}\n", lines[0], lines[1], lines[2], lines[3]).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&[
+            "filter",
+            "time",
+            "-p",
+            "t",
+            "--before",
+            "2023-07-01T00:00:00Z",
+        ])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n{}\n", lines[0], lines[1], lines[2]).as_str())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&[
+            "filter",
+            "time",
+            "-p",
+            "t",
+            "--before",
+            "2022-12-31",
+            "--negate",
+        ])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}\n{}\n{}\n{}\n", lines[1], lines[2], lines[3], lines[4]).as_str())
+        .unwrap();
+}
+
+#[test]
+fn props_values() {
+    let input = r#"{"type":"Feature","properties":{"landuse":"farm"},"geometry":null}
+{"type":"Feature","properties":{"landuse":"park"},"geometry":null}
+{"type":"Feature","properties":{"landuse":"farm"},"geometry":null}
+{"type":"Feature","properties":{"landuse":1},"geometry":null}
+{"type":"Feature","properties":{},"geometry":null}
+"#;
+
+    Assert::main_binary()
+        .with_args(&["props", "values", "landuse"])
+        .stdin(input)
+        .stdout()
+        .is("2\t\"farm\"\n1\t\"park\"\n1\t(missing)\n1\t1")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["props", "values", "landuse", "--top", "1"])
+        .stdin(input)
+        .stdout()
+        .is("2\t\"farm\"")
+        .unwrap();
+}
+
+#[test]
+fn foreign_members() {
+    let input = r#"{"type":"Feature","crs":{"type":"name"},"properties":{"a":1},"geometry":{"type":"Point","coordinates":[1,2],"g":true}}"#;
+
+    Assert::main_binary()
+        .with_args(&["props", "set", "b=2"])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"crs":{"type":"name"},"geometry":{"coordinates":[1.0,2.0],"g":true,"type":"Point"},"properties":{"a":1,"b":2},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["--strip-foreign", "gj", "f"])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[1.0,2.0],"type":"Point"},"properties":{"a":1},"type":"Feature"}"#)
+        .unwrap();
+
+    let fc = r#"{"type":"FeatureCollection","crs":{"type":"name"},"features":[{"type":"Feature","properties":{"a":1},"geometry":null}]}"#;
+    Assert::main_binary()
+        .with_args(&["gj", "f"])
+        .stdin(fc)
+        .stdout()
+        .is(r#"{"crs":{"type":"name"},"geometry":null,"properties":{"a":1},"type":"Feature"}"#)
+        .unwrap();
+}
+
+#[test]
+fn gh_compact() {
+    let mut input: String = "0123456789bcdefghjkmnpqrstuvwxyz"
+        .chars()
+        .map(|c| format!("9q{}\n", c))
+        .collect();
+    input.push_str("9r1\n9r1c\n");
+
+    Assert::main_binary()
+        .with_args(&["gh", "compact"])
+        .stdin(input.as_str())
+        .stdout()
+        .is("9q\n9r1")
+        .unwrap();
+}
+
+#[test]
+fn gh_decode() {
+    Assert::main_binary()
+        .with_args(&["gh", "decode"])
+        .stdin("9q5\n")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[-118.828125,34.453125],"type":"Point"},"properties":{"geohash":"9q5","lat_err":0.703125,"lon_err":0.703125},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["gh", "decode", "--bbox"])
+        .stdin("9q5\n")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[[-119.53125,33.75],[-118.125,33.75],[-118.125,35.15625],[-119.53125,35.15625],[-119.53125,33.75]]],"type":"Polygon"},"properties":{"geohash":"9q5","lat_err":0.703125,"lon_err":0.703125},"type":"Feature"}"#)
+        .unwrap();
+}
+
+#[test]
+fn gh_cover() {
+    Assert::main_binary()
+        .with_args(&["gh", "cover", "--max-level", "5"])
+        .stdin("34.0,-118.2\n")
+        .stdout()
+        .is("9q5cq")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["gh", "cover", "--max-cells", "8"])
+        .stdin(r#"{"type":"Polygon","coordinates":[[[-118.5,33.8],[-117.9,33.8],[-117.9,34.3],[-118.5,34.3],[-118.5,33.8]]]}"#)
+        .stdout()
+        .is("9q5\n9qh")
+        .unwrap();
+}
+
+#[test]
+fn h3_compact_uncompact() {
+    let children = "8b2a1072b598fff\n8b2a1072b599fff\n8b2a1072b59afff\n8b2a1072b59bfff\n8b2a1072b59cfff\n8b2a1072b59dfff\n8b2a1072b59efff";
+
+    Assert::main_binary()
+        .with_args(&["h3", "uncompact", "11"])
+        .stdin("8a2a1072b59ffff\n")
+        .stdout()
+        .is(children)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["h3", "compact"])
+        .stdin(children)
+        .stdout()
+        .is("8a2a1072b59ffff")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["h3", "uncompact", "9"])
+        .stdin("8a2a1072b59ffff\n")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn tile() {
+    Assert::main_binary()
+        .with_args(&["tile", "for-point", "12"])
+        .stdin("34.0522,-118.2437\n")
+        .stdout()
+        .is("12/702/1635")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["tile", "cover", "2"])
+        .stdin("LINESTRING (-10 10, 10 10)\n")
+        .stdout()
+        .is("2/1/1\n2/2/1")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["tile", "parent"])
+        .stdin("12/702/1635\n")
+        .stdout()
+        .is("11/351/817")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["tile", "children", "0/0/0"])
+        .stdout()
+        .is("1/0/0\n1/1/0\n1/0/1\n1/1/1")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["tile", "bbox", "1/0/0"])
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[[-180.0,0.0],[-180.0,85.0511287798066],[0.0,85.0511287798066],[0.0,0.0],[-180.0,0.0]]],"type":"Polygon"},"properties":{"tile":"1/0/0"},"type":"Feature"}"#)
+        .unwrap();
+}
+
+#[test]
+fn quadkey() {
+    Assert::main_binary()
+        .with_args(&["quadkey", "encode", "3/3/5"])
+        .stdout()
+        .is("213")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["quadkey", "decode"])
+        .stdin("213\n0\n")
+        .stdout()
+        .is("3/3/5\n1/0/0")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["quadkey", "point", "12"])
+        .stdin("34.0522,-118.2437\n")
+        .stdout()
+        .is("023012311132")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["quadkey", "decode", "0124"])
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn tiles_build() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("tiles.mbtiles");
+    let out_str = out.to_str().unwrap();
+    let input = r#"{"type":"Feature","properties":{"name":"a"},"geometry":{"type":"Polygon","coordinates":[[[-10,-10],[10,-10],[10,10],[-10,10],[-10,-10]]]}}
+34.0,-118.0
+"#;
+
+    Assert::main_binary()
+        .with_args(&["tiles", "build", "--max-zoom", "2", "--out", out_str])
+        .stdin(input)
+        .unwrap();
+
+    let conn = rusqlite::Connection::open(&out).unwrap();
+    let counts: Vec<(u8, u32)> = conn
+        .prepare("SELECT zoom_level, count(*) FROM tiles GROUP BY zoom_level")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(vec![(0, 1), (1, 4), (2, 5)], counts);
+    let format: String = conn
+        .query_row(
+            "SELECT value FROM metadata WHERE name = 'format'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!("pbf", format);
+
+    Assert::main_binary()
+        .with_args(&["tiles", "build", "--out", out_str])
+        .stdin(input)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn tiles_build_pmtiles() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("tiles.pmtiles");
+
+    Assert::main_binary()
+        .with_args(&[
+            "tiles",
+            "build",
+            "--max-zoom",
+            "2",
+            "--out",
+            out.to_str().unwrap(),
+        ])
+        .stdin("34.0,-118.0\n")
+        .unwrap();
+
+    let bytes = std::fs::read(&out).unwrap();
+    assert_eq!(b"PMTiles\x03", &bytes[0..8]);
+    // Addressed tiles count: one tile per zoom
+    assert_eq!(3u64.to_le_bytes(), bytes[72..80]);
+    // Min and max zoom
+    assert_eq!([0, 2], bytes[100..102]);
+}
+
+#[test]
+fn tiles_build_profile() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("tiles.mbtiles");
+    let out_str = out.to_str().unwrap();
+    let profile = dir.path().join("profile.json");
+    std::fs::write(
+        &profile,
+        r#"[{"min_zoom": 0, "max_zoom": 0, "min_area": 64, "properties": ["name"]}]"#,
+    )
+    .unwrap();
+    let input = r#"{"type":"Feature","properties":{"name":"big","pop":1},"geometry":{"type":"Polygon","coordinates":[[[-10,-10],[10,-10],[10,10],[-10,10],[-10,-10]]]}}
+{"type":"Feature","properties":{"name":"small","pop":2},"geometry":{"type":"Polygon","coordinates":[[[1,1],[1.5,1],[1.5,1.5],[1,1.5],[1,1]]]}}
+"#;
+
+    Assert::main_binary()
+        .with_args(&[
+            "tiles",
+            "build",
+            "--max-zoom",
+            "1",
+            "--profile",
+            profile.to_str().unwrap(),
+            "--out",
+            out_str,
+        ])
+        .stdin(input)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["tiles", "read", out_str, "0/0/0"])
+        .stdout()
+        .satisfies(
+            |out| out.lines().count() == 1 && out.contains(r#""properties":{"name":"big"}"#),
+            "small feature and pop property should be dropped at zoom 0",
+        )
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["tiles", "read", out_str, "1/1/0"])
+        .stdout()
+        .contains(r#""properties":{"name":"small","pop":2}"#)
+        .unwrap();
+}
+
+#[test]
+fn tiles_build_buffer() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("tiles.mbtiles");
+    let out_str = out.to_str().unwrap();
+    // Just east of the meridian, within the default buffer of 1/0/0
+    let input = "45,0.5\n";
+
+    for (buffer, tiles) in &[("64", "0/0/0\n1/0/0\n1/1/0"), ("0", "0/0/0\n1/1/0")] {
+        Assert::main_binary()
+            .with_args(&[
+                "tiles",
+                "build",
+                "--max-zoom",
+                "1",
+                "--buffer",
+                buffer,
+                "--force",
+                "--out",
+                out_str,
+            ])
+            .stdin(input)
+            .unwrap();
+        Assert::main_binary()
+            .with_args(&["tiles", "list", out_str])
+            .stdout()
+            .is(*tiles)
+            .unwrap();
+    }
+
+    Assert::main_binary()
+        .with_args(&[
+            "tiles", "build", "--buffer", "5000", "--force", "--out", out_str,
+        ])
+        .stdin(input)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn tiles_read_and_list() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in &["tiles.mbtiles", "tiles.pmtiles"] {
+        let out = dir.path().join(name);
+        let out_str = out.to_str().unwrap();
+
+        Assert::main_binary()
+            .with_args(&["tiles", "build", "--max-zoom", "1", "--out", out_str])
+            .stdin("{\"type\":\"Feature\",\"id\":3,\"properties\":{\"a\":\"b\"},\"geometry\":{\"type\":\"Point\",\"coordinates\":[-90,45]}}\n")
+            .unwrap();
+
+        Assert::main_binary()
+            .with_args(&["tiles", "list", out_str])
+            .stdout()
+            .is("0/0/0\n1/0/0")
+            .unwrap();
+
+        Assert::main_binary()
+            .with_args(&["tiles", "read", out_str, "1/0/0"])
+            .stdout()
+            .is(r#"{"geometry":{"coordinates":[-90.0,44.99588261816545],"type":"Point"},"id":3,"layer":"geoq","properties":{"a":"b"},"type":"Feature"}"#)
+            .unwrap();
+
+        Assert::main_binary()
+            .with_args(&["tiles", "read", out_str, "1/1/1"])
+            .fails()
+            .unwrap();
+    }
+}
+
+#[test]
+fn partition() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().to_str().unwrap();
+    let input = "34.0,-118.0\n34.1,-118.1\n{\"type\":\"LineString\",\"coordinates\":[[-118,34],[-100,40]]}\n";
+
+    Assert::main_binary()
+        .with_args(&["partition", "--by", "geohash:2", "--out-dir", out])
+        .stdin(input)
+        .stdout()
+        .is(format!("{}/9q.geojson\n{}/9w.geojson", out, out).as_str())
+        .unwrap();
+    // Written as GeoJSON, whatever the input format
+    let point = |lon: &str, lat: &str| {
+        format!(
+            r#"{{"geometry":{{"coordinates":[{},{}],"type":"Point"}},"properties":{{}},"type":"Feature"}}"#,
+            lon, lat
+        )
+    };
+    assert_eq!(
+        format!("{}\n{}\n", point("-118.0", "34.0"), point("-118.1", "34.1")),
+        std::fs::read_to_string(dir.path().join("9q.geojson")).unwrap()
+    );
+
+    Assert::main_binary()
+        .with_args(&[
+            "partition",
+            "--by",
+            "geohash:2",
+            "--out-dir",
+            out,
+            "--assign",
+            "duplicate",
+        ])
+        .stdin(input)
+        .unwrap();
+    assert_eq!(
+        format!(
+            "{}\n{}\n{}\n",
+            point("-118.0", "34.0"),
+            point("-118.1", "34.1"),
+            r#"{"geometry":{"coordinates":[[-118.0,34.0],[-100.0,40.0]],"type":"LineString"},"properties":{},"type":"Feature"}"#
+        ),
+        std::fs::read_to_string(dir.path().join("9q.geojson")).unwrap()
+    );
+    assert!(dir.path().join("9x.geojson").exists());
+
+    Assert::main_binary()
+        .with_args(&["partition", "--by", "h3:5", "--out-dir", out])
+        .stdin(input)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn plot() {
+    Assert::main_binary()
+        .with_args(&["plot", "--width", "10", "--height", "5", "--ascii"])
+        .stdin("{\"type\":\"LineString\",\"coordinates\":[[0,0],[10,10]]}\n")
+        .stdout()
+        .contains("      ##\n    ##\n  ##\n##\nextent: -0.2000,-0.2000,10.2000,10.2000")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["plot"])
+        .stdin("")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn serve_fgb() {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::process::{Command, Stdio};
+
+    let dir = tempfile::tempdir().unwrap();
+    let fgb = dir.path().join("data.fgb");
+    Assert::main_binary()
+        .with_args(&["fgb", "write", fgb.to_str().unwrap()])
+        .stdin("{\"type\":\"Feature\",\"properties\":{\"name\":\"b\"},\"geometry\":{\"type\":\"Point\",\"coordinates\":[-118,34]}}\n")
+        .unwrap();
+
+    // Any free port, as printed at startup
+    let mut server = Command::new(env!("CARGO_BIN_EXE_geoq"))
+        .args(["serve", "fgb", fgb.to_str().unwrap(), "--port", "0"])
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stderr = std::io::BufReader::new(server.stderr.take().unwrap());
+    let mut started = String::new();
+    std::io::BufRead::read_line(&mut stderr, &mut started).unwrap();
+    let addr = started
+        .trim()
+        .strip_prefix("Serving tiles at http://")
+        .and_then(|s| s.strip_suffix("/{z}/{x}/{y}.mvt"))
+        .unwrap()
+        .to_string();
+    assert!(!addr.ends_with(":0"));
+
+    let get = |path: &str| -> Option<String> {
+        let mut stream = TcpStream::connect(&addr).ok()?;
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: tiles.example.com\r\n\r\n",
+            path
+        )
+        .ok()?;
+        let mut response = vec![];
+        stream.read_to_end(&mut response).ok()?;
+        Some(String::from_utf8_lossy(&response).to_string())
+    };
+    let response = get("/1/0/0.mvt");
+    let empty = get("/1/1/1.mvt");
+    let missing = get("/nope");
+    let tilejson = get("/tiles.json");
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    let response = response.expect("Server should respond");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Type: application/vnd.mapbox-vector-tile\r\n"));
+    assert!(empty.unwrap().starts_with("HTTP/1.1 204 No Content\r\n"));
+    assert!(missing.unwrap().starts_with("HTTP/1.1 404 Not Found\r\n"));
+    // Tile URLs use the host the client asked for
+    assert!(tilejson
+        .unwrap()
+        .contains(r#""tiles":["http://tiles.example.com/{z}/{x}/{y}.mvt"]"#));
+}
+
+// Answers each request with a single Nominatim result for searches
+// mentioning Pennsylvania or reverse lookups at lat 38.9, and no
+// results otherwise
+fn mock_nominatim() -> String {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request_line = String::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            reader.read_line(&mut request_line).unwrap();
+            let body = if request_line.contains("/reverse") {
+                if request_line.contains("lat=38.9") {
+                    r#"{"lat":"38.9","lon":"-77.03","display_name":"Washington","address":{"city":"Washington","country_code":"us"},"osm_type":"relation","osm_id":5396194}"#
+                } else {
+                    r#"{"error":"Unable to geocode"}"#
+                }
+            } else if request_line.contains("Pennsylvania") {
+                r#"[{"lat":"38.8976633","lon":"-77.0365739","boundingbox":["38.897","38.898","-77.037","-77.036"],"display_name":"White House","address":{"house_number":"1600"},"osm_type":"way","osm_id":238241022}]"#
+            } else {
+                "[]"
+            };
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[test]
+fn geocode() {
+    let endpoint = mock_nominatim();
+    Assert::main_binary()
+        .with_args(&["geocode", "1600 Pennsylvania Ave", "--endpoint", &endpoint])
+        .stdout()
+        .is(r#"{"bbox":[-77.037,38.897,-77.036,38.898],"geometry":{"coordinates":[-77.0365739,38.8976633],"type":"Point"},"properties":{"address":{"house_number":"1600"},"display_name":"White House","osm_id":238241022,"osm_type":"way","query":"1600 Pennsylvania Ave"},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["geocode", "--endpoint", &endpoint, "--rate", "100"])
+        .stdin("nowhere\n1600 Pennsylvania Ave\n")
+        .stdout()
+        .contains(r#""query":"1600 Pennsylvania Ave""#)
+        .stderr()
+        .contains("No geocoding results for: nowhere")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["geocode", "--csv-column", "address", "--endpoint", &endpoint, "--rate", "100"])
+        .stdin("name,address\nwh,\"1600 Pennsylvania Ave, Washington\"\n")
+        .stdout()
+        .contains(r#""properties":{"address":{"house_number":"1600"},"display_name":"White House","name":"wh","osm_id":238241022,"osm_type":"way"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["geocode", "--property", "addr", "--endpoint", &endpoint])
+        .stdin("{\"type\":\"Feature\",\"properties\":{\"addr\":\"1600 Pennsylvania Ave\",\"n\":1},\"geometry\":null}\n")
+        .stdout()
+        .contains(r#""geometry":{"coordinates":[-77.0365739,38.8976633],"type":"Point"},"properties":{"addr":"1600 Pennsylvania Ave","address":{"house_number":"1600"},"display_name":"White House","n":1,"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["geocode", "--csv-column", "nope", "--endpoint", &endpoint])
+        .stdin("name,address\n")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn reverse_geocode() {
+    let endpoint = mock_nominatim();
+    Assert::main_binary()
+        .with_args(&["reverse-geocode", "--endpoint", &endpoint, "--rate", "100"])
+        .stdin("{\"type\":\"Feature\",\"properties\":{\"n\":1},\"geometry\":{\"type\":\"Point\",\"coordinates\":[-77.03,38.9]}}\n0,0\n")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[-77.03,38.9],"type":"Point"},"properties":{"address":{"city":"Washington","country_code":"us"},"display_name":"Washington","n":1,"osm_id":5396194,"osm_type":"relation"},"type":"Feature"}
+{"geometry":{"coordinates":[0.0,0.0],"type":"Point"},"properties":{},"type":"Feature"}"#)
+        .stderr()
+        .contains("No reverse geocoding result for: 0,0")
+        .unwrap();
+}
+
+#[test]
+fn reverse_geocode_boundaries() {
+    let dir = tempfile::tempdir().unwrap();
+    let boundaries = dir.path().join("boundaries.geojson");
+    std::fs::write(
+        &boundaries,
+        r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{"name":"West","iso":"W"},"geometry":{"type":"Polygon","coordinates":[[[-10,-10],[0,-10],[0,10],[-10,10],[-10,-10]]]}},{"type":"Feature","properties":{"name":"Little","level":2},"geometry":{"type":"Polygon","coordinates":[[[-6,-1],[-4,-1],[-4,1],[-6,1],[-6,-1]]]}}]}"#,
+    )
+    .unwrap();
+    Assert::main_binary()
+        .with_args(&["reverse-geocode", "--boundaries", boundaries.to_str().unwrap()])
+        .stdin("0,-5\n0,5\n")
+        .stdout()
+        .contains(r#"{"geometry":{"coordinates":[-5.0,0.0],"type":"Point"},"properties":{"iso":"W","level":2,"name":"Little"},"type":"Feature"}"#)
+        .stdout()
+        .contains(r#"{"geometry":{"coordinates":[5.0,0.0],"type":"Point"},"properties":{},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["reverse-geocode", "-b", boundaries.to_str().unwrap(), "--fields", "iso"])
+        .stdin("0,-5\n")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[-5.0,0.0],"type":"Point"},"properties":{"iso":"W"},"type":"Feature"}"#)
+        .unwrap();
+}
+
+// A 2x2 RGB PNG encoding Terrarium elevations of 100m in the
+// left column and 200m in the right
+fn terrarium_tile() -> Vec<u8> {
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::Write;
+
+    let chunk = |out: &mut Vec<u8>, kind: &[u8], body: &[u8]| {
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out.extend_from_slice(&[0, 0, 0, 0]);
+    };
+    let mut header = vec![0, 0, 0, 2, 0, 0, 0, 2];
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    let row = [0, 128, 100, 0, 128, 200, 0];
+    let mut z = ZlibEncoder::new(vec![], Compression::default());
+    z.write_all(&row).unwrap();
+    z.write_all(&row).unwrap();
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &z.finish().unwrap());
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+#[test]
+fn elevation() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("0/0")).unwrap();
+    std::fs::write(dir.path().join("0/0/0.png"), terrarium_tile()).unwrap();
+    let dem = dir.path().to_str().unwrap();
+
+    Assert::main_binary()
+        .with_args(&["elevation", "--dem", dem, "--zoom", "0"])
+        .stdin("0,0\n0,-90\n")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[0.0,0.0],"type":"Point"},"properties":{"elevation":150.0},"type":"Feature"}
+{"geometry":{"coordinates":[-90.0,0.0],"type":"Point"},"properties":{"elevation":100.0},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["elevation", "--dem", &format!("{}/{{z}}/{{x}}/{{y}}.png", dem), "--zoom", "0", "--z"])
+        .stdin("LINESTRING (-90 0, 0 0)\n")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[-90.0,0.0,100.0],[0.0,0.0,150.0]],"type":"LineString"},"properties":{},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["elevation", "--dem", dem, "--zoom", "1"])
+        .stdin("0,0\n")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[0.0,0.0],"type":"Point"},"properties":{"elevation":null},"type":"Feature"}"#)
+        .unwrap();
+}
+
+// An uncompressed, single strip GeoTIFF of 8-bit values, in WGS84 with
+// the top left corner at 10,50 and 1 degree pixels
+fn geotiff(width: u32, values: &[u8]) -> Vec<u8> {
+    let height = values.len() as u32 / width;
+    let mut extra: Vec<u8> = vec![];
+    let mut entries: Vec<(u16, u16, u32, u32)> = vec![];
+    let extra_start = 8 + values.len() as u32 + 2 + 12 * 11 + 4;
+    let mut push_extra = |bytes: &[u8]| {
+        let offset = extra_start + extra.len() as u32;
+        extra.extend_from_slice(bytes);
+        offset
+    };
+    let doubles = |d: &[f64]| d.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>();
+    let scale = push_extra(&doubles(&[1.0, 1.0, 0.0]));
+    let tiepoint = push_extra(&doubles(&[0.0, 0.0, 0.0, 10.0, 50.0, 0.0]));
+    let keys: Vec<u8> = [1u16, 1, 0, 2, 1024, 0, 1, 2, 2048, 0, 1, 4326]
+        .iter()
+        .flat_map(|k| k.to_le_bytes())
+        .collect();
+    let keys = push_extra(&keys);
+    entries.push((256, 4, 1, width));
+    entries.push((257, 4, 1, height));
+    entries.push((258, 3, 1, 8));
+    entries.push((259, 3, 1, 1));
+    entries.push((273, 4, 1, 8));
+    entries.push((278, 4, 1, height));
+    entries.push((279, 4, 1, values.len() as u32));
+    entries.push((33550, 12, 3, scale));
+    entries.push((33922, 12, 6, tiepoint));
+    entries.push((34735, 3, 12, keys));
+    entries.push((42113, 2, 2, u32::from_le_bytes([b'0', 0, 0, 0])));
+
+    let mut tiff = b"II*\0".to_vec();
+    tiff.extend_from_slice(&(8 + values.len() as u32).to_le_bytes());
+    tiff.extend_from_slice(values);
+    tiff.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    for (tag, kind, count, value) in entries {
+        tiff.extend_from_slice(&tag.to_le_bytes());
+        tiff.extend_from_slice(&kind.to_le_bytes());
+        tiff.extend_from_slice(&count.to_le_bytes());
+        if kind == 3 && count == 1 {
+            tiff.extend_from_slice(&(value as u16).to_le_bytes());
+            tiff.extend_from_slice(&[0, 0]);
+        } else {
+            tiff.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    tiff.extend_from_slice(&[0, 0, 0, 0]);
+    tiff.extend_from_slice(&extra);
+    tiff
+}
+
+#[test]
+fn raster_polygonize() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("landcover.tif");
+    std::fs::write(&path, geotiff(3, &[1, 1, 1, 1, 2, 1, 1, 1, 0])).unwrap();
+    Assert::main_binary()
+        .with_args(&["raster", "polygonize", path.to_str().unwrap()])
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[[10.0,50.0],[10.0,47.0],[12.0,47.0],[12.0,48.0],[13.0,48.0],[13.0,50.0],[10.0,50.0]],[[11.0,49.0],[12.0,49.0],[12.0,48.0],[11.0,48.0],[11.0,49.0]]],"type":"Polygon"},"properties":{"value":1},"type":"Feature"}
+{"geometry":{"coordinates":[[[11.0,49.0],[11.0,48.0],[12.0,48.0],[12.0,49.0],[11.0,49.0]]],"type":"Polygon"},"properties":{"value":2},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&[
+            "raster",
+            "polygonize",
+            path.to_str().unwrap(),
+            "--nodata",
+            "2",
+        ])
+        .stdout()
+        .contains(r#""properties":{"value":0}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&[
+            "raster",
+            "polygonize",
+            path.to_str().unwrap(),
+            "--band",
+            "2",
+        ])
+        .fails()
+        .stderr()
+        .contains("Invalid band: 2")
+        .unwrap();
+}
+
+#[test]
+fn contour() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dem.tif");
+    std::fs::write(&path, geotiff(3, &[1, 1, 1, 1, 9, 1, 1, 1, 1])).unwrap();
+    Assert::main_binary()
+        .with_args(&["contour", "--interval", "5", "--raster", path.to_str().unwrap()])
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[11.5,49.0],[11.0,49.0],[11.0,48.5],[11.5,48.0],[12.0,48.0],[12.0,48.5],[11.5,49.0]],"type":"LineString"},"properties":{"elevation":5},"type":"Feature"}"#)
+        .unwrap();
+
+    let points = r#"{"type":"Point","coordinates":[0,0,0]}
+{"type":"Point","coordinates":[2,0,0]}
+{"type":"Point","coordinates":[2,2,0]}
+{"type":"Point","coordinates":[0,2,0]}
+{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[1,1,10]}}"#;
+    Assert::main_binary()
+        .with_args(&["contour", "--interval", "4", "--base", "1"])
+        .stdin(points)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[0.1,1.9],[0.1,0.1],[1.9,0.1],[1.9,1.9],[0.1,1.9]],"type":"LineString"},"properties":{"elevation":1},"type":"Feature"}
+{"geometry":{"coordinates":[[0.5,1.5],[0.5,0.5],[1.5,0.5],[1.5,1.5],[0.5,1.5]],"type":"LineString"},"properties":{"elevation":5},"type":"Feature"}
+{"geometry":{"coordinates":[[0.9,1.1],[0.9,0.9],[1.1,0.9],[1.1,1.1],[0.9,1.1]],"type":"LineString"},"properties":{"elevation":9},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["contour", "--interval", "5", "--z-property", "depth"])
+        .stdin(r#"{"type":"Point","coordinates":[0,0,1]}"#)
+        .fails()
+        .and()
+        .stderr()
+        .contains("Expected point with Z coordinate or elevation property")
+        .unwrap();
+}
+
+// Serves OSRM route requests, with no route to 0,0, Valhalla route
+// requests, with no route for pedestrians, and Valhalla and
+// openrouteservice isochrone requests
+fn mock_router() -> String {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            let mut authorized = false;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                let lower = header.to_lowercase();
+                if let Some(len) = lower.strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+                authorized |= lower.trim() == "authorization: secret";
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let body = String::from_utf8(body).unwrap();
+            let (status, response) = if request_line.starts_with("GET /route/v1/car/") {
+                if request_line.contains("0,0") {
+                    (
+                        "400 Bad Request",
+                        r#"{"code":"NoRoute","message":"Impossible route between points"}"#,
+                    )
+                } else {
+                    (
+                        "200 OK",
+                        r#"{"code":"Ok","routes":[{"geometry":{"type":"LineString","coordinates":[[-77.03,38.89],[-77.02,38.9],[-77.01,38.91]]},"duration":300.5,"distance":2500.2}]}"#,
+                    )
+                }
+            } else if request_line.starts_with("POST /isochrone") {
+                (
+                    "200 OK",
+                    r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{"contour":20},"geometry":{"type":"Polygon","coordinates":[[[0,0],[2,0],[2,2],[0,0]]]}},{"type":"Feature","properties":{"contour":10},"geometry":{"type":"Polygon","coordinates":[[[0,0],[1,0],[1,1],[0,0]]]}}]}"#,
+                )
+            } else if request_line.starts_with("POST /v2/isochrones/foot-walking") && authorized {
+                (
+                    "200 OK",
+                    r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{"value":450.0},"geometry":{"type":"Polygon","coordinates":[[[0,0],[1,0],[1,1],[0,0]]]}}]}"#,
+                )
+            } else if request_line.starts_with("POST /v2/isochrones") {
+                (
+                    "403 Forbidden",
+                    r#"{"error":{"code":403,"message":"Access denied"}}"#,
+                )
+            } else if request_line.starts_with("POST /route") && body.contains(r#""auto""#) {
+                (
+                    "200 OK",
+                    r#"{"trip":{"legs":[{"shape":"_`tdiA~ep|qC_pR_pR_pR_pR"}],"summary":{"time":310.0,"length":2.61}}}"#,
+                )
+            } else if request_line.starts_with("POST /route") {
+                (
+                    "400 Bad Request",
+                    r#"{"error_code":442,"error":"No path could be found for input"}"#,
+                )
+            } else {
+                (
+                    "404 Not Found",
+                    r#"{"code":"InvalidUrl","message":"URL string malformed"}"#,
+                )
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                response.len(),
+                response
+            )
+            .unwrap();
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[test]
+fn route() {
+    let endpoint = mock_router();
+    Assert::main_binary()
+        .with_args(&["route", "--endpoint", &endpoint])
+        .stdin(r#"{"type":"Feature","properties":{"name":"commute"},"geometry":{"type":"LineString","coordinates":[[-77.03,38.89],[-77.01,38.91]]}}"#)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[-77.03,38.89],[-77.02,38.9],[-77.01,38.91]],"type":"LineString"},"properties":{"distance":2500.2,"duration":300.5,"name":"commute"},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["route", "--endpoint", &endpoint])
+        .stdin("MULTIPOINT((0 0),(1 1))\nLINESTRING (-77.03 38.89, -77.01 38.91)\n")
+        .stdout()
+        .contains(r#""duration":300.5"#)
+        .stderr()
+        .contains("No route found for: MULTIPOINT((0 0),(1 1))")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["route", "--engine", "valhalla", "--endpoint", &endpoint])
+        .stdin("LINESTRING (-77.03 38.89, -77.01 38.91)\n")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[-77.03,38.89],[-77.02,38.9],[-77.01,38.91]],"type":"LineString"},"properties":{"distance":2610.0,"duration":310.0},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&[
+            "route",
+            "--engine",
+            "valhalla",
+            "--profile",
+            "foot",
+            "--endpoint",
+            &endpoint,
+        ])
+        .stdin("LINESTRING (-77.03 38.89, -77.01 38.91)\n")
+        .stdout()
+        .is("")
+        .stderr()
+        .contains("No route found for")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["route", "--endpoint", &endpoint])
+        .stdin("POINT (0 0)\n")
+        .fails()
+        .and()
+        .stderr()
+        .contains("Expected LineString or MultiPoint with at least 2 points")
+        .unwrap();
+}
+
+// An OSRM server which answers its first `failures` requests with 503s
+fn flaky_router(failures: usize) -> String {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for (i, stream) in listener.incoming().enumerate() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let (status, response) = if i < failures {
+                ("503 Service Unavailable", "busy")
+            } else {
+                (
+                    "200 OK",
+                    r#"{"code":"Ok","routes":[{"geometry":{"type":"LineString","coordinates":[[0,0],[1,1]]},"duration":1,"distance":2}]}"#,
+                )
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nRetry-After: 0\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                response.len(),
+                response
+            )
+            .unwrap();
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[test]
+fn http_retries() {
+    let input: String = (0..10)
+        .map(|i| format!("{{\"type\":\"Feature\",\"properties\":{{\"n\":{}}},\"geometry\":{{\"type\":\"LineString\",\"coordinates\":[[0,0],[1,1]]}}}}\n", i))
+        .collect();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_geoq"))
+        .args(["route", "--http-concurrency", "4", "--endpoint"])
+        .arg(flaky_router(2))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child.stdin.take().unwrap().write_all(input.as_bytes())?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    assert!(output.status.success());
+    let numbers: Vec<String> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| {
+            l.split("\"n\":")
+                .nth(1)
+                .unwrap()
+                .split('}')
+                .next()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!((0..10).map(|i| i.to_string()).collect::<Vec<_>>(), numbers);
+
+    Assert::main_binary()
+        .with_args(&[
+            "route",
+            "--http-retries",
+            "0",
+            "--endpoint",
+            &flaky_router(1),
+        ])
+        .stdin("LINESTRING (0 0, 1 1)\n")
+        .fails()
+        .and()
+        .stderr()
+        .contains("OSRM request failed with 503")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["route", "--http-concurrency", "0"])
+        .stdin("LINESTRING (0 0, 1 1)\n")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn isochrone() {
+    let endpoint = mock_router();
+    Assert::main_binary()
+        .with_args(&["isochrone", "--minutes", "10,20", "--endpoint", &endpoint])
+        .stdin(r#"{"type":"Feature","properties":{"name":"home"},"geometry":{"type":"Point","coordinates":[0.5,0.2]}}"#)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[[0.0,0.0],[1.0,0.0],[1.0,1.0],[0.0,0.0]]],"type":"Polygon"},"properties":{"minutes":10,"name":"home"},"type":"Feature"}
+{"geometry":{"coordinates":[[[0.0,0.0],[2.0,0.0],[2.0,2.0],[0.0,0.0]]],"type":"Polygon"},"properties":{"minutes":20,"name":"home"},"type":"Feature"}"#)
+        .unwrap();
+
+    let ors = [
+        "isochrone",
+        "--minutes",
+        "7.5",
+        "--engine",
+        "ors",
+        "--profile",
+        "foot",
+        "--endpoint",
+        &endpoint,
+    ];
+    Assert::main_binary()
+        .with_args(&ors)
+        .with_args(&["--api-key", "secret"])
+        .stdin("POINT (0.5 0.2)")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[[0.0,0.0],[1.0,0.0],[1.0,1.0],[0.0,0.0]]],"type":"Polygon"},"properties":{"minutes":7.5},"type":"Feature"}"#)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&ors)
+        .stdin("POINT (0.5 0.2)")
+        .fails()
+        .and()
+        .stderr()
+        .contains("Access denied")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["isochrone", "--minutes", "10,x", "--endpoint", &endpoint])
+        .stdin("POINT (0.5 0.2)")
+        .fails()
+        .and()
+        .stderr()
+        .contains("Expected comma-separated positive minutes")
+        .unwrap();
+}
+
+// Serves an OGC API - Features collection of 3 features, in pages of
+// 2 linked by relative URLs, and the same as a WFS layer
+fn mock_feature_server() -> String {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let feature = |i: usize| {
+            format!(
+                r#"{{"type":"Feature","id":{},"properties":{{}},"geometry":{{"type":"Point","coordinates":[{},0]}}}}"#,
+                i, i
+            )
+        };
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let body = if request_line.contains("/collections/parcels/items?limit=2&offset=2") {
+                format!(
+                    r#"{{"type":"FeatureCollection","features":[{}],"links":[]}}"#,
+                    feature(2)
+                )
+            } else if request_line.contains("/collections/parcels/items?limit=2") {
+                format!(
+                    r#"{{"type":"FeatureCollection","features":[{},{}],"links":[{{"rel":"self","href":"items?limit=2"}},{{"rel":"next","href":"items?limit=2&offset=2"}}]}}"#,
+                    feature(0),
+                    feature(1)
+                )
+            } else if request_line.contains("startIndex=0") {
+                format!(
+                    r#"{{"type":"FeatureCollection","numberMatched":3,"features":[{},{}]}}"#,
+                    feature(0),
+                    feature(1)
+                )
+            } else if request_line.contains("startIndex=2") {
+                format!(
+                    r#"{{"type":"FeatureCollection","numberMatched":3,"features":[{}]}}"#,
+                    feature(2)
+                )
+            } else {
+                "{}".to_string()
+            };
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/geo+json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[test]
+fn ogcapi_read() {
+    let server = mock_feature_server();
+    let all = r#"{"geometry":{"coordinates":[0,0],"type":"Point"},"id":0,"properties":{},"type":"Feature"}
+{"geometry":{"coordinates":[1,0],"type":"Point"},"id":1,"properties":{},"type":"Feature"}
+{"geometry":{"coordinates":[2,0],"type":"Point"},"id":2,"properties":{},"type":"Feature"}"#;
+    Assert::main_binary()
+        .with_args(&[
+            "ogcapi",
+            "read",
+            &format!("{}/collections/parcels", server),
+            "--page-size",
+            "2",
+        ])
+        .stdout()
+        .is(all)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&[
+            "ogcapi",
+            "read",
+            &format!("{}/wfs?service=WFS&typeNames=parcels", server),
+            "--page-size",
+            "2",
+        ])
+        .stdout()
+        .is(all)
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&[
+            "ogcapi",
+            "read",
+            &format!("{}/collections/parcels/items", server),
+            "--page-size",
+            "2",
+            "--max-features",
+            "1",
+        ])
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[0,0],"type":"Point"},"id":0,"properties":{},"type":"Feature"}"#)
+        .unwrap();
+}
+
+// An object store serving HEAD, ranged GET and PUT requests for any
+// bucket, from the given initial objects. Requests must be signed when
+// `require_auth` is set.
+fn mock_object_store(objects: Vec<(&str, Vec<u8>)>, require_auth: bool) -> String {
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    let mut objects: HashMap<String, Vec<u8>> = objects
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let parts: Vec<&str> = request_line.split_whitespace().collect();
+            let (method, path) = (parts[0].to_string(), parts[1].to_string());
+            let mut headers: HashMap<String, String> = HashMap::new();
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                let (k, v) = header.split_at(header.find(':').unwrap());
+                headers.insert(k.to_lowercase(), v[1..].trim().to_string());
+            }
+            let content_length: usize = headers
+                .get("content-length")
+                .map_or(0, |l| l.parse().unwrap());
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let authorized = !require_auth
+                || headers
+                    .get("authorization")
+                    .is_some_and(|a| a.starts_with("AWS4-HMAC-SHA256 Credential=test/"));
+            let (status, response, head_len): (&str, Vec<u8>, Option<usize>) = if !authorized {
+                ("403 Forbidden", b"AccessDenied".to_vec(), None)
+            } else if method == "PUT" {
+                objects.insert(path, body);
+                ("200 OK", vec![], None)
+            } else if let Some(data) = objects.get(&path) {
+                if method == "HEAD" {
+                    ("200 OK", vec![], Some(data.len()))
+                } else if let Some(range) = headers.get("range") {
+                    let range: Vec<usize> = range["bytes=".len()..]
+                        .split('-')
+                        .map(|n| n.parse().unwrap())
+                        .collect();
+                    let end = (range[1] + 1).min(data.len());
+                    ("206 Partial Content", data[range[0]..end].to_vec(), None)
+                } else {
+                    ("200 OK", data.clone(), None)
+                }
+            } else {
+                ("404 Not Found", b"NoSuchKey".to_vec(), None)
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                head_len.unwrap_or(response.len())
+            )
+            .unwrap();
+            if method != "HEAD" {
+                stream.write_all(&response).unwrap();
+            }
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[test]
+fn object_store() {
+    use assert_cli::Environment;
+
+    let countries = std::fs::read_to_string("tests/resources/countries.geojson").unwrap();
+    let query =
+        r#"{"type":"Polygon","coordinates":[[[-10,-10],[10,-10],[10,10],[-10,10],[-10,-10]]]}"#;
+    let endpoint = mock_object_store(
+        vec![("/data/query.geojson", query.as_bytes().to_vec())],
+        true,
+    );
+    let s3 = Environment::inherit()
+        .insert("AWS_ENDPOINT_URL", &endpoint)
+        .insert("AWS_ACCESS_KEY_ID", "test")
+        .insert("AWS_SECRET_ACCESS_KEY", "secret");
+
+    Assert::main_binary()
+        .with_env(&s3)
+        .with_args(&["fgb", "write", "s3://data/countries.fgb"])
+        .stdin(countries.clone())
+        .unwrap();
+
+    // Ranged reads of the uploaded file match reading it locally
+    let dir = tempfile::tempdir().unwrap();
+    let local = dir.path().join("countries.fgb");
+    Assert::main_binary()
+        .with_args(&["fgb", "write", local.to_str().unwrap()])
+        .stdin(countries)
+        .unwrap();
+    let bbox = ["--bbox", "-10,-10,10,10"];
+    let expected = std::process::Command::new(env!("CARGO_BIN_EXE_geoq"))
+        .args(["fgb", "read", local.to_str().unwrap()])
+        .args(bbox)
+        .output()
+        .unwrap()
+        .stdout;
+    Assert::main_binary()
+        .with_env(&s3)
+        .with_args(&["fgb", "read", "s3://data/countries.fgb"])
+        .with_args(&bbox)
+        .stdout()
+        .is(String::from_utf8(expected).unwrap().trim())
+        .unwrap();
+
+    Assert::main_binary()
+        .with_env(&s3)
+        .with_args(&[
+            "filter",
+            "intersects",
+            "--query-file",
+            "s3://data/query.geojson",
+        ])
+        .stdin("POINT (1 1)\nPOINT (20 20)\n")
+        .stdout()
+        .is("POINT(1 1)")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_env(Environment::inherit().insert("AWS_ENDPOINT_URL", &endpoint))
+        .with_args(&[
+            "filter",
+            "intersects",
+            "--query-file",
+            "s3://data/query.geojson",
+        ])
+        .stdin("POINT (1 1)\n")
+        .fails()
+        .and()
+        .stderr()
+        .contains("403 Forbidden")
+        .unwrap();
+
+    let gcs = mock_object_store(
+        vec![("/bucket/query.geojson", query.as_bytes().to_vec())],
+        false,
+    );
+    Assert::main_binary()
+        .with_env(Environment::inherit().insert("STORAGE_EMULATOR_HOST", &gcs))
+        .with_args(&[
+            "filter",
+            "intersects",
+            "--query-file",
+            "gs://bucket/query.geojson",
+        ])
+        .stdin("POINT (1 1)\n")
+        .stdout()
+        .is("POINT(1 1)")
+        .unwrap();
+}
+
+#[test]
+fn binary_stdin() {
+    // WKB: POINT (1 2), then big endian POINT (3 4)
+    let mut wkb = vec![1, 1, 0, 0, 0];
+    wkb.extend(&1.0f64.to_le_bytes());
+    wkb.extend(&2.0f64.to_le_bytes());
+    wkb.extend(&[0, 0, 0, 0, 1]);
+    wkb.extend(&3.0f64.to_be_bytes());
+    wkb.extend(&4.0f64.to_be_bytes());
+    Assert::main_binary()
+        .with_args(&["wkt"])
+        .stdin(wkb)
+        .stdout()
+        .is("POINT(1 2)\nPOINT(3 4)")
+        .unwrap();
+
+    let zipped = std::fs::read("tests/resources/poly.zip").unwrap();
+    Assert::main_binary()
+        .with_args(&["gj", "f"])
+        .stdin(zipped)
+        .stdout()
+        .contains("\"PRFEDEA\":\"35043411\"")
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let fgb = dir.path().join("data.fgb");
+    Assert::main_binary()
+        .with_args(&["fgb", "write", fgb.to_str().unwrap()])
+        .stdin("{\"type\":\"Feature\",\"properties\":{\"name\":\"b\"},\"geometry\":{\"type\":\"Point\",\"coordinates\":[-118,34]}}\n")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["centroid"])
+        .stdin(std::fs::read(&fgb).unwrap())
+        .stdout()
+        .is("{\"coordinates\":[-118.0,34.0],\"type\":\"Point\"}")
+        .unwrap();
+
+    // A minimal GeoPackage feature table, with a GP header and no envelope
+    let gpkg = dir.path().join("data.gpkg");
+    let conn = rusqlite::Connection::open(&gpkg).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE gpkg_geometry_columns (table_name TEXT, column_name TEXT);
+         INSERT INTO gpkg_geometry_columns VALUES ('places', 'geom');
+         CREATE TABLE places (fid INTEGER PRIMARY KEY, geom BLOB, name TEXT);",
+    )
+    .unwrap();
+    let mut blob = vec![b'G', b'P', 0, 1, 0, 0, 0, 0, 1, 1, 0, 0, 0];
+    blob.extend(&(-118.0f64).to_le_bytes());
+    blob.extend(&34.0f64.to_le_bytes());
+    conn.execute(
+        "INSERT INTO places VALUES (7, ?1, 'la')",
+        rusqlite::params![blob],
+    )
+    .unwrap();
+    drop(conn);
+    Assert::main_binary()
+        .with_args(&["gj", "f"])
+        .stdin(std::fs::read(&gpkg).unwrap())
+        .stdout()
+        .is("{\"geometry\":{\"coordinates\":[-118.0,34.0],\"type\":\"Point\"},\"id\":7,\"properties\":{\"name\":\"la\"},\"type\":\"Feature\"}")
+        .unwrap();
+}
+
+#[test]
+fn strict_and_lenient_parsing() {
+    Assert::main_binary()
+        .with_args(&["wkt", "--strict"])
+        .stdin("34,-118\n")
+        .stdout()
+        .is("POINT(-118 34)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["wkt", "--strict"])
+        .stdin("{\"type\":\"Point\",\"coordinates\":[-218,34]}\n")
+        .fails()
+        .and()
+        .stderr()
+        .contains("Lon/Lat out of range")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["wkt"])
+        .stdin("{\"type\":\"Point\",\"coordinates\":[\"-118\",\"34\"],}\n")
+        .fails()
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["wkt", "--lenient"])
+        .stdin("[{\"type\":\"Point\",\"coordinates\":[\"-118\",\"34\"],}]\n34, -118\n")
+        .stdout()
+        .is("POINT(-118 34)\nPOINT(-118 34)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["wkt", "--strict", "--lenient"])
+        .stdin("34,-118\n")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn on_error() {
+    let input = "34,-118\nbogus line\n1,2\n";
+    Assert::main_binary()
+        .with_args(&["wkt"])
+        .stdin(input)
+        .fails()
+        .and()
+        .stderr()
+        .contains("bogus line")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["wkt", "--on-error", "skip"])
+        .stdin(input)
+        .stdout()
+        .is("POINT(-118 34)\nPOINT(2 1)")
+        .stderr()
+        .contains("Skipped 1 records with errors")
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let errors = dir.path().join("errors.jsonl");
+    Assert::main_binary()
+        .with_args(&["centroid", "--on-error", "report"])
+        .with_args(&["--error-file", errors.to_str().unwrap()])
+        .stdin(input)
+        .stdout()
+        .contains("{\"coordinates\":[2.0,1.0],\"type\":\"Point\"}")
+        .unwrap();
+    let reported = std::fs::read_to_string(&errors).unwrap();
+    assert!(reported.contains("\"input\":\"bogus line\""));
+
+    Assert::main_binary()
+        .with_args(&["wkt", "--on-error", "report"])
+        .stdin(input)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn parallel_output_order() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    // Long lines among points, so workers finish lines out of order
+    let coords: Vec<String> = (0..2000)
+        .map(|i| format!("[{},{}]", i as f64 / 2000.0, i as f64 / 2000.0))
+        .collect();
+    let line = format!(
+        "{{\"type\":\"LineString\",\"coordinates\":[{}]}}",
+        coords.join(",")
+    );
+    let mut input = vec![];
+    let mut expected = vec![];
+    for i in 0..500 {
+        if i % 7 == 0 {
+            input.push(line.clone());
+            expected.push("2000".to_string());
+        } else {
+            input.push(format!("{},{}", i % 80, i % 170));
+            expected.push("1".to_string());
+        }
+    }
+    Assert::main_binary()
+        .with_args(&["measure", "coord-count"])
+        .stdin(input.join("\n"))
+        .stdout()
+        .is(expected.join("\n").as_str())
+        .unwrap();
+
+    // Unordered output has the same lines, in whatever order
+    let output = Command::new(env!("CARGO_BIN_EXE_geoq"))
+        .args(["measure", "coord-count", "--unordered"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(input.join("\n").as_bytes())?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    let mut lines: Vec<String> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+    lines.sort();
+    expected.sort();
+    assert_eq!(expected, lines);
+}
+
+#[test]
+fn jobs() {
+    use assert_cli::Environment;
+
+    Assert::main_binary()
+        .with_args(&["wkt", "-j", "1", "--batch-size", "1"])
+        .stdin("34,-118\n9q5\n")
+        .stdout()
+        .is("POINT(-118 34)\nPOLYGON((-119.53125 33.75,-118.125 33.75,-118.125 35.15625,-119.53125 35.15625,-119.53125 33.75))")
+        .unwrap();
+    Assert::main_binary()
+        .with_env(Environment::inherit().insert("GEOQ_JOBS", "3"))
+        .with_args(&["wkt"])
+        .stdin("34,-118\n")
+        .stdout()
+        .is("POINT(-118 34)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["wkt", "--jobs", "0"])
+        .stdin("34,-118\n")
+        .fails()
+        .and()
+        .stderr()
+        .contains("Expected a positive number of jobs: 0")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["wkt", "--buffer-lines", "1", "--max-buffered-bytes", "1K"])
+        .stdin("34,-118\n1,2\n")
+        .stdout()
+        .is("POINT(-118 34)\nPOINT(2 1)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["wkt", "--timeout-per-entity", "30s"])
+        .stdin("34,-118\n1,2\n")
+        .stdout()
+        .is("POINT(-118 34)\nPOINT(2 1)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["wkt", "--timeout-per-entity", "soon"])
+        .stdin("34,-118\n")
+        .fails()
+        .and()
+        .stderr()
+        .contains("Expected a positive duration, like 30s or 500ms: soon")
+        .unwrap();
+}
+
+#[test]
+fn progress() {
+    // The bar is only drawn to a terminal, so piped output is unchanged
+    Assert::main_binary()
+        .with_args(&["wkt", "--progress"])
+        .stdin("34,-118\n1,2\n")
+        .stdout()
+        .is("POINT(-118 34)\nPOINT(2 1)")
+        .stderr()
+        .is("")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["centroid", "--progress"])
+        .stdin("34,-118\n")
+        .stdout()
+        .is("{\"coordinates\":[-118.0,34.0],\"type\":\"Point\"}")
+        .unwrap();
+}
+
+#[test]
+fn stdin_from_file() {
+    use std::fs::File;
+    use std::io::{Seek, SeekFrom, Write};
+    use std::process::Command;
+
+    // Files on stdin are mapped into memory rather than read line by line
+    let mut file = tempfile::tempfile().unwrap();
+    file.write_all(b"34,-118\nPOINT (1 2)\n{\"type\":\"Point\",\"coordinates\":[3,4]}")
+        .unwrap();
+    let run = |file: &File, args: &[&str]| {
+        let mut file = file.try_clone().unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_geoq"))
+            .args(args)
+            .stdin(file)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(
+        "POINT(-118 34)\nPOINT(1 2)\nPOINT(3 4)\n",
+        run(&file, &["wkt"])
+    );
+    assert_eq!(
+        "{\"coordinates\":[-118.0,34.0],\"type\":\"Point\"}\n{\"coordinates\":[1.0,2.0],\"type\":\"Point\"}\n{\"coordinates\":[3.0,4.0],\"type\":\"Point\"}\n",
+        run(&file, &["centroid"])
+    );
+}
+
+#[test]
+fn output_flushed_while_waiting_for_input() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::Duration;
+
+    // Parallel and serial commands
+    for (args, expected) in &[
+        (vec!["wkt"], "POINT(-118 34)"),
+        (
+            vec!["centroid"],
+            "{\"coordinates\":[-118.0,34.0],\"type\":\"Point\"}",
+        ),
+    ] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_geoq"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        stdin.write_all(b"34,-118\n").unwrap();
+        stdin.flush().unwrap();
+
+        // The first line comes out with stdin still open
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let mut line = String::new();
+            BufReader::new(stdout).read_line(&mut line).unwrap();
+            sender.send(line).unwrap();
+        });
+        let line = receiver.recv_timeout(Duration::from_secs(10));
+        drop(stdin);
+        child.wait().unwrap();
+        assert_eq!(format!("{}\n", expected), line.unwrap());
+    }
+}
+
+#[test]
+fn error_location() {
+    let input = "34,-118\n9q5\nPOINT (1\n";
+    Assert::main_binary()
+        .with_args(&["wkt"])
+        .stdin(input)
+        .fails()
+        .and()
+        .stderr()
+        .is("Application error: InvalidWkt\n  at line 3 (byte 12): POINT (1")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["tile", "parent"])
+        .stdin("12/655/1582\nnot a tile\n")
+        .fails()
+        .and()
+        .stderr()
+        .contains("at line 2 (byte 12): not a tile")
+        .unwrap();
+}
+
+#[test]
+fn json_errors() {
+    let input = "34,-118\nbogus line\n1,2\n";
+    Assert::main_binary()
+        .with_args(&["wkt", "--errors", "json", "--on-error", "skip"])
+        .stdin(input)
+        .stdout()
+        .is("POINT(-118 34)\nPOINT(2 1)")
+        .stderr()
+        .contains("{\"input\":\"bogus line\",\"kind\":\"InvalidInput\",\"level\":\"warning\",\"line\":2,\"message\":\"Unable to parse single-line input: bogus line\",\"offset\":8}")
+        .stderr()
+        .contains("{\"kind\":\"Info\",\"level\":\"info\",\"message\":\"Skipped 1 records with errors\"}")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["centroid", "--errors", "json"])
+        .stdin(input)
+        .fails()
+        .and()
+        .stderr()
+        .is("{\"input\":\"bogus line\",\"kind\":\"InvalidInput\",\"level\":\"error\",\"line\":2,\"message\":\"Unable to parse single-line input: bogus line\",\"offset\":8}")
+        .unwrap();
+}
+
+#[test]
+fn bench() {
+    use std::io::Write;
+
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(b"34,-118\nPOINT (1 2)\n{\"type\":\"Point\",\"coordinates\":[3,4]}\n")
+        .unwrap();
+    let path = file.path().to_str().unwrap();
+    Assert::main_binary()
+        .with_args(&["bench", "--input", path, "--iterations", "1"])
+        .stdout()
+        .contains("3 lines, 57 bytes, best of 1")
+        .stdout()
+        .contains("parse+handle")
+        .stdout()
+        .contains("end-to-end")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&[
+            "bench",
+            "--input",
+            path,
+            "--command",
+            "gh covering 3",
+            "-n",
+            "1",
+        ])
+        .stdout()
+        .contains("end-to-end")
+        .stderr()
+        .contains("Skipping parse+handle")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["bench", "--input", path, "--command", "nope"])
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn fgb_write_checkpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("points.wkt");
+    let fgb = dir.path().join("points.fgb");
+    let state = dir.path().join("state.json");
+    let write = || {
+        std::process::Command::new(env!("CARGO_BIN_EXE_geoq"))
+            .args(["fgb", "write", fgb.to_str().unwrap()])
+            .args(["--checkpoint", state.to_str().unwrap()])
+            .stdin(std::fs::File::open(&input).unwrap())
+            .output()
+            .unwrap()
+    };
+
+    std::fs::write(&input, "POINT (1 1)\nPOINT (1 X)\nPOINT (3 3)\n").unwrap();
+    assert!(!write().status.success());
+    assert!(state.exists());
+
+    // The first line was read before the failure, so isn't read again
+    std::fs::write(&input, "POINT (9 9)\nPOINT (2 2)\nPOINT (3 3)\n").unwrap();
+    assert!(write().status.success());
+    Assert::main_binary()
+        .with_args(&["fgb", "read", fgb.to_str().unwrap()])
+        .stdout()
+        .contains("[1,1]")
+        .stdout()
+        .contains("[2,2]")
+        .stdout()
+        .contains("[3,3]")
+        .stdout()
+        .doesnt_contain("[9,9]")
+        .unwrap();
+    assert_eq!(2, dir.path().read_dir().unwrap().count());
+}
+
+#[test]
+fn output_format() {
+    let input = r#"{"type":"Feature","properties":{"name":"a, b","pop":3},"geometry":{"type":"Polygon","coordinates":[[[0,0],[2,0],[2,2],[0,2],[0,0]]]}}
+{"type":"Feature","properties":{"name":"c"},"geometry":{"type":"Point","coordinates":[1,1]}}
+"#;
+    Assert::main_binary()
+        .with_args(&["centroid", "--format", "wkt"])
+        .stdin(input)
+        .stdout()
+        .is("POINT(1 1)\nPOINT(1 1)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["--format", "csv", "simplify", "0.1"])
+        .stdin(input)
+        .stdout()
+        .is("wkt,name,pop\n\"POLYGON((0 0,2 0,2 2,0 2,0 0))\",\"a, b\",3\nPOINT(1 1),c,")
+        .unwrap();
+    // Other output is unchanged
+    Assert::main_binary()
+        .with_args(&["gh", "point", "2", "--format", "wkt"])
+        .stdin("34,-118")
+        .stdout()
+        .is("9q")
+        .unwrap();
+
+    let wkb = std::process::Command::new(env!("CARGO_BIN_EXE_geoq"))
+        .args(["centroid", "--format", "wkb"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child.stdin.take().unwrap().write_all(input.as_bytes())?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    assert!(wkb.status.success());
+    Assert::main_binary()
+        .with_args(&["wkt"])
+        .stdin(wkb.stdout)
+        .stdout()
+        .is("POINT(1 1)\nPOINT(1 1)")
+        .unwrap();
+}
+
+#[test]
+fn output_style() {
+    let input = "{\"type\": \"Feature\", \"properties\": {\"a\": [1, 2]}, \"geometry\": null}\n";
+    Assert::main_binary()
+        .with_args(&["head", "--compact"])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"type":"Feature","properties":{"a":[1,2]},"geometry":null}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["--pretty", "head"])
+        .stdin(input)
+        .stdout()
+        .is("\u{1e}{\n  \"type\": \"Feature\",\n  \"properties\": {\n    \"a\": [\n      1,\n      2\n    ]\n  },\n  \"geometry\": null\n}")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["gh", "point", "3", "--pretty"])
+        .stdin("34,-118")
+        .stdout()
+        .is("9qh")
+        .unwrap();
+}
+
+#[test]
+fn config_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    std::fs::write(
+        &config,
+        "# Team defaults\nformat = \"wkt\"\ncompact = true\n",
+    )
+    .unwrap();
+    let centroid = |args: &[&str], env: &[(&str, &str)]| {
+        let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_geoq"))
+            .arg("centroid")
+            .args(args)
+            .env("GEOQ_CONFIG", &config)
+            .envs(env.iter().copied())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        {
+            use std::io::Write;
+            child.stdin.take().unwrap().write_all(b"34,-118\n").unwrap();
+        }
+        let out = child.wait_with_output().unwrap();
+        assert!(out.status.success());
+        String::from_utf8(out.stdout).unwrap()
+    };
+    assert_eq!("POINT(-118 34)\n", centroid(&[], &[]));
+    assert_eq!(
+        "wkt\nPOINT(-118 34)\n",
+        centroid(&[], &[("GEOQ_FORMAT", "csv")])
+    );
+    assert_eq!(
+        "{\"coordinates\":[-118.0,34.0],\"type\":\"Point\"}\n",
+        centroid(&["--format", "geojson"], &[("GEOQ_FORMAT", "csv")])
+    );
+
+    std::fs::write(&config, "format = \"wkt\n").unwrap();
+    Assert::main_binary()
+        .with_env(assert_cli::Environment::inherit().insert("GEOQ_CONFIG", &config))
+        .with_args(&["centroid"])
+        .stdin("34,-118")
+        .fails()
+        .stderr()
+        .contains("Invalid config file")
+        .unwrap();
+
+    // Settings from global options aren't warned of as unknown
+    std::fs::write(&config, "units = \"km\"\nother_max_memory = 1000000\n").unwrap();
+    Assert::main_binary()
+        .with_env(assert_cli::Environment::inherit().insert("GEOQ_CONFIG", &config))
+        .with_args(&["centroid"])
+        .stdin("34,-118")
+        .stderr()
+        .doesnt_contain("Unknown setting")
+        .unwrap();
+
+    std::fs::write(&config, "precision = 2\n").unwrap();
+    Assert::main_binary()
+        .with_env(assert_cli::Environment::inherit().insert("GEOQ_CONFIG", &config))
+        .with_args(&["gj", "geom"])
+        .stdin("34.123456,-118.987654")
+        .stdout()
+        .is(r#"{"coordinates":[-118.99,34.12],"type":"Point"}"#)
+        .stderr()
+        .doesnt_contain("Unknown setting")
+        .unwrap();
+    Assert::main_binary()
+        .with_env(assert_cli::Environment::inherit().insert("GEOQ_CONFIG", &config))
+        .with_args(&["gj", "geom", "--precision", "4"])
+        .stdin("34.123456,-118.987654")
+        .stdout()
+        .is(r#"{"coordinates":[-118.9877,34.1235],"type":"Point"}"#)
+        .unwrap();
+}
+
+#[test]
+fn precision() {
+    Assert::main_binary()
+        .with_args(&["--precision", "1", "--format", "wkt", "gj", "f"])
+        .stdin("{\"type\":\"Feature\",\"properties\":{\"a\":1.25},\"geometry\":{\"type\":\"LineString\",\"coordinates\":[[0.04,1.26],[2,3.33333]]}}")
+        .stdout()
+        .is("LINESTRING(0 1.3,2 3.3)")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["--precision", "0", "filter", "type", "Point"])
+        .stdin("{\"type\":\"Feature\",\"properties\":{\"a\":1.25},\"geometry\":{\"type\":\"Point\",\"coordinates\":[0.6,-1.2]}}")
+        .stdout()
+        .is("{\"type\":\"Feature\",\"properties\":{\"a\":1.25},\"geometry\":{\"type\":\"Point\",\"coordinates\":[1,-1]}}")
+        .unwrap();
+
+    Assert::main_binary()
+        .with_args(&["--precision", "16", "wkt"])
+        .stdin("9q5")
+        .fails()
+        .stderr()
+        .contains("Expected a number of decimal places from 0 to 15: 16")
+        .unwrap();
+}
+
+#[test]
+fn pipe() {
+    let input = "POINT (1 1)\nLINESTRING (0 0, 2 0)\nPOLYGON ((0 0, 4 0, 4 4, 0 4, 0 0))\n";
+    Assert::main_binary()
+        .with_args(&["pipe", "filter intersects 'POLYGON ((-1 -1, 3 -1, 3 3, -1 3, -1 -1))' | centroid"])
+        .stdin(input)
+        .stdout()
+        .is("{\"coordinates\":[1.0,1.0],\"type\":\"Point\"}\n{\"coordinates\":[1.0,0.0],\"type\":\"Point\"}\n{\"coordinates\":[2.0,2.0],\"type\":\"Point\"}")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["pipe", "filter intersects 'POINT (3 3)' --negate"])
+        .stdin(input)
+        .stdout()
+        .is("POINT(1 1)\nLINESTRING(0 0,2 0)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["pipe", "centroid | wkt"])
+        .stdin(input)
+        .fails()
+        .stderr()
+        .contains("can't be a pipe stage")
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let fgb = dir.path().join("centroids.fgb");
+    let file = dir.path().join("pipeline.yaml");
+    std::fs::write(
+        &file,
+        format!("# Centroids\n- centroid\n- fgb write {}\n", fgb.display()),
+    )
+    .unwrap();
+    Assert::main_binary()
+        .with_args(&["pipe", "--file", file.to_str().unwrap()])
+        .stdin(input)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["fgb", "read", fgb.to_str().unwrap()])
+        .stdout()
+        .contains("[2,2]")
+        .unwrap();
+
+    // As in the help
+    let out = dir.path().join("out.fgb");
+    Assert::main_binary()
+        .with_args(&[
+            "pipe",
+            &format!(
+                "simplify 0.001 | filter type Polygon | fgb write {}",
+                out.display()
+            ),
+        ])
+        .stdin(input)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["fgb", "read", out.to_str().unwrap()])
+        .stdout()
+        .contains(r#""features": [{"type": "Feature", "properties": {}, "geometry": {"type": "Polygon", "coordinates": [[[0,0],[4,0],[4,4],[0,4],[0,0]]]}}]}"#)
+        .unwrap();
+}
+
+#[test]
+fn transform() {
+    Assert::main_binary()
+        .with_args(&[
+            "transform",
+            "props.half = props.pop / 2; geom = centroid(geom); props.name = props.name + '!'",
+        ])
+        .stdin(r#"{"type":"Feature","properties":{"name":"a","pop":5},"geometry":{"type":"Polygon","coordinates":[[[0,0],[2,0],[2,2],[0,2],[0,0]]]}}"#)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[1.0,1.0],"type":"Point"},"properties":{"half":2.5,"name":"a!","pop":5},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "transform",
+            "geom = buffer(geom, 100); props.area = area(geom) > 31000",
+        ])
+        .stdin("POINT (-74 40.7)")
+        .fails()
+        .stderr()
+        .contains("Unexpected")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["transform", "geom = area(geom)"])
+        .stdin("POINT (-74 40.7)")
+        .fails()
+        .stderr()
+        .contains("expected a geometry")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "pipe",
+            "transform 'geom = buffer(geom, 100); props.area = area(geom)' | filter geometry --non-empty",
+            "--format",
+            "csv",
+        ])
+        .stdin("POINT (-74 40.7)")
+        .stdout()
+        .contains("wkt,area\n\"POLYGON((")
+        .unwrap();
+}
+
+#[test]
+fn completions() {
+    Assert::main_binary()
+        .with_args(&["completions", "zsh"])
+        .stdout()
+        .contains("#compdef geoq")
+        .stdout()
+        .contains("transform")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["completions", "bash"])
+        .stdout()
+        .contains("complete -F _geoq")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["completions", "tcsh"])
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn measure_json() {
+    let input = r#"{"type":"Feature","id":"a","properties":{},"geometry":{"type":"Point","coordinates":[0,0]}}
+LINESTRING (0 0, 1 1, 2 0)"#;
+    Assert::main_binary()
+        .with_args(&["measure", "distance", "0,0", "--json"])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"id":"a","units":"meters","value":0.0}
+{"id":null,"units":"meters","value":0.0}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "coord-count", "--json"])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"id":"a","units":"coordinates","value":1}
+{"id":null,"units":"coordinates","value":3}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "coord-count", "--json", "--geojson"])
+        .stdin(input)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn measure_as_property() {
+    let input = r#"{"type":"Feature","id":"a","properties":{"name":"square"},"geometry":{"type":"Polygon","coordinates":[[[0,0],[1,0],[1,1],[0,1],[0,0]]]}}"#;
+    Assert::main_binary()
+        .with_args(&["measure", "coord-count", "--as-property", "vertices"])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[[0.0,0.0],[1.0,0.0],[1.0,1.0],[0.0,1.0],[0.0,0.0]]],"type":"Polygon"},"id":"a","properties":{"name":"square","vertices":5},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "area", "--as-property", "area"])
+        .stdin(input)
+        .stdout()
+        .contains(r#""properties":{"area":12391399902.071104,"name":"square"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "length"])
+        .stdin("LINESTRING (0 0, 1 0)")
+        .stdout()
+        .is("111319.49079327357")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "distance", "0,0", "--as-property", "d"])
+        .stdin("POINT (0 0)")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[0.0,0.0],"type":"Point"},"properties":{"d":0.0},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "area", "--as-property", "area", "--json"])
+        .stdin(input)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn units() {
+    Assert::main_binary()
+        .with_args(&["measure", "length", "--units", "km", "--json"])
+        .stdin("LINESTRING (0 0, 1 0)")
+        .stdout()
+        .is(r#"{"id":null,"units":"kilometers","value":111.31949079327356}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "area", "--units", "ha"])
+        .stdin("POLYGON ((0 0, 1 0, 1 1, 0 1, 0 0))")
+        .stdout()
+        .is("1239139.9902071103")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["filter", "dwithin", "--radius", "1.2km", "0,0"])
+        .stdin("POINT (0 0.01)\nPOINT (0 0.02)")
+        .stdout()
+        .is("POINT(0 0.01)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["filter", "dwithin", "--radius", "2", "--units", "mi", "0,0"])
+        .stdin("POINT (0 0.01)\nPOINT (0 0.02)")
+        .stdout()
+        .is("POINT(0 0.01)\nPOINT(0 0.02)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "length", "--units", "parsecs"])
+        .stdin("LINESTRING (0 0, 1 0)")
+        .fails()
+        .stderr()
+        .contains("Unknown units: parsecs")
+        .unwrap();
+}
+
+#[test]
+fn other_dataset_formats() {
+    // Second datasets are read in any input format, like this zipped shapefile
+    Assert::main_binary()
+        .with_args(&[
+            "filter",
+            "intersects",
+            "--query-file",
+            "./tests/resources/poly.zip",
+        ])
+        .stdin("POINT (480000 4765300)\nPOINT (0 0)")
+        .stdout()
+        .is("POINT(480000 4765300)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["diff", "--other", "-"])
+        .stdin("1,1")
+        .fails()
+        .stderr()
+        .contains("can't be read from stdin")
+        .unwrap();
+}
+
+#[test]
+fn hash() {
+    Assert::main_binary()
+        .with_args(&["hash"])
+        .stdin("POINT (1 2)\n{\"type\":\"Point\",\"coordinates\":[1,2]}")
+        .stdout()
+        .is("4ddc678d472071b63dd260ae7d7cd0eb\n4ddc678d472071b63dd260ae7d7cd0eb")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "hash",
+            "--algorithm",
+            "geohash-centroid",
+            "--level",
+            "6",
+            "--as-property",
+            "gh",
+        ])
+        .stdin("POINT (-122.4 37.8)")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[-122.4,37.8],"type":"Point"},"properties":{"gh":"9q8zn9"},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["hash", "--algorithm", "simhash"])
+        .stdin("LINESTRING (0 0, 1 1)")
+        .stdout()
+        .satisfies(
+            |out| out.trim().len() == 16,
+            "simhash should be 16 hex digits",
+        )
+        .unwrap();
+}
+
+#[test]
+fn props_join() {
+    let input = r#"{"type":"Feature","properties":{"GEOID":"06037"},"geometry":null}
+{"type":"Feature","id":"06059","properties":{},"geometry":null}
+{"type":"Feature","properties":{"GEOID":"06073"},"geometry":null}"#;
+    Assert::main_binary()
+        .with_args(&[
+            "props",
+            "join",
+            "--lookup",
+            "./tests/resources/lookup.csv",
+            "--on",
+            "GEOID",
+        ])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"geometry":null,"properties":{"GEOID":"06037","name":"Los Angeles","pop":10014009},"type":"Feature"}
+{"geometry":null,"id":"06059","properties":{"name":"Orange, CA","pop":3186989},"type":"Feature"}
+{"type":"Feature","properties":{"GEOID":"06073"},"geometry":null}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "props",
+            "join",
+            "--lookup",
+            "./tests/resources/lookup.csv",
+            "--on",
+            "GEOID",
+            "--drop-unmatched",
+        ])
+        .stdin(input)
+        .stdout()
+        .satisfies(
+            |out| out.lines().count() == 2,
+            "unmatched feature should be dropped",
+        )
+        .unwrap();
+}
+
+#[test]
+fn props_normalize() {
+    let input = r#"{"type":"Feature","properties":{"Parcel ID":" 12-A ","ownerName":"","ZONE_CODE":"R1"},"geometry":null}"#;
+    Assert::main_binary()
+        .with_args(&[
+            "props",
+            "normalize",
+            "--keys",
+            "snake_case",
+            "--trim",
+            "--empty-as-null",
+        ])
+        .stdin(input)
+        .stdout()
+        .is(r#"{"geometry":null,"properties":{"owner_name":null,"parcel_id":"12-A","zone_code":"R1"},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["props", "normalize", "--keys", "Title"])
+        .stdin(input)
+        .fails()
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["props", "normalize", "--keys", "snake_case"])
+        .stdin(r#"{"type":"Feature","properties":{"b_x":1,"B X":2},"geometry":null}"#)
+        .fails()
+        .stderr()
+        .contains("Keys 'B X' and 'b_x' both normalize to 'b_x'")
+        .unwrap();
+}
+
+#[test]
+fn style() {
+    let input = r#"{"type":"Feature","properties":{"d":1},"geometry":{"type":"Point","coordinates":[0,0]}}
+{"type":"Feature","properties":{"d":3},"geometry":{"type":"LineString","coordinates":[[0,0],[1,1]]}}
+{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[0,0]}}"#;
+    Assert::main_binary()
+        .with_args(&["style", "--color-by", "d"])
+        .stdin(input)
+        .stdout()
+        .is(r##"{"geometry":{"coordinates":[0.0,0.0],"type":"Point"},"properties":{"d":1,"marker-color":"#440154"},"type":"Feature"}
+{"geometry":{"coordinates":[[0.0,0.0],[1.0,1.0]],"type":"LineString"},"properties":{"d":3,"stroke":"#fde725"},"type":"Feature"}
+{"geometry":{"coordinates":[0.0,0.0],"type":"Point"},"properties":{},"type":"Feature"}"##)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "style",
+            "--color-by",
+            "d",
+            "--categorical",
+            "--palette",
+            "#000,#fff",
+        ])
+        .stdin(input)
+        .stdout()
+        .contains(r##""stroke":"#ffffff""##)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["style", "--color-by", "d", "--palette", "rainbow"])
+        .stdin(input)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn graticule() {
+    Assert::main_binary()
+        .with_args(&["graticule", "--interval", "1", "--bbox=-125,32,-124,32.5", "--frame"])
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[-125.0,32.0],[-124.0,32.0]],"type":"LineString"},"properties":{"kind":"parallel","label":"32°N","value":32.0},"type":"Feature"}
+{"geometry":{"coordinates":[[-125.0,32.0],[-125.0,32.5]],"type":"LineString"},"properties":{"kind":"meridian","label":"125°W","value":-125.0},"type":"Feature"}
+{"geometry":{"coordinates":[[-124.0,32.0],[-124.0,32.5]],"type":"LineString"},"properties":{"kind":"meridian","label":"124°W","value":-124.0},"type":"Feature"}
+{"geometry":{"coordinates":[[-125.0,32.0],[-124.0,32.0],[-124.0,32.5],[-125.0,32.5],[-125.0,32.0]],"type":"LineString"},"properties":{"kind":"frame"},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["graticule", "--step", "0.5"])
+        .stdout()
+        .satisfies(
+            |out| out.lines().count() == 19 + 37,
+            "10 degree graticule should have 56 lines",
+        )
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["graticule", "--bbox", "1,2,3"])
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn bbox_arithmetic() {
+    Assert::main_binary()
+        .with_args(&["bbox", "expand", "10%", "0,0,10,20"])
+        .stdout()
+        .is("-1,-2,11,22")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["bbox", "expand", "-10%"])
+        .stdin("-10,0,0,10\n0,0,0,0\n")
+        .stdout()
+        .is("-9,1,-1,9\n0,0,0,0")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["bbox", "expand", "111.195km"])
+        .stdin("POINT (0 0)")
+        .stdout()
+        .contains("[[[1.00015")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["bbox", "intersect", "-125,32,-114,42"])
+        .stdin("-120,40,-110,50\n0,0,1,1\n")
+        .stdout()
+        .is("-120,40,-114,42")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["bbox", "expand", "10 parsecs", "0,0,1,1"])
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn snap_to() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("roads.geojsonl");
+    std::fs::write(
+        &path,
+        r#"{"type":"Feature","id":"main-st","geometry":{"type":"LineString","coordinates":[[0,0],[1,0]]},"properties":{}}
+{"type":"Feature","id":"side-st","geometry":{"type":"LineString","coordinates":[[0,0.1],[1,0.1]]},"properties":{}}
+"#,
+    )
+    .unwrap();
+    Assert::main_binary()
+        .with_args(&["snap-to", "--other", path.to_str().unwrap()])
+        .stdin("POINT (0.5 0.0003)\nPOINT (0.5 0.05)\n")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[0.5,0.0],"type":"Point"},"properties":{"snap_distance":33.35852407005987,"snap_id":"main-st"},"type":"Feature"}
+{"geometry":{"coordinates":[0.5,0.05],"type":"Point"},"properties":{"snap_distance":null,"snap_id":null},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "snap-to",
+            "--other",
+            path.to_str().unwrap(),
+            "--max-distance",
+            "6km",
+        ])
+        .stdin("POINT (0.5 0.06)")
+        .stdout()
+        .contains(r#""coordinates":[0.5,0.1]"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["snap-to", "--other", path.to_str().unwrap()])
+        .stdin("LINESTRING (0 0, 1 1)")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn trajectory() {
+    let fixes = r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[0,0]},"properties":{"device":"a","time":"2023-01-01T00:00:10Z"}}
+{"type":"Feature","geometry":{"type":"Point","coordinates":[0.001,0]},"properties":{"device":"b","time":0}}
+{"type":"Feature","geometry":{"type":"Point","coordinates":[0.001,0]},"properties":{"device":"a","time":"2023-01-01T00:00:00Z"}}
+{"type":"Feature","geometry":{"type":"Point","coordinates":[0.002,0]},"properties":{"device":"b","time":20}}
+{"type":"Feature","geometry":{"type":"Point","coordinates":[0.003,0]},"properties":{"device":"b","time":2000}}
+{"type":"Feature","geometry":{"type":"Point","coordinates":[0.004,0]},"properties":{"device":"b","time":2010}}
+"#;
+    Assert::main_binary()
+        .with_args(&[
+            "trajectory",
+            "--group-by",
+            "device",
+            "--order-by",
+            "time",
+            "--max-gap",
+            "5min",
+        ])
+        .stdin(fixes)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[0.001,0.0],[0.0,0.0]],"type":"LineString"},"properties":{"coordTimes":["2023-01-01T00:00:00Z","2023-01-01T00:00:10Z"],"device":"a","distance":111.31949079327366,"duration":10.0,"end_time":"2023-01-01T00:00:10Z","max_speed":11.131949079327367,"speed":11.131949079327367,"start_time":"2023-01-01T00:00:00Z"},"type":"Feature"}
+{"geometry":{"coordinates":[[0.001,0.0],[0.002,0.0]],"type":"LineString"},"properties":{"coordTimes":["1970-01-01T00:00:00Z","1970-01-01T00:00:20Z"],"device":"b","distance":111.31949079327366,"duration":20.0,"end_time":"1970-01-01T00:00:20Z","max_speed":5.565974539663683,"speed":5.565974539663683,"start_time":"1970-01-01T00:00:00Z"},"type":"Feature"}
+{"geometry":{"coordinates":[[0.003,0.0],[0.004,0.0]],"type":"LineString"},"properties":{"coordTimes":["1970-01-01T00:33:20Z","1970-01-01T00:33:30Z"],"device":"b","distance":111.31949079327366,"duration":10.0,"end_time":"1970-01-01T00:33:30Z","max_speed":11.131949079327367,"speed":11.131949079327367,"start_time":"1970-01-01T00:33:20Z"},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["trajectory", "--order-by", "time"])
+        .stdin(fixes)
+        .stdout()
+        .satisfies(
+            |out| out.lines().count() == 1,
+            "one track without --group-by",
+        )
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["trajectory", "--order-by", "missing"])
+        .stdin(fixes)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn trajectory_analyze() {
+    // Moves for 20 seconds, waits 5 minutes, then moves on
+    let track = r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[0,0],[0.001,0],[0.002,0],[0.002,0],[0.002,0],[0.003,0]]},"properties":{"device":"a","distance":555.0,"coordTimes":["2023-01-01T00:00:00Z","2023-01-01T00:00:10Z","2023-01-01T00:00:20Z","2023-01-01T00:02:00Z","2023-01-01T00:05:20Z","2023-01-01T00:05:30Z"]}}"#;
+    Assert::main_binary()
+        .with_args(&["trajectory", "analyze", "--stop-duration", "3min"])
+        .stdin(track)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[0.0,0.0],[0.001,0.0],[0.002,0.0]],"type":"LineString"},"properties":{"coordTimes":["2023-01-01T00:00:00Z","2023-01-01T00:00:10Z","2023-01-01T00:00:20Z"],"device":"a","distance":222.63898158654732,"duration":20.0,"end_time":"2023-01-01T00:00:20Z","max_speed":11.131949079327367,"speed":11.131949079327367,"start_time":"2023-01-01T00:00:00Z","state":"moving"},"type":"Feature"}
+{"geometry":{"coordinates":[0.002,0.0],"type":"Point"},"properties":{"device":"a","duration":300.0,"end_time":"2023-01-01T00:05:20Z","start_time":"2023-01-01T00:00:20Z","state":"stopped"},"type":"Feature"}
+{"geometry":{"coordinates":[[0.002,0.0],[0.003,0.0]],"type":"LineString"},"properties":{"coordTimes":["2023-01-01T00:05:20Z","2023-01-01T00:05:30Z"],"device":"a","distance":111.31949079327366,"duration":10.0,"end_time":"2023-01-01T00:05:30Z","max_speed":11.131949079327367,"speed":11.131949079327367,"start_time":"2023-01-01T00:05:20Z","state":"moving"},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["trajectory", "analyze", "--stop-duration", "10min"])
+        .stdin(track)
+        .stdout()
+        .satisfies(|out| !out.contains("stopped"), "no stops longer than 10min")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["trajectory", "analyze"])
+        .stdin("LINESTRING (0 0, 1 1)")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn trajectory_resample() {
+    let track = r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[0,0],[0.001,0],[0.003,0]]},"properties":{"device":"a","coordTimes":["2023-01-01T00:00:00Z","2023-01-01T00:00:10Z","2023-01-01T00:00:30Z"]}}"#;
+    Assert::main_binary()
+        .with_args(&["trajectory", "resample", "--every", "15s"])
+        .stdin(track)
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[0.0,0.0],[0.0015,0.0],[0.003,0.0]],"type":"LineString"},"properties":{"coordTimes":["2023-01-01T00:00:00Z","2023-01-01T00:00:15Z","2023-01-01T00:00:30Z"],"device":"a","distance":333.958472379821,"duration":30.0,"end_time":"2023-01-01T00:00:30Z","max_speed":11.131949079327367,"speed":11.131949079327367,"start_time":"2023-01-01T00:00:00Z"},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["trajectory", "resample", "--every", "100m"])
+        .stdin(track)
+        .stdout()
+        .contains(r#""coordTimes":["2023-01-01T00:00:00Z","2023-01-01T00:00:08.983Z","#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["trajectory", "resample", "--every", "1km"])
+        .stdin(track)
+        .stdout()
+        .is("")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["trajectory", "resample", "--every", "10"])
+        .stdin(track)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn split_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let template = dir.path().join("part-%04d.geojsonl");
+    let features = r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[1,1]},"properties":{}}
+{"type":"Feature","geometry":{"type":"Point","coordinates":[2,2]},"properties":{}}
+{"type":"Feature","geometry":{"type":"Point","coordinates":[3,3]},"properties":{}}
+"#;
+    Assert::main_binary()
+        .with_args(&[
+            "split-output",
+            "--max-features",
+            "2",
+            "--out",
+            template.to_str().unwrap(),
+        ])
+        .stdin(features)
+        .stdout()
+        .contains("part-0002.geojsonl")
+        .unwrap();
+    let first = std::fs::read_to_string(dir.path().join("part-0001.geojsonl")).unwrap();
+    assert_eq!(2, first.lines().count());
+    let second = std::fs::read_to_string(dir.path().join("part-0002.geojsonl")).unwrap();
+    assert!(second.contains("[3,3]"));
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("manifest.json")).unwrap())
+            .unwrap();
+    assert_eq!(3, manifest["features"]);
+    assert_eq!(1, manifest["parts"][1]["features"]);
+
+    Assert::main_binary()
+        .with_args(&["split-output", "--max-features", "2", "--out", "part.json"])
+        .stdin(features)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn cat() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.geojson");
+    let b = dir.path().join("b.wkt");
+    std::fs::write(
+        &a,
+        r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[1,1]},"properties":{"name":"a"}}
+"#,
+    )
+    .unwrap();
+    std::fs::write(&b, "POINT(2 2)\n").unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "cat",
+            "--source",
+            "file",
+            a.to_str().unwrap(),
+            b.to_str().unwrap(),
+        ])
+        .stdout()
+        .contains(r#""name":"a""#)
+        .stdout()
+        .contains(r#"b.wkt","name":null}"#)
+        .unwrap();
+
+    // A bare shapefile, read with its .dbf
+    Assert::main_binary()
+        .with_args(&[
+            "cat",
+            "--source",
+            "file",
+            "tests/resources/poly.shp",
+            a.to_str().unwrap(),
+        ])
+        .stdout()
+        .contains(
+            r#""EAS_ID":168.0,"PRFEDEA":"35043411","file":"tests/resources/poly.shp","name":null}"#,
+        )
+        .stdout()
+        .contains(r#""AREA":null,"EAS_ID":null,"PRFEDEA":null,"file":"#)
+        .unwrap();
+}
+
+#[test]
+fn schema() {
+    Assert::main_binary()
+        .with_args(&["schema"])
+        .stdin(
+            r#"{"type":"Feature","geometry":null,"properties":{"name":"a","pop":1}}
+{"type":"Feature","geometry":null,"properties":{"name":"b","pop":2.5}}
+{"type":"Feature","geometry":null,"properties":{"name":"a"}}
+"#,
+        )
+        .stdout()
+        .is(r#"{"distinct":2,"examples":["a","b"],"key":"name","missing":0,"null_rate":0.0,"nulls":0,"types":{"string":3}}
+{"distinct":2,"examples":[1,2.5],"key":"pop","missing":1,"null_rate":0.3333333333333333,"nulls":0,"types":{"integer":1,"number":1}}"#)
+        .unwrap();
+}
+
+#[test]
+fn estimate() {
+    let features = r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[1,1],[2,2],[1,3]]},"properties":{"name":"a"}}
+{"type":"Feature","geometry":{"type":"LineString","coordinates":[[2,1],[3,2],[2,3]]},"properties":{"name":"b"}}
+{"type":"Feature","geometry":{"type":"Point","coordinates":[2,2]},"properties":{"name":"c"}}
+"#;
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_geoq"))
+        .args(["estimate", "fgb", "--sample", "2"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child.stdin.take().unwrap().write_all(features.as_bytes())?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    let estimate: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(3, estimate["features"]);
+    assert_eq!(2, estimate["sampled"]);
+    assert_eq!(9, estimate["coordinates"]);
+    assert!(estimate["output_bytes"].as_u64().unwrap() > 0);
+    assert!(estimate["memory_bytes"].as_u64().unwrap() > 0);
+
+    Assert::main_binary()
+        .with_args(&["estimate", "parquet"])
+        .stdin("")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn buffer() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_geoq"))
+        .args(["buffer", "1km"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(br#"{"type":"Feature","id":7,"geometry":{"type":"Point","coordinates":[20,70]},"properties":{"name":"a"}}"#)?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    let feature: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(7, feature["id"]);
+    assert_eq!("a", feature["properties"]["name"]);
+    assert_eq!("Polygon", feature["geometry"]["type"]);
+    // A kilometer is about 0.009 degrees of latitude, and three times as
+    // many degrees of longitude at 70N
+    let ring = feature["geometry"]["coordinates"][0].as_array().unwrap();
+    let (mut max_x, mut max_y) = (f64::MIN, f64::MIN);
+    for c in ring {
+        max_x = max_x.max(c[0].as_f64().unwrap() - 20.0);
+        max_y = max_y.max(c[1].as_f64().unwrap() - 70.0);
+    }
+    assert!((max_y - 0.009).abs() < 0.0002, "{}", max_y);
+    assert!((max_x - 0.0263).abs() < 0.0005, "{}", max_x);
+
+    Assert::main_binary()
+        .with_args(&["buffer", "--planar", "10"])
+        .stdin("POINT(500 500)")
+        .stdout()
+        .contains("509.")
+        .unwrap();
+}
+
+#[test]
+fn reproject() {
+    Assert::main_binary()
+        .with_args(&["reproject", "--to", "EPSG:32618"])
+        .stdin(r#"{"type":"Feature","id":1,"geometry":{"type":"Point","coordinates":[-75,40]},"properties":{"a":1}}"#)
+        .stdout()
+        .contains(r#"{"geometry":{"coordinates":[500000.0,4427757.21"#)
+        .stdout()
+        .contains(r#""id":1,"properties":{"a":1}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["reproject", "--to", "3857", "--densify", "100km"])
+        .stdin("LINESTRING(0 0,1 0)")
+        .stdout()
+        .contains("[55659.74")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["reproject", "--to", "EPSG:27700"])
+        .stdin("POINT(0 0)")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn repair_rings() {
+    Assert::main_binary()
+        .with_args(&["gj", "f", "--repair-rings"])
+        .stdin("POLYGON ((1 1, 1 2, 2 2, 2 1, 1 1), (0 0, 3 0, 3 3, 0 3, 0 0), (5 5, 6 5, 6 6, 5 6, 5 5))")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[[[0.0,0.0],[3.0,0.0],[3.0,3.0],[0.0,3.0],[0.0,0.0]],[[1.0,1.0],[1.0,2.0],[2.0,2.0],[2.0,1.0],[1.0,1.0]]],[[[5.0,5.0],[6.0,5.0],[6.0,6.0],[5.0,6.0],[5.0,5.0]]]],"type":"MultiPolygon"},"properties":{},"type":"Feature"}"#)
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("repaired.fgb");
+    Assert::main_binary()
+        .with_args(&["fgb", "write", "--repair-rings", path.to_str().unwrap()])
+        .stdin("POLYGON ((1 1, 1 2, 2 2, 2 1, 1 1), (0 0, 3 0, 3 3, 0 3, 0 0))")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["fgb", "read", path.to_str().unwrap()])
+        .stdout()
+        .contains("[[[0,0],[3,0],[3,3],[0,3],[0,0]],[[1,1],[1,2],[2,2],[2,1],[1,1]]]")
+        .unwrap();
+}
+
+#[test]
+fn matrix() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("depots.geojsonl");
+    std::fs::write(
+        &path,
+        r#"{"type":"Feature","id":"north","geometry":{"type":"Point","coordinates":[-118,35]},"properties":{}}
+{"type":"Feature","id":"south","geometry":{"type":"Point","coordinates":[-118,34]},"properties":{}}
+"#,
+    )
+    .unwrap();
+    Assert::main_binary()
+        .with_args(&["--units", "km", "matrix", "--other", path.to_str().unwrap()])
+        .stdin("34,-118\n35,-118\n")
+        .stdout()
+        .is("id,north,south\n0,110.93145952287868,0\n1,0,110.93145952287868")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "matrix",
+            "--other",
+            path.to_str().unwrap(),
+            "--max-distance",
+            "1km",
+            "--json",
+        ])
+        .stdin("34,-118\n35,-118\n")
+        .stdout()
+        .is(r#"{"distance":0.0,"from":0,"to":"south"}
+{"distance":0.0,"from":1,"to":"north"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["matrix", "--other", path.to_str().unwrap()])
+        .stdin("LINESTRING (0 0, 1 1)")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn interpolate() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("zones.geojsonl");
+    std::fs::write(
+        &path,
+        r#"{"type":"Feature","id":"west","geometry":{"type":"Polygon","coordinates":[[[0,0],[0.01,0],[0.01,0.02],[0,0.02],[0,0]]]},"properties":{}}
+{"type":"Feature","id":"east","geometry":{"type":"Polygon","coordinates":[[[0.01,0],[0.02,0],[0.02,0.02],[0.01,0.02],[0.01,0]]]},"properties":{}}
+{"type":"Feature","id":"far","geometry":{"type":"Polygon","coordinates":[[[5,5],[6,5],[6,6],[5,6],[5,5]]]},"properties":{}}
+"#,
+    )
+    .unwrap();
+    let input = r#"{"type":"Feature","geometry":{"type":"Polygon","coordinates":[[[0,0],[0.02,0],[0.02,0.01],[0,0.01],[0,0]]]},"properties":{"population":100,"households":"40"}}"#;
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_geoq"))
+        .args(["interpolate", "--other"])
+        .arg(&path)
+        .args(["--value", "population", "--value", "households"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child.stdin.take().unwrap().write_all(input.as_bytes())?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    assert!(output.status.success());
+    let zones: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    let value = |i: usize, k: &str| zones[i]["properties"][k].as_f64().unwrap();
+    assert_eq!(3, zones.len());
+    assert!((value(0, "population") - 50.0).abs() < 1e-6);
+    assert!((value(1, "households") - 20.0).abs() < 1e-6);
+    assert_eq!(0.0, value(2, "population"));
+    assert_eq!("far", zones[2]["id"]);
+
+    Assert::main_binary()
+        .with_args(&["interpolate", "-o", path.to_str().unwrap(), "-v", "name"])
+        .stdin(r#"{"type":"Feature","geometry":{"type":"Polygon","coordinates":[[[0,0],[0.02,0],[0.02,0.01],[0,0.01],[0,0]]]},"properties":{"name":"tract 1"}}"#)
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn zonal() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dem.tif");
+    std::fs::write(&path, geotiff(3, &[1, 2, 3, 4, 5, 6, 7, 8, 0])).unwrap();
+    Assert::main_binary()
+        .with_args(&["zonal", "--raster", path.to_str().unwrap()])
+        .stdin("POLYGON ((10 48, 12 48, 12 50, 10 50, 10 48))\nPOLYGON ((12 47, 13 47, 13 48, 12 48, 12 47))")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[[10.0,48.0],[12.0,48.0],[12.0,50.0],[10.0,50.0],[10.0,48.0]]],"type":"Polygon"},"properties":{"count":4,"max":5,"mean":3.0,"min":1},"type":"Feature"}
+{"geometry":{"coordinates":[[[12.0,47.0],[13.0,47.0],[13.0,48.0],[12.0,48.0],[12.0,47.0]]],"type":"Polygon"},"properties":{"count":0,"max":null,"mean":null,"min":null},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "zonal",
+            "--raster",
+            path.to_str().unwrap(),
+            "--stats",
+            "sum,median",
+        ])
+        .stdin("POLYGON ((10 47, 13 47, 13 50, 10 50, 10 47))")
+        .stdout()
+        .contains(r#""properties":{"median":4.5,"sum":36}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "zonal",
+            "--raster",
+            path.to_str().unwrap(),
+            "--stats",
+            "mode",
+        ])
+        .stdin("POLYGON ((10 48, 12 48, 12 50, 10 50, 10 48))")
+        .fails()
+        .stderr()
+        .contains("Unknown statistic: mode")
+        .unwrap();
+}
+
+#[test]
+fn los() {
+    let dir = tempfile::tempdir().unwrap();
+    let dem = dir.path().join("dem.tif");
+    std::fs::write(&dem, geotiff(3, &[1, 1, 1, 1, 9, 1, 1, 1, 1])).unwrap();
+    let targets = dir.path().join("targets.geojsonl");
+    std::fs::write(
+        &targets,
+        r#"{"type":"Feature","id":"south","geometry":{"type":"Point","coordinates":[10.5,48.5]},"properties":{}}
+"#,
+    )
+    .unwrap();
+    // 111km apart, the target is below the horizon from the ground
+    Assert::main_binary()
+        .with_args(&[
+            "los",
+            "--dem",
+            dem.to_str().unwrap(),
+            "--other",
+            targets.to_str().unwrap(),
+        ])
+        .stdin("POINT (10.5 49.5)\nPOINT (20 20)")
+        .stdout()
+        .is(r#"{"geometry":{"coordinates":[[10.5,49.5],[10.5,48.5]],"type":"LineString"},"properties":{"from":0,"obstruction":[10.5,49.00002177658903],"to":"south","visible":false},"type":"Feature"}
+{"geometry":{"coordinates":[[20.0,20.0],[10.5,48.5]],"type":"LineString"},"properties":{"from":1,"obstruction":null,"to":"south","visible":null},"type":"Feature"}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "los",
+            "--dem",
+            dem.to_str().unwrap(),
+            "--other",
+            targets.to_str().unwrap(),
+            "--observer-height",
+            "1km",
+            "--target-height",
+            "1km",
+        ])
+        .stdin("POINT (10.5 49.5)")
+        .stdout()
+        .contains(r#""properties":{"from":0,"obstruction":null,"to":"south","visible":true}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "los",
+            "--dem",
+            dem.to_str().unwrap(),
+            "--other",
+            targets.to_str().unwrap(),
+        ])
+        .stdin("LINESTRING (10 48, 11 49)")
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn rhumb() {
+    let dover = "POINT (1.338 51.127)";
+    Assert::main_binary()
+        .with_args(&["measure", "bearing", "POINT (1.853 50.964)", "--rhumb"])
+        .stdin(dover)
+        .stdout()
+        .is("116.72185980258678\tPOINT(1.338 51.127)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "bearing", "POINT (1.853 50.964)", "--json"])
+        .stdin(dover)
+        .stdout()
+        .is(r#"{"id":null,"units":"degrees","value":116.52142115848176}"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&["measure", "bearing", "0,0"])
+        .stdin("LINESTRING (0 0, 1 1)")
+        .fails()
+        .unwrap();
+    // Along a parallel, the rhumb line follows it rather than bowing
+    // toward the pole
+    Assert::main_binary()
+        .with_args(&["measure", "length", "--rhumb", "--units", "km"])
+        .stdin("LINESTRING (0 60, 10 60)")
+        .stdout()
+        .is("555.9754011676646")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "measure",
+            "distance",
+            "POINT (10 60)",
+            "--rhumb",
+            "--units",
+            "km",
+        ])
+        .stdin("POINT (0 60)")
+        .stdout()
+        .is("555.9754011676646\tPOINT(0 60)")
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "destination",
+            "--bearing",
+            "90",
+            "--distance",
+            "555.9754011676646km",
+            "--rhumb",
+        ])
+        .stdin("POINT (0 60)")
+        .stdout()
+        .contains(r#""coordinates":[10.0,60.0]"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "destination",
+            "--bearing",
+            "90",
+            "--distance",
+            "600km",
+            "--rhumb",
+            "--line",
+            "--densify",
+            "200km",
+        ])
+        .stdin("POINT (0 60)")
+        .stdout()
+        .contains(r#""coordinates":[[0.0,60.0],"#)
+        .unwrap();
+    // Due north over the pole, and down the other side
+    Assert::main_binary()
+        .with_args(&[
+            "destination",
+            "--bearing",
+            "0",
+            "--distance",
+            "300km",
+            "--rhumb",
+        ])
+        .stdin("POINT (0 89)")
+        .stdout()
+        .contains(r#""coordinates":[180.0,88.30"#)
+        .unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "destination",
+            "--bearing",
+            "45",
+            "--distance",
+            "300km",
+            "--rhumb",
+        ])
+        .stdin("POINT (0 89)")
+        .fails()
+        .stderr()
+        .contains("spirals into the pole")
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ports.txt");
+    std::fs::write(&path, "60,10\n").unwrap();
+    Assert::main_binary()
+        .with_args(&[
+            "--units",
+            "km",
+            "matrix",
+            "--other",
+            path.to_str().unwrap(),
+            "--metric",
+            "rhumb",
+        ])
+        .stdin("60,0")
+        .stdout()
+        .is("id,0\n0,555.9754011676646")
+        .unwrap();
+}